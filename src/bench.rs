@@ -0,0 +1,202 @@
+//! Benchmarking queries.
+//!
+//! [`Client::benchmark`](crate::Client::benchmark) runs a query repeatedly,
+//! fetching and discarding all of its results, and reports how long each run
+//! took along with its throughput. Comparing the reports before and after a
+//! change to a view or a reflection tells whether it made the query faster.
+//!
+//! Runs only measure the client's side: the time from submitting the query
+//! to receiving its last batch, and the size of the batches once decoded.
+
+use crate::{Client, DremioClientError, QueryOptions};
+use futures::{stream, StreamExt, TryStreamExt};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// How [`Client::benchmark`](crate::Client::benchmark) runs a query.
+///
+/// # Example
+///
+/// ```
+/// use dremio_rs::bench::BenchOptions;
+///
+/// let options = BenchOptions { iterations: 50, concurrency: 4, ..Default::default() };
+/// assert_eq!(options.warmup, 1);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BenchOptions {
+    /// The number of measured runs. Defaults to 10.
+    pub iterations: usize,
+    /// The number of runs before the measured ones, one at a time, to warm up
+    /// caches on the cluster. They are not reported. Defaults to 1.
+    pub warmup: usize,
+    /// The number of measured runs in flight at once. Defaults to 1.
+    pub concurrency: usize,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        Self {
+            iterations: 10,
+            warmup: 1,
+            concurrency: 1,
+        }
+    }
+}
+
+/// One measured run of a benchmarked query.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BenchRun {
+    /// The time from submitting the query to receiving its last batch.
+    pub latency: Duration,
+    /// The time from submitting the query to receiving its first batch, or
+    /// `None` if it returned none.
+    pub first_batch: Option<Duration>,
+    /// The number of rows received.
+    pub rows: u64,
+    /// The size of the batches received, in bytes of Arrow buffers.
+    pub bytes: u64,
+}
+
+impl BenchRun {
+    /// Returns the number of rows received per second of latency.
+    pub fn rows_per_sec(&self) -> f64 {
+        per_sec(self.rows, self.latency)
+    }
+
+    /// Returns the number of bytes received per second of latency.
+    pub fn bytes_per_sec(&self) -> f64 {
+        per_sec(self.bytes, self.latency)
+    }
+}
+
+/// The measured runs of a benchmarked query, as returned by
+/// [`Client::benchmark`](crate::Client::benchmark).
+///
+/// Its `Display` implementation prints the latency percentiles and the
+/// throughput.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BenchReport {
+    /// The runs, in the order they finished.
+    pub runs: Vec<BenchRun>,
+    /// The wall-clock time of all the measured runs.
+    pub elapsed: Duration,
+}
+
+impl BenchReport {
+    /// Returns the latency that `percentile` percent of the runs did not
+    /// exceed, using the nearest-rank method, or zero if there were no runs.
+    ///
+    /// `percentile` is clamped to `0.0..=100.0`.
+    pub fn percentile(&self, percentile: f64) -> Duration {
+        let mut latencies: Vec<Duration> = self.runs.iter().map(|run| run.latency).collect();
+        latencies.sort();
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0 * latencies.len() as f64).ceil();
+        latencies
+            .get((rank as usize).max(1) - 1)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns the median latency.
+    pub fn median(&self) -> Duration {
+        self.percentile(50.0)
+    }
+
+    /// Returns the mean latency, or zero if there were no runs.
+    pub fn mean(&self) -> Duration {
+        let total: Duration = self.runs.iter().map(|run| run.latency).sum();
+        total
+            .checked_div(self.runs.len() as u32)
+            .unwrap_or_default()
+    }
+
+    /// Returns the number of rows received per second of wall-clock time,
+    /// across all the runs.
+    pub fn rows_per_sec(&self) -> f64 {
+        per_sec(self.runs.iter().map(|run| run.rows).sum(), self.elapsed)
+    }
+
+    /// Returns the number of bytes received per second of wall-clock time,
+    /// across all the runs.
+    pub fn bytes_per_sec(&self) -> f64 {
+        per_sec(self.runs.iter().map(|run| run.bytes).sum(), self.elapsed)
+    }
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} runs in {:.3?}", self.runs.len(), self.elapsed)?;
+        writeln!(
+            f,
+            "latency: mean {:.3?}, p50 {:.3?}, p90 {:.3?}, p95 {:.3?}, p99 {:.3?}, max {:.3?}",
+            self.mean(),
+            self.median(),
+            self.percentile(90.0),
+            self.percentile(95.0),
+            self.percentile(99.0),
+            self.percentile(100.0),
+        )?;
+        write!(
+            f,
+            "throughput: {:.0} rows/s, {:.0} bytes/s",
+            self.rows_per_sec(),
+            self.bytes_per_sec()
+        )
+    }
+}
+
+fn per_sec(count: u64, duration: Duration) -> f64 {
+    let secs = duration.as_secs_f64();
+    if secs > 0.0 {
+        count as f64 / secs
+    } else {
+        0.0
+    }
+}
+
+/// Runs `query` as `options` says and collects the measured runs.
+pub(crate) async fn benchmark(
+    client: &Client,
+    query: &str,
+    options: &BenchOptions,
+    query_options: &QueryOptions,
+) -> Result<BenchReport, DremioClientError> {
+    for _ in 0..options.warmup {
+        run(client, query, query_options).await?;
+    }
+    let start = Instant::now();
+    let runs = stream::iter(0..options.iterations)
+        .map(|_| run(client, query, query_options))
+        .buffer_unordered(options.concurrency.max(1))
+        .try_collect()
+        .await?;
+    Ok(BenchReport {
+        runs,
+        elapsed: start.elapsed(),
+    })
+}
+
+async fn run(
+    client: &Client,
+    query: &str,
+    options: &QueryOptions,
+) -> Result<BenchRun, DremioClientError> {
+    let start = Instant::now();
+    let mut stream = client.open_query(query, options).await?;
+    let mut result = BenchRun {
+        latency: Duration::ZERO,
+        first_batch: None,
+        rows: 0,
+        bytes: 0,
+    };
+    while let Some(batch) = stream.try_next().await? {
+        result.first_batch.get_or_insert_with(|| start.elapsed());
+        result.rows += batch.num_rows() as u64;
+        for column in batch.columns() {
+            result.bytes += column.to_data().get_slice_memory_size()? as u64;
+        }
+    }
+    result.latency = start.elapsed();
+    Ok(result)
+}
@@ -0,0 +1,142 @@
+//! `dremio ls`, `dremio describe` and `dremio search`: browse the catalog.
+//!
+//! The commands read Dremio's `INFORMATION_SCHEMA`, which covers sources,
+//! spaces, folders, tables and views alike.
+
+use crate::output::{write_stream, OutputFormat};
+use crate::ConnectionArgs;
+use clap::Args;
+use dremio_rs::sql::{parse_path, Literal};
+use dremio_rs::DremioClientError;
+use std::error::Error;
+use tokio::io::{self, BufWriter};
+
+#[derive(Args)]
+pub struct LsArgs {
+    /// Dotted path of a source, space or folder; the root when omitted.
+    path: Option<String>,
+    /// Output format.
+    #[arg(long, short, value_enum, default_value_t = OutputFormat::Table)]
+    output: OutputFormat,
+}
+
+#[derive(Args)]
+pub struct DescribeArgs {
+    /// Dotted path of a table or view.
+    path: String,
+    /// Output format.
+    #[arg(long, short, value_enum, default_value_t = OutputFormat::Table)]
+    output: OutputFormat,
+}
+
+#[derive(Args)]
+pub struct SearchArgs {
+    /// Text to look for in schema and table names, case-insensitively.
+    term: String,
+    /// Output format.
+    #[arg(long, short, value_enum, default_value_t = OutputFormat::Table)]
+    output: OutputFormat,
+}
+
+pub async fn ls(connection: &ConnectionArgs, args: LsArgs) -> Result<(), Box<dyn Error>> {
+    run_sql(connection, &list_sql(args.path.as_deref())?, args.output).await
+}
+
+pub async fn describe(
+    connection: &ConnectionArgs,
+    args: DescribeArgs,
+) -> Result<(), Box<dyn Error>> {
+    run_sql(connection, &describe_sql(&args.path)?, args.output).await
+}
+
+pub async fn search(connection: &ConnectionArgs, args: SearchArgs) -> Result<(), Box<dyn Error>> {
+    run_sql(connection, &search_sql(&args.term), args.output).await
+}
+
+async fn run_sql(
+    connection: &ConnectionArgs,
+    sql: &str,
+    output: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    let mut client = connection.connect().await?;
+    let options = output.query_options(client.query_options());
+    let stream = client.execute_stream_with_options(sql, &options).await?;
+    write_stream(stream, output, BufWriter::new(io::stdout())).await
+}
+
+/// Returns SQL listing the children of `path`: the schemas directly below it
+/// and the tables and views it contains. Without a path, lists the top-level
+/// sources and spaces.
+fn list_sql(path: Option<&str>) -> Result<String, DremioClientError> {
+    Ok(match path {
+        None => "SELECT DISTINCT \
+                 CASE WHEN STRPOS(SCHEMA_NAME, '.') > 0 \
+                 THEN SUBSTR(SCHEMA_NAME, 1, STRPOS(SCHEMA_NAME, '.') - 1) \
+                 ELSE SCHEMA_NAME END AS NAME, \
+                 'SCHEMA' AS TYPE \
+                 FROM INFORMATION_SCHEMA.SCHEMATA \
+                 WHERE SCHEMA_NAME NOT IN ('INFORMATION_SCHEMA', 'sys') \
+                 AND SCHEMA_NAME NOT LIKE 'sys.%' \
+                 ORDER BY NAME"
+            .to_string(),
+        Some(path) => {
+            let path = parse_path(path)?.join(".");
+            format!(
+                "SELECT SUBSTR(SCHEMA_NAME, {start}) AS NAME, 'SCHEMA' AS TYPE \
+                 FROM INFORMATION_SCHEMA.SCHEMATA \
+                 WHERE SCHEMA_NAME LIKE {prefix} ESCAPE '\\' \
+                 AND STRPOS(SUBSTR(SCHEMA_NAME, {start}), '.') = 0 \
+                 UNION ALL \
+                 SELECT TABLE_NAME AS NAME, TABLE_TYPE AS TYPE \
+                 FROM INFORMATION_SCHEMA.\"TABLES\" \
+                 WHERE TABLE_SCHEMA = {schema} \
+                 ORDER BY NAME",
+                start = path.chars().count() + 2,
+                prefix = literal(&format!("{}.%", escape_like(&path))),
+                schema = literal(&path),
+            )
+        }
+    })
+}
+
+/// Returns SQL describing the columns of the table or view at `path`.
+pub fn describe_sql(path: &str) -> Result<String, DremioClientError> {
+    let mut path = parse_path(path)?;
+    let table = path.pop().unwrap_or_default();
+    let mut sql = format!(
+        "SELECT COLUMN_NAME, DATA_TYPE, IS_NULLABLE \
+         FROM INFORMATION_SCHEMA.COLUMNS WHERE TABLE_NAME = {}",
+        literal(&table)
+    );
+    if !path.is_empty() {
+        sql.push_str(&format!(" AND TABLE_SCHEMA = {}", literal(&path.join("."))));
+    }
+    sql.push_str(" ORDER BY ORDINAL_POSITION");
+    Ok(sql)
+}
+
+/// Returns SQL finding tables and views whose name or schema contains `term`.
+fn search_sql(term: &str) -> String {
+    let pattern = literal(&format!("%{}%", escape_like(&term.to_lowercase())));
+    format!(
+        "SELECT TABLE_SCHEMA, TABLE_NAME, TABLE_TYPE \
+         FROM INFORMATION_SCHEMA.\"TABLES\" \
+         WHERE (LOWER(TABLE_NAME) LIKE {pattern} ESCAPE '\\' \
+         OR LOWER(TABLE_SCHEMA) LIKE {pattern} ESCAPE '\\') \
+         AND TABLE_SCHEMA NOT IN ('INFORMATION_SCHEMA', 'sys') \
+         ORDER BY TABLE_SCHEMA, TABLE_NAME"
+    )
+}
+
+/// Escapes the `LIKE` wildcards in `value`, using `\` as the escape character.
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Renders `value` as a SQL string literal.
+fn literal(value: &str) -> String {
+    Literal::from(value).to_string()
+}
@@ -0,0 +1,255 @@
+//! `dremio export`: stream the results of a query to files.
+
+use crate::output::{write_stream, OutputFormat};
+use crate::ConnectionArgs;
+use clap::{Args, ValueEnum};
+use dremio_rs::export::{PartitionedParquetWriter, Verification, VerificationReport, Verifier};
+use dremio_rs::mask::{MaskRule, Masking};
+use dremio_rs::{DremioClientError, QueryStream};
+use futures::StreamExt;
+use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
+use parquet::arrow::ArrowWriter;
+use std::error::Error;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use tokio::io::{self, BufWriter};
+
+/// File formats supported by `dremio export`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum ExportFormat {
+    /// Apache Parquet.
+    Parquet,
+    /// Comma-separated values with a header row.
+    Csv,
+    /// Newline-delimited JSON objects.
+    Json,
+}
+
+impl ExportFormat {
+    fn output_format(self) -> OutputFormat {
+        match self {
+            ExportFormat::Parquet => OutputFormat::Parquet,
+            ExportFormat::Csv => OutputFormat::Csv,
+            ExportFormat::Json => OutputFormat::Json,
+        }
+    }
+}
+
+/// Checks run on Parquet files once they have been written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum VerifyMode {
+    /// Compare the row counts in the file footers with the rows fetched.
+    Rows,
+    /// Also compare per-column null counts and checksums.
+    Checksums,
+}
+
+impl VerifyMode {
+    fn verification(self) -> Verification {
+        match self {
+            VerifyMode::Rows => Verification::RowCounts,
+            VerifyMode::Checksums => Verification::Checksums,
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct ExportArgs {
+    /// SQL statement whose results are exported.
+    #[arg(long)]
+    sql: String,
+    /// Destination file, directory when partitioning, or `-` to write CSV or
+    /// JSON to standard output.
+    #[arg(long)]
+    to: String,
+    /// File format.
+    #[arg(long, value_enum, default_value_t = ExportFormat::Parquet)]
+    format: ExportFormat,
+    /// Comma-separated columns to partition Parquet output by.
+    #[arg(long, value_delimiter = ',')]
+    partition_by: Vec<String>,
+    /// Read Parquet files back once written and check them against the rows
+    /// fetched.
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "rows")]
+    verify: Option<VerifyMode>,
+    /// Mask a column before writing, as `COLUMN=RULE` where the rule is
+    /// `hash`, `redact`, `truncate:N` or `constant:TEXT`. Repeatable.
+    #[arg(long, value_name = "COLUMN=RULE", value_parser = parse_mask)]
+    mask: Vec<(String, MaskRule)>,
+    /// Salt mixed into hashed columns.
+    #[arg(long, env = "DREMIO_MASK_SALT", hide_env_values = true)]
+    mask_salt: Option<String>,
+    /// Do not display a progress bar.
+    #[arg(long)]
+    no_progress: bool,
+}
+
+fn parse_mask(s: &str) -> Result<(String, MaskRule), String> {
+    let (column, rule) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected COLUMN=RULE, got {:?}", s))?;
+    let rule = rule.parse().map_err(|e: DremioClientError| e.to_string())?;
+    Ok((column.to_string(), rule))
+}
+
+pub async fn run(connection: &ConnectionArgs, args: ExportArgs) -> Result<(), Box<dyn Error>> {
+    let destination = local_path(&args.to)?;
+    if !args.partition_by.is_empty() && args.format != ExportFormat::Parquet {
+        return Err("--partition-by is only supported for parquet exports".into());
+    }
+    if args.verify.is_some() && args.format != ExportFormat::Parquet {
+        return Err("--verify is only supported for parquet exports".into());
+    }
+    let to_stdout = args.to == "-";
+    if to_stdout && args.format == ExportFormat::Parquet {
+        return Err("parquet exports require a file; use --format csv or json".into());
+    }
+    let mut client = connection.connect().await?;
+    let mut options = args
+        .format
+        .output_format()
+        .query_options(client.query_options());
+    if !args.mask.is_empty() {
+        let masking = args
+            .mask
+            .into_iter()
+            .fold(Masking::new(), |masking, (column, rule)| {
+                masking.with_rule(column, rule)
+            });
+        options = options.with_masking(masking.with_salt(args.mask_salt.unwrap_or_default()));
+    }
+    let stream = client
+        .execute_stream_with_options(&args.sql, &options)
+        .await?;
+
+    // The progress bar is drawn on standard error, so it does not get in the
+    // way of results piped through standard output.
+    let bar = if args.no_progress {
+        ProgressBar::hidden()
+    } else {
+        progress_bar(stream.expected_rows())?
+    };
+    let progress = bar.clone();
+    let stream = stream.with_progress(move |metrics| {
+        progress.set_position(metrics.rows);
+        progress.set_message(HumanBytes(metrics.bytes).to_string());
+    });
+
+    let verification = args.verify.map(VerifyMode::verification);
+    let (written, report) = match args.format {
+        ExportFormat::Parquet if !args.partition_by.is_empty() => {
+            write_partitioned(stream, destination, args.partition_by, verification).await?
+        }
+        ExportFormat::Parquet => write_parquet(stream, destination, verification).await?,
+        format if to_stdout => {
+            write_stream(stream, format.output_format(), BufWriter::new(io::stdout())).await?;
+            (0, None)
+        }
+        format => {
+            let out = BufWriter::new(tokio::fs::File::create(&destination).await?);
+            write_stream(stream, format.output_format(), out).await?;
+            (1, None)
+        }
+    };
+    bar.finish();
+    if to_stdout {
+        eprintln!("Exported {} rows.", bar.position());
+    } else {
+        eprintln!(
+            "Exported {} rows to {} file{}.",
+            bar.position(),
+            written,
+            if written == 1 { "" } else { "s" }
+        );
+    }
+    match report {
+        Some(report) if !report.is_ok() => Err(format!("verification failed: {}", report).into()),
+        Some(report) => {
+            eprintln!("Verified {} rows.", report.written_rows);
+            Ok(())
+        }
+        None => Ok(()),
+    }
+}
+
+/// Returns the local path for `destination`, rejecting URLs.
+fn local_path(destination: &str) -> Result<PathBuf, Box<dyn Error>> {
+    match destination.split_once("://") {
+        Some((scheme, _)) => Err(format!(
+            "{}:// destinations are not supported yet; export to a local path",
+            scheme
+        )
+        .into()),
+        None => Ok(PathBuf::from(destination)),
+    }
+}
+
+/// Creates a progress bar, with an ETA when the number of rows is known.
+fn progress_bar(expected_rows: Option<u64>) -> Result<ProgressBar, Box<dyn Error>> {
+    Ok(match expected_rows {
+        Some(total) => ProgressBar::new(total).with_style(ProgressStyle::with_template(
+            "{bar:40.cyan/blue} {human_pos}/{human_len} rows, {msg} [{elapsed_precise}, ETA {eta}]",
+        )?),
+        None => ProgressBar::new_spinner().with_style(ProgressStyle::with_template(
+            "{spinner} {human_pos} rows, {msg} [{elapsed_precise}]",
+        )?),
+    })
+}
+
+/// Writes `stream` to a single Parquet file. Returns the number of files
+/// written and, if requested, the result of checking the file.
+async fn write_parquet(
+    mut stream: QueryStream,
+    path: PathBuf,
+    verification: Option<Verification>,
+) -> Result<(usize, Option<VerificationReport>), Box<dyn Error>> {
+    let mut writer: Option<ArrowWriter<File>> = None;
+    let mut verifier = verification.map(Verifier::new);
+    while let Some(batch) = stream.next().await {
+        let batch = batch?;
+        if let Some(verifier) = verifier.as_mut() {
+            verifier.update(&batch)?;
+        }
+        if writer.is_none() {
+            let file = File::create(&path)?;
+            writer = Some(ArrowWriter::try_new(file, batch.schema(), None)?);
+        }
+        if let Some(writer) = writer.as_mut() {
+            writer.write(&batch)?;
+        }
+    }
+    match writer {
+        Some(writer) => {
+            writer.close()?;
+            let report = verifier.map(|v| v.verify([&path])).transpose()?;
+            Ok((1, report))
+        }
+        // Report the missing file rather than nothing.
+        None => {
+            let report = verifier
+                .map(|v| v.verify(std::iter::empty::<&Path>()))
+                .transpose()?;
+            Ok((0, report))
+        }
+    }
+}
+
+/// Writes `stream` to a directory of Hive-style partitioned Parquet files.
+/// Returns the number of files written and, if requested, the result of
+/// checking them.
+async fn write_partitioned(
+    mut stream: QueryStream,
+    root: PathBuf,
+    partition_by: Vec<String>,
+    verification: Option<Verification>,
+) -> Result<(usize, Option<VerificationReport>), Box<dyn Error>> {
+    let mut writer = PartitionedParquetWriter::new(root, partition_by);
+    if let Some(verification) = verification {
+        writer = writer.with_verification(verification);
+    }
+    while let Some(batch) = stream.next().await {
+        writer.write(&batch?)?;
+    }
+    let summary = writer.close()?;
+    Ok((summary.files.len(), summary.verification))
+}
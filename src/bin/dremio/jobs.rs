@@ -0,0 +1,151 @@
+//! `dremio jobs`: list, inspect and cancel jobs.
+//!
+//! Listing reads the `sys.jobs_recent` system table over Flight SQL; the other
+//! commands use the REST API.
+
+use crate::output::{write_stream, OutputFormat};
+use crate::ConnectionArgs;
+use clap::{Args, Subcommand};
+use dremio_rs::rest::{Job, JobState};
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[derive(Subcommand)]
+pub enum JobsCommand {
+    /// List recent jobs, newest first.
+    List(ListArgs),
+    /// Show the status of a job.
+    Show(JobArgs),
+    /// Cancel a running job.
+    Cancel(JobArgs),
+    /// Wait for a job to finish and show its final status.
+    Wait(WaitArgs),
+    /// Print the query profile of a job as JSON.
+    Profile(ProfileArgs),
+}
+
+#[derive(Args)]
+pub struct ListArgs {
+    /// Only list jobs that have not finished.
+    #[arg(long)]
+    running: bool,
+    /// Only list jobs submitted by this user.
+    #[arg(long)]
+    user: Option<String>,
+    /// Maximum number of jobs to list.
+    #[arg(long, default_value_t = 20)]
+    limit: u32,
+    /// Output format.
+    #[arg(long, short, value_enum, default_value_t = OutputFormat::Table)]
+    output: OutputFormat,
+}
+
+#[derive(Args)]
+pub struct JobArgs {
+    /// Job ID.
+    id: String,
+}
+
+#[derive(Args)]
+pub struct WaitArgs {
+    /// Job ID.
+    id: String,
+    /// Maximum number of seconds to wait.
+    #[arg(long, default_value_t = 3600)]
+    timeout: u64,
+}
+
+#[derive(Args)]
+pub struct ProfileArgs {
+    /// Job ID.
+    id: String,
+    /// Attempt to show; the first attempt is 0.
+    #[arg(long, default_value_t = 0)]
+    attempt: u32,
+    /// Write the profile to this file instead of standard output.
+    #[arg(long, short)]
+    file: Option<PathBuf>,
+}
+
+pub async fn run(connection: &ConnectionArgs, command: JobsCommand) -> Result<(), Box<dyn Error>> {
+    match command {
+        JobsCommand::List(args) => list(connection, args).await,
+        JobsCommand::Show(args) => {
+            let rest = connection.connect_rest().await?;
+            print_job(rest.get_job(&args.id).await?);
+            Ok(())
+        }
+        JobsCommand::Wait(args) => {
+            let rest = connection.connect_rest().await?;
+            let job = rest
+                .jobs()
+                .wait(&args.id, Duration::from_secs(args.timeout))
+                .await?;
+            let state = job.job_state;
+            print_job(job);
+            if state != JobState::Completed {
+                return Err(format!("job {} finished as {:?}", args.id, state).into());
+            }
+            Ok(())
+        }
+        JobsCommand::Cancel(args) => {
+            let rest = connection.connect_rest().await?;
+            rest.cancel_job(&args.id).await?;
+            eprintln!("Cancellation of job {} requested.", args.id);
+            Ok(())
+        }
+        JobsCommand::Profile(args) => {
+            let rest = connection.connect_rest().await?;
+            let profile = rest.job_profile(&args.id, args.attempt).await?;
+            match args.file {
+                Some(path) => {
+                    let mut out = BufWriter::new(File::create(path)?);
+                    serde_json::to_writer_pretty(&mut out, &profile)?;
+                    out.flush()?;
+                }
+                None => println!("{}", serde_json::to_string_pretty(&profile)?),
+            }
+            Ok(())
+        }
+    }
+}
+
+fn print_job(job: Job) {
+    println!("State:      {:?}", job.job_state);
+    let fields = [
+        ("Query type", job.query_type),
+        ("Queue", job.queue_name),
+        ("Started", job.started_at),
+        ("Ended", job.ended_at),
+        ("Rows", job.row_count.map(|r| r.to_string())),
+        ("Error", job.error_message),
+        ("Canceled", job.cancellation_reason),
+    ];
+    for (name, value) in fields {
+        if let Some(value) = value.filter(|v| !v.is_empty()) {
+            println!("{:<11} {}", format!("{}:", name), value);
+        }
+    }
+}
+
+async fn list(connection: &ConnectionArgs, args: ListArgs) -> Result<(), Box<dyn Error>> {
+    let mut sql = "SELECT job_id, status, query_type, user_name, submitted_ts, final_state_ts, \
+                   rows_returned, query FROM sys.jobs_recent WHERE 1 = 1"
+        .to_string();
+    if args.running {
+        sql.push_str(" AND status NOT IN ('COMPLETED', 'FAILED', 'CANCELED')");
+    }
+    if let Some(user) = &args.user {
+        sql.push_str(&format!(" AND user_name = '{}'", user.replace('\'', "''")));
+    }
+    sql.push_str(&format!(" ORDER BY submitted_ts DESC LIMIT {}", args.limit));
+
+    let mut client = connection.connect().await?;
+    let options = args.output.query_options(client.query_options());
+    let stream = client.execute_stream_with_options(&sql, &options).await?;
+    let out = tokio::io::BufWriter::new(tokio::io::stdout());
+    write_stream(stream, args.output, out).await
+}
@@ -0,0 +1,52 @@
+//! `dremio login`: save connection settings as a profile.
+
+use crate::ConnectionArgs;
+use clap::Args;
+use dremio_rs::config::Config;
+use std::error::Error;
+
+#[derive(Args)]
+pub struct LoginArgs {
+    /// Do not make this profile the default one.
+    #[arg(long)]
+    no_default: bool,
+}
+
+/// Verifies the connection settings given on the command line, prompting for
+/// a personal access token when none is given, and saves them as the profile
+/// named by `--profile` (`default` when omitted).
+///
+/// Passwords are never written to the configuration file: a password given
+/// on the command line or in `DREMIO_PASSWORD` without a token is refused,
+/// and one found in an existing profile is replaced by the token.
+pub async fn run(connection: &ConnectionArgs, args: LoginArgs) -> Result<(), Box<dyn Error>> {
+    let path = Config::default_path().ok_or("cannot locate the home directory")?;
+    let mut config = Config::load(&path)?;
+    let name = connection.profile_name().unwrap_or("default").to_string();
+
+    let existing = config.profiles.get(&name).cloned().unwrap_or_default();
+    let mut profile = connection.apply(existing);
+    if profile.user.is_none() {
+        return Err("no user given; pass --user or set DREMIO_USER".into());
+    }
+    if profile.token.is_none() && connection.has_password() {
+        return Err(
+            "dremio login does not save passwords; pass a personal access token with \
+             --token or DREMIO_TOKEN, or omit it to be prompted for one"
+                .into(),
+        );
+    }
+    if profile.token.is_none() {
+        profile.token = Some(rpassword::prompt_password("Personal access token: ")?);
+    }
+    profile.password = None;
+
+    profile.connect().await?;
+    config.profiles.insert(name.clone(), profile);
+    if !args.no_default || config.default_profile.is_none() {
+        config.default_profile = Some(name.clone());
+    }
+    config.save(&path)?;
+    eprintln!("Saved profile {:?} to {}.", name, path.display());
+    Ok(())
+}
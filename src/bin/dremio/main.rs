@@ -0,0 +1,190 @@
+//! `dremio`, a command-line client for Dremio built on `dremio-rs`.
+//!
+//! Connection details are taken from command-line flags, the `DREMIO_URL`,
+//! `DREMIO_USER`, `DREMIO_PASSWORD` and `DREMIO_TOKEN` environment variables,
+//! or a profile saved with `dremio login`.
+
+mod catalog;
+mod export;
+mod jobs;
+mod login;
+mod migrate;
+mod output;
+mod query;
+mod repl;
+
+use clap::{Args, Parser, Subcommand};
+use dremio_rs::config::{Config, Profile};
+use dremio_rs::rest::RestClient;
+use dremio_rs::Client;
+use std::error::Error;
+use std::process::ExitCode;
+
+/// Flight SQL endpoint used when neither the command line nor a profile names one.
+const DEFAULT_URL: &str = "http://localhost:32010";
+
+#[derive(Parser)]
+#[command(
+    name = "dremio",
+    version,
+    about = "Command-line client for Dremio Flight SQL"
+)]
+struct Cli {
+    #[command(flatten)]
+    connection: ConnectionArgs,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List the contents of a source, space or folder.
+    Ls(catalog::LsArgs),
+    /// Describe the columns of a table or view.
+    Describe(catalog::DescribeArgs),
+    /// Search tables and views by name.
+    Search(catalog::SearchArgs),
+    /// Stream the results of a query to files.
+    Export(export::ExportArgs),
+    /// Execute a SQL query and print or save the results.
+    Query(query::QueryArgs),
+    /// List, inspect and cancel jobs.
+    #[command(subcommand)]
+    Jobs(jobs::JobsCommand),
+    /// Save connection settings and a personal access token as a profile.
+    Login(login::LoginArgs),
+    /// Start an interactive SQL shell.
+    Repl(repl::ReplArgs),
+    /// Apply the versioned SQL scripts of a directory that have not run yet.
+    Migrate(migrate::MigrateArgs),
+}
+
+/// Options shared by all subcommands that talk to Dremio.
+///
+/// Values given here take precedence over those of the selected profile.
+#[derive(Args)]
+pub struct ConnectionArgs {
+    /// Profile from the configuration file to connect with.
+    #[arg(long, env = "DREMIO_PROFILE", global = true)]
+    profile: Option<String>,
+    /// Flight SQL endpoint of the Dremio coordinator [default: http://localhost:32010].
+    #[arg(long, env = "DREMIO_URL", global = true)]
+    url: Option<String>,
+    /// User name for authentication.
+    #[arg(long, env = "DREMIO_USER", global = true)]
+    user: Option<String>,
+    /// Password for authentication.
+    #[arg(long, env = "DREMIO_PASSWORD", hide_env_values = true, global = true)]
+    password: Option<String>,
+    /// Personal access token, used instead of a password.
+    #[arg(long, env = "DREMIO_TOKEN", hide_env_values = true, global = true)]
+    token: Option<String>,
+    /// REST API endpoint; defaults to port 9047 on the Flight SQL host.
+    #[arg(long, env = "DREMIO_REST_URL", global = true)]
+    rest_url: Option<String>,
+}
+
+impl ConnectionArgs {
+    /// Returns the name of the profile selected with `--profile`, if any.
+    pub fn profile_name(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    /// Returns whether a password was given on the command line or in the
+    /// environment.
+    pub fn has_password(&self) -> bool {
+        self.password.is_some()
+    }
+
+    /// Returns the connection settings: the selected profile, or the default
+    /// profile, overridden by the values given on the command line.
+    pub fn profile(&self) -> Result<Profile, Box<dyn Error>> {
+        let config = match Config::default_path() {
+            Some(path) => Config::load(path)?,
+            None => Config::default(),
+        };
+        let profile = match &self.profile {
+            Some(name) => config.profile(Some(name))?.clone(),
+            None => config.profile(None).cloned().unwrap_or_default(),
+        };
+        Ok(self.apply(profile))
+    }
+
+    /// Overrides the settings of `profile` with those given on the command line.
+    pub fn apply(&self, mut profile: Profile) -> Profile {
+        if let Some(url) = &self.url {
+            profile.url = url.clone();
+        }
+        if profile.url.is_empty() {
+            profile.url = DEFAULT_URL.to_string();
+        }
+        if let Some(user) = &self.user {
+            profile.user = Some(user.clone());
+        }
+        if let Some(password) = &self.password {
+            profile.password = Some(password.clone());
+            profile.token = None;
+        }
+        if let Some(token) = &self.token {
+            profile.token = Some(token.clone());
+        }
+        if let Some(rest_url) = &self.rest_url {
+            profile.rest_url = Some(rest_url.clone());
+        }
+        profile
+    }
+
+    /// Connects and authenticates to Dremio.
+    pub async fn connect(&self) -> Result<Client, Box<dyn Error>> {
+        Ok(self.authenticated_profile()?.connect().await?)
+    }
+
+    /// Logs in to Dremio's REST API.
+    pub async fn connect_rest(&self) -> Result<RestClient, Box<dyn Error>> {
+        Ok(self.authenticated_profile()?.connect_rest().await?)
+    }
+
+    /// Returns the connection settings, checking that they include credentials.
+    fn authenticated_profile(&self) -> Result<Profile, Box<dyn Error>> {
+        let profile = self.profile()?;
+        if profile.user.is_none() {
+            return Err("no user given; pass --user, set DREMIO_USER or use a profile".into());
+        }
+        if profile.password.is_none() && profile.token.is_none() {
+            return Err(
+                "no password given; pass --password or --token, set DREMIO_PASSWORD or \
+                 DREMIO_TOKEN, or use a profile"
+                    .into(),
+            );
+        }
+        Ok(profile)
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
+    match cli.command {
+        Command::Ls(args) => catalog::ls(&cli.connection, args).await,
+        Command::Describe(args) => catalog::describe(&cli.connection, args).await,
+        Command::Search(args) => catalog::search(&cli.connection, args).await,
+        Command::Export(args) => export::run(&cli.connection, args).await,
+        Command::Query(args) => query::run(&cli.connection, args).await,
+        Command::Jobs(command) => jobs::run(&cli.connection, command).await,
+        Command::Login(args) => login::run(&cli.connection, args).await,
+        Command::Repl(args) => repl::run(&cli.connection, args).await,
+        Command::Migrate(args) => migrate::run(&cli.connection, args).await,
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run(Cli::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        // The reader of a pipeline such as `dremio query ... | head` has
+        // seen all it wanted.
+        Err(e) if output::is_broken_pipe(e.as_ref()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
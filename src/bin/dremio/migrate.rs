@@ -0,0 +1,37 @@
+//! `dremio migrate`: apply versioned SQL scripts.
+
+use crate::ConnectionArgs;
+use clap::Args;
+use dremio_rs::migrate::Migrator;
+use std::error::Error;
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct MigrateArgs {
+    /// Directory of migration scripts named like V1__create_spaces.sql.
+    dir: PathBuf,
+    /// Table recording the applied versions, such as lake.meta.schema_migrations.
+    #[arg(long)]
+    table: String,
+    /// Print the statements that would run instead of running them.
+    #[arg(long)]
+    dry_run: bool,
+}
+
+pub async fn run(connection: &ConnectionArgs, args: MigrateArgs) -> Result<(), Box<dyn Error>> {
+    let migrator = Migrator::new(&args.table)?
+        .with_dir(&args.dir)?
+        .with_dry_run(args.dry_run);
+    let mut client = connection.connect().await?;
+    let report = migrator.run(&mut client).await?;
+    print!("{}", report);
+    if args.dry_run {
+        for run in &report.migrations {
+            println!("\n-- V{} {}", run.version, run.name);
+            for statement in &run.statements {
+                println!("{};", statement);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,97 @@
+//! Rendering of query results for the terminal and for files.
+
+use arrow::array::RecordBatch;
+use arrow::util::pretty::pretty_format_batches;
+use clap::ValueEnum;
+use dremio_rs::export::{write_text, TextFormat};
+use dremio_rs::flatten::FlattenOptions;
+use dremio_rs::{DremioClientError, IntervalMode, QueryOptions, QueryStream};
+use std::error::Error;
+use std::io::{self, Write};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+/// Output formats supported by commands that print results.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable table.
+    Table,
+    /// Comma-separated values with a header row.
+    Csv,
+    /// Newline-delimited JSON objects.
+    Json,
+    /// Apache Parquet (requires an output file).
+    Parquet,
+}
+
+impl OutputFormat {
+    /// Returns the query options needed to render results in this format.
+    ///
+    /// Text formats cannot represent nested or interval columns, so those are
+    /// flattened and converted to ISO-8601 strings respectively.
+    pub fn query_options(self, base: &QueryOptions) -> QueryOptions {
+        match self {
+            OutputFormat::Csv => base
+                .clone()
+                .with_interval_mode(IntervalMode::Iso8601)
+                .with_flatten(FlattenOptions::new()),
+            OutputFormat::Table | OutputFormat::Json => {
+                base.clone().with_interval_mode(IntervalMode::Iso8601)
+            }
+            OutputFormat::Parquet => base.clone(),
+        }
+    }
+}
+
+/// Writes all batches of `stream` to `out` in a text format, and flushes it.
+///
+/// CSV and JSON are written batch by batch as the writer accepts them, so a
+/// slow consumer at the end of a pipe throttles the query.
+pub async fn write_stream<W: AsyncWrite + Unpin>(
+    stream: QueryStream,
+    format: OutputFormat,
+    mut out: W,
+) -> Result<(), Box<dyn Error>> {
+    match format {
+        OutputFormat::Table => {
+            let batches = stream.collect_batches().await?;
+            let mut rendered = Vec::new();
+            write_table(&batches, &mut rendered)?;
+            out.write_all(&rendered).await?;
+        }
+        OutputFormat::Csv => {
+            write_text(stream, TextFormat::Csv, &mut out).await?;
+        }
+        OutputFormat::Json => {
+            write_text(stream, TextFormat::Json, &mut out).await?;
+        }
+        OutputFormat::Parquet => return Err("parquet output requires --file".into()),
+    }
+    out.flush().await?;
+    Ok(())
+}
+
+/// Returns whether `error` was caused by the reader of standard output going
+/// away, as when piping into `head`.
+pub fn is_broken_pipe(error: &(dyn Error + 'static)) -> bool {
+    let mut source = Some(error);
+    while let Some(error) = source {
+        if let Some(e) = error.downcast_ref::<io::Error>() {
+            return e.kind() == io::ErrorKind::BrokenPipe;
+        }
+        if let Some(DremioClientError::IoError(e)) =
+            error.downcast_ref::<DremioClientError>().map(|e| e.root())
+        {
+            return e.kind() == io::ErrorKind::BrokenPipe;
+        }
+        source = error.source();
+    }
+    false
+}
+
+/// Writes `batches` as a human-readable table.
+pub fn write_table<W: Write>(batches: &[RecordBatch], mut out: W) -> Result<(), Box<dyn Error>> {
+    writeln!(out, "{}", pretty_format_batches(batches)?)?;
+    let rows: usize = batches.iter().map(|b| b.num_rows()).sum();
+    writeln!(out, "({} row{})", rows, if rows == 1 { "" } else { "s" })?;
+    Ok(())
+}
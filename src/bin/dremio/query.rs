@@ -0,0 +1,42 @@
+//! `dremio query`: execute a statement and print or save its results.
+
+use crate::output::{write_stream, OutputFormat};
+use crate::ConnectionArgs;
+use clap::Args;
+use std::error::Error;
+use std::path::PathBuf;
+use tokio::fs::File;
+use tokio::io::{self, BufWriter};
+
+#[derive(Args)]
+pub struct QueryArgs {
+    /// SQL statement to execute.
+    sql: String,
+    /// Output format.
+    #[arg(long, short, value_enum, default_value_t = OutputFormat::Table)]
+    output: OutputFormat,
+    /// Write results to this file instead of standard output.
+    #[arg(long, short)]
+    file: Option<PathBuf>,
+}
+
+pub async fn run(connection: &ConnectionArgs, args: QueryArgs) -> Result<(), Box<dyn Error>> {
+    let mut client = connection.connect().await?;
+    if args.output == OutputFormat::Parquet {
+        let path = args.file.ok_or("parquet output requires --file")?;
+        let path = path.to_str().ok_or("output path is not valid UTF-8")?;
+        client.write_parquet(&args.sql, path).await?;
+        return Ok(());
+    }
+    let options = args.output.query_options(client.query_options());
+    let stream = client
+        .execute_stream_with_options(&args.sql, &options)
+        .await?;
+    match args.file {
+        Some(path) => {
+            let out = BufWriter::new(File::create(path).await?);
+            write_stream(stream, args.output, out).await
+        }
+        None => write_stream(stream, args.output, BufWriter::new(io::stdout())).await,
+    }
+}
@@ -0,0 +1,194 @@
+//! `dremio repl`: an interactive SQL shell.
+
+use crate::catalog::describe_sql;
+use crate::output::{write_table, OutputFormat};
+use crate::ConnectionArgs;
+use arrow::array::RecordBatch;
+use clap::Args;
+use dremio_rs::Client;
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use std::error::Error;
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::Instant;
+
+const HELP: &str = "\
+Statements end with ';' and may span several lines.
+
+  \\d              list tables and views
+  \\d NAME         describe the columns of a table or view
+  \\dn             list schemas (sources, spaces and folders)
+  \\timing         toggle printing of query durations
+  \\?              show this help
+  \\q              quit";
+
+#[derive(Args)]
+pub struct ReplArgs {
+    /// File used to persist command history.
+    #[arg(long)]
+    history: Option<PathBuf>,
+}
+
+struct Session {
+    client: Client,
+    timing: bool,
+}
+
+pub async fn run(connection: &ConnectionArgs, args: ReplArgs) -> Result<(), Box<dyn Error>> {
+    let url = connection.profile()?.url;
+    let client = connection.connect().await?;
+    let mut session = Session {
+        client,
+        timing: false,
+    };
+    let history = args.history.or_else(default_history_path);
+    let mut editor = DefaultEditor::new()?;
+    if let Some(path) = &history {
+        let _ = editor.load_history(path);
+    }
+    println!("Connected to {}. Type \\? for help.", url);
+    let mut buffer = String::new();
+    loop {
+        let prompt = if buffer.is_empty() {
+            "dremio> "
+        } else {
+            "     -> "
+        };
+        let line = match editor.readline(prompt) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => {
+                buffer.clear();
+                continue;
+            }
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+        let trimmed = line.trim();
+        if buffer.is_empty() && trimmed.starts_with('\\') {
+            let _ = editor.add_history_entry(trimmed);
+            if !session.meta_command(trimmed).await {
+                break;
+            }
+            continue;
+        }
+        if trimmed.is_empty() {
+            continue;
+        }
+        if !buffer.is_empty() {
+            buffer.push('\n');
+        }
+        buffer.push_str(&line);
+        if !trimmed.ends_with(';') {
+            continue;
+        }
+        let _ = editor.add_history_entry(buffer.as_str());
+        let sql = buffer.trim().trim_end_matches(';').to_string();
+        buffer.clear();
+        session.execute(&sql).await;
+    }
+    if let Some(path) = &history {
+        let _ = editor.save_history(path);
+    }
+    Ok(())
+}
+
+impl Session {
+    /// Runs a backslash command. Returns `false` when the shell should exit.
+    async fn meta_command(&mut self, command: &str) -> bool {
+        let mut parts = command.splitn(2, char::is_whitespace);
+        let name = parts.next().unwrap_or_default();
+        let argument = parts.next().map(str::trim).filter(|a| !a.is_empty());
+        match (name, argument) {
+            ("\\q", _) => return false,
+            ("\\?", _) => println!("{}", HELP),
+            ("\\timing", _) => {
+                self.timing = !self.timing;
+                println!("Timing is {}.", if self.timing { "on" } else { "off" });
+            }
+            ("\\d", None) => {
+                self.execute(
+                    "SELECT TABLE_SCHEMA, TABLE_NAME, TABLE_TYPE \
+                     FROM INFORMATION_SCHEMA.\"TABLES\" \
+                     WHERE TABLE_SCHEMA NOT IN ('INFORMATION_SCHEMA', 'sys') \
+                     ORDER BY TABLE_SCHEMA, TABLE_NAME",
+                )
+                .await
+            }
+            ("\\d", Some(name)) => match describe_sql(name) {
+                Ok(sql) => self.execute(&sql).await,
+                Err(e) => eprintln!("error: {}", e),
+            },
+            ("\\dn", _) => {
+                self.execute(
+                    "SELECT SCHEMA_NAME, TYPE FROM INFORMATION_SCHEMA.SCHEMATA ORDER BY SCHEMA_NAME",
+                )
+                .await
+            }
+            _ => eprintln!("Unknown command {}. Type \\? for help.", name),
+        }
+        true
+    }
+
+    /// Executes `sql` and prints its results, reporting errors without exiting.
+    async fn execute(&mut self, sql: &str) {
+        let started = Instant::now();
+        let options = OutputFormat::Table.query_options(self.client.query_options());
+        match self
+            .client
+            .get_record_batches_with_options(sql, &options)
+            .await
+        {
+            Ok(batches) => {
+                if let Err(e) = print_paged(&batches) {
+                    eprintln!("error: {}", e);
+                }
+            }
+            Err(e) => eprintln!("error: {}", e),
+        }
+        if self.timing {
+            println!("Time: {:.3} s", started.elapsed().as_secs_f64());
+        }
+    }
+}
+
+/// Prints `batches` as a table, through `$PAGER` when the output is taller
+/// than the terminal.
+fn print_paged(batches: &[RecordBatch]) -> Result<(), Box<dyn Error>> {
+    let mut rendered = Vec::new();
+    write_table(batches, &mut rendered)?;
+    let lines = rendered.iter().filter(|b| **b == b'\n').count();
+    let height = std::env::var("LINES")
+        .ok()
+        .and_then(|l| l.parse::<usize>().ok())
+        .unwrap_or(24);
+    if lines < height || !io::stdout().is_terminal() {
+        io::stdout().write_all(&rendered)?;
+        return Ok(());
+    }
+    let pager = std::env::var("PAGER").unwrap_or_else(|_| "less -FRSX".to_string());
+    let mut words = pager.split_whitespace();
+    let program = words.next().unwrap_or("less");
+    match Command::new(program)
+        .args(words)
+        .stdin(Stdio::piped())
+        .spawn()
+    {
+        Ok(mut child) => {
+            if let Some(mut stdin) = child.stdin.take() {
+                // The pager may exit before reading everything; that is not an error.
+                let _ = stdin.write_all(&rendered);
+            }
+            child.wait()?;
+        }
+        Err(_) => io::stdout().write_all(&rendered)?,
+    }
+    Ok(())
+}
+
+fn default_history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(".dremio_history"))
+}
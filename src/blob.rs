@@ -0,0 +1,201 @@
+//! Handling of very large binary and string cells.
+//!
+//! Datasets that store documents or images in `VARBINARY`/`VARCHAR` columns can
+//! produce cells of several megabytes, which are expensive to keep in memory
+//! and awkward to export. [`SpillOptions`] moves every cell above a size
+//! threshold into its own file: the cell is replaced by null and the file path
+//! is recorded in a companion `<column>__spilled` text column.
+//!
+//! Spilling can be requested per query with
+//! [`QueryOptions::with_spill`](crate::QueryOptions::with_spill).
+
+use crate::convert::type_mismatch;
+use crate::DremioClientError;
+use arrow::array::{
+    make_array, Array, ArrayRef, AsArray, BooleanBufferBuilder, RecordBatch, StringArray,
+};
+use arrow::buffer::NullBuffer;
+use arrow::datatypes::{DataType, Field, FieldRef, Schema};
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Suffix of the companion column that holds the paths of spilled cells.
+pub const SPILL_COLUMN_SUFFIX: &str = "__spilled";
+
+/// Distinguishes `SpillOptions` created in the same process.
+static INSTANCES: AtomicU64 = AtomicU64::new(0);
+
+/// Settings for spilling oversized binary and string cells to files.
+#[derive(Clone)]
+pub struct SpillOptions {
+    dir: PathBuf,
+    threshold: usize,
+    /// Makes file names unique across `SpillOptions` and processes sharing
+    /// `dir`, including processes that reuse an earlier process id.
+    prefix: String,
+    counter: Arc<AtomicU64>,
+}
+
+impl fmt::Debug for SpillOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SpillOptions")
+            .field("dir", &self.dir)
+            .field("threshold", &self.threshold)
+            .finish()
+    }
+}
+
+impl SpillOptions {
+    /// Spills cells larger than `threshold` bytes into files under `dir`.
+    ///
+    /// Files are never overwritten: every cell gets a new file whose name is
+    /// unique to these options, and spilling fails if it exists anyway.
+    pub fn new(dir: impl Into<PathBuf>, threshold: usize) -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        Self {
+            dir: dir.into(),
+            threshold,
+            prefix: format!(
+                "{}-{:x}-{}",
+                std::process::id(),
+                nanos,
+                INSTANCES.fetch_add(1, Ordering::Relaxed)
+            ),
+            counter: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Returns the directory spilled cells are written to.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the size above which cells are spilled.
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    fn next_path(&self) -> PathBuf {
+        let n = self.counter.fetch_add(1, Ordering::Relaxed);
+        self.dir.join(format!("cell-{}-{:08}.bin", self.prefix, n))
+    }
+
+    /// Writes `bytes` to a new file, failing rather than replacing a file
+    /// another cell was spilled to.
+    fn write_cell(&self, bytes: &[u8]) -> Result<PathBuf, DremioClientError> {
+        let path = self.next_path();
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        file.write_all(bytes)?;
+        Ok(path)
+    }
+}
+
+/// Returns `true` for the variable-length binary and string types that may be
+/// spilled.
+pub fn is_blob_type(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Binary
+            | DataType::LargeBinary
+            | DataType::BinaryView
+            | DataType::Utf8
+            | DataType::LargeUtf8
+            | DataType::Utf8View
+    )
+}
+
+/// Returns the bytes of the cell at `row` for any binary or string array,
+/// including the `Large` and `View` variants, or `None` if the value is null.
+pub fn cell_bytes(array: &dyn Array, row: usize) -> Result<Option<&[u8]>, DremioClientError> {
+    if array.is_null(row) {
+        return Ok(None);
+    }
+    let bytes = match array.data_type() {
+        DataType::Binary => array.as_binary::<i32>().value(row),
+        DataType::LargeBinary => array.as_binary::<i64>().value(row),
+        DataType::BinaryView => array.as_binary_view().value(row),
+        DataType::FixedSizeBinary(_) => array.as_fixed_size_binary().value(row),
+        DataType::Utf8 => array.as_string::<i32>().value(row).as_bytes(),
+        DataType::LargeUtf8 => array.as_string::<i64>().value(row).as_bytes(),
+        DataType::Utf8View => array.as_string_view().value(row).as_bytes(),
+        other => return Err(type_mismatch("binary or string", other)),
+    };
+    Ok(Some(bytes))
+}
+
+/// Moves cells larger than the configured threshold out of `batch` into files.
+///
+/// Every binary or string column `c` is followed by a `c__spilled` column. For
+/// spilled cells `c` is null and `c__spilled` holds the file path; for all
+/// other cells `c__spilled` is null.
+pub fn spill_large_cells(
+    batch: RecordBatch,
+    options: &SpillOptions,
+) -> Result<RecordBatch, DremioClientError> {
+    let schema = batch.schema();
+    if !schema.fields().iter().any(|f| is_blob_type(f.data_type())) {
+        return Ok(batch);
+    }
+    let mut fields: Vec<FieldRef> = Vec::with_capacity(schema.fields().len() + 1);
+    let mut columns: Vec<ArrayRef> = Vec::with_capacity(batch.num_columns() + 1);
+    for (field, column) in schema.fields().iter().zip(batch.columns()) {
+        if !is_blob_type(field.data_type()) {
+            fields.push(field.clone());
+            columns.push(column.clone());
+            continue;
+        }
+        let (column, references) = spill_column(column, options)?;
+        fields.push(Arc::new(Field::clone(field).with_nullable(true)));
+        columns.push(column);
+        fields.push(Arc::new(Field::new(
+            format!("{}{}", field.name(), SPILL_COLUMN_SUFFIX),
+            DataType::Utf8,
+            true,
+        )));
+        columns.push(Arc::new(references));
+    }
+    let schema = Schema::new_with_metadata(fields, schema.metadata().clone());
+    Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+}
+
+fn spill_column(
+    column: &ArrayRef,
+    options: &SpillOptions,
+) -> Result<(ArrayRef, StringArray), DremioClientError> {
+    let mut references: Vec<Option<String>> = vec![None; column.len()];
+    let mut valid = BooleanBufferBuilder::new(column.len());
+    let mut spilled_any = false;
+    for (row, reference) in references.iter_mut().enumerate() {
+        match cell_bytes(column.as_ref(), row)? {
+            Some(bytes) if bytes.len() > options.threshold => {
+                if !spilled_any {
+                    fs::create_dir_all(&options.dir)?;
+                    spilled_any = true;
+                }
+                let path = options.write_cell(bytes)?;
+                *reference = Some(path.to_string_lossy().into_owned());
+                valid.append(false);
+            }
+            Some(_) => valid.append(true),
+            None => valid.append(false),
+        }
+    }
+    let references = StringArray::from(references);
+    if !spilled_any {
+        return Ok((column.clone(), references));
+    }
+    let nulls = NullBuffer::new(valid.finish());
+    let data = column.to_data().into_builder().nulls(Some(nulls)).build()?;
+    Ok((make_array(data), references))
+}
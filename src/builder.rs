@@ -0,0 +1,954 @@
+//! Configuring how clients connect.
+
+use crate::dns::{self, Resolve};
+use crate::retry::RetryPolicy;
+use crate::status;
+use crate::{Client, DremioClientError, QueryOptions, SessionOptions};
+use arrow::error::ArrowError;
+use arrow_flight::flight_service_client::FlightServiceClient;
+use arrow_flight::sql::client::FlightSqlServiceClient;
+use futures::future::BoxFuture;
+use hyper_util::rt::TokioIo;
+use std::error::Error as StdError;
+use std::fmt;
+use std::net::SocketAddr;
+#[cfg(feature = "tls")]
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tonic::codegen::Service;
+use tonic::metadata::{AsciiMetadataKey, AsciiMetadataValue};
+#[cfg(feature = "tls")]
+use tonic::transport::{Certificate, ClientTlsConfig, Identity};
+use tonic::transport::{Channel, Endpoint, Error as TonicError, Uri};
+
+/// The part of the `user-agent` header identifying this crate.
+const USER_AGENT: &str = concat!("dremio-rs/", env!("CARGO_PKG_VERSION"));
+
+/// Dremio's default Flight port.
+const FLIGHT_PORT: u16 = 32010;
+
+/// How long each probe may take before the next combination is tried.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+type BoxError = Box<dyn StdError + Send + Sync>;
+
+/// Opens a channel to an endpoint over a custom connector.
+type Connect = dyn Fn(&Endpoint) -> BoxFuture<'static, Result<Channel, TonicError>> + Send + Sync;
+
+/// How a [`Client`] authenticates to the coordinator.
+#[derive(Clone, PartialEq, Eq)]
+pub enum Auth {
+    /// A user name and password, exchanged for a session token with a
+    /// Flight handshake. Dremio also accepts a personal access token as the
+    /// password.
+    Basic {
+        /// The user name.
+        user: String,
+        /// The password.
+        pass: String,
+    },
+    /// A personal access token or another bearer token, sent in the
+    /// `authorization` header of every request without a handshake.
+    Token(String),
+}
+
+impl fmt::Debug for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Keep secrets out of logs.
+        match self {
+            Auth::Basic { user, .. } => f
+                .debug_struct("Basic")
+                .field("user", user)
+                .finish_non_exhaustive(),
+            Auth::Token(_) => f.write_str("Token(..)"),
+        }
+    }
+}
+
+/// Configures how a [`Client`] connects to the coordinator.
+///
+/// [`Client::new`] covers the common case; the builder is for connections
+/// that need more control, such as resolving the coordinator's host name
+/// with a service registry.
+///
+/// # Example
+///
+/// ```no_run
+/// use dremio_rs::dns::StaticHosts;
+/// use dremio_rs::ClientBuilder;
+///
+/// #[tokio::main]
+/// async fn main() {
+///   let hosts = StaticHosts::new().with_host("dremio.internal", "10.0.0.7".parse().unwrap());
+///   let mut client = ClientBuilder::new("http://dremio.internal:32010")
+///     .with_credentials("dremio", "dremio123")
+///     .with_resolver(hosts)
+///     .connect()
+///     .await
+///     .unwrap();
+/// }
+/// ```
+#[derive(Clone)]
+pub struct ClientBuilder {
+    url: String,
+    auth: Option<Auth>,
+    resolver: Option<Arc<dyn Resolve>>,
+    address: Option<SocketAddr>,
+    connector: Option<Arc<Connect>>,
+    client_name: Option<String>,
+    probe_timeout: Duration,
+    connect_timeout: Option<Duration>,
+    request_timeout: Option<Duration>,
+    tcp_keepalive: Option<Duration>,
+    http2_keepalive_interval: Option<Duration>,
+    max_inbound_message_size: Option<usize>,
+    max_outbound_message_size: Option<usize>,
+    headers: Vec<(String, String)>,
+    retry_policy: RetryPolicy,
+    #[cfg(feature = "rest")]
+    rest_url: Option<String>,
+    #[cfg(feature = "tls")]
+    tls_key_log: bool,
+    #[cfg(feature = "tls")]
+    tls_config: Option<ClientTlsConfig>,
+    #[cfg(feature = "tls")]
+    ca_certificate: Option<PathBuf>,
+    #[cfg(feature = "tls")]
+    client_identity: Option<(PathBuf, PathBuf)>,
+    #[cfg(feature = "tls")]
+    tls_domain: Option<String>,
+}
+
+impl fmt::Debug for ClientBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientBuilder")
+            .field("url", &self.url)
+            .field("auth", &self.auth)
+            .field("resolver", &self.resolver.is_some())
+            .field("address", &self.address)
+            .field("connector", &self.connector.is_some())
+            .field("client_name", &self.client_name)
+            .field("probe_timeout", &self.probe_timeout)
+            .field("connect_timeout", &self.connect_timeout)
+            .field("request_timeout", &self.request_timeout)
+            .field("tcp_keepalive", &self.tcp_keepalive)
+            .field("http2_keepalive_interval", &self.http2_keepalive_interval)
+            .field("max_inbound_message_size", &self.max_inbound_message_size)
+            .field("max_outbound_message_size", &self.max_outbound_message_size)
+            .field(
+                "headers",
+                &self.headers.iter().map(|(key, _)| key).collect::<Vec<_>>(),
+            )
+            .field("retry_policy", &self.retry_policy)
+            .finish()
+    }
+}
+
+impl ClientBuilder {
+    /// Creates a builder for a client of the coordinator at `url`, such as
+    /// `http://localhost:32010`. The `grpc+tls://` and `grpc://` (or
+    /// `grpc+tcp://`) schemes used by other Flight clients are accepted as
+    /// spellings of `https://` and `http://`.
+    ///
+    /// `url` may also be just a host name, with or without a port, such as
+    /// `dremio.example.com`, in which case [`connect`](Self::connect) probes
+    /// for the scheme and port the coordinator serves Flight on.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            auth: None,
+            resolver: None,
+            address: None,
+            connector: None,
+            client_name: None,
+            probe_timeout: PROBE_TIMEOUT,
+            connect_timeout: None,
+            request_timeout: None,
+            tcp_keepalive: None,
+            http2_keepalive_interval: None,
+            max_inbound_message_size: None,
+            max_outbound_message_size: None,
+            headers: Vec::new(),
+            retry_policy: RetryPolicy::none(),
+            #[cfg(feature = "rest")]
+            rest_url: None,
+            #[cfg(feature = "tls")]
+            tls_key_log: false,
+            #[cfg(feature = "tls")]
+            tls_config: None,
+            #[cfg(feature = "tls")]
+            ca_certificate: None,
+            #[cfg(feature = "tls")]
+            client_identity: None,
+            #[cfg(feature = "tls")]
+            tls_domain: None,
+        }
+    }
+
+    /// Authenticates with a user name and password. Without credentials, no
+    /// handshake takes place.
+    pub fn with_credentials(mut self, user: impl Into<String>, pass: impl Into<String>) -> Self {
+        self.auth = Some(Auth::Basic {
+            user: user.into(),
+            pass: pass.into(),
+        });
+        self
+    }
+
+    /// Authenticates with a personal access token or another bearer token,
+    /// such as those of Dremio Cloud, sent with every request instead of a
+    /// handshake.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::ClientBuilder;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let token = std::env::var("DREMIO_PAT").unwrap();
+    ///   let mut client = ClientBuilder::new("grpc+tls://data.dremio.cloud:443")
+    ///     .with_token(token)
+    ///     .connect()
+    ///     .await
+    ///     .unwrap();
+    /// }
+    /// ```
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.auth = Some(Auth::Token(token.into()));
+        self
+    }
+
+    /// Authenticates as `auth` says, replacing any credentials or token set
+    /// before.
+    pub fn with_auth(mut self, auth: Auth) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// Resolves the host name of the URL with `resolver` instead of the
+    /// system resolver. See [`dns`](crate::dns).
+    pub fn with_resolver(mut self, resolver: impl Resolve) -> Self {
+        self.resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    /// Dials `addr`, an IPv4 or IPv6 address, instead of the host of the URL.
+    ///
+    /// The URL still gives the scheme and the authority sent with every
+    /// request, so a coordinator behind a proxy or load balancer that routes
+    /// on the host name can be reached at a known address. Takes precedence
+    /// over [`with_resolver`](Self::with_resolver).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dremio_rs::ClientBuilder;
+    ///
+    /// let builder = ClientBuilder::new("http://dremio.example.com:32010")
+    ///     .with_address("[fd00::7]:32010".parse().unwrap());
+    /// ```
+    pub fn with_address(mut self, addr: SocketAddr) -> Self {
+        self.address = Some(addr);
+        self
+    }
+
+    /// Opens connections with `connector` instead of over TCP, for example
+    /// over a Unix domain socket or through an SSH tunnel.
+    ///
+    /// `connector` is a `tower` service called with the URL whenever the
+    /// channel (re)connects, and returning any Tokio byte stream. The URL
+    /// then only provides the scheme and the authority sent with requests.
+    /// Takes precedence over [`with_resolver`](Self::with_resolver) and
+    /// [`with_address`](Self::with_address).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::ClientBuilder;
+    /// use std::future::Future;
+    /// use std::pin::Pin;
+    /// use std::task::{Context, Poll};
+    /// use tokio::net::UnixStream;
+    /// use tonic::codegen::Service;
+    /// use tonic::transport::Uri;
+    ///
+    /// #[derive(Clone)]
+    /// struct Socket(&'static str);
+    ///
+    /// impl Service<Uri> for Socket {
+    ///   type Response = UnixStream;
+    ///   type Error = std::io::Error;
+    ///   type Future = Pin<Box<dyn Future<Output = std::io::Result<UnixStream>> + Send>>;
+    ///
+    ///   fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+    ///     Poll::Ready(Ok(()))
+    ///   }
+    ///
+    ///   fn call(&mut self, _: Uri) -> Self::Future {
+    ///     Box::pin(UnixStream::connect(self.0))
+    ///   }
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let mut client = ClientBuilder::new("http://dremio")
+    ///     .with_credentials("dremio", "dremio123")
+    ///     .with_connector(Socket("/var/run/dremio/flight.sock"))
+    ///     .connect()
+    ///     .await
+    ///     .unwrap();
+    /// }
+    /// ```
+    pub fn with_connector<C>(mut self, connector: C) -> Self
+    where
+        C: Service<Uri> + Clone + Send + Sync + 'static,
+        C::Response: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+        C::Future: Send + 'static,
+        C::Error: Into<BoxError>,
+    {
+        self.connector = Some(Arc::new(move |endpoint: &Endpoint| {
+            let endpoint = endpoint.clone();
+            let connector = TokioConnector(connector.clone());
+            Box::pin(async move { endpoint.connect_with_connector(connector).await })
+        }));
+        self
+    }
+
+    /// Identifies the application using the client to the coordinator.
+    ///
+    /// `name` and `version` are sent in the `user-agent` header of every
+    /// request, ahead of this crate's own, as in `etl-loader/1.4.2
+    /// dremio-rs/0.2.7`. Dremio records the header with each job, so
+    /// administrators can tell which application ran a query.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dremio_rs::ClientBuilder;
+    ///
+    /// let builder = ClientBuilder::new("http://localhost:32010")
+    ///     .with_client_name("etl-loader", env!("CARGO_PKG_VERSION"));
+    /// ```
+    pub fn with_client_name(mut self, name: &str, version: &str) -> Self {
+        self.client_name = Some(format!("{}/{}", name, version));
+        self
+    }
+
+    /// Fails connection attempts that take longer than `timeout`, including
+    /// reconnections after the coordinator went away.
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Fails requests the coordinator has not started answering within
+    /// `timeout`.
+    ///
+    /// Results already being streamed are not cut off; use
+    /// [`QueryOptions::with_timeout`] for a deadline on whole queries.
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Enables TCP keepalive probes after the connection has been idle for
+    /// `idle`, so that firewalls and load balancers do not drop it. Not
+    /// applied to connections opened by a
+    /// [custom connector](Self::with_connector).
+    pub fn with_tcp_keepalive(mut self, idle: Duration) -> Self {
+        self.tcp_keepalive = Some(idle);
+        self
+    }
+
+    /// Sends HTTP/2 pings every `interval` while requests are in flight, so a
+    /// connection that died during a long-running query is noticed.
+    pub fn with_http2_keepalive_interval(mut self, interval: Duration) -> Self {
+        self.http2_keepalive_interval = Some(interval);
+        self
+    }
+
+    /// Accepts gRPC messages of up to `bytes` from the coordinator, instead of
+    /// tonic's default of 4 MiB, which large record batches exceed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dremio_rs::ClientBuilder;
+    /// use std::time::Duration;
+    ///
+    /// let builder = ClientBuilder::new("http://localhost:32010")
+    ///     .with_credentials("dremio", "dremio123")
+    ///     .with_connect_timeout(Duration::from_secs(5))
+    ///     .with_tcp_keepalive(Duration::from_secs(60))
+    ///     .with_max_inbound_message_size(64 * 1024 * 1024)
+    ///     .with_header("x-request-source", "nightly-export");
+    /// ```
+    pub fn with_max_inbound_message_size(mut self, bytes: usize) -> Self {
+        self.max_inbound_message_size = Some(bytes);
+        self
+    }
+
+    /// Sends gRPC messages of up to `bytes` to the coordinator, for example
+    /// when uploading large batches. Unlimited by default.
+    pub fn with_max_outbound_message_size(mut self, bytes: usize) -> Self {
+        self.max_outbound_message_size = Some(bytes);
+        self
+    }
+
+    /// Sends the gRPC metadata header `key: value` with every request, for
+    /// example for a proxy in front of the coordinator or to set a Dremio
+    /// session property such as `routing_tag`. Keys must be lowercase ASCII.
+    pub fn with_header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((key.into(), value.into()));
+        self
+    }
+
+    /// Sets Dremio session properties, such as the default schema or the
+    /// routing queue, for every query, replacing the headers of the same
+    /// names. Queries can override them with
+    /// [`QueryOptions::with_session_options`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::{ClientBuilder, SessionOptions};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let mut client = ClientBuilder::new("http://localhost:32010")
+    ///     .with_credentials("dremio", "dremio123")
+    ///     .with_session_options(SessionOptions::new().with_schema("lake.sales"))
+    ///     .connect()
+    ///     .await
+    ///     .unwrap();
+    ///   let batches = client.get_record_batches("SELECT * FROM orders").await.unwrap();
+    /// }
+    /// ```
+    pub fn with_session_options(mut self, session: SessionOptions) -> Self {
+        for (key, value) in session.headers() {
+            self.headers.retain(|(k, _)| k != key);
+            self.headers.push((key.to_string(), value.to_string()));
+        }
+        self
+    }
+
+    /// Sets how long each combination of scheme and port may take to connect
+    /// and authenticate when probing a bare host name, three seconds by
+    /// default.
+    pub fn with_probe_timeout(mut self, timeout: Duration) -> Self {
+        self.probe_timeout = timeout;
+        self
+    }
+
+    /// Retries submitting queries, fetching their results and the catalog
+    /// metadata calls as `policy` says, re-authenticating when the session
+    /// token was rejected. By default requests are not retried. See
+    /// [`retry`](crate::retry).
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Sets the base URL of the REST API, such as `https://dremio.example.com`,
+    /// for [`Client::connect_rest`]. Defaults to port 9047 of the
+    /// coordinator's host.
+    #[cfg(feature = "rest")]
+    pub fn with_rest_url(mut self, url: impl Into<String>) -> Self {
+        self.rest_url = Some(url.into());
+        self
+    }
+
+    /// Logs in to the REST API with the credentials of this builder.
+    #[cfg(feature = "rest")]
+    pub(crate) async fn connect_rest(&self) -> Result<crate::rest::RestClient, DremioClientError> {
+        let url = match &self.rest_url {
+            Some(url) => url.clone(),
+            None => default_rest_url(&self.url)?,
+        };
+        match &self.auth {
+            Some(Auth::Basic { user, pass }) => {
+                crate::rest::RestClient::login(&url, user, pass).await
+            }
+            Some(Auth::Token(token)) => Ok(crate::rest::RestClient::with_token(&url, token)),
+            None => Err(DremioClientError::ConfigError(
+                "no credentials to log in to the REST API with".to_string(),
+            )),
+        }
+    }
+
+    /// Returns how requests are retried.
+    pub(crate) fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    /// Writes the secrets of TLS sessions to the key log file named by the
+    /// `SSLKEYLOGFILE` environment variable, so that tools such as Wireshark
+    /// can decrypt the Flight traffic captured while diagnosing a problem.
+    ///
+    /// Only use this for debugging: anyone who can read the file can read
+    /// the traffic. Nothing is written when the variable is not set or the
+    /// URL does not use `https`.
+    #[cfg(feature = "tls")]
+    pub fn with_tls_key_log(mut self) -> Self {
+        self.tls_key_log = true;
+        self
+    }
+
+    /// Configures TLS with `config` instead of verifying the coordinator's
+    /// certificate against the platform's trusted roots.
+    ///
+    /// The settings below are applied on top of `config`, and the domain name
+    /// is always the one given to [`with_tls_domain`](Self::with_tls_domain)
+    /// or else the host of the URL.
+    #[cfg(feature = "tls")]
+    pub fn with_tls_config(mut self, config: ClientTlsConfig) -> Self {
+        self.tls_config = Some(config);
+        self
+    }
+
+    /// Also trusts the certificate authorities in the PEM file at `path`, for
+    /// coordinators whose certificates are issued by a private CA.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::ClientBuilder;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let mut client = ClientBuilder::new("grpc+tls://dremio.example.com:32010")
+    ///     .with_credentials("dremio", "dremio123")
+    ///     .with_ca_certificate("/etc/dremio/ca.pem")
+    ///     .with_client_identity("/etc/dremio/client.pem", "/etc/dremio/client.key")
+    ///     .connect()
+    ///     .await
+    ///     .unwrap();
+    /// }
+    /// ```
+    #[cfg(feature = "tls")]
+    pub fn with_ca_certificate(mut self, path: impl Into<PathBuf>) -> Self {
+        self.ca_certificate = Some(path.into());
+        self
+    }
+
+    /// Presents the certificate in the PEM file at `cert`, with the private
+    /// key in the PEM file at `key`, to coordinators requiring mutual TLS.
+    #[cfg(feature = "tls")]
+    pub fn with_client_identity(
+        mut self,
+        cert: impl Into<PathBuf>,
+        key: impl Into<PathBuf>,
+    ) -> Self {
+        self.client_identity = Some((cert.into(), key.into()));
+        self
+    }
+
+    /// Verifies the coordinator's certificate against `domain` instead of the
+    /// host of the URL, for example when connecting through a tunnel or by IP
+    /// address.
+    #[cfg(feature = "tls")]
+    pub fn with_tls_domain(mut self, domain: impl Into<String>) -> Self {
+        self.tls_domain = Some(domain.into());
+        self
+    }
+
+    /// Connects to the coordinator and authenticates.
+    ///
+    /// When the URL is just a host name, the common combinations of scheme
+    /// and port are tried in turn, each for at most the
+    /// [probe timeout](Self::with_probe_timeout): `https` (with the `tls`
+    /// feature) and then `http` on the given port, or on Dremio's default
+    /// port 32010 followed by `https` on port 443 when no port is given. The
+    /// first combination that connects and authenticates is used. A rejected
+    /// user name or password ends the probing, since the coordinator was
+    /// found.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(Client)` if the connection is successful and authentication succeeds.
+    /// - `Err(DremioClientError::ProbeFailed)` if no combination worked, listing
+    ///   what was tried and why each attempt failed.
+    /// - `Err(DremioClientError)` if the URL is invalid, or an error occurs
+    ///   during connection or authentication.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::ClientBuilder;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   match ClientBuilder::new("dremio.example.com")
+    ///     .with_credentials("dremio", "dremio123")
+    ///     .connect()
+    ///     .await
+    ///   {
+    ///     Ok(client) => println!("connected"),
+    ///     Err(e) => eprintln!("{}", e),
+    ///   }
+    /// }
+    /// ```
+    pub async fn connect(self) -> Result<Client, DremioClientError> {
+        if self.url.contains("://") {
+            return self.connect_to(&self.url).await;
+        }
+        let mut attempts = Vec::new();
+        for url in probe_urls(&self.url)? {
+            let error = match tokio::time::timeout(self.probe_timeout, self.connect_to(&url)).await
+            {
+                Ok(Ok(client)) => return Ok(client),
+                Ok(Err(e)) if unauthenticated(&e) => return Err(e),
+                Ok(Err(e)) => describe(&e),
+                Err(_) => format!("no answer within {:?}", self.probe_timeout),
+            };
+            attempts.push(ProbeAttempt { url, error });
+        }
+        Err(DremioClientError::ProbeFailed(ProbeFailure {
+            host: self.url.clone(),
+            attempts,
+        }))
+    }
+
+    /// Connects to the coordinator at `url` and authenticates.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "connect", skip(self), err)
+    )]
+    async fn connect_to(&self, url: &str) -> Result<Client, DremioClientError> {
+        let mut endpoint = Endpoint::from_shared(normalize_scheme(url))?;
+        let uri = endpoint.uri().clone();
+        if let Some(addr) = self.address {
+            // IPv6 addresses are displayed in brackets, as URLs require.
+            let scheme = uri.scheme_str().unwrap_or("http");
+            let dialed = Endpoint::from_shared(format!("{}://{}", scheme, addr))?;
+            endpoint = dialed.origin(uri.clone());
+        }
+        // Verify the certificate against the URL, not the dialed address.
+        #[cfg(feature = "tls")]
+        let domain = self.tls_domain.as_deref().or(uri.host());
+        #[cfg(not(feature = "tls"))]
+        let domain = uri.host();
+        let endpoint = self.configure(endpoint, domain)?;
+        let channel = match (&self.connector, &self.resolver) {
+            (Some(connect), _) => connect(&endpoint).await?,
+            (None, Some(resolver)) if self.address.is_none() => {
+                endpoint
+                    .connect_with_connector(dns::connector(resolver.clone(), self.tcp_keepalive))
+                    .await?
+            }
+            _ => endpoint.connect().await?,
+        };
+        let mut client = self.flight_sql_client(channel)?;
+        match &self.auth {
+            Some(Auth::Basic { .. }) => {
+                self.handshake(&mut client).await?;
+            }
+            Some(Auth::Token(token)) => client.set_token(token.clone()),
+            None => {}
+        }
+        Ok(Client {
+            flight_sql_service_client: client,
+            cache: None,
+            schema_cache: None,
+            query_options: QueryOptions::default(),
+            stale_metadata_retry: false,
+            headers: self.headers.clone().into(),
+            settings: Arc::new(self.clone()),
+            session_token: Arc::default(),
+        })
+    }
+
+    /// Describes the cluster and identity queries are sent with, so that
+    /// cached results are never shared between clusters or users. Tokens are
+    /// hashed to keep them out of cache keys and file names.
+    pub(crate) fn cache_scope(&self) -> String {
+        let identity = match &self.auth {
+            Some(Auth::Basic { user, .. }) => format!("user:{user}"),
+            Some(Auth::Token(token)) => format!(
+                "token:{:016x}",
+                crate::cache::fnv1a(token.as_bytes(), 0xcbf2_9ce4_8422_2325)
+            ),
+            None => "anonymous".to_string(),
+        };
+        format!("{}/{:?}/{}", self.url, self.address, identity)
+    }
+
+    /// Exchanges the user name and password for a new session token, which
+    /// `client` sends from then on. Returns whether a handshake took place,
+    /// which only happens when authenticating with a password.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err)
+    )]
+    pub(crate) async fn handshake(
+        &self,
+        client: &mut FlightSqlServiceClient<Channel>,
+    ) -> Result<bool, DremioClientError> {
+        match &self.auth {
+            Some(Auth::Basic { user, pass }) => {
+                // The client would send its current token instead.
+                client.clear_token();
+                client
+                    .handshake(user, pass)
+                    .await
+                    .map_err(|e| status::classify(e.into()))?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Returns a client for the executor at `location`, a URL from a Flight
+    /// endpoint, authenticated like `coordinator`.
+    ///
+    /// The channel connects on first use, with the settings of this builder
+    /// except those that only apply to the coordinator: its address, resolver
+    /// or connector and the TLS domain name.
+    pub(crate) fn location_client(
+        &self,
+        location: &str,
+        coordinator: &FlightSqlServiceClient<Channel>,
+    ) -> Result<FlightSqlServiceClient<Channel>, DremioClientError> {
+        let endpoint = Endpoint::from_shared(normalize_scheme(location))?;
+        let host = endpoint.uri().host().map(str::to_string);
+        let endpoint = self.configure(endpoint, host.as_deref())?;
+        let mut client = self.flight_sql_client(endpoint.connect_lazy())?;
+        if let Some(token) = coordinator.token() {
+            client.set_token(token.clone());
+        }
+        Ok(client)
+    }
+
+    /// Applies the transport settings to `endpoint`, verifying TLS
+    /// certificates against `domain`.
+    fn configure(
+        &self,
+        mut endpoint: Endpoint,
+        domain: Option<&str>,
+    ) -> Result<Endpoint, DremioClientError> {
+        let user_agent = match &self.client_name {
+            Some(name) => format!("{} {}", name, USER_AGENT),
+            None => USER_AGENT.to_string(),
+        };
+        endpoint = endpoint.user_agent(user_agent)?;
+        if let Some(timeout) = self.connect_timeout {
+            endpoint = endpoint.connect_timeout(timeout);
+        }
+        if let Some(timeout) = self.request_timeout {
+            endpoint = endpoint.timeout(timeout);
+        }
+        if let Some(interval) = self.http2_keepalive_interval {
+            endpoint = endpoint.http2_keep_alive_interval(interval);
+        }
+        endpoint = endpoint.tcp_keepalive(self.tcp_keepalive);
+        #[cfg(not(feature = "tls"))]
+        let _ = domain;
+        #[cfg(feature = "tls")]
+        if endpoint.uri().scheme_str() == Some("https") {
+            let mut tls = match &self.tls_config {
+                Some(config) => config.clone(),
+                None => ClientTlsConfig::new().with_enabled_roots(),
+            };
+            if let Some(path) = &self.ca_certificate {
+                tls = tls.ca_certificate(Certificate::from_pem(read_pem(path)?));
+            }
+            if let Some((cert, key)) = &self.client_identity {
+                tls = tls.identity(Identity::from_pem(read_pem(cert)?, read_pem(key)?));
+            }
+            if let Some(domain) = domain {
+                tls = tls.domain_name(domain.trim_start_matches('[').trim_end_matches(']'));
+            }
+            if self.tls_key_log {
+                tls = tls.use_key_log();
+            }
+            endpoint = endpoint.tls_config(tls)?;
+        }
+        Ok(endpoint)
+    }
+
+    /// Wraps `channel` in a Flight SQL client with the message size limits and
+    /// extra headers of this builder.
+    fn flight_sql_client(
+        &self,
+        channel: Channel,
+    ) -> Result<FlightSqlServiceClient<Channel>, DremioClientError> {
+        let mut flight = FlightServiceClient::new(channel);
+        if let Some(bytes) = self.max_inbound_message_size {
+            flight = flight.max_decoding_message_size(bytes);
+        }
+        if let Some(bytes) = self.max_outbound_message_size {
+            flight = flight.max_encoding_message_size(bytes);
+        }
+        let mut client = FlightSqlServiceClient::new_from_inner(flight);
+        for (key, value) in &self.headers {
+            check_header(key, value)?;
+            client.set_header(key, value);
+        }
+        Ok(client)
+    }
+}
+
+/// A combination of scheme and port tried while probing a host name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProbeAttempt {
+    /// The URL tried, such as `https://dremio.example.com:32010`.
+    pub url: String,
+    /// Why connecting or authenticating failed.
+    pub error: String,
+}
+
+/// No combination of scheme and port reached the coordinator, see
+/// [`ClientBuilder::connect`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProbeFailure {
+    host: String,
+    attempts: Vec<ProbeAttempt>,
+}
+
+impl ProbeFailure {
+    /// Returns the host name that was probed, as given to the builder.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Returns the combinations tried, in order.
+    pub fn attempts(&self) -> &[ProbeAttempt] {
+        &self.attempts
+    }
+}
+
+impl fmt::Display for ProbeFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no Flight SQL service found at {}; tried:", self.host)?;
+        for attempt in &self.attempts {
+            write!(f, "\n  {}: {}", attempt.url, attempt.error)?;
+        }
+        if cfg!(not(feature = "tls")) {
+            write!(
+                f,
+                "\n  (https was not tried, as the `tls` feature is not enabled)"
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks that `key: value` can be sent as gRPC metadata, which the Flight SQL
+/// client would otherwise only report when sending a request.
+fn check_header(key: &str, value: &str) -> Result<(), DremioClientError> {
+    let invalid = |e: &dyn fmt::Display| {
+        ArrowError::InvalidArgumentError(format!("invalid header {:?}: {}", key, e))
+    };
+    AsciiMetadataKey::from_str(key).map_err(|e| invalid(&e))?;
+    AsciiMetadataValue::try_from(value).map_err(|e| invalid(&e))?;
+    Ok(())
+}
+
+/// Spells the Flight URL schemes `grpc+tls` and `grpc`/`grpc+tcp` as `https`
+/// and `http`.
+fn normalize_scheme(url: &str) -> String {
+    match url.split_once("://") {
+        Some(("grpc+tls", rest)) => format!("https://{}", rest),
+        Some(("grpc" | "grpc+tcp", rest)) => format!("http://{}", rest),
+        _ => url.to_string(),
+    }
+}
+
+/// Returns the REST API endpoint of the cluster whose Flight SQL endpoint is
+/// `url`, which may be a bare host name: port 9047 of the same host, over
+/// HTTPS if Flight SQL uses TLS.
+#[cfg(feature = "rest")]
+pub(crate) fn default_rest_url(url: &str) -> Result<String, DremioClientError> {
+    let invalid = || DremioClientError::ConfigError(format!("invalid URL {:?}", url));
+    let flight = match normalize_scheme(url) {
+        url if url.contains("://") => url,
+        host => format!("http://{}", host),
+    };
+    let mut rest = reqwest::Url::parse(&flight).map_err(|_| invalid())?;
+    rest.set_port(Some(9047)).map_err(|()| invalid())?;
+    Ok(rest.as_str().trim_end_matches('/').to_string())
+}
+
+/// Reads a PEM file configured for TLS.
+#[cfg(feature = "tls")]
+fn read_pem(path: &Path) -> Result<Vec<u8>, DremioClientError> {
+    std::fs::read(path)
+        .map_err(|e| DremioClientError::ConfigError(format!("{}: {}", path.display(), e)))
+}
+
+/// Returns the URLs to probe for `host`, a host name with an optional port.
+fn probe_urls(host: &str) -> Result<Vec<String>, DremioClientError> {
+    let uri: Uri = format!("http://{}", host).parse().map_err(|_| {
+        ArrowError::InvalidArgumentError(format!("invalid host name or URL: {}", host))
+    })?;
+    let name = uri.host().unwrap_or(host);
+    let mut combinations = vec![("https", uri.port_u16().unwrap_or(FLIGHT_PORT))];
+    combinations.push(("http", combinations[0].1));
+    if uri.port_u16().is_none() {
+        combinations.push(("https", 443));
+    }
+    Ok(combinations
+        .into_iter()
+        .filter(|(scheme, _)| cfg!(feature = "tls") || *scheme == "http")
+        .map(|(scheme, port)| format!("{}://{}:{}", scheme, name, port))
+        .collect())
+}
+
+/// Returns whether the coordinator rejected the credentials.
+fn unauthenticated(e: &DremioClientError) -> bool {
+    match e {
+        DremioClientError::AuthenticationFailed(_) => true,
+        DremioClientError::ArrowError(ArrowError::IpcError(message)) => {
+            message.contains("Unauthenticated")
+                || message.contains("does not have valid authentication credentials")
+        }
+        _ => false,
+    }
+}
+
+/// Spells out `e` and its causes, which for transport errors is where the
+/// useful part is, such as "Connection refused".
+fn describe(e: &DremioClientError) -> String {
+    let mut description = e.to_string();
+    let mut source = e.source();
+    while let Some(cause) = source {
+        let cause_text = cause.to_string();
+        if !description.contains(&cause_text) {
+            description = format!("{}: {}", description, cause_text);
+        }
+        source = cause.source();
+    }
+    description
+}
+
+/// Adapts a connector returning Tokio streams to the channel, which expects
+/// Hyper streams.
+#[derive(Clone)]
+struct TokioConnector<C>(C);
+
+impl<C> Service<Uri> for TokioConnector<C>
+where
+    C: Service<Uri>,
+    C::Response: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+    C::Future: Send + 'static,
+    C::Error: Into<BoxError>,
+{
+    type Response = TokioIo<C::Response>;
+    type Error = BoxError;
+    type Future = BoxFuture<'static, Result<Self::Response, BoxError>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        self.0.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let connecting = self.0.call(uri);
+        Box::pin(async move { Ok(TokioIo::new(connecting.await.map_err(Into::into)?)) })
+    }
+}
@@ -0,0 +1,603 @@
+//! Result caching for repeated queries.
+//!
+//! Dashboards and other polling consumers often re-run the exact same SQL every
+//! few seconds. Attaching a [`ResultCache`] to a [`Client`](crate::Client) lets
+//! identical queries be answered from memory instead of round-tripping to Dremio.
+//!
+//! Two implementations are provided:
+//!
+//! - [`MemoryCache`], an in-process LRU cache.
+//! - [`DiskCache`], which stores results as Arrow IPC files so they survive
+//!   process restarts and can be shared between worker processes.
+//!
+//! [`SchemaCache`] answers repeated schema lookups with
+//! [`Client::get_schema`](crate::Client::get_schema) in the same way.
+//!
+//! Results are only reused by clients connected to the same cluster as the
+//! same user, with the same headers and session options, so one cache can
+//! safely be shared between clients.
+//!
+//! Caching is opt-in and best-effort: a cache that cannot store a result simply
+//! behaves like a miss, and the query is executed against Dremio as usual.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use dremio_rs::Client;
+//! use dremio_rs::cache::MemoryCache;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let cache = MemoryCache::new()
+//!         .with_ttl(Duration::from_secs(30))
+//!         .with_max_bytes(256 * 1024 * 1024);
+//!     let mut client = Client::new("http://localhost:32010", "dremio", "dremio123")
+//!         .await
+//!         .unwrap()
+//!         .with_result_cache(cache);
+//!     // The second call is served from memory.
+//!     client.get_record_batches("SELECT * FROM sys.options").await.unwrap();
+//!     client.get_record_batches("SELECT *  FROM sys.options").await.unwrap();
+//! }
+//! ```
+
+use arrow::array::RecordBatch;
+use arrow::datatypes::{Schema, SchemaRef};
+use arrow::ipc::reader::FileReader;
+use arrow::ipc::writer::FileWriter;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// The key under which a query result is cached.
+///
+/// Keys are built from the SQL text after whitespace normalization, so queries
+/// that differ only in formatting share an entry. Quoted literals and identifiers
+/// are preserved verbatim. Parameter values can be appended for parameterized
+/// queries so that different bindings do not collide.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CacheKey(String);
+
+impl CacheKey {
+    /// Creates a key from a SQL query, normalizing insignificant whitespace and
+    /// trailing semicolons.
+    pub fn new(sql: &str) -> Self {
+        Self(normalize_sql(sql))
+    }
+
+    /// Appends a parameter value to the key.
+    pub fn with_param(mut self, param: impl fmt::Display) -> Self {
+        self.0.push('\u{1f}');
+        self.0.push_str(&param.to_string());
+        self
+    }
+
+    /// Returns the normalized key text.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for CacheKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Collapses runs of whitespace outside of quoted sections into a single space
+/// and strips leading/trailing whitespace and semicolons. `--` comments are
+/// kept verbatim along with the newline ending them, so text after a comment
+/// is never mistaken for part of it.
+pub(crate) fn normalize_sql(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut quote: Option<char> = None;
+    let mut comment = false;
+    let mut pending_space = false;
+    for c in sql.chars() {
+        match quote {
+            _ if comment => {
+                out.push(c);
+                comment = c != '\n';
+            }
+            Some(q) => {
+                out.push(c);
+                if c == q {
+                    quote = None;
+                }
+            }
+            None if c.is_whitespace() => pending_space = true,
+            None => {
+                if pending_space && !out.is_empty() && !out.ends_with('\n') {
+                    out.push(' ');
+                }
+                pending_space = false;
+                if c == '\'' || c == '"' || c == '`' {
+                    quote = Some(c);
+                }
+                out.push(c);
+                comment = out.ends_with("--");
+            }
+        }
+    }
+    while out.ends_with(';') || out.ends_with(char::is_whitespace) {
+        out.pop();
+    }
+    out
+}
+
+/// A store for query results, consulted by [`Client`](crate::Client) before
+/// executing a query.
+///
+/// Implementations must be safe to share between threads; a single cache may
+/// be attached to several clients.
+pub trait ResultCache: Send + Sync {
+    /// Returns the cached batches for `key`, or `None` on a miss or expired entry.
+    fn get(&self, key: &CacheKey) -> Option<Vec<RecordBatch>>;
+
+    /// Stores `batches` under `key`, possibly evicting other entries.
+    fn put(&self, key: CacheKey, batches: &[RecordBatch]);
+
+    /// Removes the entry for `key`, if present.
+    fn invalidate(&self, key: &CacheKey);
+
+    /// Removes all entries.
+    fn clear(&self);
+}
+
+/// Returns the in-memory footprint of a result set, as used for cache accounting.
+pub(crate) fn batches_size(batches: &[RecordBatch]) -> usize {
+    batches.iter().map(|b| b.get_array_memory_size()).sum()
+}
+
+struct MemoryEntry {
+    batches: Vec<RecordBatch>,
+    size: usize,
+    inserted: Instant,
+    tick: u64,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    entries: HashMap<CacheKey, MemoryEntry>,
+    order: BTreeMap<u64, CacheKey>,
+    bytes: usize,
+    tick: u64,
+}
+
+impl MemoryState {
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.order.remove(&entry.tick);
+            self.bytes -= entry.size;
+        }
+    }
+
+    fn touch(&mut self, key: &CacheKey) {
+        self.tick += 1;
+        let tick = self.tick;
+        if let Some(entry) = self.entries.get_mut(key) {
+            self.order.remove(&entry.tick);
+            entry.tick = tick;
+            self.order.insert(tick, key.clone());
+        }
+    }
+
+    fn evict_lru(&mut self) -> bool {
+        match self.order.iter().next().map(|(_, k)| k.clone()) {
+            Some(key) => {
+                self.remove(&key);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// An in-memory least-recently-used [`ResultCache`] bounded by entry age,
+/// total size in bytes, and number of entries.
+///
+/// Cloning a `MemoryCache` yields a handle to the same underlying storage.
+#[derive(Clone)]
+pub struct MemoryCache {
+    state: Arc<Mutex<MemoryState>>,
+    ttl: Option<Duration>,
+    max_bytes: usize,
+    max_entries: usize,
+}
+
+impl Default for MemoryCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryCache {
+    /// Creates a cache with a 60 second TTL, a 64 MiB size bound and at most
+    /// 1024 entries.
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(MemoryState::default())),
+            ttl: Some(Duration::from_secs(60)),
+            max_bytes: 64 * 1024 * 1024,
+            max_entries: 1024,
+        }
+    }
+
+    /// Sets how long an entry remains valid after being stored.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Disables time-based expiry; entries are only removed by eviction.
+    pub fn without_ttl(mut self) -> Self {
+        self.ttl = None;
+        self
+    }
+
+    /// Sets the maximum total size of cached results. Results larger than this
+    /// bound are never cached.
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Sets the maximum number of cached results.
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Returns the number of entries currently held.
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Returns `true` if the cache holds no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the total size in bytes of the cached results.
+    pub fn size_bytes(&self) -> usize {
+        self.lock().bytes
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MemoryState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl ResultCache for MemoryCache {
+    fn get(&self, key: &CacheKey) -> Option<Vec<RecordBatch>> {
+        let mut state = self.lock();
+        let expired = match state.entries.get(key) {
+            Some(entry) => self.ttl.is_some_and(|ttl| entry.inserted.elapsed() > ttl),
+            None => return None,
+        };
+        if expired {
+            state.remove(key);
+            return None;
+        }
+        state.touch(key);
+        state.entries.get(key).map(|e| e.batches.clone())
+    }
+
+    fn put(&self, key: CacheKey, batches: &[RecordBatch]) {
+        let size = batches_size(batches);
+        if size > self.max_bytes || self.max_entries == 0 {
+            return;
+        }
+        let mut state = self.lock();
+        state.remove(&key);
+        while state.bytes + size > self.max_bytes || state.entries.len() >= self.max_entries {
+            if !state.evict_lru() {
+                break;
+            }
+        }
+        state.tick += 1;
+        let tick = state.tick;
+        state.order.insert(tick, key.clone());
+        state.bytes += size;
+        state.entries.insert(
+            key,
+            MemoryEntry {
+                batches: batches.to_vec(),
+                size,
+                inserted: Instant::now(),
+                tick,
+            },
+        );
+    }
+
+    fn invalidate(&self, key: &CacheKey) {
+        self.lock().remove(key);
+    }
+
+    fn clear(&self) {
+        *self.lock() = MemoryState::default();
+    }
+}
+
+/// A [`ResultCache`] that persists results as Arrow IPC files in a directory.
+///
+/// Entries expire based on the file modification time, and the directory is
+/// trimmed to `max_bytes` by removing the oldest files after each insert.
+/// Files are written to a temporary name and atomically renamed into place, so
+/// several processes may safely share one cache directory.
+#[derive(Clone, Debug)]
+pub struct DiskCache {
+    dir: PathBuf,
+    ttl: Option<Duration>,
+    max_bytes: u64,
+}
+
+const DISK_CACHE_EXTENSION: &str = "arrow";
+
+/// Numbers the temporary files written by this process.
+static TEMP_FILES: AtomicU64 = AtomicU64::new(0);
+
+impl DiskCache {
+    /// Creates a disk cache rooted at `dir`, creating the directory if needed.
+    ///
+    /// Defaults to a one hour TTL and a 1 GiB size bound.
+    pub fn new(dir: impl AsRef<Path>) -> std::io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            ttl: Some(Duration::from_secs(60 * 60)),
+            max_bytes: 1024 * 1024 * 1024,
+        })
+    }
+
+    /// Sets how long an entry remains valid after being written.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Disables time-based expiry; entries are only removed by size eviction.
+    pub fn without_ttl(mut self) -> Self {
+        self.ttl = None;
+        self
+    }
+
+    /// Sets the maximum total size of the cache directory.
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Returns the directory holding the cache files.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path_for(&self, key: &CacheKey) -> PathBuf {
+        let bytes = key.as_str().as_bytes();
+        let name = format!(
+            "{:016x}{:016x}.{}",
+            fnv1a(bytes, 0xcbf2_9ce4_8422_2325),
+            fnv1a(bytes, 0x8422_2325_cbf2_9ce4),
+            DISK_CACHE_EXTENSION
+        );
+        self.dir.join(name)
+    }
+
+    fn is_expired(&self, modified: SystemTime) -> bool {
+        match self.ttl {
+            Some(ttl) => modified.elapsed().map(|age| age > ttl).unwrap_or(false),
+            None => false,
+        }
+    }
+
+    fn read(&self, path: &Path) -> Option<Vec<RecordBatch>> {
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+        if self.is_expired(modified) {
+            let _ = fs::remove_file(path);
+            return None;
+        }
+        let reader = FileReader::try_new(BufReader::new(File::open(path).ok()?), None).ok()?;
+        reader.collect::<Result<Vec<_>, _>>().ok()
+    }
+
+    fn write(&self, path: &Path, batches: &[RecordBatch]) -> Result<(), crate::DremioClientError> {
+        // Unique per write, so that concurrent stores of the same key, from
+        // this process or another, never share a temporary file.
+        let tmp = path.with_extension(format!(
+            "tmp-{}-{}",
+            std::process::id(),
+            TEMP_FILES.fetch_add(1, Ordering::Relaxed)
+        ));
+        let schema = match batches.first() {
+            Some(batch) => batch.schema(),
+            None => Arc::new(Schema::empty()),
+        };
+        let result: Result<(), crate::DremioClientError> = (|| {
+            let file = OpenOptions::new().write(true).create_new(true).open(&tmp)?;
+            let mut writer = FileWriter::try_new(file, &schema)?;
+            for batch in batches {
+                writer.write(batch)?;
+            }
+            writer.finish()?;
+            fs::rename(&tmp, path)?;
+            Ok(())
+        })();
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+        result
+    }
+
+    fn evict(&self) {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return;
+        };
+        let mut files: Vec<(SystemTime, u64, PathBuf)> = entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().extension().and_then(|x| x.to_str()) == Some(DISK_CACHE_EXTENSION))
+            .filter_map(|e| {
+                let meta = e.metadata().ok()?;
+                Some((meta.modified().ok()?, meta.len(), e.path()))
+            })
+            .collect();
+        files.sort();
+        let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+        for (modified, len, path) in files {
+            if total <= self.max_bytes && !self.is_expired(modified) {
+                continue;
+            }
+            if fs::remove_file(&path).is_ok() {
+                total -= len;
+            }
+        }
+    }
+}
+
+/// 64-bit FNV-1a, used for stable cache file names across processes.
+pub(crate) fn fnv1a(bytes: &[u8], offset: u64) -> u64 {
+    bytes.iter().fold(offset, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+impl ResultCache for DiskCache {
+    fn get(&self, key: &CacheKey) -> Option<Vec<RecordBatch>> {
+        self.read(&self.path_for(key))
+    }
+
+    fn put(&self, key: CacheKey, batches: &[RecordBatch]) {
+        if self.write(&self.path_for(&key), batches).is_ok() {
+            self.evict();
+        }
+    }
+
+    fn invalidate(&self, key: &CacheKey) {
+        let _ = fs::remove_file(self.path_for(key));
+    }
+
+    fn clear(&self) {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return;
+        };
+        for entry in entries.filter_map(|e| e.ok()) {
+            if entry.path().extension().and_then(|x| x.to_str()) == Some(DISK_CACHE_EXTENSION) {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+    }
+}
+
+/// An in-memory cache of query schemas, consulted by
+/// [`Client::get_schema`](crate::Client::get_schema).
+///
+/// Tools that introspect many views spend most of their startup time asking
+/// Dremio for schemas that rarely change. Entries are keyed by the normalized
+/// SQL text, like [`ResultCache`] entries, and expire after a TTL so that
+/// schema changes are eventually picked up. When full, the oldest entry is
+/// evicted.
+///
+/// Cloning a `SchemaCache` yields a handle to the same underlying storage.
+#[derive(Clone, Debug)]
+pub struct SchemaCache {
+    entries: Arc<Mutex<HashMap<CacheKey, (SchemaRef, Instant)>>>,
+    ttl: Option<Duration>,
+    max_entries: usize,
+}
+
+impl Default for SchemaCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SchemaCache {
+    /// Creates a cache with a 5 minute TTL and at most 4096 entries.
+    pub fn new() -> Self {
+        Self {
+            entries: Arc::new(Mutex::new(HashMap::new())),
+            ttl: Some(Duration::from_secs(5 * 60)),
+            max_entries: 4096,
+        }
+    }
+
+    /// Sets how long an entry remains valid after being stored.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Disables time-based expiry; entries are only removed by eviction.
+    pub fn without_ttl(mut self) -> Self {
+        self.ttl = None;
+        self
+    }
+
+    /// Sets the maximum number of cached schemas.
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Returns the cached schema for `key`, or `None` on a miss or expired entry.
+    pub fn get(&self, key: &CacheKey) -> Option<SchemaRef> {
+        let mut entries = self.lock();
+        let (schema, inserted) = entries.get(key)?;
+        if self.ttl.is_some_and(|ttl| inserted.elapsed() > ttl) {
+            entries.remove(key);
+            return None;
+        }
+        Some(schema.clone())
+    }
+
+    /// Stores `schema` under `key`, evicting the oldest entry if the cache is full.
+    pub fn put(&self, key: CacheKey, schema: SchemaRef) {
+        if self.max_entries == 0 {
+            return;
+        }
+        let mut entries = self.lock();
+        entries.remove(&key);
+        if entries.len() >= self.max_entries {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, (_, inserted))| *inserted)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(key, (schema, Instant::now()));
+    }
+
+    /// Removes the entry for `key`, if present.
+    pub fn invalidate(&self, key: &CacheKey) {
+        self.lock().remove(key);
+    }
+
+    /// Removes all entries.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// Returns the number of entries currently held, including expired ones
+    /// not looked up since they expired.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns `true` if the cache holds no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<CacheKey, (SchemaRef, Instant)>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -0,0 +1,84 @@
+//! Cancelling queries on the server.
+//!
+//! Dropping a [`QueryStream`](crate::QueryStream) stops fetching its results,
+//! but the job may keep running on the coordinator. A [`QueryHandle`] asks
+//! Dremio to cancel it, with the Flight `CancelFlightInfo` action.
+
+use crate::DremioClientError;
+use arrow_flight::error::FlightError;
+use arrow_flight::sql::client::FlightSqlServiceClient;
+use arrow_flight::{
+    Action, CancelFlightInfoRequest, CancelFlightInfoResult, CancelStatus, FlightInfo,
+};
+use prost::Message;
+use tonic::transport::Channel;
+
+/// The type of the Flight action cancelling a query.
+pub(crate) const CANCEL_FLIGHT_INFO: &str = "CancelFlightInfo";
+
+/// A handle on a submitted query, to cancel it on the server.
+///
+/// Handles are obtained from [`QueryStream::handle`](crate::QueryStream::handle)
+/// and are cheap to clone, so one can be handed to another task while the
+/// stream is being consumed.
+///
+/// # Example
+///
+/// ```no_run
+/// use dremio_rs::Client;
+/// use std::time::Duration;
+///
+/// #[tokio::main]
+/// async fn main() {
+///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+///   let stream = client.execute_stream("SELECT * FROM lake.events").await.unwrap();
+///   let handle = stream.handle().unwrap();
+///   tokio::spawn(async move {
+///     tokio::time::sleep(Duration::from_secs(60)).await;
+///     handle.cancel().await.unwrap();
+///   });
+///   let batches = stream.collect_batches().await;
+/// }
+/// ```
+#[derive(Clone)]
+pub struct QueryHandle {
+    client: FlightSqlServiceClient<Channel>,
+    flight_info: FlightInfo,
+}
+
+impl QueryHandle {
+    pub(crate) fn new(client: FlightSqlServiceClient<Channel>, flight_info: FlightInfo) -> Self {
+        Self {
+            client,
+            flight_info,
+        }
+    }
+
+    /// Returns the `FlightInfo` Dremio returned for the query.
+    pub fn flight_info(&self) -> &FlightInfo {
+        &self.flight_info
+    }
+
+    /// Asks Dremio to cancel the query.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(CancelStatus)` as reported by Dremio: `Cancelled` or
+    ///   `Cancelling` if the query is being cancelled, `NotCancellable` if it
+    ///   has already completed.
+    /// - `Err(DremioClientError)` if the request failed.
+    pub async fn cancel(&self) -> Result<CancelStatus, DremioClientError> {
+        let request = CancelFlightInfoRequest::new(self.flight_info.clone());
+        let action = Action::new(CANCEL_FLIGHT_INFO, request.encode_to_vec());
+        let mut results = self.client.clone().do_action(action).await?;
+        let result = results
+            .message()
+            .await
+            .map_err(FlightError::from)?
+            .ok_or_else(|| FlightError::protocol("no response to CancelFlightInfo"))?;
+        let result = CancelFlightInfoResult::decode(result.body)
+            .map_err(|e| FlightError::DecodeError(e.to_string()))?;
+        Ok(result.status())
+    }
+}
@@ -0,0 +1,377 @@
+//! Data quality assertions evaluated while results are fetched.
+//!
+//! [`Checks`] declares expectations on the rows of a query, such as columns
+//! without nulls, unique keys, values within a range or a minimum number of
+//! rows. Attached with [`QueryOptions::with_checks`](crate::QueryOptions::with_checks)
+//! or [`QueryStream::with_checks`](crate::QueryStream::with_checks), they are
+//! evaluated on each batch as it arrives, so an extract validates itself
+//! without a second pass over the data.
+//!
+//! A required check fails the stream with [`DremioClientError::CheckFailed`]
+//! as soon as a batch violates it. A check added as a warning instead counts
+//! its violations and reports them once the stream is exhausted.
+
+use crate::DremioClientError;
+use arrow::array::{Array, AsArray, RecordBatch};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Float64Type};
+use arrow::error::ArrowError;
+use arrow::row::{OwnedRow, RowConverter, SortField};
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+
+/// An expectation on the rows of a query.
+///
+/// Columns are named as they appear after the transformations configured in
+/// [`QueryOptions`](crate::QueryOptions), such as column renaming.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Check {
+    /// The column contains no nulls.
+    NotNull(String),
+    /// No two rows have the same values in these columns. Nulls compare
+    /// equal to each other.
+    Unique(Vec<String>),
+    /// Non-null values of a numeric column lie within the bounds, inclusive.
+    Range {
+        /// The column checked.
+        column: String,
+        /// The smallest value allowed, if any.
+        min: Option<f64>,
+        /// The largest value allowed, if any.
+        max: Option<f64>,
+    },
+    /// The query returns at least this many rows. Only evaluated once all
+    /// rows have arrived.
+    MinRows(u64),
+}
+
+impl Check {
+    /// Expects `column` to contain no nulls.
+    pub fn not_null(column: impl Into<String>) -> Self {
+        Check::NotNull(column.into())
+    }
+
+    /// Expects the values of `columns` to be unique across rows.
+    pub fn unique<I, S>(columns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Check::Unique(columns.into_iter().map(Into::into).collect())
+    }
+
+    /// Expects the values of `column` to lie between `min` and `max`.
+    pub fn range(column: impl Into<String>, min: Option<f64>, max: Option<f64>) -> Self {
+        Check::Range {
+            column: column.into(),
+            min,
+            max,
+        }
+    }
+
+    /// Expects the query to return at least `rows` rows.
+    pub fn min_rows(rows: u64) -> Self {
+        Check::MinRows(rows)
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Check::NotNull(column) => write!(f, "{:?} is not null", column),
+            Check::Unique(columns) => write!(f, "{:?} is unique", columns),
+            Check::Range { column, min, max } => match (min, max) {
+                (Some(min), Some(max)) => write!(f, "{:?} is between {} and {}", column, min, max),
+                (Some(min), None) => write!(f, "{:?} is at least {}", column, min),
+                (None, Some(max)) => write!(f, "{:?} is at most {}", column, max),
+                (None, None) => write!(f, "{:?} is in range", column),
+            },
+            Check::MinRows(rows) => write!(f, "at least {} rows", rows),
+        }
+    }
+}
+
+/// What happens when a [`Check`] is violated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Severity {
+    /// Fail the query.
+    #[default]
+    Fail,
+    /// Report the violation and keep going.
+    Warn,
+}
+
+/// A violated [`Check`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Violation {
+    /// The check that was violated.
+    pub check: Check,
+    /// Whether the violation failed the query.
+    pub severity: Severity,
+    /// The number of offending rows, or for [`Check::MinRows`] the number of
+    /// rows returned.
+    pub rows: u64,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.check {
+            Check::MinRows(_) => write!(f, "expected {}, got {}", self.check, self.rows),
+            _ => write!(f, "expected {}, violated by {} rows", self.check, self.rows),
+        }
+    }
+}
+
+impl std::error::Error for Violation {}
+
+type WarningHook = Arc<dyn Fn(&Violation) + Send + Sync>;
+
+/// A set of [`Check`]s to evaluate on query results.
+///
+/// # Example
+///
+/// ```
+/// use arrow::array::{Int32Array, RecordBatch};
+/// use dremio_rs::checks::{Check, Checks};
+/// use dremio_rs::QueryStream;
+/// use std::sync::Arc;
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let ids = Arc::new(Int32Array::from(vec![1, 2, 2]));
+/// let batch = RecordBatch::try_from_iter([("id", ids as _)]).unwrap();
+/// let checks = Checks::new()
+///   .require(Check::not_null("id"))
+///   .warn(Check::unique(["id"]))
+///   .on_warning(|violation| eprintln!("warning: {}", violation));
+/// let batches = QueryStream::from_batches(vec![batch])
+///   .with_checks(checks)
+///   .collect_batches()
+///   .await
+///   .unwrap();
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct Checks {
+    checks: Vec<(Check, Severity)>,
+    on_warning: Option<WarningHook>,
+}
+
+impl fmt::Debug for Checks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Checks")
+            .field("checks", &self.checks)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Checks {
+    /// Creates an empty set of checks.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a check that fails the query when violated.
+    pub fn require(mut self, check: Check) -> Self {
+        self.checks.push((check, Severity::Fail));
+        self
+    }
+
+    /// Adds a check whose violations are only reported, once all rows have
+    /// arrived, to the hook set with [`on_warning`](Self::on_warning).
+    pub fn warn(mut self, check: Check) -> Self {
+        self.checks.push((check, Severity::Warn));
+        self
+    }
+
+    /// Sets the function called with each violated warning check. Without
+    /// one, warnings are dropped.
+    pub fn on_warning<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&Violation) + Send + Sync + 'static,
+    {
+        self.on_warning = Some(Arc::new(hook));
+        self
+    }
+
+    /// Returns the checks and their severity, in the order they were added.
+    pub fn checks(&self) -> &[(Check, Severity)] {
+        &self.checks
+    }
+
+    /// Returns whether there are no checks.
+    pub fn is_empty(&self) -> bool {
+        self.checks.is_empty()
+    }
+
+    /// Evaluates the checks on results that are already complete, such as
+    /// cached ones, exactly as [`QueryStream::with_checks`](crate::QueryStream::with_checks)
+    /// would while fetching them.
+    pub(crate) fn verify(&self, batches: &[RecordBatch]) -> Result<(), DremioClientError> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        let mut checker = Checker::new(self.clone());
+        for batch in batches {
+            checker.update(batch)?;
+        }
+        checker.finish()
+    }
+}
+
+/// Evaluates [`Checks`] incrementally over the batches of one query.
+pub(crate) struct Checker {
+    checks: Checks,
+    violations: Vec<u64>,
+    seen: Vec<Option<(RowConverter, HashSet<OwnedRow>)>>,
+    rows: u64,
+    failed: bool,
+}
+
+impl Checker {
+    pub(crate) fn new(checks: Checks) -> Self {
+        let count = checks.checks.len();
+        Self {
+            checks,
+            violations: vec![0; count],
+            seen: (0..count).map(|_| None).collect(),
+            rows: 0,
+            failed: false,
+        }
+    }
+
+    /// Records that the query failed, so [`finish`](Self::finish) reports
+    /// nothing more.
+    pub(crate) fn abandon(&mut self) {
+        self.failed = true;
+    }
+
+    /// Evaluates the checks on `batch`, failing on the first required check
+    /// it violates.
+    pub(crate) fn update(&mut self, batch: &RecordBatch) -> Result<(), DremioClientError> {
+        let result = self.evaluate(batch);
+        if result.is_err() {
+            self.failed = true;
+        }
+        result
+    }
+
+    fn evaluate(&mut self, batch: &RecordBatch) -> Result<(), DremioClientError> {
+        self.rows += batch.num_rows() as u64;
+        for (i, (check, severity)) in self.checks.checks.iter().enumerate() {
+            let rows = match check {
+                Check::NotNull(column) => column_of(batch, column)?.logical_null_count() as u64,
+                Check::Unique(columns) => duplicates(&mut self.seen[i], batch, columns)?,
+                Check::Range { column, min, max } => out_of_range(batch, column, *min, *max)?,
+                Check::MinRows(_) => 0,
+            };
+            if rows > 0 && *severity == Severity::Fail {
+                return Err(Violation {
+                    check: check.clone(),
+                    severity: *severity,
+                    rows,
+                }
+                .into());
+            }
+            self.violations[i] += rows;
+        }
+        Ok(())
+    }
+
+    /// Evaluates the checks that need all rows and reports the warnings.
+    pub(crate) fn finish(&mut self) -> Result<(), DremioClientError> {
+        if std::mem::replace(&mut self.failed, true) {
+            return Ok(());
+        }
+        let mut failure = None;
+        for (i, (check, severity)) in self.checks.checks.iter().enumerate() {
+            let violation = match check {
+                Check::MinRows(min) if self.rows < *min => Violation {
+                    check: check.clone(),
+                    severity: *severity,
+                    rows: self.rows,
+                },
+                _ if self.violations[i] > 0 => Violation {
+                    check: check.clone(),
+                    severity: *severity,
+                    rows: self.violations[i],
+                },
+                _ => continue,
+            };
+            match severity {
+                Severity::Fail => {
+                    failure.get_or_insert(violation);
+                }
+                Severity::Warn => {
+                    if let Some(hook) = &self.checks.on_warning {
+                        hook(&violation);
+                    }
+                }
+            }
+        }
+        match failure {
+            Some(violation) => Err(violation.into()),
+            None => Ok(()),
+        }
+    }
+}
+
+fn column_of<'a>(
+    batch: &'a RecordBatch,
+    column: &str,
+) -> Result<&'a arrow::array::ArrayRef, DremioClientError> {
+    batch.column_by_name(column).ok_or_else(|| {
+        ArrowError::SchemaError(format!("unknown column {:?} in check", column)).into()
+    })
+}
+
+/// Counts the rows of `batch` whose `columns` were already seen, remembering
+/// the new ones.
+fn duplicates(
+    seen: &mut Option<(RowConverter, HashSet<OwnedRow>)>,
+    batch: &RecordBatch,
+    columns: &[String],
+) -> Result<u64, DremioClientError> {
+    let arrays = columns
+        .iter()
+        .map(|column| column_of(batch, column).cloned())
+        .collect::<Result<Vec<_>, _>>()?;
+    let (converter, keys) = match seen {
+        Some(seen) => seen,
+        None => {
+            let fields = arrays
+                .iter()
+                .map(|array| SortField::new(array.data_type().clone()))
+                .collect();
+            seen.insert((RowConverter::new(fields)?, HashSet::new()))
+        }
+    };
+    let rows = converter.convert_columns(&arrays)?;
+    Ok(rows.iter().filter(|row| !keys.insert(row.owned())).count() as u64)
+}
+
+/// Counts the non-null values of `column` outside of `min` and `max`.
+fn out_of_range(
+    batch: &RecordBatch,
+    column: &str,
+    min: Option<f64>,
+    max: Option<f64>,
+) -> Result<u64, DremioClientError> {
+    let array = column_of(batch, column)?;
+    if !array.data_type().is_numeric() {
+        return Err(ArrowError::SchemaError(format!(
+            "cannot check the range of column {:?} of type {}",
+            column,
+            array.data_type()
+        ))
+        .into());
+    }
+    let values = cast(array, &DataType::Float64)?;
+    let values = values.as_primitive::<Float64Type>();
+    Ok(values
+        .iter()
+        .flatten()
+        .filter(|value| min.is_some_and(|min| *value < min) || max.is_some_and(|max| *value > max))
+        .count() as u64)
+}
@@ -0,0 +1,282 @@
+//! Named connection profiles stored in a configuration file.
+//!
+//! Profiles let applications and the `dremio` command-line client switch
+//! between clusters by name. They are kept in a TOML file, by default
+//! `~/.dremio/config.toml`, or the file named by `DREMIO_CONFIG`:
+//!
+//! ```toml
+//! default_profile = "prod"
+//!
+//! [profiles.prod]
+//! url = "https://dremio.example.com:32010"
+//! user = "alice"
+//! token = "..."
+//!
+//! [profiles.local]
+//! url = "http://localhost:32010"
+//! user = "dremio"
+//! password = "dremio123"
+//! ```
+//!
+//! This module is only available with the `config` feature.
+
+use crate::{Client, ClientBuilder, DremioClientError};
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Environment variable naming the configuration file.
+pub const CONFIG_ENV: &str = "DREMIO_CONFIG";
+
+/// Connection settings for one Dremio cluster.
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    /// Flight SQL endpoint of the coordinator.
+    pub url: String,
+    /// User name for authentication.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// Password for authentication.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// Personal access token, sent in place of the password when set, or as
+    /// a bearer token when no user is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// REST API endpoint; defaults to port 9047 on the Flight SQL host.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rest_url: Option<String>,
+}
+
+impl fmt::Debug for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Keep secrets out of logs.
+        f.debug_struct("Profile")
+            .field("url", &self.url)
+            .field("user", &self.user)
+            .field("password", &self.password.as_ref().map(|_| Redacted))
+            .field("token", &self.token.as_ref().map(|_| Redacted))
+            .field("rest_url", &self.rest_url)
+            .finish()
+    }
+}
+
+/// Stands in for a secret in `Debug` output.
+struct Redacted;
+
+impl fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("..")
+    }
+}
+
+/// The contents of a configuration file.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Config {
+    /// Profile used when none is named explicitly.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_profile: Option<String>,
+    /// Profiles by name.
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl Config {
+    /// Returns the path of the configuration file: `$DREMIO_CONFIG` if set,
+    /// otherwise `.dremio/config.toml` in the home directory.
+    pub fn default_path() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os(CONFIG_ENV) {
+            return Some(PathBuf::from(path));
+        }
+        std::env::var_os("HOME")
+            .or_else(|| std::env::var_os("USERPROFILE"))
+            .map(|home| PathBuf::from(home).join(".dremio").join("config.toml"))
+    }
+
+    /// Reads a configuration file. A missing file yields an empty configuration.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, DremioClientError> {
+        let path = path.as_ref();
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+        toml::from_str(&text)
+            .map_err(|e| DremioClientError::ConfigError(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Writes the configuration to `path`, creating parent directories.
+    ///
+    /// Profiles may contain secrets, so on Unix the file is only readable and
+    /// writable by its owner.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), DremioClientError> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let text = toml::to_string_pretty(self)
+            .map_err(|e| DremioClientError::ConfigError(e.to_string()))?;
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+            options.mode(0o600);
+            if path.exists() {
+                fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+            }
+        }
+        options.open(path)?.write_all(text.as_bytes())?;
+        Ok(())
+    }
+
+    /// Returns the profile called `name`, or the default profile when `name`
+    /// is `None`.
+    pub fn profile(&self, name: Option<&str>) -> Result<&Profile, DremioClientError> {
+        let name = name
+            .or(self.default_profile.as_deref())
+            .ok_or_else(|| DremioClientError::ConfigError("no profile selected".to_string()))?;
+        self.profiles
+            .get(name)
+            .ok_or_else(|| DremioClientError::ConfigError(format!("unknown profile {:?}", name)))
+    }
+}
+
+impl Profile {
+    /// Connects and authenticates to the cluster described by this profile.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::config::Config;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///    let config = Config::load(Config::default_path().unwrap()).unwrap();
+    ///    let mut client = config.profile(Some("prod")).unwrap().connect().await.unwrap();
+    /// }
+    /// ```
+    pub async fn connect(&self) -> Result<Client, DremioClientError> {
+        self.builder()?.connect().await
+    }
+
+    /// Returns a builder for a client of the cluster described by this
+    /// profile, for example to pass to
+    /// [`Client::reconfigure`](crate::Client::reconfigure).
+    pub fn builder(&self) -> Result<ClientBuilder, DremioClientError> {
+        let builder = Client::builder(&self.url);
+        if let (None, Some(token)) = (&self.user, &self.token) {
+            return Ok(builder.with_token(token));
+        }
+        let (user, secret) = self.credentials()?;
+        Ok(builder.with_credentials(user, secret))
+    }
+
+    /// Logs in to the REST API of the cluster described by this profile.
+    #[cfg(feature = "rest")]
+    pub async fn connect_rest(&self) -> Result<crate::rest::RestClient, DremioClientError> {
+        let url = self.rest_url()?;
+        match &self.token {
+            Some(token) => Ok(crate::rest::RestClient::with_token(&url, token)),
+            None => {
+                let (user, password) = self.credentials()?;
+                crate::rest::RestClient::login(&url, user, password).await
+            }
+        }
+    }
+
+    /// Returns the REST API endpoint, derived from the Flight SQL endpoint when
+    /// not set explicitly.
+    #[cfg(feature = "rest")]
+    pub fn rest_url(&self) -> Result<String, DremioClientError> {
+        match &self.rest_url {
+            Some(url) => Ok(url.clone()),
+            None => crate::builder::default_rest_url(&self.url),
+        }
+    }
+
+    /// Returns the user name and the secret to authenticate with: the token
+    /// when there is one, the password otherwise.
+    fn credentials(&self) -> Result<(&str, &str), DremioClientError> {
+        let user = self
+            .user
+            .as_deref()
+            .ok_or_else(|| DremioClientError::ConfigError("no user configured".to_string()))?;
+        let secret = self
+            .token
+            .as_deref()
+            .or(self.password.as_deref())
+            .ok_or_else(|| {
+                DremioClientError::ConfigError("no token or password configured".to_string())
+            })?;
+        Ok((user, secret))
+    }
+}
+
+/// Watches a profile in the configuration file at `path` for changes.
+///
+/// The file is read every `interval`, and the profile called `name`, or the
+/// default profile when `name` is `None`, is yielded whenever it differs from
+/// the one read before, starting with the one read when the watch is
+/// created. A file that cannot be read or parsed, for example while it is
+/// being written, yields an error and is read again at the next interval.
+/// Together with [`Client::reconfigure`], long-running services pick up
+/// rotated credentials without restarting.
+///
+/// # Example
+///
+/// ```no_run
+/// use dremio_rs::config::{self, Config};
+/// use futures::StreamExt;
+/// use std::time::Duration;
+///
+/// #[tokio::main]
+/// async fn main() {
+///    let path = Config::default_path().unwrap();
+///    let profile = Config::load(&path).unwrap().profile(Some("prod")).unwrap().clone();
+///    let mut client = profile.connect().await.unwrap();
+///    let mut changes = Box::pin(config::watch(&path, Some("prod"), Duration::from_secs(30)).unwrap());
+///    while let Some(change) = changes.next().await {
+///        match change.and_then(|profile| profile.builder()) {
+///            Ok(builder) => {
+///                if let Err(e) = client.reconfigure(builder).await {
+///                    eprintln!("keeping the old connection: {}", e);
+///                }
+///            }
+///            Err(e) => eprintln!("cannot read the profile: {}", e),
+///        }
+///    }
+/// }
+/// ```
+pub fn watch(
+    path: impl AsRef<Path>,
+    name: Option<&str>,
+    interval: Duration,
+) -> Result<impl Stream<Item = Result<Profile, DremioClientError>> + Send, DremioClientError> {
+    let path = path.as_ref().to_path_buf();
+    let name = name.map(str::to_string);
+    let current = Config::load(&path)?.profile(name.as_deref())?.clone();
+    Ok(futures::stream::unfold(
+        (path, name, current),
+        move |(path, name, mut current)| async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let profile =
+                    Config::load(&path).and_then(|config| config.profile(name.as_deref()).cloned());
+                match profile {
+                    Ok(profile) if profile == current => continue,
+                    Ok(profile) => {
+                        current = profile.clone();
+                        return Some((Ok(profile), (path, name, current)));
+                    }
+                    Err(e) => return Some((Err(e), (path, name, current))),
+                }
+            }
+        },
+    ))
+}
@@ -0,0 +1,423 @@
+//! Helpers for converting Arrow values returned by Dremio into Rust types.
+//!
+//! # Timestamps
+//!
+//! Dremio returns `TIMESTAMP` columns as Arrow timestamps without a timezone.
+//! The stored values are instants in UTC; the missing timezone only means that
+//! Dremio did not annotate them. [`with_timezone`] attaches a timezone to every
+//! timestamp column of a batch without changing the underlying instants, and
+//! the `timestamp_*` helpers read single values as seconds/nanoseconds since
+//! the Unix epoch or, with the `chrono` and `time` features, as
+//! `chrono::DateTime<Utc>` and `time::OffsetDateTime`.
+//!
+//! # Decimals
+//!
+//! `DECIMAL` columns arrive as `Decimal128` arrays holding an unscaled `i128`
+//! and a per-column scale. The `decimal_*` helpers read single values as an
+//! `(i128, scale)` pair, `f64`, a string, or, with the `rust_decimal` feature,
+//! a `rust_decimal::Decimal`. [`cast_decimals`] converts whole columns.
+//!
+//! # Intervals
+//!
+//! `INTERVAL` columns arrive as one of Arrow's three interval types.
+//! [`interval_value`] reads any of them as an [`IntervalValue`], which can be
+//! rendered as an ISO-8601 duration or, with the `chrono` feature, converted to
+//! a `chrono::Duration`. [`cast_intervals`] converts whole columns to ISO-8601
+//! strings, which is how intervals are best represented in text formats.
+
+use crate::DremioClientError;
+use arrow::array::{Array, ArrayRef, AsArray, RecordBatch, StringArray};
+use arrow::compute::cast;
+use arrow::datatypes::{
+    DataType, Decimal128Type, Field, FieldRef, IntervalDayTimeType, IntervalMonthDayNanoType,
+    IntervalUnit, IntervalYearMonthType, Schema, TimeUnit, TimestampMicrosecondType,
+    TimestampMillisecondType, TimestampNanosecondType, TimestampSecondType,
+};
+use arrow::error::ArrowError;
+use std::fmt;
+use std::sync::Arc;
+
+/// Returns a copy of `batch` in which every top-level timestamp column is
+/// annotated with `tz` (e.g. `"UTC"`, `"+02:00"` or `"Europe/Berlin"`).
+///
+/// The instants themselves are unchanged: naive timestamps are interpreted as
+/// UTC, and timestamps that already carry a timezone are re-annotated.
+pub fn with_timezone(batch: RecordBatch, tz: &str) -> Result<RecordBatch, DremioClientError> {
+    let tz: Arc<str> = Arc::from(tz);
+    map_columns(
+        batch,
+        |field| matches!(field.data_type(), DataType::Timestamp(_, _)),
+        |column| {
+            let column: ArrayRef = match column.data_type() {
+                DataType::Timestamp(TimeUnit::Second, _) => Arc::new(
+                    column
+                        .as_primitive::<TimestampSecondType>()
+                        .clone()
+                        .with_timezone(tz.clone()),
+                ),
+                DataType::Timestamp(TimeUnit::Millisecond, _) => Arc::new(
+                    column
+                        .as_primitive::<TimestampMillisecondType>()
+                        .clone()
+                        .with_timezone(tz.clone()),
+                ),
+                DataType::Timestamp(TimeUnit::Microsecond, _) => Arc::new(
+                    column
+                        .as_primitive::<TimestampMicrosecondType>()
+                        .clone()
+                        .with_timezone(tz.clone()),
+                ),
+                _ => Arc::new(
+                    column
+                        .as_primitive::<TimestampNanosecondType>()
+                        .clone()
+                        .with_timezone(tz.clone()),
+                ),
+            };
+            Ok(column)
+        },
+    )
+}
+
+/// Replaces every top-level column whose field matches `select` with the
+/// output of `convert`, adjusting the schema to the new data types.
+pub(crate) fn map_columns<P, F>(
+    batch: RecordBatch,
+    select: P,
+    mut convert: F,
+) -> Result<RecordBatch, DremioClientError>
+where
+    P: Fn(&Field) -> bool,
+    F: FnMut(&ArrayRef) -> Result<ArrayRef, DremioClientError>,
+{
+    let schema = batch.schema();
+    if !schema.fields().iter().any(|f| select(f)) {
+        return Ok(batch);
+    }
+    let mut fields: Vec<FieldRef> = Vec::with_capacity(schema.fields().len());
+    let mut columns = Vec::with_capacity(batch.num_columns());
+    for (field, column) in schema.fields().iter().zip(batch.columns()) {
+        if select(field) {
+            let column = convert(column)?;
+            fields.push(Arc::new(
+                Field::clone(field).with_data_type(column.data_type().clone()),
+            ));
+            columns.push(column);
+        } else {
+            fields.push(field.clone());
+            columns.push(column.clone());
+        }
+    }
+    let schema = Schema::new_with_metadata(fields, schema.metadata().clone());
+    Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+}
+
+/// Returns the timestamp at `row` as whole seconds and sub-second nanoseconds
+/// since the Unix epoch in UTC, or `None` if the value is null.
+pub fn timestamp_parts(
+    array: &dyn Array,
+    row: usize,
+) -> Result<Option<(i64, u32)>, DremioClientError> {
+    if array.is_null(row) {
+        return Ok(None);
+    }
+    let (value, per_second) = match array.data_type() {
+        DataType::Timestamp(TimeUnit::Second, _) => {
+            (array.as_primitive::<TimestampSecondType>().value(row), 1)
+        }
+        DataType::Timestamp(TimeUnit::Millisecond, _) => (
+            array.as_primitive::<TimestampMillisecondType>().value(row),
+            1_000,
+        ),
+        DataType::Timestamp(TimeUnit::Microsecond, _) => (
+            array.as_primitive::<TimestampMicrosecondType>().value(row),
+            1_000_000,
+        ),
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => (
+            array.as_primitive::<TimestampNanosecondType>().value(row),
+            1_000_000_000,
+        ),
+        other => return Err(type_mismatch("timestamp", other)),
+    };
+    let secs = value.div_euclid(per_second);
+    let nanos = value.rem_euclid(per_second) * (1_000_000_000 / per_second);
+    Ok(Some((secs, nanos as u32)))
+}
+
+/// Returns the timestamp at `row` as nanoseconds since the Unix epoch in UTC,
+/// or `None` if the value is null.
+pub fn timestamp_nanos(array: &dyn Array, row: usize) -> Result<Option<i128>, DremioClientError> {
+    Ok(timestamp_parts(array, row)?
+        .map(|(secs, nanos)| i128::from(secs) * 1_000_000_000 + i128::from(nanos)))
+}
+
+/// Returns the timestamp at `row` as a `chrono::DateTime<Utc>`, or `None` if
+/// the value is null.
+#[cfg(feature = "chrono")]
+pub fn timestamp_to_chrono(
+    array: &dyn Array,
+    row: usize,
+) -> Result<Option<chrono::DateTime<chrono::Utc>>, DremioClientError> {
+    timestamp_parts(array, row)?
+        .map(|(secs, nanos)| {
+            chrono::DateTime::from_timestamp(secs, nanos)
+                .ok_or_else(|| out_of_range("chrono::DateTime"))
+        })
+        .transpose()
+}
+
+/// Returns the timestamp at `row` as a `time::OffsetDateTime` in UTC, or
+/// `None` if the value is null.
+#[cfg(feature = "time")]
+pub fn timestamp_to_time(
+    array: &dyn Array,
+    row: usize,
+) -> Result<Option<time::OffsetDateTime>, DremioClientError> {
+    timestamp_nanos(array, row)?
+        .map(|nanos| {
+            time::OffsetDateTime::from_unix_timestamp_nanos(nanos)
+                .map_err(|_| out_of_range("time::OffsetDateTime"))
+        })
+        .transpose()
+}
+
+/// Returns a copy of `batch` in which every top-level `Decimal128` column is
+/// cast to `to_type`, typically `DataType::Float64` or `DataType::Utf8`.
+pub fn cast_decimals(
+    batch: RecordBatch,
+    to_type: &DataType,
+) -> Result<RecordBatch, DremioClientError> {
+    map_columns(
+        batch,
+        |field| matches!(field.data_type(), DataType::Decimal128(_, _)),
+        |column| Ok(cast(column, to_type)?),
+    )
+}
+
+/// Returns the decimal at `row` as its unscaled value and scale, or `None` if
+/// the value is null. The decimal equals `value * 10^-scale`.
+pub fn decimal_parts(
+    array: &dyn Array,
+    row: usize,
+) -> Result<Option<(i128, i8)>, DremioClientError> {
+    let DataType::Decimal128(_, scale) = array.data_type() else {
+        return Err(type_mismatch("decimal", array.data_type()));
+    };
+    if array.is_null(row) {
+        return Ok(None);
+    }
+    Ok(Some((
+        array.as_primitive::<Decimal128Type>().value(row),
+        *scale,
+    )))
+}
+
+/// Returns the decimal at `row` as an `f64`, or `None` if the value is null.
+/// Values with more than 15 significant digits lose precision.
+pub fn decimal_to_f64(array: &dyn Array, row: usize) -> Result<Option<f64>, DremioClientError> {
+    Ok(
+        decimal_parts(array, row)?
+            .map(|(value, scale)| value as f64 / 10f64.powi(i32::from(scale))),
+    )
+}
+
+/// Returns the decimal at `row` formatted with its full scale (e.g. `"12.50"`),
+/// or `None` if the value is null.
+pub fn decimal_to_string(
+    array: &dyn Array,
+    row: usize,
+) -> Result<Option<String>, DremioClientError> {
+    decimal_parts(array, row)?;
+    if array.is_null(row) {
+        return Ok(None);
+    }
+    Ok(Some(
+        array.as_primitive::<Decimal128Type>().value_as_string(row),
+    ))
+}
+
+/// Returns the decimal at `row` as a `rust_decimal::Decimal`, or `None` if the
+/// value is null. Fails if the value does not fit in 96 bits or its scale
+/// exceeds 28.
+#[cfg(feature = "rust_decimal")]
+pub fn decimal_to_rust_decimal(
+    array: &dyn Array,
+    row: usize,
+) -> Result<Option<rust_decimal::Decimal>, DremioClientError> {
+    decimal_parts(array, row)?
+        .map(|(value, scale)| {
+            let (value, scale) = if scale < 0 {
+                let factor = 10i128
+                    .checked_pow(u32::from(scale.unsigned_abs()))
+                    .ok_or_else(|| out_of_range("rust_decimal::Decimal"))?;
+                let value = value
+                    .checked_mul(factor)
+                    .ok_or_else(|| out_of_range("rust_decimal::Decimal"))?;
+                (value, 0)
+            } else {
+                (value, scale as u32)
+            };
+            rust_decimal::Decimal::try_from_i128_with_scale(value, scale)
+                .map_err(|_| out_of_range("rust_decimal::Decimal"))
+        })
+        .transpose()
+}
+
+/// An interval value normalized from any of Arrow's interval types.
+///
+/// Months and days are kept separate from the sub-day part because their
+/// length in seconds depends on the calendar date they are applied to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct IntervalValue {
+    /// Number of months.
+    pub months: i32,
+    /// Number of days.
+    pub days: i32,
+    /// Number of nanoseconds.
+    pub nanos: i64,
+}
+
+impl IntervalValue {
+    /// Formats the interval as an ISO-8601 duration such as `P1Y2M3DT4H5M6.5S`.
+    /// Negative components keep their sign, e.g. `P-3D`.
+    pub fn to_iso8601(&self) -> String {
+        self.to_string()
+    }
+
+    /// Converts the interval to a `chrono::Duration`.
+    ///
+    /// Returns `None` if the interval has a month component, since a month has
+    /// no fixed length. Days are counted as 24 hours.
+    #[cfg(feature = "chrono")]
+    pub fn to_chrono_duration(&self) -> Option<chrono::Duration> {
+        if self.months != 0 {
+            return None;
+        }
+        chrono::Duration::try_days(i64::from(self.days))?
+            .checked_add(&chrono::Duration::nanoseconds(self.nanos))
+    }
+}
+
+impl fmt::Display for IntervalValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.months == 0 && self.days == 0 && self.nanos == 0 {
+            return f.write_str("PT0S");
+        }
+        f.write_str("P")?;
+        let (years, months) = (self.months / 12, self.months % 12);
+        if years != 0 {
+            write!(f, "{}Y", years)?;
+        }
+        if months != 0 {
+            write!(f, "{}M", months)?;
+        }
+        if self.days != 0 {
+            write!(f, "{}D", self.days)?;
+        }
+        if self.nanos != 0 {
+            f.write_str("T")?;
+            let sign = if self.nanos < 0 { "-" } else { "" };
+            let total = self.nanos.unsigned_abs();
+            let hours = total / 3_600_000_000_000;
+            let minutes = total / 60_000_000_000 % 60;
+            let seconds = total / 1_000_000_000 % 60;
+            let fraction = total % 1_000_000_000;
+            if hours != 0 {
+                write!(f, "{}{}H", sign, hours)?;
+            }
+            if minutes != 0 {
+                write!(f, "{}{}M", sign, minutes)?;
+            }
+            if seconds != 0 || fraction != 0 {
+                write!(f, "{}{}", sign, seconds)?;
+                if fraction != 0 {
+                    let digits = format!("{:09}", fraction);
+                    write!(f, ".{}", digits.trim_end_matches('0'))?;
+                }
+                f.write_str("S")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the interval at `row` as an [`IntervalValue`], or `None` if the
+/// value is null.
+pub fn interval_value(
+    array: &dyn Array,
+    row: usize,
+) -> Result<Option<IntervalValue>, DremioClientError> {
+    let value = match array.data_type() {
+        DataType::Interval(_) if array.is_null(row) => return Ok(None),
+        DataType::Interval(IntervalUnit::YearMonth) => IntervalValue {
+            months: array.as_primitive::<IntervalYearMonthType>().value(row),
+            ..Default::default()
+        },
+        DataType::Interval(IntervalUnit::DayTime) => {
+            let value = array.as_primitive::<IntervalDayTimeType>().value(row);
+            IntervalValue {
+                months: 0,
+                days: value.days,
+                nanos: i64::from(value.milliseconds) * 1_000_000,
+            }
+        }
+        DataType::Interval(IntervalUnit::MonthDayNano) => {
+            let value = array.as_primitive::<IntervalMonthDayNanoType>().value(row);
+            IntervalValue {
+                months: value.months,
+                days: value.days,
+                nanos: value.nanoseconds,
+            }
+        }
+        other => return Err(type_mismatch("interval", other)),
+    };
+    Ok(Some(value))
+}
+
+/// Returns the interval at `row` as an ISO-8601 duration string, or `None` if
+/// the value is null.
+pub fn interval_to_iso8601(
+    array: &dyn Array,
+    row: usize,
+) -> Result<Option<String>, DremioClientError> {
+    Ok(interval_value(array, row)?.map(|v| v.to_iso8601()))
+}
+
+/// Returns the interval at `row` as a `chrono::Duration`, or `None` if the
+/// value is null. Fails for intervals with a month component.
+#[cfg(feature = "chrono")]
+pub fn interval_to_chrono(
+    array: &dyn Array,
+    row: usize,
+) -> Result<Option<chrono::Duration>, DremioClientError> {
+    interval_value(array, row)?
+        .map(|v| {
+            v.to_chrono_duration()
+                .ok_or_else(|| out_of_range("chrono::Duration"))
+        })
+        .transpose()
+}
+
+/// Returns a copy of `batch` in which every top-level interval column is
+/// replaced by its ISO-8601 string representation.
+pub fn cast_intervals(batch: RecordBatch) -> Result<RecordBatch, DremioClientError> {
+    map_columns(
+        batch,
+        |field| matches!(field.data_type(), DataType::Interval(_)),
+        |column| {
+            let strings = (0..column.len())
+                .map(|row| interval_to_iso8601(column.as_ref(), row))
+                .collect::<Result<StringArray, _>>()?;
+            Ok(Arc::new(strings) as ArrayRef)
+        },
+    )
+}
+
+pub(crate) fn type_mismatch(expected: &str, actual: &DataType) -> DremioClientError {
+    ArrowError::CastError(format!("expected a {} column, found {}", expected, actual)).into()
+}
+
+#[cfg(any(feature = "chrono", feature = "time", feature = "rust_decimal"))]
+fn out_of_range(target: &str) -> DremioClientError {
+    ArrowError::CastError(format!("value out of range for {}", target)).into()
+}
@@ -0,0 +1,256 @@
+//! Copying query results from one cluster into a table on another.
+//!
+//! [`copy`] streams the results of a query on a source [`Client`] into a
+//! table reached through a destination `Client`, using Flight SQL bulk
+//! ingestion (`DoPut` with `CommandStatementIngest`). Batches are never
+//! collected in memory, so tables of any size can be copied, for example to
+//! migrate datasets or to seed a disaster-recovery cluster.
+//!
+//! The copy is not atomic: if it fails part-way, the rows already ingested
+//! stay in the destination table.
+
+use crate::{Client, DremioClientError, QueryMetrics, QueryOptions, QueryPhase};
+use arrow::array::RecordBatch;
+use arrow::error::ArrowError;
+use arrow_flight::sql::client::FlightSqlServiceClient;
+use arrow_flight::sql::{
+    CommandStatementIngest, TableDefinitionOptions, TableExistsOption, TableNotExistOption,
+};
+use futures::stream::{self, Stream, StreamExt};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tonic::transport::Channel;
+
+/// What [`copy`] does when the destination table already exists.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IfExists {
+    /// Fail without writing anything.
+    #[default]
+    Fail,
+    /// Add the copied rows to the existing ones.
+    Append,
+    /// Drop the table and recreate it from the copied rows.
+    Replace,
+}
+
+impl IfExists {
+    fn option(self) -> TableExistsOption {
+        match self {
+            IfExists::Fail => TableExistsOption::Fail,
+            IfExists::Append => TableExistsOption::Append,
+            IfExists::Replace => TableExistsOption::Replace,
+        }
+    }
+}
+
+type ProgressCallback = Arc<dyn Fn(&QueryMetrics) + Send + Sync>;
+
+/// Settings for [`copy`].
+#[derive(Clone)]
+pub struct CopyOptions {
+    query_options: QueryOptions,
+    if_exists: IfExists,
+    parallelism: usize,
+    progress: Option<ProgressCallback>,
+}
+
+impl Default for CopyOptions {
+    fn default() -> Self {
+        Self {
+            query_options: QueryOptions::default(),
+            if_exists: IfExists::default(),
+            parallelism: 1,
+            progress: None,
+        }
+    }
+}
+
+impl std::fmt::Debug for CopyOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CopyOptions")
+            .field("query_options", &self.query_options)
+            .field("if_exists", &self.if_exists)
+            .field("parallelism", &self.parallelism)
+            .field("progress", &self.progress.is_some())
+            .finish()
+    }
+}
+
+impl CopyOptions {
+    /// Creates options that fail if the table exists and ingest over a single
+    /// stream.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the options for the query on the source cluster, such as its
+    /// timeout, endpoint concurrency or batch size.
+    pub fn with_query_options(mut self, options: QueryOptions) -> Self {
+        self.query_options = options;
+        self
+    }
+
+    /// Sets what happens when the destination table already exists.
+    pub fn with_if_exists(mut self, if_exists: IfExists) -> Self {
+        self.if_exists = if_exists;
+        self
+    }
+
+    /// Sets the number of ingestion streams opened to the destination at
+    /// once. Batches are spread over the streams in turn. Defaults to 1.
+    pub fn with_parallelism(mut self, streams: usize) -> Self {
+        self.parallelism = streams.max(1);
+        self
+    }
+
+    /// Calls `callback` with updated [`QueryMetrics`] after every batch read
+    /// from the source.
+    pub fn with_progress<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&QueryMetrics) + Send + Sync + 'static,
+    {
+        self.progress = Some(Arc::new(callback));
+        self
+    }
+}
+
+/// Rows and batches written by [`copy`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CopySummary {
+    /// Number of rows sent to the destination.
+    pub rows: u64,
+    /// Number of batches sent to the destination.
+    pub batches: u64,
+}
+
+/// Runs `query` on `source` and ingests its results into `table` on
+/// `destination`.
+///
+/// `table` is the path of the destination table, one element per component;
+/// the last one names the table and the others its schema. The first batch
+/// creates the table, or is handled according to
+/// [`CopyOptions::with_if_exists`] if it exists; the remaining batches are
+/// appended over up to [`CopyOptions::with_parallelism`] streams. A query
+/// without any result batches leaves the destination untouched.
+///
+/// The destination must support Flight SQL bulk ingestion.
+///
+/// # Example
+///
+/// ```no_run
+/// use dremio_rs::copy::{copy, CopyOptions, IfExists};
+/// use dremio_rs::Client;
+///
+/// #[tokio::main]
+/// async fn main() {
+///   let mut source = Client::new("http://primary:32010", "dremio", "dremio123").await.unwrap();
+///   let destination = Client::new("http://standby:32010", "dremio", "dremio123").await.unwrap();
+///   let options = CopyOptions::new()
+///     .with_if_exists(IfExists::Replace)
+///     .with_parallelism(4)
+///     .with_progress(|metrics| eprintln!("{} rows", metrics.rows));
+///   let summary = copy(&mut source, "SELECT * FROM lake.orders", &destination, ["lake", "orders"], &options)
+///     .await
+///     .unwrap();
+///   println!("copied {} rows", summary.rows);
+/// }
+/// ```
+pub async fn copy<I, S>(
+    source: &mut Client,
+    query: &str,
+    destination: &Client,
+    table: I,
+    options: &CopyOptions,
+) -> Result<CopySummary, DremioClientError>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let table: Vec<String> = table.into_iter().map(|s| s.as_ref().to_string()).collect();
+    let Some((name, schema)) = table.split_last() else {
+        return Err(ArrowError::InvalidArgumentError("empty table path".to_string()).into());
+    };
+    let command =
+        |if_not_exist: TableNotExistOption, if_exists: TableExistsOption| CommandStatementIngest {
+            table_definition_options: Some(TableDefinitionOptions {
+                if_not_exist: if_not_exist.into(),
+                if_exists: if_exists.into(),
+            }),
+            table: name.clone(),
+            schema: (!schema.is_empty()).then(|| schema.join(".")),
+            ..Default::default()
+        };
+
+    let mut stream = source
+        .execute_stream_with_options(query, &options.query_options)
+        .await?;
+    if let Some(progress) = options.progress.clone() {
+        stream = stream.with_progress(move |metrics| progress(metrics));
+    }
+    let Some(first) = stream.next().await.transpose()? else {
+        return Ok(CopySummary::default());
+    };
+    let mut summary = CopySummary {
+        rows: first.num_rows() as u64,
+        batches: 1,
+    };
+    let written = |e: DremioClientError| e.in_query(QueryPhase::Write, query);
+    ingest(
+        destination.request_client(None)?,
+        command(TableNotExistOption::Create, options.if_exists.option()),
+        stream::iter([first]),
+    )
+    .await
+    .map_err(written)?;
+
+    let mut senders: Vec<mpsc::Sender<RecordBatch>> = Vec::new();
+    let mut workers: Vec<JoinHandle<Result<i64, DremioClientError>>> = Vec::new();
+    let mut next = 0;
+    while let Some(batch) = stream.next().await {
+        let batch = match batch {
+            Ok(batch) => batch,
+            Err(e) => {
+                workers.iter().for_each(JoinHandle::abort);
+                return Err(e);
+            }
+        };
+        if next == senders.len() {
+            let (tx, mut rx) = mpsc::channel(1);
+            let batches = stream::poll_fn(move |cx| rx.poll_recv(cx));
+            workers.push(tokio::spawn(ingest(
+                destination.request_client(None)?,
+                command(TableNotExistOption::Fail, TableExistsOption::Append),
+                batches,
+            )));
+            senders.push(tx);
+        }
+        summary.rows += batch.num_rows() as u64;
+        summary.batches += 1;
+        if senders[next].send(batch).await.is_err() {
+            // The worker has failed; its error is reported below.
+            break;
+        }
+        next = (next + 1) % options.parallelism;
+    }
+    drop(senders);
+    for worker in workers {
+        worker
+            .await
+            .map_err(|e| DremioClientError::IoError(std::io::Error::other(e)))?
+            .map_err(written)?;
+    }
+    Ok(summary)
+}
+
+/// Ingests `batches` over one `DoPut` stream.
+async fn ingest<S>(
+    mut client: FlightSqlServiceClient<Channel>,
+    command: CommandStatementIngest,
+    batches: S,
+) -> Result<i64, DremioClientError>
+where
+    S: Stream<Item = RecordBatch> + Send + 'static,
+{
+    Ok(client.execute_ingest(command, batches.map(Ok)).await?)
+}
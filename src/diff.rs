@@ -0,0 +1,383 @@
+//! Comparing the results of two queries row by row.
+//!
+//! [`diff_queries`] runs two queries ordered by a set of key columns and
+//! merges their results as they stream in, reporting the rows only found in
+//! the second query (added), only found in the first one (removed), and
+//! found in both with different values (changed). Only the current batch of
+//! each query and a bounded number of example rows are kept in memory, so
+//! results of any size can be compared, for example to check that a
+//! rewritten view or a reflection returns the same rows as the original.
+//!
+//! Both queries must return the same columns; columns are matched by name and
+//! the second query's values are cast to the types of the first.
+
+use crate::sql::quote_ident;
+use crate::{Client, DremioClientError, QueryOptions, QueryStream};
+use arrow::array::{ArrayRef, RecordBatch, UInt32Array};
+use arrow::compute::{cast, take_record_batch, SortOptions};
+use arrow::datatypes::SchemaRef;
+use arrow::error::ArrowError;
+use arrow::row::{OwnedRow, RowConverter, Rows, SortField};
+use futures::StreamExt;
+use std::cmp::Ordering;
+
+/// A differing row, kept as an example in a [`ResultDiff`].
+///
+/// Each batch holds the single row concerned.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RowChange {
+    /// A row only returned by the second query.
+    Added(RecordBatch),
+    /// A row only returned by the first query.
+    Removed(RecordBatch),
+    /// A row returned by both queries with different values.
+    Changed {
+        /// The row returned by the first query.
+        before: RecordBatch,
+        /// The row returned by the second query.
+        after: RecordBatch,
+    },
+}
+
+/// Counts of differing rows between two query results, as returned by
+/// [`diff_queries`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ResultDiff {
+    /// Rows only returned by the second query.
+    pub added: u64,
+    /// Rows only returned by the first query.
+    pub removed: u64,
+    /// Rows returned by both queries with different values.
+    pub changed: u64,
+    /// Rows returned identically by both queries.
+    pub unchanged: u64,
+    /// The first differing rows, up to [`DiffOptions::with_examples`].
+    pub examples: Vec<RowChange>,
+}
+
+impl ResultDiff {
+    /// Returns whether both queries returned the same rows.
+    pub fn is_empty(&self) -> bool {
+        self.added == 0 && self.removed == 0 && self.changed == 0
+    }
+}
+
+/// Settings for [`diff_queries`].
+#[derive(Clone, Debug)]
+pub struct DiffOptions {
+    query_options: QueryOptions,
+    examples: usize,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            query_options: QueryOptions::default(),
+            examples: 10,
+        }
+    }
+}
+
+impl DiffOptions {
+    /// Creates options keeping 10 example rows.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the options for both queries.
+    pub fn with_query_options(mut self, options: QueryOptions) -> Self {
+        self.query_options = options;
+        self
+    }
+
+    /// Sets how many differing rows are kept in [`ResultDiff::examples`].
+    pub fn with_examples(mut self, rows: usize) -> Self {
+        self.examples = rows;
+        self
+    }
+}
+
+/// Compares the results of `first` and `second`, matching rows on the `keys`
+/// columns.
+///
+/// Both queries are wrapped to order their rows by the keys, so they must be
+/// `SELECT` (or `WITH`) statements. Rows with duplicate keys are matched in
+/// the order they arrive.
+///
+/// # Example
+///
+/// ```no_run
+/// use dremio_rs::diff::{diff_queries, DiffOptions};
+/// use dremio_rs::Client;
+///
+/// #[tokio::main]
+/// async fn main() {
+///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+///   let diff = diff_queries(
+///     &mut client,
+///     "SELECT * FROM lake.orders_v1",
+///     "SELECT * FROM lake.orders_v2",
+///     &["order_id"],
+///     &DiffOptions::new(),
+///   )
+///   .await
+///   .unwrap();
+///   println!("{} added, {} removed, {} changed", diff.added, diff.removed, diff.changed);
+/// }
+/// ```
+pub async fn diff_queries(
+    client: &mut Client,
+    first: &str,
+    second: &str,
+    keys: &[&str],
+    options: &DiffOptions,
+) -> Result<ResultDiff, DremioClientError> {
+    if keys.is_empty() {
+        return Err(
+            ArrowError::InvalidArgumentError("no key columns to diff on".to_string()).into(),
+        );
+    }
+    let order_by = keys
+        .iter()
+        .map(|key| format!("{} ASC NULLS LAST", quote_ident(key)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let ordered = |query: &str| {
+        let statement = query.trim().trim_end_matches(';').trim_end();
+        format!("SELECT * FROM (\n{}\n) ORDER BY {}", statement, order_by)
+    };
+    let left = client
+        .execute_stream_with_options(&ordered(first), &options.query_options)
+        .await?;
+    let right = client
+        .execute_stream_with_options(&ordered(second), &options.query_options)
+        .await?;
+    let mut left = Side::new(left);
+    let mut right = Side::new(right);
+
+    // Both sides are converted with the schema of the first non-empty batch.
+    let first_schema = left.peek_schema().await?;
+    let second_schema = right.peek_schema().await?;
+    let Some(schema) = first_schema.or(second_schema) else {
+        return Ok(ResultDiff::default());
+    };
+    let encoder = Encoder::new(schema, keys)?;
+
+    let mut diff = ResultDiff::default();
+    loop {
+        let l = left.current(&encoder).await?;
+        let r = right.current(&encoder).await?;
+        let ordering = match (l, r) {
+            (None, None) => break,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(l), Some(r)) => left.key(l).cmp(&right.key(r)),
+        };
+        match ordering {
+            Ordering::Less => {
+                diff.removed += 1;
+                if diff.examples.len() < options.examples {
+                    diff.examples.extend(left.row()?.map(RowChange::Removed));
+                }
+                left.advance();
+            }
+            Ordering::Greater => {
+                diff.added += 1;
+                if diff.examples.len() < options.examples {
+                    diff.examples.extend(right.row()?.map(RowChange::Added));
+                }
+                right.advance();
+            }
+            Ordering::Equal => {
+                let (l, r) = (left.position, right.position);
+                if left.values(l) == right.values(r) {
+                    diff.unchanged += 1;
+                } else {
+                    diff.changed += 1;
+                    if diff.examples.len() < options.examples {
+                        if let (Some(before), Some(after)) = (left.row()?, right.row()?) {
+                            diff.examples.push(RowChange::Changed { before, after });
+                        }
+                    }
+                }
+                left.advance();
+                right.advance();
+            }
+        }
+    }
+    Ok(diff)
+}
+
+/// Converts batches to the row format, so keys and values can be compared
+/// with the same ordering on both sides.
+struct Encoder {
+    schema: SchemaRef,
+    key_indices: Vec<usize>,
+    value_indices: Vec<usize>,
+    keys: RowConverter,
+    values: RowConverter,
+}
+
+impl Encoder {
+    fn new(schema: SchemaRef, keys: &[&str]) -> Result<Self, DremioClientError> {
+        let key_indices = keys
+            .iter()
+            .map(|key| schema.index_of(key))
+            .collect::<Result<Vec<_>, _>>()?;
+        let value_indices: Vec<usize> = (0..schema.fields().len())
+            .filter(|i| !key_indices.contains(i))
+            .collect();
+        // Nulls last, matching the ORDER BY of the queries.
+        let options = SortOptions {
+            descending: false,
+            nulls_first: false,
+        };
+        let field =
+            |i: &usize| SortField::new_with_options(schema.field(*i).data_type().clone(), options);
+        Ok(Self {
+            keys: RowConverter::new(key_indices.iter().map(field).collect())?,
+            values: RowConverter::new(value_indices.iter().map(field).collect())?,
+            schema,
+            key_indices,
+            value_indices,
+        })
+    }
+
+    /// Reorders and casts the columns of `batch` to the reference schema.
+    fn align(&self, batch: &RecordBatch) -> Result<RecordBatch, DremioClientError> {
+        let batch_schema = batch.schema();
+        if batch_schema.fields().len() != self.schema.fields().len() {
+            return Err(mismatch(&self.schema, &batch_schema));
+        }
+        let columns = self
+            .schema
+            .fields()
+            .iter()
+            .map(|field| {
+                let index = batch_schema
+                    .index_of(field.name())
+                    .map_err(|_| mismatch(&self.schema, &batch_schema))?;
+                Ok(cast(batch.column(index), field.data_type())?)
+            })
+            .collect::<Result<Vec<ArrayRef>, DremioClientError>>()?;
+        Ok(RecordBatch::try_new(self.schema.clone(), columns)?)
+    }
+
+    fn encode(&self, batch: &RecordBatch) -> Result<(Rows, Rows), DremioClientError> {
+        let project = |indices: &[usize]| -> Vec<ArrayRef> {
+            indices.iter().map(|i| batch.column(*i).clone()).collect()
+        };
+        Ok((
+            self.keys.convert_columns(&project(&self.key_indices))?,
+            self.values.convert_columns(&project(&self.value_indices))?,
+        ))
+    }
+}
+
+fn mismatch(expected: &SchemaRef, found: &SchemaRef) -> DremioClientError {
+    let names = |schema: &SchemaRef| {
+        schema
+            .fields()
+            .iter()
+            .map(|f| f.name().as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    ArrowError::SchemaError(format!(
+        "queries return different columns: [{}] and [{}]",
+        names(expected),
+        names(found)
+    ))
+    .into()
+}
+
+/// One query being merged, positioned on its current row.
+struct Side {
+    stream: QueryStream,
+    pending: Option<RecordBatch>,
+    batch: Option<(RecordBatch, Rows, Rows)>,
+    position: usize,
+    previous: Option<OwnedRow>,
+}
+
+impl Side {
+    fn new(stream: QueryStream) -> Self {
+        Self {
+            stream,
+            pending: None,
+            batch: None,
+            position: 0,
+            previous: None,
+        }
+    }
+
+    /// Returns the schema of the first non-empty batch, holding it back for
+    /// [`current`](Self::current).
+    async fn peek_schema(&mut self) -> Result<Option<SchemaRef>, DremioClientError> {
+        while let Some(batch) = self.stream.next().await {
+            let batch = batch?;
+            if batch.num_rows() > 0 {
+                let schema = batch.schema();
+                self.pending = Some(batch);
+                return Ok(Some(schema));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Loads batches until a row is available, returning its position, or
+    /// `None` once the query is exhausted.
+    async fn current(&mut self, encoder: &Encoder) -> Result<Option<usize>, DremioClientError> {
+        loop {
+            if let Some((batch, keys, _)) = &self.batch {
+                if self.position < batch.num_rows() {
+                    if let Some(previous) = &self.previous {
+                        if keys.row(self.position) < previous.row() {
+                            return Err(ArrowError::InvalidArgumentError(
+                                "query results are not ordered by the key columns".to_string(),
+                            )
+                            .into());
+                        }
+                    }
+                    return Ok(Some(self.position));
+                }
+            }
+            let batch = match self.pending.take() {
+                Some(batch) => batch,
+                None => match self.stream.next().await {
+                    Some(batch) => batch?,
+                    None => return Ok(None),
+                },
+            };
+            let batch = encoder.align(&batch)?;
+            let (keys, values) = encoder.encode(&batch)?;
+            self.batch = Some((batch, keys, values));
+            self.position = 0;
+        }
+    }
+
+    fn key(&self, position: usize) -> Option<arrow::row::Row<'_>> {
+        let (_, keys, _) = self.batch.as_ref()?;
+        Some(keys.row(position))
+    }
+
+    fn values(&self, position: usize) -> Option<arrow::row::Row<'_>> {
+        let (_, _, values) = self.batch.as_ref()?;
+        Some(values.row(position))
+    }
+
+    /// Returns a copy of the current row that does not keep its batch alive,
+    /// or `None` before the first batch has been loaded.
+    fn row(&self) -> Result<Option<RecordBatch>, DremioClientError> {
+        let Some((batch, _, _)) = &self.batch else {
+            return Ok(None);
+        };
+        let index = UInt32Array::from(vec![self.position as u32]);
+        Ok(Some(take_record_batch(batch, &index)?))
+    }
+
+    fn advance(&mut self) {
+        self.previous = self.key(self.position).map(|key| key.owned());
+        self.position += 1;
+    }
+}
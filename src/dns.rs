@@ -0,0 +1,148 @@
+//! Resolving the coordinator's host name.
+//!
+//! By default the host in the URL passed to [`Client::new`](crate::Client::new)
+//! is resolved with the system resolver. Deployments that discover
+//! coordinators through a service registry instead can give a
+//! [`ClientBuilder`](crate::ClientBuilder) their own [`Resolve`]
+//! implementation, or a [`StaticHosts`] mapping. The resolver is called
+//! whenever the channel (re)connects, so a coordinator that moves is found
+//! again after a reconnect.
+//!
+//! The URL keeps its host name, which is still sent as the HTTP/2 authority
+//! and used to verify TLS certificates. A port in the URL takes precedence
+//! over the ports of the resolved addresses.
+
+use futures::future::BoxFuture;
+use hyper_util::client::legacy::connect::dns::{GaiResolver, Name};
+use hyper_util::client::legacy::connect::HttpConnector;
+use std::collections::HashMap;
+use std::future::Future;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tonic::codegen::Service;
+
+/// Resolves host names to socket addresses.
+///
+/// Implemented for closures taking the host name and returning a future, so
+/// a lookup in a service registry can be plugged in directly.
+///
+/// # Example
+///
+/// ```
+/// use dremio_rs::ClientBuilder;
+/// use std::net::SocketAddr;
+///
+/// let builder = ClientBuilder::new("http://dremio.service:32010").with_resolver(|host: &str| {
+///     let host = host.to_string();
+///     async move {
+///         // Look the host up in the service registry.
+///         assert_eq!(host, "dremio.service");
+///         Ok(vec![SocketAddr::from(([10, 0, 0, 7], 32010))])
+///     }
+/// });
+/// ```
+pub trait Resolve: Send + Sync + 'static {
+    /// Returns the addresses of `host`, to be tried in order.
+    fn resolve(&self, host: &str) -> BoxFuture<'static, io::Result<Vec<SocketAddr>>>;
+}
+
+impl<F, Fut> Resolve for F
+where
+    F: Fn(&str) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = io::Result<Vec<SocketAddr>>> + Send + 'static,
+{
+    fn resolve(&self, host: &str) -> BoxFuture<'static, io::Result<Vec<SocketAddr>>> {
+        Box::pin(self(host))
+    }
+}
+
+/// A fixed mapping of host names to addresses, like an `/etc/hosts` file
+/// private to the client.
+///
+/// Host names not in the mapping are resolved with the system resolver.
+///
+/// # Example
+///
+/// ```
+/// use dremio_rs::dns::StaticHosts;
+/// use dremio_rs::ClientBuilder;
+///
+/// let hosts = StaticHosts::new().with_host("dremio.internal", "10.0.0.7".parse().unwrap());
+/// let builder = ClientBuilder::new("http://dremio.internal:32010").with_resolver(hosts);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct StaticHosts {
+    hosts: HashMap<String, Vec<IpAddr>>,
+}
+
+impl StaticHosts {
+    /// Creates an empty mapping.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maps `host` to `addr`, in addition to the addresses it is already
+    /// mapped to. Host names are matched case-insensitively.
+    pub fn with_host(mut self, host: &str, addr: IpAddr) -> Self {
+        self.hosts
+            .entry(host.to_ascii_lowercase())
+            .or_default()
+            .push(addr);
+        self
+    }
+}
+
+impl Resolve for StaticHosts {
+    fn resolve(&self, host: &str) -> BoxFuture<'static, io::Result<Vec<SocketAddr>>> {
+        match self.hosts.get(&host.to_ascii_lowercase()) {
+            // Port 0 is replaced with the port of the URL.
+            Some(addrs) => {
+                let addrs = addrs.iter().map(|ip| SocketAddr::new(*ip, 0)).collect();
+                Box::pin(futures::future::ready(Ok(addrs)))
+            }
+            None => system_resolve(host),
+        }
+    }
+}
+
+/// Resolves `host` with the system resolver.
+fn system_resolve(host: &str) -> BoxFuture<'static, io::Result<Vec<SocketAddr>>> {
+    let name = Name::from_str(host).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e));
+    Box::pin(async move { Ok(GaiResolver::new().call(name?).await?.collect()) })
+}
+
+/// Adapts a [`Resolve`] implementation to the connector of the channel.
+#[derive(Clone)]
+pub(crate) struct Resolver(pub(crate) Arc<dyn Resolve>);
+
+impl Service<Name> for Resolver {
+    type Response = std::vec::IntoIter<SocketAddr>;
+    type Error = io::Error;
+    type Future = BoxFuture<'static, io::Result<Self::Response>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, name: Name) -> Self::Future {
+        let addrs = self.0.resolve(name.as_str());
+        Box::pin(async move { Ok(addrs.await?.into_iter()) })
+    }
+}
+
+/// Returns a TCP connector resolving host names with `resolver`, configured
+/// like the one tonic uses by default.
+pub(crate) fn connector(
+    resolver: Arc<dyn Resolve>,
+    tcp_keepalive: Option<Duration>,
+) -> HttpConnector<Resolver> {
+    let mut connector = HttpConnector::new_with_resolver(Resolver(resolver));
+    connector.enforce_http(false);
+    connector.set_nodelay(true);
+    connector.set_keepalive(tcp_keepalive);
+    connector
+}
@@ -0,0 +1,137 @@
+//! An abstraction over query execution.
+//!
+//! Application code that depends on [`DremioExecutor`] rather than on
+//! [`Client`] can be unit tested with a fake implementation instead of a live
+//! cluster.
+
+use crate::{Client, DremioClientError, QueryOptions, QueryStream};
+use arrow::array::RecordBatch;
+use std::future::Future;
+
+/// Executes SQL queries and exports their results.
+///
+/// Implemented by [`Client`]. Only the methods taking [`QueryOptions`] are
+/// required; the others have default implementations built on them.
+///
+/// # Example
+///
+/// ```no_run
+/// use dremio_rs::{Client, DremioClientError, DremioExecutor};
+///
+/// async fn count_options<E: DremioExecutor>(executor: &mut E) -> Result<usize, DremioClientError> {
+///     let batches = executor.get_record_batches("SELECT * FROM sys.options").await?;
+///     Ok(batches.iter().map(|b| b.num_rows()).sum())
+/// }
+///
+/// #[tokio::main]
+/// async fn main() {
+///    let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+///    println!("{}", count_options(&mut client).await.unwrap());
+/// }
+/// ```
+pub trait DremioExecutor: Send {
+    /// Executes a SQL query and returns its results as a stream of batches.
+    fn execute_stream_with_options(
+        &mut self,
+        query: &str,
+        options: &QueryOptions,
+    ) -> impl Future<Output = Result<QueryStream, DremioClientError>> + Send;
+
+    /// Executes a SQL query with default options and returns its results as
+    /// a stream of batches.
+    fn execute_stream(
+        &mut self,
+        query: &str,
+    ) -> impl Future<Output = Result<QueryStream, DremioClientError>> + Send {
+        async move {
+            self.execute_stream_with_options(query, &QueryOptions::default())
+                .await
+        }
+    }
+
+    /// Executes a SQL query and collects its results.
+    fn get_record_batches_with_options(
+        &mut self,
+        query: &str,
+        options: &QueryOptions,
+    ) -> impl Future<Output = Result<Vec<RecordBatch>, DremioClientError>> + Send;
+
+    /// Executes a SQL query with default options and collects its results.
+    fn get_record_batches(
+        &mut self,
+        query: &str,
+    ) -> impl Future<Output = Result<Vec<RecordBatch>, DremioClientError>> + Send {
+        async move {
+            self.get_record_batches_with_options(query, &QueryOptions::default())
+                .await
+        }
+    }
+
+    /// Executes a SQL query and returns at most `rows` of its rows in a single
+    /// batch, stopping the transfer and cancelling the query once enough rows
+    /// have arrived.
+    fn peek(
+        &mut self,
+        query: &str,
+        rows: usize,
+    ) -> impl Future<Output = Result<RecordBatch, DremioClientError>> + Send {
+        async move {
+            self.execute_stream_with_options(query, &QueryOptions::default())
+                .await?
+                .head(rows, true)
+                .await
+        }
+    }
+
+    /// Executes a SQL query and writes its results to a Parquet file.
+    #[cfg(feature = "parquet")]
+    fn write_parquet(
+        &mut self,
+        query: &str,
+        path: &str,
+    ) -> impl Future<Output = Result<(), DremioClientError>> + Send {
+        async move {
+            let stream = self.execute_stream(query).await?;
+            crate::export::write_parquet(stream, path.as_ref(), None, None).await?;
+            Ok(())
+        }
+    }
+}
+
+impl DremioExecutor for Client {
+    async fn execute_stream_with_options(
+        &mut self,
+        query: &str,
+        options: &QueryOptions,
+    ) -> Result<QueryStream, DremioClientError> {
+        Client::execute_stream_with_options(self, query, options).await
+    }
+
+    async fn execute_stream(&mut self, query: &str) -> Result<QueryStream, DremioClientError> {
+        Client::execute_stream(self, query).await
+    }
+
+    async fn get_record_batches_with_options(
+        &mut self,
+        query: &str,
+        options: &QueryOptions,
+    ) -> Result<Vec<RecordBatch>, DremioClientError> {
+        Client::get_record_batches_with_options(self, query, options).await
+    }
+
+    async fn get_record_batches(
+        &mut self,
+        query: &str,
+    ) -> Result<Vec<RecordBatch>, DremioClientError> {
+        Client::get_record_batches(self, query).await
+    }
+
+    async fn peek(&mut self, query: &str, rows: usize) -> Result<RecordBatch, DremioClientError> {
+        Client::peek(self, query, rows).await
+    }
+
+    #[cfg(feature = "parquet")]
+    async fn write_parquet(&mut self, query: &str, path: &str) -> Result<(), DremioClientError> {
+        Client::write_parquet(self, query, path).await
+    }
+}
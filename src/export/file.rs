@@ -0,0 +1,87 @@
+use crate::{DremioClientError, QueryStream};
+use arrow::array::{RecordBatch, RecordBatchWriter};
+use arrow::csv::WriterBuilder;
+use arrow::datatypes::SchemaRef;
+use arrow::ipc::writer::{FileWriter, IpcWriteOptions};
+use arrow::json::LineDelimitedWriter;
+use futures::StreamExt;
+use std::fs::File;
+use std::path::Path;
+
+/// Writes all batches of `stream` to a new file at `path`, with the writer
+/// `create` returns for the file and the schema of the results, and returns
+/// the number of rows written.
+///
+/// Batches are written as they arrive. The schema is the one Dremio announced
+/// for the results, or else that of the first batch; the file is only left
+/// unwritten when neither is known.
+pub(crate) async fn write_file<W, F>(
+    mut stream: QueryStream,
+    path: &Path,
+    create: F,
+) -> Result<u64, DremioClientError>
+where
+    W: RecordBatchWriter,
+    F: FnOnce(File, SchemaRef) -> Result<W, DremioClientError>,
+{
+    let (schema, first) = first_schema(&mut stream).await?;
+    let mut writer = create(File::create(path)?, schema)?;
+    let mut batches = futures::stream::iter(first.map(Ok)).chain(stream);
+    let mut rows = 0;
+    while let Some(batch) = batches.next().await {
+        let batch = batch?;
+        writer.write(&batch)?;
+        rows += batch.num_rows() as u64;
+    }
+    writer.close()?;
+    Ok(rows)
+}
+
+/// Returns the schema Dremio announced for `stream`, or else that of its
+/// first batch, which is taken from the stream and returned along with it.
+pub(crate) async fn first_schema(
+    stream: &mut QueryStream,
+) -> Result<(SchemaRef, Option<RecordBatch>), DremioClientError> {
+    if let Some(schema) = stream.schema() {
+        return Ok((schema, None));
+    }
+    let batch = stream
+        .next()
+        .await
+        .ok_or(DremioClientError::EmptyResult)??;
+    Ok((batch.schema(), Some(batch)))
+}
+
+/// Writes all batches of `stream` to a CSV file at `path` with the settings
+/// of `builder`.
+pub(crate) async fn write_csv(
+    stream: QueryStream,
+    path: &Path,
+    builder: WriterBuilder,
+) -> Result<u64, DremioClientError> {
+    write_file(stream, path, |file, schema| {
+        let mut writer = builder.build(file);
+        // Writes the header row, even if no rows follow.
+        writer.write(&RecordBatch::new_empty(schema))?;
+        Ok(writer)
+    })
+    .await
+}
+
+/// Writes all batches of `stream` to a newline-delimited JSON file at `path`.
+pub(crate) async fn write_json(stream: QueryStream, path: &Path) -> Result<u64, DremioClientError> {
+    write_file(stream, path, |file, _| Ok(LineDelimitedWriter::new(file))).await
+}
+
+/// Writes all batches of `stream` to an Arrow IPC file at `path` with
+/// `options`.
+pub(crate) async fn write_ipc(
+    stream: QueryStream,
+    path: &Path,
+    options: IpcWriteOptions,
+) -> Result<u64, DremioClientError> {
+    write_file(stream, path, |file, schema| {
+        Ok(FileWriter::try_new_with_options(file, &schema, options)?)
+    })
+    .await
+}
@@ -0,0 +1,263 @@
+use super::{Verification, VerificationReport, Verifier};
+use crate::sql::{filter_query, max_value, quote_ident, value_literal};
+use crate::{Client, DremioClientError, QueryOptions, QueryPhase};
+use arrow::array::ArrayRef;
+use arrow::error::ArrowError;
+use futures::StreamExt;
+use parquet::arrow::ArrowWriter;
+use parquet::file::properties::WriterProperties;
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Numbers the files written by this process.
+static PARTS: AtomicU64 = AtomicU64::new(0);
+
+/// Exports the rows of a query added since the previous export.
+///
+/// Each [`run`](Self::run) only fetches the rows whose watermark column, such
+/// as an ingestion timestamp or an increasing key, is greater than the
+/// largest value exported so far, and writes them to a new Parquet file. The
+/// largest value is kept as a SQL literal in a local state file, which is
+/// only replaced once the new file has been written in full, so a failed run
+/// can simply be retried.
+///
+/// A run that is interrupted after writing its file but before saving the
+/// new watermark exports the same rows again on the next run.
+///
+/// Files are named after the time of the run, the process and a counter, so
+/// that runs sharing a directory never write to the same file; a run fails
+/// rather than replace an existing file.
+///
+/// With [`with_verification`](Self::with_verification), each new file is
+/// checked before it is moved into place; a file that fails the check is
+/// discarded and the watermark is left unchanged.
+///
+/// # Example
+///
+/// ```no_run
+/// use dremio_rs::export::IncrementalSync;
+/// use dremio_rs::Client;
+///
+/// #[tokio::main]
+/// async fn main() {
+///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+///   let sync = IncrementalSync::new("SELECT * FROM lake.events", "ingested_at", "events.watermark");
+///   let summary = sync.run(&mut client, "events").await.unwrap();
+///   println!("exported {} new rows", summary.rows);
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct IncrementalSync {
+    query: String,
+    column: String,
+    state: PathBuf,
+    query_options: QueryOptions,
+    properties: Option<WriterProperties>,
+    verification: Option<Verification>,
+}
+
+/// What a single [`IncrementalSync::run`] exported.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SyncSummary {
+    /// The Parquet file written, or `None` if there were no new rows.
+    pub file: Option<PathBuf>,
+    /// Number of rows exported.
+    pub rows: u64,
+    /// The watermark after the run, as a SQL literal.
+    pub watermark: Option<String>,
+    /// The result of checking the new file, if verification was enabled
+    /// with [`IncrementalSync::with_verification`] and a file was written.
+    /// If the check failed, [`file`](Self::file) is `None`.
+    pub verification: Option<VerificationReport>,
+}
+
+impl IncrementalSync {
+    /// Creates a sync of the results of `query`, tracking the largest value
+    /// of `column` in the file `state`.
+    pub fn new(
+        query: impl Into<String>,
+        column: impl Into<String>,
+        state: impl Into<PathBuf>,
+    ) -> Self {
+        Self {
+            query: query.into(),
+            column: column.into(),
+            state: state.into(),
+            query_options: QueryOptions::default(),
+            properties: None,
+            verification: None,
+        }
+    }
+
+    /// Sets the options for the query.
+    pub fn with_query_options(mut self, options: QueryOptions) -> Self {
+        self.query_options = options;
+        self
+    }
+
+    /// Sets the Parquet writer properties used for every file.
+    pub fn with_properties(mut self, properties: WriterProperties) -> Self {
+        self.properties = Some(properties);
+        self
+    }
+
+    /// Checks each new file against the rows fetched, as thoroughly as
+    /// `verification` says, before it is moved into place and the watermark
+    /// is advanced. The report is returned in [`SyncSummary::verification`].
+    pub fn with_verification(mut self, verification: Verification) -> Self {
+        self.verification = Some(verification);
+        self
+    }
+
+    /// Returns the current watermark as a SQL literal, or `None` before the
+    /// first export of any rows.
+    pub fn watermark(&self) -> Result<Option<String>, DremioClientError> {
+        match fs::read_to_string(&self.state) {
+            Ok(text) if text.trim().is_empty() => Ok(None),
+            Ok(text) => Ok(Some(text.trim().to_string())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Returns the query fetching the rows beyond the current watermark.
+    pub fn next_query(&self) -> Result<String, DremioClientError> {
+        Ok(match self.watermark()? {
+            Some(watermark) => filter_query(
+                &self.query,
+                &format!("{} > {}", quote_ident(&self.column), watermark),
+            ),
+            None => self.query.clone(),
+        })
+    }
+
+    /// Exports the new rows to a file in the directory `dir` and advances
+    /// the watermark.
+    pub async fn run(
+        &self,
+        client: &mut Client,
+        dir: impl AsRef<Path>,
+    ) -> Result<SyncSummary, DremioClientError> {
+        let query = self.next_query()?;
+        let mut stream = client
+            .execute_stream_with_options(&query, &self.query_options)
+            .await?;
+        let dir = dir.as_ref();
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let path = dir.join(format!(
+            "part-{:013}-{}-{}.parquet",
+            millis,
+            std::process::id(),
+            PARTS.fetch_add(1, Ordering::Relaxed)
+        ));
+        let partial = path.with_extension("parquet.tmp");
+
+        let mut writer: Option<ArrowWriter<File>> = None;
+        let mut verifier = self.verification.map(Verifier::new);
+        let mut highest: Option<ArrayRef> = None;
+        let mut rows = 0;
+        let written = |e: DremioClientError| e.in_query(QueryPhase::Write, &query);
+        while let Some(batch) = stream.next().await {
+            let batch = match batch {
+                Ok(batch) => batch,
+                Err(e) => {
+                    let _ = fs::remove_file(&partial);
+                    return Err(e);
+                }
+            };
+            if batch.num_rows() == 0 {
+                continue;
+            }
+            rows += batch.num_rows() as u64;
+            let mut write = || -> Result<(), DremioClientError> {
+                let column = batch.column_by_name(&self.column).ok_or_else(|| {
+                    ArrowError::SchemaError(format!("unknown watermark column {:?}", self.column))
+                })?;
+                highest = max_value(highest.take(), column)?;
+                if writer.is_none() {
+                    fs::create_dir_all(dir)?;
+                    let file = OpenOptions::new()
+                        .write(true)
+                        .create_new(true)
+                        .open(&partial)?;
+                    let properties = self.properties.clone();
+                    writer = Some(ArrowWriter::try_new(file, batch.schema(), properties)?);
+                }
+                if let Some(writer) = writer.as_mut() {
+                    writer.write(&batch)?;
+                }
+                if let Some(verifier) = verifier.as_mut() {
+                    verifier.update(&batch)?;
+                }
+                Ok(())
+            };
+            if let Err(e) = write() {
+                let _ = fs::remove_file(&partial);
+                return Err(written(e));
+            }
+        }
+
+        let Some(writer) = writer else {
+            return Ok(SyncSummary {
+                file: None,
+                rows: 0,
+                watermark: self.watermark()?,
+                verification: None,
+            });
+        };
+        if let Err(e) = writer.close() {
+            let _ = fs::remove_file(&partial);
+            return Err(written(e.into()));
+        }
+        let verification = match &verifier {
+            Some(verifier) => match verifier.verify([&partial]) {
+                Ok(report) => Some(report),
+                Err(e) => {
+                    let _ = fs::remove_file(&partial);
+                    return Err(written(e));
+                }
+            },
+            None => None,
+        };
+        if let Some(report) = verification.as_ref().filter(|r| !r.is_ok()) {
+            let _ = fs::remove_file(&partial);
+            return Ok(SyncSummary {
+                file: None,
+                rows,
+                watermark: self.watermark()?,
+                verification: Some(report.clone()),
+            });
+        }
+        let commit = || -> Result<Option<String>, DremioClientError> {
+            // Unlike a rename, linking fails if the file exists.
+            fs::hard_link(&partial, &path)?;
+            fs::remove_file(&partial)?;
+            let watermark = match &highest {
+                Some(value) => Some(value_literal(value, 0)?),
+                None => self.watermark()?,
+            };
+            if let Some(watermark) = &watermark {
+                let mut pending = self.state.clone().into_os_string();
+                pending.push(".tmp");
+                fs::write(&pending, watermark)?;
+                fs::rename(&pending, &self.state)?;
+            }
+            Ok(watermark)
+        };
+        let watermark = commit().map_err(|e| {
+            let _ = fs::remove_file(&partial);
+            written(e)
+        })?;
+        Ok(SyncSummary {
+            file: Some(path),
+            rows,
+            watermark,
+            verification,
+        })
+    }
+}
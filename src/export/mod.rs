@@ -0,0 +1,62 @@
+//! Writing query results to files and streams.
+//!
+//! [`PartitionedParquetWriter`] writes a stream of batches to a directory of
+//! Parquet files laid out Hive-style: every distinct combination of values in
+//! the partition columns gets its own `col=value/...` subdirectory, and the
+//! partition columns themselves are left out of the files.
+//!
+//! [`ParallelExporter`] splits a query into ranges of one column and fetches
+//! them concurrently over several connections, writing one Parquet file per
+//! range.
+//!
+//! [`IncrementalSync`] exports only the rows added since its previous run,
+//! tracking the largest value of a watermark column in a local state file.
+//!
+//! [`write_text`] writes a stream of batches as CSV or newline-delimited JSON
+//! to any [`AsyncWrite`](tokio::io::AsyncWrite), such as standard output or a
+//! socket, waiting for the writer to accept each batch before fetching more.
+//!
+//! With the `object_store` feature, [`ObjectFormat`] chooses the format of
+//! results uploaded to S3, GCS, Azure or any other
+//! [`ObjectStore`](object_store::ObjectStore) by
+//! [`Client::write_to_store`](crate::Client::write_to_store), in parts as
+//! batches arrive.
+//!
+//! [`Verifier`] checks Parquet files once they have been written, comparing
+//! the row counts in their footers, and optionally per-column null counts and
+//! checksums, with the rows that were fetched. The Parquet exporters take the
+//! check as an option, see for example
+//! [`ParallelExporter::with_verification`] and
+//! [`Client::write_parquet_verified`](crate::Client::write_parquet_verified).
+
+mod file;
+#[cfg(feature = "parquet")]
+mod incremental;
+#[cfg(feature = "parquet")]
+mod parallel;
+#[cfg(feature = "parquet")]
+mod parquet;
+#[cfg(feature = "parquet")]
+mod partitioned;
+#[cfg(feature = "object_store")]
+mod store;
+mod text;
+#[cfg(feature = "parquet")]
+mod verify;
+
+#[cfg(feature = "parquet")]
+pub(crate) use self::parquet::write_parquet;
+pub(crate) use file::{write_csv, write_ipc, write_json};
+#[cfg(feature = "parquet")]
+pub use incremental::{IncrementalSync, SyncSummary};
+#[cfg(feature = "parquet")]
+pub use parallel::ParallelExporter;
+#[cfg(feature = "parquet")]
+pub use partitioned::{ExportSummary, PartitionedParquetWriter, NULL_PARTITION};
+#[cfg(feature = "object_store")]
+pub(crate) use store::write_object;
+#[cfg(feature = "object_store")]
+pub use store::ObjectFormat;
+pub use text::{write_text, TextFormat};
+#[cfg(feature = "parquet")]
+pub use verify::{ColumnCheck, Verification, VerificationReport, Verifier};
@@ -0,0 +1,300 @@
+use super::parquet::partial_path;
+use super::{ExportSummary, Verification, Verifier};
+use crate::sql::{filter_query, quote_ident, value_literal};
+use crate::{Client, DremioClientError, QueryOptions, QueryPhase};
+use arrow::array::{Array, AsArray, Int64Array};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Int64Type};
+use arrow::error::ArrowError;
+use futures::StreamExt;
+use parquet::arrow::ArrowWriter;
+use parquet::file::properties::WriterProperties;
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Exports the results of a query as several shards fetched concurrently.
+///
+/// The query is split into range predicates on one column, such as a date or
+/// an integer key: its minimum and maximum are queried first, then the range
+/// between them is cut into equal parts. Each shard runs on one of the
+/// clients passed to [`export`](Self::export), so that shards are fetched over
+/// separate connections, and is written to its own `part-NNNN.parquet` file.
+///
+/// Rows where the column is null belong to the first shard. Shards without
+/// rows write no file. Files are written under temporary names and only
+/// renamed once every shard has succeeded, so a failed export leaves no
+/// files behind, not even those of the shards that were complete.
+///
+/// With [`with_verification`](Self::with_verification), the files are
+/// checked against the rows of all shards once every shard is written.
+///
+/// # Example
+///
+/// ```no_run
+/// use dremio_rs::export::ParallelExporter;
+/// use dremio_rs::Client;
+///
+/// #[tokio::main]
+/// async fn main() {
+///   let mut clients = Vec::new();
+///   for _ in 0..4 {
+///     clients.push(Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap());
+///   }
+///   let summary = ParallelExporter::new("SELECT * FROM lake.orders", "order_date")
+///     .with_shards(16)
+///     .export(&mut clients, "orders")
+///     .await
+///     .unwrap();
+///   println!("wrote {} rows to {} files", summary.rows, summary.files.len());
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ParallelExporter {
+    query: String,
+    column: String,
+    shards: usize,
+    query_options: QueryOptions,
+    properties: Option<WriterProperties>,
+    verification: Option<Verification>,
+}
+
+impl ParallelExporter {
+    /// Creates an exporter splitting `query` on `column`, one shard per
+    /// client unless [`with_shards`](Self::with_shards) says otherwise.
+    ///
+    /// `column` must be an integer, date or timestamp column of the results.
+    pub fn new(query: impl Into<String>, column: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            column: column.into(),
+            shards: 0,
+            query_options: QueryOptions::default(),
+            properties: None,
+            verification: None,
+        }
+    }
+
+    /// Sets the number of shards. Shards outnumbering the clients wait for
+    /// one to become free.
+    pub fn with_shards(mut self, shards: usize) -> Self {
+        self.shards = shards.max(1);
+        self
+    }
+
+    /// Sets the options for the shard queries.
+    pub fn with_query_options(mut self, options: QueryOptions) -> Self {
+        self.query_options = options;
+        self
+    }
+
+    /// Sets the Parquet writer properties used for every file.
+    pub fn with_properties(mut self, properties: WriterProperties) -> Self {
+        self.properties = Some(properties);
+        self
+    }
+
+    /// Checks the files written against the rows fetched, as thoroughly as
+    /// `verification` says. The report is returned in
+    /// [`ExportSummary::verification`], and fails if no file was written.
+    pub fn with_verification(mut self, verification: Verification) -> Self {
+        self.verification = Some(verification);
+        self
+    }
+
+    /// Returns the query of each shard, splitting the range into the number
+    /// of shards set with [`with_shards`](Self::with_shards), if any.
+    ///
+    /// Queries the bounds of the split column with `client`; the returned
+    /// queries can be run in any way, for example from separate processes.
+    pub async fn shard_queries(
+        &self,
+        client: &mut Client,
+    ) -> Result<Vec<String>, DremioClientError> {
+        let column = quote_ident(&self.column);
+        let bounds = format!(
+            "SELECT MIN({column}) AS lo, MAX({column}) AS hi FROM (\n{}\n)",
+            self.query.trim().trim_end_matches(';')
+        );
+        let batches = client
+            .get_record_batches_with_options(&bounds, &self.query_options.transport_only())
+            .await?;
+        let Some(batch) = batches.iter().find(|b| b.num_rows() > 0) else {
+            return Ok(vec![self.query.clone()]);
+        };
+        // Dremio returns dates in milliseconds; split them on whole days.
+        let data_type = match batch.column(0).data_type() {
+            DataType::Date64 => DataType::Date32,
+            data_type => data_type.clone(),
+        };
+        let to_int = |index: usize| -> Result<Option<i64>, DremioClientError> {
+            let values = cast(&cast(batch.column(index), &data_type)?, &DataType::Int64)?;
+            let values = values.as_primitive::<Int64Type>();
+            Ok(values.is_valid(0).then(|| values.value(0)))
+        };
+        let (Some(lo), Some(hi)) = (to_int(0)?, to_int(1)?) else {
+            // Only nulls, or no rows at all.
+            return Ok(vec![self.query.clone()]);
+        };
+
+        let shards = if self.shards == 0 { 1 } else { self.shards } as i128;
+        let step = ((hi as i128 - lo as i128 + 1) + shards - 1) / shards;
+        let mut queries = Vec::new();
+        let mut start = lo as i128;
+        while start <= hi as i128 {
+            let end = start + step;
+            let mut predicate = format!("{column} >= {}", literal(&data_type, start as i64)?);
+            if end <= hi as i128 {
+                predicate.push_str(&format!(
+                    " AND {column} < {}",
+                    literal(&data_type, end as i64)?
+                ));
+            }
+            if queries.is_empty() {
+                predicate = format!("({predicate}) OR {column} IS NULL");
+            }
+            queries.push(filter_query(&self.query, &predicate));
+            start = end;
+        }
+        Ok(queries)
+    }
+
+    /// Runs the shards concurrently, one per client at a time, and writes
+    /// them below the directory `dir`.
+    pub async fn export(
+        &self,
+        clients: &mut [Client],
+        dir: impl AsRef<Path>,
+    ) -> Result<ExportSummary, DremioClientError> {
+        let dir = dir.as_ref();
+        let exporter = match self.shards {
+            0 => self.clone().with_shards(clients.len()),
+            _ => self.clone(),
+        };
+        let Some(first) = clients.first_mut() else {
+            return Err(
+                ArrowError::InvalidArgumentError("no clients to export with".to_string()).into(),
+            );
+        };
+        let queries = exporter.shard_queries(first).await?;
+        fs::create_dir_all(dir)?;
+        let pending = Mutex::new(queries.into_iter().enumerate().collect::<VecDeque<_>>());
+        let verifier = self.verification.map(|v| Mutex::new(Verifier::new(v)));
+        let partials = Mutex::new(Vec::new());
+        let workers = clients.iter_mut().map(|client| {
+            let pending = &pending;
+            let verifier = verifier.as_ref();
+            let partials = &partials;
+            async move {
+                let mut written = Vec::new();
+                loop {
+                    let next = pending.lock().unwrap().pop_front();
+                    let Some((shard, query)) = next else {
+                        return Ok::<_, DremioClientError>(written);
+                    };
+                    let path = dir.join(format!("part-{:04}.parquet", shard));
+                    if let Some(rows) = self
+                        .write_shard(client, &query, &path, verifier, partials)
+                        .await?
+                    {
+                        written.push((path, rows));
+                    }
+                }
+            }
+        });
+        // The other workers are dropped on the first error, possibly in the
+        // middle of a shard.
+        let written = match futures::future::try_join_all(workers).await {
+            Ok(written) => written,
+            Err(e) => {
+                for partial in partials.into_inner().unwrap() {
+                    let _ = fs::remove_file(partial);
+                }
+                return Err(e);
+            }
+        };
+        let mut summary = ExportSummary::default();
+        for (path, rows) in written.into_iter().flatten() {
+            fs::rename(partial_path(&path), &path)?;
+            summary.files.push(path);
+            summary.rows += rows;
+        }
+        summary.files.sort();
+        if let Some(verifier) = verifier {
+            let report = verifier.into_inner().unwrap().verify(&summary.files)?;
+            summary.verification = Some(report);
+        }
+        Ok(summary)
+    }
+
+    /// Writes the results of `query` to the temporary name of `path`,
+    /// recording them with `verifier`, if any, and the file in `partials`.
+    /// Returns the number of rows written, or `None` if there were no batches
+    /// and no file was created.
+    async fn write_shard(
+        &self,
+        client: &mut Client,
+        query: &str,
+        path: &Path,
+        verifier: Option<&Mutex<Verifier>>,
+        partials: &Mutex<Vec<PathBuf>>,
+    ) -> Result<Option<u64>, DremioClientError> {
+        let mut stream = client
+            .execute_stream_with_options(query, &self.query_options)
+            .await?;
+        let mut writer: Option<ArrowWriter<File>> = None;
+        let mut rows = 0;
+        let written = |e: DremioClientError| e.in_query(QueryPhase::Write, query);
+        while let Some(batch) = stream.next().await {
+            let batch = batch?;
+            rows += batch.num_rows() as u64;
+            let mut write = || -> Result<(), DremioClientError> {
+                if writer.is_none() {
+                    let partial = partial_path(path);
+                    partials.lock().unwrap().push(partial.clone());
+                    let file = File::create(partial)?;
+                    writer = Some(ArrowWriter::try_new(
+                        file,
+                        batch.schema(),
+                        self.properties.clone(),
+                    )?);
+                }
+                if let Some(writer) = writer.as_mut() {
+                    writer.write(&batch)?;
+                }
+                if let Some(verifier) = verifier {
+                    verifier.lock().unwrap().update(&batch)?;
+                }
+                Ok(())
+            };
+            write().map_err(written)?;
+        }
+        match writer {
+            Some(writer) => {
+                writer.close().map_err(|e| written(e.into()))?;
+                Ok(Some(rows))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// Returns the SQL literal for `value`, the integer representation of a value
+/// of `data_type`.
+fn literal(data_type: &DataType, value: i64) -> Result<String, DremioClientError> {
+    let value = Int64Array::from(vec![value]);
+    let value = match data_type {
+        DataType::Date32 => cast(&cast(&value, &DataType::Int32)?, &DataType::Date32)?,
+        DataType::Timestamp(unit, _) => cast(&value, &DataType::Timestamp(*unit, None))?,
+        data_type if data_type.is_integer() => Arc::new(value),
+        data_type => {
+            return Err(ArrowError::InvalidArgumentError(format!(
+                "cannot split a query on a column of type {}",
+                data_type
+            ))
+            .into())
+        }
+    };
+    value_literal(&value, 0)
+}
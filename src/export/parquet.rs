@@ -0,0 +1,66 @@
+use super::file::write_file;
+use super::{ExportSummary, Verification, Verifier};
+use crate::{DremioClientError, QueryStream};
+use parquet::arrow::ArrowWriter;
+use parquet::file::properties::WriterProperties;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Writes all batches of `stream` to a Parquet file at `path`, and returns
+/// the file and the number of rows written. With `verification`, the file is
+/// checked against the batches once it is closed.
+///
+/// Only the row group being filled is held in memory: it is flushed to the
+/// file once it reaches the row group size of `properties`. The file is
+/// written under a temporary name and only moved to `path` once complete, so
+/// a failed export leaves no partial file behind.
+pub(crate) async fn write_parquet(
+    stream: QueryStream,
+    path: &Path,
+    properties: Option<WriterProperties>,
+    verification: Option<Verification>,
+) -> Result<ExportSummary, DremioClientError> {
+    let verifier = verification.map(|v| Arc::new(Mutex::new(Verifier::new(v))));
+    let stream = match &verifier {
+        Some(verifier) => {
+            let verifier = verifier.clone();
+            stream.map_batches(move |batch| {
+                verifier.lock().unwrap().update(&batch)?;
+                Ok(batch)
+            })
+        }
+        None => stream,
+    };
+    let partial = partial_path(path);
+    let rows = match write_file(stream, &partial, |file, schema| {
+        Ok(ArrowWriter::try_new(file, schema, properties)?)
+    })
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            let _ = fs::remove_file(&partial);
+            return Err(e);
+        }
+    };
+    fs::rename(&partial, path)?;
+    let verification = match verifier {
+        Some(verifier) => Some(verifier.lock().unwrap().verify([path])?),
+        None => None,
+    };
+    Ok(ExportSummary {
+        files: vec![path.to_path_buf()],
+        rows,
+        verification,
+    })
+}
+
+/// Returns the temporary name a file at `path` is written under until it is
+/// complete.
+pub(crate) fn partial_path(path: &Path) -> PathBuf {
+    let mut partial = OsString::from(path.as_os_str());
+    partial.push(".tmp");
+    PathBuf::from(partial)
+}
@@ -0,0 +1,273 @@
+use super::{Verification, VerificationReport, Verifier};
+use crate::DremioClientError;
+use arrow::array::{Array, AsArray, RecordBatch, UInt32Array};
+use arrow::compute::{cast, take_record_batch};
+use arrow::datatypes::{DataType, SchemaRef};
+use arrow::error::ArrowError;
+use parquet::arrow::ArrowWriter;
+use parquet::file::properties::WriterProperties;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+/// Directory name used for rows whose partition value is null.
+pub const NULL_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+
+/// Returns the name of the `index`th Parquet file of a partition directory.
+fn part_file(index: usize) -> String {
+    format!("part-{:04}.parquet", index)
+}
+
+/// Writes batches to a directory of Parquet files, one per partition, or
+/// several once a file reaches the size set with
+/// [`with_max_rows_per_file`](Self::with_max_rows_per_file) or
+/// [`with_max_bytes_per_file`](Self::with_max_bytes_per_file).
+///
+/// # Example
+///
+/// ```no_run
+/// use dremio_rs::export::PartitionedParquetWriter;
+/// use dremio_rs::{Client, QueryOptions};
+/// use futures::StreamExt;
+///
+/// #[tokio::main]
+/// async fn main() {
+///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+///   let mut stream = client
+///     .execute_stream_with_options("SELECT * FROM sales", &QueryOptions::new())
+///     .await
+///     .unwrap();
+///   let mut writer = PartitionedParquetWriter::new("sales", vec!["dt".to_string()]);
+///   while let Some(batch) = stream.next().await {
+///     writer.write(&batch.unwrap()).unwrap();
+///   }
+///   let summary = writer.close().unwrap();
+///   println!("wrote {} files", summary.files.len());
+/// }
+/// ```
+pub struct PartitionedParquetWriter {
+    root: PathBuf,
+    partition_by: Vec<String>,
+    properties: Option<WriterProperties>,
+    max_rows_per_file: Option<u64>,
+    max_bytes_per_file: Option<usize>,
+    writers: HashMap<PathBuf, PartFile>,
+    parts: HashMap<PathBuf, usize>,
+    files: Vec<PathBuf>,
+    verifier: Option<Verifier>,
+    rows: u64,
+}
+
+/// The file of a partition directory being written.
+struct PartFile {
+    writer: ArrowWriter<File>,
+    rows: u64,
+}
+
+/// Files and rows written by a [`PartitionedParquetWriter`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExportSummary {
+    /// Paths of the Parquet files written, sorted.
+    pub files: Vec<PathBuf>,
+    /// Total number of rows written.
+    pub rows: u64,
+    /// The result of checking the files, if verification was enabled with
+    /// [`PartitionedParquetWriter::with_verification`].
+    pub verification: Option<VerificationReport>,
+}
+
+impl PartitionedParquetWriter {
+    /// Creates a writer that writes below `root`, partitioning rows by the
+    /// values of the `partition_by` columns.
+    ///
+    /// Files are named `part-0000.parquet`, `part-0001.parquet` and so on
+    /// within each directory. With no partition columns they are written
+    /// directly in `root`.
+    pub fn new(root: impl Into<PathBuf>, partition_by: Vec<String>) -> Self {
+        Self {
+            root: root.into(),
+            partition_by,
+            properties: None,
+            max_rows_per_file: None,
+            max_bytes_per_file: None,
+            writers: HashMap::new(),
+            parts: HashMap::new(),
+            files: Vec::new(),
+            verifier: None,
+            rows: 0,
+        }
+    }
+
+    /// Sets the Parquet writer properties used for every file.
+    pub fn with_properties(mut self, properties: WriterProperties) -> Self {
+        self.properties = Some(properties);
+        self
+    }
+
+    /// Starts a new file in a partition directory once its current file holds
+    /// `rows` rows. Zero is taken as one.
+    pub fn with_max_rows_per_file(mut self, rows: u64) -> Self {
+        self.max_rows_per_file = Some(rows.max(1));
+        self
+    }
+
+    /// Starts a new file in a partition directory once its current file has
+    /// grown to about `bytes` bytes, counting the buffered row group at its
+    /// in-memory size. Files end at a batch boundary, so they may exceed it.
+    pub fn with_max_bytes_per_file(mut self, bytes: usize) -> Self {
+        self.max_bytes_per_file = Some(bytes);
+        self
+    }
+
+    /// Checks the files once they are closed, as described by
+    /// `verification`. The report is returned in
+    /// [`ExportSummary::verification`]; partition columns are not part of
+    /// the files and are left out of per-column checks.
+    pub fn with_verification(mut self, verification: Verification) -> Self {
+        self.verifier = Some(Verifier::new(verification));
+        self
+    }
+
+    /// Writes the rows of `batch` to the files of their partitions.
+    pub fn write(&mut self, batch: &RecordBatch) -> Result<(), DremioClientError> {
+        if batch.num_rows() == 0 {
+            return Ok(());
+        }
+        self.rows += batch.num_rows() as u64;
+        if self.partition_by.is_empty() {
+            if let Some(verifier) = self.verifier.as_mut() {
+                verifier.update(batch)?;
+            }
+            let dir = self.root.clone();
+            return self.write_rows(&dir, batch);
+        }
+
+        let schema = batch.schema();
+        let mut keys = Vec::with_capacity(self.partition_by.len());
+        let mut indices = Vec::with_capacity(self.partition_by.len());
+        for name in &self.partition_by {
+            let index = schema.index_of(name).map_err(|_| {
+                ArrowError::SchemaError(format!("unknown partition column {:?}", name))
+            })?;
+            keys.push(cast(batch.column(index), &DataType::Utf8)?);
+            indices.push(index);
+        }
+        let data_indices: Vec<usize> = (0..schema.fields().len())
+            .filter(|i| !indices.contains(i))
+            .collect();
+        let data = batch.project(&data_indices)?;
+        if let Some(verifier) = self.verifier.as_mut() {
+            verifier.update(&data)?;
+        }
+
+        let mut partitions: Vec<(PathBuf, Vec<u32>)> = Vec::new();
+        let mut positions: HashMap<PathBuf, usize> = HashMap::new();
+        for row in 0..batch.num_rows() {
+            let mut dir = self.root.clone();
+            for (name, values) in self.partition_by.iter().zip(&keys) {
+                let values = values.as_string::<i32>();
+                let value = if values.is_null(row) {
+                    NULL_PARTITION.to_string()
+                } else {
+                    escape(values.value(row))
+                };
+                dir.push(format!("{}={}", escape(name), value));
+            }
+            let position = *positions.entry(dir.clone()).or_insert_with(|| {
+                partitions.push((dir, Vec::new()));
+                partitions.len() - 1
+            });
+            partitions[position].1.push(row as u32);
+        }
+
+        for (dir, rows) in partitions {
+            let rows = take_record_batch(&data, &UInt32Array::from(rows))?;
+            self.write_rows(&dir, &rows)?;
+        }
+        Ok(())
+    }
+
+    /// Finishes every file and returns what was written.
+    pub fn close(self) -> Result<ExportSummary, DremioClientError> {
+        for file in self.writers.into_values() {
+            file.writer.close()?;
+        }
+        let mut files = self.files;
+        files.sort();
+        let verification = match &self.verifier {
+            Some(verifier) => Some(verifier.verify(&files)?),
+            None => None,
+        };
+        Ok(ExportSummary {
+            files,
+            rows: self.rows,
+            verification,
+        })
+    }
+
+    /// Writes `batch` to the files of the partition directory `dir`, starting
+    /// new files as they fill up.
+    fn write_rows(&mut self, dir: &Path, batch: &RecordBatch) -> Result<(), DremioClientError> {
+        let (max_rows, max_bytes) = (self.max_rows_per_file, self.max_bytes_per_file);
+        let mut offset = 0;
+        while offset < batch.num_rows() {
+            let file = self.file(dir, batch.schema())?;
+            let room = max_rows.map_or(u64::MAX, |max| max.saturating_sub(file.rows));
+            let len = (batch.num_rows() - offset).min(room.try_into().unwrap_or(usize::MAX));
+            file.writer.write(&batch.slice(offset, len))?;
+            file.rows += len as u64;
+            offset += len;
+            let full = max_rows.is_some_and(|max| file.rows >= max)
+                || max_bytes.is_some_and(|max| {
+                    file.writer.bytes_written() + file.writer.in_progress_size() >= max
+                });
+            if full {
+                if let Some(file) = self.writers.remove(dir) {
+                    file.writer.close()?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the file being written in the partition directory `dir`,
+    /// creating the next one if there is none.
+    fn file(&mut self, dir: &Path, schema: SchemaRef) -> Result<&mut PartFile, DremioClientError> {
+        match self.writers.entry(dir.to_path_buf()) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => {
+                let index = self.parts.entry(dir.to_path_buf()).or_default();
+                let path = dir.join(part_file(*index));
+                *index += 1;
+                fs::create_dir_all(dir)?;
+                let file = File::create(&path)?;
+                let writer = ArrowWriter::try_new(file, schema, self.properties.clone())?;
+                self.files.push(path);
+                Ok(entry.insert(PartFile { writer, rows: 0 }))
+            }
+        }
+    }
+}
+
+/// Percent-encodes the characters that cannot appear in a partition
+/// directory name.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '/' | '\\' | '=' | '%' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => {
+                escaped.push_str(&format!("%{:02X}", c as u32))
+            }
+            c if c.is_control() => escaped.push_str(&format!("%{:02X}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    if escaped.is_empty() {
+        NULL_PARTITION.to_string()
+    } else if escaped == "." || escaped == ".." {
+        escaped.replace('.', "%2E")
+    } else {
+        escaped
+    }
+}
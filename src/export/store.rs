@@ -0,0 +1,137 @@
+use super::file::first_schema;
+use crate::{DremioClientError, QueryStream};
+use arrow::array::{RecordBatch, RecordBatchWriter};
+use arrow::csv::WriterBuilder;
+use arrow::datatypes::SchemaRef;
+use arrow::ipc::writer::{FileWriter, IpcWriteOptions};
+use arrow::json::LineDelimitedWriter;
+use futures::StreamExt;
+use object_store::buffered::BufWriter;
+use object_store::path::Path;
+use object_store::ObjectStore;
+#[cfg(feature = "parquet")]
+use parquet::arrow::ArrowWriter;
+#[cfg(feature = "parquet")]
+use parquet::file::properties::WriterProperties;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use tokio::io::AsyncWriteExt;
+
+/// File formats written to an object store by
+/// [`Client::write_to_store`](crate::Client::write_to_store).
+#[derive(Clone, Debug)]
+pub enum ObjectFormat {
+    /// A Parquet file, written with the given properties.
+    #[cfg(feature = "parquet")]
+    Parquet(WriterProperties),
+    /// A CSV file, written with the given settings.
+    Csv(WriterBuilder),
+    /// A newline-delimited JSON file.
+    Json,
+    /// An Arrow IPC file, written with the given options.
+    Ipc(IpcWriteOptions),
+}
+
+/// A `Write` handle on a buffer, drained into the upload after every batch.
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Writes all batches of `stream` in `format` to the object at `path` in
+/// `store`, and returns the number of rows written.
+///
+/// The object is uploaded in parts as batches are encoded, without a local
+/// file. If anything fails, the upload is aborted and no object is created.
+pub(crate) async fn write_object(
+    stream: QueryStream,
+    store: Arc<dyn ObjectStore>,
+    path: &Path,
+    format: ObjectFormat,
+) -> Result<u64, DremioClientError> {
+    let buffer = SharedBuffer::default();
+    let out = buffer.clone();
+    match format {
+        #[cfg(feature = "parquet")]
+        ObjectFormat::Parquet(properties) => {
+            upload(stream, store, path, buffer, |schema| {
+                Ok(ArrowWriter::try_new(out, schema, Some(properties))?)
+            })
+            .await
+        }
+        ObjectFormat::Csv(builder) => {
+            upload(stream, store, path, buffer, |schema| {
+                let mut writer = builder.build(out);
+                // Writes the header row, even if no rows follow.
+                writer.write(&RecordBatch::new_empty(schema))?;
+                Ok(writer)
+            })
+            .await
+        }
+        ObjectFormat::Json => {
+            upload(stream, store, path, buffer, |_| {
+                Ok(LineDelimitedWriter::new(out))
+            })
+            .await
+        }
+        ObjectFormat::Ipc(options) => {
+            upload(stream, store, path, buffer, |schema| {
+                Ok(FileWriter::try_new_with_options(out, &schema, options)?)
+            })
+            .await
+        }
+    }
+}
+
+/// Encodes the batches of `stream` with the writer `create` returns, which
+/// writes into `buffer`, and uploads what it writes to `path`.
+async fn upload<W, F>(
+    mut stream: QueryStream,
+    store: Arc<dyn ObjectStore>,
+    path: &Path,
+    buffer: SharedBuffer,
+    create: F,
+) -> Result<u64, DremioClientError>
+where
+    W: RecordBatchWriter,
+    F: FnOnce(SchemaRef) -> Result<W, DremioClientError>,
+{
+    let (schema, first) = first_schema(&mut stream).await?;
+    let mut upload = BufWriter::new(store, path.clone());
+    let result = async {
+        let mut writer = create(schema)?;
+        let mut batches = futures::stream::iter(first.map(Ok)).chain(stream);
+        let mut rows = 0;
+        while let Some(batch) = batches.next().await {
+            let batch = batch?;
+            writer.write(&batch)?;
+            rows += batch.num_rows() as u64;
+            upload.write_all(&buffer.take()).await?;
+        }
+        writer.close()?;
+        upload.write_all(&buffer.take()).await?;
+        upload.shutdown().await?;
+        Ok::<_, DremioClientError>(rows)
+    }
+    .await;
+    if result.is_err() {
+        // The error that caused it matters more than a failure to clean up.
+        let _ = upload.abort().await;
+    }
+    result
+}
@@ -0,0 +1,88 @@
+use crate::{DremioClientError, QueryStream};
+use arrow::array::RecordBatch;
+use futures::StreamExt;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+/// Text formats supported by [`write_text`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextFormat {
+    /// Comma-separated values with a header row.
+    Csv,
+    /// Newline-delimited JSON objects.
+    Json,
+}
+
+/// Writes all batches of `stream` to `out` in `format`, and returns the
+/// number of rows written.
+///
+/// CSV output starts with a single header row, which is written from the
+/// schema of the stream even if no rows follow.
+///
+/// Each batch is encoded in memory and written before the next one is
+/// fetched, so a slow reader on the other end of `out` slows down the query
+/// instead of letting results pile up. Columns the format cannot represent,
+/// such as nested ones in CSV, must be converted with [`QueryOptions`]
+/// beforehand.
+///
+/// [`QueryOptions`]: crate::QueryOptions
+///
+/// # Example
+///
+/// ```no_run
+/// use dremio_rs::export::{write_text, TextFormat};
+/// use dremio_rs::{Client, QueryOptions};
+/// use tokio::io::AsyncWriteExt;
+///
+/// #[tokio::main]
+/// async fn main() {
+///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+///   let stream = client
+///     .execute_stream_with_options("SELECT * FROM sys.options", &QueryOptions::new())
+///     .await
+///     .unwrap();
+///   let mut stdout = tokio::io::stdout();
+///   write_text(stream, TextFormat::Csv, &mut stdout).await.unwrap();
+///   stdout.flush().await.unwrap();
+/// }
+/// ```
+pub async fn write_text<W>(
+    mut stream: QueryStream,
+    format: TextFormat,
+    mut out: W,
+) -> Result<u64, DremioClientError>
+where
+    W: AsyncWrite + Unpin,
+{
+    let mut rows = 0;
+    let mut header_written = false;
+    let mut buffer = Vec::new();
+    while let Some(batch) = stream.next().await {
+        let batch = batch?;
+        match format {
+            TextFormat::Csv => {
+                // The writer emits the header on its first write, even for
+                // an empty batch.
+                arrow::csv::WriterBuilder::new()
+                    .with_header(!header_written)
+                    .build(&mut buffer)
+                    .write(&batch)?;
+                header_written = true;
+            }
+            TextFormat::Json => {
+                let mut writer = arrow::json::LineDelimitedWriter::new(&mut buffer);
+                writer.write(&batch)?;
+                writer.finish()?;
+            }
+        }
+        rows += batch.num_rows() as u64;
+        out.write_all(&buffer).await?;
+        buffer.clear();
+    }
+    if let (TextFormat::Csv, false, Some(schema)) = (format, header_written, stream.schema()) {
+        arrow::csv::WriterBuilder::new()
+            .build(&mut buffer)
+            .write(&RecordBatch::new_empty(schema))?;
+        out.write_all(&buffer).await?;
+    }
+    Ok(rows)
+}
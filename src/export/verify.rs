@@ -0,0 +1,257 @@
+use crate::DremioClientError;
+use arrow::array::{Array, ArrayRef, RecordBatch};
+use arrow::row::{RowConverter, SortField};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use std::fmt;
+use std::fs::File;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::Path;
+
+/// How thoroughly exported files are checked once they have been written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Verification {
+    /// Compare the row counts in the file footers with the rows exported.
+    /// Only the footers are read back.
+    #[default]
+    RowCounts,
+    /// Also read every file back and compare the null count and a checksum
+    /// of the values of each column with those of the rows exported.
+    Checksums,
+}
+
+/// Null count and checksum of one column, as exported and as read back.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ColumnCheck {
+    /// Name of the column.
+    pub name: String,
+    /// Nulls in the exported rows.
+    pub expected_nulls: u64,
+    /// Nulls in the files.
+    pub written_nulls: u64,
+    /// Checksum of the exported values.
+    pub expected_checksum: u64,
+    /// Checksum of the values in the files.
+    pub written_checksum: u64,
+}
+
+impl ColumnCheck {
+    /// Returns whether the files hold the same values as the exported rows.
+    pub fn matches(&self) -> bool {
+        self.expected_nulls == self.written_nulls && self.expected_checksum == self.written_checksum
+    }
+}
+
+/// The outcome of checking exported files against the rows that were fetched.
+///
+/// Checksums do not depend on the order of the rows, so they also hold for
+/// rows spread over several files. They are only comparable within a report.
+///
+/// An export that wrote no file does not pass, even if no rows were fetched;
+/// one whose files hold no rows passes but is [empty](Self::is_empty).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VerificationReport {
+    /// Files read back.
+    pub files: u64,
+    /// Rows fetched and handed to the writer.
+    pub expected_rows: u64,
+    /// Rows recorded in the footers of the files.
+    pub written_rows: u64,
+    /// Per-column checks, empty unless [`Verification::Checksums`] was used.
+    pub columns: Vec<ColumnCheck>,
+}
+
+impl VerificationReport {
+    /// Returns whether any file was written and the row counts and every
+    /// column check match.
+    pub fn is_ok(&self) -> bool {
+        self.files > 0
+            && self.expected_rows == self.written_rows
+            && self.columns.iter().all(ColumnCheck::matches)
+    }
+
+    /// Returns whether the files hold no rows, or no file was written.
+    pub fn is_empty(&self) -> bool {
+        self.written_rows == 0
+    }
+}
+
+impl fmt::Display for VerificationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.files == 0 {
+            return write!(f, "{} rows exported, no file written", self.expected_rows);
+        }
+        write!(
+            f,
+            "{} rows exported, {} rows written to {} files",
+            self.expected_rows, self.written_rows, self.files
+        )?;
+        for column in self.columns.iter().filter(|c| !c.matches()) {
+            write!(
+                f,
+                "; column \"{}\" differs ({} nulls exported, {} written)",
+                column.name, column.expected_nulls, column.written_nulls
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Tracks the rows handed to an export so that the files written can be
+/// checked against them afterwards.
+///
+/// [`PartitionedParquetWriter::with_verification`](super::PartitionedParquetWriter::with_verification)
+/// uses a `Verifier` internally; use one directly when writing files some
+/// other way.
+///
+/// # Example
+///
+/// ```no_run
+/// use dremio_rs::export::{Verification, Verifier};
+/// # fn main() -> Result<(), dremio_rs::DremioClientError> {
+/// # let batches: Vec<arrow::array::RecordBatch> = vec![];
+/// let mut verifier = Verifier::new(Verification::Checksums);
+/// for batch in &batches {
+///     // ... write `batch` to out.parquet ...
+///     verifier.update(batch)?;
+/// }
+/// let report = verifier.verify(["out.parquet"])?;
+/// assert!(report.is_ok(), "{}", report);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Verifier {
+    verification: Verification,
+    expected: Digest,
+}
+
+impl Verifier {
+    /// Creates a verifier that has not seen any rows.
+    pub fn new(verification: Verification) -> Self {
+        Self {
+            verification,
+            expected: Digest::default(),
+        }
+    }
+
+    /// Records the rows of `batch` as exported.
+    pub fn update(&mut self, batch: &RecordBatch) -> Result<(), DremioClientError> {
+        self.expected
+            .update(batch, self.verification == Verification::Checksums)
+    }
+
+    /// Reads back the Parquet `files` and compares them with the rows
+    /// recorded so far. A file that does not exist fails the check with an
+    /// error; no files at all fail it in the report.
+    pub fn verify<I, P>(&self, files: I) -> Result<VerificationReport, DremioClientError>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let checksums = self.verification == Verification::Checksums;
+        let mut written = Digest::default();
+        let mut count = 0;
+        for path in files {
+            count += 1;
+            let builder = ParquetRecordBatchReaderBuilder::try_new(File::open(path)?)?;
+            if !checksums {
+                written.rows += builder.metadata().file_metadata().num_rows() as u64;
+                continue;
+            }
+            for batch in builder.build()? {
+                written.update(&batch?, true)?;
+            }
+        }
+
+        let mut columns: Vec<ColumnCheck> = self
+            .expected
+            .columns
+            .iter()
+            .map(|(name, expected)| {
+                let written = written.column(name);
+                ColumnCheck {
+                    name: name.clone(),
+                    expected_nulls: expected.nulls,
+                    written_nulls: written.nulls,
+                    expected_checksum: expected.checksum,
+                    written_checksum: written.checksum,
+                }
+            })
+            .collect();
+        // Columns that only appear in the files.
+        for (name, written) in &written.columns {
+            if self.expected.columns.iter().all(|(n, _)| n != name) {
+                columns.push(ColumnCheck {
+                    name: name.clone(),
+                    written_nulls: written.nulls,
+                    written_checksum: written.checksum,
+                    ..Default::default()
+                });
+            }
+        }
+        Ok(VerificationReport {
+            files: count,
+            expected_rows: self.expected.rows,
+            written_rows: written.rows,
+            columns,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct ColumnDigest {
+    nulls: u64,
+    checksum: u64,
+}
+
+/// Row count and, optionally, per-column digests of a set of batches.
+#[derive(Debug, Default)]
+struct Digest {
+    rows: u64,
+    columns: Vec<(String, ColumnDigest)>,
+}
+
+impl Digest {
+    fn update(&mut self, batch: &RecordBatch, checksums: bool) -> Result<(), DremioClientError> {
+        self.rows += batch.num_rows() as u64;
+        if !checksums {
+            return Ok(());
+        }
+        for (field, array) in batch.schema().fields().iter().zip(batch.columns()) {
+            let checksum = checksum(array)?;
+            let nulls = array.logical_null_count() as u64;
+            let i = match self.columns.iter().position(|(n, _)| n == field.name()) {
+                Some(i) => i,
+                None => {
+                    self.columns
+                        .push((field.name().clone(), ColumnDigest::default()));
+                    self.columns.len() - 1
+                }
+            };
+            let digest = &mut self.columns[i].1;
+            digest.nulls += nulls;
+            digest.checksum = digest.checksum.wrapping_add(checksum);
+        }
+        Ok(())
+    }
+
+    fn column(&self, name: &str) -> ColumnDigest {
+        self.columns
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, digest)| *digest)
+            .unwrap_or_default()
+    }
+}
+
+/// Sums the hashes of the row-encoded values of `array`, so that the result
+/// does not depend on the order of the values.
+fn checksum(array: &ArrayRef) -> Result<u64, DremioClientError> {
+    let converter = RowConverter::new(vec![SortField::new(array.data_type().clone())])?;
+    let rows = converter.convert_columns(std::slice::from_ref(array))?;
+    Ok(rows.iter().fold(0u64, |sum, row| {
+        let mut hasher = DefaultHasher::new();
+        row.as_ref().hash(&mut hasher);
+        sum.wrapping_add(hasher.finish())
+    }))
+}
@@ -0,0 +1,262 @@
+//! Filtering result rows on the client.
+//!
+//! A [`Predicate`] compares columns with literal values using Arrow compute
+//! kernels. Attached with
+//! [`QueryOptions::with_filter`](crate::QueryOptions::with_filter), it drops
+//! non-matching rows from each batch as it streams in. [`Predicate::filter`]
+//! applies it to batches directly, so one set of results, for example from a
+//! result cache, can be sliced differently for each of its consumers.
+//!
+//! Comparisons with null never match, as in SQL.
+
+use crate::DremioClientError;
+use arrow::array::{
+    ArrayRef, BooleanArray, Datum, Float64Array, Int64Array, RecordBatch, Scalar, StringArray,
+};
+use arrow::compute::kernels::boolean::{and_kleene, not, or_kleene};
+use arrow::compute::kernels::cmp;
+use arrow::compute::{cast, filter_record_batch, is_not_null, is_null};
+use arrow::error::ArrowError;
+use std::sync::Arc;
+
+/// A literal value compared with a column.
+///
+/// Values are cast to the type of the column, so dates and timestamps can be
+/// given as strings such as `"2024-01-31"`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// A boolean.
+    Boolean(bool),
+    /// An integer.
+    Int(i64),
+    /// A floating point number.
+    Float(f64),
+    /// A string.
+    Utf8(String),
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Boolean(value)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Self {
+        Value::Int(value.into())
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Int(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Utf8(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Utf8(value)
+    }
+}
+
+impl Value {
+    fn to_array(&self) -> ArrayRef {
+        match self {
+            Value::Boolean(value) => Arc::new(BooleanArray::from(vec![*value])),
+            Value::Int(value) => Arc::new(Int64Array::from(vec![*value])),
+            Value::Float(value) => Arc::new(Float64Array::from(vec![*value])),
+            Value::Utf8(value) => Arc::new(StringArray::from(vec![value.as_str()])),
+        }
+    }
+}
+
+/// How a column is compared with a [`Value`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    /// Equal to the value.
+    Eq,
+    /// Not equal to the value.
+    NotEq,
+    /// Less than the value.
+    Lt,
+    /// Less than or equal to the value.
+    LtEq,
+    /// Greater than the value.
+    Gt,
+    /// Greater than or equal to the value.
+    GtEq,
+}
+
+/// A condition on the rows of a batch.
+///
+/// # Example
+///
+/// ```
+/// use arrow::array::{Int32Array, RecordBatch};
+/// use dremio_rs::filter::Predicate;
+/// use std::sync::Arc;
+///
+/// let ids = Arc::new(Int32Array::from(vec![1, 5, 10]));
+/// let batch = RecordBatch::try_from_iter([("id", ids as _)]).unwrap();
+/// let predicate = Predicate::between("id", 2, 10).and(!Predicate::eq("id", 10));
+/// assert_eq!(predicate.filter(&batch).unwrap().num_rows(), 1);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum Predicate {
+    /// Compares a column with a value.
+    Compare {
+        /// The column compared.
+        column: String,
+        /// The comparison.
+        op: Comparison,
+        /// The value compared with.
+        value: Value,
+    },
+    /// The column is null.
+    IsNull(String),
+    /// The column is not null.
+    IsNotNull(String),
+    /// Both predicates hold.
+    And(Box<Predicate>, Box<Predicate>),
+    /// Either predicate holds.
+    Or(Box<Predicate>, Box<Predicate>),
+    /// The predicate does not hold.
+    Not(Box<Predicate>),
+}
+
+impl Predicate {
+    /// Compares `column` with `value`.
+    pub fn compare(column: impl Into<String>, op: Comparison, value: impl Into<Value>) -> Self {
+        Predicate::Compare {
+            column: column.into(),
+            op,
+            value: value.into(),
+        }
+    }
+
+    /// Matches rows where `column` equals `value`.
+    pub fn eq(column: impl Into<String>, value: impl Into<Value>) -> Self {
+        Self::compare(column, Comparison::Eq, value)
+    }
+
+    /// Matches rows where `column` differs from `value`.
+    pub fn not_eq(column: impl Into<String>, value: impl Into<Value>) -> Self {
+        Self::compare(column, Comparison::NotEq, value)
+    }
+
+    /// Matches rows where `column` is less than `value`.
+    pub fn lt(column: impl Into<String>, value: impl Into<Value>) -> Self {
+        Self::compare(column, Comparison::Lt, value)
+    }
+
+    /// Matches rows where `column` is at most `value`.
+    pub fn lt_eq(column: impl Into<String>, value: impl Into<Value>) -> Self {
+        Self::compare(column, Comparison::LtEq, value)
+    }
+
+    /// Matches rows where `column` is greater than `value`.
+    pub fn gt(column: impl Into<String>, value: impl Into<Value>) -> Self {
+        Self::compare(column, Comparison::Gt, value)
+    }
+
+    /// Matches rows where `column` is at least `value`.
+    pub fn gt_eq(column: impl Into<String>, value: impl Into<Value>) -> Self {
+        Self::compare(column, Comparison::GtEq, value)
+    }
+
+    /// Matches rows where `column` lies between `low` and `high`, inclusive.
+    pub fn between(
+        column: impl Into<String>,
+        low: impl Into<Value>,
+        high: impl Into<Value>,
+    ) -> Self {
+        let column = column.into();
+        Self::gt_eq(column.clone(), low).and(Self::lt_eq(column, high))
+    }
+
+    /// Matches rows where `column` is null.
+    pub fn is_null(column: impl Into<String>) -> Self {
+        Predicate::IsNull(column.into())
+    }
+
+    /// Matches rows where `column` is not null.
+    pub fn is_not_null(column: impl Into<String>) -> Self {
+        Predicate::IsNotNull(column.into())
+    }
+
+    /// Matches rows matching both `self` and `other`.
+    pub fn and(self, other: Predicate) -> Self {
+        Predicate::And(Box::new(self), Box::new(other))
+    }
+
+    /// Matches rows matching `self`, `other` or both.
+    pub fn or(self, other: Predicate) -> Self {
+        Predicate::Or(Box::new(self), Box::new(other))
+    }
+
+    /// Returns whether each row of `batch` matches, or null where the
+    /// outcome depends on a null.
+    pub fn evaluate(&self, batch: &RecordBatch) -> Result<BooleanArray, DremioClientError> {
+        Ok(match self {
+            Predicate::Compare { column, op, value } => {
+                let column = column_of(batch, column)?;
+                let value = cast(&value.to_array(), column.data_type())?;
+                let value = Scalar::new(value);
+                let column: &dyn Datum = column;
+                match op {
+                    Comparison::Eq => cmp::eq(column, &value)?,
+                    Comparison::NotEq => cmp::neq(column, &value)?,
+                    Comparison::Lt => cmp::lt(column, &value)?,
+                    Comparison::LtEq => cmp::lt_eq(column, &value)?,
+                    Comparison::Gt => cmp::gt(column, &value)?,
+                    Comparison::GtEq => cmp::gt_eq(column, &value)?,
+                }
+            }
+            Predicate::IsNull(column) => is_null(column_of(batch, column)?)?,
+            Predicate::IsNotNull(column) => is_not_null(column_of(batch, column)?)?,
+            Predicate::And(left, right) => {
+                and_kleene(&left.evaluate(batch)?, &right.evaluate(batch)?)?
+            }
+            Predicate::Or(left, right) => {
+                or_kleene(&left.evaluate(batch)?, &right.evaluate(batch)?)?
+            }
+            Predicate::Not(predicate) => not(&predicate.evaluate(batch)?)?,
+        })
+    }
+
+    /// Returns the rows of `batch` that match.
+    pub fn filter(&self, batch: &RecordBatch) -> Result<RecordBatch, DremioClientError> {
+        Ok(filter_record_batch(batch, &self.evaluate(batch)?)?)
+    }
+}
+
+/// Matches rows not matching the predicate.
+impl std::ops::Not for Predicate {
+    type Output = Predicate;
+
+    fn not(self) -> Predicate {
+        Predicate::Not(Box::new(self))
+    }
+}
+
+fn column_of<'a>(batch: &'a RecordBatch, column: &str) -> Result<&'a ArrayRef, DremioClientError> {
+    batch.column_by_name(column).ok_or_else(|| {
+        ArrowError::SchemaError(format!(
+            "filtered column {:?} is not in the results",
+            column
+        ))
+        .into()
+    })
+}
@@ -0,0 +1,288 @@
+//! Flattening of nested result columns.
+//!
+//! Dremio results may contain `STRUCT`, `LIST` and `MAP` columns, which flat
+//! formats such as CSV cannot represent. [`flatten_batch`] rewrites a batch so
+//! that it contains only primitive columns:
+//!
+//! - struct fields become columns named `parent.child` (the separator is
+//!   configurable), recursively;
+//! - lists are either encoded as JSON text, exploded into one row per element,
+//!   or left untouched, depending on [`ListMode`];
+//! - maps are encoded as JSON text.
+//!
+//! Flattening can also be requested per query with
+//! [`QueryOptions::with_flatten`](crate::QueryOptions::with_flatten).
+
+use crate::DremioClientError;
+use arrow::array::{
+    make_array, Array, ArrayRef, AsArray, FixedSizeListArray, GenericListArray, OffsetSizeTrait,
+    RecordBatch, StringArray, UInt32Array,
+};
+use arrow::buffer::NullBuffer;
+use arrow::compute::take;
+use arrow::datatypes::{DataType, Field, FieldRef, Schema};
+use arrow::error::ArrowError;
+use arrow::json::LineDelimitedWriter;
+use std::sync::Arc;
+
+/// How list columns are flattened.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ListMode {
+    /// Encode each list as a JSON array string.
+    #[default]
+    Json,
+    /// Emit one row per list element, repeating the other columns. Null and
+    /// empty lists produce a single row with a null element.
+    Explode,
+    /// Leave list columns as they are.
+    Keep,
+}
+
+/// Settings for [`flatten_batch`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FlattenOptions {
+    separator: String,
+    lists: ListMode,
+}
+
+impl Default for FlattenOptions {
+    fn default() -> Self {
+        Self {
+            separator: ".".to_string(),
+            lists: ListMode::default(),
+        }
+    }
+}
+
+impl FlattenOptions {
+    /// Creates options that join struct field names with `.` and encode lists
+    /// as JSON.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the separator placed between parent and child field names.
+    pub fn with_separator(mut self, separator: impl Into<String>) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    /// Sets how list columns are flattened.
+    pub fn with_list_mode(mut self, mode: ListMode) -> Self {
+        self.lists = mode;
+        self
+    }
+}
+
+/// Flattens all nested columns of `batch` according to `options`.
+pub fn flatten_batch(
+    batch: &RecordBatch,
+    options: &FlattenOptions,
+) -> Result<RecordBatch, DremioClientError> {
+    let mut batch = flatten_structs(batch, options)?;
+    while let Some(index) = next_list_to_explode(&batch, options) {
+        batch = explode(&batch, index)?;
+        batch = flatten_structs(&batch, options)?;
+    }
+    Ok(batch)
+}
+
+fn next_list_to_explode(batch: &RecordBatch, options: &FlattenOptions) -> Option<usize> {
+    if options.lists != ListMode::Explode {
+        return None;
+    }
+    batch.schema().fields().iter().position(|f| {
+        matches!(
+            f.data_type(),
+            DataType::List(_) | DataType::LargeList(_) | DataType::FixedSizeList(_, _)
+        )
+    })
+}
+
+/// Expands struct columns into dotted top-level columns and JSON-encodes maps
+/// (and lists, in [`ListMode::Json`]).
+fn flatten_structs(
+    batch: &RecordBatch,
+    options: &FlattenOptions,
+) -> Result<RecordBatch, DremioClientError> {
+    let schema = batch.schema();
+    let mut fields = Vec::with_capacity(schema.fields().len());
+    let mut columns = Vec::with_capacity(batch.num_columns());
+    for (field, column) in schema.fields().iter().zip(batch.columns()) {
+        flatten_column(
+            field.name().clone(),
+            field,
+            column.clone(),
+            options,
+            &mut fields,
+            &mut columns,
+        )?;
+    }
+    let schema = Schema::new_with_metadata(fields, schema.metadata().clone());
+    Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+}
+
+fn flatten_column(
+    name: String,
+    field: &FieldRef,
+    column: ArrayRef,
+    options: &FlattenOptions,
+    fields: &mut Vec<FieldRef>,
+    columns: &mut Vec<ArrayRef>,
+) -> Result<(), DremioClientError> {
+    match field.data_type() {
+        DataType::Struct(children) => {
+            let parent = column.as_struct();
+            for (child_field, child) in children.iter().zip(parent.columns()) {
+                let child = with_parent_nulls(child, parent.nulls())?;
+                // Null parents make their children null, even where the
+                // children themselves are not nullable.
+                let child_field = match field.is_nullable() {
+                    true => Arc::new(Field::clone(child_field).with_nullable(true)),
+                    false => child_field.clone(),
+                };
+                let child_name = format!("{}{}{}", name, options.separator, child_field.name());
+                flatten_column(child_name, &child_field, child, options, fields, columns)?;
+            }
+        }
+        DataType::Map(_, _) => push_json(name, &column, fields, columns)?,
+        DataType::List(_) | DataType::LargeList(_) | DataType::FixedSizeList(_, _)
+            if options.lists == ListMode::Json =>
+        {
+            push_json(name, &column, fields, columns)?
+        }
+        _ => {
+            fields.push(Arc::new(Field::clone(field).with_name(name)));
+            columns.push(column);
+        }
+    }
+    Ok(())
+}
+
+/// Marks child values as null wherever the parent struct is null.
+fn with_parent_nulls(
+    child: &ArrayRef,
+    parent: Option<&NullBuffer>,
+) -> Result<ArrayRef, DremioClientError> {
+    if parent.is_none() {
+        return Ok(child.clone());
+    }
+    let nulls = NullBuffer::union(parent, child.nulls());
+    let data = child.to_data().into_builder().nulls(nulls).build()?;
+    Ok(make_array(data))
+}
+
+fn push_json(
+    name: String,
+    column: &ArrayRef,
+    fields: &mut Vec<FieldRef>,
+    columns: &mut Vec<ArrayRef>,
+) -> Result<(), DremioClientError> {
+    fields.push(Arc::new(Field::new(name, DataType::Utf8, true)));
+    columns.push(Arc::new(json_encode(column)?));
+    Ok(())
+}
+
+/// Encodes every value of `column` as a JSON document, keeping nulls.
+pub(crate) fn json_encode(column: &ArrayRef) -> Result<StringArray, DremioClientError> {
+    const KEY: &str = "v";
+    let schema = Schema::new(vec![Field::new(KEY, column.data_type().clone(), true)]);
+    let batch = RecordBatch::try_new(Arc::new(schema), vec![column.clone()])?;
+    let mut writer = LineDelimitedWriter::new(Vec::new());
+    writer.write(&batch)?;
+    writer.finish()?;
+    let output = String::from_utf8_lossy(&writer.into_inner()).into_owned();
+    let prefix = format!("{{\"{}\":", KEY);
+    Ok(output
+        .lines()
+        .map(|line| {
+            line.strip_prefix(&prefix)
+                .and_then(|rest| rest.strip_suffix('}'))
+                .map(str::to_string)
+        })
+        .collect())
+}
+
+/// Emits one row per element of the list column at `index`.
+fn explode(batch: &RecordBatch, index: usize) -> Result<RecordBatch, DremioClientError> {
+    let column = batch.column(index);
+    let (rows, elements, values) = match column.data_type() {
+        DataType::LargeList(_) => explode_indices(column.as_list::<i64>()),
+        DataType::FixedSizeList(_, _) => explode_fixed_indices(column.as_fixed_size_list()),
+        _ => explode_indices(column.as_list::<i32>()),
+    };
+    let element_field = match batch.schema().field(index).data_type() {
+        DataType::List(f) | DataType::LargeList(f) | DataType::FixedSizeList(f, _) => f.clone(),
+        other => {
+            return Err(ArrowError::InvalidArgumentError(format!(
+                "cannot explode column {} of type {}",
+                batch.schema().field(index).name(),
+                other
+            ))
+            .into())
+        }
+    };
+    let schema = batch.schema();
+    let mut fields = Vec::with_capacity(schema.fields().len());
+    let mut columns = Vec::with_capacity(batch.num_columns());
+    for (i, (field, column)) in schema.fields().iter().zip(batch.columns()).enumerate() {
+        if i == index {
+            fields.push(Arc::new(
+                Field::clone(&element_field)
+                    .with_name(field.name())
+                    .with_nullable(true),
+            ));
+            columns.push(take(values.as_ref(), &elements, None)?);
+        } else {
+            fields.push(field.clone());
+            columns.push(take(column.as_ref(), &rows, None)?);
+        }
+    }
+    let schema = Schema::new_with_metadata(fields, schema.metadata().clone());
+    Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+}
+
+/// Returns, for each output row, the source row and the element index into the
+/// list values (null for null or empty lists), plus the list values array.
+fn explode_indices<O: OffsetSizeTrait>(
+    list: &GenericListArray<O>,
+) -> (UInt32Array, UInt32Array, ArrayRef) {
+    let offsets = list.value_offsets();
+    let (rows, elements) = element_indices(list, |row| {
+        (offsets[row].as_usize(), offsets[row + 1].as_usize())
+    });
+    (rows, elements, list.values().clone())
+}
+
+/// Like [`explode_indices`], for lists of a fixed size.
+fn explode_fixed_indices(list: &FixedSizeListArray) -> (UInt32Array, UInt32Array, ArrayRef) {
+    let size = list.value_length() as usize;
+    let (rows, elements) = element_indices(list, |row| {
+        let start = list.value_offset(row) as usize;
+        (start, start + size)
+    });
+    (rows, elements, list.values().clone())
+}
+
+/// Returns the source row and element index of each output row, given the
+/// range of elements of every row of `list`.
+fn element_indices(
+    list: &dyn Array,
+    range: impl Fn(usize) -> (usize, usize),
+) -> (UInt32Array, UInt32Array) {
+    let mut rows = Vec::new();
+    let mut elements = Vec::new();
+    for row in 0..list.len() {
+        let (start, end) = range(row);
+        if list.is_null(row) || start == end {
+            rows.push(row as u32);
+            elements.push(None);
+        } else {
+            for element in start..end {
+                rows.push(row as u32);
+                elements.push(Some(element as u32));
+            }
+        }
+    }
+    (UInt32Array::from(rows), UInt32Array::from(elements))
+}
@@ -0,0 +1,92 @@
+//! Writing local Arrow data into Dremio tables.
+//!
+//! [`Client::ingest`](crate::Client::ingest) sends a stream of batches to a
+//! table with Flight SQL bulk ingestion (`DoPut` with
+//! `CommandStatementIngest`), without going through files. The target must be
+//! a source Dremio can write to, such as an Iceberg catalog.
+
+use crate::{sql, DremioClientError};
+use arrow::error::ArrowError;
+use arrow_flight::sql::{
+    CommandStatementIngest, TableDefinitionOptions, TableExistsOption, TableNotExistOption,
+};
+use std::collections::HashMap;
+
+/// How [`Client::ingest`](crate::Client::ingest) treats the target table.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IngestMode {
+    /// Create the table, failing if it already exists.
+    #[default]
+    Create,
+    /// Add the rows to the table, creating it if it does not exist.
+    Append,
+    /// Replace the rows of the table, creating it if it does not exist.
+    Replace,
+}
+
+/// Settings for [`Client::ingest`](crate::Client::ingest).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IngestOptions {
+    mode: IngestMode,
+    temporary: bool,
+    properties: HashMap<String, String>,
+}
+
+impl IngestOptions {
+    /// Creates options that create the table, failing if it exists.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how the target table is treated.
+    pub fn with_mode(mut self, mode: IngestMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Creates a temporary table, for servers that support them.
+    pub fn with_temporary(mut self, temporary: bool) -> Self {
+        self.temporary = temporary;
+        self
+    }
+
+    /// Passes the backend-specific option `key` to the server, such as a
+    /// table property.
+    pub fn with_property(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.properties.insert(key.into(), value.into());
+        self
+    }
+
+    /// Returns how the target table is treated.
+    pub fn mode(&self) -> IngestMode {
+        self.mode
+    }
+
+    /// Builds the ingestion command for the table at `path`, whose last
+    /// element names the table and the others its schema. The schema is sent
+    /// as a quoted path, so components may contain dots.
+    pub(crate) fn command(
+        &self,
+        path: &[String],
+    ) -> Result<CommandStatementIngest, DremioClientError> {
+        let Some((table, schema)) = path.split_last() else {
+            return Err(ArrowError::InvalidArgumentError("empty table path".to_string()).into());
+        };
+        let (if_not_exist, if_exists) = match self.mode {
+            IngestMode::Create => (TableNotExistOption::Create, TableExistsOption::Fail),
+            IngestMode::Append => (TableNotExistOption::Create, TableExistsOption::Append),
+            IngestMode::Replace => (TableNotExistOption::Create, TableExistsOption::Replace),
+        };
+        Ok(CommandStatementIngest {
+            table_definition_options: Some(TableDefinitionOptions {
+                if_not_exist: if_not_exist.into(),
+                if_exists: if_exists.into(),
+            }),
+            table: table.clone(),
+            schema: (!schema.is_empty()).then(|| sql::quote_path(schema)),
+            temporary: self.temporary,
+            options: self.properties.clone(),
+            ..Default::default()
+        })
+    }
+}
@@ -13,7 +13,8 @@
 //! - Authenticate with username and password.
 //! - Execute SQL queries.
 //! - Retrieve query results as `Vec<RecordBatch>`.
-//! - Write query results to Parquet files.
+//! - Write query results to Parquet files (`parquet` feature, enabled by default).
+//! - Emit `tracing` spans for connections, queries and exports (`tracing` feature).
 //!
 //! # Example
 //!
@@ -31,20 +32,90 @@
 //!     }
 //!
 //!     // Write query results to a Parquet file
+//!     #[cfg(feature = "parquet")]
 //!     client.write_parquet("SELECT * FROM sys.options", "sys_options.parquet").await?;
 //!
 //!     Ok(())
 //! }
 //! ```
 
+pub mod bench;
+pub mod blob;
+mod builder;
+pub mod cache;
+mod cancel;
+pub mod checks;
+#[cfg(feature = "config")]
+pub mod config;
+pub mod convert;
+pub mod copy;
+pub mod diff;
+pub mod dns;
+mod executor;
+pub mod export;
+pub mod filter;
+pub mod flatten;
+pub mod ingest;
+mod lossy;
+pub mod mask;
+pub mod metadata;
+pub mod migrate;
+mod options;
+pub mod phases;
+pub mod prepared;
+pub mod profile;
+pub mod render;
+#[cfg(feature = "rest")]
+pub mod rest;
+pub mod retry;
+#[cfg(feature = "serde")]
+pub mod rows;
+#[cfg(feature = "scheduler")]
+pub mod scheduler;
+pub mod schema;
+pub mod schema_diff;
+mod session;
+pub mod snapshot;
+pub mod sql;
+pub mod sql_error;
+mod stale;
+mod status;
+mod stream;
+#[cfg(feature = "tracing")]
+mod telemetry;
+pub mod templates;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod warmup;
+pub mod watch;
+
+pub use builder::{Auth, ClientBuilder, ProbeAttempt, ProbeFailure};
+pub use cancel::QueryHandle;
+pub use executor::DremioExecutor;
+pub use options::{DecimalMode, EndpointOrder, IntervalMode, QueryOptions, TimestampMode};
+pub use session::SessionOptions;
+pub use stream::{QueryMetrics, QueryStats, QueryStream};
+
 use arrow::array::RecordBatch;
+use arrow::datatypes::SchemaRef;
 use arrow::error::ArrowError;
+use arrow_flight::encode::FlightDataEncoderBuilder;
 use arrow_flight::error::FlightError;
 use arrow_flight::sql::client::FlightSqlServiceClient;
-use futures::stream::StreamExt;
-use parquet::arrow::ArrowWriter;
+use arrow_flight::sql::{CommandGetDbSchemas, CommandGetTables, SqlInfo};
+use arrow_flight::{FlightClient, FlightDescriptor, FlightInfo, PutResult};
+use cache::{CacheKey, ResultCache};
+use futures::{Stream, StreamExt, TryStreamExt};
+use options::{apply_deadline, apply_query_headers, Deadline};
+#[cfg(feature = "parquet")]
 use parquet::errors::ParquetError;
+#[cfg(feature = "parquet")]
+use parquet::file::properties::WriterProperties;
+use std::collections::HashMap;
+use std::future::Future;
 use std::io::Error as IoError;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 use thiserror::Error;
 use tonic::transport::{Channel, Endpoint, Error as TonicError};
 
@@ -64,17 +135,267 @@ pub enum DremioClientError {
     #[error("IO Error: {0}")]
     IoError(#[from] IoError),
     /// An error originating from the `parquet` file format library.
+    #[cfg(feature = "parquet")]
     #[error("Parquet Error: {0}")]
     ParquetError(#[from] ParquetError),
+    /// The result schema did not match the schema expected by the caller.
+    #[error("Schema Mismatch: {0}")]
+    SchemaMismatch(#[from] schema::SchemaMismatch),
+    /// The results violated a required data quality check.
+    #[error("Check Failed: {0}")]
+    CheckFailed(#[from] checks::Violation),
+    /// The query did not complete within its configured timeout.
+    #[error("Timeout Error: query exceeded its timeout of {0:?}")]
+    TimeoutError(Duration),
+    /// An error originating from the HTTP client used for the REST API.
+    #[cfg(feature = "rest")]
+    #[error("HTTP Error: {0}")]
+    HttpError(#[from] reqwest::Error),
+    /// An error originating from the Docker test harness.
+    #[cfg(feature = "testcontainers")]
+    #[error("Container Error: {0}")]
+    ContainerError(#[from] testcontainers::TestcontainersError),
+    /// A row could not be deserialized into the requested type.
+    #[cfg(feature = "serde")]
+    #[error("Row Error: {0}")]
+    RowError(#[from] rows::RowError),
+    /// A configuration file or profile was missing or invalid.
+    #[error("Config Error: {0}")]
+    ConfigError(String),
+    /// No scheme and port probed for a bare host name reached the coordinator.
+    #[error("Probe Failed: {0}")]
+    ProbeFailed(builder::ProbeFailure),
+    /// The REST API returned an unsuccessful response.
+    #[error("REST Error: {status}: {message}")]
+    RestError {
+        /// HTTP status code of the response.
+        status: u16,
+        /// Error message returned by Dremio.
+        message: String,
+    },
+    /// A job submitted through the REST API failed or was canceled.
+    #[error("Job Failed: {job_id}: {message}")]
+    JobFailed {
+        /// The ID of the job.
+        job_id: String,
+        /// The error message or cancellation reason reported by Dremio.
+        message: String,
+    },
+    /// The server rejected the credentials or the session token.
+    #[error("Authentication Failed: {0}")]
+    AuthenticationFailed(String),
+    /// Dremio could not parse, validate, plan or run the query.
+    #[error("Query Failed: {message}")]
+    QueryFailed {
+        /// The gRPC status code returned by Dremio.
+        code: tonic::Code,
+        /// The error message returned by Dremio.
+        message: String,
+        /// The ID of the Dremio job, when the message gives it.
+        query_id: Option<String>,
+    },
+    /// An endpoint of the query's `FlightInfo` had no ticket to fetch its
+    /// results with.
+    #[error("Missing Ticket: endpoint {0} of the query has no ticket")]
+    MissingTicket(usize),
+    /// The query returned no record batches, where at least one was needed.
+    #[error("Empty Result: the query returned no record batches")]
+    EmptyResult,
+    /// An error annotated with the query it occurred in.
+    #[error("Query Error: {phase} {query:?}: {source}")]
+    Query {
+        /// The SQL text of the query.
+        query: String,
+        /// The stage of the query the error occurred in.
+        phase: QueryPhase,
+        /// The underlying error.
+        #[source]
+        source: Box<DremioClientError>,
+    },
+}
+
+/// A `Result` whose error type defaults to [`DremioClientError`].
+pub type Result<T, E = DremioClientError> = std::result::Result<T, E>;
+
+/// The stage of a query in which an error occurred.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum QueryPhase {
+    /// Submitting the query and planning it, up to the announcement of its
+    /// endpoints.
+    Execute,
+    /// Fetching and converting result batches.
+    Fetch,
+    /// Writing results to a file.
+    Write,
+}
+
+impl std::fmt::Display for QueryPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            QueryPhase::Execute => "executing",
+            QueryPhase::Fetch => "fetching",
+            QueryPhase::Write => "writing",
+        })
+    }
+}
+
+impl DremioClientError {
+    /// Annotates this error with the query and phase it occurred in.
+    ///
+    /// Errors that already carry a query are returned unchanged, so the
+    /// innermost, most specific context wins.
+    pub fn in_query(self, phase: QueryPhase, query: &str) -> Self {
+        match self {
+            DremioClientError::Query { .. } => self,
+            source => DremioClientError::Query {
+                query: query.to_string(),
+                phase,
+                source: Box::new(source),
+            },
+        }
+    }
+
+    /// Returns the query this error occurred in, if known.
+    pub fn query(&self) -> Option<&str> {
+        match self {
+            DremioClientError::Query { query, .. } => Some(query),
+            _ => None,
+        }
+    }
+
+    /// Returns the phase of the query this error occurred in, if known.
+    pub fn phase(&self) -> Option<QueryPhase> {
+        match self {
+            DremioClientError::Query { phase, .. } => Some(*phase),
+            _ => None,
+        }
+    }
+
+    /// Returns the error without any query annotation, for matching on the
+    /// kind of failure.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::{Client, DremioClientError};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   if let Err(e) = client.get_record_batches("SELECT * FROM sys.options").await {
+    ///     if let DremioClientError::TimeoutError(_) = e.root() {
+    ///       eprintln!("{:?} timed out while {}", e.query(), e.phase().unwrap());
+    ///     }
+    ///   }
+    /// }
+    /// ```
+    pub fn root(&self) -> &DremioClientError {
+        match self {
+            DremioClientError::Query { source, .. } => source.root(),
+            _ => self,
+        }
+    }
+
+    /// Returns the path of the dataset this error blames on missing or
+    /// outdated metadata, such as "Table 'lake.orders' not found" right after
+    /// new files have landed.
+    ///
+    /// Refreshing the dataset's metadata may make the query succeed; see
+    /// [`Client::with_stale_metadata_retry`].
+    pub fn stale_dataset(&self) -> Option<Vec<String>> {
+        stale::stale_dataset(&self.server_message()?)
+    }
+
+    /// Returns where in the SQL text the error occurred, for syntax and
+    /// validation errors whose message gives a line and column.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let sql = "SELECT * FRM sys.options";
+    ///   if let Err(e) = client.get_record_batches(sql).await {
+    ///     if let Some(position) = e.sql_position() {
+    ///       eprintln!("error at {}:\n{}", position, position.render_snippet(sql));
+    ///     }
+    ///   }
+    /// }
+    /// ```
+    pub fn sql_position(&self) -> Option<sql_error::SqlErrorPosition> {
+        sql_error::SqlErrorPosition::parse(&self.server_message()?)
+    }
+
+    /// Returns the message of the error returned by the server, if any.
+    fn server_message(&self) -> Option<String> {
+        match self.root() {
+            DremioClientError::FlightError(FlightError::Tonic(status)) => {
+                Some(status.message().to_string())
+            }
+            DremioClientError::ArrowError(ArrowError::IpcError(status)) => {
+                stale::status_message(status)
+            }
+            DremioClientError::JobFailed { message, .. }
+            | DremioClientError::QueryFailed { message, .. }
+            | DremioClientError::AuthenticationFailed(message) => Some(message.clone()),
+            _ => None,
+        }
+    }
 }
 
+/// The location of endpoints served over the connection that returned them.
+const REUSE_CONNECTION: &str = "arrow-flight-reuse-connection:";
+
 /// A client for interacting with Dremio's Flight SQL service.
 ///
 /// This client wraps the `FlightSqlServiceClient` and provides a simplified
 /// interface for common operations such as executing SQL queries and
 /// retrieving data as Arrow `RecordBatch`es, or writing them to Parquet files.
+///
+/// Cloning a client is cheap: the clones multiplex their requests over the
+/// same connection and share its session, result cache and schema cache, so
+/// concurrent tasks can each run queries on their own clone without another
+/// handshake. Settings changed afterwards, such as the default query options
+/// or a [`reconfigure`](Self::reconfigure)d connection, only apply to the
+/// clone they are changed on.
+///
+/// # Example
+///
+/// ```no_run
+/// use dremio_rs::Client;
+///
+/// #[tokio::main]
+/// async fn main() {
+///   let client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+///   let tasks: Vec<_> = ["sys.options", "sys.nodes"]
+///     .into_iter()
+///     .map(|table| {
+///       let mut client = client.clone();
+///       tokio::spawn(async move { client.get_record_batches(&format!("SELECT * FROM {}", table)).await })
+///     })
+///     .collect();
+///   for task in tasks {
+///     let batches = task.await.unwrap().unwrap();
+///     println!("{} batches", batches.len());
+///   }
+/// }
+/// ```
+#[derive(Clone)]
 pub struct Client {
     flight_sql_service_client: FlightSqlServiceClient<Channel>,
+    cache: Option<Arc<dyn ResultCache>>,
+    schema_cache: Option<cache::SchemaCache>,
+    query_options: QueryOptions,
+    stale_metadata_retry: bool,
+    headers: Arc<[(String, String)]>,
+    settings: Arc<ClientBuilder>,
+    /// The session token obtained by re-authenticating, which replaces the
+    /// one `flight_sql_service_client` was created with.
+    session_token: Arc<RwLock<Option<String>>>,
 }
 
 impl Client {
@@ -103,12 +424,313 @@ impl Client {
     /// }
     /// ```
     pub async fn new(url: &str, user: &str, pass: &str) -> Result<Self, DremioClientError> {
-        let mut client =
-            FlightSqlServiceClient::new(Endpoint::from_shared(url.to_string())?.connect().await?);
-        client.handshake(user, pass).await?;
-        Ok(Self {
-            flight_sql_service_client: client,
-        })
+        ClientBuilder::new(url)
+            .with_credentials(user, pass)
+            .connect()
+            .await
+    }
+
+    /// Creates a new `Client` authenticating with a personal access token.
+    ///
+    /// The token is sent as a bearer token with every request rather than
+    /// exchanged in a handshake, as Dremio Cloud expects.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the Dremio coordinator (e.g., "grpc+tls://data.dremio.cloud:443").
+    /// * `token` - The personal access token.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(Self)` if the connection is successful.
+    /// - `Err(DremioClientError)` if an error occurs while connecting. An
+    ///   invalid token is only reported by the first request.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///    let token = std::env::var("DREMIO_PAT").unwrap();
+    ///    let mut client = Client::new_with_token("http://localhost:32010", &token).await.unwrap();
+    /// }
+    /// ```
+    pub async fn new_with_token(url: &str, token: &str) -> Result<Self, DremioClientError> {
+        ClientBuilder::new(url).with_token(token).connect().await
+    }
+
+    /// Returns a builder for a client of the coordinator at `url`, for
+    /// connections that need more settings than [`new`](Self::new) takes.
+    pub fn builder(url: impl Into<String>) -> ClientBuilder {
+        ClientBuilder::new(url)
+    }
+
+    /// Connects with new settings, such as rotated credentials or another
+    /// coordinator address, and sends all later queries over the new
+    /// connection.
+    ///
+    /// Queries already running, including open [`QueryStream`]s, finish on
+    /// the old connection, which is closed once the last of them is done. The
+    /// result cache, the schema cache and the default query options are
+    /// kept, but cached entries are keyed on the cluster and identity they
+    /// were fetched with, so results cached before reconfiguring are only
+    /// served again if both are unchanged. If the new connection cannot be
+    /// established or authenticated, the client keeps using the old one.
+    ///
+    /// # Arguments
+    ///
+    /// * `builder` - The settings to connect with.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(())` once the new connection is in use.
+    /// - `Err(DremioClientError)` if connecting or authenticating failed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let mut client = Client::new("http://localhost:32010", "dremio", "old-token").await.unwrap();
+    ///   // Later, after the token was rotated:
+    ///   client
+    ///     .reconfigure(Client::builder("http://localhost:32010").with_credentials("dremio", "new-token"))
+    ///     .await
+    ///     .unwrap();
+    /// }
+    /// ```
+    pub async fn reconfigure(&mut self, builder: ClientBuilder) -> Result<(), DremioClientError> {
+        let fresh = builder.connect().await?;
+        self.flight_sql_service_client = fresh.flight_sql_service_client;
+        self.headers = fresh.headers;
+        self.settings = fresh.settings;
+        self.session_token = fresh.session_token;
+        Ok(())
+    }
+
+    /// Attaches a result cache to this client.
+    ///
+    /// Once set, `get_record_batches` (and methods built on it) first look up the
+    /// normalized query text in the cache and only contact Dremio on a miss.
+    ///
+    /// # Arguments
+    ///
+    /// * `cache` - The cache to consult, e.g. a [`cache::MemoryCache`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    /// use dremio_rs::cache::MemoryCache;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///    let client = Client::new("http://localhost:32010", "dremio", "dremio123")
+    ///        .await
+    ///        .unwrap()
+    ///        .with_result_cache(MemoryCache::new());
+    /// }
+    /// ```
+    pub fn with_result_cache<C: ResultCache + 'static>(mut self, cache: C) -> Self {
+        self.cache = Some(Arc::new(cache));
+        self
+    }
+
+    /// Sets the default options used by methods that do not take explicit
+    /// `QueryOptions`, such as `get_record_batches` and `write_parquet`.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - The options applied to every query unless overridden.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::{Client, QueryOptions};
+    /// use dremio_rs::schema::ColumnNaming;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///    let client = Client::new("http://localhost:32010", "dremio", "dremio123")
+    ///        .await
+    ///        .unwrap()
+    ///        .with_query_options(QueryOptions::new().with_column_naming(ColumnNaming::SnakeCase));
+    /// }
+    /// ```
+    pub fn with_query_options(mut self, options: QueryOptions) -> Self {
+        self.query_options = options;
+        self
+    }
+
+    /// Returns the default options applied to queries run by this client.
+    pub fn query_options(&self) -> &QueryOptions {
+        &self.query_options
+    }
+
+    /// Refreshes metadata and retries once when a query fails because a
+    /// dataset's metadata is missing or out of date.
+    ///
+    /// When enabled, a failure naming a dataset (see
+    /// [`DremioClientError::stale_dataset`]) triggers
+    /// `ALTER TABLE <dataset> REFRESH METADATA`, after which the query is run
+    /// again. If the refresh fails, the original error is returned. Streams
+    /// returned by `execute_stream_with_options` are only retried for errors
+    /// raised before the stream is handed out. Disabled by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether to refresh metadata and retry.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///    let mut client = Client::new("http://localhost:32010", "dremio", "dremio123")
+    ///        .await
+    ///        .unwrap()
+    ///        .with_stale_metadata_retry(true);
+    ///    client.get_record_batches("SELECT * FROM lake.orders").await.unwrap();
+    /// }
+    /// ```
+    pub fn with_stale_metadata_retry(mut self, enabled: bool) -> Self {
+        self.stale_metadata_retry = enabled;
+        self
+    }
+
+    /// Returns the result cache attached to this client, if any.
+    pub fn result_cache(&self) -> Option<&Arc<dyn ResultCache>> {
+        self.cache.as_ref()
+    }
+
+    /// Attaches a schema cache to this client.
+    ///
+    /// Once set, [`get_schema`](Self::get_schema) only asks Dremio for the
+    /// schema of queries it has not seen within the cache's TTL.
+    ///
+    /// # Arguments
+    ///
+    /// * `cache` - The cache to consult. Clones share their entries, so one
+    ///   cache can serve several clients.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use dremio_rs::Client;
+    /// use dremio_rs::cache::SchemaCache;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///    let client = Client::new("http://localhost:32010", "dremio", "dremio123")
+    ///        .await
+    ///        .unwrap()
+    ///        .with_schema_cache(SchemaCache::new().with_ttl(Duration::from_secs(600)));
+    /// }
+    /// ```
+    pub fn with_schema_cache(mut self, cache: cache::SchemaCache) -> Self {
+        self.schema_cache = Some(cache);
+        self
+    }
+
+    /// Returns the schema cache attached to this client, if any.
+    pub fn schema_cache(&self) -> Option<&cache::SchemaCache> {
+        self.schema_cache.as_ref()
+    }
+
+    /// Returns the schema of the results of a SQL query without fetching any
+    /// rows.
+    ///
+    /// A `SELECT` is wrapped in a `LIMIT 0` subquery, so Dremio plans it but
+    /// returns no data, and the schema is read from the `FlightInfo`. Other
+    /// statements, and queries for which Dremio announces no schema, are
+    /// prepared instead, which plans them without running them. The schema
+    /// reflects the client's default options, such as timestamp conversions
+    /// and column selections. With a schema cache attached, repeated calls for
+    /// the same query are answered locally.
+    ///
+    /// The schema is known even for queries that return no rows, for example
+    /// to create the writer of a file before any results arrive.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The SQL query string to describe.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(SchemaRef)` with the schema of the results.
+    /// - `Err(DremioClientError)` if the query cannot be planned.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let schema = client.get_schema("SELECT * FROM lake.orders").await.unwrap();
+    ///   for field in schema.fields() {
+    ///     println!("{}: {}", field.name(), field.data_type());
+    ///   }
+    /// }
+    /// ```
+    pub async fn get_schema(&self, query: &str) -> Result<SchemaRef, DremioClientError> {
+        self.get_schema_with_options(query, &self.query_options)
+            .await
+    }
+
+    /// Returns the schema of the results of a SQL query under `options`, see
+    /// [`get_schema`](Self::get_schema).
+    async fn get_schema_with_options(
+        &self,
+        query: &str,
+        options: &QueryOptions,
+    ) -> Result<SchemaRef, DremioClientError> {
+        let key = self.cache_key(query, options);
+        if let Some(schema) = self.schema_cache.as_ref().and_then(|c| c.get(&key)) {
+            return Ok(schema);
+        }
+        let fetch = async {
+            let mut client = self.query_client(options);
+            apply_deadline(&mut client, options.deadline())?;
+            let limited = sql::limit_query(query, 0);
+            let announced = if limited != query {
+                let info = client.execute(limited, None).await?;
+                // Empty when Dremio announces no schema.
+                (!info.schema.is_empty())
+                    .then(|| info.try_decode_schema())
+                    .transpose()?
+            } else {
+                None
+            };
+            let schema = match announced {
+                Some(schema) => schema,
+                None => {
+                    let statement = client.prepare(query.to_string(), None).await?;
+                    let schema = statement.dataset_schema()?.clone();
+                    statement.close().await?;
+                    schema
+                }
+            };
+            options.transformed_schema(Arc::new(schema))
+        };
+        let schema = fetch
+            .await
+            .map_err(|e| status::classify(e).in_query(QueryPhase::Execute, query))?;
+        if let Some(cache) = &self.schema_cache {
+            cache.put(key, schema.clone());
+        }
+        Ok(schema)
     }
 
     /// Executes a SQL query against Dremio and retrieves the results as a vector of `RecordBatch`es.
@@ -141,36 +763,66 @@ impl Client {
         &mut self,
         query: &str,
     ) -> Result<Vec<RecordBatch>, DremioClientError> {
-        let flight_info = self
-            .flight_sql_service_client
-            .execute(query.to_string(), None)
-            .await?;
-        let ticket = flight_info.endpoint[0]
-            .ticket
-            .clone()
-            .expect("Missing ticket");
-        let mut stream = self.flight_sql_service_client.do_get(ticket).await?;
-        let mut batches = Vec::new();
+        let options = self.query_options.clone();
+        self.get_record_batches_with_options(query, &options).await
+    }
 
-        while let Some(batch) = stream.next().await {
-            batches.push(batch?);
-        }
-        Ok(batches)
+    /// Executes a SQL query with per-query options and retrieves the results as a
+    /// vector of `RecordBatch`es.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The SQL query string to execute.
+    /// * `options` - Settings applied to this query only, such as a timeout.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(Vec<RecordBatch>)` containing the query results if successful.
+    /// - `Err(DremioClientError)` if an error occurs during query execution or data retrieval.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use dremio_rs::{Client, QueryOptions};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let options = QueryOptions::new().with_timeout(Duration::from_secs(10));
+    ///   let batches = client
+    ///     .get_record_batches_with_options("SELECT * FROM sys.options", &options)
+    ///     .await
+    ///     .unwrap();
+    /// }
+    /// ```
+    pub async fn get_record_batches_with_options(
+        &mut self,
+        query: &str,
+        options: &QueryOptions,
+    ) -> Result<Vec<RecordBatch>, DremioClientError> {
+        Ok(self.fetch_batches(query, options).await?.1)
     }
 
-    /// Executes a SQL query and writes the results directly to a Parquet file.
+    /// Executes a SQL query and retrieves its results along with their
+    /// schema.
+    ///
+    /// The schema is known even when the query returns no batches, so code
+    /// writing or validating the results needs no special case for empty
+    /// ones. It is the schema of the batches when there are any, and
+    /// otherwise the one Dremio announced for the query.
     ///
     /// # Arguments
     ///
     /// * `query` - The SQL query string to execute.
-    /// * `path` - The file path where the Parquet file will be written.
     ///
     /// # Returns
     ///
     /// A `Result` which is:
-    /// - `Ok(())` if the Parquet file is successfully written.
-    /// - `Err(DremioClientError)` if an error occurs during query execution,
-    ///   data retrieval, or file writing.
+    /// - `Ok((SchemaRef, Vec<RecordBatch>))` containing the schema and the
+    ///   query results if successful.
+    /// - `Err(DremioClientError)` if an error occurs during query execution or data retrieval.
     ///
     /// # Example
     ///
@@ -179,34 +831,2174 @@ impl Client {
     ///
     /// #[tokio::main]
     /// async fn main() {
-    ///  let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
-    ///  client.write_parquet("SELECT * FROM sys.options", "my_table.parquet").await.unwrap();
+    ///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let (schema, batches) = client
+    ///     .get_record_batches_with_schema("SELECT * FROM sys.options WHERE 1 = 0")
+    ///     .await
+    ///     .unwrap();
+    ///   println!("{} columns, {} batches", schema.fields().len(), batches.len());
     /// }
     /// ```
-    pub async fn write_parquet(
+    pub async fn get_record_batches_with_schema(
         &mut self,
         query: &str,
-        path: &str,
-    ) -> Result<(), DremioClientError> {
-        let batches = self.get_record_batches(query).await?;
-        let file = std::fs::File::create(path)?;
-        let mut writer = ArrowWriter::try_new(file, batches[0].schema(), None)?;
-        for batch in batches {
-            writer.write(&batch)?;
-        }
-        writer.close()?;
-        Ok(())
+    ) -> Result<(SchemaRef, Vec<RecordBatch>), DremioClientError> {
+        let options = self.query_options.clone();
+        let (announced, batches) = self.fetch_batches(query, &options).await?;
+        let schema = match (batches.first(), announced) {
+            (Some(batch), _) => batch.schema(),
+            (None, Some(schema)) => schema,
+            // Cached results without batches have no schema.
+            (None, None) => self.get_schema(query).await?,
+        };
+        Ok((schema, batches))
     }
 
-    /// Returns a shared reference to the underlying `FlightSqlServiceClient`.
+    /// Executes a DML or DDL statement, such as `INSERT`, `DELETE` or
+    /// `CREATE TABLE ... AS`, and returns the number of rows it affected.
     ///
-    /// This can be used to access more advanced Flight SQL operations not directly
-    /// exposed by the `Client` interface.
+    /// The statement is sent with the Flight SQL update command, so no result
+    /// batches are transferred. It uses the client's default options, such
+    /// as the timeout and impersonated user.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The SQL statement to execute.
     ///
     /// # Returns
     ///
-    /// A reference to the `FlightSqlServiceClient<Channel>`.
-    pub fn inner(&self) -> &FlightSqlServiceClient<Channel> {
-        &self.flight_sql_service_client
+    /// A `Result` which is:
+    /// - `Ok(i64)` with the number of affected rows, as reported by Dremio.
+    /// - `Err(DremioClientError)` if the statement failed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let rows = client
+    ///     .execute_update("DELETE FROM lake.orders WHERE order_date < DATE '2020-01-01'")
+    ///     .await
+    ///     .unwrap();
+    ///   println!("deleted {} rows", rows);
+    /// }
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip(self), fields(query_id), ret, err)
+    )]
+    pub async fn execute_update(&self, query: &str) -> Result<i64, DremioClientError> {
+        let options = &self.query_options;
+        let mut client = self.query_client(options);
+        let update = async {
+            apply_deadline(&mut client, options.deadline())?;
+            Ok(client.execute_update(query.to_string(), None).await?)
+        };
+        update.await.map_err(|e: DremioClientError| {
+            let e = status::classify(e);
+            #[cfg(feature = "tracing")]
+            telemetry::record_query_id(&e);
+            e.in_query(QueryPhase::Execute, query)
+        })
+    }
+
+    /// Writes a stream of batches into a table with Flight SQL bulk
+    /// ingestion.
+    ///
+    /// Batches are sent as the stream yields them, without collecting them
+    /// first. The table is created from the schema of the first batch unless
+    /// `options` say to append to or replace an existing one. A stream
+    /// without any batches leaves the table untouched. The client's default
+    /// options apply, such as the timeout and impersonated user.
+    ///
+    /// # Arguments
+    ///
+    /// * `table` - The path of the table, one element per component; the last
+    ///   one names the table and the others its schema.
+    /// * `batches` - The batches to write, all with the same schema.
+    /// * `options` - Whether to create, append to or replace the table.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(i64)` with the number of rows written, as reported by Dremio.
+    /// - `Err(DremioClientError)` if the table path is empty or the
+    ///   ingestion failed, for example because the table already exists.
+    ///   Errors returned by the server are classified like those of queries,
+    ///   with the quoted table path in place of the query text.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use arrow::array::{Int64Array, RecordBatch};
+    /// use dremio_rs::ingest::{IngestMode, IngestOptions};
+    /// use dremio_rs::Client;
+    /// use std::sync::Arc;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let batch = RecordBatch::try_from_iter([("id", Arc::new(Int64Array::from(vec![1, 2])) as _)]).unwrap();
+    ///   let options = IngestOptions::new().with_mode(IngestMode::Append);
+    ///   let rows = client
+    ///     .ingest(["lake", "orders"], futures::stream::iter([batch]), &options)
+    ///     .await
+    ///     .unwrap();
+    ///   println!("wrote {} rows", rows);
+    /// }
+    /// ```
+    pub async fn ingest<I, S, B>(
+        &self,
+        table: I,
+        batches: B,
+        options: &ingest::IngestOptions,
+    ) -> Result<i64, DremioClientError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+        B: Stream<Item = RecordBatch> + Send + 'static,
+    {
+        let table: Vec<String> = table.into_iter().map(|s| s.as_ref().to_string()).collect();
+        let command = options.command(&table)?;
+        let mut batches = batches.boxed();
+        let Some(first) = batches.next().await else {
+            return Ok(0);
+        };
+        let mut client = self.query_client(&self.query_options);
+        let batches = futures::stream::iter([first]).chain(batches).map(Ok);
+        let ingest = async {
+            apply_deadline(&mut client, self.query_options.deadline())?;
+            Ok(client.execute_ingest(command, batches).await?)
+        };
+        ingest.await.map_err(|e: DremioClientError| {
+            status::classify(e).in_query(QueryPhase::Execute, &sql::quote_path(&table))
+        })
+    }
+
+    /// Prepares a SQL query with `?` placeholders for parameters, to be
+    /// executed once or many times with values bound to them.
+    ///
+    /// Binding values instead of splicing them into the SQL text rules out
+    /// SQL injection, and Dremio plans the query only once.
+    ///
+    /// # Arguments
+    ///
+    /// * `sql` - The SQL query string, with `?` where parameters go.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(PreparedStatement)` once Dremio has planned the query.
+    /// - `Err(DremioClientError)` if the query could not be prepared.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use arrow::array::{ArrayRef, StringArray};
+    /// use dremio_rs::Client;
+    /// use std::sync::Arc;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let mut statement = client.prepare("SELECT * FROM sys.options WHERE name = ?").await.unwrap();
+    ///   for name in ["planner.slice_target", "exec.queue.enable"] {
+    ///     let value: ArrayRef = Arc::new(StringArray::from(vec![name]));
+    ///     statement.bind_values([value]).unwrap();
+    ///     let batches = statement.execute().await.unwrap().collect_batches().await.unwrap();
+    ///     println!("{}: {} batches", name, batches.len());
+    ///   }
+    ///   statement.close().await.unwrap();
+    /// }
+    /// ```
+    pub async fn prepare(
+        &self,
+        sql: &str,
+    ) -> Result<prepared::PreparedStatement<'_>, DremioClientError> {
+        prepared::PreparedStatement::prepare(self, sql, self.query_options.clone()).await
+    }
+
+    /// Logs in to Dremio's REST API with the credentials of this client, for
+    /// what Flight SQL does not offer, such as job profiles, catalog
+    /// management and reflection refreshes.
+    ///
+    /// The REST API is expected on port 9047 of the coordinator's host unless
+    /// [`ClientBuilder::with_rest_url`] says otherwise.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(RestClient)` logged in as the user of this client, or with its
+    ///   personal access token.
+    /// - `Err(DremioClientError)` if the client has no credentials or the
+    ///   login fails.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let rest = client.connect_rest().await.unwrap();
+    ///   rest.catalog().create_view(&["marts", "orders"], "SELECT * FROM lake.orders").await.unwrap();
+    /// }
+    /// ```
+    #[cfg(feature = "rest")]
+    pub async fn connect_rest(&self) -> Result<rest::RestClient, DremioClientError> {
+        self.settings.connect_rest().await
+    }
+
+    /// Executes a SQL query through Dremio's REST API instead of Flight SQL,
+    /// and collects its results.
+    ///
+    /// This is a fallback for when the Flight endpoints of the query cannot
+    /// be reached; it logs in with [`connect_rest`](Self::connect_rest) and
+    /// runs the query with
+    /// [`RestClient::execute_via_rest`](rest::RestClient::execute_via_rest),
+    /// which is much slower and returns some column types as strings. The
+    /// query options of the client are not applied.
+    ///
+    /// # Arguments
+    ///
+    /// * `sql` - The SQL query string to execute.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(Vec<RecordBatch>)` with a batch per page of results, or a single
+    ///   empty batch carrying the schema if there are no rows.
+    /// - `Err(DremioClientError)` if the login, the query or a request fails.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let batches = client.execute_via_rest("SELECT * FROM sys.options").await.unwrap();
+    ///   println!("{:?}", batches);
+    /// }
+    /// ```
+    #[cfg(feature = "rest")]
+    pub async fn execute_via_rest(&self, sql: &str) -> Result<Vec<RecordBatch>, DremioClientError> {
+        self.connect_rest().await?.execute_via_rest(sql).await
+    }
+
+    /// Lists the catalogs of the server.
+    ///
+    /// Dremio does not group schemas into catalogs, so the list is usually
+    /// empty; it is there for servers that do.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(Vec<String>)` with the names of the catalogs.
+    /// - `Err(DremioClientError)` if the catalogs could not be listed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   for catalog in client.get_catalogs().await.unwrap() {
+    ///     println!("{}", catalog);
+    ///   }
+    /// }
+    /// ```
+    pub async fn get_catalogs(&self) -> Result<Vec<String>, DremioClientError> {
+        let batches = self
+            .get_metadata(|mut client| async move { client.get_catalogs().await })
+            .await?;
+        metadata::strings(&batches, "catalog_name")
+    }
+
+    /// Lists the schemas of the server: in Dremio, its sources, spaces and
+    /// folders.
+    ///
+    /// # Arguments
+    ///
+    /// * `catalog` - Only list schemas of this catalog; `Some("")` lists
+    ///   those without a catalog, and `None` all of them.
+    /// * `filter` - Only list schemas whose name matches this SQL `LIKE`
+    ///   pattern, where `%` matches any text and `_` any character.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(Vec<SchemaInfo>)` with the matching schemas.
+    /// - `Err(DremioClientError)` if the schemas could not be listed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   for schema in client.get_schemas(None, Some("lake.%")).await.unwrap() {
+    ///     println!("{}", schema.name);
+    ///   }
+    /// }
+    /// ```
+    pub async fn get_schemas(
+        &self,
+        catalog: Option<&str>,
+        filter: Option<&str>,
+    ) -> Result<Vec<metadata::SchemaInfo>, DremioClientError> {
+        let request = CommandGetDbSchemas {
+            catalog: catalog.map(str::to_string),
+            db_schema_filter_pattern: filter.map(str::to_string),
+        };
+        let batches = self
+            .get_metadata(|mut client| {
+                let request = request.clone();
+                async move { client.get_db_schemas(request).await }
+            })
+            .await?;
+        metadata::schemas(&batches)
+    }
+
+    /// Lists the tables and views of the server: in Dremio, its physical and
+    /// virtual datasets.
+    ///
+    /// # Arguments
+    ///
+    /// * `catalog` - Only list tables of this catalog; `Some("")` lists
+    ///   those without a catalog, and `None` all of them.
+    /// * `schema_pattern` - Only list tables in schemas matching this SQL
+    ///   `LIKE` pattern.
+    /// * `table_pattern` - Only list tables whose name matches this SQL
+    ///   `LIKE` pattern.
+    /// * `types` - Only list tables of these types, as listed by
+    ///   [`get_table_types`](Self::get_table_types); all of them if empty.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(Vec<TableInfo>)` with the matching tables.
+    /// - `Err(DremioClientError)` if the tables could not be listed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let views = client
+    ///     .get_tables(None, Some("lake.%"), None, &["VIEW"])
+    ///     .await
+    ///     .unwrap();
+    ///   for view in views {
+    ///     println!("{:?}.{}", view.schema, view.name);
+    ///   }
+    /// }
+    /// ```
+    pub async fn get_tables(
+        &self,
+        catalog: Option<&str>,
+        schema_pattern: Option<&str>,
+        table_pattern: Option<&str>,
+        types: &[&str],
+    ) -> Result<Vec<metadata::TableInfo>, DremioClientError> {
+        let request = CommandGetTables {
+            catalog: catalog.map(str::to_string),
+            db_schema_filter_pattern: schema_pattern.map(str::to_string),
+            table_name_filter_pattern: table_pattern.map(str::to_string),
+            table_types: types.iter().map(|t| t.to_string()).collect(),
+            include_schema: false,
+        };
+        let batches = self
+            .get_metadata(|mut client| {
+                let request = request.clone();
+                async move { client.get_tables(request).await }
+            })
+            .await?;
+        metadata::tables(&batches)
+    }
+
+    /// Lists the types of tables of the server, such as `TABLE`, `VIEW` and
+    /// `SYSTEM_TABLE`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(Vec<String>)` with the table types.
+    /// - `Err(DremioClientError)` if the table types could not be listed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   println!("{:?}", client.get_table_types().await.unwrap());
+    /// }
+    /// ```
+    pub async fn get_table_types(&self) -> Result<Vec<String>, DremioClientError> {
+        let batches = self
+            .get_metadata(|mut client| async move { client.get_table_types().await })
+            .await?;
+        metadata::strings(&batches, "table_type")
+    }
+
+    /// Retrieves information about the server and the SQL it supports.
+    ///
+    /// The batches have an `info_name` column, the `SqlInfo` code of each
+    /// value, and a `value` column holding the values in a dense union, as
+    /// described by the Flight SQL specification.
+    ///
+    /// # Arguments
+    ///
+    /// * `info` - The information to retrieve; everything the server provides
+    ///   if empty.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(Vec<RecordBatch>)` with the requested information.
+    /// - `Err(DremioClientError)` if the information could not be retrieved.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use arrow_flight::sql::SqlInfo;
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let batches = client
+    ///     .get_sql_info([SqlInfo::FlightSqlServerName, SqlInfo::FlightSqlServerVersion])
+    ///     .await
+    ///     .unwrap();
+    ///   println!("{:?}", batches);
+    /// }
+    /// ```
+    pub async fn get_sql_info(
+        &self,
+        info: impl IntoIterator<Item = SqlInfo>,
+    ) -> Result<Vec<RecordBatch>, DremioClientError> {
+        let info: Vec<SqlInfo> = info.into_iter().collect();
+        self.get_metadata(|mut client| {
+            let info = info.clone();
+            async move { client.get_sql_info(info).await }
+        })
+        .await
+    }
+
+    /// Executes several SQL queries concurrently and retrieves the results of
+    /// each.
+    ///
+    /// At most `max_concurrency` queries run at a time, multiplexed over the
+    /// client's connection, so a dashboard fanning out dozens of queries does
+    /// not flood the coordinator. Each query is run as by
+    /// `get_record_batches`, with the client's default options and result
+    /// cache, and a failing query does not stop the others.
+    ///
+    /// # Arguments
+    ///
+    /// * `queries` - The SQL query strings to execute.
+    /// * `max_concurrency` - How many queries may run at once; 0 counts as 1.
+    ///
+    /// # Returns
+    ///
+    /// Each query paired with its result, in the order the queries were given.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let results = client
+    ///     .execute_many(["SELECT COUNT(*) FROM sys.options", "SELECT * FROM sys.version"], 8)
+    ///     .await;
+    ///   for (query, result) in results {
+    ///     match result {
+    ///       Ok(batches) => println!("{}: {} batches", query, batches.len()),
+    ///       Err(e) => eprintln!("{}: {}", query, e),
+    ///     }
+    ///   }
+    /// }
+    /// ```
+    pub async fn execute_many<I>(
+        &self,
+        queries: I,
+        max_concurrency: usize,
+    ) -> Vec<(String, Result<Vec<RecordBatch>, DremioClientError>)>
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        futures::stream::iter(queries)
+            .map(|query| async move {
+                let query = query.into();
+                let result = self.fetch_batches(&query, &self.query_options).await;
+                (query, result.map(|(_, batches)| batches))
+            })
+            .buffered(max_concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    /// Runs a query to completion, consulting the result cache and retrying
+    /// on stale metadata. Returns the announced schema unless the results
+    /// came from the cache.
+    async fn fetch_batches(
+        &self,
+        query: &str,
+        options: &QueryOptions,
+    ) -> Result<(Option<SchemaRef>, Vec<RecordBatch>), DremioClientError> {
+        let key = self.cache.as_ref().map(|_| self.cache_key(query, options));
+        if let (Some(cache), Some(key)) = (&self.cache, &key) {
+            if let Some(batches) = cache.get(key) {
+                if let Some(batch) = batches.first() {
+                    options
+                        .validate_schema(&batch.schema())
+                        .map_err(|e| e.in_query(QueryPhase::Fetch, query))?;
+                }
+                options
+                    .checks()
+                    .verify(&batches)
+                    .map_err(|e| e.in_query(QueryPhase::Fetch, query))?;
+                return Ok((None, batches));
+            }
+        }
+        let (schema, batches) = match self.collect_query_with_schema(query, options).await {
+            Err(e) if self.refresh_stale_metadata(&e, options).await => {
+                self.collect_query_with_schema(query, options).await?
+            }
+            result => result?,
+        };
+        if let (Some(cache), Some(key)) = (&self.cache, key) {
+            cache.put(key, &batches);
+        }
+        Ok((schema, batches))
+    }
+
+    /// Executes a SQL query with the client's default options and returns a
+    /// stream over its results.
+    ///
+    /// Batches are yielded as Dremio sends them, so results larger than
+    /// memory can be processed one batch at a time. `get_record_batches`
+    /// collects the same stream into a vector.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The SQL query string to execute.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(QueryStream)` once the query has been accepted by Dremio. The
+    ///   stream yields `Result<RecordBatch, DremioClientError>` items.
+    /// - `Err(DremioClientError)` if the query could not be executed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    /// use futures::TryStreamExt;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let mut stream = client.execute_stream("SELECT * FROM sys.options").await.unwrap();
+    ///   let mut rows = 0;
+    ///   while let Some(batch) = stream.try_next().await.unwrap() {
+    ///     rows += batch.num_rows();
+    ///   }
+    ///   println!("{} rows", rows);
+    /// }
+    /// ```
+    pub async fn execute_stream(&mut self, query: &str) -> Result<QueryStream, DremioClientError> {
+        let options = self.query_options.clone();
+        self.execute_stream_with_options(query, &options).await
+    }
+
+    /// Executes a SQL query and returns a stream over its results.
+    ///
+    /// Unlike `get_record_batches`, batches are yielded as they arrive rather than
+    /// being collected first, and the result cache is not consulted. When
+    /// `options` sets a prefetch depth, the next batches are pulled in the
+    /// background while the current one is processed.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The SQL query string to execute.
+    /// * `options` - Settings applied to this query only, such as prefetch depth.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(QueryStream)` once the query has been accepted by Dremio.
+    /// - `Err(DremioClientError)` if the query could not be executed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::{Client, QueryOptions};
+    /// use futures::StreamExt;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let options = QueryOptions::new().with_prefetch(2);
+    ///   let mut stream = client
+    ///     .execute_stream_with_options("SELECT * FROM sys.options", &options)
+    ///     .await
+    ///     .unwrap();
+    ///   while let Some(batch) = stream.next().await {
+    ///     println!("{:?}", batch.unwrap());
+    ///   }
+    /// }
+    /// ```
+    pub async fn execute_stream_with_options(
+        &mut self,
+        query: &str,
+        options: &QueryOptions,
+    ) -> Result<QueryStream, DremioClientError> {
+        match self.open_query(query, options).await {
+            Err(e) if self.refresh_stale_metadata(&e, options).await => {
+                self.open_query(query, options).await
+            }
+            result => result,
+        }
+    }
+
+    /// Executes a SQL query and returns a cursor over its rows as values of
+    /// `T`, deserialized with serde as they arrive.
+    ///
+    /// See [`rows`] for how columns map to Rust values. The query uses the
+    /// client's default options; set a prefetch depth with
+    /// [`QueryOptions::with_prefetch`] to read ahead while rows are processed.
+    /// Requires the `serde` feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The SQL query string to execute.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(TypedCursor<T>)` once the query has been accepted by Dremio.
+    /// - `Err(DremioClientError)` if the query could not be executed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Setting {
+    ///   name: String,
+    ///   #[serde(rename = "type")]
+    ///   kind: String,
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let mut cursor = client.cursor::<Setting>("SELECT name, type FROM sys.options").await.unwrap();
+    ///   while let Some(setting) = cursor.try_next().await.unwrap() {
+    ///     println!("{}: {}", setting.name, setting.kind);
+    ///   }
+    /// }
+    /// ```
+    #[cfg(feature = "serde")]
+    pub async fn cursor<T: serde::de::DeserializeOwned>(
+        &mut self,
+        query: &str,
+    ) -> Result<rows::TypedCursor<T>, DremioClientError> {
+        let options = self.query_options.clone();
+        Ok(self
+            .execute_stream_with_options(query, &options)
+            .await?
+            .typed())
+    }
+
+    /// Executes a SQL query and retrieves its rows as values of `T`,
+    /// deserialized with serde.
+    ///
+    /// Like [`get_record_batches`](Self::get_record_batches), the query uses
+    /// the client's default options and the result cache, and all rows are
+    /// held in memory; [`cursor`](Self::cursor) reads large results one batch
+    /// at a time instead. See [`rows`] for how columns map to Rust values.
+    /// Requires the `serde` feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The SQL query string to execute.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(Vec<T>)` with one value per row, in the order Dremio sent them.
+    /// - `Err(DremioClientError::RowError)` if a row does not fit `T`, such as
+    ///   a column of the wrong type or a null in a field that is not an
+    ///   `Option`, naming the row, within its batch, and the column.
+    /// - `Err(DremioClientError)` if the query could not be executed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Order {
+    ///   id: i64,
+    ///   customer: Option<String>,
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let orders = client.query_as::<Order>("SELECT id, customer FROM lake.orders").await.unwrap();
+    ///   println!("{} orders", orders.len());
+    /// }
+    /// ```
+    #[cfg(feature = "serde")]
+    pub async fn query_as<T: serde::de::DeserializeOwned>(
+        &mut self,
+        query: &str,
+    ) -> Result<Vec<T>, DremioClientError> {
+        let mut values = Vec::new();
+        for batch in self.get_record_batches(query).await? {
+            values.extend(rows::from_batch(&batch)?);
+        }
+        Ok(values)
+    }
+
+    /// Runs a query to completion, without retrying.
+    async fn collect_query(
+        &self,
+        query: &str,
+        options: &QueryOptions,
+    ) -> Result<Vec<RecordBatch>, DremioClientError> {
+        self.open_query(query, options)
+            .await?
+            .collect_batches()
+            .await
+    }
+
+    /// Runs a query to completion, without retrying, returning its announced
+    /// schema along with the results.
+    async fn collect_query_with_schema(
+        &self,
+        query: &str,
+        options: &QueryOptions,
+    ) -> Result<(Option<SchemaRef>, Vec<RecordBatch>), DremioClientError> {
+        let stream = self.open_query(query, options).await?;
+        let schema = stream.schema();
+        Ok((schema, stream.collect_batches().await?))
+    }
+
+    /// Starts a query, annotating its errors with the query text, without
+    /// retrying.
+    async fn open_query(
+        &self,
+        query: &str,
+        options: &QueryOptions,
+    ) -> Result<QueryStream, DremioClientError> {
+        let stream = self
+            .start_query(query, options)
+            .await
+            .map_err(|e| e.in_query(QueryPhase::Execute, query))?;
+        let query = query.to_string();
+        Ok(stream.map_err(move |e| e.in_query(QueryPhase::Fetch, &query)))
+    }
+
+    /// Runs a Flight SQL metadata command, sent by `request` through a copy of
+    /// the underlying client, and collects its results.
+    async fn get_metadata<F, Fut>(
+        &self,
+        mut request: F,
+    ) -> Result<Vec<RecordBatch>, DremioClientError>
+    where
+        F: FnMut(FlightSqlServiceClient<Channel>) -> Fut,
+        Fut: Future<Output = Result<FlightInfo, ArrowError>>,
+    {
+        // Conversions meant for query results would garble the metadata.
+        let options = self.query_options.transport_only();
+        let deadline = options.deadline();
+        let (flight_info, client) = self
+            .with_retries(&options, deadline, |client| {
+                let flight_info = request(client.clone());
+                async move { Ok((flight_info.await?, client)) }
+            })
+            .await?;
+        self.open_flight_info(flight_info, client, deadline, &options)?
+            .collect_batches()
+            .await
+    }
+
+    /// Refreshes the metadata of the dataset blamed by `error`, if retrying on
+    /// stale metadata is enabled. Returns whether the query should be retried.
+    async fn refresh_stale_metadata(
+        &self,
+        error: &DremioClientError,
+        options: &QueryOptions,
+    ) -> bool {
+        if !self.stale_metadata_retry {
+            return false;
+        }
+        let Some(dataset) = error.stale_dataset() else {
+            return false;
+        };
+        let refresh = format!("ALTER TABLE {} REFRESH METADATA", sql::quote_path(dataset));
+        self.collect_query(&refresh, &options.transport_only())
+            .await
+            .is_ok()
+    }
+
+    /// Submits a query and opens a stream over its endpoints.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "execute",
+            skip(self, options),
+            fields(query_id, endpoints, expected_rows),
+            err
+        )
+    )]
+    async fn start_query(
+        &self,
+        query: &str,
+        options: &QueryOptions,
+    ) -> Result<QueryStream, DremioClientError> {
+        let deadline = options.deadline();
+        let result = self
+            .with_retries(options, deadline, |mut client| {
+                let query = query.to_string();
+                async move { Ok((client.execute(query, None).await?, client)) }
+            })
+            .await;
+        #[cfg(feature = "tracing")]
+        if let Err(e) = &result {
+            telemetry::record_query_id(e);
+        }
+        let (flight_info, client) = result?;
+        self.open_flight_info(flight_info, client, deadline, options)
+    }
+
+    /// Sends a request with `attempt`, given a copy of the underlying client
+    /// for the requests of a query, retrying as the retry policy says. Errors
+    /// returned by the server are classified, see [`status::classify`].
+    ///
+    /// A request rejected as unauthenticated is retried once the password
+    /// handshake has been repeated, since the session may have been lost in
+    /// a restart of the coordinator.
+    async fn with_retries<T, F, Fut>(
+        &self,
+        options: &QueryOptions,
+        deadline: Option<Deadline>,
+        mut attempt: F,
+    ) -> Result<T, DremioClientError>
+    where
+        F: FnMut(FlightSqlServiceClient<Channel>) -> Fut,
+        Fut: Future<Output = Result<T, DremioClientError>>,
+    {
+        let policy = self.settings.retry_policy();
+        let mut attempts = 1;
+        loop {
+            let mut client = self.query_client(options);
+            apply_deadline(&mut client, deadline)?;
+            let error = match attempt(client).await {
+                Err(e) => e,
+                result => return result,
+            };
+            if attempts < policy.max_attempts()
+                && status::status_code(&error) == Some(tonic::Code::Unauthenticated)
+            {
+                if !self.reauthenticate().await? {
+                    return Err(status::classify(error));
+                }
+            } else {
+                match policy.delay(&error, attempts) {
+                    Some(delay) => tokio::time::sleep(delay).await,
+                    None => return Err(status::classify(status::expired(error, deadline))),
+                }
+            }
+            attempts += 1;
+        }
+    }
+
+    /// Repeats the password handshake and sends the new session token with
+    /// all later requests. Returns whether a handshake took place, which only
+    /// happens for clients authenticated with a password.
+    async fn reauthenticate(&self) -> Result<bool, DremioClientError> {
+        let mut client = self.flight_sql_service_client.clone();
+        if !self.settings.handshake(&mut client).await? {
+            return Ok(false);
+        }
+        *self.session_token.write().unwrap() = client.token().cloned();
+        Ok(true)
+    }
+
+    /// Opens a stream over the endpoints of a submitted query, fetching those
+    /// without a location through `coordinator`.
+    pub(crate) fn open_flight_info(
+        &self,
+        flight_info: FlightInfo,
+        coordinator: FlightSqlServiceClient<Channel>,
+        deadline: Option<Deadline>,
+        options: &QueryOptions,
+    ) -> Result<QueryStream, DremioClientError> {
+        let announced = match flight_info.clone().try_decode_schema() {
+            Ok(schema) => match options.transformed_schema(Arc::new(schema)) {
+                Ok(schema) => Some(schema),
+                Err(e) if options.expected_schema().is_some() => return Err(e),
+                Err(_) => None,
+            },
+            Err(_) => None,
+        };
+        // Validate against the announced schema up front where possible, so a
+        // mismatch fails before any data is transferred.
+        let schema_checked = match (options.expected_schema(), &announced) {
+            (Some(_), Some(schema)) => {
+                options.validate_schema(schema)?;
+                true
+            }
+            (Some(_), None) => false,
+            (None, _) => true,
+        };
+        let expected_rows = u64::try_from(flight_info.total_records).ok();
+        #[cfg(feature = "tracing")]
+        {
+            let span = tracing::Span::current();
+            span.record("endpoints", flight_info.endpoint.len());
+            if let Some(rows) = expected_rows {
+                span.record("expected_rows", rows);
+            }
+        }
+        // Without the deadline, which may have passed by the time it is used.
+        let handle = cancel::QueryHandle::new(self.query_client(options), flight_info.clone());
+        let mut executors: HashMap<String, FlightSqlServiceClient<Channel>> = HashMap::new();
+        let mut endpoints = Vec::with_capacity(flight_info.endpoint.len());
+        for (index, endpoint) in flight_info.endpoint.into_iter().enumerate() {
+            let ticket = endpoint
+                .ticket
+                .ok_or(DremioClientError::MissingTicket(index))?;
+            // Any of the locations serves the ticket; none means the
+            // coordinator does.
+            let client = match endpoint.location.first().map(|location| &location.uri) {
+                Some(uri) if !uri.is_empty() && !uri.starts_with(REUSE_CONNECTION) => {
+                    match executors.get(uri) {
+                        Some(client) => client.clone(),
+                        None => {
+                            let mut client = self.settings.location_client(uri, &coordinator)?;
+                            apply_query_headers(&mut client, options);
+                            executors.insert(uri.clone(), client.clone());
+                            client
+                        }
+                    }
+                }
+                _ => coordinator.clone(),
+            };
+            endpoints.push((client, ticket));
+        }
+        Ok(QueryStream::from_endpoints(
+            endpoints,
+            self.headers.clone(),
+            deadline,
+            options,
+            self.settings.retry_policy().clone(),
+        )
+        .with_expected_rows(expected_rows)
+        .with_schema(announced)
+        .with_handle(handle)
+        .map_batches(options.batch_transformer(schema_checked))
+        .rechunk(options.batch_size())
+        .with_checks(options.checks().clone())
+        .with_progress_hook(options.progress_hook())
+        .prefetch(options.prefetch())
+        .cancel_at(deadline))
+    }
+
+    /// Executes a SQL query and returns a preview of its first rows.
+    ///
+    /// `SELECT` and `WITH` statements are given a `LIMIT`, so Dremio stops
+    /// producing rows early: they are wrapped in a subquery, or have the
+    /// `LIMIT` appended if they end in an `ORDER BY`, which keeps the order.
+    /// For other statements the results are cut off on the client once
+    /// `rows` rows have arrived and the query is cancelled on the server. The
+    /// query uses the client's default options and bypasses the result cache.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The SQL query string to execute.
+    /// * `rows` - The maximum number of rows to return.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(RecordBatch)` with at most `rows` rows. A query that produced no
+    ///   batches at all yields an empty batch with the announced schema.
+    /// - `Err(DremioClientError)` if an error occurs during query execution or data retrieval.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let preview = client.peek("SELECT * FROM sys.options", 10).await.unwrap();
+    ///   println!("{:?}", preview);
+    /// }
+    /// ```
+    pub async fn peek(
+        &mut self,
+        query: &str,
+        rows: usize,
+    ) -> Result<RecordBatch, DremioClientError> {
+        let options = self.query_options.clone();
+        let limited = sql::limit_query(query, rows);
+        // Statements that cannot be limited keep running on the server.
+        let cancel_rest = limited == query;
+        self.execute_stream_with_options(&limited, &options)
+            .await?
+            .head(rows, cancel_rest)
+            .await
+    }
+
+    /// Executes a SQL query and renders its first rows as a Markdown table.
+    ///
+    /// The query is fetched like [`peek`](Self::peek) does, with one extra
+    /// row to tell whether it returned more than `limit` rows, in which case
+    /// the table is followed by a note. See [`render::to_markdown`] for the
+    /// format.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The SQL query string to execute.
+    /// * `limit` - The maximum number of rows to render.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(String)` with the table, empty if the query produced no batches.
+    /// - `Err(DremioClientError)` if an error occurs during query execution or data retrieval.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let table = client.to_markdown("SELECT * FROM lake.orders", 20).await.unwrap();
+    ///   println!("{}", table);
+    /// }
+    /// ```
+    pub async fn to_markdown(
+        &mut self,
+        query: &str,
+        limit: usize,
+    ) -> Result<String, DremioClientError> {
+        let preview = self.peek(query, limit.saturating_add(1)).await?;
+        render::to_markdown(&[preview], limit)
+    }
+
+    /// Executes a SQL query and renders its first rows as an HTML table.
+    ///
+    /// Works like [`to_markdown`](Self::to_markdown). See [`render::to_html`]
+    /// for the format.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The SQL query string to execute.
+    /// * `limit` - The maximum number of rows to render.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(String)` with the table, empty if the query produced no batches.
+    /// - `Err(DremioClientError)` if an error occurs during query execution or data retrieval.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let body = client.to_html("SELECT * FROM lake.orders", 20).await.unwrap();
+    ///   assert!(body.starts_with("<table>"));
+    /// }
+    /// ```
+    pub async fn to_html(
+        &mut self,
+        query: &str,
+        limit: usize,
+    ) -> Result<String, DremioClientError> {
+        let preview = self.peek(query, limit.saturating_add(1)).await?;
+        render::to_html(&[preview], limit)
+    }
+
+    /// Retrieves a random sample of the rows of a table.
+    ///
+    /// See [`sql::sample_sql`] for the SQL that is generated. The query uses
+    /// the client's default options.
+    ///
+    /// # Arguments
+    ///
+    /// * `table` - The dotted path of the table, such as `space.table`.
+    /// * `spec` - The number or fraction of rows to sample.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(Vec<RecordBatch>)` containing the sampled rows.
+    /// - `Err(DremioClientError)` if the path or fraction is invalid, or the query fails.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::sql::SampleSpec;
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let rows = client.sample("lake.orders", SampleSpec::Rows(10_000)).await.unwrap();
+    ///   let share = client.sample("lake.orders", SampleSpec::Fraction(0.01)).await.unwrap();
+    /// }
+    /// ```
+    pub async fn sample(
+        &mut self,
+        table: &str,
+        spec: sql::SampleSpec,
+    ) -> Result<Vec<RecordBatch>, DremioClientError> {
+        let query = sql::sample_sql(table, spec)?;
+        self.get_record_batches(&query).await
+    }
+
+    /// Executes two queries and compares their results row by row, matching
+    /// rows on the `keys` columns.
+    ///
+    /// Results are merged as they stream in, so memory use does not depend on
+    /// their size. See [`diff::diff_queries`] for details and more options.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - The SQL query returning the reference rows.
+    /// * `second` - The SQL query whose rows are compared with them.
+    /// * `keys` - The columns identifying a row in both results.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(ResultDiff)` with the number of added, removed and changed rows
+    ///   and the first few of them.
+    /// - `Err(DremioClientError)` if either query fails or their columns differ.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let diff = client
+    ///     .diff("SELECT * FROM lake.orders_v1", "SELECT * FROM lake.orders_v2", &["order_id"])
+    ///     .await
+    ///     .unwrap();
+    ///   assert!(diff.is_empty(), "{} rows changed", diff.changed);
+    /// }
+    /// ```
+    pub async fn diff(
+        &mut self,
+        first: &str,
+        second: &str,
+        keys: &[&str],
+    ) -> Result<diff::ResultDiff, DremioClientError> {
+        let options = diff::DiffOptions::new().with_query_options(self.query_options.clone());
+        diff::diff_queries(self, first, second, keys, &options).await
+    }
+
+    /// Executes a SQL query and computes summary statistics of its results.
+    ///
+    /// For every column, counts the values and nulls, estimates the number of
+    /// distinct values, and finds the minimum, maximum and, for numeric
+    /// columns, the mean. Batches are profiled as they arrive and then
+    /// dropped, so the results are never held in memory. Use a
+    /// [`Profiler`](profile::Profiler) directly to profile results while
+    /// doing something else with them.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The SQL query string to execute.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(ResultSummary)` with the statistics of each column.
+    /// - `Err(DremioClientError)` if an error occurs during query execution
+    ///   or data retrieval.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let summary = client.describe_result("SELECT * FROM lake.orders").await.unwrap();
+    ///   println!("{}", summary);
+    /// }
+    /// ```
+    pub async fn describe_result(
+        &mut self,
+        query: &str,
+    ) -> Result<profile::ResultSummary, DremioClientError> {
+        self.profile_result(query, &profile::ProfileOptions::new())
+            .await
+    }
+
+    /// Executes a SQL query and profiles its results, including what
+    /// `options` asks for on top of the statistics of
+    /// [`describe_result`](Self::describe_result).
+    ///
+    /// Histograms and frequent values are computed in a single pass with
+    /// bounded memory per column, so whole datasets can be profiled, for
+    /// example to enrich a data catalog.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The SQL query string to execute.
+    /// * `options` - The histograms and frequent values to compute.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(ResultSummary)` with the profile of each column.
+    /// - `Err(DremioClientError)` if an error occurs during query execution
+    ///   or data retrieval.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    /// use dremio_rs::profile::ProfileOptions;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let options = ProfileOptions::new().with_histogram_bins(20).with_top_values(10);
+    ///   let summary = client.profile_result("SELECT * FROM lake.orders", &options).await.unwrap();
+    ///   for value in &summary.column("region").unwrap().top_values {
+    ///     println!("{}: {}", value.value, value.count);
+    ///   }
+    /// }
+    /// ```
+    pub async fn profile_result(
+        &mut self,
+        query: &str,
+        options: &profile::ProfileOptions,
+    ) -> Result<profile::ResultSummary, DremioClientError> {
+        let query_options = self.query_options.clone();
+        let mut stream = self
+            .execute_stream_with_options(query, &query_options)
+            .await?;
+        let mut profiler = profile::Profiler::with_options(*options);
+        while let Some(batch) = futures::StreamExt::next(&mut stream).await {
+            profiler.update(&batch?)?;
+        }
+        Ok(profiler.finish())
+    }
+
+    /// Runs a SQL query repeatedly and measures how long each run takes.
+    ///
+    /// After `options.warmup` unmeasured runs, the query runs
+    /// `options.iterations` times, with up to `options.concurrency` runs in
+    /// flight at once over this client's connection. Every run fetches all of
+    /// the results and drops them as they arrive. The query uses the client's
+    /// default options and bypasses the result cache.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The SQL query string to benchmark.
+    /// * `options` - The number of runs and how many run at once.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(BenchReport)` with the latency and throughput of every run.
+    /// - `Err(DremioClientError)` if any run fails.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::bench::BenchOptions;
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let options = BenchOptions { iterations: 20, warmup: 2, concurrency: 4 };
+    ///   let report = client.benchmark("SELECT * FROM lake.orders", options).await.unwrap();
+    ///   println!("{}", report);
+    ///   println!("p99: {:?}", report.percentile(99.0));
+    /// }
+    /// ```
+    pub async fn benchmark(
+        &mut self,
+        query: &str,
+        options: bench::BenchOptions,
+    ) -> Result<bench::BenchReport, DremioClientError> {
+        let query_options = self.query_options.clone();
+        bench::benchmark(self, query, &options, &query_options).await
+    }
+
+    /// Executes a SQL query, reporting the phases its job goes through.
+    ///
+    /// The state of the job is sampled every `interval` while the query runs,
+    /// telling time spent planning, queued for workload management and
+    /// executing apart from time spent fetching the results. `on_event` is
+    /// called whenever a new phase is noticed. See [`phases`] for how the job
+    /// is found. The query uses the client's default options and bypasses
+    /// the result cache.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The SQL query string to execute.
+    /// * `interval` - The time between samples of the job's state.
+    /// * `on_event` - Called with each phase as it is entered.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(PhaseReport)` with the results and the phases of the query.
+    /// - `Err(DremioClientError)` if an error occurs during query execution or
+    ///   data retrieval. Failing samples are not errors.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::phases::JobPhase;
+    /// use dremio_rs::Client;
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let report = client
+    ///     .trace_phases("SELECT * FROM lake.orders", Duration::from_millis(500), |event| {
+    ///       eprintln!("{:?}: {}", event.at, event.phase);
+    ///     })
+    ///     .await
+    ///     .unwrap();
+    ///   println!("{}", report);
+    ///   println!("queued for {:?}", report.time_in(JobPhase::Queued));
+    /// }
+    /// ```
+    pub async fn trace_phases<F>(
+        &self,
+        query: &str,
+        interval: Duration,
+        on_event: F,
+    ) -> Result<phases::PhaseReport, DremioClientError>
+    where
+        F: FnMut(&phases::PhaseEvent) + Send,
+    {
+        phases::trace_phases(self, query, interval, &self.query_options, on_event).await
+    }
+
+    /// Tails an append-only dataset, yielding the rows added to it.
+    ///
+    /// `sql_template` is run every `interval`, with its `{after}`
+    /// placeholder replaced by a condition selecting the rows whose
+    /// `key_column` is greater than the greatest value seen so far. See
+    /// [`watch`] for the details. The queries use the client's default
+    /// options.
+    ///
+    /// # Arguments
+    ///
+    /// * `sql_template` - The query to run, containing an `{after}` placeholder.
+    /// * `key_column` - The increasing column identifying new rows, such as an ID
+    ///   or an ingestion timestamp.
+    /// * `interval` - The time between the end of a query and the start of the next.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(Watch)`, a stream of the batches of new rows.
+    /// - `Err(DremioClientError)` if `sql_template` has no `{after}` placeholder.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    /// use futures::TryStreamExt;
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let mut watch = client
+    ///     .watch("SELECT * FROM lake.events WHERE {after}", "event_id", Duration::from_secs(10))
+    ///     .unwrap();
+    ///   while let Some(batch) = watch.try_next().await.unwrap() {
+    ///     println!("{} new events", batch.num_rows());
+    ///   }
+    /// }
+    /// ```
+    pub fn watch(
+        &self,
+        sql_template: &str,
+        key_column: &str,
+        interval: Duration,
+    ) -> Result<watch::Watch<'_>, DremioClientError> {
+        watch::Watch::new(
+            self,
+            sql_template,
+            key_column,
+            interval,
+            self.query_options.clone(),
+        )
+    }
+
+    /// Waits for the reflections of a set of datasets to be able to
+    /// accelerate queries, then runs a set of representative queries.
+    ///
+    /// Stale reflections are refreshed first when `warmup` has a REST client.
+    /// See [`warmup`] for the steps taken.
+    ///
+    /// # Arguments
+    ///
+    /// * `warmup` - The datasets to wait for and the queries to run.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(WarmupReport)` with the reflections and the queries run.
+    /// - `Err(DremioClientError)` if a query fails, or
+    ///   `DremioClientError::TimeoutError` if the reflections are not ready
+    ///   within the timeout of `warmup`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::warmup::Warmup;
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let warmup = Warmup::new()
+    ///     .with_dataset("lake.sales.orders")
+    ///     .unwrap()
+    ///     .with_query("SELECT region, SUM(amount) FROM lake.sales.orders GROUP BY region");
+    ///   let report = client.warm_reflections(&warmup).await.unwrap();
+    ///   println!("ready after {:?}", report.waited);
+    /// }
+    /// ```
+    pub async fn warm_reflections(
+        &mut self,
+        warmup: &warmup::Warmup,
+    ) -> Result<warmup::WarmupReport, DremioClientError> {
+        warmup::warm_reflections(self, warmup).await
+    }
+
+    /// Executes a SQL query and writes the results directly to a Parquet file.
+    ///
+    /// Batches are written as they are fetched, so memory use is bounded by
+    /// the size of a row group rather than that of the results. The file takes
+    /// the schema Dremio announced for the results, and is written even when
+    /// the query returns no rows.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The SQL query string to execute.
+    /// * `path` - The file path where the Parquet file will be written.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(())` if the Parquet file is successfully written.
+    /// - `Err(DremioClientError)` if an error occurs during query execution,
+    ///   data retrieval, or file writing.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///  let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///  client.write_parquet("SELECT * FROM sys.options", "my_table.parquet").await.unwrap();
+    /// }
+    /// ```
+    #[cfg(feature = "parquet")]
+    pub async fn write_parquet(
+        &mut self,
+        query: &str,
+        path: &str,
+    ) -> Result<(), DremioClientError> {
+        self.write_parquet_inner(query, path, None, None).await?;
+        Ok(())
+    }
+
+    /// Executes a SQL query and writes the results to a Parquet file with the
+    /// given writer properties.
+    ///
+    /// Works like [`write_parquet`](Self::write_parquet), with `properties`
+    /// choosing the compression codec, row group size, dictionary encoding,
+    /// statistics and writer version of the file, so that it can be tuned for
+    /// the engines that will read it.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The SQL query string to execute.
+    /// * `path` - The file path where the Parquet file will be written.
+    /// * `properties` - The Parquet writer properties.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(())` if the Parquet file is successfully written.
+    /// - `Err(DremioClientError)` if an error occurs during query execution,
+    ///   data retrieval, or file writing.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    /// use parquet::basic::{Compression, ZstdLevel};
+    /// use parquet::file::properties::{EnabledStatistics, WriterProperties};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///  let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///  let properties = WriterProperties::builder()
+    ///    .set_compression(Compression::ZSTD(ZstdLevel::try_new(3).unwrap()))
+    ///    .set_max_row_group_size(250_000)
+    ///    .set_statistics_enabled(EnabledStatistics::Page)
+    ///    .build();
+    ///  client
+    ///    .write_parquet_with_properties("SELECT * FROM sys.options", "options.parquet", properties)
+    ///    .await
+    ///    .unwrap();
+    /// }
+    /// ```
+    #[cfg(feature = "parquet")]
+    pub async fn write_parquet_with_properties(
+        &mut self,
+        query: &str,
+        path: &str,
+        properties: WriterProperties,
+    ) -> Result<(), DremioClientError> {
+        self.write_parquet_inner(query, path, Some(properties), None)
+            .await?;
+        Ok(())
+    }
+
+    /// Executes a SQL query and returns a stream over its results for writing
+    /// them to a file, whose schema is known even when Dremio announces none
+    /// and the query returns no rows.
+    async fn export_stream(
+        &mut self,
+        query: &str,
+        options: &QueryOptions,
+    ) -> Result<QueryStream, DremioClientError> {
+        let stream = self.execute_stream_with_options(query, options).await?;
+        if stream.schema().is_some() {
+            return Ok(stream);
+        }
+        let schema = self.get_schema_with_options(query, options).await?;
+        Ok(stream.with_schema(Some(schema)))
+    }
+
+    #[cfg(feature = "parquet")]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "write_parquet", skip(self, properties), ret, err)
+    )]
+    async fn write_parquet_inner(
+        &mut self,
+        query: &str,
+        path: &str,
+        properties: Option<WriterProperties>,
+        verification: Option<export::Verification>,
+    ) -> Result<export::ExportSummary, DremioClientError> {
+        let options = self.query_options.clone();
+        let stream = self.export_stream(query, &options).await?;
+        export::write_parquet(stream, path.as_ref(), properties, verification)
+            .await
+            .map_err(|e| e.in_query(QueryPhase::Write, query))
+    }
+
+    /// Executes a SQL query and writes the results to a CSV file.
+    ///
+    /// Batches are written as they are fetched. `builder` sets the delimiter,
+    /// whether a header row is written, and how values are formatted. Nested
+    /// columns cannot be written to CSV and must be flattened beforehand, with
+    /// [`QueryOptions::with_flatten`] in the client's default options.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The SQL query string to execute.
+    /// * `path` - The file path where the CSV file will be written.
+    /// * `builder` - The settings of the CSV writer.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(u64)` with the number of rows written.
+    /// - `Err(DremioClientError)` if an error occurs during query execution,
+    ///   data retrieval, or file writing.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use arrow::csv::WriterBuilder;
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///  let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///  let builder = WriterBuilder::new().with_delimiter(b'\t');
+    ///  client.write_csv("SELECT * FROM sys.options", "options.tsv", builder).await.unwrap();
+    /// }
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip(self, builder), ret, err)
+    )]
+    pub async fn write_csv(
+        &mut self,
+        query: &str,
+        path: &str,
+        builder: arrow::csv::WriterBuilder,
+    ) -> Result<u64, DremioClientError> {
+        let options = self.query_options.clone();
+        let stream = self.export_stream(query, &options).await?;
+        export::write_csv(stream, path.as_ref(), builder)
+            .await
+            .map_err(|e| e.in_query(QueryPhase::Write, query))
+    }
+
+    /// Executes a SQL query and writes the results to a newline-delimited
+    /// JSON file, one object per row.
+    ///
+    /// Batches are written as they are fetched. Null values are left out of
+    /// the objects.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The SQL query string to execute.
+    /// * `path` - The file path where the JSON file will be written.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(u64)` with the number of rows written.
+    /// - `Err(DremioClientError)` if an error occurs during query execution,
+    ///   data retrieval, or file writing.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///  let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///  client.write_json("SELECT * FROM sys.options", "options.jsonl").await.unwrap();
+    /// }
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(self), ret, err))]
+    pub async fn write_json(&mut self, query: &str, path: &str) -> Result<u64, DremioClientError> {
+        let options = self.query_options.clone();
+        let stream = self.export_stream(query, &options).await?;
+        export::write_json(stream, path.as_ref())
+            .await
+            .map_err(|e| e.in_query(QueryPhase::Write, query))
+    }
+
+    /// Executes a SQL query and writes the results to an Arrow IPC file.
+    ///
+    /// Batches are written as they are fetched, in the Arrow IPC file format
+    /// read by [`FileReader`](arrow::ipc::reader::FileReader) and by other
+    /// Arrow implementations. `options` sets the metadata version, alignment
+    /// and buffer compression of the file; compression requires arrow's
+    /// `ipc_compression` feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The SQL query string to execute.
+    /// * `path` - The file path where the IPC file will be written.
+    /// * `options` - The settings of the IPC writer.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(u64)` with the number of rows written.
+    /// - `Err(DremioClientError)` if an error occurs during query execution,
+    ///   data retrieval, or file writing.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use arrow::ipc::writer::IpcWriteOptions;
+    /// use arrow::ipc::CompressionType;
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///  let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///  let options = IpcWriteOptions::default()
+    ///    .try_with_compression(Some(CompressionType::ZSTD))
+    ///    .unwrap();
+    ///  client.write_ipc("SELECT * FROM sys.options", "options.arrow", options).await.unwrap();
+    /// }
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip(self, options), ret, err)
+    )]
+    pub async fn write_ipc(
+        &mut self,
+        query: &str,
+        path: &str,
+        options: arrow::ipc::writer::IpcWriteOptions,
+    ) -> Result<u64, DremioClientError> {
+        let query_options = self.query_options.clone();
+        let stream = self.export_stream(query, &query_options).await?;
+        export::write_ipc(stream, path.as_ref(), options)
+            .await
+            .map_err(|e| e.in_query(QueryPhase::Write, query))
+    }
+
+    /// Executes a SQL query and uploads the results to an object store, such
+    /// as S3, GCS or Azure Blob Storage.
+    ///
+    /// Batches are encoded as they are fetched and uploaded in parts with a
+    /// multipart upload, so no local file is needed and memory use is bounded
+    /// by the size of a part, or of a row group for Parquet. If the query or
+    /// the upload fails, the upload is aborted and no object is created.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The SQL query string to execute.
+    /// * `store` - The object store to upload to.
+    /// * `path` - The path of the object in `store`.
+    /// * `format` - The file format and its settings.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(u64)` with the number of rows written.
+    /// - `Err(DremioClientError)` if an error occurs during query execution,
+    ///   data retrieval or the upload.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use arrow::csv::WriterBuilder;
+    /// use dremio_rs::export::ObjectFormat;
+    /// use dremio_rs::Client;
+    /// use object_store::local::LocalFileSystem;
+    /// use object_store::path::Path;
+    /// use std::sync::Arc;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///  let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///  let store = Arc::new(LocalFileSystem::new_with_prefix("/exports").unwrap());
+    ///  let format = ObjectFormat::Csv(WriterBuilder::new());
+    ///  client
+    ///    .write_to_store("SELECT * FROM sys.options", store, &Path::from("options.csv"), format)
+    ///    .await
+    ///    .unwrap();
+    /// }
+    /// ```
+    #[cfg(feature = "object_store")]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip(self, store, format), ret, err)
+    )]
+    pub async fn write_to_store(
+        &mut self,
+        query: &str,
+        store: Arc<dyn object_store::ObjectStore>,
+        path: &object_store::path::Path,
+        format: export::ObjectFormat,
+    ) -> Result<u64, DremioClientError> {
+        let options = self.query_options.clone();
+        let stream = self.export_stream(query, &options).await?;
+        export::write_object(stream, store, path, format)
+            .await
+            .map_err(|e| e.in_query(QueryPhase::Write, query))
+    }
+
+    /// Executes a SQL query, writes the results to a Parquet file and checks
+    /// the file against the rows fetched.
+    ///
+    /// Works like [`write_parquet_with_properties`](Self::write_parquet_with_properties),
+    /// or [`write_parquet`](Self::write_parquet) when `properties` is `None`.
+    /// Once the file is closed, its footer is read back and, with
+    /// [`Verification::Checksums`](export::Verification::Checksums), its
+    /// contents as well, so that unattended extracts can be trusted.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The SQL query string to execute.
+    /// * `path` - The file path where the Parquet file will be written.
+    /// * `properties` - The Parquet writer properties, if not the defaults.
+    /// * `verification` - How thoroughly the file is checked.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(VerificationReport)` describing the checks, whether or not they
+    ///   passed. A query without rows writes a file without rows, which the
+    ///   report shows as [empty](export::VerificationReport::is_empty).
+    /// - `Err(DremioClientError)` if an error occurs during query execution,
+    ///   data retrieval, file writing or reading the file back. No file is
+    ///   left at `path` if the results could not be written in full.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::export::Verification;
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///  let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///  let report = client
+    ///    .write_parquet_verified("SELECT * FROM sys.options", "options.parquet", None, Verification::Checksums)
+    ///    .await
+    ///    .unwrap();
+    ///  assert!(report.is_ok(), "{}", report);
+    /// }
+    /// ```
+    #[cfg(feature = "parquet")]
+    pub async fn write_parquet_verified(
+        &mut self,
+        query: &str,
+        path: &str,
+        properties: Option<WriterProperties>,
+        verification: export::Verification,
+    ) -> Result<export::VerificationReport, DremioClientError> {
+        let summary = self
+            .write_parquet_inner(query, path, properties, Some(verification))
+            .await?;
+        Ok(summary.verification.unwrap_or_default())
+    }
+
+    /// Executes a SQL query and writes the results to a Hive-style
+    /// partitioned directory of Parquet files with `writer`.
+    ///
+    /// Batches are split by the values of the writer's partition columns as
+    /// they arrive, into `column=value/part-0000.parquet` files below its
+    /// root directory, and new files are started as the writer's size limits
+    /// are reached.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The SQL query string to execute.
+    /// * `writer` - The writer, set up with the root directory, the partition
+    ///   columns and how files are written.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(ExportSummary)` with the files and rows written.
+    /// - `Err(DremioClientError)` if an error occurs during query execution,
+    ///   data retrieval or file writing.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::export::PartitionedParquetWriter;
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///  let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///  let writer = PartitionedParquetWriter::new("sales", vec!["region".to_string(), "dt".to_string()])
+    ///    .with_max_rows_per_file(5_000_000);
+    ///  let summary = client
+    ///    .write_parquet_partitioned("SELECT * FROM lake.sales", writer)
+    ///    .await
+    ///    .unwrap();
+    ///  println!("wrote {} rows to {} files", summary.rows, summary.files.len());
+    /// }
+    /// ```
+    #[cfg(feature = "parquet")]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip(self, writer), fields(rows), err)
+    )]
+    pub async fn write_parquet_partitioned(
+        &mut self,
+        query: &str,
+        mut writer: export::PartitionedParquetWriter,
+    ) -> Result<export::ExportSummary, DremioClientError> {
+        let options = self.query_options.clone();
+        let mut stream = self.execute_stream_with_options(query, &options).await?;
+        let written = |e: DremioClientError| e.in_query(QueryPhase::Write, query);
+        while let Some(batch) = stream.next().await {
+            writer.write(&batch?).map_err(written)?;
+        }
+        let summary = writer.close().map_err(written)?;
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("rows", summary.rows);
+        Ok(summary)
+    }
+
+    /// Executes a SQL query and saves its full results to a local snapshot
+    /// file, to be replayed offline with a [`SnapshotReader`](snapshot::SnapshotReader).
+    ///
+    /// Batches are written to an Arrow IPC file as they arrive, along with the
+    /// query and the time it was captured. They are saved as returned by the
+    /// server: result transformations are applied when they are replayed.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The SQL query string to execute.
+    /// * `path` - The file path where the snapshot will be written. Missing
+    ///   parent directories are created.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(SnapshotInfo)` describing the snapshot written.
+    /// - `Err(DremioClientError)` if an error occurs during query execution,
+    ///   data retrieval or file writing.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let info = client.snapshot("SELECT * FROM sys.options", "snapshots/options.arrow").await.unwrap();
+    ///   println!("saved {} rows", info.rows);
+    /// }
+    /// ```
+    pub async fn snapshot(
+        &mut self,
+        query: &str,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<snapshot::SnapshotInfo, DremioClientError> {
+        let path = path.as_ref();
+        let options = self.query_options.transport_only();
+        let stream = self.execute_stream_with_options(query, &options).await?;
+        let write = async {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            snapshot::write_snapshot(stream, query, path).await
+        };
+        write
+            .await
+            .map_err(|e| e.in_query(QueryPhase::Write, query))
+    }
+
+    /// Executes a SQL query and streams its results to another Arrow Flight
+    /// server with `DoPut`.
+    ///
+    /// Batches are sent as they arrive from Dremio, without collecting them
+    /// first, so the results can be republished to, for example, an internal
+    /// caching Flight server. The query uses the client's default options.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The SQL query string to execute.
+    /// * `target_url` - The URL of the Flight server receiving the results.
+    /// * `descriptor` - The descriptor identifying the results on that server.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(Vec<PutResult>)` with the acknowledgements sent by the target server.
+    /// - `Err(DremioClientError)` if the query fails or the target server
+    ///   rejects the results.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use arrow_flight::FlightDescriptor;
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   client
+    ///     .forward(
+    ///       "SELECT * FROM sys.options",
+    ///       "http://cache:8815",
+    ///       FlightDescriptor::new_path(vec!["sys".to_string(), "options".to_string()]),
+    ///     )
+    ///     .await
+    ///     .unwrap();
+    /// }
+    /// ```
+    pub async fn forward(
+        &mut self,
+        query: &str,
+        target_url: &str,
+        descriptor: FlightDescriptor,
+    ) -> Result<Vec<PutResult>, DremioClientError> {
+        let options = self.query_options.clone();
+        let stream = self.execute_stream_with_options(query, &options).await?;
+        let mut target = FlightClient::new(
+            Endpoint::from_shared(target_url.to_string())?
+                .connect()
+                .await
+                .map_err(|e| status::classify(e.into()).in_query(QueryPhase::Write, query))?,
+        );
+        // Keep query errors aside, so they are reported as such rather than
+        // as a failed upload.
+        let failure = Arc::new(std::sync::Mutex::new(None));
+        let slot = failure.clone();
+        let batches = TryStreamExt::map_err(stream, move |e| {
+            *slot.lock().unwrap() = Some(e);
+            FlightError::ProtocolError("query failed".to_string())
+        });
+        let data = FlightDataEncoderBuilder::new()
+            .with_flight_descriptor(Some(descriptor))
+            .build(batches);
+        let result = match target.do_put(data).await {
+            Ok(results) => results.try_collect().await,
+            Err(e) => Err(e),
+        };
+        if let Some(e) = failure.lock().unwrap().take() {
+            return Err(e);
+        }
+        result.map_err(|e| status::classify(e.into()).in_query(QueryPhase::Write, query))
+    }
+
+    /// Returns a copy of the underlying Flight SQL client for a single request,
+    /// carrying the time left before `deadline` as the gRPC deadline.
+    fn request_client(
+        &self,
+        deadline: Option<Deadline>,
+    ) -> Result<FlightSqlServiceClient<Channel>, DremioClientError> {
+        let mut client = self.authenticated_client();
+        apply_deadline(&mut client, deadline)?;
+        Ok(client)
+    }
+
+    /// Returns the key caching the results of `query` under `options`. Besides
+    /// the settings shaping the results, it covers the cluster, the identity
+    /// and the headers and session options the client sends, since a cache
+    /// may be shared between clients or, on disk, between processes.
+    fn cache_key(&self, query: &str, options: &QueryOptions) -> CacheKey {
+        CacheKey::new(query)
+            .with_param(self.settings.cache_scope())
+            .with_param(format!("{:?}", self.headers))
+            .with_param(options.result_fingerprint())
+    }
+
+    /// Returns a copy of the underlying Flight SQL client for the requests of
+    /// a query, running as the user `options` impersonate, if any.
+    fn query_client(&self, options: &QueryOptions) -> FlightSqlServiceClient<Channel> {
+        let mut client = self.authenticated_client();
+        apply_query_headers(&mut client, options);
+        client
+    }
+
+    /// Returns a copy of the underlying Flight SQL client, sending the
+    /// session token obtained by re-authenticating, if any.
+    fn authenticated_client(&self) -> FlightSqlServiceClient<Channel> {
+        let mut client = self.flight_sql_service_client.clone();
+        if let Some(token) = self.session_token.read().unwrap().clone() {
+            client.set_token(token);
+        }
+        client
+    }
+
+    /// Copies the session token obtained by re-authenticating, if any, into
+    /// the underlying client, so that it is handed out authenticated.
+    fn sync_token(&mut self) {
+        if let Some(token) = self.session_token.read().unwrap().clone() {
+            self.flight_sql_service_client.set_token(token);
+        }
+    }
+
+    /// Returns a shared reference to the underlying `FlightSqlServiceClient`.
+    ///
+    /// This can be used to access more advanced Flight SQL operations not directly
+    /// exposed by the `Client` interface. The client sends the session token
+    /// it was last handed out with; if the session has been re-established
+    /// since, use [`inner_mut`](Self::inner_mut) to get one with the new token.
+    ///
+    /// # Returns
+    ///
+    /// A reference to the `FlightSqlServiceClient<Channel>`.
+    pub fn inner(&self) -> &FlightSqlServiceClient<Channel> {
+        &self.flight_sql_service_client
+    }
+
+    /// Returns a mutable reference to the underlying `FlightSqlServiceClient`.
+    ///
+    /// Most Flight SQL operations take `&mut self`, so this is the way to call
+    /// those not directly exposed by the `Client` interface. The returned
+    /// client is already authenticated, with the session token obtained by
+    /// re-authenticating if the session was lost, for example in a restart of
+    /// the coordinator.
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the `FlightSqlServiceClient<Channel>`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let info = client.inner_mut().get_catalogs().await.unwrap();
+    ///   println!("{:?}", info);
+    /// }
+    /// ```
+    pub fn inner_mut(&mut self) -> &mut FlightSqlServiceClient<Channel> {
+        self.sync_token();
+        &mut self.flight_sql_service_client
+    }
+
+    /// Consumes the client and returns the underlying `FlightSqlServiceClient`.
+    ///
+    /// # Returns
+    ///
+    /// The authenticated `FlightSqlServiceClient<Channel>`, with the session
+    /// token obtained by re-authenticating, if any.
+    pub fn into_inner(mut self) -> FlightSqlServiceClient<Channel> {
+        self.sync_token();
+        self.flight_sql_service_client
     }
 }
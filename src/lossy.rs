@@ -0,0 +1,220 @@
+//! Decoding of Flight streams that tolerates invalid UTF-8.
+//!
+//! Arrow validates string columns while decoding IPC messages, so a single
+//! invalid byte sequence fails the whole fetch. In lossy mode the stream is
+//! decoded with every top-level string column read as binary, and the values
+//! are then converted back to strings, replacing invalid sequences with
+//! U+FFFD. Nested string fields are still validated strictly.
+
+use crate::options::{grpc_timeout_header, Deadline, IMPERSONATION_HEADER};
+use crate::retry::RetryPolicy;
+use crate::stream::StatsCounters;
+use crate::DremioClientError;
+use arrow::array::{ArrayRef, AsArray, GenericStringArray, OffsetSizeTrait, RecordBatch};
+use arrow::datatypes::{DataType, Field, FieldRef, Schema, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::ipc::{root_as_message, MessageHeader};
+use arrow_flight::sql::client::FlightSqlServiceClient;
+use arrow_flight::utils::flight_data_to_arrow_batch;
+use arrow_flight::{FlightData, Ticket};
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use tonic::metadata::{AsciiMetadataKey, MetadataValue};
+use tonic::transport::Channel;
+use tonic::Streaming;
+
+/// Opens a `do_get` stream for `ticket` that decodes string columns lossily.
+///
+/// `headers` are the extra gRPC metadata of the client, which the raw Flight
+/// client does not send by itself.
+pub(crate) fn endpoint_stream(
+    client: FlightSqlServiceClient<Channel>,
+    ticket: Ticket,
+    deadline: Option<Deadline>,
+    headers: Arc<[(String, String)]>,
+    user: Option<String>,
+    stats: Arc<StatsCounters>,
+    retry: RetryPolicy,
+) -> BoxStream<'static, Result<RecordBatch, DremioClientError>> {
+    stream::once(async move {
+        let data = retry
+            .run(|| open(&client, ticket.clone(), deadline, &headers, user.as_deref()))
+            .await?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!("opened do_get stream");
+        Ok::<_, DremioClientError>(decode(data, stats))
+    })
+    .try_flatten()
+    .boxed()
+}
+
+async fn open(
+    client: &FlightSqlServiceClient<Channel>,
+    ticket: Ticket,
+    deadline: Option<Deadline>,
+    headers: &[(String, String)],
+    user: Option<&str>,
+) -> Result<Streaming<FlightData>, DremioClientError> {
+    let mut request = tonic::Request::new(ticket);
+    for (key, value) in headers {
+        let key =
+            AsciiMetadataKey::from_str(key).map_err(|e| ArrowError::IpcError(e.to_string()))?;
+        let value =
+            MetadataValue::try_from(value).map_err(|e| ArrowError::IpcError(e.to_string()))?;
+        request.metadata_mut().insert(key, value);
+    }
+    if let Some(token) = client.token() {
+        let value = MetadataValue::try_from(format!("Bearer {}", token))
+            .map_err(|e| ArrowError::IpcError(e.to_string()))?;
+        request.metadata_mut().insert("authorization", value);
+    }
+    if let Some(user) = user {
+        let value =
+            MetadataValue::try_from(user).map_err(|e| ArrowError::IpcError(e.to_string()))?;
+        request.metadata_mut().insert(IMPERSONATION_HEADER, value);
+    }
+    if let Some(deadline) = deadline {
+        let remaining = deadline
+            .remaining()
+            .ok_or(DremioClientError::TimeoutError(deadline.timeout()))?;
+        let value = MetadataValue::try_from(grpc_timeout_header(remaining))
+            .map_err(|e| ArrowError::IpcError(e.to_string()))?;
+        request.metadata_mut().insert("grpc-timeout", value);
+    }
+    let mut flight = client.inner().clone();
+    let response = flight
+        .do_get(request)
+        .await
+        .map_err(|status| ArrowError::IpcError(status.to_string()))?;
+    Ok(response.into_inner())
+}
+
+struct DecodeState {
+    data: Streaming<FlightData>,
+    schema: Option<(SchemaRef, SchemaRef)>,
+    stats: Arc<StatsCounters>,
+    done: bool,
+}
+
+fn decode(
+    data: Streaming<FlightData>,
+    stats: Arc<StatsCounters>,
+) -> BoxStream<'static, Result<RecordBatch, DremioClientError>> {
+    let state = DecodeState {
+        data,
+        schema: None,
+        stats,
+        done: false,
+    };
+    stream::unfold(state, |mut state| async move {
+        if state.done {
+            return None;
+        }
+        match next_batch(&mut state).await {
+            Ok(Some(batch)) => Some((Ok(batch), state)),
+            Ok(None) => None,
+            Err(e) => {
+                state.done = true;
+                Some((Err(e), state))
+            }
+        }
+    })
+    .boxed()
+}
+
+async fn next_batch(state: &mut DecodeState) -> Result<Option<RecordBatch>, DremioClientError> {
+    let dictionaries: HashMap<i64, ArrayRef> = HashMap::new();
+    loop {
+        let Some(data) = state
+            .data
+            .message()
+            .await
+            .map_err(|status| ArrowError::IpcError(status.to_string()))?
+        else {
+            return Ok(None);
+        };
+        state.stats.add_message(&data);
+        if data.data_header.is_empty() {
+            continue;
+        }
+        let message = root_as_message(&data.data_header[..])
+            .map_err(|e| ArrowError::ParseError(format!("invalid IPC message: {}", e)))?;
+        match message.header_type() {
+            MessageHeader::Schema => {
+                let schema = Arc::new(Schema::try_from(&data)?);
+                state.schema = Some((schema.clone(), strings_as_binary(&schema)));
+            }
+            MessageHeader::RecordBatch => {
+                let (schema, binary_schema) = state.schema.clone().ok_or_else(|| {
+                    ArrowError::IpcError("record batch received before schema".to_string())
+                })?;
+                let batch = flight_data_to_arrow_batch(&data, binary_schema, &dictionaries)?;
+                return Ok(Some(binary_to_strings(batch, schema, &state.stats)?));
+            }
+            MessageHeader::DictionaryBatch => {
+                return Err(ArrowError::NotYetImplemented(
+                    "dictionary-encoded results are not supported in lossy UTF-8 mode".to_string(),
+                )
+                .into())
+            }
+            _ => continue,
+        }
+    }
+}
+
+/// Replaces the top-level string fields of `schema` with binary fields of the
+/// same offset width.
+fn strings_as_binary(schema: &Schema) -> SchemaRef {
+    let fields: Vec<FieldRef> = schema
+        .fields()
+        .iter()
+        .map(|f| match f.data_type() {
+            DataType::Utf8 => Arc::new(Field::clone(f).with_data_type(DataType::Binary)),
+            DataType::LargeUtf8 => Arc::new(Field::clone(f).with_data_type(DataType::LargeBinary)),
+            _ => f.clone(),
+        })
+        .collect();
+    Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()))
+}
+
+/// Converts the binary columns that stand in for string columns back to
+/// strings, counting values that contained invalid UTF-8.
+fn binary_to_strings(
+    batch: RecordBatch,
+    schema: SchemaRef,
+    stats: &StatsCounters,
+) -> Result<RecordBatch, DremioClientError> {
+    let columns = schema
+        .fields()
+        .iter()
+        .zip(batch.columns())
+        .map(|(field, column)| match field.data_type() {
+            DataType::Utf8 => lossy_strings::<i32>(column, stats),
+            DataType::LargeUtf8 => lossy_strings::<i64>(column, stats),
+            _ => column.clone(),
+        })
+        .collect();
+    Ok(RecordBatch::try_new(schema, columns)?)
+}
+
+fn lossy_strings<O: OffsetSizeTrait>(column: &ArrayRef, stats: &StatsCounters) -> ArrayRef {
+    let binary = column.as_binary::<O>();
+    let mut invalid = 0;
+    let strings: GenericStringArray<O> = binary
+        .iter()
+        .map(|value| {
+            value.map(|bytes| {
+                let text = String::from_utf8_lossy(bytes);
+                if matches!(text, Cow::Owned(_)) {
+                    invalid += 1;
+                }
+                text
+            })
+        })
+        .collect();
+    stats.add_invalid_utf8(invalid);
+    Arc::new(strings)
+}
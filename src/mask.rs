@@ -0,0 +1,230 @@
+//! Masking sensitive columns in query results.
+//!
+//! [`Masking`] replaces the values of selected columns before batches reach
+//! the caller, so results containing personal data can be exported to files
+//! for lower environments without it. Each column gets a [`MaskRule`]: a
+//! salted hash that still allows joins and counts of distinct values, a
+//! truncated prefix, a constant, or nothing at all.
+//!
+//! Masking can be requested per query with
+//! [`QueryOptions::with_masking`](crate::QueryOptions::with_masking), which
+//! applies it to everything fetched or exported with those options.
+
+use crate::blob::{cell_bytes, is_blob_type};
+use crate::cache::fnv1a;
+use crate::convert::type_mismatch;
+use crate::DremioClientError;
+use arrow::array::{new_null_array, Array, ArrayRef, AsArray, RecordBatch, StringArray};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Field, FieldRef, Schema};
+use arrow::error::ArrowError;
+use arrow::util::display::array_value_to_string;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// How the values of a masked column are replaced.
+///
+/// Nulls stay null under every rule.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MaskRule {
+    /// Replace values with the hex digest of a salted 64-bit hash of their
+    /// bytes, or of their text for non-string types. Equal values get equal
+    /// digests. The hash is not cryptographic: keep the salt secret.
+    Hash,
+    /// Replace all values with null, keeping the column type.
+    Redact,
+    /// Keep the first characters of string values.
+    Truncate(usize),
+    /// Replace values with a fixed string.
+    Constant(String),
+}
+
+impl fmt::Display for MaskRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MaskRule::Hash => f.write_str("hash"),
+            MaskRule::Redact => f.write_str("redact"),
+            MaskRule::Truncate(chars) => write!(f, "truncate:{}", chars),
+            MaskRule::Constant(value) => write!(f, "constant:{}", value),
+        }
+    }
+}
+
+/// Parses the form written by `Display`: `hash`, `redact`, `truncate:N` or
+/// `constant:TEXT`.
+impl FromStr for MaskRule {
+    type Err = DremioClientError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, argument) = match s.split_once(':') {
+            Some((name, argument)) => (name, Some(argument)),
+            None => (s, None),
+        };
+        match (name.to_ascii_lowercase().as_str(), argument) {
+            ("hash", None) => Ok(MaskRule::Hash),
+            ("redact", None) => Ok(MaskRule::Redact),
+            ("truncate", Some(chars)) => chars.parse().map(MaskRule::Truncate).map_err(|_| {
+                ArrowError::InvalidArgumentError(format!("invalid truncate length {:?}", chars))
+                    .into()
+            }),
+            ("constant", Some(value)) => Ok(MaskRule::Constant(value.to_string())),
+            _ => Err(ArrowError::InvalidArgumentError(format!(
+                "invalid mask rule {:?}, expected hash, redact, truncate:N or constant:TEXT",
+                s
+            ))
+            .into()),
+        }
+    }
+}
+
+/// The columns to mask in query results, and how.
+///
+/// Columns are named as they appear after column renaming and flattening.
+/// Masking a column that is missing from the results is an error, so a typo
+/// cannot leave data unmasked.
+///
+/// # Example
+///
+/// ```
+/// use arrow::array::{RecordBatch, StringArray};
+/// use dremio_rs::mask::{MaskRule, Masking};
+/// use std::sync::Arc;
+///
+/// let emails = Arc::new(StringArray::from(vec!["ada@example.com"]));
+/// let batch = RecordBatch::try_from_iter([("email", emails as _)]).unwrap();
+/// let masking = Masking::new()
+///   .with_salt("s3cret")
+///   .with_rule("email", MaskRule::Truncate(3));
+/// let masked = masking.apply(batch).unwrap();
+/// assert_eq!(masked.column(0).as_any().downcast_ref::<StringArray>().unwrap().value(0), "ada");
+/// ```
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Masking {
+    rules: BTreeMap<String, MaskRule>,
+    salt: String,
+}
+
+impl fmt::Debug for Masking {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Masking")
+            .field("rules", &self.rules)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Masking {
+    /// Creates a masking without rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Masks `column` with `rule`, replacing any previous rule for it.
+    pub fn with_rule(mut self, column: impl Into<String>, rule: MaskRule) -> Self {
+        self.rules.insert(column.into(), rule);
+        self
+    }
+
+    /// Sets the salt mixed into [`MaskRule::Hash`] digests. Digests only
+    /// match across exports made with the same salt.
+    pub fn with_salt(mut self, salt: impl Into<String>) -> Self {
+        self.salt = salt.into();
+        self
+    }
+
+    /// Returns the rule of each masked column.
+    pub fn rules(&self) -> &BTreeMap<String, MaskRule> {
+        &self.rules
+    }
+
+    /// Returns a copy of `batch` with the masked columns replaced.
+    pub fn apply(&self, batch: RecordBatch) -> Result<RecordBatch, DremioClientError> {
+        if self.rules.is_empty() {
+            return Ok(batch);
+        }
+        let schema = batch.schema();
+        if let Some(column) = self
+            .rules
+            .keys()
+            .find(|column| schema.column_with_name(column).is_none())
+        {
+            return Err(ArrowError::SchemaError(format!(
+                "masked column {:?} is not in the results",
+                column
+            ))
+            .into());
+        }
+        let mut fields: Vec<FieldRef> = Vec::with_capacity(schema.fields().len());
+        let mut columns: Vec<ArrayRef> = Vec::with_capacity(batch.num_columns());
+        for (field, column) in schema.fields().iter().zip(batch.columns()) {
+            let Some(rule) = self.rules.get(field.name()) else {
+                fields.push(field.clone());
+                columns.push(column.clone());
+                continue;
+            };
+            let masked = self.mask(column, rule)?;
+            fields.push(Arc::new(Field::new(
+                field.name(),
+                masked.data_type().clone(),
+                field.is_nullable() || *rule == MaskRule::Redact,
+            )));
+            columns.push(masked);
+        }
+        let schema = Schema::new_with_metadata(fields, schema.metadata().clone());
+        Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+    }
+
+    /// Describes the rules and salt without revealing the salt.
+    pub(crate) fn fingerprint(&self) -> String {
+        let salt = fnv1a(self.salt.as_bytes(), 0xcbf2_9ce4_8422_2325);
+        format!("{:?}/{:016x}", self.rules, salt)
+    }
+
+    fn mask(&self, column: &ArrayRef, rule: &MaskRule) -> Result<ArrayRef, DremioClientError> {
+        let len = column.len();
+        let values: StringArray = match rule {
+            MaskRule::Redact => return Ok(new_null_array(column.data_type(), len)),
+            MaskRule::Hash => {
+                let offset = fnv1a(self.salt.as_bytes(), 0xcbf2_9ce4_8422_2325);
+                let binary = is_blob_type(column.data_type())
+                    || matches!(column.data_type(), DataType::FixedSizeBinary(_));
+                (0..len)
+                    .map(|row| {
+                        if column.is_null(row) {
+                            return Ok(None);
+                        }
+                        let hash = if binary {
+                            fnv1a(
+                                cell_bytes(column.as_ref(), row)?.unwrap_or_default(),
+                                offset,
+                            )
+                        } else {
+                            let text = array_value_to_string(column, row)?;
+                            fnv1a(text.as_bytes(), offset)
+                        };
+                        Ok(Some(format!("{:016x}", hash)))
+                    })
+                    .collect::<Result<_, DremioClientError>>()?
+            }
+            MaskRule::Truncate(chars) => {
+                if !matches!(
+                    column.data_type(),
+                    DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View
+                ) {
+                    return Err(type_mismatch("string", column.data_type()));
+                }
+                let strings = cast(column, &DataType::Utf8)?;
+                strings
+                    .as_string::<i32>()
+                    .iter()
+                    .map(|value| value.map(|value| value.chars().take(*chars).collect::<String>()))
+                    .collect()
+            }
+            MaskRule::Constant(value) => (0..len)
+                .map(|row| column.is_valid(row).then_some(value.as_str()))
+                .collect(),
+        };
+        Ok(Arc::new(values))
+    }
+}
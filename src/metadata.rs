@@ -0,0 +1,104 @@
+//! Catalog metadata, as listed by the Flight SQL metadata commands.
+//!
+//! [`Client::get_catalogs`], [`Client::get_schemas`], [`Client::get_tables`]
+//! and [`Client::get_table_types`] decode the batches returned by Dremio into
+//! the types of this module. In Dremio, schemas are the dotted paths of
+//! sources, spaces and folders, such as `Samples.samples.dremio.com`, and
+//! tables are physical and virtual datasets.
+//!
+//! [`Client::get_catalogs`]: crate::Client::get_catalogs
+//! [`Client::get_schemas`]: crate::Client::get_schemas
+//! [`Client::get_tables`]: crate::Client::get_tables
+//! [`Client::get_table_types`]: crate::Client::get_table_types
+
+use crate::DremioClientError;
+use arrow::array::{Array, AsArray, RecordBatch, StringArray};
+use arrow::error::ArrowError;
+
+/// A database schema, as listed by
+/// [`Client::get_schemas`](crate::Client::get_schemas).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SchemaInfo {
+    /// The catalog the schema belongs to, if any.
+    pub catalog: Option<String>,
+    /// The name of the schema.
+    pub name: String,
+}
+
+/// A table or view, as listed by
+/// [`Client::get_tables`](crate::Client::get_tables).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TableInfo {
+    /// The catalog the table belongs to, if any.
+    pub catalog: Option<String>,
+    /// The schema the table belongs to, if any.
+    pub schema: Option<String>,
+    /// The name of the table.
+    pub name: String,
+    /// The type of the table, one of those listed by
+    /// [`Client::get_table_types`](crate::Client::get_table_types).
+    pub table_type: String,
+}
+
+/// Returns the string column `name` of `batch`.
+fn column<'a>(batch: &'a RecordBatch, name: &str) -> Result<&'a StringArray, DremioClientError> {
+    batch
+        .column_by_name(name)
+        .and_then(|column| column.as_string_opt::<i32>())
+        .ok_or_else(|| {
+            ArrowError::SchemaError(format!("expected a string column named {:?}", name)).into()
+        })
+}
+
+fn value(column: &StringArray, row: usize) -> Option<String> {
+    column.is_valid(row).then(|| column.value(row).to_string())
+}
+
+/// Decodes the single string column `name` of `batches`, skipping nulls.
+pub(crate) fn strings(
+    batches: &[RecordBatch],
+    name: &str,
+) -> Result<Vec<String>, DremioClientError> {
+    let mut values = Vec::new();
+    for batch in batches {
+        let column = column(batch, name)?;
+        values.extend(column.iter().flatten().map(str::to_string));
+    }
+    Ok(values)
+}
+
+/// Decodes the results of `CommandGetDbSchemas`.
+pub(crate) fn schemas(batches: &[RecordBatch]) -> Result<Vec<SchemaInfo>, DremioClientError> {
+    let mut schemas = Vec::new();
+    for batch in batches {
+        let catalogs = column(batch, "catalog_name")?;
+        let names = column(batch, "db_schema_name")?;
+        for row in 0..batch.num_rows() {
+            schemas.push(SchemaInfo {
+                catalog: value(catalogs, row),
+                name: names.value(row).to_string(),
+            });
+        }
+    }
+    Ok(schemas)
+}
+
+/// Decodes the results of `CommandGetTables`.
+pub(crate) fn tables(batches: &[RecordBatch]) -> Result<Vec<TableInfo>, DremioClientError> {
+    let mut tables = Vec::new();
+    for batch in batches {
+        let catalogs = column(batch, "catalog_name")?;
+        let schemas = column(batch, "db_schema_name")?;
+        let names = column(batch, "table_name")?;
+        let types = column(batch, "table_type")?;
+        for row in 0..batch.num_rows() {
+            tables.push(TableInfo {
+                catalog: value(catalogs, row),
+                schema: value(schemas, row),
+                name: names.value(row).to_string(),
+                table_type: types.value(row).to_string(),
+            });
+        }
+    }
+    Ok(tables)
+}
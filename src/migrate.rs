@@ -0,0 +1,458 @@
+//! Versioned SQL migrations.
+//!
+//! A [`Migrator`] applies numbered SQL scripts, such as the statements
+//! creating spaces, views and grants, in order, and records each applied
+//! version in a tracking table so that every script runs exactly once per
+//! cluster.
+//!
+//! Scripts are files named `V<version>__<name>.sql` or `<version>_<name>.sql`,
+//! such as `V1__create_spaces.sql` or `0002_sales_views.sql`. A script may
+//! hold several statements separated by semicolons; they are executed one at
+//! a time, as Dremio runs a single statement per query.
+//!
+//! The tracking table is an Iceberg table with the columns `version`,
+//! `name`, `checksum` and `applied_at`, created on the first run in a
+//! writable source, for example `lake.meta.schema_migrations`. The checksum
+//! of each applied script is compared on later runs, so editing a script
+//! that has already been applied is an error rather than silently ignored.
+//!
+//! Dremio has no transactional DDL: when a statement fails, the statements
+//! of the same script that ran before it stay applied, the script is not
+//! recorded, and the run stops. Migrations are best written to be
+//! re-runnable, for example with `CREATE OR REPLACE VIEW`. Runs are not
+//! locked against each other, so only one should run at a time.
+
+use crate::cache::fnv1a;
+use crate::sql::{parse_path, quote_path, Literal};
+use crate::{DremioClientError, DremioExecutor};
+use arrow::array::{Array, AsArray, RecordBatch};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Int64Type};
+use arrow::error::ArrowError;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// A versioned SQL script.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Migration {
+    version: u64,
+    name: String,
+    sql: String,
+}
+
+impl Migration {
+    /// Creates a migration running the statements of `sql`.
+    pub fn new(version: u64, name: impl Into<String>, sql: impl Into<String>) -> Self {
+        Self {
+            version,
+            name: name.into(),
+            sql: sql.into(),
+        }
+    }
+
+    /// Reads a migration from a file named `V<version>__<name>.sql` or
+    /// `<version>_<name>.sql`.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, DremioClientError> {
+        let path = path.as_ref();
+        let file_name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("");
+        let (version, name) = parse_file_name(file_name).ok_or_else(|| {
+            invalid(format!(
+                "migration file {} is not named V<version>__<name>.sql",
+                path.display()
+            ))
+        })?;
+        Ok(Self::new(version, name, fs::read_to_string(path)?))
+    }
+
+    /// Returns the version of the migration.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Returns the name of the migration.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the SQL of the migration.
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// Returns a checksum of the SQL, ignoring trailing whitespace and line
+    /// ending style.
+    pub fn checksum(&self) -> String {
+        let normalized: Vec<&str> = self.sql.trim().lines().map(str::trim_end).collect();
+        format!(
+            "{:016x}",
+            fnv1a(normalized.join("\n").as_bytes(), 0xcbf2_9ce4_8422_2325)
+        )
+    }
+
+    /// Returns the statements of the migration, without the semicolons
+    /// separating them.
+    ///
+    /// Semicolons in string literals, quoted identifiers and comments do not
+    /// separate statements, and statements made only of comments are
+    /// dropped.
+    pub fn statements(&self) -> Vec<String> {
+        split_statements(&self.sql)
+    }
+}
+
+impl fmt::Display for Migration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "V{} {}", self.version, self.name)
+    }
+}
+
+/// A migration applied by [`Migrator::run`], or that would be in a dry run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MigrationRun {
+    /// The version of the migration.
+    pub version: u64,
+    /// The name of the migration.
+    pub name: String,
+    /// The statements executed, in order.
+    pub statements: Vec<String>,
+    /// The time taken to execute the statements, zero in a dry run.
+    pub elapsed: Duration,
+}
+
+/// The outcome of [`Migrator::run`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MigrationReport {
+    /// The versions that had been applied before the run, in order.
+    pub already_applied: Vec<u64>,
+    /// The migrations applied by the run, or that would be in a dry run.
+    pub migrations: Vec<MigrationRun>,
+    /// Whether the run was a dry run, which executed nothing.
+    pub dry_run: bool,
+}
+
+impl fmt::Display for MigrationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verb = if self.dry_run {
+            "would apply"
+        } else {
+            "applied"
+        };
+        writeln!(
+            f,
+            "{} migration(s) already applied, {} {}",
+            self.already_applied.len(),
+            verb,
+            self.migrations.len()
+        )?;
+        for run in &self.migrations {
+            writeln!(
+                f,
+                "  V{} {} ({} statement(s), {:.3?})",
+                run.version,
+                run.name,
+                run.statements.len(),
+                run.elapsed
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Applies [`Migration`]s in version order, see the [module
+/// documentation](self).
+///
+/// # Example
+///
+/// ```no_run
+/// use dremio_rs::migrate::Migrator;
+/// use dremio_rs::Client;
+///
+/// #[tokio::main]
+/// async fn main() {
+///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+///   let migrator = Migrator::new("lake.meta.schema_migrations")
+///     .unwrap()
+///     .with_dir("migrations")
+///     .unwrap();
+///   let report = migrator.run(&mut client).await.unwrap();
+///   print!("{}", report);
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Migrator {
+    table: Vec<String>,
+    migrations: BTreeMap<u64, Migration>,
+    dry_run: bool,
+}
+
+impl Migrator {
+    /// Creates a migrator recording applied versions in the table `table`, a
+    /// dotted path such as `lake.meta.schema_migrations`.
+    pub fn new(table: &str) -> Result<Self, DremioClientError> {
+        Ok(Self {
+            table: parse_path(table)?,
+            migrations: BTreeMap::new(),
+            dry_run: false,
+        })
+    }
+
+    /// Adds `migration`. Two migrations cannot share a version.
+    pub fn with_migration(mut self, migration: Migration) -> Result<Self, DremioClientError> {
+        if let Some(existing) = self.migrations.get(&migration.version) {
+            return Err(invalid(format!(
+                "migrations {} and {} have the same version",
+                existing, migration
+            )));
+        }
+        self.migrations.insert(migration.version, migration);
+        Ok(self)
+    }
+
+    /// Adds the migration files in `dir`. Files without the `.sql`
+    /// extension are ignored.
+    pub fn with_dir(mut self, dir: impl AsRef<Path>) -> Result<Self, DremioClientError> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_file() && path.extension().is_some_and(|extension| extension == "sql") {
+                self = self.with_migration(Migration::from_file(&path)?)?;
+            }
+        }
+        Ok(self)
+    }
+
+    /// Reports the migrations that would be applied without executing them
+    /// or creating the tracking table.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Returns the migrations, in version order.
+    pub fn migrations(&self) -> impl Iterator<Item = &Migration> {
+        self.migrations.values()
+    }
+
+    /// Applies the migrations that have not been applied yet, in version
+    /// order.
+    ///
+    /// Fails before executing anything if an applied migration was modified
+    /// since, or if a pending migration has a lower version than one already
+    /// applied. Otherwise stops at the first failing statement, see the
+    /// [module documentation](self).
+    pub async fn run<E: DremioExecutor>(
+        &self,
+        executor: &mut E,
+    ) -> Result<MigrationReport, DremioClientError> {
+        let table = quote_path(&self.table);
+        if !self.dry_run {
+            executor
+                .get_record_batches(&format!(
+                    "CREATE TABLE IF NOT EXISTS {} \
+                     (version BIGINT, name VARCHAR, checksum VARCHAR, applied_at TIMESTAMP)",
+                    table
+                ))
+                .await?;
+        }
+        let applied = match self.applied(executor, &table).await {
+            Ok(applied) => applied,
+            // In a dry run on a fresh cluster the table does not exist yet.
+            Err(e) if self.dry_run && e.stale_dataset().is_some() => BTreeMap::new(),
+            Err(e) => return Err(e),
+        };
+        let pending = self.pending(&applied)?;
+        let mut report = MigrationReport {
+            already_applied: applied.keys().copied().collect(),
+            migrations: Vec::new(),
+            dry_run: self.dry_run,
+        };
+        for migration in pending {
+            let statements = migration.statements();
+            let started = Instant::now();
+            if !self.dry_run {
+                for statement in &statements {
+                    executor.get_record_batches(statement).await?;
+                }
+                executor
+                    .get_record_batches(&format!(
+                        "INSERT INTO {} (version, name, checksum, applied_at) \
+                         VALUES ({}, {}, {}, CURRENT_TIMESTAMP)",
+                        table,
+                        migration.version,
+                        Literal::from(migration.name.as_str()),
+                        Literal::from(migration.checksum())
+                    ))
+                    .await?;
+            }
+            report.migrations.push(MigrationRun {
+                version: migration.version,
+                name: migration.name.clone(),
+                statements,
+                elapsed: if self.dry_run {
+                    Duration::ZERO
+                } else {
+                    started.elapsed()
+                },
+            });
+        }
+        Ok(report)
+    }
+
+    /// Reads the applied versions and their checksums from the tracking table.
+    async fn applied<E: DremioExecutor>(
+        &self,
+        executor: &mut E,
+        table: &str,
+    ) -> Result<BTreeMap<u64, String>, DremioClientError> {
+        let batches = executor
+            .get_record_batches(&format!("SELECT version, checksum FROM {}", table))
+            .await?;
+        let mut applied = BTreeMap::new();
+        for batch in batches {
+            read_applied(&batch, &mut applied)?;
+        }
+        Ok(applied)
+    }
+
+    /// Returns the migrations not in `applied`, checking the others have not
+    /// changed.
+    fn pending(
+        &self,
+        applied: &BTreeMap<u64, String>,
+    ) -> Result<Vec<&Migration>, DremioClientError> {
+        let latest = applied.keys().next_back().copied();
+        let mut pending = Vec::new();
+        for migration in self.migrations.values() {
+            match applied.get(&migration.version) {
+                Some(checksum) if *checksum != migration.checksum() => {
+                    return Err(invalid(format!(
+                        "migration {} was modified after it was applied",
+                        migration
+                    )))
+                }
+                Some(_) => {}
+                None if latest.is_some_and(|latest| migration.version < latest) => {
+                    return Err(invalid(format!(
+                        "migration {} is older than the latest applied version V{}",
+                        migration,
+                        latest.unwrap_or_default()
+                    )))
+                }
+                None => pending.push(migration),
+            }
+        }
+        Ok(pending)
+    }
+}
+
+fn read_applied(
+    batch: &RecordBatch,
+    applied: &mut BTreeMap<u64, String>,
+) -> Result<(), DremioClientError> {
+    let versions = cast(batch.column(0), &DataType::Int64)?;
+    let checksums = cast(batch.column(1), &DataType::Utf8)?;
+    let (versions, checksums) = (
+        versions.as_primitive::<Int64Type>(),
+        checksums.as_string::<i32>(),
+    );
+    for row in 0..batch.num_rows() {
+        if versions.is_null(row) {
+            continue;
+        }
+        let version = u64::try_from(versions.value(row))
+            .map_err(|_| invalid(format!("invalid migration version {}", versions.value(row))))?;
+        let checksum = if checksums.is_null(row) {
+            String::new()
+        } else {
+            checksums.value(row).to_string()
+        };
+        applied.insert(version, checksum);
+    }
+    Ok(())
+}
+
+/// Splits `V12__name.sql` or `0012_name.sql` into its version and name.
+fn parse_file_name(file_name: &str) -> Option<(u64, String)> {
+    let stem = file_name.strip_suffix(".sql")?;
+    let stem = stem.strip_prefix(['V', 'v']).unwrap_or(stem);
+    let digits = stem.find(|c: char| !c.is_ascii_digit())?;
+    let version = stem[..digits].parse().ok()?;
+    let name = stem[digits..].trim_start_matches('_');
+    if digits == 0 || name.is_empty() || !stem[digits..].starts_with('_') {
+        return None;
+    }
+    Some((version, name.to_string()))
+}
+
+/// Splits SQL into statements at the semicolons outside of literals, quoted
+/// identifiers and comments.
+fn split_statements(sql: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut has_code = false;
+    let mut chars = sql.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            ';' => {
+                if has_code {
+                    statements.push(current.trim().to_string());
+                }
+                current.clear();
+                has_code = false;
+                continue;
+            }
+            '\'' | '"' => {
+                current.push(c);
+                has_code = true;
+                // A doubled quote inside is an escaped quote, which this
+                // handles as closing and reopening the literal.
+                for next in chars.by_ref() {
+                    current.push(next);
+                    if next == c {
+                        break;
+                    }
+                }
+                continue;
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                current.push(c);
+                for next in chars.by_ref() {
+                    current.push(next);
+                    if next == '\n' {
+                        break;
+                    }
+                }
+                continue;
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                current.push(c);
+                current.extend(chars.next());
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    current.push(next);
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+                continue;
+            }
+            c if !c.is_whitespace() => has_code = true,
+            _ => {}
+        }
+        current.push(c);
+    }
+    if has_code {
+        statements.push(current.trim().to_string());
+    }
+    statements
+}
+
+fn invalid(message: String) -> DremioClientError {
+    ArrowError::InvalidArgumentError(message).into()
+}
@@ -0,0 +1,715 @@
+//! Per-query options.
+
+use crate::blob::{spill_large_cells, SpillOptions};
+use crate::checks::Checks;
+use crate::filter::Predicate;
+use crate::flatten::{flatten_batch, FlattenOptions};
+use crate::mask::Masking;
+use crate::schema::{rename_columns, select_columns, ColumnNaming, SchemaExpectation};
+use crate::stream::{AppMetadataHook, ProgressHook};
+use crate::{DremioClientError, QueryMetrics, SessionOptions};
+use arrow::array::RecordBatch;
+use arrow::datatypes::{DataType, Schema, SchemaRef};
+use arrow_flight::sql::client::FlightSqlServiceClient;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tonic::transport::Channel;
+
+/// How batches from several endpoints are interleaved when they are fetched in
+/// parallel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EndpointOrder {
+    /// Emit all batches of the first endpoint, then the second, and so on,
+    /// matching the order in which Dremio listed the endpoints. This keeps the
+    /// result order of queries with `ORDER BY` intact. Batches of the current
+    /// endpoint are yielded as they arrive, while the endpoints after it read
+    /// ahead a bounded number of batches each until their turn.
+    #[default]
+    Preserve,
+    /// Emit batches as soon as any endpoint produces them. This minimizes
+    /// buffering but interleaves endpoints arbitrarily.
+    AsReady,
+}
+
+/// How timestamp columns in query results are annotated.
+///
+/// Dremio returns timestamps without a timezone even though the values are UTC
+/// instants, which downstream tools often misread as local time. See
+/// [`convert::with_timezone`](crate::convert::with_timezone).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum TimestampMode {
+    /// Leave timestamp columns exactly as returned by Dremio.
+    #[default]
+    AsReturned,
+    /// Annotate timestamp columns as UTC.
+    Utc,
+    /// Annotate timestamp columns with the given timezone, such as
+    /// `"Europe/Berlin"` or `"+05:30"`. The instants are unchanged.
+    Timezone(String),
+}
+
+/// How `DECIMAL` columns in query results are represented.
+///
+/// See [`convert::cast_decimals`](crate::convert::cast_decimals).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DecimalMode {
+    /// Keep decimals as Arrow `Decimal128` columns.
+    #[default]
+    AsReturned,
+    /// Convert decimals to `Float64`, trading precision for convenience.
+    Float64,
+    /// Convert decimals to exact string representations.
+    Utf8,
+}
+
+/// How `INTERVAL` columns in query results are represented.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IntervalMode {
+    /// Keep intervals as Arrow interval columns.
+    #[default]
+    AsReturned,
+    /// Convert intervals to ISO-8601 duration strings such as `P1DT2H`.
+    Iso8601,
+}
+
+/// Options that control how a single query is executed and fetched.
+///
+/// Pass a `QueryOptions` to [`Client::get_record_batches_with_options`](crate::Client::get_record_batches_with_options)
+/// to override the client defaults for one query, or set client-wide defaults
+/// with [`Client::with_query_options`](crate::Client::with_query_options).
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use dremio_rs::QueryOptions;
+///
+/// let options = QueryOptions::new().with_timeout(Duration::from_secs(30));
+/// assert_eq!(options.timeout(), Some(Duration::from_secs(30)));
+/// ```
+#[derive(Clone, Debug)]
+pub struct QueryOptions {
+    timeout: Option<Duration>,
+    prefetch: usize,
+    endpoint_concurrency: usize,
+    endpoint_order: EndpointOrder,
+    batch_size: Option<usize>,
+    timestamps: TimestampMode,
+    decimals: DecimalMode,
+    intervals: IntervalMode,
+    flatten: Option<FlattenOptions>,
+    column_naming: ColumnNaming,
+    filter: Option<Predicate>,
+    selected_columns: Option<Vec<String>>,
+    masking: Option<Masking>,
+    expected_schema: Option<SchemaExpectation>,
+    checks: Checks,
+    spill: Option<SpillOptions>,
+    lossy_utf8: bool,
+    app_metadata: Option<AppMetadataHook>,
+    progress: Option<ProgressHook>,
+    impersonated_user: Option<String>,
+    session: SessionOptions,
+}
+
+impl Default for QueryOptions {
+    fn default() -> Self {
+        Self {
+            timeout: None,
+            prefetch: 0,
+            endpoint_concurrency: 1,
+            endpoint_order: EndpointOrder::default(),
+            batch_size: None,
+            timestamps: TimestampMode::default(),
+            decimals: DecimalMode::default(),
+            intervals: IntervalMode::default(),
+            flatten: None,
+            column_naming: ColumnNaming::default(),
+            filter: None,
+            selected_columns: None,
+            masking: None,
+            expected_schema: None,
+            checks: Checks::default(),
+            spill: None,
+            lossy_utf8: false,
+            app_metadata: None,
+            progress: None,
+            impersonated_user: None,
+            session: SessionOptions::default(),
+        }
+    }
+}
+
+impl QueryOptions {
+    /// Creates options with all settings left at their defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum time the query may take, from submission until the last
+    /// batch has been received.
+    ///
+    /// The remaining time is sent to Dremio as the gRPC deadline (`grpc-timeout`)
+    /// on both the `execute` and `do_get` calls, so the server can abandon the
+    /// job once the deadline has passed. Queries whose results have not all
+    /// been received by then are also cancelled explicitly, see
+    /// [`QueryHandle`](crate::QueryHandle), and so are queries whose stream is
+    /// dropped before its end, as soon as it is dropped.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Returns the configured per-query timeout.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Sets how many batches a [`QueryStream`](crate::QueryStream) reads ahead
+    /// while the consumer is still processing the current batch.
+    ///
+    /// Prefetching overlaps network transfer with processing at the cost of
+    /// holding up to `depth` extra batches in memory. Defaults to `0` (disabled).
+    pub fn with_prefetch(mut self, depth: usize) -> Self {
+        self.prefetch = depth;
+        self
+    }
+
+    /// Returns the configured prefetch depth.
+    pub fn prefetch(&self) -> usize {
+        self.prefetch
+    }
+
+    /// Sets how many endpoints of a multi-endpoint result are fetched at the
+    /// same time. Defaults to `1`, which reads endpoints one after another.
+    pub fn with_endpoint_concurrency(mut self, concurrency: usize) -> Self {
+        self.endpoint_concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Returns the configured endpoint concurrency.
+    pub fn endpoint_concurrency(&self) -> usize {
+        self.endpoint_concurrency
+    }
+
+    /// Sets how batches from endpoints fetched in parallel are ordered.
+    /// Defaults to [`EndpointOrder::Preserve`].
+    pub fn with_endpoint_order(mut self, order: EndpointOrder) -> Self {
+        self.endpoint_order = order;
+        self
+    }
+
+    /// Returns the configured endpoint order.
+    pub fn endpoint_order(&self) -> EndpointOrder {
+        self.endpoint_order
+    }
+
+    /// Re-chunks results into batches of `rows` rows each, the last one
+    /// possibly smaller, however Dremio happened to split them.
+    ///
+    /// Runs of small batches are merged and large batches are split, which
+    /// keeps the row groups of written files and the memory held per batch
+    /// predictable. Zero leaves the batches as they arrive.
+    pub fn with_batch_size(mut self, rows: usize) -> Self {
+        self.batch_size = (rows > 0).then_some(rows);
+        self
+    }
+
+    /// Returns the target number of rows per batch, if re-chunking is enabled.
+    pub fn batch_size(&self) -> Option<usize> {
+        self.batch_size
+    }
+
+    /// Sets how timestamp columns in the results are annotated.
+    pub fn with_timestamp_mode(mut self, mode: TimestampMode) -> Self {
+        self.timestamps = mode;
+        self
+    }
+
+    /// Returns the configured timestamp mode.
+    pub fn timestamp_mode(&self) -> &TimestampMode {
+        &self.timestamps
+    }
+
+    /// Sets how decimal columns in the results are represented.
+    pub fn with_decimal_mode(mut self, mode: DecimalMode) -> Self {
+        self.decimals = mode;
+        self
+    }
+
+    /// Returns the configured decimal mode.
+    pub fn decimal_mode(&self) -> DecimalMode {
+        self.decimals
+    }
+
+    /// Sets how interval columns in the results are represented.
+    pub fn with_interval_mode(mut self, mode: IntervalMode) -> Self {
+        self.intervals = mode;
+        self
+    }
+
+    /// Returns the configured interval mode.
+    pub fn interval_mode(&self) -> IntervalMode {
+        self.intervals
+    }
+
+    /// Flattens nested struct, list and map columns in the results.
+    ///
+    /// See [`flatten`](crate::flatten) for how each nested type is rewritten.
+    pub fn with_flatten(mut self, flatten: FlattenOptions) -> Self {
+        self.flatten = Some(flatten);
+        self
+    }
+
+    /// Returns the configured flattening options, if any.
+    pub fn flatten(&self) -> Option<&FlattenOptions> {
+        self.flatten.as_ref()
+    }
+
+    /// Sets how result column names are normalized. Renaming happens after
+    /// flattening, so flattened `parent.child` names are normalized as well.
+    pub fn with_column_naming(mut self, naming: ColumnNaming) -> Self {
+        self.column_naming = naming;
+        self
+    }
+
+    /// Returns the configured column naming.
+    pub fn column_naming(&self) -> &ColumnNaming {
+        &self.column_naming
+    }
+
+    /// Keeps only the rows matching `predicate`, dropping the others from
+    /// each batch as it arrives.
+    ///
+    /// The predicate sees columns as named after renaming, including columns
+    /// left out by [`select_columns`](Self::select_columns). Filtering in the
+    /// SQL is usually cheaper; this suits queries that cannot be changed.
+    pub fn with_filter(mut self, predicate: Predicate) -> Self {
+        self.filter = Some(predicate);
+        self
+    }
+
+    /// Returns the configured row filter, if any.
+    pub fn filter(&self) -> Option<&Predicate> {
+        self.filter.as_ref()
+    }
+
+    /// Keeps only `columns` in the results, in that order, dropping the other
+    /// columns from each batch as it arrives.
+    ///
+    /// This narrows the results of a fixed query, such as a saved view,
+    /// without changing its SQL. Columns are named as they appear after
+    /// renaming; a missing column fails the query.
+    pub fn select_columns(mut self, columns: &[&str]) -> Self {
+        self.selected_columns = Some(columns.iter().map(|c| c.to_string()).collect());
+        self
+    }
+
+    /// Returns the columns kept in the results, if narrowed with
+    /// [`select_columns`](Self::select_columns).
+    pub fn selected_columns(&self) -> Option<&[String]> {
+        self.selected_columns.as_deref()
+    }
+
+    /// Masks columns of the results, after they have been renamed, for
+    /// example to export personal data to lower environments.
+    pub fn with_masking(mut self, masking: Masking) -> Self {
+        self.masking = Some(masking);
+        self
+    }
+
+    /// Returns the configured column masking, if any.
+    pub fn masking(&self) -> Option<&Masking> {
+        self.masking.as_ref()
+    }
+
+    /// Moves binary and string cells above a size threshold into files.
+    ///
+    /// See [`blob`](crate::blob) for the resulting column layout.
+    pub fn with_spill(mut self, spill: SpillOptions) -> Self {
+        self.spill = Some(spill);
+        self
+    }
+
+    /// Returns the configured spill options, if any.
+    pub fn spill(&self) -> Option<&SpillOptions> {
+        self.spill.as_ref()
+    }
+
+    /// Replaces invalid UTF-8 in string columns with U+FFFD instead of failing
+    /// the query.
+    ///
+    /// The number of repaired values is reported by
+    /// [`QueryStream::stats`](crate::QueryStream::stats). Only top-level string
+    /// columns are repaired; dictionary-encoded results are not supported in
+    /// this mode.
+    pub fn with_lossy_utf8(mut self, lossy: bool) -> Self {
+        self.lossy_utf8 = lossy;
+        self
+    }
+
+    /// Returns whether lossy UTF-8 decoding is enabled.
+    pub fn lossy_utf8(&self) -> bool {
+        self.lossy_utf8
+    }
+
+    /// Calls `hook` with the `app_metadata` of every Flight message that
+    /// carries some, such as progress or job information attached by the
+    /// server.
+    ///
+    /// The metadata is passed as the raw bytes sent by the server. The hook
+    /// runs on the task decoding the results, as messages arrive, so with
+    /// prefetching it may run ahead of the batches being consumed. The number
+    /// of such messages is reported by
+    /// [`QueryStream::stats`](crate::QueryStream::stats).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dremio_rs::QueryOptions;
+    ///
+    /// let options = QueryOptions::new()
+    ///     .with_app_metadata(|metadata| eprintln!("server sent {} bytes", metadata.len()));
+    /// ```
+    pub fn with_app_metadata<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&[u8]) + Send + Sync + 'static,
+    {
+        self.app_metadata = Some(AppMetadataHook(Arc::new(hook)));
+        self
+    }
+
+    pub(crate) fn app_metadata_hook(&self) -> Option<AppMetadataHook> {
+        self.app_metadata.clone()
+    }
+
+    /// Calls `callback` with updated [`QueryMetrics`] after every batch
+    /// fetched, so that progress can be reported by methods that consume the
+    /// results themselves, such as
+    /// [`Client::write_parquet`](crate::Client::write_parquet) when set in the
+    /// client's default options.
+    ///
+    /// The callback runs as batches are fetched, before prefetching, so it
+    /// may run ahead of the batches being written.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dremio_rs::QueryOptions;
+    ///
+    /// let options = QueryOptions::new().with_progress(|metrics| {
+    ///     eprintln!("{} rows, {} bytes in {:?}", metrics.rows, metrics.bytes_transferred, metrics.elapsed)
+    /// });
+    /// ```
+    pub fn with_progress<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&QueryMetrics) + Send + Sync + 'static,
+    {
+        self.progress = Some(ProgressHook(Arc::new(callback)));
+        self
+    }
+
+    /// Publishes updated [`QueryMetrics`] on `sender` after every batch
+    /// fetched, like [`with_progress`](Self::with_progress).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dremio_rs::{QueryMetrics, QueryOptions};
+    /// use tokio::sync::watch;
+    ///
+    /// let (sender, progress) = watch::channel(QueryMetrics::default());
+    /// let options = QueryOptions::new().with_progress_channel(sender);
+    /// assert_eq!(progress.borrow().rows, 0);
+    /// ```
+    pub fn with_progress_channel(self, sender: watch::Sender<QueryMetrics>) -> Self {
+        self.with_progress(move |metrics| {
+            sender.send_replace(metrics.clone());
+        })
+    }
+
+    pub(crate) fn progress_hook(&self) -> Option<ProgressHook> {
+        self.progress.clone()
+    }
+
+    /// Runs the query as `user` rather than as the authenticated user.
+    ///
+    /// This relies on Dremio's inbound impersonation: the authenticated
+    /// user must be allowed to impersonate `user` by an inbound
+    /// impersonation policy, or the query fails. A multi-tenant service can
+    /// then share one connection while each query is planned, authorized and
+    /// recorded in the job history as its end user. Cached results are only
+    /// reused for the same user.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dremio_rs::QueryOptions;
+    ///
+    /// let options = QueryOptions::new().with_impersonation("alice");
+    /// assert_eq!(options.impersonated_user(), Some("alice"));
+    /// ```
+    pub fn with_impersonation(mut self, user: impl Into<String>) -> Self {
+        self.impersonated_user = Some(user.into());
+        self
+    }
+
+    /// Returns the user the query runs as, if impersonating one.
+    pub fn impersonated_user(&self) -> Option<&str> {
+        self.impersonated_user.as_deref()
+    }
+
+    /// Sets Dremio session properties, such as the default schema or the
+    /// routing queue, for this query, overriding those of the client set with
+    /// [`ClientBuilder::with_session_options`](crate::ClientBuilder::with_session_options).
+    /// Cached results are only reused for the same properties.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dremio_rs::{QueryOptions, SessionOptions};
+    ///
+    /// let options = QueryOptions::new()
+    ///     .with_session_options(SessionOptions::new().with_routing_queue("Reporting"));
+    /// assert_eq!(options.session_options().property("routing_queue"), Some("Reporting"));
+    /// ```
+    pub fn with_session_options(mut self, session: SessionOptions) -> Self {
+        self.session = session;
+        self
+    }
+
+    /// Returns the session properties set for the query.
+    pub fn session_options(&self) -> &SessionOptions {
+        &self.session
+    }
+
+    /// Requires the result schema to satisfy `expectation`.
+    ///
+    /// The check runs against the schema announced by Dremio, after the
+    /// configured transformations such as column renaming, and before any data
+    /// is fetched. A mismatch fails the query with an error whose
+    /// [`root`](DremioClientError::root) is [`DremioClientError::SchemaMismatch`].
+    pub fn expect_schema(mut self, expectation: SchemaExpectation) -> Self {
+        self.expected_schema = Some(expectation);
+        self
+    }
+
+    /// Returns the configured schema expectation, if any.
+    pub fn expected_schema(&self) -> Option<&SchemaExpectation> {
+        self.expected_schema.as_ref()
+    }
+
+    /// Evaluates `checks` on the results as they are fetched.
+    ///
+    /// Checks see batches after the configured transformations. A violated
+    /// required check fails the query with an error whose
+    /// [`root`](DremioClientError::root) is [`DremioClientError::CheckFailed`].
+    pub fn with_checks(mut self, checks: Checks) -> Self {
+        self.checks = checks;
+        self
+    }
+
+    /// Returns the data quality checks evaluated on the results.
+    pub fn checks(&self) -> &Checks {
+        &self.checks
+    }
+
+    /// Returns the schema that batches will have once the configured
+    /// transformations have been applied to batches of `schema`.
+    pub(crate) fn transformed_schema(
+        &self,
+        schema: SchemaRef,
+    ) -> Result<SchemaRef, DremioClientError> {
+        Ok(self.transform(RecordBatch::new_empty(schema))?.schema())
+    }
+
+    /// Checks `schema` (after transformations) against the expected schema.
+    pub(crate) fn validate_schema(&self, schema: &Schema) -> Result<(), DremioClientError> {
+        match &self.expected_schema {
+            Some(expectation) => Ok(expectation.validate(schema)?),
+            None => Ok(()),
+        }
+    }
+
+    /// Describes the settings that change the shape of the results, so that
+    /// cached results are only reused for queries with identical settings.
+    pub(crate) fn result_fingerprint(&self) -> String {
+        format!(
+            "{:?}/{:?}/{:?}/{:?}/{:?}/{:?}/{:?}/{:?}/{:?}/{}/{:?}/{:?}/{:?}",
+            self.timestamps,
+            self.decimals,
+            self.intervals,
+            self.flatten,
+            self.column_naming,
+            self.filter,
+            self.selected_columns,
+            self.masking.as_ref().map(Masking::fingerprint),
+            self.spill,
+            self.lossy_utf8,
+            self.batch_size,
+            self.impersonated_user,
+            self.session.headers()
+        )
+    }
+
+    /// Returns a copy of these options without the settings that change the
+    /// shape of the results, keeping those that only affect how they are fetched.
+    pub(crate) fn transport_only(&self) -> QueryOptions {
+        QueryOptions {
+            timeout: self.timeout,
+            prefetch: self.prefetch,
+            endpoint_concurrency: self.endpoint_concurrency,
+            endpoint_order: self.endpoint_order,
+            lossy_utf8: self.lossy_utf8,
+            impersonated_user: self.impersonated_user.clone(),
+            session: self.session.clone(),
+            ..QueryOptions::default()
+        }
+    }
+
+    /// Returns a function applying [`transform`](Self::transform) to each batch
+    /// and validating the schema of the first one, unless it has already been
+    /// validated against the schema announced by the server.
+    pub(crate) fn batch_transformer(
+        &self,
+        schema_checked: bool,
+    ) -> impl FnMut(RecordBatch) -> Result<RecordBatch, DremioClientError> + Send + 'static {
+        let options = self.clone();
+        let mut check_schema = !schema_checked;
+        move |batch| {
+            let batch = options.transform(batch)?;
+            if check_schema {
+                options.validate_schema(&batch.schema())?;
+                check_schema = false;
+            }
+            Ok(batch)
+        }
+    }
+
+    /// Applies the per-batch transformations configured on these options.
+    pub(crate) fn transform(&self, batch: RecordBatch) -> Result<RecordBatch, DremioClientError> {
+        let batch = match &self.timestamps {
+            TimestampMode::AsReturned => batch,
+            TimestampMode::Utc => crate::convert::with_timezone(batch, "UTC")?,
+            TimestampMode::Timezone(tz) => crate::convert::with_timezone(batch, tz)?,
+        };
+        let batch = match self.decimals {
+            DecimalMode::AsReturned => batch,
+            DecimalMode::Float64 => crate::convert::cast_decimals(batch, &DataType::Float64)?,
+            DecimalMode::Utf8 => crate::convert::cast_decimals(batch, &DataType::Utf8)?,
+        };
+        let batch = match self.intervals {
+            IntervalMode::AsReturned => batch,
+            IntervalMode::Iso8601 => crate::convert::cast_intervals(batch)?,
+        };
+        let batch = match &self.flatten {
+            Some(flatten) => flatten_batch(&batch, flatten)?,
+            None => batch,
+        };
+        let batch = rename_columns(batch, &self.column_naming)?;
+        let batch = match &self.filter {
+            Some(predicate) => predicate.filter(&batch)?,
+            None => batch,
+        };
+        let batch = match &self.selected_columns {
+            Some(columns) => select_columns(&batch, columns)?,
+            None => batch,
+        };
+        let batch = match &self.masking {
+            Some(masking) => masking.apply(batch)?,
+            None => batch,
+        };
+        match &self.spill {
+            Some(spill) => spill_large_cells(batch, spill),
+            None => Ok(batch),
+        }
+    }
+
+    pub(crate) fn deadline(&self) -> Option<Deadline> {
+        self.timeout.map(Deadline::after)
+    }
+}
+
+/// A point in time by which a query must complete.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Deadline {
+    at: Instant,
+    timeout: Duration,
+}
+
+impl Deadline {
+    pub(crate) fn after(timeout: Duration) -> Self {
+        Self {
+            at: Instant::now() + timeout,
+            timeout,
+        }
+    }
+
+    /// The time left before the deadline, or `None` if it has already passed.
+    pub(crate) fn remaining(&self) -> Option<Duration> {
+        let remaining = self.at.saturating_duration_since(Instant::now());
+        (!remaining.is_zero()).then_some(remaining)
+    }
+
+    /// The originally configured timeout.
+    pub(crate) fn timeout(&self) -> Duration {
+        self.timeout
+    }
+}
+
+/// Sets the time left before `deadline` as the gRPC deadline of `client`.
+pub(crate) fn apply_deadline(
+    client: &mut FlightSqlServiceClient<Channel>,
+    deadline: Option<Deadline>,
+) -> Result<(), DremioClientError> {
+    if let Some(deadline) = deadline {
+        let remaining = deadline
+            .remaining()
+            .ok_or(DremioClientError::TimeoutError(deadline.timeout()))?;
+        client.set_header("grpc-timeout", grpc_timeout_header(remaining));
+    }
+    Ok(())
+}
+
+/// The header naming the user a query runs as, see
+/// [`QueryOptions::with_impersonation`].
+pub(crate) const IMPERSONATION_HEADER: &str = "impersonation_target";
+
+/// Sets the user `options` impersonate, if any, and their session
+/// properties on `client`.
+pub(crate) fn apply_query_headers(
+    client: &mut FlightSqlServiceClient<Channel>,
+    options: &QueryOptions,
+) {
+    if let Some(user) = options.impersonated_user() {
+        client.set_header(IMPERSONATION_HEADER, user);
+    }
+    for (key, value) in options.session_options().headers() {
+        client.set_header(key, value);
+    }
+}
+
+/// Encodes a duration as a `grpc-timeout` header value.
+///
+/// The gRPC wire format allows at most eight digits followed by a unit, so the
+/// finest unit that can represent the duration is chosen, rounding up.
+pub(crate) fn grpc_timeout_header(timeout: Duration) -> String {
+    const MAX: u128 = 99_999_999;
+    let nanos = timeout.as_nanos();
+    let units: [(u128, &str); 6] = [
+        (1, "n"),
+        (1_000, "u"),
+        (1_000_000, "m"),
+        (1_000_000_000, "S"),
+        (60_000_000_000, "M"),
+        (3_600_000_000_000, "H"),
+    ];
+    for (scale, unit) in units {
+        let value = nanos.div_ceil(scale);
+        if value <= MAX {
+            return format!("{}{}", value, unit);
+        }
+    }
+    format!("{}H", MAX)
+}
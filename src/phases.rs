@@ -0,0 +1,272 @@
+//! Breaking the time a query takes down into job phases.
+//!
+//! A slow query can be waiting in a workload management queue, planning,
+//! executing, or held up by the client fetching its results.
+//! [`Client::trace_phases`](crate::Client::trace_phases) tells them apart by
+//! sampling the state of the query's job in the `sys.jobs_recent` system
+//! table while it runs, and noting when the first batch arrives.
+//!
+//! To find its job, the query is submitted with a comment identifying it
+//! appended on its own line, so error positions are unaffected. The samples
+//! are queries of their own and are only taken as often as asked. When the
+//! system table cannot be read, for example for lack of privileges, only the
+//! phases seen by the client are reported.
+
+use crate::{Client, DremioClientError, QueryOptions};
+use arrow::array::{Array, AsArray, RecordBatch};
+use arrow::compute::cast;
+use arrow::datatypes::DataType;
+use futures::future::{self, Either};
+use futures::TryStreamExt;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// A phase in the life of a query.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum JobPhase {
+    /// Submitted, but the job has not been seen in `sys.jobs_recent` yet.
+    Submitted,
+    /// Retrieving metadata and planning the query.
+    Planning,
+    /// Waiting in a workload management queue or for an engine to start.
+    Queued,
+    /// Running, before the first batch reached the client.
+    Executing,
+    /// Receiving results, from the first batch to the last.
+    Fetching,
+}
+
+impl JobPhase {
+    /// Returns the phase of a job in the Dremio job state `state`, such as
+    /// `ENQUEUED`, or `None` for states that end the job or are unknown.
+    pub fn from_state(state: &str) -> Option<Self> {
+        match state {
+            "PENDING" | "METADATA_RETRIEVAL" | "PLANNING" | "EXECUTION_PLANNING" => {
+                Some(JobPhase::Planning)
+            }
+            "ENQUEUED" | "QUEUED" | "ENGINE_START" => Some(JobPhase::Queued),
+            "STARTING" | "RUNNING" => Some(JobPhase::Executing),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for JobPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            JobPhase::Submitted => "submitted",
+            JobPhase::Planning => "planning",
+            JobPhase::Queued => "queued",
+            JobPhase::Executing => "executing",
+            JobPhase::Fetching => "fetching",
+        })
+    }
+}
+
+/// The start of a phase, as passed to the callback of
+/// [`Client::trace_phases`](crate::Client::trace_phases).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PhaseEvent {
+    /// The phase entered.
+    pub phase: JobPhase,
+    /// The time from submitting the query to noticing the phase.
+    pub at: Duration,
+    /// The Dremio job state the phase was derived from, such as `ENQUEUED`.
+    /// `None` for the phases seen by the client.
+    pub state: Option<String>,
+}
+
+/// The results and phases of a traced query, as returned by
+/// [`Client::trace_phases`](crate::Client::trace_phases).
+#[derive(Clone, Debug)]
+pub struct PhaseReport {
+    /// The batches returned by the query.
+    pub batches: Vec<RecordBatch>,
+    /// The ID of the query's job, if it was found in `sys.jobs_recent`.
+    pub job_id: Option<String>,
+    /// The phases in the order they were entered.
+    pub events: Vec<PhaseEvent>,
+    /// The time from submitting the query to receiving its last batch.
+    pub elapsed: Duration,
+}
+
+impl PhaseReport {
+    /// Returns the time spent in `phase`, as far as the samples tell.
+    ///
+    /// A phase lasts from its event to the next one, so time is attributed
+    /// to a phase only once a sample has seen it, with a delay of up to the
+    /// sampling interval.
+    pub fn time_in(&self, phase: JobPhase) -> Duration {
+        let ends = self
+            .events
+            .iter()
+            .skip(1)
+            .map(|event| event.at)
+            .chain([self.elapsed]);
+        self.events
+            .iter()
+            .zip(ends)
+            .filter(|(event, _)| event.phase == phase)
+            .map(|(event, end)| end.saturating_sub(event.at))
+            .sum()
+    }
+}
+
+impl fmt::Display for PhaseReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.3?} total", self.elapsed)?;
+        for phase in [
+            JobPhase::Submitted,
+            JobPhase::Planning,
+            JobPhase::Queued,
+            JobPhase::Executing,
+            JobPhase::Fetching,
+        ] {
+            if self.events.iter().any(|event| event.phase == phase) {
+                write!(f, ", {} {:.3?}", phase, self.time_in(phase))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The events of a traced query, shared by the query and the sampling.
+struct Trace<F> {
+    started: Instant,
+    job_id: Option<String>,
+    events: Vec<PhaseEvent>,
+    on_event: F,
+}
+
+impl<F: FnMut(&PhaseEvent)> Trace<F> {
+    /// Records entering `phase`, unless the query is already in it.
+    fn enter(&mut self, phase: JobPhase, state: Option<String>) {
+        if self.events.last().map(|event| event.phase) == Some(phase) {
+            return;
+        }
+        let event = PhaseEvent {
+            phase,
+            at: self.started.elapsed(),
+            state,
+        };
+        (self.on_event)(&event);
+        self.events.push(event);
+    }
+
+    fn fetching(&self) -> bool {
+        self.events
+            .last()
+            .is_some_and(|event| event.phase == JobPhase::Fetching)
+    }
+}
+
+/// Runs `query`, sampling the state of its job every `interval`.
+pub(crate) async fn trace_phases<F>(
+    client: &Client,
+    query: &str,
+    interval: Duration,
+    options: &QueryOptions,
+    on_event: F,
+) -> Result<PhaseReport, DremioClientError>
+where
+    F: FnMut(&PhaseEvent) + Send,
+{
+    let marker = marker();
+    let marked = format!("{}\n{}", query.trim_end().trim_end_matches(';'), marker);
+    let trace = Mutex::new(Trace {
+        started: Instant::now(),
+        job_id: None,
+        events: Vec::new(),
+        on_event,
+    });
+    trace.lock().unwrap().enter(JobPhase::Submitted, None);
+
+    let run = async {
+        let mut stream = client.open_query(&marked, options).await?;
+        let mut batches = Vec::new();
+        while let Some(batch) = stream.try_next().await? {
+            if batches.is_empty() {
+                trace.lock().unwrap().enter(JobPhase::Fetching, None);
+            }
+            batches.push(batch);
+        }
+        Ok::<_, DremioClientError>(batches)
+    };
+    let sample = async {
+        let options = options.transport_only();
+        loop {
+            tokio::time::sleep(interval).await;
+            // Sampling is best effort: a failed sample is skipped.
+            let Ok(Some((job_id, state))) = job_state(client, &marker, &options).await else {
+                continue;
+            };
+            let mut trace = trace.lock().unwrap();
+            trace.job_id = Some(job_id);
+            if let Some(phase) = JobPhase::from_state(&state) {
+                if !trace.fetching() {
+                    trace.enter(phase, Some(state));
+                }
+            }
+        }
+    };
+    let batches = match future::select(std::pin::pin!(run), std::pin::pin!(sample)).await {
+        Either::Left((batches, _)) => batches?,
+        // Sampling never ends, but should it, the query still runs to the end.
+        Either::Right(((), run)) => run.await?,
+    };
+    let trace = trace.into_inner().unwrap();
+    Ok(PhaseReport {
+        batches,
+        job_id: trace.job_id,
+        events: trace.events,
+        elapsed: trace.started.elapsed(),
+    })
+}
+
+/// Returns a comment unique to this query, to find its job by.
+fn marker() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    format!(
+        "/* dremio-rs trace {:x}-{:x}-{:x} */",
+        std::process::id(),
+        nanos,
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Returns the ID and state of the job whose SQL ends with `marker`.
+async fn job_state(
+    client: &Client,
+    marker: &str,
+    options: &QueryOptions,
+) -> Result<Option<(String, String)>, DremioClientError> {
+    let sql = format!(
+        "SELECT job_id, status FROM sys.jobs_recent WHERE \"query\" LIKE '%{}' \
+         ORDER BY submitted_ts DESC LIMIT 1",
+        marker
+    );
+    let batches: Vec<RecordBatch> = client
+        .open_query(&sql, options)
+        .await?
+        .collect_batches()
+        .await?;
+    let Some(batch) = batches.iter().find(|batch| batch.num_rows() > 0) else {
+        return Ok(None);
+    };
+    let job_id = cast(batch.column(0), &DataType::Utf8)?;
+    let status = cast(batch.column(1), &DataType::Utf8)?;
+    let (job_id, status) = (job_id.as_string::<i32>(), status.as_string::<i32>());
+    if job_id.is_null(0) || status.is_null(0) {
+        return Ok(None);
+    }
+    Ok(Some((
+        job_id.value(0).to_string(),
+        status.value(0).to_string(),
+    )))
+}
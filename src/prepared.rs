@@ -0,0 +1,184 @@
+//! Prepared statements with bound parameters.
+//!
+//! A [`PreparedStatement`], created by [`Client::prepare`], is planned once by
+//! Dremio and can then be executed any number of times. Values are bound to
+//! its `?` placeholders as Arrow arrays and sent separately from the SQL
+//! text, so user input never needs to be spliced into a query.
+
+use crate::status;
+use crate::{Client, DremioClientError, QueryOptions, QueryPhase, QueryStream};
+use arrow::array::{ArrayRef, RecordBatch};
+use arrow::compute::cast;
+use arrow::datatypes::{Field, Schema};
+use arrow::error::ArrowError;
+use arrow_flight::sql::client::PreparedStatement as FlightPreparedStatement;
+use std::sync::Arc;
+use tonic::transport::Channel;
+
+/// A query planned by Dremio, with parameters bound to its placeholders.
+///
+/// Each call to [`execute`](Self::execute) or
+/// [`execute_update`](Self::execute_update) uses the parameters bound last.
+/// Binding several rows runs the statement once per row. Statements should be
+/// [closed](Self::close) once no longer needed, so that Dremio can free them.
+///
+/// # Example
+///
+/// ```no_run
+/// use arrow::array::{ArrayRef, Int64Array, StringArray};
+/// use dremio_rs::Client;
+/// use std::sync::Arc;
+///
+/// #[tokio::main]
+/// async fn main() {
+///   let client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+///   let mut statement = client
+///     .prepare("SELECT * FROM lake.orders WHERE customer = ? AND quantity > ?")
+///     .await
+///     .unwrap();
+///   let customer: ArrayRef = Arc::new(StringArray::from(vec!["O'Brien"]));
+///   let quantity: ArrayRef = Arc::new(Int64Array::from(vec![10]));
+///   statement.bind_values([customer, quantity]).unwrap();
+///   let batches = statement.execute().await.unwrap().collect_batches().await.unwrap();
+///   statement.close().await.unwrap();
+/// }
+/// ```
+pub struct PreparedStatement<'a> {
+    client: &'a Client,
+    sql: String,
+    options: QueryOptions,
+    inner: FlightPreparedStatement<Channel>,
+    parameter_schema: Schema,
+    dataset_schema: Schema,
+}
+
+impl<'a> PreparedStatement<'a> {
+    pub(crate) async fn prepare(
+        client: &'a Client,
+        sql: &str,
+        options: QueryOptions,
+    ) -> Result<Self, DremioClientError> {
+        let inner = client
+            .query_client(&options)
+            .prepare(sql.to_string(), None)
+            .await
+            .map_err(|e| status::classify(e.into()).in_query(QueryPhase::Execute, sql))?;
+        Ok(PreparedStatement {
+            client,
+            sql: sql.to_string(),
+            options,
+            parameter_schema: inner.parameter_schema()?.clone(),
+            dataset_schema: inner.dataset_schema()?.clone(),
+            inner,
+        })
+    }
+
+    /// Returns the SQL text of the statement.
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// Returns the schema of the parameters, as announced by Dremio, which is
+    /// empty when Dremio does not describe them.
+    pub fn parameter_schema(&self) -> &Schema {
+        &self.parameter_schema
+    }
+
+    /// Returns the schema of the results, as announced by Dremio.
+    pub fn dataset_schema(&self) -> &Schema {
+        &self.dataset_schema
+    }
+
+    /// Binds the columns of `params` to the placeholders, in order, one
+    /// execution per row.
+    ///
+    /// # Returns
+    ///
+    /// An error if Dremio announced the parameters and `params` has another
+    /// number of columns.
+    pub fn bind(&mut self, params: RecordBatch) -> Result<(), DremioClientError> {
+        let expected = self.parameter_schema().fields().len();
+        if expected > 0 && params.num_columns() != expected {
+            return Err(ArrowError::InvalidArgumentError(format!(
+                "statement has {} parameters, {} were bound",
+                expected,
+                params.num_columns()
+            ))
+            .into());
+        }
+        self.inner.set_parameters(params)?;
+        Ok(())
+    }
+
+    /// Binds `values` to the placeholders, in order, each array holding the
+    /// values of one parameter; a single value is an array of length one.
+    ///
+    /// Values are cast to the types Dremio announced for the parameters,
+    /// when it did.
+    pub fn bind_values(
+        &mut self,
+        values: impl IntoIterator<Item = ArrayRef>,
+    ) -> Result<(), DremioClientError> {
+        let values: Vec<ArrayRef> = values.into_iter().collect();
+        let announced = self.parameter_schema().fields().clone();
+        let mut fields = Vec::with_capacity(values.len());
+        let mut columns = Vec::with_capacity(values.len());
+        for (i, value) in values.into_iter().enumerate() {
+            let (field, value) = match announced.get(i) {
+                Some(field) => (
+                    field.as_ref().clone().with_nullable(true),
+                    cast(&value, field.data_type())?,
+                ),
+                None => (
+                    Field::new(format!("${}", i + 1), value.data_type().clone(), true),
+                    value,
+                ),
+            };
+            fields.push(field);
+            columns.push(value);
+        }
+        let params = RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)?;
+        self.bind(params)
+    }
+
+    /// Executes the statement with the bound parameters and returns a stream
+    /// over its results.
+    ///
+    /// The stream is fetched like those of
+    /// [`Client::execute_stream`](crate::Client::execute_stream), with the
+    /// client's default options.
+    pub async fn execute(&mut self) -> Result<QueryStream, DremioClientError> {
+        let deadline = self.options.deadline();
+        let flight_info = self
+            .inner
+            .execute()
+            .await
+            .map_err(|e| status::classify(e.into()).in_query(QueryPhase::Execute, &self.sql))?;
+        let coordinator = self.client.query_client(&self.options);
+        let sql = self.sql.clone();
+        Ok(self
+            .client
+            .open_flight_info(flight_info, coordinator, deadline, &self.options)
+            .map_err(|e| e.in_query(QueryPhase::Execute, &sql))?
+            .map_err(move |e| e.in_query(QueryPhase::Fetch, &sql)))
+    }
+
+    /// Executes the statement, an `INSERT`, `UPDATE`, `DELETE` or DDL
+    /// statement, with the bound parameters.
+    ///
+    /// # Returns
+    ///
+    /// The number of rows affected, as reported by Dremio.
+    pub async fn execute_update(&mut self) -> Result<i64, DremioClientError> {
+        self.inner
+            .execute_update()
+            .await
+            .map_err(|e| status::classify(e.into()).in_query(QueryPhase::Execute, &self.sql))
+    }
+
+    /// Closes the statement, letting Dremio free it.
+    pub async fn close(self) -> Result<(), DremioClientError> {
+        self.inner.close().await?;
+        Ok(())
+    }
+}
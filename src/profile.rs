@@ -0,0 +1,619 @@
+//! Summary statistics over query results.
+//!
+//! A [`Profiler`] computes, for every column, the number of values and nulls,
+//! the minimum and maximum, the mean of numeric columns and an estimate of
+//! the number of distinct values. It is updated one batch at a time and keeps
+//! a fixed amount of state per column, so results of any size can be
+//! profiled as they stream in, for example while they are being exported,
+//! without fetching them a second time.
+//!
+//! With [`ProfileOptions`], a profiler also builds an equi-width histogram of
+//! each numeric column and tracks its most frequent values, for data catalog
+//! enrichment jobs that need more than the basic statistics.
+//!
+//! [`Client::describe_result`](crate::Client::describe_result) profiles the
+//! results of a query in one call.
+
+use crate::DremioClientError;
+use arrow::array::{Array, ArrayRef, AsArray, RecordBatch};
+use arrow::compute::{cast, concat, sort_to_indices, SortOptions};
+use arrow::datatypes::{DataType, Float64Type, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::row::{RowConverter, SortField};
+use arrow::util::display::array_value_to_string;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// Statistics of one result column, as computed by a [`Profiler`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnSummary {
+    /// The column name.
+    pub name: String,
+    /// The column type.
+    pub data_type: DataType,
+    /// The number of non-null values.
+    pub count: u64,
+    /// The number of nulls.
+    pub nulls: u64,
+    /// An estimate of the number of distinct non-null values, within about
+    /// 2% for large counts, unless the type cannot be compared.
+    pub distinct: Option<u64>,
+    /// The smallest value, formatted for display, if the type is ordered.
+    pub min: Option<String>,
+    /// The largest value, formatted for display, if the type is ordered.
+    pub max: Option<String>,
+    /// The mean of the values of numeric columns.
+    pub mean: Option<f64>,
+    /// The distribution of the values of numeric columns, when requested with
+    /// [`ProfileOptions::with_histogram_bins`].
+    pub histogram: Option<Histogram>,
+    /// The most frequent values, most frequent first, when requested with
+    /// [`ProfileOptions::with_top_values`].
+    pub top_values: Vec<ValueCount>,
+}
+
+/// One range of values of a [`Histogram`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bin {
+    /// The smallest value of the range.
+    pub lower: f64,
+    /// The end of the range, excluded except for the last bin.
+    pub upper: f64,
+    /// The number of values in the range.
+    pub count: u64,
+}
+
+/// An equi-width histogram of the finite values of a numeric column.
+///
+/// The range covered grows as values arrive, by doubling the width of the
+/// bins, so it can be somewhat wider than the range of the values, and the
+/// outer bins may be empty. Counts are exact.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Histogram {
+    /// The bins, in increasing order of values.
+    pub bins: Vec<Bin>,
+}
+
+/// A frequent value and how often it occurs, as reported in
+/// [`ColumnSummary::top_values`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValueCount {
+    /// The value, formatted for display.
+    pub value: String,
+    /// The number of occurrences. For columns with many distinct values this
+    /// is an upper bound, at most [`error`](Self::error) above the true count.
+    pub count: u64,
+    /// The largest possible overestimation of [`count`](Self::count); zero
+    /// when the count is exact.
+    pub error: u64,
+}
+
+/// What a [`Profiler`] computes on top of the basic statistics.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProfileOptions {
+    histogram_bins: usize,
+    top_values: usize,
+}
+
+impl ProfileOptions {
+    /// Creates options computing only the basic statistics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds a histogram with `bins` bins for each numeric column. Odd
+    /// numbers are rounded up; zero disables histograms.
+    pub fn with_histogram_bins(mut self, bins: usize) -> Self {
+        self.histogram_bins = bins.div_ceil(2) * 2;
+        self
+    }
+
+    /// Tracks the `count` most frequent values of each column. Zero disables
+    /// the tracking.
+    ///
+    /// Ten times as many candidates are counted per column: counts are exact
+    /// for columns with fewer distinct values than that, and any value found
+    /// in more than one row in `10 * count` is among the candidates.
+    pub fn with_top_values(mut self, count: usize) -> Self {
+        self.top_values = count;
+        self
+    }
+}
+
+/// Statistics of a whole result, as computed by a [`Profiler`].
+///
+/// Its `Display` implementation prints one line per column, in the spirit
+/// of `pandas.DataFrame.describe`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ResultSummary {
+    /// The number of rows.
+    pub rows: u64,
+    /// The statistics of each column, in result order.
+    pub columns: Vec<ColumnSummary>,
+}
+
+impl ResultSummary {
+    /// Returns the statistics of the column `name`.
+    pub fn column(&self, name: &str) -> Option<&ColumnSummary> {
+        self.columns.iter().find(|column| column.name == name)
+    }
+}
+
+impl fmt::Display for ResultSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let header = [
+            "column", "type", "count", "nulls", "distinct", "min", "max", "mean",
+        ];
+        let lines: Vec<[String; 8]> = self
+            .columns
+            .iter()
+            .map(|c| {
+                [
+                    c.name.clone(),
+                    c.data_type.to_string(),
+                    c.count.to_string(),
+                    c.nulls.to_string(),
+                    c.distinct.map(|d| d.to_string()).unwrap_or_default(),
+                    c.min.clone().unwrap_or_default(),
+                    c.max.clone().unwrap_or_default(),
+                    c.mean
+                        .map(|mean| format!("{:.4}", mean))
+                        .unwrap_or_default(),
+                ]
+            })
+            .collect();
+        let mut widths = header.map(str::len);
+        for line in &lines {
+            for (width, cell) in widths.iter_mut().zip(line) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        writeln!(f, "{} rows", self.rows)?;
+        let row = |f: &mut fmt::Formatter<'_>, cells: &[&str]| {
+            let cells: Vec<String> = cells
+                .iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect();
+            writeln!(f, "{}", cells.join("  ").trim_end())
+        };
+        row(f, &header)?;
+        for line in &lines {
+            row(f, &line.each_ref().map(String::as_str))?;
+        }
+        Ok(())
+    }
+}
+
+/// Computes a [`ResultSummary`] incrementally.
+///
+/// # Example
+///
+/// ```
+/// use arrow::array::{Int32Array, RecordBatch};
+/// use dremio_rs::profile::Profiler;
+/// use std::sync::Arc;
+///
+/// let values = Arc::new(Int32Array::from(vec![Some(1), Some(3), None, Some(3)]));
+/// let batch = RecordBatch::try_from_iter([("v", values as _)]).unwrap();
+/// let mut profiler = Profiler::new();
+/// profiler.update(&batch).unwrap();
+/// let summary = profiler.finish();
+/// let v = summary.column("v").unwrap();
+/// assert_eq!((v.count, v.nulls, v.distinct), (3, 1, Some(2)));
+/// assert_eq!(v.max.as_deref(), Some("3"));
+/// ```
+#[derive(Default)]
+pub struct Profiler {
+    options: ProfileOptions,
+    schema: Option<SchemaRef>,
+    rows: u64,
+    columns: Vec<ColumnProfiler>,
+}
+
+impl fmt::Debug for Profiler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Profiler")
+            .field("rows", &self.rows)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Profiler {
+    /// Creates a profiler that has seen no rows.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a profiler that also computes what `options` asks for.
+    pub fn with_options(options: ProfileOptions) -> Self {
+        Self {
+            options,
+            ..Self::default()
+        }
+    }
+
+    /// Adds the rows of `batch` to the statistics.
+    ///
+    /// All batches must have the schema of the first one.
+    pub fn update(&mut self, batch: &RecordBatch) -> Result<(), DremioClientError> {
+        let schema = batch.schema();
+        match &self.schema {
+            Some(expected) if expected.fields() != schema.fields() => {
+                return Err(ArrowError::SchemaError(
+                    "batches with different schemas cannot be profiled together".to_string(),
+                )
+                .into());
+            }
+            Some(_) => {}
+            None => {
+                self.columns = schema
+                    .fields()
+                    .iter()
+                    .map(|field| {
+                        ColumnProfiler::new(field.name(), field.data_type(), &self.options)
+                    })
+                    .collect();
+                self.schema = Some(schema);
+            }
+        }
+        self.rows += batch.num_rows() as u64;
+        for (column, values) in self.columns.iter_mut().zip(batch.columns()) {
+            column.update(values)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the statistics of the rows seen so far.
+    pub fn finish(&self) -> ResultSummary {
+        ResultSummary {
+            rows: self.rows,
+            columns: self.columns.iter().map(ColumnProfiler::summary).collect(),
+        }
+    }
+}
+
+struct ColumnProfiler {
+    name: String,
+    data_type: DataType,
+    count: u64,
+    nulls: u64,
+    converter: Option<RowConverter>,
+    distinct: HyperLogLog,
+    top: Option<SpaceSaving>,
+    ordered: bool,
+    min: Option<ArrayRef>,
+    max: Option<ArrayRef>,
+    sum: Option<f64>,
+    histogram: Option<HistogramBuilder>,
+}
+
+impl ColumnProfiler {
+    fn new(name: &str, data_type: &DataType, options: &ProfileOptions) -> Self {
+        let numeric = data_type.is_numeric();
+        Self {
+            name: name.to_string(),
+            data_type: data_type.clone(),
+            count: 0,
+            nulls: 0,
+            converter: RowConverter::new(vec![SortField::new(data_type.clone())]).ok(),
+            distinct: HyperLogLog::new(),
+            top: (options.top_values > 0).then(|| SpaceSaving::new(options.top_values)),
+            ordered: true,
+            min: None,
+            max: None,
+            sum: numeric.then_some(0.0),
+            histogram: (numeric && options.histogram_bins > 0)
+                .then(|| HistogramBuilder::new(options.histogram_bins)),
+        }
+    }
+
+    fn update(&mut self, values: &ArrayRef) -> Result<(), DremioClientError> {
+        let nulls = values.logical_null_count() as u64;
+        self.nulls += nulls;
+        self.count += values.len() as u64 - nulls;
+
+        if let Some(converter) = &self.converter {
+            let rows = converter.convert_columns(std::slice::from_ref(values))?;
+            let mut counts: HashMap<&[u8], (u64, usize)> = HashMap::new();
+            for (i, row) in rows.iter().enumerate() {
+                if values.is_valid(i) {
+                    self.distinct.insert(row.as_ref());
+                    if self.top.is_some() {
+                        counts.entry(row.data()).or_insert((0, i)).0 += 1;
+                    }
+                }
+            }
+            if let Some(top) = self.top.as_mut() {
+                for (key, (count, row)) in counts {
+                    top.insert(key, count, || array_value_to_string(values, row))?;
+                }
+            }
+        }
+
+        if self.ordered {
+            match (
+                extreme(self.min.take(), values, false),
+                extreme(self.max.take(), values, true),
+            ) {
+                (Ok(min), Ok(max)) => (self.min, self.max) = (min, max),
+                // Types without an ordering, such as structs, have no bounds.
+                _ => self.ordered = false,
+            }
+        }
+
+        if let Some(sum) = self.sum.as_mut() {
+            let values = cast(values, &DataType::Float64)?;
+            let values = values.as_primitive::<Float64Type>();
+            *sum += values.iter().flatten().sum::<f64>();
+            if let Some(histogram) = self.histogram.as_mut() {
+                histogram.update(values.iter().flatten().filter(|value| value.is_finite()));
+            }
+        }
+        Ok(())
+    }
+
+    fn summary(&self) -> ColumnSummary {
+        let display = |value: &Option<ArrayRef>| {
+            value
+                .as_ref()
+                .and_then(|value| array_value_to_string(value, 0).ok())
+        };
+        ColumnSummary {
+            name: self.name.clone(),
+            data_type: self.data_type.clone(),
+            count: self.count,
+            nulls: self.nulls,
+            distinct: self
+                .converter
+                .as_ref()
+                .map(|_| self.distinct.estimate().min(self.count)),
+            min: display(&self.min),
+            max: display(&self.max),
+            mean: self
+                .sum
+                .filter(|_| self.count > 0)
+                .map(|sum| sum / self.count as f64),
+            histogram: self
+                .histogram
+                .as_ref()
+                .and_then(HistogramBuilder::histogram),
+            top_values: self.top.as_ref().map(SpaceSaving::top).unwrap_or_default(),
+        }
+    }
+}
+
+/// Returns a single-element array holding the smallest, or with `descending`
+/// the largest, non-null value among `current` and `values`.
+fn extreme(
+    current: Option<ArrayRef>,
+    values: &ArrayRef,
+    descending: bool,
+) -> Result<Option<ArrayRef>, DremioClientError> {
+    let candidates = match &current {
+        Some(current) => concat(&[current.as_ref(), values.as_ref()])?,
+        None => values.clone(),
+    };
+    let options = SortOptions {
+        descending,
+        nulls_first: false,
+    };
+    let indices = sort_to_indices(&candidates, Some(options), Some(1))?;
+    match indices.iter().flatten().next() {
+        Some(index) if candidates.is_valid(index as usize) => {
+            Ok(Some(candidates.slice(index as usize, 1)))
+        }
+        _ => Ok(current),
+    }
+}
+
+/// Number of bits of the hash selecting a register.
+const HLL_PRECISION: u32 = 12;
+
+/// A HyperLogLog sketch estimating the number of distinct values inserted.
+struct HyperLogLog {
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    fn new() -> Self {
+        Self {
+            registers: vec![0; 1 << HLL_PRECISION],
+        }
+    }
+
+    fn insert(&mut self, value: &[u8]) {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        let hash = hasher.finish();
+        let index = (hash >> (64 - HLL_PRECISION)) as usize;
+        let rest = hash << HLL_PRECISION | 1 << (HLL_PRECISION - 1);
+        let rank = rest.leading_zeros() as u8 + 1;
+        self.registers[index] = self.registers[index].max(rank);
+    }
+
+    fn estimate(&self) -> u64 {
+        let m = self.registers.len() as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|r| 2f64.powi(-i32::from(*r)))
+            .sum();
+        let estimate = alpha * m * m / sum;
+        let zeros = self.registers.iter().filter(|r| **r == 0).count();
+        if estimate <= 2.5 * m && zeros > 0 {
+            // Linear counting is more accurate for small cardinalities.
+            (m * (m / zeros as f64).ln()).round() as u64
+        } else {
+            estimate.round() as u64
+        }
+    }
+}
+
+/// Builds a [`Histogram`] whose range doubles whenever a value falls outside
+/// of it, merging pairs of adjacent bins so that counts stay exact.
+struct HistogramBuilder {
+    origin: f64,
+    width: f64,
+    counts: Vec<u64>,
+}
+
+impl HistogramBuilder {
+    fn new(bins: usize) -> Self {
+        Self {
+            origin: 0.0,
+            width: 0.0,
+            counts: vec![0; bins],
+        }
+    }
+
+    fn update(&mut self, values: impl Iterator<Item = f64> + Clone) {
+        if self.width == 0.0 {
+            // Size the range after the first values seen.
+            let (lo, hi) = values
+                .clone()
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+                    (lo.min(v), hi.max(v))
+                });
+            if lo > hi {
+                return;
+            }
+            let span = hi - lo;
+            self.origin = lo;
+            self.width = if span > 0.0 {
+                span / self.counts.len() as f64
+            } else {
+                lo.abs().max(1.0) / self.counts.len() as f64
+            };
+        }
+        for value in values {
+            let bins = self.counts.len();
+            let index = loop {
+                let end = self.origin + self.width * bins as f64;
+                if value < self.origin {
+                    self.grow(true);
+                } else if value > end {
+                    self.grow(false);
+                } else {
+                    let index = ((value - self.origin) / self.width) as usize;
+                    break index.min(bins - 1);
+                }
+            };
+            self.counts[index] += 1;
+        }
+    }
+
+    /// Doubles the width of the bins, extending the range downwards with
+    /// `lower`, upwards otherwise.
+    fn grow(&mut self, lower: bool) {
+        let bins = self.counts.len();
+        let half = bins / 2;
+        let merged: Vec<u64> = self
+            .counts
+            .chunks(2)
+            .map(|pair| pair.iter().sum())
+            .collect();
+        self.counts = vec![0; bins];
+        let start = if lower { half } else { 0 };
+        self.counts[start..start + half].copy_from_slice(&merged);
+        if lower {
+            self.origin -= self.width * bins as f64;
+        }
+        self.width *= 2.0;
+    }
+
+    fn histogram(&self) -> Option<Histogram> {
+        if self.width == 0.0 {
+            return None;
+        }
+        let bins = self
+            .counts
+            .iter()
+            .enumerate()
+            .map(|(i, count)| Bin {
+                lower: self.origin + self.width * i as f64,
+                upper: self.origin + self.width * (i + 1) as f64,
+                count: *count,
+            })
+            .collect();
+        Some(Histogram { bins })
+    }
+}
+
+/// Tracks the most frequent values with the Space-Saving algorithm: a fixed
+/// number of candidates are counted, and a new value replaces the least
+/// frequent one, inheriting its count as a possible overestimation.
+struct SpaceSaving {
+    top: usize,
+    capacity: usize,
+    candidates: HashMap<Vec<u8>, Candidate>,
+}
+
+struct Candidate {
+    value: String,
+    count: u64,
+    error: u64,
+}
+
+impl SpaceSaving {
+    fn new(top: usize) -> Self {
+        Self {
+            top,
+            capacity: top.saturating_mul(10),
+            candidates: HashMap::new(),
+        }
+    }
+
+    fn insert(
+        &mut self,
+        key: &[u8],
+        count: u64,
+        display: impl FnOnce() -> Result<String, ArrowError>,
+    ) -> Result<(), DremioClientError> {
+        if let Some(candidate) = self.candidates.get_mut(key) {
+            candidate.count += count;
+            return Ok(());
+        }
+        let error = if self.candidates.len() < self.capacity {
+            0
+        } else {
+            let Some((evicted, floor)) = self
+                .candidates
+                .iter()
+                .min_by_key(|(_, candidate)| candidate.count)
+                .map(|(key, candidate)| (key.clone(), candidate.count))
+            else {
+                return Ok(());
+            };
+            self.candidates.remove(&evicted);
+            floor
+        };
+        self.candidates.insert(
+            key.to_vec(),
+            Candidate {
+                value: display()?,
+                count: count + error,
+                error,
+            },
+        );
+        Ok(())
+    }
+
+    fn top(&self) -> Vec<ValueCount> {
+        let mut top: Vec<ValueCount> = self
+            .candidates
+            .values()
+            .map(|candidate| ValueCount {
+                value: candidate.value.clone(),
+                count: candidate.count,
+                error: candidate.error,
+            })
+            .collect();
+        top.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+        top.truncate(self.top);
+        top
+    }
+}
@@ -0,0 +1,194 @@
+//! Rendering results as Markdown and HTML tables.
+//!
+//! [`to_markdown`] and [`to_html`] turn a few batches into a table that can be
+//! dropped into a chat message, a pull request comment or an email. They are
+//! meant for previews: every value is formatted as text, and at most `limit`
+//! rows are rendered, followed by a note when rows were left out.
+//! [`Client::to_markdown`](crate::Client::to_markdown) and
+//! [`Client::to_html`](crate::Client::to_html) run a query and render its
+//! first rows.
+//!
+//! Nulls are rendered as empty cells.
+
+use crate::DremioClientError;
+use arrow::array::RecordBatch;
+use arrow::datatypes::SchemaRef;
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+use std::fmt::Write;
+
+/// Renders the first `limit` rows of `batches` as a GitHub-flavored Markdown
+/// table.
+///
+/// Numeric columns are right-aligned. Pipes are escaped and line breaks
+/// replaced with `<br>`, so every row stays on one line.
+///
+/// # Example
+///
+/// ```
+/// use arrow::array::{Int32Array, RecordBatch, StringArray};
+/// use dremio_rs::render::to_markdown;
+/// use std::sync::Arc;
+///
+/// let batch = RecordBatch::try_from_iter([
+///     ("id", Arc::new(Int32Array::from(vec![1, 2])) as _),
+///     ("name", Arc::new(StringArray::from(vec!["a|b", "c"])) as _),
+/// ])
+/// .unwrap();
+/// assert_eq!(
+///     to_markdown(&[batch], 1).unwrap(),
+///     "| id | name |\n| ---: | --- |\n| 1 | a\\|b |\n\n_Showing the first 1 rows._\n"
+/// );
+/// ```
+pub fn to_markdown(batches: &[RecordBatch], limit: usize) -> Result<String, DremioClientError> {
+    let Some(table) = Table::new(batches, limit)? else {
+        return Ok(String::new());
+    };
+    let mut out = String::new();
+    let cell = |text: &str| {
+        text.replace('\\', "\\\\")
+            .replace('|', "\\|")
+            .replace("\r\n", "<br>")
+            .replace(['\n', '\r'], "<br>")
+    };
+    out.push('|');
+    for field in table.schema.fields() {
+        let _ = write!(out, " {} |", cell(field.name()));
+    }
+    out.push_str("\n|");
+    for field in table.schema.fields() {
+        out.push_str(if field.data_type().is_numeric() {
+            " ---: |"
+        } else {
+            " --- |"
+        });
+    }
+    out.push('\n');
+    for row in &table.rows {
+        out.push('|');
+        for value in row {
+            let _ = write!(out, " {} |", cell(value));
+        }
+        out.push('\n');
+    }
+    if table.truncated {
+        let _ = writeln!(out, "\n_Showing the first {} rows._", table.rows.len());
+    }
+    Ok(out)
+}
+
+/// Renders the first `limit` rows of `batches` as an HTML `<table>`.
+///
+/// Column names and values are escaped. Numeric cells get a
+/// `style="text-align: right"` attribute, so the table reads well in email
+/// clients that ignore style sheets.
+///
+/// # Example
+///
+/// ```
+/// use arrow::array::{RecordBatch, StringArray};
+/// use dremio_rs::render::to_html;
+/// use std::sync::Arc;
+///
+/// let names = Arc::new(StringArray::from(vec!["<b>"]));
+/// let batch = RecordBatch::try_from_iter([("name", names as _)]).unwrap();
+/// assert!(to_html(&[batch], 10).unwrap().contains("<td>&lt;b&gt;</td>"));
+/// ```
+pub fn to_html(batches: &[RecordBatch], limit: usize) -> Result<String, DremioClientError> {
+    let Some(table) = Table::new(batches, limit)? else {
+        return Ok(String::new());
+    };
+    let numeric: Vec<bool> = table
+        .schema
+        .fields()
+        .iter()
+        .map(|field| field.data_type().is_numeric())
+        .collect();
+    let mut out = String::from("<table>\n<thead>\n<tr>");
+    for (field, numeric) in table.schema.fields().iter().zip(&numeric) {
+        let _ = write!(out, "<th{}>{}</th>", align(*numeric), escape(field.name()));
+    }
+    out.push_str("</tr>\n</thead>\n<tbody>\n");
+    for row in &table.rows {
+        out.push_str("<tr>");
+        for (value, numeric) in row.iter().zip(&numeric) {
+            let _ = write!(out, "<td{}>{}</td>", align(*numeric), escape(value));
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</tbody>\n</table>\n");
+    if table.truncated {
+        let _ = writeln!(
+            out,
+            "<p><em>Showing the first {} rows.</em></p>",
+            table.rows.len()
+        );
+    }
+    Ok(out)
+}
+
+fn align(numeric: bool) -> &'static str {
+    if numeric {
+        " style=\"text-align: right\""
+    } else {
+        ""
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// The formatted values of the rows to render.
+struct Table {
+    schema: SchemaRef,
+    rows: Vec<Vec<String>>,
+    truncated: bool,
+}
+
+impl Table {
+    /// Formats the first `limit` rows, or returns `None` if there are no
+    /// columns to render.
+    fn new(batches: &[RecordBatch], limit: usize) -> Result<Option<Self>, DremioClientError> {
+        let Some(first) = batches.first().filter(|batch| batch.num_columns() > 0) else {
+            return Ok(None);
+        };
+        let options = FormatOptions::default();
+        let mut rows = Vec::new();
+        let mut truncated = false;
+        for batch in batches {
+            let formatters = batch
+                .columns()
+                .iter()
+                .map(|column| ArrayFormatter::try_new(column.as_ref(), &options))
+                .collect::<Result<Vec<_>, _>>()?;
+            for row in 0..batch.num_rows() {
+                if rows.len() == limit {
+                    truncated = true;
+                    break;
+                }
+                rows.push(
+                    formatters
+                        .iter()
+                        .map(|formatter| formatter.value(row).to_string())
+                        .collect(),
+                );
+            }
+        }
+        Ok(Some(Self {
+            schema: first.schema(),
+            rows,
+            truncated,
+        }))
+    }
+}
@@ -0,0 +1,1051 @@
+//! Access to Dremio's REST API.
+//!
+//! Flight SQL only runs queries; job status, cancellation and query profiles,
+//! catalog management and reflection refreshes are available through the REST
+//! API served by the coordinator, by default on port 9047. A [`RestClient`]
+//! logs in on its own, or with the credentials of a Flight SQL client through
+//! [`Client::connect_rest`](crate::Client::connect_rest). This module is only
+//! available with the `rest` feature.
+
+use crate::DremioClientError;
+use arrow::array::{ArrayRef, ListBuilder, RecordBatch, StringArray, StringBuilder};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use arrow::json::ReaderBuilder;
+use reqwest::header::AUTHORIZATION;
+use reqwest::{RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A client for Dremio's REST API.
+#[derive(Clone, Debug)]
+pub struct RestClient {
+    http: reqwest::Client,
+    url: String,
+    authorization: String,
+}
+
+/// State of a Dremio job.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum JobState {
+    /// The job has not been submitted yet.
+    NotSubmitted,
+    /// The job is starting.
+    Starting,
+    /// The job is running.
+    Running,
+    /// The job finished successfully.
+    Completed,
+    /// The job was canceled.
+    Canceled,
+    /// The job failed.
+    Failed,
+    /// Cancellation was requested but has not completed yet.
+    CancellationRequested,
+    /// The job is waiting in a workload management queue.
+    Enqueued,
+    /// The job is waiting to be scheduled.
+    Pending,
+    /// Metadata of the queried datasets is being retrieved.
+    MetadataRetrieval,
+    /// The query is being planned.
+    Planning,
+    /// The job is queued for execution.
+    Queued,
+    /// The engine that will run the job is starting.
+    EngineStart,
+    /// The execution plan is being prepared.
+    ExecutionPlanning,
+    /// A state not known to this version of the client.
+    #[serde(other)]
+    Unknown,
+}
+
+impl JobState {
+    /// Returns `true` if the job has finished, successfully or not.
+    pub fn is_terminal(self) -> bool {
+        matches!(
+            self,
+            JobState::Completed | JobState::Canceled | JobState::Failed
+        )
+    }
+}
+
+/// Status of a job, as returned by `GET /api/v3/job/{id}`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Job {
+    /// Current state of the job.
+    pub job_state: JobState,
+    /// Number of rows produced, once known.
+    pub row_count: Option<u64>,
+    /// Error message of a failed job.
+    pub error_message: Option<String>,
+    /// Reason given when the job was canceled.
+    pub cancellation_reason: Option<String>,
+    /// Time the job started, in ISO-8601 format.
+    pub started_at: Option<String>,
+    /// Time the job ended, in ISO-8601 format.
+    pub ended_at: Option<String>,
+    /// How the job was submitted, e.g. `UI_RUN` or `FLIGHT`.
+    pub query_type: Option<String>,
+    /// Name of the workload management queue the job ran in.
+    pub queue_name: Option<String>,
+}
+
+impl Job {
+    /// Returns `true` if the job has finished, successfully or not.
+    pub fn is_terminal(&self) -> bool {
+        self.job_state.is_terminal()
+    }
+}
+
+/// Job operations of a [`RestClient`], as returned by [`RestClient::jobs`].
+#[derive(Clone, Debug)]
+pub struct Jobs<'a> {
+    rest: &'a RestClient,
+    poll_interval: Duration,
+}
+
+impl Jobs<'_> {
+    /// Sets how often the state of a job is checked while waiting for it.
+    /// Defaults to one second.
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Waits for a job to finish, successfully or not.
+    ///
+    /// Use it after statements that start work in the background, such as
+    /// metadata refreshes, `CREATE TABLE AS` or reflection refreshes, to
+    /// continue once the work is done.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The job ID.
+    /// * `timeout` - How long to wait for the job to finish.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(Job)` with the final state and statistics of the job, including
+    ///   jobs that failed or were canceled.
+    /// - `Err(DremioClientError::TimeoutError)` if the job is still running
+    ///   after `timeout`, or another `Err(DremioClientError)` if its status
+    ///   cannot be retrieved.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::rest::{JobState, RestClient};
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///    let rest = RestClient::login("http://localhost:9047", "dremio", "dremio123").await.unwrap();
+    ///    let job = rest
+    ///        .jobs()
+    ///        .wait("1a2b3c4d-0000-0000-0000-000000000000", Duration::from_secs(600))
+    ///        .await
+    ///        .unwrap();
+    ///    if job.job_state != JobState::Completed {
+    ///        eprintln!("job did not complete: {:?}", job.error_message);
+    ///    }
+    /// }
+    /// ```
+    pub async fn wait(&self, id: &str, timeout: Duration) -> Result<Job, DremioClientError> {
+        let start = Instant::now();
+        loop {
+            let job = self.rest.get_job(id).await?;
+            if job.is_terminal() {
+                return Ok(job);
+            }
+            let remaining = timeout.saturating_sub(start.elapsed());
+            if remaining.is_zero() {
+                return Err(DremioClientError::TimeoutError(timeout));
+            }
+            tokio::time::sleep(self.poll_interval.min(remaining)).await;
+        }
+    }
+}
+
+/// Catalog operations of a [`RestClient`], as returned by
+/// [`RestClient::catalog`].
+///
+/// Entities are exchanged as the JSON objects of Dremio's catalog API, whose
+/// fields depend on their `entityType`: sources, spaces, folders and
+/// datasets. Every entity carries a `tag`, the version it was read at;
+/// updates and deletions given a tag fail if the entity has changed since.
+///
+/// # Example
+///
+/// ```no_run
+/// use dremio_rs::rest::RestClient;
+///
+/// #[tokio::main]
+/// async fn main() {
+///    let rest = RestClient::login("http://localhost:9047", "dremio", "dremio123").await.unwrap();
+///    let catalog = rest.catalog();
+///    let view = catalog
+///        .create_view(&["marts", "revenue"], "SELECT region, SUM(amount) AS amount FROM lake.sales GROUP BY region")
+///        .await
+///        .unwrap();
+///    let id = view["id"].as_str().unwrap();
+///    catalog.delete(id, view["tag"].as_str()).await.unwrap();
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Catalog<'a> {
+    rest: &'a RestClient,
+}
+
+impl Catalog<'_> {
+    /// Returns the entity at `path`, such as `["lake", "sales", "orders"]`.
+    pub async fn get(&self, path: &[&str]) -> Result<Value, DremioClientError> {
+        let url = self.rest.catalog_url(path)?;
+        Ok(self
+            .rest
+            .send(self.rest.http.get(url))
+            .await?
+            .json()
+            .await?)
+    }
+
+    /// Returns the entity with the ID `id`.
+    pub async fn get_by_id(&self, id: &str) -> Result<Value, DremioClientError> {
+        self.rest.get(&format!("/api/v3/catalog/{}", id)).await
+    }
+
+    /// Creates an entity, such as a space, source or view, from its JSON
+    /// description, and returns it as created, with its ID and tag.
+    pub async fn create(&self, entity: &Value) -> Result<Value, DremioClientError> {
+        let request = self
+            .rest
+            .http
+            .post(format!("{}/api/v3/catalog", self.rest.url))
+            .json(entity);
+        Ok(self.rest.send(request).await?.json().await?)
+    }
+
+    /// Creates a view at `path` defined by `sql`, and returns it as created.
+    pub async fn create_view(&self, path: &[&str], sql: &str) -> Result<Value, DremioClientError> {
+        self.create(&serde_json::json!({
+            "entityType": "dataset",
+            "type": "VIRTUAL_DATASET",
+            "path": path,
+            "sql": sql,
+        }))
+        .await
+    }
+
+    /// Replaces the entity with the ID `id` by `entity`, which should carry
+    /// the tag it was read at, and returns it as updated.
+    pub async fn update(&self, id: &str, entity: &Value) -> Result<Value, DremioClientError> {
+        let request = self
+            .rest
+            .http
+            .put(format!("{}/api/v3/catalog/{}", self.rest.url, id))
+            .json(entity);
+        Ok(self.rest.send(request).await?.json().await?)
+    }
+
+    /// Deletes the entity with the ID `id`, along with its children. With a
+    /// `tag`, nothing is deleted if the entity has changed since.
+    pub async fn delete(&self, id: &str, tag: Option<&str>) -> Result<(), DremioClientError> {
+        let mut request = self
+            .rest
+            .http
+            .delete(format!("{}/api/v3/catalog/{}", self.rest.url, id));
+        if let Some(tag) = tag {
+            request = request.query(&[("tag", tag)]);
+        }
+        self.rest.send(request).await?;
+        Ok(())
+    }
+}
+
+/// An object found by [`RestClient::search`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchHit {
+    /// The path of the object, such as `["lake", "sales", "orders"]`.
+    pub path: Vec<String>,
+    /// The kind of object, such as `TABLE`, `VIEW`, `FOLDER` or `SOURCE`.
+    pub object_type: String,
+    /// The tags (labels) of the object.
+    pub tags: Vec<String>,
+    /// The start of the object's wiki, if it has one.
+    pub wiki_snippet: Option<String>,
+}
+
+/// The datasets a dataset is built from and the datasets built from it, as
+/// returned by [`RestClient::lineage`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+pub struct Lineage {
+    /// The datasets the dataset reads from directly.
+    #[serde(default)]
+    pub parents: Vec<LineageNode>,
+    /// The views that read from the dataset directly.
+    #[serde(default)]
+    pub children: Vec<LineageNode>,
+}
+
+/// A dataset in a [`Lineage`].
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineageNode {
+    /// The catalog ID of the dataset.
+    pub id: String,
+    /// The path of the dataset, such as `["lake", "sales", "orders"]`.
+    #[serde(default)]
+    pub path: Vec<String>,
+    /// The kind of dataset: `VIRTUAL` for views, `PROMOTED` or `DIRECT` for
+    /// tables.
+    pub dataset_type: Option<String>,
+}
+
+/// A reflection suggested by Dremio, as returned by
+/// [`RestClient::recommend_reflections`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReflectionRecommendation {
+    /// `RAW` or `AGGREGATION`.
+    pub reflection_type: String,
+    /// The columns a raw reflection stores.
+    pub display_fields: Vec<String>,
+    /// The columns an aggregation reflection groups by.
+    pub dimension_fields: Vec<String>,
+    /// The columns an aggregation reflection aggregates.
+    pub measure_fields: Vec<String>,
+    /// The columns the reflection is partitioned by.
+    pub partition_fields: Vec<String>,
+    /// The columns the reflection is sorted by.
+    pub sort_fields: Vec<String>,
+    /// The recommendation as returned by Dremio, which can be submitted to
+    /// the reflection API to create the reflection.
+    pub definition: Value,
+}
+
+#[derive(Deserialize)]
+struct Recommendations {
+    #[serde(default)]
+    data: Vec<Value>,
+}
+
+/// Restricts search to catalog objects, in Dremio's filter syntax.
+const SEARCH_FILTER: &str = r#"category in ["TABLE", "VIEW", "FOLDER", "SPACE", "SOURCE"]"#;
+
+/// The longest wiki snippet returned by search, in characters.
+const WIKI_SNIPPET: usize = 200;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchPage {
+    #[serde(default)]
+    results: Vec<SearchResult>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchResult {
+    #[serde(default)]
+    category: String,
+    catalog_object: Option<SearchObject>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchObject {
+    #[serde(default)]
+    path: Vec<String>,
+    #[serde(rename = "type")]
+    object_type: Option<String>,
+    #[serde(default)]
+    labels: Vec<String>,
+    wiki: Option<String>,
+}
+
+/// The most rows Dremio returns per page of job results.
+const RESULTS_PAGE: usize = 500;
+
+#[derive(Deserialize)]
+struct SubmittedJob {
+    id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JobResults {
+    row_count: u64,
+    #[serde(default)]
+    schema: Vec<ResultField>,
+    #[serde(default)]
+    rows: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+struct ResultField {
+    name: String,
+    #[serde(rename = "type")]
+    data_type: Value,
+}
+
+#[derive(Deserialize)]
+struct CatalogEntity {
+    id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CatalogItem {
+    #[serde(default)]
+    path: Vec<String>,
+    #[serde(default)]
+    entity_type: String,
+    #[serde(default)]
+    children: Vec<CatalogChild>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CatalogChild {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct Grants {
+    #[serde(default)]
+    grants: Vec<Grant>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Grant {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    grantee_type: String,
+    #[serde(default)]
+    privileges: Vec<String>,
+}
+
+/// Who holds which privileges on the objects of a catalog subtree, as
+/// returned by [`RestClient::privileges`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PrivilegeReport {
+    /// One entry per object and grantee, objects in breadth-first order.
+    pub entries: Vec<PrivilegeEntry>,
+}
+
+/// The privileges one user or role holds on one object.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrivilegeEntry {
+    /// The path of the object, such as `["lake", "sales", "orders"]`.
+    pub path: Vec<String>,
+    /// The kind of object, such as `space`, `folder` or `dataset`.
+    pub object_type: String,
+    /// The name of the user or role.
+    pub grantee: String,
+    /// `USER` or `ROLE`.
+    pub grantee_type: String,
+    /// The privileges granted, such as `SELECT` or `ALTER`.
+    pub privileges: Vec<String>,
+}
+
+impl PrivilegeReport {
+    /// Returns the entries of the user or role named `grantee`.
+    pub fn for_grantee<'a>(&'a self, grantee: &'a str) -> impl Iterator<Item = &'a PrivilegeEntry> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.grantee == grantee)
+    }
+
+    /// Returns the report as a record batch with the columns `path` (the
+    /// quoted path), `object_type`, `grantee`, `grantee_type` and
+    /// `privileges` (a list of strings), to be written out or queried.
+    pub fn to_record_batch(&self) -> Result<RecordBatch, DremioClientError> {
+        let text = |f: fn(&PrivilegeEntry) -> String| -> ArrayRef {
+            Arc::new(StringArray::from_iter_values(self.entries.iter().map(f)))
+        };
+        let mut privileges = ListBuilder::new(StringBuilder::new());
+        for entry in &self.entries {
+            privileges.append_value(entry.privileges.iter().map(Some));
+        }
+        Ok(RecordBatch::try_from_iter([
+            ("path", text(|entry| crate::sql::quote_path(&entry.path))),
+            ("object_type", text(|entry| entry.object_type.clone())),
+            ("grantee", text(|entry| entry.grantee.clone())),
+            ("grantee_type", text(|entry| entry.grantee_type.clone())),
+            ("privileges", Arc::new(privileges.finish()) as ArrayRef),
+        ])?)
+    }
+}
+
+#[derive(Deserialize)]
+struct LoginResponse {
+    token: String,
+}
+
+impl RestClient {
+    /// Logs in with a user name and password.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The base URL of the REST API (e.g., "http://localhost:9047").
+    /// * `user` - The username for authentication.
+    /// * `pass` - The password for authentication.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::rest::RestClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///    let rest = RestClient::login("http://localhost:9047", "dremio", "dremio123").await.unwrap();
+    ///    let job = rest.get_job("1a2b3c4d-0000-0000-0000-000000000000").await.unwrap();
+    ///    println!("{:?}", job.job_state);
+    /// }
+    /// ```
+    pub async fn login(url: &str, user: &str, pass: &str) -> Result<Self, DremioClientError> {
+        let url = url.trim_end_matches('/').to_string();
+        let http = reqwest::Client::new();
+        let response = http
+            .post(format!("{}/apiv2/login", url))
+            .json(&serde_json::json!({ "userName": user, "password": pass }))
+            .send()
+            .await?;
+        let login: LoginResponse = check(response).await?.json().await?;
+        Ok(Self {
+            http,
+            url,
+            authorization: format!("_dremio{}", login.token),
+        })
+    }
+
+    /// Creates a client that authenticates with a personal access token.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The base URL of the REST API (e.g., "http://localhost:9047").
+    /// * `token` - The personal access token.
+    pub fn with_token(url: &str, token: &str) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            url: url.trim_end_matches('/').to_string(),
+            authorization: format!("Bearer {}", token),
+        }
+    }
+
+    /// Returns the base URL of the REST API.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Returns the status of a job.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The job ID.
+    pub async fn get_job(&self, id: &str) -> Result<Job, DremioClientError> {
+        self.get(&format!("/api/v3/job/{}", id)).await
+    }
+
+    /// Returns the job operations of this client, such as waiting for a job
+    /// to finish.
+    pub fn jobs(&self) -> Jobs<'_> {
+        Jobs {
+            rest: self,
+            poll_interval: Duration::from_secs(1),
+        }
+    }
+
+    /// Returns the catalog operations of this client, to read, create,
+    /// update and delete sources, spaces, folders and views.
+    pub fn catalog(&self) -> Catalog<'_> {
+        Catalog { rest: self }
+    }
+
+    /// Requests cancellation of a running job.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The job ID.
+    pub async fn cancel_job(&self, id: &str) -> Result<(), DremioClientError> {
+        let request = self
+            .http
+            .post(format!("{}/api/v3/job/{}/cancel", self.url, id));
+        self.send(request).await?;
+        Ok(())
+    }
+
+    /// Returns the query profile of a job as JSON.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The job ID.
+    /// * `attempt` - The attempt to return; `0` is the first attempt.
+    pub async fn job_profile(&self, id: &str, attempt: u32) -> Result<Value, DremioClientError> {
+        self.get(&format!("/apiv2/profiles/{}.json?attempt={}", id, attempt))
+            .await
+    }
+
+    /// Runs a SQL query through the REST API and converts its JSON results
+    /// to record batches.
+    ///
+    /// This is a fallback for networks where only the REST port is open; it
+    /// is much slower than Flight SQL, as results travel as JSON in pages of
+    /// 500 rows, and loses some type fidelity: binary, interval and nested
+    /// columns are returned as strings, timestamps with millisecond
+    /// precision.
+    ///
+    /// # Arguments
+    ///
+    /// * `sql` - The SQL query string to execute.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(Vec<RecordBatch>)` with a batch per page of results, or a single
+    ///   empty batch carrying the schema if there are no rows.
+    /// - `Err(DremioClientError::JobFailed)` if the query fails, or another
+    ///   `Err(DremioClientError)` if a request fails or the results cannot be
+    ///   converted.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::rest::RestClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///    let rest = RestClient::login("http://localhost:9047", "dremio", "dremio123").await.unwrap();
+    ///    let batches = rest.execute_via_rest("SELECT * FROM sys.options").await.unwrap();
+    ///    println!("{:?}", batches);
+    /// }
+    /// ```
+    pub async fn execute_via_rest(&self, sql: &str) -> Result<Vec<RecordBatch>, DremioClientError> {
+        let request = self
+            .http
+            .post(format!("{}/api/v3/sql", self.url))
+            .json(&serde_json::json!({ "sql": sql }));
+        let submitted: SubmittedJob = self.send(request).await?.json().await?;
+        let job = self
+            .jobs()
+            .with_poll_interval(Duration::from_millis(250))
+            .wait(&submitted.id, Duration::MAX)
+            .await?;
+        if job.job_state != JobState::Completed {
+            return Err(DremioClientError::JobFailed {
+                job_id: submitted.id,
+                message: job
+                    .error_message
+                    .or(job.cancellation_reason)
+                    .unwrap_or_else(|| format!("job finished as {:?}", job.job_state)),
+            });
+        }
+        let mut schema = None;
+        let mut batches = Vec::new();
+        let mut offset = 0;
+        loop {
+            let page: JobResults = self
+                .get(&format!(
+                    "/api/v3/job/{}/results?offset={}&limit={}",
+                    submitted.id, offset, RESULTS_PAGE
+                ))
+                .await?;
+            let schema = schema.get_or_insert_with(|| result_schema(&page.schema));
+            let rows = page.rows.len();
+            if rows > 0 {
+                batches.push(rows_to_batch(schema, page.rows)?);
+            }
+            offset += rows;
+            if rows == 0 || offset as u64 >= page.row_count {
+                break;
+            }
+        }
+        if batches.is_empty() {
+            let schema = schema.unwrap_or_else(|| Arc::new(Schema::empty()));
+            batches.push(RecordBatch::new_empty(schema));
+        }
+        Ok(batches)
+    }
+
+    /// Searches the catalog for objects whose name, path, tags or wiki
+    /// match `query`, as the search box of the Dremio UI does.
+    ///
+    /// Results that are not catalog objects, such as jobs, are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The words to search for.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::rest::RestClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///    let rest = RestClient::login("http://localhost:9047", "dremio", "dremio123").await.unwrap();
+    ///    for hit in rest.search("customer churn").await.unwrap() {
+    ///        println!("{} {}", hit.object_type, hit.path.join("."));
+    ///    }
+    /// }
+    /// ```
+    pub async fn search(&self, query: &str) -> Result<Vec<SearchHit>, DremioClientError> {
+        let mut hits = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut body = serde_json::json!({ "query": query, "filter": SEARCH_FILTER });
+            if let Some(token) = &page_token {
+                body["pageToken"] = Value::from(token.as_str());
+            }
+            let request = self
+                .http
+                .post(format!("{}/api/v3/search", self.url))
+                .json(&body);
+            let page: SearchPage = self.send(request).await?.json().await?;
+            hits.extend(page.results.into_iter().filter_map(|result| {
+                let object = result.catalog_object?;
+                Some(SearchHit {
+                    path: object.path,
+                    object_type: object.object_type.unwrap_or(result.category),
+                    tags: object.labels,
+                    wiki_snippet: object.wiki.as_deref().and_then(wiki_snippet),
+                })
+            }));
+            match page.next_page_token {
+                Some(token) if !token.is_empty() => page_token = Some(token),
+                _ => return Ok(hits),
+            }
+        }
+    }
+
+    /// Returns the ID of a dataset, space, source or folder in the catalog.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the entity, such as `["lake", "sales", "orders"]`.
+    pub async fn catalog_id(&self, path: &[&str]) -> Result<String, DremioClientError> {
+        let url = self.catalog_url(path)?;
+        let entity: CatalogEntity = self.send(self.http.get(url)).await?.json().await?;
+        Ok(entity.id)
+    }
+
+    /// Returns the URL of the catalog entity at `path`, with its components
+    /// percent-encoded.
+    fn catalog_url(&self, path: &[&str]) -> Result<reqwest::Url, DremioClientError> {
+        let invalid = || DremioClientError::ConfigError(format!("invalid URL {:?}", self.url));
+        let mut url = reqwest::Url::parse(&self.url).map_err(|_| invalid())?;
+        url.path_segments_mut()
+            .map_err(|()| invalid())?
+            .pop_if_empty()
+            .extend(["api", "v3", "catalog", "by-path"])
+            .extend(path);
+        Ok(url)
+    }
+
+    /// Returns the direct parents and children of a dataset in the catalog
+    /// graph.
+    ///
+    /// Following the children of each child in turn gives every view a
+    /// change to the dataset can affect.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the dataset, such as `["lake", "sales", "orders"]`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::rest::RestClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///    let rest = RestClient::login("http://localhost:9047", "dremio", "dremio123").await.unwrap();
+    ///    let lineage = rest.lineage(&["marts", "revenue"]).await.unwrap();
+    ///    for child in &lineage.children {
+    ///        println!("affects {}", child.path.join("."));
+    ///    }
+    /// }
+    /// ```
+    pub async fn lineage(&self, path: &[&str]) -> Result<Lineage, DremioClientError> {
+        let id = self.catalog_id(path).await?;
+        self.get(&format!("/api/v3/catalog/{}/graph", id)).await
+    }
+
+    /// Returns the reflections Dremio recommends for a dataset, based on the
+    /// queries run against it.
+    ///
+    /// Recommendations are only available on Dremio versions that provide
+    /// them; others answer with a [`DremioClientError::RestError`].
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the dataset, such as `["lake", "sales", "orders"]`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::rest::RestClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///    let rest = RestClient::login("http://localhost:9047", "dremio", "dremio123").await.unwrap();
+    ///    for recommendation in rest.recommend_reflections(&["lake", "orders"]).await.unwrap() {
+    ///        println!(
+    ///            "{}: dimensions {:?}, measures {:?}",
+    ///            recommendation.reflection_type,
+    ///            recommendation.dimension_fields,
+    ///            recommendation.measure_fields
+    ///        );
+    ///    }
+    /// }
+    /// ```
+    pub async fn recommend_reflections(
+        &self,
+        path: &[&str],
+    ) -> Result<Vec<ReflectionRecommendation>, DremioClientError> {
+        let id = self.catalog_id(path).await?;
+        let request = self.http.post(format!(
+            "{}/api/v3/dataset/{}/reflection/recommendation",
+            self.url, id
+        ));
+        let recommendations: Recommendations = self.send(request).await?.json().await?;
+        Ok(recommendations
+            .data
+            .into_iter()
+            .map(|definition| {
+                let names = |key: &str| -> Vec<String> {
+                    definition
+                        .get(key)
+                        .and_then(Value::as_array)
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|field| field.get("name")?.as_str().map(str::to_string))
+                        .collect()
+                };
+                ReflectionRecommendation {
+                    reflection_type: definition
+                        .get("type")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string(),
+                    display_fields: names("displayFields"),
+                    dimension_fields: names("dimensionFields"),
+                    measure_fields: names("measureFields"),
+                    partition_fields: names("partitionFields"),
+                    sort_fields: names("sortFields"),
+                    definition,
+                }
+            })
+            .collect())
+    }
+
+    /// Lists who holds which privileges on an object and everything below it
+    /// in the catalog, such as a space with its folders and views.
+    ///
+    /// Every object takes two requests, one for its children and one for its
+    /// grants, so large subtrees take a while. Grants require an edition of
+    /// Dremio with access control; others answer with a
+    /// [`DremioClientError::RestError`].
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the root of the subtree, such as `["marketing"]`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::rest::RestClient;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///    let rest = RestClient::login("http://localhost:9047", "dremio", "dremio123").await.unwrap();
+    ///    let report = rest.privileges(&["marketing"]).await.unwrap();
+    ///    for entry in report.for_grantee("analysts") {
+    ///        println!("{}: {:?}", entry.path.join("."), entry.privileges);
+    ///    }
+    /// }
+    /// ```
+    pub async fn privileges(&self, path: &[&str]) -> Result<PrivilegeReport, DremioClientError> {
+        let mut report = PrivilegeReport::default();
+        let mut pending = VecDeque::from([self.catalog_id(path).await?]);
+        while let Some(id) = pending.pop_front() {
+            let mut page_token: Option<String> = None;
+            let item = loop {
+                let mut query = vec![("maxChildren", "1000".to_string())];
+                query.extend(page_token.take().map(|token| ("pageToken", token)));
+                let request = self
+                    .http
+                    .get(format!("{}/api/v3/catalog/{}", self.url, id))
+                    .query(&query);
+                let item: CatalogItem = self.send(request).await?.json().await?;
+                pending.extend(item.children.iter().map(|child| child.id.clone()));
+                match &item.next_page_token {
+                    Some(token) if !token.is_empty() => page_token = Some(token.clone()),
+                    _ => break item,
+                }
+            };
+            let grants: Grants = self.get(&format!("/api/v3/catalog/{}/grants", id)).await?;
+            report
+                .entries
+                .extend(grants.grants.into_iter().map(|grant| PrivilegeEntry {
+                    path: item.path.clone(),
+                    object_type: item.entity_type.clone(),
+                    grantee: grant.name,
+                    grantee_type: grant.grantee_type,
+                    privileges: grant.privileges,
+                }));
+        }
+        Ok(report)
+    }
+
+    /// Triggers a refresh of every reflection that depends on a dataset.
+    ///
+    /// Returns once the refresh has been scheduled; the reflections report
+    /// their progress in the `sys.reflections` system table.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the dataset, such as `["lake", "sales", "orders"]`.
+    pub async fn refresh_reflections(&self, path: &[&str]) -> Result<(), DremioClientError> {
+        let id = self.catalog_id(path).await?;
+        let request = self
+            .http
+            .post(format!("{}/api/v3/catalog/{}/refresh", self.url, id));
+        self.send(request).await?;
+        Ok(())
+    }
+
+    /// Triggers a refresh of a single reflection, such as one listed in the
+    /// `sys.reflections` system table.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The reflection ID.
+    pub async fn refresh_reflection(&self, id: &str) -> Result<(), DremioClientError> {
+        let request = self
+            .http
+            .post(format!("{}/api/v3/reflection/{}/refresh", self.url, id));
+        self.send(request).await?;
+        Ok(())
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, DremioClientError> {
+        let request = self.http.get(format!("{}{}", self.url, path));
+        Ok(self.send(request).await?.json().await?)
+    }
+
+    async fn send(&self, request: RequestBuilder) -> Result<Response, DremioClientError> {
+        let response = request
+            .header(AUTHORIZATION, &self.authorization)
+            .send()
+            .await?;
+        check(response).await
+    }
+}
+
+/// Returns the first paragraph of `wiki`, cut to at most [`WIKI_SNIPPET`]
+/// characters, or `None` if it is blank.
+fn wiki_snippet(wiki: &str) -> Option<String> {
+    let paragraph = wiki.trim().split("\n\n").next()?.trim();
+    if paragraph.is_empty() {
+        return None;
+    }
+    let mut snippet: String = paragraph.chars().take(WIKI_SNIPPET).collect();
+    if snippet.len() < paragraph.len() {
+        snippet.push('…');
+    }
+    Some(snippet.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// Builds the Arrow schema of job results from the schema Dremio reports.
+fn result_schema(fields: &[ResultField]) -> SchemaRef {
+    let fields: Vec<Field> = fields
+        .iter()
+        .map(|field| Field::new(&field.name, arrow_type(&field.data_type), true))
+        .collect();
+    Arc::new(Schema::new(fields))
+}
+
+/// Maps a Dremio type, such as `{"name": "DECIMAL", "precision": 10,
+/// "scale": 2}`, to the Arrow type its JSON values are decoded as.
+fn arrow_type(data_type: &Value) -> DataType {
+    let int = |key: &str| data_type.get(key).and_then(Value::as_u64);
+    match data_type
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+    {
+        "BOOLEAN" => DataType::Boolean,
+        "INTEGER" => DataType::Int32,
+        "BIGINT" => DataType::Int64,
+        "FLOAT" => DataType::Float32,
+        "DOUBLE" => DataType::Float64,
+        "DECIMAL" => match (int("precision"), int("scale")) {
+            (Some(precision @ 1..=38), Some(scale)) if scale <= precision => {
+                DataType::Decimal128(precision as u8, scale as i8)
+            }
+            _ => DataType::Utf8,
+        },
+        "DATE" => DataType::Date32,
+        "TIME" => DataType::Time32(TimeUnit::Millisecond),
+        "TIMESTAMP" => DataType::Timestamp(TimeUnit::Millisecond, None),
+        _ => DataType::Utf8,
+    }
+}
+
+/// Decodes a page of JSON rows into a batch of `schema`.
+fn rows_to_batch(
+    schema: &SchemaRef,
+    mut rows: Vec<Value>,
+) -> Result<RecordBatch, DremioClientError> {
+    // Values of string columns that are not strings, such as lists, are kept
+    // as their JSON text.
+    for row in &mut rows {
+        let Some(row) = row.as_object_mut() else {
+            continue;
+        };
+        for field in schema.fields() {
+            if field.data_type() != &DataType::Utf8 {
+                continue;
+            }
+            if let Some(value) = row.get_mut(field.name()) {
+                if !value.is_string() && !value.is_null() {
+                    *value = Value::String(value.to_string());
+                }
+            }
+        }
+    }
+    let mut decoder = ReaderBuilder::new(schema.clone())
+        .with_batch_size(rows.len().max(1))
+        .build_decoder()?;
+    decoder.serialize(&rows)?;
+    Ok(decoder
+        .flush()?
+        .unwrap_or_else(|| RecordBatch::new_empty(schema.clone())))
+}
+
+/// Turns unsuccessful responses into errors, using the message Dremio returns
+/// in the body when there is one.
+async fn check(response: Response) -> Result<Response, DremioClientError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    let message = serde_json::from_str::<Value>(&body)
+        .ok()
+        .and_then(|v| v.get("errorMessage")?.as_str().map(str::to_string))
+        .unwrap_or(body);
+    Err(DremioClientError::RestError {
+        status: status.as_u16(),
+        message,
+    })
+}
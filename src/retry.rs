@@ -0,0 +1,168 @@
+//! Retrying requests that failed for transient reasons.
+//!
+//! A coordinator restarting, a load balancer dropping idle connections or a
+//! network blip make requests fail with `UNAVAILABLE`, although the same
+//! request would succeed a moment later. With a [`RetryPolicy`] set through
+//! [`ClientBuilder::with_retry_policy`](crate::ClientBuilder::with_retry_policy),
+//! submitting queries, fetching their results and the catalog metadata calls
+//! are retried with exponential backoff.
+//!
+//! The channel to the coordinator reconnects by itself on the next request
+//! once the connection is lost. Session tokens do not survive a coordinator
+//! restart though: a request rejected as `UNAUTHENTICATED` by a client
+//! authenticated with a user name and password repeats the handshake and is
+//! retried with the new token.
+//!
+//! Only the start of a result stream is retried: once batches have been
+//! received, an error ends the stream as before.
+
+use crate::status::status_code;
+use crate::DremioClientError;
+use std::future::Future;
+use std::time::Duration;
+use tonic::Code;
+
+/// How failed requests are retried.
+///
+/// The default policy makes up to three attempts, waiting 100 milliseconds
+/// before the second and 200 before the third, and retries `UNAVAILABLE`
+/// errors only.
+///
+/// # Example
+///
+/// ```no_run
+/// use dremio_rs::retry::RetryPolicy;
+/// use dremio_rs::ClientBuilder;
+/// use std::time::Duration;
+///
+/// #[tokio::main]
+/// async fn main() {
+///   let policy = RetryPolicy::new()
+///     .with_max_attempts(5)
+///     .with_initial_backoff(Duration::from_millis(250))
+///     .with_retryable_codes([tonic::Code::Unavailable, tonic::Code::ResourceExhausted]);
+///   let mut client = ClientBuilder::new("http://localhost:32010")
+///     .with_credentials("dremio", "dremio123")
+///     .with_retry_policy(policy)
+///     .connect()
+///     .await
+///     .unwrap();
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+    retryable: Vec<Code>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            multiplier: 2.0,
+            retryable: vec![Code::Unavailable],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Creates the default policy.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a policy that makes a single attempt, which is how clients
+    /// behave unless a policy is set.
+    pub fn none() -> Self {
+        Self::default().with_max_attempts(1)
+    }
+
+    /// Sets how many times a request is attempted in total, including the
+    /// first attempt. Zero is taken as one.
+    pub fn with_max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    /// Sets how long to wait before the first retry.
+    pub fn with_initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Sets the longest wait between two attempts.
+    pub fn with_max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// Sets the factor by which the wait grows after every retry. Factors
+    /// below one are taken as one.
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    /// Sets the gRPC status codes of the errors to retry, replacing the
+    /// default `UNAVAILABLE`.
+    pub fn with_retryable_codes(mut self, codes: impl IntoIterator<Item = Code>) -> Self {
+        self.retryable = codes.into_iter().collect();
+        self
+    }
+
+    /// Returns how many times a request is attempted in total.
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Returns the gRPC status codes of the errors retried.
+    pub fn retryable_codes(&self) -> &[Code] {
+        &self.retryable
+    }
+
+    /// Returns how long to wait after the failed attempt number `attempt`,
+    /// counting from one, before the next.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let backoff = self.initial_backoff.as_secs_f64() * self.multiplier.powi(exponent);
+        Duration::try_from_secs_f64(backoff)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff)
+    }
+
+    /// Returns whether `error` is worth retrying, going by its status code.
+    pub fn is_retryable(&self, error: &DremioClientError) -> bool {
+        status_code(error).is_some_and(|code| self.retryable.contains(&code))
+    }
+
+    /// Returns how long to wait before retrying after the failed attempt
+    /// number `attempt`, or `None` if the request should not be retried.
+    pub(crate) fn delay(&self, error: &DremioClientError, attempt: u32) -> Option<Duration> {
+        (attempt < self.max_attempts && self.is_retryable(error)).then(|| self.backoff(attempt))
+    }
+
+    /// Runs `attempt` until it succeeds, fails with an error that is not
+    /// retryable, or the attempts are used up.
+    pub(crate) async fn run<T, F, Fut>(&self, mut attempt: F) -> Result<T, DremioClientError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, DremioClientError>>,
+    {
+        let mut attempts = 1;
+        loop {
+            match attempt().await {
+                Err(e) => match self.delay(&e, attempts) {
+                    Some(delay) => tokio::time::sleep(delay).await,
+                    None => return Err(e),
+                },
+                result => return result,
+            }
+            attempts += 1;
+        }
+    }
+}
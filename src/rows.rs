@@ -0,0 +1,715 @@
+//! Reading query results as Rust values.
+//!
+//! Rows of a `RecordBatch` can be deserialized into any type implementing
+//! serde's `Deserialize`, typically a struct whose fields are named after the
+//! columns. This module is only available with the `serde` feature.
+//!
+//! Columns map to Rust values as follows:
+//!
+//! - integers, floats and booleans to the corresponding Rust types, checking
+//!   that values fit;
+//! - strings to `String` or unit enum variants;
+//! - decimals to floats, or to their exact text for other types, such as
+//!   `rust_decimal::Decimal`;
+//! - lists to sequences, structs and maps to structs or maps;
+//! - nulls to `None`;
+//! - other types, such as dates and timestamps, to their text, for example
+//!   `2024-01-31T10:11:12`, which `chrono` types parse.
+//!
+//! Fields missing from the results are an error unless they are `Option`s or
+//! have a `#[serde(default)]`. Columns without a field are ignored.
+
+use crate::{DremioClientError, QueryStream};
+use arrow::array::{
+    Array, ArrayRef, AsArray, BooleanArray, GenericListArray, MapArray, OffsetSizeTrait,
+    RecordBatch, StructArray,
+};
+use arrow::compute::cast;
+use arrow::datatypes::{self as types, DataType, Schema};
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+use futures::{Stream, StreamExt};
+use serde::de::{
+    self,
+    value::{SeqDeserializer, StringDeserializer},
+    DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+};
+use std::fmt;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+/// A row that could not be deserialized.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RowError {
+    /// The index of the row in its batch, when known.
+    pub row: Option<usize>,
+    /// The column whose value could not be deserialized, when known.
+    pub column: Option<String>,
+    /// What went wrong, such as `invalid type: string "x", expected i32`.
+    pub message: String,
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.row, &self.column) {
+            (Some(row), Some(column)) => write!(f, "row {}, column {:?}: ", row, column)?,
+            (Some(row), None) => write!(f, "row {}: ", row)?,
+            (None, Some(column)) => write!(f, "column {:?}: ", column)?,
+            (None, None) => {}
+        }
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for RowError {}
+
+impl de::Error for RowError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self {
+            row: None,
+            column: None,
+            message: msg.to_string(),
+        }
+    }
+}
+
+/// Deserializes every row of `batch`.
+///
+/// # Example
+///
+/// ```
+/// use arrow::array::{Int64Array, RecordBatch, StringArray};
+/// use serde::Deserialize;
+/// use std::sync::Arc;
+///
+/// #[derive(Deserialize)]
+/// struct City {
+///     name: String,
+///     population: Option<i64>,
+/// }
+///
+/// let batch = RecordBatch::try_from_iter([
+///     ("name", Arc::new(StringArray::from(vec!["Lyon", "Nice"])) as _),
+///     ("population", Arc::new(Int64Array::from(vec![Some(522_250), None])) as _),
+/// ])
+/// .unwrap();
+/// let cities: Vec<City> = dremio_rs::rows::from_batch(&batch).unwrap();
+/// assert_eq!(cities[0].population, Some(522_250));
+/// assert_eq!(cities[1].population, None);
+/// ```
+pub fn from_batch<T: DeserializeOwned>(batch: &RecordBatch) -> Result<Vec<T>, DremioClientError> {
+    let batch = normalize(batch)?;
+    (0..batch.num_rows())
+        .map(|row| deserialize_row(&batch, row))
+        .collect()
+}
+
+/// Deserializes row `row` of `batch`, which must have been normalized.
+fn deserialize_row<T: DeserializeOwned>(
+    batch: &RecordBatch,
+    row: usize,
+) -> Result<T, DremioClientError> {
+    T::deserialize(RowDeserializer { batch, row }).map_err(|mut e| {
+        e.row = Some(row);
+        DremioClientError::RowError(e)
+    })
+}
+
+/// Decodes dictionary columns, so values can be read without looking them up.
+fn normalize(batch: &RecordBatch) -> Result<RecordBatch, DremioClientError> {
+    if !batch
+        .schema()
+        .fields()
+        .iter()
+        .any(|field| matches!(field.data_type(), DataType::Dictionary(_, _)))
+    {
+        return Ok(batch.clone());
+    }
+    let mut fields = Vec::with_capacity(batch.num_columns());
+    let mut columns = Vec::with_capacity(batch.num_columns());
+    for (field, column) in batch.schema().fields().iter().zip(batch.columns()) {
+        match field.data_type() {
+            DataType::Dictionary(_, value) => {
+                fields.push(
+                    field
+                        .as_ref()
+                        .clone()
+                        .with_data_type(value.as_ref().clone()),
+                );
+                columns.push(cast(column, value)?);
+            }
+            _ => {
+                fields.push(field.as_ref().clone());
+                columns.push(column.clone());
+            }
+        }
+    }
+    Ok(RecordBatch::try_new(
+        Arc::new(Schema::new(fields)),
+        columns,
+    )?)
+}
+
+/// Streams the rows of a query as values of `T`, as returned by
+/// [`QueryStream::typed`] and [`Client::cursor`](crate::Client::cursor).
+///
+/// Rows are deserialized one at a time as they are read, so only the current
+/// batch is held in memory, plus the batches read ahead when the query sets
+/// a prefetch depth (see
+/// [`QueryOptions::with_prefetch`](crate::QueryOptions::with_prefetch)).
+pub struct TypedCursor<T> {
+    stream: QueryStream,
+    batch: Option<RecordBatch>,
+    row: usize,
+    done: bool,
+    _type: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> TypedCursor<T> {
+    pub(crate) fn new(stream: QueryStream) -> Self {
+        Self {
+            stream,
+            batch: None,
+            row: 0,
+            done: false,
+            _type: PhantomData,
+        }
+    }
+
+    /// Returns the next row, or `None` once all rows have been read.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Order {
+    ///   id: i64,
+    ///   amount: f64,
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), dremio_rs::DremioClientError> {
+    ///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await?;
+    ///   let mut cursor = client.cursor::<Order>("SELECT id, amount FROM lake.orders").await?;
+    ///   while let Some(order) = cursor.try_next().await? {
+    ///     println!("{}: {}", order.id, order.amount);
+    ///   }
+    ///   Ok(())
+    /// }
+    /// ```
+    pub async fn try_next(&mut self) -> Result<Option<T>, DremioClientError> {
+        self.next().await.transpose()
+    }
+}
+
+impl<T: DeserializeOwned> Stream for TypedCursor<T> {
+    type Item = Result<T, DremioClientError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        loop {
+            if let Some(batch) = &this.batch {
+                if this.row < batch.num_rows() {
+                    let item = deserialize_row(batch, this.row);
+                    this.row += 1;
+                    if item.is_err() {
+                        this.done = true;
+                        this.batch = None;
+                    }
+                    return Poll::Ready(Some(item));
+                }
+                this.batch = None;
+            }
+            if this.done {
+                return Poll::Ready(None);
+            }
+            let item = match futures::ready!(this.stream.poll_next_unpin(cx)) {
+                Some(Ok(batch)) => normalize(&batch),
+                Some(Err(e)) => Err(e),
+                None => {
+                    this.done = true;
+                    return Poll::Ready(None);
+                }
+            };
+            match item {
+                Ok(batch) => {
+                    this.batch = Some(batch);
+                    this.row = 0;
+                }
+                Err(e) => {
+                    this.done = true;
+                    return Poll::Ready(Some(Err(e)));
+                }
+            }
+        }
+    }
+}
+
+impl QueryStream {
+    /// Turns the stream into a cursor over its rows as values of `T`. See
+    /// [`rows`](crate::rows) for how columns map to Rust values.
+    pub fn typed<T: DeserializeOwned>(self) -> TypedCursor<T> {
+        TypedCursor::new(self)
+    }
+}
+
+/// Deserializes a row of a batch as a map from column names to values, or as
+/// a sequence of values.
+struct RowDeserializer<'a> {
+    batch: &'a RecordBatch,
+    row: usize,
+}
+
+impl<'a> RowDeserializer<'a> {
+    /// The deserializer of the only column, for rows read as a single value.
+    fn single(&self) -> Result<CellDeserializer<'a>, RowError> {
+        match self.batch.columns() {
+            [column] => Ok(CellDeserializer {
+                array: column.as_ref(),
+                row: self.row,
+            }),
+            columns => Err(de::Error::custom(format!(
+                "expected a single column, found {}",
+                columns.len()
+            ))),
+        }
+    }
+
+    fn fields(&self) -> Fields<'a> {
+        let schema = self.batch.schema_ref();
+        Fields {
+            names: schema.fields().iter().map(|f| f.name().as_str()).collect(),
+            columns: self.batch.columns().iter().map(|c| c.as_ref()).collect(),
+            row: self.row,
+            next: 0,
+        }
+    }
+}
+
+macro_rules! forward_to_single {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RowError> {
+                self.single()?.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for RowDeserializer<'_> {
+    type Error = RowError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RowError> {
+        visitor.visit_map(self.fields())
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, RowError> {
+        visitor.visit_map(self.fields())
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RowError> {
+        visitor.visit_map(self.fields())
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RowError> {
+        visitor.visit_seq(self.fields())
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, RowError> {
+        visitor.visit_seq(self.fields())
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, RowError> {
+        visitor.visit_seq(self.fields())
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, RowError> {
+        self.single()?.deserialize_newtype_struct(name, visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, RowError> {
+        self.single()?.deserialize_enum(name, variants, visitor)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, RowError> {
+        visitor.visit_unit()
+    }
+
+    forward_to_single! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char deserialize_str
+        deserialize_string deserialize_bytes deserialize_byte_buf deserialize_option
+        deserialize_unit deserialize_identifier deserialize_ignored_any
+    }
+}
+
+/// The named values of a row or of a struct value.
+struct Fields<'a> {
+    names: Vec<&'a str>,
+    columns: Vec<&'a dyn Array>,
+    row: usize,
+    next: usize,
+}
+
+impl<'de> MapAccess<'de> for Fields<'_> {
+    type Error = RowError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, RowError> {
+        let Some(name) = self.names.get(self.next) else {
+            return Ok(None);
+        };
+        seed.deserialize(name.into_deserializer()).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, RowError> {
+        let index = self.next;
+        self.next += 1;
+        let cell = CellDeserializer {
+            array: self.columns[index],
+            row: self.row,
+        };
+        seed.deserialize(cell).map_err(|mut e| {
+            // Name the outermost column; nested fields add to the message.
+            e.column = Some(match e.column.take() {
+                Some(inner) => format!("{}.{}", self.names[index], inner),
+                None => self.names[index].to_string(),
+            });
+            e
+        })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.names.len() - self.next)
+    }
+}
+
+impl<'de> SeqAccess<'de> for Fields<'_> {
+    type Error = RowError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, RowError> {
+        if self.next >= self.columns.len() {
+            return Ok(None);
+        }
+        self.next_value_seed(seed).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.columns.len() - self.next)
+    }
+}
+
+/// Deserializes the value of one cell of an array.
+struct CellDeserializer<'a> {
+    array: &'a dyn Array,
+    row: usize,
+}
+
+impl CellDeserializer<'_> {
+    /// Formats the value as Arrow displays it.
+    fn text(&self) -> Result<String, RowError> {
+        let options = FormatOptions::default();
+        let formatter = ArrayFormatter::try_new(self.array, &options)
+            .map_err(<RowError as de::Error>::custom)?;
+        Ok(formatter.value(self.row).to_string())
+    }
+
+    /// Returns the value of a decimal cell as a float.
+    fn decimal_f64(&self) -> Option<f64> {
+        match self.array.data_type() {
+            DataType::Decimal32(_, scale) => {
+                let value = self
+                    .array
+                    .as_primitive::<types::Decimal32Type>()
+                    .value(self.row);
+                Some(value as f64 / 10f64.powi(*scale as i32))
+            }
+            DataType::Decimal64(_, scale) => {
+                let value = self
+                    .array
+                    .as_primitive::<types::Decimal64Type>()
+                    .value(self.row);
+                Some(value as f64 / 10f64.powi(*scale as i32))
+            }
+            DataType::Decimal128(_, scale) => {
+                let value = self
+                    .array
+                    .as_primitive::<types::Decimal128Type>()
+                    .value(self.row);
+                Some(value as f64 / 10f64.powi(*scale as i32))
+            }
+            DataType::Decimal256(_, _) => self.text().ok()?.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+fn visit_list<'de, O: OffsetSizeTrait, V: Visitor<'de>>(
+    list: &GenericListArray<O>,
+    row: usize,
+    visitor: V,
+) -> Result<V::Value, RowError> {
+    visitor.visit_seq(Elements {
+        values: list.value(row),
+        next: 0,
+    })
+}
+
+fn visit_struct<'de, V: Visitor<'de>>(
+    array: &StructArray,
+    row: usize,
+    visitor: V,
+) -> Result<V::Value, RowError> {
+    visitor.visit_map(Fields {
+        names: array.fields().iter().map(|f| f.name().as_str()).collect(),
+        columns: array.columns().iter().map(|c| c.as_ref()).collect(),
+        row,
+        next: 0,
+    })
+}
+
+fn visit_map<'de, V: Visitor<'de>>(
+    array: &MapArray,
+    row: usize,
+    visitor: V,
+) -> Result<V::Value, RowError> {
+    let entries = array.value(row);
+    visitor.visit_map(Entries {
+        keys: entries.column(0).clone(),
+        values: entries.column(1).clone(),
+        next: 0,
+    })
+}
+
+impl<'de> de::Deserializer<'de> for CellDeserializer<'_> {
+    type Error = RowError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RowError> {
+        let (array, row) = (self.array, self.row);
+        if array.is_null(row) {
+            return visitor.visit_none();
+        }
+        match array.data_type() {
+            DataType::Null => visitor.visit_none(),
+            DataType::Boolean => {
+                let array: &BooleanArray = array.as_boolean();
+                visitor.visit_bool(array.value(row))
+            }
+            DataType::Int8 => visitor.visit_i8(array.as_primitive::<types::Int8Type>().value(row)),
+            DataType::Int16 => {
+                visitor.visit_i16(array.as_primitive::<types::Int16Type>().value(row))
+            }
+            DataType::Int32 => {
+                visitor.visit_i32(array.as_primitive::<types::Int32Type>().value(row))
+            }
+            DataType::Int64 => {
+                visitor.visit_i64(array.as_primitive::<types::Int64Type>().value(row))
+            }
+            DataType::UInt8 => {
+                visitor.visit_u8(array.as_primitive::<types::UInt8Type>().value(row))
+            }
+            DataType::UInt16 => {
+                visitor.visit_u16(array.as_primitive::<types::UInt16Type>().value(row))
+            }
+            DataType::UInt32 => {
+                visitor.visit_u32(array.as_primitive::<types::UInt32Type>().value(row))
+            }
+            DataType::UInt64 => {
+                visitor.visit_u64(array.as_primitive::<types::UInt64Type>().value(row))
+            }
+            DataType::Float16 => visitor.visit_f32(
+                array
+                    .as_primitive::<types::Float16Type>()
+                    .value(row)
+                    .to_f32(),
+            ),
+            DataType::Float32 => {
+                visitor.visit_f32(array.as_primitive::<types::Float32Type>().value(row))
+            }
+            DataType::Float64 => {
+                visitor.visit_f64(array.as_primitive::<types::Float64Type>().value(row))
+            }
+            DataType::Utf8 => visitor.visit_str(array.as_string::<i32>().value(row)),
+            DataType::LargeUtf8 => visitor.visit_str(array.as_string::<i64>().value(row)),
+            DataType::Utf8View => visitor.visit_str(array.as_string_view().value(row)),
+            DataType::Binary => visitor.visit_bytes(array.as_binary::<i32>().value(row)),
+            DataType::LargeBinary => visitor.visit_bytes(array.as_binary::<i64>().value(row)),
+            DataType::BinaryView => visitor.visit_bytes(array.as_binary_view().value(row)),
+            DataType::FixedSizeBinary(_) => {
+                visitor.visit_bytes(array.as_fixed_size_binary().value(row))
+            }
+            DataType::List(_) => visit_list(array.as_list::<i32>(), row, visitor),
+            DataType::LargeList(_) => visit_list(array.as_list::<i64>(), row, visitor),
+            DataType::FixedSizeList(_, _) => visitor.visit_seq(Elements {
+                values: array.as_fixed_size_list().value(row),
+                next: 0,
+            }),
+            DataType::Struct(_) => visit_struct(array.as_struct(), row, visitor),
+            DataType::Map(_, _) => visit_map(array.as_map(), row, visitor),
+            _ => visitor.visit_string(self.text()?),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RowError> {
+        if self.array.is_null(self.row) {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RowError> {
+        match self.decimal_f64() {
+            Some(value) if !self.array.is_null(self.row) => visitor.visit_f32(value as f32),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RowError> {
+        match self.decimal_f64() {
+            Some(value) if !self.array.is_null(self.row) => visitor.visit_f64(value),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RowError> {
+        // Binary values read as sequences of bytes, such as `Vec<u8>`.
+        let bytes = match self.array.data_type() {
+            _ if self.array.is_null(self.row) => None,
+            DataType::Binary => Some(self.array.as_binary::<i32>().value(self.row)),
+            DataType::LargeBinary => Some(self.array.as_binary::<i64>().value(self.row)),
+            DataType::BinaryView => Some(self.array.as_binary_view().value(self.row)),
+            DataType::FixedSizeBinary(_) => Some(self.array.as_fixed_size_binary().value(self.row)),
+            _ => None,
+        };
+        match bytes {
+            Some(bytes) => visitor.visit_seq(SeqDeserializer::new(bytes.iter().copied())),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, RowError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, RowError> {
+        // Unit variants are read from their name.
+        let name: StringDeserializer<RowError> = self.text()?.into_deserializer();
+        visitor.visit_enum(name)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 char str string bytes byte_buf
+        unit unit_struct tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// The elements of a list value.
+struct Elements {
+    values: ArrayRef,
+    next: usize,
+}
+
+impl<'de> SeqAccess<'de> for Elements {
+    type Error = RowError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, RowError> {
+        if self.next >= self.values.len() {
+            return Ok(None);
+        }
+        let row = self.next;
+        self.next += 1;
+        seed.deserialize(CellDeserializer {
+            array: self.values.as_ref(),
+            row,
+        })
+        .map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.values.len() - self.next)
+    }
+}
+
+/// The entries of a map value.
+struct Entries {
+    keys: ArrayRef,
+    values: ArrayRef,
+    next: usize,
+}
+
+impl<'de> MapAccess<'de> for Entries {
+    type Error = RowError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, RowError> {
+        if self.next >= self.keys.len() {
+            return Ok(None);
+        }
+        seed.deserialize(CellDeserializer {
+            array: self.keys.as_ref(),
+            row: self.next,
+        })
+        .map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, RowError> {
+        let row = self.next;
+        self.next += 1;
+        seed.deserialize(CellDeserializer {
+            array: self.values.as_ref(),
+            row,
+        })
+    }
+}
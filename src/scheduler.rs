@@ -0,0 +1,385 @@
+//! Running queries and exports on a schedule.
+//!
+//! A [`Scheduler`] runs registered [`Job`]s on cron expressions or fixed
+//! intervals for as long as its [`SchedulerHandle`] is kept, so that a small
+//! extract service does not need an external orchestrator. A job whose
+//! previous run is still going when it is due again is skipped rather than
+//! started twice, failed runs can be retried, and a completion hook is told
+//! about every run.
+//!
+//! Cron expressions have the usual five fields — minute, hour, day of month,
+//! month and day of week — and are evaluated in UTC.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use dremio_rs::scheduler::{Job, Schedule, Scheduler};
+//! use dremio_rs::Client;
+//! use std::time::Duration;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!   let job = Job::new("nightly", Schedule::cron("0 2 * * *").unwrap(), || async {
+//!     let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await?;
+//!     client.get_record_batches("SELECT * FROM lake.daily_totals").await?;
+//!     Ok(())
+//!   })
+//!   .with_retries(3, Duration::from_secs(30));
+//!   let _scheduler = Scheduler::new()
+//!     .with_job(job)
+//!     .on_complete(|report| eprintln!("{}: {:?}", report.job, report.outcome))
+//!     .start();
+//!   tokio::signal::ctrl_c().await.unwrap();
+//! }
+//! ```
+
+use crate::DremioClientError;
+use arrow::error::ArrowError;
+use chrono::{DateTime, Datelike, TimeDelta, TimeZone, Timelike, Utc};
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinSet;
+
+/// When a [`Job`] runs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Schedule {
+    /// At the minutes matching a cron expression, in UTC.
+    Cron(Cron),
+    /// Repeatedly, this long after the previous run was due.
+    Every(Duration),
+}
+
+impl Schedule {
+    /// Parses a five-field cron expression such as `"0 2 * * 1-5"`.
+    ///
+    /// Fields accept `*`, single values, ranges (`1-5`), lists (`1,15`) and
+    /// steps (`*/10`, `0-30/5`). Day of week runs from 0 (Sunday) to 7
+    /// (Sunday again). When both the day of month and the day of week are
+    /// restricted, a day matching either is enough, as in cron.
+    pub fn cron(expression: &str) -> Result<Self, DremioClientError> {
+        Cron::parse(expression).map(Schedule::Cron)
+    }
+
+    /// Runs every `interval`, starting one interval after the scheduler
+    /// starts.
+    pub fn every(interval: Duration) -> Self {
+        Schedule::Every(interval)
+    }
+
+    /// Returns the first time the schedule is due strictly after `after`.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Schedule::Cron(cron) => cron.next_after(after),
+            Schedule::Every(interval) => Some(after + TimeDelta::from_std(*interval).ok()?),
+        }
+    }
+}
+
+/// A parsed cron expression, see [`Schedule::cron`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Cron {
+    fn parse(expression: &str) -> Result<Self, DremioClientError> {
+        let invalid = || {
+            DremioClientError::from(ArrowError::InvalidArgumentError(format!(
+                "invalid cron expression {:?}",
+                expression
+            )))
+        };
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            return Err(invalid());
+        };
+        let mut weekday_bits = field(weekdays, 0, 7).ok_or_else(invalid)?;
+        // Both 0 and 7 are Sunday.
+        if weekday_bits & (1 << 7) != 0 {
+            weekday_bits = (weekday_bits | 1) & !(1 << 7);
+        }
+        Ok(Self {
+            minutes: field(minutes, 0, 59).ok_or_else(invalid)?,
+            hours: field(hours, 0, 23).ok_or_else(invalid)?,
+            days: field(days, 1, 31).ok_or_else(invalid)?,
+            months: field(months, 1, 12).ok_or_else(invalid)?,
+            weekdays: weekday_bits,
+            any_day: days.starts_with('*'),
+            any_weekday: weekdays.starts_with('*'),
+        })
+    }
+
+    fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let has = |bits: u64, value: u32| bits & (1 << value) != 0;
+        let mut t = after.with_second(0)?.with_nanosecond(0)? + TimeDelta::minutes(1);
+        // Every valid expression matches within a leap-year cycle.
+        let limit = t + TimeDelta::days(366 * 4 + 1);
+        while t < limit {
+            if !has(self.months, t.month()) {
+                let (year, month) = match t.month() {
+                    12 => (t.year() + 1, 1),
+                    month => (t.year(), month + 1),
+                };
+                t = Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).single()?;
+                continue;
+            }
+            let day = has(self.days, t.day());
+            let weekday = has(self.weekdays, t.weekday().num_days_from_sunday());
+            // As in cron, either day field starting with `*` means both must
+            // match; otherwise matching either is enough.
+            let day_matches = if self.any_day || self.any_weekday {
+                day && weekday
+            } else {
+                day || weekday
+            };
+            if !day_matches {
+                t = t.date_naive().succ_opt()?.and_hms_opt(0, 0, 0)?.and_utc();
+                continue;
+            }
+            if !has(self.hours, t.hour()) {
+                t = t.with_minute(0)? + TimeDelta::hours(1);
+                continue;
+            }
+            if !has(self.minutes, t.minute()) {
+                t += TimeDelta::minutes(1);
+                continue;
+            }
+            return Some(t);
+        }
+        None
+    }
+}
+
+/// Parses one cron field into a bit set of the values it matches.
+fn field(field: &str, min: u32, max: u32) -> Option<u64> {
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<usize>().ok().filter(|s| *s > 0)?),
+            None => (part, 1),
+        };
+        let (lo, hi) = if range == "*" {
+            (min, max)
+        } else if let Some((lo, hi)) = range.split_once('-') {
+            (lo.parse().ok()?, hi.parse().ok()?)
+        } else {
+            let value = range.parse().ok()?;
+            // "5/15" means from 5 to the end in steps of 15.
+            (value, if step > 1 { max } else { value })
+        };
+        if lo < min || hi > max || lo > hi {
+            return None;
+        }
+        for value in (lo..=hi).step_by(step) {
+            bits |= 1 << value;
+        }
+    }
+    Some(bits)
+}
+
+type Task = Arc<dyn Fn() -> BoxFuture<'static, Result<(), DremioClientError>> + Send + Sync>;
+
+/// A named task run by a [`Scheduler`].
+#[derive(Clone)]
+pub struct Job {
+    name: String,
+    schedule: Schedule,
+    task: Task,
+    retries: u32,
+    retry_delay: Duration,
+}
+
+impl std::fmt::Debug for Job {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Job")
+            .field("name", &self.name)
+            .field("schedule", &self.schedule)
+            .field("retries", &self.retries)
+            .field("retry_delay", &self.retry_delay)
+            .finish()
+    }
+}
+
+impl Job {
+    /// Creates a job calling `task` whenever `schedule` is due.
+    ///
+    /// `task` typically connects a [`Client`](crate::Client) and runs a query
+    /// or an export with it.
+    pub fn new<F, Fut>(name: impl Into<String>, schedule: Schedule, task: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), DremioClientError>> + Send + 'static,
+    {
+        Self {
+            name: name.into(),
+            schedule,
+            task: Arc::new(move || task().boxed()),
+            retries: 0,
+            retry_delay: Duration::ZERO,
+        }
+    }
+
+    /// Retries a failed run up to `retries` times, waiting `delay` before the
+    /// first retry and twice as long before each further one.
+    pub fn with_retries(mut self, retries: u32, delay: Duration) -> Self {
+        self.retries = retries;
+        self.retry_delay = delay;
+        self
+    }
+
+    /// Returns the name of the job.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// How a run of a [`Job`] ended.
+#[derive(Debug)]
+pub enum JobOutcome {
+    /// The task succeeded, possibly after retries.
+    Succeeded,
+    /// The task failed on its last attempt with this error.
+    Failed(DremioClientError),
+    /// The run was skipped because the previous one was still going.
+    Skipped,
+}
+
+/// A completed, failed or skipped run, passed to the completion hook.
+#[derive(Debug)]
+pub struct JobReport {
+    /// Name of the job.
+    pub job: String,
+    /// When the run was due.
+    pub scheduled: DateTime<Utc>,
+    /// How long the run took, including retries.
+    pub duration: Duration,
+    /// Number of times the task was called.
+    pub attempts: u32,
+    /// How the run ended.
+    pub outcome: JobOutcome,
+}
+
+type Hook = Arc<dyn Fn(&JobReport) + Send + Sync>;
+
+/// Runs [`Job`]s on their schedules.
+#[derive(Clone, Default)]
+pub struct Scheduler {
+    jobs: Vec<Job>,
+    hook: Option<Hook>,
+}
+
+impl Scheduler {
+    /// Creates a scheduler without jobs.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `job`.
+    pub fn with_job(mut self, job: Job) -> Self {
+        self.jobs.push(job);
+        self
+    }
+
+    /// Calls `hook` after every run of every job, including skipped ones.
+    pub fn on_complete<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&JobReport) + Send + Sync + 'static,
+    {
+        self.hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Starts running the jobs on the current Tokio runtime.
+    ///
+    /// The jobs run until the returned handle is stopped or dropped.
+    pub fn start(self) -> SchedulerHandle {
+        let mut tasks = JoinSet::new();
+        for job in self.jobs {
+            tasks.spawn(run_job(job, self.hook.clone()));
+        }
+        SchedulerHandle { tasks }
+    }
+}
+
+/// Running jobs started by [`Scheduler::start`]. Dropping the handle stops
+/// them, cancelling any run in progress.
+pub struct SchedulerHandle {
+    tasks: JoinSet<()>,
+}
+
+impl SchedulerHandle {
+    /// Stops every job, cancelling any run in progress.
+    pub async fn stop(mut self) {
+        self.tasks.shutdown().await;
+    }
+}
+
+/// Waits for each due time of `job` and starts a run, unless the previous one
+/// is still going.
+async fn run_job(job: Job, hook: Option<Hook>) {
+    let running = Arc::new(AtomicBool::new(false));
+    let mut runs = JoinSet::new();
+    let mut last = Utc::now();
+    while let Some(due) = job.schedule.next_after(last) {
+        let wait = (due - Utc::now()).to_std().unwrap_or_default();
+        tokio::time::sleep(wait).await;
+        last = due;
+        // Reap finished runs so the set does not grow.
+        while runs.try_join_next().is_some() {}
+        if running.swap(true, Ordering::AcqRel) {
+            if let Some(hook) = &hook {
+                hook(&JobReport {
+                    job: job.name.clone(),
+                    scheduled: due,
+                    duration: Duration::ZERO,
+                    attempts: 0,
+                    outcome: JobOutcome::Skipped,
+                });
+            }
+            continue;
+        }
+        let (job, hook, running) = (job.clone(), hook.clone(), running.clone());
+        runs.spawn(async move {
+            let report = attempt(&job, due).await;
+            running.store(false, Ordering::Release);
+            if let Some(hook) = hook {
+                hook(&report);
+            }
+        });
+    }
+}
+
+/// Runs the task of `job`, retrying as configured.
+async fn attempt(job: &Job, scheduled: DateTime<Utc>) -> JobReport {
+    let started = std::time::Instant::now();
+    let mut delay = job.retry_delay;
+    let mut attempts = 0;
+    let outcome = loop {
+        attempts += 1;
+        match (job.task)().await {
+            Ok(()) => break JobOutcome::Succeeded,
+            Err(e) if attempts > job.retries => break JobOutcome::Failed(e),
+            Err(_) => {
+                tokio::time::sleep(delay).await;
+                delay = delay.saturating_mul(2);
+            }
+        }
+    };
+    JobReport {
+        job: job.name.clone(),
+        scheduled,
+        duration: started.elapsed(),
+        attempts,
+        outcome,
+    }
+}
@@ -0,0 +1,329 @@
+//! Utilities for working with result schemas.
+
+use crate::DremioClientError;
+use arrow::array::{RecordBatch, RecordBatchOptions};
+use arrow::datatypes::{DataType, Field, FieldRef, Schema, SchemaRef};
+use arrow::error::ArrowError;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// How result column names are rewritten before batches are returned.
+///
+/// Depending on the source, Dremio may return upper-case or mixed-case column
+/// names. Normalizing them lets consumers rely on a single convention.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ColumnNaming {
+    /// Keep column names as returned by Dremio.
+    #[default]
+    AsReturned,
+    /// Convert column names to lower case.
+    Lowercase,
+    /// Convert column names to `snake_case`, e.g. `OrderDate` becomes `order_date`.
+    SnakeCase,
+    /// Rename columns using an explicit mapping; unmapped columns keep their name.
+    Mapping(BTreeMap<String, String>),
+}
+
+impl ColumnNaming {
+    /// Returns the normalized form of a single column name.
+    pub fn apply(&self, name: &str) -> String {
+        match self {
+            ColumnNaming::AsReturned => name.to_string(),
+            ColumnNaming::Lowercase => name.to_lowercase(),
+            ColumnNaming::SnakeCase => to_snake_case(name),
+            ColumnNaming::Mapping(mapping) => mapping
+                .get(name)
+                .cloned()
+                .unwrap_or_else(|| name.to_string()),
+        }
+    }
+}
+
+/// Converts an identifier such as `CustomerID`, `orderDate` or `First Name` to
+/// `snake_case`.
+pub fn to_snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut out = String::with_capacity(name.len() + 4);
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !out.is_empty() && !out.ends_with('_') {
+                out.push('_');
+            }
+            continue;
+        }
+        if c.is_uppercase() && i > 0 && !out.is_empty() && !out.ends_with('_') {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            if prev.is_lowercase() || prev.is_numeric() || (prev.is_uppercase() && next_is_lower) {
+                out.push('_');
+            }
+        }
+        out.extend(c.to_lowercase());
+    }
+    while out.ends_with('_') {
+        out.pop();
+    }
+    out
+}
+
+/// Returns a copy of `batch` with its top-level columns renamed according to
+/// `naming`.
+pub fn rename_columns(
+    batch: RecordBatch,
+    naming: &ColumnNaming,
+) -> Result<RecordBatch, DremioClientError> {
+    if *naming == ColumnNaming::AsReturned {
+        return Ok(batch);
+    }
+    let schema = batch.schema();
+    let fields: Vec<FieldRef> = schema
+        .fields()
+        .iter()
+        .map(|f| Arc::new(Field::clone(f).with_name(naming.apply(f.name()))))
+        .collect();
+    let schema = Schema::new_with_metadata(fields, schema.metadata().clone());
+    let options = RecordBatchOptions::new().with_row_count(Some(batch.num_rows()));
+    Ok(RecordBatch::try_new_with_options(
+        Arc::new(schema),
+        batch.columns().to_vec(),
+        &options,
+    )?)
+}
+
+/// Returns a copy of `batch` with only the named `columns`, in that order.
+pub fn select_columns(
+    batch: &RecordBatch,
+    columns: &[String],
+) -> Result<RecordBatch, DremioClientError> {
+    let schema = batch.schema();
+    let indices = columns
+        .iter()
+        .map(|column| {
+            schema.index_of(column).map_err(|_| {
+                ArrowError::SchemaError(format!(
+                    "selected column {:?} is not in the results",
+                    column
+                ))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(batch.project(&indices)?)
+}
+
+/// A schema that query results are expected to match.
+///
+/// Attach an expectation to a query with
+/// [`QueryOptions::expect_schema`](crate::QueryOptions::expect_schema) to fail
+/// as soon as the result schema is known, instead of discovering upstream
+/// changes to a virtual dataset further down a pipeline.
+///
+/// By default column names and data types must match exactly, columns may
+/// appear in any order, no extra columns are allowed and nullability is not
+/// checked.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+/// use arrow::datatypes::{DataType, Field, Schema};
+/// use dremio_rs::schema::SchemaExpectation;
+///
+/// let expected = Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, false)]));
+/// let actual = Schema::new(vec![Field::new("id", DataType::Int32, false)]);
+/// let mismatch = SchemaExpectation::new(expected).validate(&actual).unwrap_err();
+/// assert_eq!(mismatch.differences().len(), 1);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SchemaExpectation {
+    expected: SchemaRef,
+    allow_extra_columns: bool,
+    check_order: bool,
+    check_types: bool,
+    check_nullability: bool,
+}
+
+impl SchemaExpectation {
+    /// Creates an expectation for `expected` with the default strictness.
+    pub fn new(expected: SchemaRef) -> Self {
+        Self {
+            expected,
+            allow_extra_columns: false,
+            check_order: false,
+            check_types: true,
+            check_nullability: false,
+        }
+    }
+
+    /// Creates an expectation that also checks column order and nullability.
+    pub fn strict(expected: SchemaRef) -> Self {
+        Self::new(expected)
+            .with_check_order(true)
+            .with_check_nullability(true)
+    }
+
+    /// Allows the result to contain columns that are not in the expected schema.
+    pub fn with_allow_extra_columns(mut self, allow: bool) -> Self {
+        self.allow_extra_columns = allow;
+        self
+    }
+
+    /// Requires columns to appear in the same order as in the expected schema.
+    pub fn with_check_order(mut self, check: bool) -> Self {
+        self.check_order = check;
+        self
+    }
+
+    /// Requires data types to match exactly.
+    pub fn with_check_types(mut self, check: bool) -> Self {
+        self.check_types = check;
+        self
+    }
+
+    /// Rejects nullable result columns where the expected column is not
+    /// nullable. A non-nullable result column always satisfies a nullable
+    /// expectation.
+    pub fn with_check_nullability(mut self, check: bool) -> Self {
+        self.check_nullability = check;
+        self
+    }
+
+    /// Returns the expected schema.
+    pub fn expected(&self) -> &SchemaRef {
+        &self.expected
+    }
+
+    /// Compares `actual` against the expected schema and returns every
+    /// difference found.
+    pub fn validate(&self, actual: &Schema) -> Result<(), SchemaMismatch> {
+        let mut differences = Vec::new();
+        for (position, expected) in self.expected.fields().iter().enumerate() {
+            let Some((actual_position, field)) = actual.column_with_name(expected.name()) else {
+                differences.push(SchemaDifference::Missing {
+                    name: expected.name().clone(),
+                    data_type: expected.data_type().clone(),
+                });
+                continue;
+            };
+            if self.check_types && field.data_type() != expected.data_type() {
+                differences.push(SchemaDifference::TypeMismatch {
+                    name: expected.name().clone(),
+                    expected: expected.data_type().clone(),
+                    actual: field.data_type().clone(),
+                });
+            }
+            if self.check_nullability && field.is_nullable() && !expected.is_nullable() {
+                differences.push(SchemaDifference::NullabilityMismatch {
+                    name: expected.name().clone(),
+                });
+            }
+            if self.check_order && actual_position != position {
+                differences.push(SchemaDifference::OrderMismatch {
+                    name: expected.name().clone(),
+                    expected: position,
+                    actual: actual_position,
+                });
+            }
+        }
+        if !self.allow_extra_columns {
+            for field in actual.fields() {
+                if self.expected.column_with_name(field.name()).is_none() {
+                    differences.push(SchemaDifference::Unexpected {
+                        name: field.name().clone(),
+                        data_type: field.data_type().clone(),
+                    });
+                }
+            }
+        }
+        if differences.is_empty() {
+            Ok(())
+        } else {
+            Err(SchemaMismatch { differences })
+        }
+    }
+}
+
+/// A single difference between an expected and an actual schema.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SchemaDifference {
+    /// An expected column is absent from the result.
+    Missing { name: String, data_type: DataType },
+    /// The result contains a column that was not expected.
+    Unexpected { name: String, data_type: DataType },
+    /// A column has a different data type than expected.
+    TypeMismatch {
+        name: String,
+        expected: DataType,
+        actual: DataType,
+    },
+    /// A column is nullable although the expected column is not.
+    NullabilityMismatch { name: String },
+    /// A column appears at a different position than expected.
+    OrderMismatch {
+        name: String,
+        expected: usize,
+        actual: usize,
+    },
+}
+
+impl fmt::Display for SchemaDifference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaDifference::Missing { name, data_type } => {
+                write!(f, "missing column \"{}\" ({})", name, data_type)
+            }
+            SchemaDifference::Unexpected { name, data_type } => {
+                write!(f, "unexpected column \"{}\" ({})", name, data_type)
+            }
+            SchemaDifference::TypeMismatch {
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "column \"{}\" has type {}, expected {}",
+                name, actual, expected
+            ),
+            SchemaDifference::NullabilityMismatch { name } => {
+                write!(f, "column \"{}\" is nullable, expected non-nullable", name)
+            }
+            SchemaDifference::OrderMismatch {
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "column \"{}\" is at position {}, expected {}",
+                name, actual, expected
+            ),
+        }
+    }
+}
+
+/// The differences found when a result schema does not match a
+/// [`SchemaExpectation`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SchemaMismatch {
+    differences: Vec<SchemaDifference>,
+}
+
+impl SchemaMismatch {
+    /// Returns the individual differences.
+    pub fn differences(&self) -> &[SchemaDifference] {
+        &self.differences
+    }
+}
+
+impl fmt::Display for SchemaMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, difference) in self.differences.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "{}", difference)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for SchemaMismatch {}
@@ -0,0 +1,327 @@
+//! Comparing the schemas of query results, files and tables.
+//!
+//! [`diff`] reports the columns added, removed or retyped between two Arrow
+//! schemas, for example to stop a pipeline before it writes results whose
+//! schema has drifted from the table it feeds. The schemas can come from a
+//! query ([`query_schema`]), a Parquet file ([`parquet_schema`], `parquet`
+//! feature) or a Delta table ([`delta_schema`], `delta` feature).
+//!
+//! # Example
+//!
+//! ```no_run
+//! use dremio_rs::schema_diff::{diff, query_schema};
+//! use dremio_rs::Client;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!   let client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+//!   let existing = query_schema(&client, "SELECT * FROM marts.orders").await.unwrap();
+//!   let incoming = query_schema(&client, "SELECT * FROM staging.orders").await.unwrap();
+//!   let changes = diff(&existing, &incoming);
+//!   if !changes.is_empty() {
+//!     panic!("schema changed: {}", changes);
+//!   }
+//! }
+//! ```
+
+use crate::{Client, DremioClientError};
+use arrow::datatypes::{DataType, Schema, SchemaRef};
+use std::fmt;
+
+/// A change to a single column between two schemas.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ColumnChange {
+    /// The column only exists in the new schema.
+    Added { name: String, data_type: DataType },
+    /// The column only exists in the old schema.
+    Removed { name: String, data_type: DataType },
+    /// The column exists in both schemas with different data types.
+    Retyped {
+        name: String,
+        from: DataType,
+        to: DataType,
+    },
+}
+
+impl fmt::Display for ColumnChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColumnChange::Added { name, data_type } => {
+                write!(f, "added column \"{}\" ({})", name, data_type)
+            }
+            ColumnChange::Removed { name, data_type } => {
+                write!(f, "removed column \"{}\" ({})", name, data_type)
+            }
+            ColumnChange::Retyped { name, from, to } => {
+                write!(f, "column \"{}\" changed from {} to {}", name, from, to)
+            }
+        }
+    }
+}
+
+/// The column changes between two schemas, as returned by [`diff`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SchemaDiff {
+    changes: Vec<ColumnChange>,
+}
+
+impl SchemaDiff {
+    /// Returns whether the schemas have the same columns and types.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Returns every change: removed and retyped columns in the order of the
+    /// old schema, then added columns in the order of the new one.
+    pub fn changes(&self) -> &[ColumnChange] {
+        &self.changes
+    }
+
+    /// Returns the names of the added columns.
+    pub fn added(&self) -> impl Iterator<Item = &str> {
+        self.changes.iter().filter_map(|change| match change {
+            ColumnChange::Added { name, .. } => Some(name.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Returns the names of the removed columns.
+    pub fn removed(&self) -> impl Iterator<Item = &str> {
+        self.changes.iter().filter_map(|change| match change {
+            ColumnChange::Removed { name, .. } => Some(name.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Returns the names of the retyped columns.
+    pub fn retyped(&self) -> impl Iterator<Item = &str> {
+        self.changes.iter().filter_map(|change| match change {
+            ColumnChange::Retyped { name, .. } => Some(name.as_str()),
+            _ => None,
+        })
+    }
+}
+
+impl fmt::Display for SchemaDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.changes.is_empty() {
+            return f.write_str("no changes");
+        }
+        for (i, change) in self.changes.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares the columns of `from` and `to`, matched by name.
+///
+/// Column order, nullability and metadata are not compared.
+///
+/// # Example
+///
+/// ```
+/// use arrow::datatypes::{DataType, Field, Schema};
+/// use dremio_rs::schema_diff::diff;
+///
+/// let old = Schema::new(vec![
+///     Field::new("id", DataType::Int32, false),
+///     Field::new("note", DataType::Utf8, true),
+/// ]);
+/// let new = Schema::new(vec![
+///     Field::new("id", DataType::Int64, false),
+///     Field::new("amount", DataType::Float64, true),
+/// ]);
+/// let changes = diff(&old, &new);
+/// assert_eq!(changes.retyped().collect::<Vec<_>>(), ["id"]);
+/// assert_eq!(changes.removed().collect::<Vec<_>>(), ["note"]);
+/// assert_eq!(changes.added().collect::<Vec<_>>(), ["amount"]);
+/// ```
+pub fn diff(from: &Schema, to: &Schema) -> SchemaDiff {
+    let mut changes = Vec::new();
+    for old in from.fields() {
+        match to.field_with_name(old.name()) {
+            Ok(new) if new.data_type() != old.data_type() => changes.push(ColumnChange::Retyped {
+                name: old.name().clone(),
+                from: old.data_type().clone(),
+                to: new.data_type().clone(),
+            }),
+            Ok(_) => {}
+            Err(_) => changes.push(ColumnChange::Removed {
+                name: old.name().clone(),
+                data_type: old.data_type().clone(),
+            }),
+        }
+    }
+    for new in to.fields() {
+        if from.field_with_name(new.name()).is_err() {
+            changes.push(ColumnChange::Added {
+                name: new.name().clone(),
+                data_type: new.data_type().clone(),
+            });
+        }
+    }
+    SchemaDiff { changes }
+}
+
+/// Returns the schema of the results of `query`, after the transformations
+/// configured in the client's default options, without fetching any rows.
+///
+/// Equivalent to [`Client::get_schema`], and answered from its schema cache
+/// when one is attached.
+pub async fn query_schema(client: &Client, query: &str) -> Result<SchemaRef, DremioClientError> {
+    client.get_schema(query).await
+}
+
+/// Returns the Arrow schema stored in the Parquet file at `path`.
+#[cfg(feature = "parquet")]
+pub fn parquet_schema(path: impl AsRef<std::path::Path>) -> Result<SchemaRef, DremioClientError> {
+    let file = std::fs::File::open(path)?;
+    let builder = parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(file)?;
+    Ok(builder.schema().clone())
+}
+
+/// Returns the current schema of the Delta table in the directory `path`.
+///
+/// The schema is read from the most recent `metaData` action in the JSON
+/// commits of the transaction log. Tables whose log has been compacted into
+/// checkpoints only, with the JSON commits removed, are not supported.
+#[cfg(feature = "delta")]
+pub fn delta_schema(path: impl AsRef<std::path::Path>) -> Result<SchemaRef, DremioClientError> {
+    delta::schema(path.as_ref())
+}
+
+#[cfg(feature = "delta")]
+mod delta {
+    use crate::DremioClientError;
+    use arrow::datatypes::{DataType, Field, Fields, Schema, SchemaRef, TimeUnit};
+    use arrow::error::ArrowError;
+    use serde_json::Value;
+    use std::path::Path;
+    use std::sync::Arc;
+
+    fn invalid(message: String) -> DremioClientError {
+        ArrowError::SchemaError(message).into()
+    }
+
+    pub(super) fn schema(table: &Path) -> Result<SchemaRef, DremioClientError> {
+        let log = table.join("_delta_log");
+        let mut commits = Vec::new();
+        for entry in std::fs::read_dir(&log)? {
+            let path = entry?.path();
+            if path.extension().and_then(|x| x.to_str()) == Some("json") {
+                commits.push(path);
+            }
+        }
+        // Commit files are named after zero-padded versions.
+        commits.sort();
+        for commit in commits.iter().rev() {
+            let text = std::fs::read_to_string(commit)?;
+            let metadata = text
+                .lines()
+                .rev()
+                .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+                .find_map(|action| action.get("metaData").cloned());
+            if let Some(metadata) = metadata {
+                let schema = metadata
+                    .get("schemaString")
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| invalid(format!("{} has no schemaString", commit.display())))?;
+                let schema: Value = serde_json::from_str(schema)
+                    .map_err(|e| invalid(format!("invalid Delta schema: {}", e)))?;
+                return Ok(Arc::new(Schema::new(struct_fields(&schema)?)));
+            }
+        }
+        Err(invalid(format!(
+            "no Delta metadata found in {}",
+            log.display()
+        )))
+    }
+
+    fn struct_fields(value: &Value) -> Result<Fields, DremioClientError> {
+        let fields = value
+            .get("fields")
+            .and_then(Value::as_array)
+            .ok_or_else(|| invalid(format!("invalid Delta struct type {}", value)))?;
+        fields
+            .iter()
+            .map(|field| {
+                let name = field
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default();
+                let nullable = field
+                    .get("nullable")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(true);
+                let data_type = data_type(field.get("type").unwrap_or(&Value::Null))?;
+                Ok(Field::new(name, data_type, nullable))
+            })
+            .collect()
+    }
+
+    fn data_type(value: &Value) -> Result<DataType, DremioClientError> {
+        if let Some(name) = value.as_str() {
+            return primitive(name)
+                .ok_or_else(|| invalid(format!("unsupported Delta type {}", name)));
+        }
+        match value.get("type").and_then(Value::as_str) {
+            Some("struct") => Ok(DataType::Struct(struct_fields(value)?)),
+            Some("array") => {
+                let element = data_type(value.get("elementType").unwrap_or(&Value::Null))?;
+                let nullable = value
+                    .get("containsNull")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(true);
+                Ok(DataType::List(Arc::new(Field::new(
+                    "element", element, nullable,
+                ))))
+            }
+            Some("map") => {
+                let key = data_type(value.get("keyType").unwrap_or(&Value::Null))?;
+                let item = data_type(value.get("valueType").unwrap_or(&Value::Null))?;
+                let nullable = value
+                    .get("valueContainsNull")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(true);
+                let entries = Field::new_struct(
+                    "key_value",
+                    vec![
+                        Field::new("key", key, false),
+                        Field::new("value", item, nullable),
+                    ],
+                    false,
+                );
+                Ok(DataType::Map(Arc::new(entries), false))
+            }
+            _ => Err(invalid(format!("unsupported Delta type {}", value))),
+        }
+    }
+
+    fn primitive(name: &str) -> Option<DataType> {
+        Some(match name {
+            "string" => DataType::Utf8,
+            "long" => DataType::Int64,
+            "integer" => DataType::Int32,
+            "short" => DataType::Int16,
+            "byte" => DataType::Int8,
+            "float" => DataType::Float32,
+            "double" => DataType::Float64,
+            "boolean" => DataType::Boolean,
+            "binary" => DataType::Binary,
+            "date" => DataType::Date32,
+            "timestamp" => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+            "timestamp_ntz" => DataType::Timestamp(TimeUnit::Microsecond, None),
+            _ => {
+                let (precision, scale) = name
+                    .strip_prefix("decimal(")?
+                    .strip_suffix(')')?
+                    .split_once(',')?;
+                DataType::Decimal128(precision.trim().parse().ok()?, scale.trim().parse().ok()?)
+            }
+        })
+    }
+}
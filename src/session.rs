@@ -0,0 +1,94 @@
+//! Dremio session properties sent as Flight headers.
+
+/// Session properties Dremio reads from the headers of Flight requests: the
+/// default schema unqualified table names are resolved in, and the routing of
+/// queries to a queue or engine.
+///
+/// Set them for all queries of a client with
+/// [`ClientBuilder::with_session_options`](crate::ClientBuilder::with_session_options),
+/// or for a single query with
+/// [`QueryOptions::with_session_options`](crate::QueryOptions::with_session_options),
+/// whose properties take precedence.
+///
+/// # Example
+///
+/// ```
+/// use dremio_rs::SessionOptions;
+///
+/// let session = SessionOptions::new()
+///     .with_schema("lake.sales")
+///     .with_routing_queue("High Cost User Queries");
+/// assert_eq!(
+///     session.headers(),
+///     [("schema", "lake.sales"), ("routing_queue", "High Cost User Queries")]
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SessionOptions {
+    headers: Vec<(String, String)>,
+}
+
+impl SessionOptions {
+    /// Creates options without any session properties.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Resolves unqualified table names in `schema`, a dotted path such as
+    /// `lake.sales`, like `USE lake.sales` would.
+    pub fn with_schema(self, schema: impl Into<String>) -> Self {
+        self.with_property("schema", schema)
+    }
+
+    /// Routes queries to the workload management queue named `queue`.
+    pub fn with_routing_queue(self, queue: impl Into<String>) -> Self {
+        self.with_property("routing_queue", queue)
+    }
+
+    /// Tags queries with `tag`, for workload management rules to route them.
+    pub fn with_routing_tag(self, tag: impl Into<String>) -> Self {
+        self.with_property("routing_tag", tag)
+    }
+
+    /// Runs queries on the engine named `engine`.
+    pub fn with_routing_engine(self, engine: impl Into<String>) -> Self {
+        self.with_property("routing_engine", engine)
+    }
+
+    /// Sets the session property `key`, sent as a header of that name, for
+    /// properties without a method of their own. Keys must be lowercase
+    /// ASCII.
+    pub fn with_property(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let key = key.into();
+        self.headers.retain(|(k, _)| *k != key);
+        self.headers.push((key, value.into()));
+        self
+    }
+
+    /// Returns the default schema, if set.
+    pub fn schema(&self) -> Option<&str> {
+        self.property("schema")
+    }
+
+    /// Returns the value of the session property `key`, if set.
+    pub fn property(&self, key: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the headers carrying the session properties, in the order they
+    /// were set.
+    pub fn headers(&self) -> Vec<(&str, &str)> {
+        self.headers
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect()
+    }
+
+    /// Returns whether no session property is set.
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+    }
+}
@@ -0,0 +1,337 @@
+//! Saving query results locally and replaying them offline.
+//!
+//! [`Client::snapshot`](crate::Client::snapshot) streams the full result of a
+//! query, schema and batches, into a single Arrow IPC file along with the
+//! query text and the time it was captured. A [`SnapshotReader`] answers the
+//! same queries from such files through [`DremioExecutor`], so code written
+//! against the client keeps working without a cluster, whether for offline
+//! analysis or for tests running on production-shaped data.
+//!
+//! Snapshots use the same format as the recordings of
+//! [`testing::Recorder`](crate::testing), so a directory of snapshots can also
+//! be replayed by the testing helpers.
+
+use crate::cache::normalize_sql;
+use crate::{DremioClientError, DremioExecutor, QueryOptions, QueryStream};
+use arrow::array::RecordBatch;
+use arrow::datatypes::{Schema, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::ipc::reader::FileReader;
+use arrow::ipc::writer::FileWriter;
+use arrow_flight::error::FlightError;
+use futures::StreamExt;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tonic::Status;
+
+const BUNDLE_EXTENSION: &str = "arrow";
+const QUERY_KEY: &str = "dremio.query";
+const ERROR_KEY: &str = "dremio.error";
+const CAPTURED_AT_KEY: &str = "dremio.captured_at";
+const ROWS_KEY: &str = "dremio.rows";
+
+/// Describes a snapshot file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SnapshotInfo {
+    /// The file holding the snapshot.
+    pub path: PathBuf,
+    /// The query whose results were saved.
+    pub query: String,
+    /// The schema of the results.
+    pub schema: SchemaRef,
+    /// The number of rows saved.
+    pub rows: u64,
+    /// When the results were captured, if recorded.
+    pub captured_at: Option<SystemTime>,
+}
+
+/// A [`DremioExecutor`] answering queries from snapshot files.
+///
+/// Queries only match the exact SQL of a snapshot, up to whitespace. Result
+/// transformations configured in [`QueryOptions`] are applied when the
+/// results are replayed.
+///
+/// # Example
+///
+/// ```no_run
+/// use dremio_rs::snapshot::SnapshotReader;
+/// use dremio_rs::{Client, DremioExecutor};
+///
+/// #[tokio::main]
+/// async fn main() {
+///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+///   client.snapshot("SELECT * FROM lake.orders", "snapshots/orders.arrow").await.unwrap();
+///
+///   // Later, without a cluster:
+///   let mut reader = SnapshotReader::open("snapshots").unwrap();
+///   let batches = reader.get_record_batches("SELECT * FROM lake.orders").await.unwrap();
+/// }
+/// ```
+#[derive(Debug)]
+pub struct SnapshotReader {
+    snapshots: Vec<SnapshotInfo>,
+}
+
+impl SnapshotReader {
+    /// Opens the snapshot file at `path`, or every snapshot in it if it is a
+    /// directory.
+    ///
+    /// Only the file footers are read up front; batches are read when a
+    /// query is replayed.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, DremioClientError> {
+        let path = path.as_ref();
+        let paths = if path.is_dir() {
+            bundle_paths(path)?
+        } else {
+            vec![path.to_path_buf()]
+        };
+        let snapshots = paths
+            .iter()
+            .map(|path| Ok(read_bundle(path, false)?.info(path)))
+            .collect::<Result<_, DremioClientError>>()?;
+        Ok(Self { snapshots })
+    }
+
+    /// Returns the snapshots available for replay.
+    pub fn snapshots(&self) -> &[SnapshotInfo] {
+        &self.snapshots
+    }
+
+    fn replay(&self, query: &str) -> Result<Vec<RecordBatch>, DremioClientError> {
+        let query = normalize_sql(query);
+        let snapshot = self
+            .snapshots
+            .iter()
+            .rev()
+            .find(|snapshot| normalize_sql(&snapshot.query) == query)
+            .ok_or_else(|| {
+                ArrowError::InvalidArgumentError(format!("no snapshot of query {:?}", query))
+            })?;
+        let bundle = read_bundle(&snapshot.path, true)?;
+        match bundle.error {
+            Some(message) => Err(Bundle::error(&message)),
+            None => Ok(bundle.batches),
+        }
+    }
+}
+
+impl DremioExecutor for SnapshotReader {
+    async fn execute_stream_with_options(
+        &mut self,
+        query: &str,
+        options: &QueryOptions,
+    ) -> Result<QueryStream, DremioClientError> {
+        let batches = self.replay(query)?;
+        Ok(QueryStream::from_batches(batches)
+            .map_batches(options.batch_transformer(false))
+            .rechunk(options.batch_size())
+            .with_checks(options.checks().clone()))
+    }
+
+    async fn get_record_batches_with_options(
+        &mut self,
+        query: &str,
+        options: &QueryOptions,
+    ) -> Result<Vec<RecordBatch>, DremioClientError> {
+        self.execute_stream_with_options(query, options)
+            .await?
+            .collect_batches()
+            .await
+    }
+}
+
+/// Writes the batches of `stream` to a snapshot of `query` at `path`, as
+/// they arrive.
+pub(crate) async fn write_snapshot(
+    mut stream: QueryStream,
+    query: &str,
+    path: &Path,
+) -> Result<SnapshotInfo, DremioClientError> {
+    // Capture times are saved in milliseconds.
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let captured_at = UNIX_EPOCH + Duration::from_millis(millis as u64);
+    let mut writer: Option<FileWriter<File>> = None;
+    let mut rows = 0;
+    while let Some(batch) = stream.next().await {
+        let batch = batch?;
+        if writer.is_none() {
+            writer = Some(create_bundle(
+                path,
+                &batch.schema(),
+                query,
+                None,
+                captured_at,
+            )?);
+        }
+        if let Some(writer) = writer.as_mut() {
+            writer.write(&batch)?;
+        }
+        rows += batch.num_rows() as u64;
+    }
+    let mut writer = match writer {
+        Some(writer) => writer,
+        None => create_bundle(path, &Arc::new(Schema::empty()), query, None, captured_at)?,
+    };
+    writer.write_metadata(ROWS_KEY, rows.to_string());
+    writer.finish()?;
+    Ok(SnapshotInfo {
+        path: path.to_path_buf(),
+        query: query.to_string(),
+        schema: writer.schema().clone(),
+        rows,
+        captured_at: Some(captured_at),
+    })
+}
+
+/// The contents of a snapshot or recording file.
+pub(crate) struct Bundle {
+    pub(crate) query: String,
+    pub(crate) schema: SchemaRef,
+    pub(crate) batches: Vec<RecordBatch>,
+    pub(crate) error: Option<String>,
+    rows: u64,
+    captured_at: Option<SystemTime>,
+}
+
+impl Bundle {
+    /// Returns the status a server would have failed the query with.
+    pub(crate) fn status(message: &str) -> Status {
+        Status::unknown(message)
+    }
+
+    /// Returns the error the client would have failed the query with.
+    pub(crate) fn error(message: &str) -> DremioClientError {
+        FlightError::Tonic(Box::new(Self::status(message))).into()
+    }
+
+    fn info(self, path: &Path) -> SnapshotInfo {
+        SnapshotInfo {
+            path: path.to_path_buf(),
+            query: self.query,
+            schema: self.schema,
+            rows: self.rows,
+            captured_at: self.captured_at,
+        }
+    }
+}
+
+/// Reads all bundles in `dir`, ordered by file name.
+#[cfg(feature = "testing")]
+pub(crate) fn read_bundles(dir: &Path) -> Result<Vec<Bundle>, DremioClientError> {
+    bundle_paths(dir)?
+        .iter()
+        .map(|path| read_bundle(path, true))
+        .collect()
+}
+
+fn bundle_paths(dir: &Path) -> Result<Vec<PathBuf>, DremioClientError> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|x| x.to_str()) == Some(BUNDLE_EXTENSION) {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Reads the bundle at `path`, with its batches only if `batches` is set.
+fn read_bundle(path: &Path, batches: bool) -> Result<Bundle, DremioClientError> {
+    let reader = FileReader::try_new(BufReader::new(File::open(path)?), None)?;
+    let schema = reader.schema();
+    let metadata = reader.custom_metadata().clone();
+    let query = metadata.get(QUERY_KEY).cloned().ok_or_else(|| {
+        ArrowError::InvalidArgumentError(format!("{} is not a query snapshot", path.display()))
+    })?;
+    let captured_at = metadata
+        .get(CAPTURED_AT_KEY)
+        .and_then(|millis| millis.parse().ok())
+        .map(|millis| UNIX_EPOCH + Duration::from_millis(millis));
+    let error = metadata.get(ERROR_KEY).cloned();
+    let recorded_rows = metadata.get(ROWS_KEY).and_then(|rows| rows.parse().ok());
+    let (batches, rows) = match (batches, recorded_rows) {
+        (false, Some(rows)) => (Vec::new(), rows),
+        (false, None) => {
+            let mut rows = 0;
+            for batch in reader {
+                rows += batch?.num_rows() as u64;
+            }
+            (Vec::new(), rows)
+        }
+        (true, _) => {
+            let batches = reader.collect::<Result<Vec<_>, _>>()?;
+            let rows = batches.iter().map(|b| b.num_rows() as u64).sum();
+            (batches, rows)
+        }
+    };
+    Ok(Bundle {
+        query,
+        schema,
+        batches,
+        error,
+        rows,
+        captured_at,
+    })
+}
+
+/// Writes a bundle of `batches`, or of the failure `error`, to `path`.
+#[cfg(feature = "testing")]
+pub(crate) fn write_bundle(
+    path: &Path,
+    query: &str,
+    batches: &[RecordBatch],
+    error: Option<&str>,
+) -> Result<(), DremioClientError> {
+    let schema = match batches.first() {
+        Some(batch) => batch.schema(),
+        None => Arc::new(Schema::empty()),
+    };
+    let mut writer = create_bundle(path, &schema, query, error, SystemTime::now())?;
+    let mut rows = 0;
+    for batch in batches {
+        writer.write(batch)?;
+        rows += batch.num_rows();
+    }
+    writer.write_metadata(ROWS_KEY, rows.to_string());
+    writer.finish()?;
+    Ok(())
+}
+
+fn create_bundle(
+    path: &Path,
+    schema: &SchemaRef,
+    query: &str,
+    error: Option<&str>,
+    captured_at: SystemTime,
+) -> Result<FileWriter<File>, DremioClientError> {
+    let mut writer = FileWriter::try_new(File::create(path)?, schema)?;
+    writer.write_metadata(QUERY_KEY, query);
+    if let Some(error) = error {
+        writer.write_metadata(ERROR_KEY, error);
+    }
+    let millis = captured_at
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    writer.write_metadata(CAPTURED_AT_KEY, millis.to_string());
+    Ok(writer)
+}
+
+/// Names bundles after a hash of the normalized query, so capturing a query
+/// again overwrites its previous bundle.
+#[cfg(feature = "testing")]
+pub(crate) fn bundle_file_name(query: &str) -> String {
+    let query = normalize_sql(query);
+    format!(
+        "{:016x}.{}",
+        crate::cache::fnv1a(query.as_bytes(), 0xcbf2_9ce4_8422_2325),
+        BUNDLE_EXTENSION
+    )
+}
@@ -0,0 +1,604 @@
+//! Building SQL text without string concatenation.
+//!
+//! [`QueryBuilder`] assembles simple `SELECT` statements from identifiers and
+//! literal values, quoting each one so user-supplied input cannot change the
+//! structure of the query. Raw SQL fragments can still be mixed in where the
+//! builder falls short.
+//!
+//! [`quote_ident`], [`quote_path`] and [`parse_path`] convert between dataset
+//! paths and their SQL spelling on their own.
+
+use crate::DremioClientError;
+use arrow::array::{Array, ArrayRef, AsArray};
+use arrow::compute::{cast, concat, sort_to_indices, SortOptions};
+use arrow::datatypes::{DataType, Float64Type};
+use arrow::error::ArrowError;
+use arrow::util::display::array_value_to_string;
+use std::fmt;
+
+/// A SQL literal value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Literal {
+    /// `NULL`.
+    Null,
+    /// `TRUE` or `FALSE`.
+    Bool(bool),
+    /// An integer.
+    Int(i64),
+    /// A floating point number.
+    Float(f64),
+    /// A character string.
+    Str(String),
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Literal::Null => f.write_str("NULL"),
+            Literal::Bool(true) => f.write_str("TRUE"),
+            Literal::Bool(false) => f.write_str("FALSE"),
+            Literal::Int(value) => write!(f, "{}", value),
+            Literal::Float(value) if value.is_nan() => f.write_str("CAST('NaN' AS DOUBLE)"),
+            Literal::Float(value) if value.is_infinite() => {
+                let sign = if *value < 0.0 { "-" } else { "" };
+                write!(f, "CAST('{}Infinity' AS DOUBLE)", sign)
+            }
+            Literal::Float(value) => write!(f, "{:?}", value),
+            Literal::Str(value) => write!(f, "'{}'", value.replace('\'', "''")),
+        }
+    }
+}
+
+impl From<bool> for Literal {
+    fn from(value: bool) -> Self {
+        Literal::Bool(value)
+    }
+}
+
+macro_rules! int_literal {
+    ($($t:ty),*) => {
+        $(impl From<$t> for Literal {
+            fn from(value: $t) -> Self {
+                Literal::Int(i64::from(value))
+            }
+        })*
+    };
+}
+
+int_literal!(i8, i16, i32, i64, u8, u16, u32);
+
+impl From<f32> for Literal {
+    fn from(value: f32) -> Self {
+        Literal::Float(f64::from(value))
+    }
+}
+
+impl From<f64> for Literal {
+    fn from(value: f64) -> Self {
+        Literal::Float(value)
+    }
+}
+
+impl From<&str> for Literal {
+    fn from(value: &str) -> Self {
+        Literal::Str(value.to_string())
+    }
+}
+
+impl From<String> for Literal {
+    fn from(value: String) -> Self {
+        Literal::Str(value)
+    }
+}
+
+impl<T: Into<Literal>> From<Option<T>> for Literal {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Literal::Null, Into::into)
+    }
+}
+
+/// A comparison operator for [`QueryBuilder::filter`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    /// `=`
+    Eq,
+    /// `<>`
+    NotEq,
+    /// `<`
+    Lt,
+    /// `<=`
+    LtEq,
+    /// `>`
+    Gt,
+    /// `>=`
+    GtEq,
+    /// `LIKE`
+    Like,
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Comparison::Eq => "=",
+            Comparison::NotEq => "<>",
+            Comparison::Lt => "<",
+            Comparison::LtEq => "<=",
+            Comparison::Gt => ">",
+            Comparison::GtEq => ">=",
+            Comparison::Like => "LIKE",
+        })
+    }
+}
+
+/// Sort direction for [`QueryBuilder::order_by`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Order {
+    /// Ascending.
+    #[default]
+    Asc,
+    /// Descending.
+    Desc,
+}
+
+/// Builds a `SELECT` statement.
+///
+/// Column names and table paths are quoted as identifiers and values are
+/// rendered as literals, so they are safe to take from user input. The
+/// `*_raw` methods insert SQL verbatim and must only be given trusted text.
+/// Filters are combined with `AND`.
+///
+/// # Example
+///
+/// ```
+/// use dremio_rs::sql::{Comparison, Order, QueryBuilder};
+///
+/// let sql = QueryBuilder::new()
+///     .select(["city", "pop"])
+///     .from(["Samples", "samples.dremio.com", "zips.json"])
+///     .filter("state", Comparison::Eq, "MA")
+///     .filter("pop", Comparison::Gt, 10_000)
+///     .order_by("pop", Order::Desc)
+///     .limit(10)
+///     .build();
+/// assert_eq!(
+///     sql,
+///     r#"SELECT "city", "pop" FROM "Samples"."samples.dremio.com"."zips.json" WHERE "state" = 'MA' AND "pop" > 10000 ORDER BY "pop" DESC LIMIT 10"#
+/// );
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct QueryBuilder {
+    columns: Vec<String>,
+    from: Option<String>,
+    filters: Vec<String>,
+    order_by: Vec<String>,
+    limit: Option<u64>,
+    offset: Option<u64>,
+}
+
+impl QueryBuilder {
+    /// Creates a builder for `SELECT *` without a `FROM` clause.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds columns to the select list.
+    pub fn select<I, S>(mut self, columns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.columns
+            .extend(columns.into_iter().map(|c| quote_ident(c.as_ref())));
+        self
+    }
+
+    /// Adds a raw SQL expression, such as `COUNT(*) AS n`, to the select list.
+    pub fn select_raw(mut self, expression: &str) -> Self {
+        self.columns.push(expression.to_string());
+        self
+    }
+
+    /// Selects from the dataset at `path`, one element per path component.
+    pub fn from<I, S>(mut self, path: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.from = Some(quote_path(path));
+        self
+    }
+
+    /// Selects from a raw SQL table expression, such as a subquery or join.
+    pub fn from_raw(mut self, table: &str) -> Self {
+        self.from = Some(table.to_string());
+        self
+    }
+
+    /// Keeps rows where `column` compares to `value` with `op`.
+    pub fn filter(mut self, column: &str, op: Comparison, value: impl Into<Literal>) -> Self {
+        self.filters
+            .push(format!("{} {} {}", quote_ident(column), op, value.into()));
+        self
+    }
+
+    /// Keeps rows where `column` is one of `values`. An empty list matches no
+    /// rows.
+    pub fn filter_in<I, V>(mut self, column: &str, values: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<Literal>,
+    {
+        let values: Vec<String> = values.into_iter().map(|v| v.into().to_string()).collect();
+        self.filters.push(if values.is_empty() {
+            "FALSE".to_string()
+        } else {
+            format!("{} IN ({})", quote_ident(column), values.join(", "))
+        });
+        self
+    }
+
+    /// Keeps rows where `column` is `NULL`, or is not when `is_null` is false.
+    pub fn filter_null(mut self, column: &str, is_null: bool) -> Self {
+        let not = if is_null { "" } else { "NOT " };
+        self.filters
+            .push(format!("{} IS {}NULL", quote_ident(column), not));
+        self
+    }
+
+    /// Keeps rows matching a raw SQL condition.
+    pub fn filter_raw(mut self, condition: &str) -> Self {
+        self.filters.push(format!("({})", condition));
+        self
+    }
+
+    /// Sorts by `column`. Later calls add tie-breakers.
+    pub fn order_by(mut self, column: &str, order: Order) -> Self {
+        let direction = match order {
+            Order::Asc => "ASC",
+            Order::Desc => "DESC",
+        };
+        self.order_by
+            .push(format!("{} {}", quote_ident(column), direction));
+        self
+    }
+
+    /// Returns at most `limit` rows.
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Skips the first `offset` rows.
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Returns the SQL text.
+    pub fn build(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for QueryBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SELECT ")?;
+        if self.columns.is_empty() {
+            f.write_str("*")?;
+        } else {
+            f.write_str(&self.columns.join(", "))?;
+        }
+        if let Some(from) = &self.from {
+            write!(f, " FROM {}", from)?;
+        }
+        if !self.filters.is_empty() {
+            write!(f, " WHERE {}", self.filters.join(" AND "))?;
+        }
+        if !self.order_by.is_empty() {
+            write!(f, " ORDER BY {}", self.order_by.join(", "))?;
+        }
+        if let Some(limit) = self.limit {
+            write!(f, " LIMIT {}", limit)?;
+        }
+        if let Some(offset) = self.offset {
+            write!(f, " OFFSET {}", offset)?;
+        }
+        Ok(())
+    }
+}
+
+/// Quotes `name` as a single Dremio identifier.
+///
+/// The name is always wrapped in double quotes, with embedded double quotes
+/// doubled. Dremio resolves quoted identifiers case-insensitively, so quoting
+/// never changes which object a name refers to, while it keeps dots, spaces,
+/// `@` and reserved words such as `user` from being misread.
+///
+/// # Example
+///
+/// ```
+/// use dremio_rs::sql::quote_ident;
+///
+/// assert_eq!(quote_ident("sys.version"), r#""sys.version""#);
+/// assert_eq!(quote_ident(r#"say "hi""#), r#""say ""hi""""#);
+/// ```
+pub fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Quotes every component of a dataset path and joins them with dots.
+///
+/// # Example
+///
+/// ```
+/// use dremio_rs::sql::quote_path;
+///
+/// assert_eq!(quote_path(["@dremio", "sys.version"]), r#""@dremio"."sys.version""#);
+/// ```
+pub fn quote_path<I, S>(path: I) -> String
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    path.into_iter()
+        .map(|part| quote_ident(part.as_ref()))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Splits a dotted dataset path as written in SQL into its components.
+///
+/// Dots inside double-quoted components are part of the name, and quotes are
+/// removed. Fails on empty components and unterminated quotes.
+///
+/// # Example
+///
+/// ```
+/// use dremio_rs::sql::parse_path;
+///
+/// let path = parse_path(r#""@dremio"."sys.version""#).unwrap();
+/// assert_eq!(path, vec!["@dremio", "sys.version"]);
+/// assert_eq!(parse_path("Samples.zips").unwrap(), vec!["Samples", "zips"]);
+/// ```
+pub fn parse_path(path: &str) -> Result<Vec<String>, DremioClientError> {
+    let invalid = |reason: &str| {
+        DremioClientError::from(ArrowError::InvalidArgumentError(format!(
+            "invalid dataset path {:?}: {}",
+            path, reason
+        )))
+    };
+    let mut parts = Vec::new();
+    let mut part = String::new();
+    let mut quoted = false;
+    let mut chars = path.trim().chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                part.push('"');
+            }
+            '"' => quoted = !quoted,
+            '.' if !quoted => {
+                if part.trim().is_empty() {
+                    return Err(invalid("empty component"));
+                }
+                parts.push(std::mem::take(&mut part).trim().to_string());
+            }
+            c => part.push(c),
+        }
+    }
+    if quoted {
+        return Err(invalid("unterminated quote"));
+    }
+    if part.trim().is_empty() {
+        return Err(invalid("empty component"));
+    }
+    parts.push(part.trim().to_string());
+    Ok(parts)
+}
+
+/// How many rows [`sample_sql`] draws from a table.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SampleSpec {
+    /// A fixed number of rows, chosen uniformly at random.
+    Rows(u64),
+    /// Each row independently with this probability, between 0 and 1.
+    Fraction(f64),
+}
+
+/// Returns SQL drawing a random sample from the dataset at `path`, a dotted
+/// path as accepted by [`parse_path`].
+///
+/// A fixed number of rows is drawn by ordering the whole table randomly,
+/// which scans all of it; sampling a fraction filters rows as they are read
+/// and is cheaper on large tables.
+///
+/// # Example
+///
+/// ```
+/// use dremio_rs::sql::{sample_sql, SampleSpec};
+///
+/// assert_eq!(
+///     sample_sql("lake.orders", SampleSpec::Fraction(0.01)).unwrap(),
+///     r#"SELECT * FROM "lake"."orders" WHERE RANDOM() < 0.01"#
+/// );
+/// ```
+pub fn sample_sql(path: &str, spec: SampleSpec) -> Result<String, DremioClientError> {
+    let table = quote_path(parse_path(path)?);
+    match spec {
+        SampleSpec::Rows(rows) => Ok(format!(
+            "SELECT * FROM {} ORDER BY RANDOM() LIMIT {}",
+            table, rows
+        )),
+        SampleSpec::Fraction(fraction) if (0.0..=1.0).contains(&fraction) => Ok(format!(
+            "SELECT * FROM {} WHERE RANDOM() < {}",
+            table,
+            Literal::Float(fraction)
+        )),
+        SampleSpec::Fraction(fraction) => Err(ArrowError::InvalidArgumentError(format!(
+            "sample fraction {} is not between 0 and 1",
+            fraction
+        ))
+        .into()),
+    }
+}
+
+/// Limits a `SELECT` (or `WITH`) statement to at most `rows` rows. Other
+/// statements, which cannot be used as subqueries, are returned as is.
+///
+/// Statements are wrapped in a subquery with a `LIMIT`, except those ending
+/// in a top-level `ORDER BY`, which Dremio would drop from a subquery without
+/// a limit of its own: these get the `LIMIT` appended instead. Ordered
+/// statements with an `OFFSET` or `FETCH` but no `LIMIT` are returned as is.
+pub(crate) fn limit_query(query: &str, rows: usize) -> String {
+    let statement = query.trim().trim_end_matches(';').trim_end();
+    let keyword: String = statement
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect();
+    if !keyword.eq_ignore_ascii_case("select") && !keyword.eq_ignore_ascii_case("with") {
+        return query.to_string();
+    }
+    let words = top_level_words(statement);
+    let order_by = words
+        .windows(2)
+        .rposition(|pair| pair[0] == "ORDER" && pair[1] == "BY");
+    let after = order_by.map_or(&[][..], |i| &words[i + 2..]);
+    let has = |word: &str| after.iter().any(|w| w == word);
+    // The line breaks end any trailing `--` comment inside the statement.
+    if order_by.is_none() || has("LIMIT") {
+        format!("SELECT * FROM (\n{}\n) LIMIT {}", statement, rows)
+    } else if has("OFFSET") || has("FETCH") {
+        query.to_string()
+    } else {
+        format!("{}\nLIMIT {}", statement, rows)
+    }
+}
+
+/// Returns the words of `statement` outside of parentheses, literals,
+/// quoted identifiers and comments, in upper case.
+fn top_level_words(statement: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut depth = 0usize;
+    let mut chars = statement.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_alphanumeric() || c == '_' {
+            if depth == 0 {
+                word.extend(c.to_uppercase());
+            }
+            continue;
+        }
+        if !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            // A doubled quote inside is an escaped quote, which this handles
+            // as closing and reopening the literal.
+            '\'' | '"' => {
+                for next in chars.by_ref() {
+                    if next == c {
+                        break;
+                    }
+                }
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            _ => {}
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// Wraps a query so that only the rows matching `predicate` are returned.
+#[cfg(feature = "parquet")]
+pub(crate) fn filter_query(query: &str, predicate: &str) -> String {
+    let statement = query.trim().trim_end_matches(';').trim_end();
+    format!("SELECT * FROM (\n{}\n) WHERE {}", statement, predicate)
+}
+
+/// Returns the SQL literal for the value at `index` of `array`, which holds
+/// numbers, booleans, strings, dates or timestamps, possibly
+/// dictionary-encoded.
+pub(crate) fn value_literal(array: &dyn Array, index: usize) -> Result<String, DremioClientError> {
+    if array.is_null(index) {
+        return Ok(Literal::Null.to_string());
+    }
+    let value = array.slice(index, 1);
+    let value = match value.data_type() {
+        DataType::Dictionary(_, values) => cast(&value, values)?,
+        _ => value,
+    };
+    Ok(match value.data_type() {
+        DataType::Date32 | DataType::Date64 => {
+            let date = cast(&value, &DataType::Date32)?;
+            format!("DATE '{}'", array_value_to_string(&date, 0)?)
+        }
+        DataType::Timestamp(unit, _) => {
+            // Dremio timestamps have no time zone, so they are written in UTC.
+            let timestamp = cast(&value, &DataType::Timestamp(*unit, None))?;
+            let text = array_value_to_string(&timestamp, 0)?;
+            format!("TIMESTAMP '{}'", text.replace('T', " "))
+        }
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => {
+            Literal::Str(array_value_to_string(&value, 0)?).to_string()
+        }
+        data_type if data_type.is_floating() => {
+            let value = cast(&value, &DataType::Float64)?;
+            Literal::Float(value.as_primitive::<Float64Type>().value(0)).to_string()
+        }
+        data_type if data_type.is_numeric() || data_type == &DataType::Boolean => {
+            array_value_to_string(&value, 0)?
+        }
+        data_type => {
+            return Err(ArrowError::InvalidArgumentError(format!(
+                "cannot write a {} value as a SQL literal",
+                data_type
+            ))
+            .into())
+        }
+    })
+}
+
+/// Returns a one-element array holding the greatest non-null value among
+/// `current` and `values`, or `current` if `values` are all null.
+pub(crate) fn max_value(
+    current: Option<ArrayRef>,
+    values: &ArrayRef,
+) -> Result<Option<ArrayRef>, DremioClientError> {
+    let candidates = match &current {
+        Some(current) => concat(&[current.as_ref(), values.as_ref()])?,
+        None => values.clone(),
+    };
+    let options = SortOptions {
+        descending: true,
+        nulls_first: false,
+    };
+    let indices = sort_to_indices(&candidates, Some(options), Some(1))?;
+    match indices.iter().flatten().next() {
+        Some(index) if candidates.is_valid(index as usize) => {
+            Ok(Some(candidates.slice(index as usize, 1)))
+        }
+        _ => Ok(current),
+    }
+}
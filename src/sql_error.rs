@@ -0,0 +1,166 @@
+//! Locating errors in SQL text.
+//!
+//! Dremio reports where a query fails to parse or validate in the text of its
+//! error messages, in one of a few shapes:
+//!
+//! - `Encountered "FRM" at line 1, column 10.` for syntax errors,
+//! - `From line 1, column 15 to line 1, column 21: ...` for validation errors,
+//! - `startLine 1`, `startColumn 10`, `endLine 1`, `endColumn 12` in the
+//!   error context Dremio appends.
+//!
+//! [`DremioClientError::sql_position`](crate::DremioClientError::sql_position)
+//! extracts them as a [`SqlErrorPosition`], which
+//! [`render_snippet`](SqlErrorPosition::render_snippet) turns into a
+//! compiler-style excerpt of the query.
+
+use std::fmt;
+
+/// The location of an error in the text of a query.
+///
+/// Lines and columns start at 1 and count characters; the end is inclusive.
+/// Positions refer to the SQL as Dremio received it, which differs from the
+/// text given to helpers that rewrite queries, such as
+/// [`Client::peek`](crate::Client::peek).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SqlErrorPosition {
+    /// The line the error starts on.
+    pub line: usize,
+    /// The column the error starts at.
+    pub column: usize,
+    /// The line the error ends on.
+    pub end_line: usize,
+    /// The column the error ends at.
+    pub end_column: usize,
+    /// The offending text, when the message quotes it.
+    pub fragment: Option<String>,
+}
+
+impl SqlErrorPosition {
+    /// Extracts the position of an error from a Dremio error `message`, or
+    /// returns `None` if it does not give one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dremio_rs::sql_error::SqlErrorPosition;
+    ///
+    /// let message = "Failure parsing the query. Encountered \"FRM\" at line 1, column 10.";
+    /// let position = SqlErrorPosition::parse(message).unwrap();
+    /// assert_eq!((position.line, position.column, position.end_column), (1, 10, 12));
+    /// assert_eq!(position.fragment.as_deref(), Some("FRM"));
+    /// ```
+    pub fn parse(message: &str) -> Option<Self> {
+        let fragment = message
+            .find("Encountered \"")
+            .and_then(|start| {
+                let rest = &message[start + "Encountered \"".len()..];
+                rest.find("\" at line ").map(|end| &rest[..end])
+            })
+            .map(str::to_string);
+        if let Some(range) = message.find("From line ") {
+            let rest = &message[range..];
+            let (line, rest) = number_after(rest, "From line ")?;
+            let (column, rest) = number_after(rest, ", column ")?;
+            let (end_line, rest) = number_after(rest, " to line ")?;
+            let (end_column, _) = number_after(rest, ", column ")?;
+            return Some(Self {
+                line,
+                column,
+                end_line,
+                end_column,
+                fragment,
+            });
+        }
+        if let Some(at) = message.find("at line ") {
+            let (line, rest) = number_after(&message[at..], "at line ")?;
+            let (column, _) = number_after(rest, ", column ")?;
+            // Syntax errors only give the start; the fragment gives the end
+            // when it fits on the line.
+            let end_column = match &fragment {
+                Some(text) if !text.is_empty() && !text.contains('\n') => {
+                    column + text.chars().count() - 1
+                }
+                _ => column,
+            };
+            return Some(Self {
+                line,
+                column,
+                end_line: line,
+                end_column,
+                fragment,
+            });
+        }
+        let field = |name: &str| {
+            let start = message.find(name)?;
+            number_after(&message[start..], name).map(|(n, _)| n)
+        };
+        let line = field("startLine ")?;
+        let column = field("startColumn ")?;
+        Some(Self {
+            line,
+            column,
+            end_line: field("endLine ").unwrap_or(line),
+            end_column: field("endColumn ").unwrap_or(column),
+            fragment,
+        })
+    }
+
+    /// Renders the lines of `sql` covered by this position, each followed by
+    /// a line of carets under the offending text.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dremio_rs::sql_error::SqlErrorPosition;
+    ///
+    /// let position = SqlErrorPosition::parse("Encountered \"FRM\" at line 2, column 3.").unwrap();
+    /// assert_eq!(
+    ///     position.render_snippet("SELECT *\n  FRM t"),
+    ///     "2 |   FRM t\n  |   ^^^\n"
+    /// );
+    /// ```
+    pub fn render_snippet(&self, sql: &str) -> String {
+        let last = self.end_line.max(self.line);
+        let width = last.to_string().len();
+        let mut out = String::new();
+        for (index, text) in sql.lines().enumerate() {
+            let number = index + 1;
+            if number < self.line || number > last {
+                continue;
+            }
+            let chars: Vec<char> = text.chars().collect();
+            let start = if number == self.line { self.column } else { 1 };
+            let end = if number == last {
+                self.end_column
+            } else {
+                chars.len()
+            };
+            // Keep tabs in the indentation so the carets line up.
+            let indent: String = chars
+                .iter()
+                .take(start.saturating_sub(1))
+                .map(|c| if *c == '\t' { '\t' } else { ' ' })
+                .collect();
+            let carets = "^".repeat((end + 1).saturating_sub(start).max(1));
+            out.push_str(&format!("{:>width$} | {}\n", number, text));
+            out.push_str(&format!("{:>width$} | {}{}\n", "", indent, carets));
+        }
+        out
+    }
+}
+
+impl fmt::Display for SqlErrorPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// Parses the number following `prefix` at the start of `text`, returning it
+/// with the rest of the text.
+fn number_after<'a>(text: &'a str, prefix: &str) -> Option<(usize, &'a str)> {
+    let rest = text.strip_prefix(prefix)?;
+    let digits = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    Some((rest[..digits].parse().ok()?, &rest[digits..]))
+}
@@ -0,0 +1,83 @@
+//! Recognizing errors caused by outdated dataset metadata.
+//!
+//! Dremio caches the list of files and partitions behind each dataset. When
+//! new data lands, queries can fail until the metadata is refreshed, with
+//! messages naming the affected dataset in one of a few shapes.
+
+use crate::sql::parse_path;
+
+/// Phrases that mark a failure as caused by missing or outdated metadata.
+const STALE_MARKERS: &[&str] = &[
+    "not found",
+    "unable to find table",
+    "out of date",
+    "stale",
+    "refresh metadata",
+    "metadata refresh",
+];
+
+/// Returns the path of the dataset a stale-metadata error `message` refers
+/// to, or `None` if the message is not of that kind or names no dataset.
+pub(crate) fn stale_dataset(message: &str) -> Option<Vec<String>> {
+    // ASCII lowercasing keeps byte offsets valid for `message`.
+    let lower = message.to_ascii_lowercase();
+    if !STALE_MARKERS.iter().any(|marker| lower.contains(marker)) {
+        return None;
+    }
+    // "Object 'orders' not found within 'lake.sales'"
+    if let Some(start) = lower.find("object '") {
+        let start = start + "object ".len();
+        let rest = &message[start..];
+        let name = quoted(rest, '\'', '\'')?;
+        let mut path = match lower[start..].find("within '") {
+            Some(within) => {
+                parse_path(quoted(&rest[within + "within ".len()..], '\'', '\'')?).ok()?
+            }
+            None => Vec::new(),
+        };
+        path.push(name.to_string());
+        return Some(path);
+    }
+    // "Table 'lake.sales.orders' not found", "dataset [lake.sales.orders] is out of date"
+    let name = match (message.find('\''), message.find('[')) {
+        (Some(quote), Some(bracket)) if bracket < quote => quoted(&message[bracket..], '[', ']'),
+        (Some(quote), _) => quoted(&message[quote..], '\'', '\''),
+        (None, Some(bracket)) => quoted(&message[bracket..], '[', ']'),
+        (None, None) => None,
+    }?;
+    // Paths are sometimes printed as lists, e.g. "[lake, sales, orders]".
+    if name.contains(", ") {
+        return Some(
+            name.split(", ")
+                .map(|part| part.trim().to_string())
+                .collect(),
+        );
+    }
+    parse_path(name).ok()
+}
+
+/// Returns the text between `open` at the start of `text` and the next `close`.
+fn quoted(text: &str, open: char, close: char) -> Option<&str> {
+    let rest = text.strip_prefix(open)?;
+    rest.find(close).map(|end| &rest[..end])
+}
+
+/// Extracts the message from a `tonic::Status` printed with `{:?}`, which is
+/// how the Flight SQL client reports failed calls.
+pub(crate) fn status_message(debug: &str) -> Option<String> {
+    let start = debug.find("message: \"")? + "message: \"".len();
+    let mut message = String::new();
+    let mut chars = debug[start..].chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Some(message),
+            '\\' => match chars.next()? {
+                'n' => message.push('\n'),
+                't' => message.push('\t'),
+                c => message.push(c),
+            },
+            c => message.push(c),
+        }
+    }
+    None
+}
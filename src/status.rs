@@ -0,0 +1,100 @@
+//! Reading gRPC statuses back from the errors of the Flight SQL client.
+//!
+//! The Flight SQL client reports failed calls as `ArrowError::IpcError`
+//! holding the `tonic::Status` printed with `{:?}`, or with `{}` for
+//! handshakes and lossy decoding, so the code and message are parsed from
+//! that text.
+
+use crate::options::Deadline;
+use crate::DremioClientError;
+use arrow::error::ArrowError;
+use arrow_flight::error::FlightError;
+use tonic::Code;
+
+/// Returns the gRPC status code `error` was caused by, if any.
+pub(crate) fn status_code(error: &DremioClientError) -> Option<Code> {
+    match error.root() {
+        DremioClientError::FlightError(FlightError::Tonic(status)) => Some(status.code()),
+        DremioClientError::ArrowError(ArrowError::IpcError(status)) => {
+            let rest = &status[status.find("code: ")? + "code: ".len()..];
+            (0..=16).map(Code::from_i32).find(|code| {
+                let named = rest
+                    .strip_prefix(&format!("{:?}", code))
+                    .is_some_and(|after| !after.starts_with(char::is_alphanumeric));
+                named || rest.starts_with(&format!("'{}'", code))
+            })
+        }
+        DremioClientError::AuthenticationFailed(_) => Some(Code::Unauthenticated),
+        DremioClientError::QueryFailed { code, .. } => Some(*code),
+        _ => None,
+    }
+}
+
+/// Turns a status returned by the server into the error variant describing
+/// it: [`DremioClientError::AuthenticationFailed`] for rejected credentials,
+/// and [`DremioClientError::QueryFailed`] for queries Dremio could not
+/// parse, validate, plan or run.
+///
+/// Statuses reporting a transient condition, such as an unavailable
+/// coordinator or an expired deadline, are returned unchanged.
+pub(crate) fn classify(error: DremioClientError) -> DremioClientError {
+    let Some(code) = status_code(&error) else {
+        return error;
+    };
+    let message = error.server_message().unwrap_or_default();
+    match (code, error) {
+        (_, error @ DremioClientError::Query { .. }) => error,
+        (
+            Code::Unavailable
+            | Code::DeadlineExceeded
+            | Code::Cancelled
+            | Code::ResourceExhausted
+            | Code::Aborted,
+            error,
+        ) => error,
+        (_, error @ DremioClientError::AuthenticationFailed(_))
+        | (_, error @ DremioClientError::QueryFailed { .. }) => error,
+        (Code::Unauthenticated, _) => DremioClientError::AuthenticationFailed(message),
+        (code, _) => DremioClientError::QueryFailed {
+            code,
+            query_id: query_id(&message),
+            message,
+        },
+    }
+}
+
+/// Reports `error` as a [`DremioClientError::TimeoutError`] if it is the
+/// server or the transport giving up on a call once `deadline` has passed.
+pub(crate) fn expired(error: DremioClientError, deadline: Option<Deadline>) -> DremioClientError {
+    match deadline {
+        Some(deadline)
+            if deadline.remaining().is_none()
+                && matches!(
+                    status_code(&error),
+                    Some(Code::DeadlineExceeded | Code::Cancelled)
+                ) =>
+        {
+            DremioClientError::TimeoutError(deadline.timeout())
+        }
+        _ => error,
+    }
+}
+
+/// Extracts the ID of the query from a message of Dremio, such as
+/// `... (Query ID: 1a6b8f2c-34d1-a9e0-7b2c-5d1f0e9a8c00)`.
+fn query_id(message: &str) -> Option<String> {
+    let lower = message.to_ascii_lowercase();
+    [
+        "query id", "queryid", "query_id", "job id", "jobid", "job_id",
+    ]
+    .iter()
+    .find_map(|label| {
+        let start = lower.find(label)? + label.len();
+        let rest = message[start..].trim_start_matches([':', '=', ' ']);
+        let id: String = rest
+            .chars()
+            .take_while(|c| c.is_ascii_hexdigit() || *c == '-')
+            .collect();
+        (id.len() >= 8).then_some(id)
+    })
+}
@@ -0,0 +1,738 @@
+//! Incremental access to query results.
+
+use crate::cancel::QueryHandle;
+use crate::checks::{Checker, Checks};
+use crate::options::{apply_deadline, Deadline, EndpointOrder};
+use crate::retry::RetryPolicy;
+use crate::status;
+use crate::{DremioClientError, QueryOptions};
+use arrow::array::RecordBatch;
+use arrow::compute::{concat_batches, BatchCoalescer};
+use arrow::datatypes::{Schema, SchemaRef};
+use arrow_flight::decode::DecodedPayload;
+use arrow_flight::sql::client::FlightSqlServiceClient;
+use arrow_flight::{CancelStatus, FlightData, Ticket};
+use futures::future::{self, Either};
+use futures::stream::{self, BoxStream, Stream, StreamExt, TryStreamExt};
+use std::fmt;
+use std::pin::{pin, Pin};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio::task::JoinHandle;
+use tonic::transport::Channel;
+
+/// A stream of `RecordBatch`es produced by a query.
+///
+/// Batches are yielded as they arrive from Dremio, so results of any size can
+/// be processed without holding them all in memory. The stream ends after the
+/// last batch or after the first error.
+pub struct QueryStream {
+    inner: BoxStream<'static, Result<RecordBatch, DremioClientError>>,
+    stats: Arc<StatsCounters>,
+    expected_rows: Option<u64>,
+    schema: Option<SchemaRef>,
+    handle: Option<QueryHandle>,
+}
+
+/// Progress of a query, as reported to callbacks registered with
+/// [`QueryStream::with_progress`] or
+/// [`QueryOptions::with_progress`](crate::QueryOptions::with_progress).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QueryMetrics {
+    /// Rows received so far.
+    pub rows: u64,
+    /// Batches received so far.
+    pub batches: u64,
+    /// In-memory size of the batches received so far, in bytes.
+    pub bytes: u64,
+    /// Size of the Flight messages received so far, in bytes, as sent over
+    /// the network.
+    pub bytes_transferred: u64,
+    /// Batches received so far from each endpoint, in the order Dremio
+    /// listed the endpoints.
+    pub endpoint_batches: Vec<u64>,
+    /// Time since progress reporting started.
+    pub elapsed: Duration,
+    /// Total number of rows announced by Dremio, when known.
+    pub expected_rows: Option<u64>,
+}
+
+impl QueryMetrics {
+    /// Returns the fraction of expected rows received so far, when the total
+    /// is known.
+    pub fn fraction(&self) -> Option<f64> {
+        self.expected_rows
+            .filter(|total| *total > 0)
+            .map(|total| (self.rows as f64 / total as f64).min(1.0))
+    }
+}
+
+/// Statistics collected while a [`QueryStream`] is consumed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QueryStats {
+    /// Number of string values that contained invalid UTF-8 and were repaired
+    /// in lossy mode (see [`QueryOptions::with_lossy_utf8`](crate::QueryOptions::with_lossy_utf8)).
+    pub invalid_utf8_values: u64,
+    /// Number of Flight messages that carried `app_metadata` (see
+    /// [`QueryOptions::with_app_metadata`](crate::QueryOptions::with_app_metadata)).
+    pub app_metadata_messages: u64,
+}
+
+type AppMetadataFn = dyn Fn(&[u8]) + Send + Sync;
+
+type ProgressFn = dyn Fn(&QueryMetrics) + Send + Sync;
+
+/// A callback receiving the progress of a query.
+#[derive(Clone)]
+pub(crate) struct ProgressHook(pub(crate) Arc<ProgressFn>);
+
+impl fmt::Debug for ProgressHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressHook")
+    }
+}
+
+/// A callback receiving the `app_metadata` of Flight messages.
+#[derive(Clone)]
+pub(crate) struct AppMetadataHook(pub(crate) Arc<AppMetadataFn>);
+
+impl fmt::Debug for AppMetadataHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("AppMetadataHook")
+    }
+}
+
+/// Shared counters behind [`QueryStats`], updated by the decoding tasks.
+#[derive(Debug, Default)]
+pub(crate) struct StatsCounters {
+    invalid_utf8_values: AtomicU64,
+    app_metadata_messages: AtomicU64,
+    app_metadata: Option<AppMetadataHook>,
+    bytes_transferred: AtomicU64,
+    endpoint_batches: Vec<AtomicU64>,
+}
+
+impl StatsCounters {
+    pub(crate) fn add_invalid_utf8(&self, count: u64) {
+        if count > 0 {
+            self.invalid_utf8_values.fetch_add(count, Ordering::Relaxed);
+        }
+    }
+
+    /// Counts the size of a Flight message and passes its `app_metadata` to
+    /// the hook, if any.
+    pub(crate) fn add_message(&self, data: &FlightData) {
+        let size = data.data_header.len() + data.data_body.len() + data.app_metadata.len();
+        self.bytes_transferred
+            .fetch_add(size as u64, Ordering::Relaxed);
+        if data.app_metadata.is_empty() {
+            return;
+        }
+        self.app_metadata_messages.fetch_add(1, Ordering::Relaxed);
+        if let Some(hook) = &self.app_metadata {
+            (hook.0)(&data.app_metadata);
+        }
+    }
+
+    fn add_batch(&self, endpoint: usize) {
+        if let Some(batches) = self.endpoint_batches.get(endpoint) {
+            batches.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Copies the transfer counters into `metrics`.
+    fn fill(&self, metrics: &mut QueryMetrics) {
+        metrics.bytes_transferred = self.bytes_transferred.load(Ordering::Relaxed);
+        metrics.endpoint_batches = self
+            .endpoint_batches
+            .iter()
+            .map(|batches| batches.load(Ordering::Relaxed))
+            .collect();
+    }
+
+    fn snapshot(&self) -> QueryStats {
+        QueryStats {
+            invalid_utf8_values: self.invalid_utf8_values.load(Ordering::Relaxed),
+            app_metadata_messages: self.app_metadata_messages.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl QueryStream {
+    pub(crate) fn new<S>(stream: S) -> Self
+    where
+        S: Stream<Item = Result<RecordBatch, DremioClientError>> + Send + 'static,
+    {
+        Self::with_stats(stream, Arc::default())
+    }
+
+    /// Creates a stream that yields `batches`, for example to stand in for
+    /// query results in tests.
+    pub fn from_batches(batches: Vec<RecordBatch>) -> Self {
+        let rows = batches.iter().map(|b| b.num_rows() as u64).sum();
+        Self::new(stream::iter(batches.into_iter().map(Ok))).with_expected_rows(Some(rows))
+    }
+
+    fn with_stats<S>(stream: S, stats: Arc<StatsCounters>) -> Self
+    where
+        S: Stream<Item = Result<RecordBatch, DremioClientError>> + Send + 'static,
+    {
+        Self {
+            inner: stream.boxed(),
+            stats,
+            expected_rows: None,
+            schema: None,
+            handle: None,
+        }
+    }
+
+    /// Replaces the underlying stream, keeping the collected statistics.
+    fn with_inner<F, S>(self, f: F) -> Self
+    where
+        F: FnOnce(BoxStream<'static, Result<RecordBatch, DremioClientError>>) -> S,
+        S: Stream<Item = Result<RecordBatch, DremioClientError>> + Send + 'static,
+    {
+        Self {
+            inner: f(self.inner).boxed(),
+            stats: self.stats,
+            expected_rows: self.expected_rows,
+            schema: self.schema,
+            handle: self.handle,
+        }
+    }
+
+    /// Records the total number of rows announced for the query.
+    pub(crate) fn with_expected_rows(mut self, rows: Option<u64>) -> Self {
+        self.expected_rows = rows;
+        self
+    }
+
+    /// Records the schema announced for the query.
+    pub(crate) fn with_schema(mut self, schema: Option<SchemaRef>) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    /// Records the handle cancelling the query.
+    pub(crate) fn with_handle(mut self, handle: QueryHandle) -> Self {
+        self.handle = Some(handle);
+        self
+    }
+
+    /// Cancels the query on the server once `deadline` has passed, unless
+    /// the stream has ended by then.
+    ///
+    /// The timer runs on a task of its own, which ends as soon as the stream
+    /// does. If the stream is dropped before it ends, the query is cancelled
+    /// right away rather than at the deadline. Errors caused by the expired
+    /// deadline are reported as [`DremioClientError::TimeoutError`].
+    pub(crate) fn cancel_at(self, deadline: Option<Deadline>) -> Self {
+        let (Some(deadline), Some(handle)) = (deadline, self.handle.clone()) else {
+            return self;
+        };
+        let (ended, done) = oneshot::channel::<()>();
+        tokio::spawn(async move {
+            let expired = pin!(tokio::time::sleep(deadline.remaining().unwrap_or_default()));
+            // The sender is dropped without sending when the stream is
+            // dropped before its end.
+            if let Either::Left((Ok(()), _)) = future::select(done, expired).await {
+                return;
+            }
+            let _ = handle.cancel().await;
+        });
+        let mut ended = Some(ended);
+        self.map_err(move |e| status::expired(e, Some(deadline)))
+            .with_inner(|inner| {
+                inner.chain(stream::poll_fn(move |_| {
+                    if let Some(ended) = ended.take() {
+                        let _ = ended.send(());
+                    }
+                    Poll::Ready(None)
+                }))
+            })
+    }
+
+    /// Returns the statistics collected so far.
+    pub fn stats(&self) -> QueryStats {
+        self.stats.snapshot()
+    }
+
+    /// Returns the total number of rows announced by Dremio, when known.
+    pub fn expected_rows(&self) -> Option<u64> {
+        self.expected_rows
+    }
+
+    /// Returns the schema Dremio announced for the results, with the
+    /// conversions of the query options applied, when known.
+    ///
+    /// It is known before any batch arrives, and for results without rows.
+    pub fn schema(&self) -> Option<SchemaRef> {
+        self.schema.clone()
+    }
+
+    /// Returns a handle to cancel the query on the server, when the stream
+    /// comes from Dremio.
+    pub fn handle(&self) -> Option<QueryHandle> {
+        self.handle.clone()
+    }
+
+    /// Stops fetching results and asks Dremio to cancel the query.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is:
+    /// - `Ok(CancelStatus)` as reported by Dremio, or `NotCancellable` for
+    ///   streams that do not come from Dremio.
+    /// - `Err(DremioClientError)` if the request failed.
+    pub async fn cancel(self) -> Result<CancelStatus, DremioClientError> {
+        let Self { inner, handle, .. } = self;
+        drop(inner);
+        match handle {
+            Some(handle) => handle.cancel().await,
+            None => Ok(CancelStatus::NotCancellable),
+        }
+    }
+
+    /// Calls `callback` with updated [`QueryMetrics`] after every batch.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::{Client, QueryOptions};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let stream = client
+    ///     .execute_stream_with_options("SELECT * FROM sys.options", &QueryOptions::new())
+    ///     .await
+    ///     .unwrap()
+    ///     .with_progress(|metrics| eprintln!("{} rows", metrics.rows));
+    ///   let batches = stream.collect_batches().await.unwrap();
+    /// }
+    /// ```
+    pub fn with_progress<F>(self, mut callback: F) -> Self
+    where
+        F: FnMut(&QueryMetrics) + Send + 'static,
+    {
+        let started = Instant::now();
+        let stats = self.stats.clone();
+        let mut metrics = QueryMetrics {
+            expected_rows: self.expected_rows,
+            ..Default::default()
+        };
+        self.with_inner(move |inner| {
+            inner.inspect(move |item| {
+                if let Ok(batch) = item {
+                    metrics.rows += batch.num_rows() as u64;
+                    metrics.batches += 1;
+                    metrics.bytes += batch.get_array_memory_size() as u64;
+                    metrics.elapsed = started.elapsed();
+                    stats.fill(&mut metrics);
+                    callback(&metrics);
+                }
+            })
+        })
+    }
+
+    /// Publishes updated [`QueryMetrics`] after every batch on a `watch`
+    /// channel, whose receiver is returned along with the stream.
+    ///
+    /// Unlike a callback, the receiver can be polled at the pace of its
+    /// reader, such as a progress bar redrawn on a timer or a task exporting
+    /// metrics, and always holds the latest progress.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dremio_rs::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+    ///   let stream = client.execute_stream("SELECT * FROM sys.options").await.unwrap();
+    ///   let (stream, mut progress) = stream.watch_progress();
+    ///   tokio::spawn(async move {
+    ///     while progress.changed().await.is_ok() {
+    ///       eprintln!("{} rows", progress.borrow().rows);
+    ///     }
+    ///   });
+    ///   let batches = stream.collect_batches().await.unwrap();
+    /// }
+    /// ```
+    pub fn watch_progress(self) -> (Self, watch::Receiver<QueryMetrics>) {
+        let (sender, receiver) = watch::channel(QueryMetrics {
+            expected_rows: self.expected_rows,
+            ..Default::default()
+        });
+        let stream = self.with_progress(move |metrics| {
+            sender.send_replace(metrics.clone());
+        });
+        (stream, receiver)
+    }
+
+    /// Calls the progress hook of the query options, if any.
+    pub(crate) fn with_progress_hook(self, hook: Option<ProgressHook>) -> Self {
+        match hook {
+            Some(hook) => self.with_progress(move |metrics| (hook.0)(metrics)),
+            None => self,
+        }
+    }
+
+    /// Evaluates `checks` on every batch as it is yielded.
+    ///
+    /// A batch violating a required check is replaced by a
+    /// [`DremioClientError::CheckFailed`] error. Checks that need every row,
+    /// such as [`Check::MinRows`](crate::checks::Check::MinRows), are
+    /// evaluated and warnings reported once the stream is exhausted.
+    pub fn with_checks(self, checks: Checks) -> Self {
+        if checks.is_empty() {
+            return self;
+        }
+        let checker = Arc::new(Mutex::new(Checker::new(checks)));
+        let last = checker.clone();
+        self.with_inner(move |inner| {
+            let checked = inner.map(move |item| {
+                let mut checker = checker.lock().unwrap();
+                match item {
+                    Ok(batch) => checker.update(&batch).map(|()| batch),
+                    Err(e) => {
+                        checker.abandon();
+                        Err(e)
+                    }
+                }
+            });
+            let finished = stream::once(async move { last.lock().unwrap().finish() })
+                .filter_map(|result| async move { result.err().map(Err) });
+            checked.chain(finished)
+        })
+    }
+
+    /// Fetches the given endpoint tickets, each from the client serving it,
+    /// up to the endpoint concurrency of `options` at a time, merging their
+    /// batches in the requested order. `headers` are the extra gRPC metadata
+    /// the clients were configured with.
+    pub(crate) fn from_endpoints(
+        endpoints: Vec<(FlightSqlServiceClient<Channel>, Ticket)>,
+        headers: Arc<[(String, String)]>,
+        deadline: Option<Deadline>,
+        options: &QueryOptions,
+        retry: RetryPolicy,
+    ) -> Self {
+        let stats = Arc::new(StatsCounters {
+            app_metadata: options.app_metadata_hook(),
+            endpoint_batches: endpoints.iter().map(|_| AtomicU64::new(0)).collect(),
+            ..StatsCounters::default()
+        });
+        let (concurrency, order) = (options.endpoint_concurrency(), options.endpoint_order());
+        let lossy_utf8 = options.lossy_utf8();
+        let user = options.impersonated_user().map(str::to_string);
+        // The raw Flight client of lossy decoding sends the headers itself;
+        // those of the session properties come last, to take precedence.
+        let session = options.session_options().headers();
+        let headers: Arc<[(String, String)]> = headers
+            .iter()
+            .cloned()
+            .chain(
+                session
+                    .into_iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string())),
+            )
+            .collect();
+        let endpoint_stats = stats.clone();
+        // Endpoints are fetched lazily, once the span of the query is left.
+        #[cfg(feature = "tracing")]
+        let parent = tracing::Span::current();
+        let endpoints = stream::iter(endpoints.into_iter().enumerate().map(
+            move |(index, (client, ticket))| {
+                let batches = if lossy_utf8 {
+                    crate::lossy::endpoint_stream(
+                        client,
+                        ticket,
+                        deadline,
+                        headers.clone(),
+                        user.clone(),
+                        endpoint_stats.clone(),
+                        retry.clone(),
+                    )
+                } else {
+                    endpoint_stream(
+                        client,
+                        ticket,
+                        deadline,
+                        endpoint_stats.clone(),
+                        retry.clone(),
+                    )
+                };
+                #[cfg(feature = "tracing")]
+                let batches = crate::telemetry::endpoint(batches, index, &parent);
+                let stats = endpoint_stats.clone();
+                batches.inspect_ok(move |_| stats.add_batch(index))
+            },
+        ));
+        if concurrency <= 1 {
+            return Self::with_stats(endpoints.flatten(), stats);
+        }
+        match order {
+            EndpointOrder::AsReady => {
+                Self::with_stats(endpoints.flatten_unordered(concurrency), stats)
+            }
+            // Each endpoint is read on a task of its own, up to `concurrency`
+            // at a time, and drained in order: the first one streams through
+            // while the others read ahead until their channel is full.
+            EndpointOrder::Preserve => Self::with_stats(
+                endpoints
+                    .map(|endpoint| future::ready(Prefetch::spawn(endpoint, ENDPOINT_READ_AHEAD)))
+                    .buffered(concurrency)
+                    .flatten(),
+                stats,
+            ),
+        }
+    }
+
+    /// Applies `f` to every batch as it is yielded.
+    pub(crate) fn map_batches<F>(self, mut f: F) -> Self
+    where
+        F: FnMut(RecordBatch) -> Result<RecordBatch, DremioClientError> + Send + 'static,
+    {
+        self.with_inner(|inner| inner.map(move |item| item.and_then(&mut f)))
+    }
+
+    /// Applies `f` to every error as it is yielded.
+    pub(crate) fn map_err<F>(self, f: F) -> Self
+    where
+        F: FnMut(DremioClientError) -> DremioClientError + Send + 'static,
+    {
+        self.with_inner(|inner| inner.map_err(f))
+    }
+
+    /// Re-chunks the stream into batches of exactly `rows` rows, except for
+    /// the last one. `None` leaves the batches as they arrive.
+    ///
+    /// Small batches are buffered until enough rows have arrived and large
+    /// ones are split. A result made only of empty batches still yields one
+    /// empty batch, so its schema is not lost.
+    pub(crate) fn rechunk(self, rows: Option<usize>) -> Self {
+        let Some(rows) = rows.filter(|rows| *rows > 0) else {
+            return self;
+        };
+        self.with_inner(|inner| Rechunk {
+            inner,
+            rows,
+            coalescer: None,
+            emitted: false,
+            done: false,
+        })
+    }
+
+    /// Reads ahead up to `depth` batches on a background task while the
+    /// consumer is busy with the current one. A depth of zero disables
+    /// prefetching.
+    pub(crate) fn prefetch(self, depth: usize) -> Self {
+        if depth == 0 {
+            return self;
+        }
+        self.with_inner(|inner| Prefetch::spawn(inner, depth))
+    }
+
+    /// Collects up to `rows` rows into a single batch and drops the rest of
+    /// the stream, which stops fetching further batches. With `cancel_rest`,
+    /// Dremio is also asked to cancel the query once enough rows have
+    /// arrived, for queries that are not limited on the server.
+    ///
+    /// Returns an empty batch with the announced schema if the stream yields
+    /// no batches, or without columns if no schema was announced either.
+    pub(crate) async fn head(
+        mut self,
+        rows: usize,
+        cancel_rest: bool,
+    ) -> Result<RecordBatch, DremioClientError> {
+        let mut batches = Vec::new();
+        let mut remaining = rows;
+        let mut schema = None;
+        let announced = self.schema();
+        while let Some(batch) = self.next().await {
+            let batch = batch?;
+            schema.get_or_insert_with(|| batch.schema());
+            let batch = batch.slice(0, batch.num_rows().min(remaining));
+            remaining -= batch.num_rows();
+            batches.push(batch);
+            if remaining == 0 {
+                if cancel_rest {
+                    // The preview is complete; failing to stop the job
+                    // does not make it wrong.
+                    let _ = self.cancel().await;
+                }
+                break;
+            }
+        }
+        let schema = schema
+            .or(announced)
+            .unwrap_or_else(|| Arc::new(Schema::empty()));
+        Ok(concat_batches(&schema, &batches)?)
+    }
+
+    /// Collects the remaining batches into a vector.
+    pub async fn collect_batches(mut self) -> Result<Vec<RecordBatch>, DremioClientError> {
+        let mut batches = Vec::new();
+        while let Some(batch) = self.next().await {
+            batches.push(batch?);
+        }
+        Ok(batches)
+    }
+}
+
+impl Stream for QueryStream {
+    type Item = Result<RecordBatch, DremioClientError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+/// Stream behind [`QueryStream::rechunk`].
+struct Rechunk {
+    inner: BoxStream<'static, Result<RecordBatch, DremioClientError>>,
+    rows: usize,
+    coalescer: Option<BatchCoalescer>,
+    emitted: bool,
+    done: bool,
+}
+
+impl Rechunk {
+    /// Flushes the buffered rows once the input has ended.
+    fn finish(&mut self) -> Result<(), DremioClientError> {
+        self.done = true;
+        if let Some(coalescer) = &mut self.coalescer {
+            let schema = coalescer.schema();
+            coalescer.finish_buffered_batch()?;
+            if !self.emitted && !coalescer.has_completed_batch() {
+                coalescer.push_batch(RecordBatch::new_empty(schema))?;
+                coalescer.finish_buffered_batch()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Stream for Rechunk {
+    type Item = Result<RecordBatch, DremioClientError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        loop {
+            if let Some(batch) = this
+                .coalescer
+                .as_mut()
+                .and_then(|c| c.next_completed_batch())
+            {
+                this.emitted = true;
+                return Poll::Ready(Some(Ok(batch)));
+            }
+            if this.done {
+                return Poll::Ready(None);
+            }
+            let result = match std::task::ready!(this.inner.poll_next_unpin(cx)) {
+                Some(Ok(batch)) => {
+                    let rows = this.rows;
+                    this.coalescer
+                        .get_or_insert_with(|| BatchCoalescer::new(batch.schema(), rows))
+                        .push_batch(batch)
+                        .map_err(DremioClientError::from)
+                }
+                Some(Err(e)) => Err(e),
+                None => this.finish(),
+            };
+            if let Err(e) = result {
+                this.done = true;
+                this.coalescer = None;
+                return Poll::Ready(Some(Err(e)));
+            }
+        }
+    }
+}
+
+/// Batches read ahead from each endpoint waiting for its turn when endpoints
+/// are fetched in parallel in the order Dremio listed them.
+const ENDPOINT_READ_AHEAD: usize = 16;
+
+/// Receiving end of a prefetching task; aborts the task when dropped so an
+/// abandoned stream stops pulling from the server.
+struct Prefetch {
+    rx: mpsc::Receiver<Result<RecordBatch, DremioClientError>>,
+    task: JoinHandle<()>,
+}
+
+impl Prefetch {
+    /// Reads `inner` on a background task, up to `depth` items ahead.
+    fn spawn<S>(mut inner: S, depth: usize) -> Self
+    where
+        S: Stream<Item = Result<RecordBatch, DremioClientError>> + Send + Unpin + 'static,
+    {
+        let (tx, rx) = mpsc::channel(depth);
+        let task = tokio::spawn(async move {
+            while let Some(item) = inner.next().await {
+                let failed = item.is_err();
+                if tx.send(item).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+        Prefetch { rx, task }
+    }
+}
+
+impl Stream for Prefetch {
+    type Item = Result<RecordBatch, DremioClientError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+impl Drop for Prefetch {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Opens a `do_get` stream for a single endpoint ticket.
+fn endpoint_stream(
+    client: FlightSqlServiceClient<Channel>,
+    ticket: Ticket,
+    deadline: Option<Deadline>,
+    stats: Arc<StatsCounters>,
+    retry: RetryPolicy,
+) -> BoxStream<'static, Result<RecordBatch, DremioClientError>> {
+    stream::once(async move {
+        let stream = retry
+            .run(|| {
+                let (mut client, ticket) = (client.clone(), ticket.clone());
+                async move {
+                    apply_deadline(&mut client, deadline)?;
+                    Ok::<_, DremioClientError>(client.do_get(ticket).await?)
+                }
+            })
+            .await?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!("opened do_get stream");
+        // Decode the messages ourselves to see their app_metadata, which
+        // `FlightRecordBatchStream` drops.
+        let batches = stream
+            .into_inner()
+            .map_err(DremioClientError::from)
+            .try_filter_map(move |data| {
+                stats.add_message(&data.inner);
+                future::ready(Ok(match data.payload {
+                    DecodedPayload::RecordBatch(batch) => Some(batch),
+                    DecodedPayload::Schema(_) | DecodedPayload::None => None,
+                }))
+            });
+        Ok::<_, DremioClientError>(batches)
+    })
+    .try_flatten()
+    .boxed()
+}
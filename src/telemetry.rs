@@ -0,0 +1,78 @@
+//! Structured `tracing` spans and events, with the `tracing` feature.
+//!
+//! Connecting, handshakes, query submission and the writers are instrumented
+//! where they are defined. The endpoints of a query are fetched lazily, long
+//! after the query was submitted, so their streams are wrapped here in spans
+//! of their own, which nest under the span of the query and record the
+//! batches and rows each endpoint yielded.
+
+use crate::DremioClientError;
+use arrow::array::RecordBatch;
+use futures::stream::{BoxStream, Stream, StreamExt};
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use tracing::Span;
+
+/// Fetches the endpoint `index` of a query inside a span under `parent`.
+pub(crate) fn endpoint(
+    stream: BoxStream<'static, Result<RecordBatch, DremioClientError>>,
+    index: usize,
+    parent: &Span,
+) -> BoxStream<'static, Result<RecordBatch, DremioClientError>> {
+    let span = tracing::debug_span!(
+        parent: parent,
+        "fetch_endpoint",
+        endpoint = index,
+        batches = tracing::field::Empty,
+        rows = tracing::field::Empty,
+    );
+    Instrumented {
+        inner: stream,
+        span,
+        batches: 0,
+        rows: 0,
+    }
+    .boxed()
+}
+
+/// Records the ID Dremio gave the query in the current span, when `error`
+/// reports one.
+pub(crate) fn record_query_id(error: &DremioClientError) {
+    if let DremioClientError::QueryFailed {
+        query_id: Some(id), ..
+    } = error.root()
+    {
+        Span::current().record("query_id", id.as_str());
+    }
+}
+
+struct Instrumented {
+    inner: BoxStream<'static, Result<RecordBatch, DremioClientError>>,
+    span: Span,
+    batches: u64,
+    rows: u64,
+}
+
+impl Stream for Instrumented {
+    type Item = Result<RecordBatch, DremioClientError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let _entered = this.span.enter();
+        let item = ready!(this.inner.poll_next_unpin(cx));
+        match &item {
+            Some(Ok(batch)) => {
+                this.batches += 1;
+                this.rows += batch.num_rows() as u64;
+                tracing::trace!(rows = batch.num_rows(), "received batch");
+            }
+            Some(Err(e)) => tracing::warn!(error = %e, "fetching endpoint failed"),
+            None => {
+                this.span.record("batches", this.batches);
+                this.span.record("rows", this.rows);
+                tracing::debug!(batches = this.batches, rows = this.rows, "fetched endpoint");
+            }
+        }
+        Poll::Ready(item)
+    }
+}
@@ -0,0 +1,386 @@
+//! SQL kept in `.sql` files, rendered with parameters.
+//!
+//! A [`TemplateLibrary`] loads every `.sql` file below a directory, so query
+//! text can live next to the code and be shared with analysts who edit it in
+//! their own tools. Each template is named after its path relative to the
+//! directory, without the extension, such as `reports/daily`.
+//!
+//! Templates contain placeholders between double braces:
+//!
+//! - `{{ name }}` is replaced with the parameter `name` as a SQL literal,
+//!   quoted and escaped, or with a comma-separated list of literals for list
+//!   parameters, to be used as in `IN ({{ ids }})`. An empty list renders as
+//!   `NULL`, which matches nothing;
+//! - `{{ name | ident }}` quotes a string parameter as an identifier, such as
+//!   a column name;
+//! - `{{ name | path }}` quotes a dotted dataset path such as `lake.orders`;
+//! - `{{ name | raw }}` inserts the parameter as is, and must only be used
+//!   with trusted values;
+//! - `{{> other/template }}` includes the template `other/template`, named
+//!   relative to the library rather than to the including file.
+//!
+//! ```sql
+//! -- reports/daily.sql
+//! SELECT {{ column | ident }}, SUM(amount) AS total
+//! FROM {{ table | path }}
+//! WHERE {{> filters/active }} AND region IN ({{ regions }})
+//! GROUP BY 1
+//! ```
+//!
+//! Parameters are given as [`Params`] or, with the `serde` feature, as any
+//! struct implementing `Serialize`.
+
+use crate::sql::{parse_path, quote_ident, quote_path, Literal};
+use crate::DremioClientError;
+use arrow::error::ArrowError;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+/// Parameter values to render templates with.
+///
+/// # Example
+///
+/// ```
+/// use dremio_rs::templates::{Params, TemplateLibrary};
+///
+/// let library = TemplateLibrary::new()
+///     .with_template("orders", "SELECT * FROM orders WHERE state = {{ state }} AND id IN ({{ ids }})")
+///     .unwrap();
+/// let params = Params::new().with("state", "O'Hare").with_list("ids", [1, 2]);
+/// assert_eq!(
+///     library.render("orders", &params).unwrap(),
+///     "SELECT * FROM orders WHERE state = 'O''Hare' AND id IN (1, 2)"
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Params {
+    values: HashMap<String, Param>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Param {
+    Value(Literal),
+    List(Vec<Literal>),
+}
+
+impl Params {
+    /// Creates an empty set of parameters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the parameter `name` to `value`.
+    pub fn with(mut self, name: &str, value: impl Into<Literal>) -> Self {
+        self.values
+            .insert(name.to_string(), Param::Value(value.into()));
+        self
+    }
+
+    /// Sets the parameter `name` to a list of values.
+    pub fn with_list<I, V>(mut self, name: &str, values: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<Literal>,
+    {
+        let values = values.into_iter().map(Into::into).collect();
+        self.values.insert(name.to_string(), Param::List(values));
+        self
+    }
+
+    /// Takes the parameters from the fields of `value`, a struct or map.
+    ///
+    /// Fields must be strings, numbers, booleans, `None` or sequences of
+    /// those, which become list parameters. Requires the `serde` feature.
+    #[cfg(feature = "serde")]
+    pub fn from_serialize<T: serde::Serialize>(value: &T) -> Result<Self, DremioClientError> {
+        use serde_json::Value;
+
+        fn literal(name: &str, value: Value) -> Result<Literal, DremioClientError> {
+            Ok(match value {
+                Value::Null => Literal::Null,
+                Value::Bool(value) => Literal::Bool(value),
+                Value::Number(number) => match number.as_i64() {
+                    Some(value) => Literal::Int(value),
+                    None => Literal::Float(number.as_f64().unwrap_or(f64::NAN)),
+                },
+                Value::String(value) => Literal::Str(value),
+                Value::Array(_) | Value::Object(_) => {
+                    return Err(invalid(format!(
+                        "template parameter {} must be a string, number, boolean or null",
+                        name
+                    )))
+                }
+            })
+        }
+
+        let value =
+            serde_json::to_value(value).map_err(|e| ArrowError::ExternalError(Box::new(e)))?;
+        let Value::Object(fields) = value else {
+            return Err(invalid(
+                "template parameters must be a struct or a map".to_string(),
+            ));
+        };
+        let mut params = Params::new();
+        for (name, value) in fields {
+            let param = match value {
+                Value::Array(values) => Param::List(
+                    values
+                        .into_iter()
+                        .map(|value| literal(&name, value))
+                        .collect::<Result<_, _>>()?,
+                ),
+                value => Param::Value(literal(&name, value)?),
+            };
+            params.values.insert(name, param);
+        }
+        Ok(params)
+    }
+}
+
+/// How a parameter is written into the SQL.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Filter {
+    Literal,
+    Ident,
+    Path,
+    Raw,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Segment {
+    Text(String),
+    Param { name: String, filter: Filter },
+    Include(String),
+}
+
+/// A set of named SQL templates, see the [module documentation](self).
+///
+/// # Example
+///
+/// ```no_run
+/// use dremio_rs::templates::{Params, TemplateLibrary};
+/// use dremio_rs::Client;
+///
+/// #[tokio::main]
+/// async fn main() {
+///   let library = TemplateLibrary::load("sql").unwrap();
+///   let sql = library
+///     .render("reports/daily", &Params::new().with("table", "lake.orders"))
+///     .unwrap();
+///   let mut client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+///   let batches = client.get_record_batches(&sql).await.unwrap();
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct TemplateLibrary {
+    templates: BTreeMap<String, Vec<Segment>>,
+}
+
+impl TemplateLibrary {
+    /// Creates an empty library.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads every `.sql` file below `dir`, including subdirectories.
+    ///
+    /// Fails if a file cannot be read or has an unclosed or malformed
+    /// placeholder. Includes are only resolved when rendering.
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self, DremioClientError> {
+        let mut library = Self::new();
+        library.load_dir(dir.as_ref(), "")?;
+        Ok(library)
+    }
+
+    fn load_dir(&mut self, dir: &Path, prefix: &str) -> Result<(), DremioClientError> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                let Some(dir_name) = path.file_name().and_then(|name| name.to_str()) else {
+                    continue;
+                };
+                let prefix = format!("{}{}/", prefix, dir_name);
+                self.load_dir(&path, &prefix)?;
+            } else if path.extension().is_some_and(|extension| extension == "sql") {
+                let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                    continue;
+                };
+                let name = format!("{}{}", prefix, stem);
+                let text = fs::read_to_string(&path)?;
+                self.templates.insert(name.clone(), parse(&name, &text)?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the template `name` with the SQL `text`, replacing any template
+    /// of the same name.
+    pub fn with_template(mut self, name: &str, text: &str) -> Result<Self, DremioClientError> {
+        self.templates.insert(name.to_string(), parse(name, text)?);
+        Ok(self)
+    }
+
+    /// Returns the names of the templates, in alphabetical order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.templates.keys().map(String::as_str)
+    }
+
+    /// Returns whether the library has a template `name`.
+    pub fn contains(&self, name: &str) -> bool {
+        self.templates.contains_key(name)
+    }
+
+    /// Renders the template `name` with `params`.
+    ///
+    /// Fails if the template or one it includes does not exist, if includes
+    /// form a cycle, or if a parameter is missing or cannot be written as
+    /// its placeholder asks.
+    pub fn render(&self, name: &str, params: &Params) -> Result<String, DremioClientError> {
+        let mut sql = String::new();
+        self.render_into(name, params, &mut Vec::new(), &mut sql)?;
+        Ok(sql)
+    }
+
+    /// Renders the template `name` with the fields of `params`, see
+    /// [`Params::from_serialize`]. Requires the `serde` feature.
+    #[cfg(feature = "serde")]
+    pub fn render_with<T: serde::Serialize>(
+        &self,
+        name: &str,
+        params: &T,
+    ) -> Result<String, DremioClientError> {
+        self.render(name, &Params::from_serialize(params)?)
+    }
+
+    fn render_into<'a>(
+        &'a self,
+        name: &'a str,
+        params: &Params,
+        stack: &mut Vec<&'a str>,
+        sql: &mut String,
+    ) -> Result<(), DremioClientError> {
+        let segments = self
+            .templates
+            .get(name)
+            .ok_or_else(|| invalid(format!("no template named {}", name)))?;
+        if stack.contains(&name) {
+            return Err(invalid(format!(
+                "templates include each other: {} > {}",
+                stack.join(" > "),
+                name
+            )));
+        }
+        stack.push(name);
+        for segment in segments {
+            match segment {
+                Segment::Text(text) => sql.push_str(text),
+                Segment::Param {
+                    name: param,
+                    filter,
+                } => {
+                    let value = params.values.get(param).ok_or_else(|| {
+                        invalid(format!("template {} needs the parameter {}", name, param))
+                    })?;
+                    sql.push_str(&write_param(name, param, value, *filter)?);
+                }
+                Segment::Include(include) => self.render_into(include, params, stack, sql)?,
+            }
+        }
+        stack.pop();
+        Ok(())
+    }
+}
+
+/// Writes the parameter `param` of the template `template` as `filter` asks.
+fn write_param(
+    template: &str,
+    param: &str,
+    value: &Param,
+    filter: Filter,
+) -> Result<String, DremioClientError> {
+    let text = match (filter, value) {
+        (Filter::Literal, Param::Value(value)) => return Ok(value.to_string()),
+        (Filter::Literal, Param::List(values)) if values.is_empty() => {
+            return Ok("NULL".to_string())
+        }
+        (Filter::Literal, Param::List(values)) => {
+            let values: Vec<String> = values.iter().map(Literal::to_string).collect();
+            return Ok(values.join(", "));
+        }
+        (_, Param::Value(Literal::Str(text))) => text.clone(),
+        (Filter::Raw, Param::Value(value)) => return Ok(value.to_string()),
+        _ => {
+            return Err(invalid(format!(
+                "parameter {} of template {} must be a string",
+                param, template
+            )))
+        }
+    };
+    Ok(match filter {
+        Filter::Ident => quote_ident(&text),
+        Filter::Path => quote_path(parse_path(&text)?),
+        _ => text,
+    })
+}
+
+/// Splits the text of the template `name` into segments.
+fn parse(name: &str, text: &str) -> Result<Vec<Segment>, DremioClientError> {
+    let mut segments = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let offset = text.len() - rest.len() + start;
+        let line = text[..offset].matches('\n').count() + 1;
+        if start > 0 {
+            segments.push(Segment::Text(rest[..start].to_string()));
+        }
+        let Some(end) = rest[start..].find("}}") else {
+            return Err(invalid(format!("{}:{}: unclosed placeholder", name, line)));
+        };
+        let placeholder = rest[start + 2..start + end].trim();
+        let error = |message: &str| {
+            invalid(format!(
+                "{}:{}: {} in {{{{ {} }}}}",
+                name, line, message, placeholder
+            ))
+        };
+        let segment = if let Some(include) = placeholder.strip_prefix('>') {
+            let include = include.trim();
+            if include.is_empty() {
+                return Err(error("missing template name"));
+            }
+            Segment::Include(include.to_string())
+        } else {
+            let (param, filter) = match placeholder.split_once('|') {
+                Some((param, filter)) => (param.trim(), filter.trim()),
+                None => (placeholder, ""),
+            };
+            let filter = match filter {
+                "" => Filter::Literal,
+                "ident" => Filter::Ident,
+                "path" => Filter::Path,
+                "raw" => Filter::Raw,
+                _ => return Err(error("unknown filter")),
+            };
+            let valid =
+                !param.is_empty() && param.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid {
+                return Err(error("invalid parameter name"));
+            }
+            Segment::Param {
+                name: param.to_string(),
+                filter,
+            }
+        };
+        segments.push(segment);
+        rest = &rest[start + end + 2..];
+    }
+    if !rest.is_empty() {
+        segments.push(Segment::Text(rest.to_string()));
+    }
+    Ok(segments)
+}
+
+fn invalid(message: String) -> DremioClientError {
+    ArrowError::InvalidArgumentError(message).into()
+}
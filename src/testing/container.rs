@@ -0,0 +1,164 @@
+use crate::{Client, DremioClientError};
+use testcontainers::core::{ContainerPort, WaitFor};
+use testcontainers::runners::AsyncRunner;
+use testcontainers::{ContainerAsync, GenericImage};
+
+const IMAGE: &str = "dremio/dremio-oss";
+const REST_PORT: ContainerPort = ContainerPort::Tcp(9047);
+const FLIGHT_PORT: ContainerPort = ContainerPort::Tcp(32010);
+const READY_MESSAGE: &str = "com.dremio.dac.server.DremioServer - Started on http://localhost:9047";
+
+/// Settings for a Dremio OSS instance started in a Docker container.
+///
+/// Starting the container waits until Dremio is up and creates its first
+/// user, so the instance is ready for queries. Requires a running Docker
+/// daemon and the `testcontainers` feature.
+///
+/// # Example
+///
+/// ```no_run
+/// use dremio_rs::testing::DremioContainer;
+///
+/// #[tokio::main]
+/// async fn main() {
+///    let dremio = DremioContainer::new().start().await.unwrap();
+///    let mut client = dremio.connect().await.unwrap();
+///    client.get_record_batches("SELECT * FROM sys.options").await.unwrap();
+///    dremio.stop().await.unwrap();
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct DremioContainer {
+    tag: String,
+    user: String,
+    password: String,
+}
+
+impl Default for DremioContainer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DremioContainer {
+    /// Uses the `latest` image with user `dremio` and password `dremio123`.
+    pub fn new() -> Self {
+        Self {
+            tag: "latest".to_string(),
+            user: "dremio".to_string(),
+            password: "dremio123".to_string(),
+        }
+    }
+
+    /// Sets the tag of the `dremio/dremio-oss` image to run.
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.tag = tag.to_string();
+        self
+    }
+
+    /// Sets the credentials of the first user, created on startup.
+    pub fn with_credentials(mut self, user: &str, password: &str) -> Self {
+        self.user = user.to_string();
+        self.password = password.to_string();
+        self
+    }
+
+    /// Starts the container, waits for Dremio to come up and creates the
+    /// first user.
+    pub async fn start(self) -> Result<RunningDremio, DremioClientError> {
+        let container = GenericImage::new(IMAGE, &self.tag)
+            .with_exposed_port(REST_PORT)
+            .with_exposed_port(FLIGHT_PORT)
+            .with_wait_for(WaitFor::message_on_stdout(READY_MESSAGE))
+            .start()
+            .await?;
+        let host = container.get_host().await?;
+        let rest_url = format!(
+            "http://{}:{}",
+            host,
+            container.get_host_port_ipv4(REST_PORT).await?
+        );
+        let flight_url = format!(
+            "http://{}:{}",
+            host,
+            container.get_host_port_ipv4(FLIGHT_PORT).await?
+        );
+        let dremio = RunningDremio {
+            container,
+            rest_url,
+            flight_url,
+            user: self.user,
+            password: self.password,
+        };
+        dremio.create_first_user().await?;
+        Ok(dremio)
+    }
+}
+
+/// A Dremio instance started by [`DremioContainer`]. Dropping it removes the
+/// container.
+pub struct RunningDremio {
+    container: ContainerAsync<GenericImage>,
+    rest_url: String,
+    flight_url: String,
+    user: String,
+    password: String,
+}
+
+impl RunningDremio {
+    /// Returns the Flight SQL endpoint, to pass to [`Client::new`].
+    pub fn flight_url(&self) -> &str {
+        &self.flight_url
+    }
+
+    /// Returns the REST API endpoint.
+    pub fn rest_url(&self) -> &str {
+        &self.rest_url
+    }
+
+    /// Returns the name of the first user.
+    pub fn user(&self) -> &str {
+        &self.user
+    }
+
+    /// Returns the password of the first user.
+    pub fn password(&self) -> &str {
+        &self.password
+    }
+
+    /// Connects to the instance as the first user.
+    pub async fn connect(&self) -> Result<Client, DremioClientError> {
+        Client::new(&self.flight_url, &self.user, &self.password).await
+    }
+
+    /// Stops and removes the container.
+    pub async fn stop(self) -> Result<(), DremioClientError> {
+        self.container.stop().await?;
+        self.container.rm().await?;
+        Ok(())
+    }
+
+    async fn create_first_user(&self) -> Result<(), DremioClientError> {
+        let body = serde_json::json!({
+            "userName": self.user,
+            "firstName": self.user,
+            "lastName": self.user,
+            "email": format!("{}@example.com", self.user),
+            "createdAt": 1526186430755u64,
+            "password": self.password,
+        });
+        let response = reqwest::Client::new()
+            .put(format!("{}/apiv2/bootstrap/firstuser", self.rest_url))
+            .header("Authorization", "_dremionull")
+            .json(&body)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(DremioClientError::RestError {
+                status: response.status().as_u16(),
+                message: response.text().await.unwrap_or_default(),
+            });
+        }
+        Ok(())
+    }
+}
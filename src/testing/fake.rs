@@ -0,0 +1,1059 @@
+use super::Pattern;
+use crate::cancel::CANCEL_FLIGHT_INFO;
+use crate::snapshot::{read_bundles, Bundle};
+use crate::sql;
+use crate::DremioClientError;
+use arrow::array::RecordBatch;
+use arrow::datatypes::{Schema, SchemaRef};
+use arrow::ipc::writer::IpcWriteOptions;
+use arrow_flight::decode::FlightRecordBatchStream;
+use arrow_flight::encode::FlightDataEncoderBuilder;
+use arrow_flight::error::FlightError;
+use arrow_flight::flight_descriptor::DescriptorType;
+use arrow_flight::flight_service_server::FlightServiceServer;
+use arrow_flight::sql::metadata::SqlInfoDataBuilder;
+use arrow_flight::sql::server::{FlightSqlService, PeekableFlightDataStream};
+use arrow_flight::sql::{
+    ActionClosePreparedStatementRequest, ActionCreatePreparedStatementRequest,
+    ActionCreatePreparedStatementResult, Any, Command, CommandGetCatalogs, CommandGetDbSchemas,
+    CommandGetSqlInfo, CommandGetTableTypes, CommandGetTables, CommandPreparedStatementQuery,
+    CommandPreparedStatementUpdate, CommandStatementIngest, CommandStatementQuery,
+    CommandStatementUpdate, DoPutPreparedStatementResult, ProstMessageExt, SqlInfo,
+    TableExistsOption, TableNotExistOption, TicketStatementQuery,
+};
+use arrow_flight::{
+    Action, CancelFlightInfoRequest, CancelFlightInfoResult, CancelStatus, FlightData,
+    FlightDescriptor, FlightEndpoint, FlightInfo, HandshakeRequest, HandshakeResponse, IpcMessage,
+    PutResult, SchemaAsIpc, Ticket,
+};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use prost::Message;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tonic::metadata::{MetadataMap, MetadataValue};
+use tonic::transport::server::TcpIncoming;
+use tonic::transport::Server;
+use tonic::{Request, Response, Status, Streaming};
+
+/// How the server answers handshakes.
+#[derive(Clone)]
+enum Handshake {
+    AcceptAny,
+    Credentials { user: String, password: String },
+    Reject(String),
+}
+
+#[derive(Clone)]
+struct FakeEndpoint {
+    batches: Vec<RecordBatch>,
+    error: Option<Status>,
+    location: Option<String>,
+}
+
+/// The catalog in which a [`FakeFlightSqlServer`] lists its tables.
+const CATALOG: &str = "DREMIO";
+
+/// The table types listed by a [`FakeFlightSqlServer`]; its tables are all
+/// of type `TABLE`.
+const TABLE_TYPES: [&str; 3] = ["SYSTEM_TABLE", "TABLE", "VIEW"];
+
+/// The answer of a [`FakeFlightSqlServer`] to a query.
+///
+/// A response either fails the query outright or announces a schema and one
+/// or more endpoints, each serving its own batches. Endpoints can fail after
+/// sending their batches, to exercise errors that happen mid-stream.
+#[derive(Clone)]
+pub struct FakeResponse {
+    schema: SchemaRef,
+    endpoints: Vec<FakeEndpoint>,
+    error: Option<Status>,
+    delay: Duration,
+    app_metadata: Vec<Vec<u8>>,
+}
+
+impl FakeResponse {
+    /// Creates a response announcing `schema`, without any endpoints.
+    pub fn new(schema: SchemaRef) -> Self {
+        Self {
+            schema,
+            endpoints: Vec::new(),
+            error: None,
+            delay: Duration::ZERO,
+            app_metadata: Vec::new(),
+        }
+    }
+
+    /// Creates a response that fails the query when it is submitted.
+    pub fn error(error: Status) -> Self {
+        Self {
+            error: Some(error),
+            ..Self::new(Arc::new(Schema::empty()))
+        }
+    }
+
+    /// Adds an endpoint serving `batches`.
+    pub fn with_endpoint(mut self, batches: Vec<RecordBatch>) -> Self {
+        self.endpoints.push(FakeEndpoint {
+            batches,
+            error: None,
+            location: None,
+        });
+        self
+    }
+
+    /// Adds an endpoint whose ticket is to be fetched from `location`, such
+    /// as the URL of another fake server standing in for an executor.
+    ///
+    /// The ticket names the query and the position of the endpoint, so the
+    /// server at `location` must answer the same query, registered at the
+    /// same position, with an endpoint at the same position. This server
+    /// serves `batches` if asked instead.
+    pub fn with_endpoint_at(mut self, location: &str, batches: Vec<RecordBatch>) -> Self {
+        self.endpoints.push(FakeEndpoint {
+            batches,
+            error: None,
+            location: Some(location.to_string()),
+        });
+        self
+    }
+
+    /// Adds an endpoint serving `batches` and then failing with `error`.
+    pub fn with_failing_endpoint(mut self, batches: Vec<RecordBatch>, error: Status) -> Self {
+        self.endpoints.push(FakeEndpoint {
+            batches,
+            error: Some(error),
+            location: None,
+        });
+        self
+    }
+
+    /// Waits for `delay` before answering, like a query that takes long to
+    /// plan or is queued.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Attaches `metadata` as `app_metadata` to the next batch message of
+    /// every endpoint, like a server reporting progress along with the
+    /// results. Metadata beyond the number of batches is not sent.
+    pub fn with_app_metadata(mut self, metadata: &[u8]) -> Self {
+        self.app_metadata.push(metadata.to_vec());
+        self
+    }
+
+    fn total_records(&self) -> i64 {
+        self.endpoints
+            .iter()
+            .flat_map(|endpoint| &endpoint.batches)
+            .map(|batch| batch.num_rows() as i64)
+            .sum()
+    }
+}
+
+/// An in-process Flight SQL server answering queries with canned results.
+///
+/// Where [`MockClient`](super::MockClient) replaces the client, this server
+/// replaces the cluster, so tests exercise the real [`Client`](crate::Client)
+/// over gRPC: handshakes, tickets, multiple endpoints and transport errors.
+///
+/// Responses are registered against SQL patterns, matched like those of
+/// [`MockClient`](super::MockClient). Queries matching no pattern fail with
+/// `INVALID_ARGUMENT`. By default every handshake succeeds; requests other
+/// than handshakes must carry a bearer token issued by the server.
+///
+/// Updates registered with [`on_update`](Self::on_update) answer
+/// [`Client::execute_update`](crate::Client::execute_update). Queries and
+/// updates can also be prepared. The parameters bound to them are recorded,
+/// see [`FakeServerHandle::parameters`], but do not change the response.
+/// Queries can be cancelled with `CancelFlightInfo`, which always succeeds;
+/// cancelled queries are recorded, see [`FakeServerHandle::cancelled`].
+/// Transient failures and coordinator restarts can be simulated with
+/// [`FakeServerHandle::fail_next`] and [`FakeServerHandle::revoke_tokens`].
+///
+/// Batches sent with Flight SQL bulk ingestion are kept in memory per table,
+/// and can be inspected with [`FakeServerHandle::table`]. So are batches
+/// uploaded without authentication by a plain `DoPut` for a path descriptor,
+/// which replace the table named by the joined path.
+///
+/// The Flight SQL metadata commands list these tables, in the catalog
+/// `DREMIO`, along with the schemas they are in: every prefix of their
+/// paths, the way Dremio lists sources, spaces and folders.
+///
+/// # Example
+///
+/// ```
+/// use arrow::array::{Int32Array, RecordBatch};
+/// use arrow::datatypes::{DataType, Field, Schema};
+/// use dremio_rs::testing::{FakeFlightSqlServer, FakeResponse};
+/// use dremio_rs::Client;
+/// use std::sync::Arc;
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let schema = Arc::new(Schema::new(vec![Field::new("n", DataType::Int32, false)]));
+/// let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(Int32Array::from(vec![1, 2]))]).unwrap();
+/// let server = FakeFlightSqlServer::new()
+///     .with_credentials("dremio", "dremio123")
+///     .on("SELECT * FROM t", FakeResponse::new(schema).with_endpoint(vec![batch]))
+///     .start()
+///     .await
+///     .unwrap();
+///
+/// let mut client = Client::new(&server.url(), "dremio", "dremio123").await.unwrap();
+/// let batches = client.get_record_batches("select * from t").await.unwrap();
+/// assert_eq!(batches[0].num_rows(), 2);
+/// assert_eq!(server.queries(), vec!["select * from t"]);
+/// # }
+/// ```
+pub struct FakeFlightSqlServer {
+    handshake: Handshake,
+    tokens: HashSet<String>,
+    rules: Vec<(Pattern, FakeResponse)>,
+    updates: Vec<(Pattern, i64)>,
+    tables: HashMap<String, Vec<RecordBatch>>,
+}
+
+impl Default for FakeFlightSqlServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FakeFlightSqlServer {
+    /// Creates a server that accepts any credentials and has no responses.
+    pub fn new() -> Self {
+        Self {
+            handshake: Handshake::AcceptAny,
+            tokens: HashSet::new(),
+            rules: Vec::new(),
+            updates: Vec::new(),
+            tables: HashMap::new(),
+        }
+    }
+
+    /// Only accepts handshakes with the given user name and password.
+    pub fn with_credentials(mut self, user: &str, password: &str) -> Self {
+        self.handshake = Handshake::Credentials {
+            user: user.to_string(),
+            password: password.to_string(),
+        };
+        self
+    }
+
+    /// Accepts `token` as a bearer token without a handshake, like a
+    /// personal access token.
+    pub fn with_token(mut self, token: &str) -> Self {
+        self.tokens.insert(token.to_string());
+        self
+    }
+
+    /// Fails every handshake with `UNAUTHENTICATED` and `message`.
+    pub fn rejecting_handshake(mut self, message: &str) -> Self {
+        self.handshake = Handshake::Reject(message.to_string());
+        self
+    }
+
+    /// Creates the table `name`, a dot-separated path such as `lake.orders`,
+    /// holding `batches`, as a target for bulk ingestion.
+    pub fn with_table(mut self, name: &str, batches: Vec<RecordBatch>) -> Self {
+        self.tables.insert(name.to_string(), batches);
+        self
+    }
+
+    /// Answers queries matching `pattern` with `response`.
+    pub fn on(mut self, pattern: &str, response: FakeResponse) -> Self {
+        self.rules
+            .push((Pattern::Glob(pattern.to_string()), response));
+        self
+    }
+
+    /// Answers updates, such as `INSERT` or `CREATE TABLE` statements,
+    /// matching `pattern` with `rows` affected rows.
+    pub fn on_update(mut self, pattern: &str, rows: i64) -> Self {
+        self.updates
+            .push((Pattern::Glob(pattern.to_string()), rows));
+        self
+    }
+
+    /// Creates a server answering the queries recorded by a
+    /// [`Recorder`](super::Recorder) in `dir`, each from a single endpoint.
+    ///
+    /// Recorded queries only match the exact same SQL, up to whitespace.
+    pub fn replay(dir: impl AsRef<Path>) -> Result<Self, DremioClientError> {
+        let mut server = Self::new();
+        for recording in read_bundles(dir.as_ref())? {
+            let response = match recording.error {
+                Some(message) => FakeResponse::error(Bundle::status(&message)),
+                None => FakeResponse::new(recording.schema).with_endpoint(recording.batches),
+            };
+            server
+                .rules
+                .push((Pattern::Literal(recording.query), response));
+        }
+        Ok(server)
+    }
+
+    /// Starts serving on a free port of the loopback interface.
+    ///
+    /// The server runs on the current Tokio runtime until the returned handle
+    /// is shut down or dropped.
+    pub async fn start(self) -> Result<FakeServerHandle, DremioClientError> {
+        let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0))).await?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(State {
+            handshake: self.handshake,
+            rules: self.rules,
+            updates: self.updates,
+            tokens: Mutex::new(self.tokens),
+            handshakes: AtomicUsize::new(0),
+            failures: Mutex::new(VecDeque::new()),
+            queries: Mutex::new(Vec::new()),
+            user_agents: Mutex::new(Vec::new()),
+            impersonations: Mutex::new(Vec::new()),
+            metadata: Mutex::new(Vec::new()),
+            cancelled: Mutex::new(Vec::new()),
+            parameters: Mutex::new(Vec::new()),
+            tables: Mutex::new(self.tables),
+        });
+        let service = FakeService {
+            state: state.clone(),
+        };
+        let (shutdown, signal) = oneshot::channel::<()>();
+        let task = tokio::spawn(
+            Server::builder()
+                .add_service(FlightServiceServer::new(service))
+                .serve_with_incoming_shutdown(TcpIncoming::from(listener), async {
+                    let _ = signal.await;
+                }),
+        );
+        Ok(FakeServerHandle {
+            addr,
+            state,
+            shutdown: Some(shutdown),
+            task: Some(task),
+        })
+    }
+}
+
+/// A running [`FakeFlightSqlServer`]. Dropping the handle stops the server.
+pub struct FakeServerHandle {
+    addr: SocketAddr,
+    state: Arc<State>,
+    shutdown: Option<oneshot::Sender<()>>,
+    task: Option<JoinHandle<Result<(), tonic::transport::Error>>>,
+}
+
+impl FakeServerHandle {
+    /// Returns the URL to pass to [`Client::new`](crate::Client::new).
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Returns the queries submitted so far, in order.
+    pub fn queries(&self) -> Vec<String> {
+        self.state.queries.lock().unwrap().clone()
+    }
+
+    /// Returns the `user-agent` headers the queries were submitted with, in
+    /// order.
+    pub fn user_agents(&self) -> Vec<String> {
+        self.state.user_agents.lock().unwrap().clone()
+    }
+
+    /// Returns the `impersonation_target` headers of the calls submitting
+    /// queries and fetching their results, in order, `None` where the header
+    /// was missing.
+    pub fn impersonations(&self) -> Vec<Option<String>> {
+        self.state.impersonations.lock().unwrap().clone()
+    }
+
+    /// Returns the values of the header `key` on the calls submitting
+    /// queries and fetching their results, in order, `None` where the header
+    /// was missing.
+    pub fn headers(&self, key: &str) -> Vec<Option<String>> {
+        self.state
+            .metadata
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|metadata| {
+                metadata
+                    .get(key)
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string)
+            })
+            .collect()
+    }
+
+    /// Returns the queries cancelled so far, in order.
+    pub fn cancelled(&self) -> Vec<String> {
+        self.state.cancelled.lock().unwrap().clone()
+    }
+
+    /// Returns the parameters bound to prepared statements so far, in order.
+    pub fn parameters(&self) -> Vec<RecordBatch> {
+        self.state.parameters.lock().unwrap().clone()
+    }
+
+    /// Returns the batches of the table `name`, a dot-separated path such as
+    /// `lake.orders`, if it exists. Components containing dots are quoted, as
+    /// in `lake."2024.q1".orders`.
+    pub fn table(&self, name: &str) -> Option<Vec<RecordBatch>> {
+        self.state.tables.lock().unwrap().get(name).cloned()
+    }
+
+    /// Fails the next `count` calls other than handshakes with `error`, like
+    /// a coordinator that is briefly unavailable.
+    pub fn fail_next(&self, count: usize, error: Status) {
+        let mut failures = self.state.failures.lock().unwrap();
+        failures.extend(std::iter::repeat_n(error, count));
+    }
+
+    /// Forgets the bearer tokens accepted so far, like a coordinator that
+    /// restarted: calls carrying them fail with `UNAUTHENTICATED` until the
+    /// client authenticates again.
+    pub fn revoke_tokens(&self) {
+        self.state.tokens.lock().unwrap().clear();
+    }
+
+    /// Returns the number of handshakes answered so far, successful or not.
+    pub fn handshakes(&self) -> usize {
+        self.state.handshakes.load(Ordering::Relaxed)
+    }
+
+    /// Stops the server and waits for it to finish.
+    pub async fn shutdown(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(task) = self.task.take() {
+            let _ = task.await;
+        }
+    }
+}
+
+impl Drop for FakeServerHandle {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+struct State {
+    handshake: Handshake,
+    rules: Vec<(Pattern, FakeResponse)>,
+    updates: Vec<(Pattern, i64)>,
+    tokens: Mutex<HashSet<String>>,
+    handshakes: AtomicUsize,
+    failures: Mutex<VecDeque<Status>>,
+    queries: Mutex<Vec<String>>,
+    user_agents: Mutex<Vec<String>>,
+    impersonations: Mutex<Vec<Option<String>>>,
+    metadata: Mutex<Vec<MetadataMap>>,
+    cancelled: Mutex<Vec<String>>,
+    parameters: Mutex<Vec<RecordBatch>>,
+    tables: Mutex<HashMap<String, Vec<RecordBatch>>>,
+}
+
+impl State {
+    fn record_headers<T>(&self, request: &Request<T>) {
+        let user = request
+            .metadata()
+            .get("impersonation_target")
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        self.impersonations.lock().unwrap().push(user);
+        self.metadata
+            .lock()
+            .unwrap()
+            .push(request.metadata().clone());
+    }
+
+    fn authorize<T>(&self, request: &Request<T>) -> Result<(), Status> {
+        if let Some(error) = self.failures.lock().unwrap().pop_front() {
+            return Err(error);
+        }
+        let token = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        match token {
+            Some(token) if self.tokens.lock().unwrap().contains(token) => Ok(()),
+            _ => Err(Status::unauthenticated("missing or invalid bearer token")),
+        }
+    }
+
+    fn check_credentials<T>(&self, request: &Request<T>) -> Result<(), Status> {
+        let (user, password) = match &self.handshake {
+            Handshake::AcceptAny => return Ok(()),
+            Handshake::Reject(message) => return Err(Status::unauthenticated(message.clone())),
+            Handshake::Credentials { user, password } => (user, password),
+        };
+        let given = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Basic "))
+            .and_then(|value| BASE64_STANDARD.decode(value).ok())
+            .and_then(|value| String::from_utf8(value).ok());
+        if given.as_deref() == Some(&format!("{}:{}", user, password)) {
+            Ok(())
+        } else {
+            Err(Status::unauthenticated("invalid user name or password"))
+        }
+    }
+
+    /// Returns the number of rows affected by the update `sql`, if it matches
+    /// one.
+    fn update(&self, sql: &str) -> Option<i64> {
+        self.updates
+            .iter()
+            .find(|(pattern, _)| pattern.matches(sql))
+            .map(|(_, rows)| *rows)
+    }
+
+    /// Returns the schema and name of every table, sorted.
+    fn table_paths(&self) -> Vec<(String, String)> {
+        let mut paths: Vec<(String, String)> = self
+            .tables
+            .lock()
+            .unwrap()
+            .keys()
+            .map(|path| match path.rsplit_once('.') {
+                Some((schema, name)) => (schema.to_string(), name.to_string()),
+                None => (String::new(), path.clone()),
+            })
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    fn endpoint(&self, handle: &[u8]) -> Result<(&FakeResponse, &FakeEndpoint), Status> {
+        let invalid = || Status::invalid_argument("unknown ticket");
+        let handle = std::str::from_utf8(handle).map_err(|_| invalid())?;
+        let (rule, endpoint) = handle.split_once('/').ok_or_else(invalid)?;
+        let rule: usize = rule.parse().map_err(|_| invalid())?;
+        let endpoint: usize = endpoint.parse().map_err(|_| invalid())?;
+        let (_, response) = self.rules.get(rule).ok_or_else(invalid)?;
+        Ok((
+            response,
+            response.endpoints.get(endpoint).ok_or_else(invalid)?,
+        ))
+    }
+}
+
+/// Returns the key of the table at `path`: its components joined with dots,
+/// with the ones containing dots quoted.
+fn table_name(path: &[String]) -> String {
+    path.iter()
+        .map(|part| match part.contains('.') {
+            true => sql::quote_ident(part),
+            false => part.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Returns the SQL of a prepared statement from its handle, which is the SQL
+/// itself.
+fn prepared_sql(handle: &[u8]) -> Result<String, Status> {
+    String::from_utf8(handle.to_vec())
+        .map_err(|_| Status::invalid_argument("unknown prepared statement"))
+}
+
+#[derive(Clone)]
+struct FakeService {
+    state: Arc<State>,
+}
+
+impl FakeService {
+    /// Answers `query` with the endpoints of the first matching response.
+    async fn flight_info(
+        &self,
+        query: &str,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        self.state.queries.lock().unwrap().push(query.to_string());
+        self.state.record_headers(&request);
+        let user_agent = request
+            .metadata()
+            .get("user-agent")
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        self.state
+            .user_agents
+            .lock()
+            .unwrap()
+            .push(user_agent.to_string());
+        let (index, response) = self
+            .state
+            .rules
+            .iter()
+            .enumerate()
+            .find(|(_, (pattern, _))| pattern.matches(query))
+            .map(|(index, (_, response))| (index, response))
+            .ok_or_else(|| {
+                Status::invalid_argument(format!("no response for query {:?}", query))
+            })?;
+        tokio::time::sleep(response.delay).await;
+        if let Some(error) = &response.error {
+            return Err(error.clone());
+        }
+        let mut info = FlightInfo::new()
+            .try_with_schema(&response.schema)
+            .map_err(|e| Status::internal(e.to_string()))?
+            .with_total_records(response.total_records())
+            .with_descriptor(request.into_inner());
+        for (position, endpoint) in response.endpoints.iter().enumerate() {
+            let ticket = TicketStatementQuery {
+                statement_handle: format!("{}/{}", index, position).into(),
+            };
+            let mut flight_endpoint =
+                FlightEndpoint::new().with_ticket(Ticket::new(ticket.as_any().encode_to_vec()));
+            if let Some(location) = &endpoint.location {
+                flight_endpoint = flight_endpoint.with_location(location);
+            }
+            info = info.with_endpoint(flight_endpoint);
+        }
+        Ok(Response::new(info))
+    }
+
+    /// Answers a metadata command with a single endpoint whose ticket is the
+    /// command itself, to be answered when it is fetched.
+    fn metadata_info(
+        &self,
+        command: impl ProstMessageExt,
+        schema: SchemaRef,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        self.state.authorize(&request)?;
+        let ticket = Ticket::new(command.as_any().encode_to_vec());
+        let info = FlightInfo::new()
+            .try_with_schema(&schema)
+            .map_err(|e| Status::internal(e.to_string()))?
+            .with_endpoint(FlightEndpoint::new().with_ticket(ticket))
+            .with_descriptor(request.into_inner());
+        Ok(Response::new(info))
+    }
+
+    /// Sends the results of a metadata command.
+    fn metadata_batch(
+        &self,
+        batch: Result<RecordBatch, FlightError>,
+    ) -> Result<Response<BoxStream<'static, Result<FlightData, Status>>>, Status> {
+        let batch = batch.map_err(Status::from)?;
+        let data = FlightDataEncoderBuilder::new()
+            .with_schema(batch.schema())
+            .build(stream::iter([Ok(batch)]))
+            .map_err(Status::from);
+        Ok(Response::new(data.boxed()))
+    }
+}
+
+/// Builds the results of `command` for `tables`, given as schemas and names.
+fn tables_batch(
+    command: CommandGetTables,
+    tables: &[(String, String)],
+) -> Result<RecordBatch, FlightError> {
+    let mut builder = command.into_builder();
+    for (schema, name) in tables {
+        builder.append(CATALOG, schema, name, "TABLE", &Schema::empty())?;
+    }
+    builder.build()
+}
+
+/// Builds the results of `command` for the schemas containing `tables`.
+fn schemas_batch(
+    command: CommandGetDbSchemas,
+    tables: &[(String, String)],
+) -> Result<RecordBatch, FlightError> {
+    let mut schemas = BTreeSet::new();
+    for (schema, _) in tables.iter().filter(|(schema, _)| !schema.is_empty()) {
+        let mut prefix = String::new();
+        for part in schema.split('.') {
+            if !prefix.is_empty() {
+                prefix.push('.');
+            }
+            prefix.push_str(part);
+            schemas.insert(prefix.clone());
+        }
+    }
+    let mut builder = command.into_builder();
+    for schema in schemas {
+        builder.append(CATALOG, schema);
+    }
+    builder.build()
+}
+
+/// Builds the results of `command` with the name and version of the server.
+fn sql_info_batch(command: CommandGetSqlInfo) -> Result<RecordBatch, FlightError> {
+    let mut builder = SqlInfoDataBuilder::new();
+    builder.append(SqlInfo::FlightSqlServerName, "Fake Flight SQL server");
+    builder.append(SqlInfo::FlightSqlServerVersion, env!("CARGO_PKG_VERSION"));
+    builder.append(SqlInfo::FlightSqlServerReadOnly, false);
+    command.into_builder(&builder.build()?).build()
+}
+
+#[tonic::async_trait]
+impl FlightSqlService for FakeService {
+    type FlightService = Self;
+
+    async fn do_handshake(
+        &self,
+        request: Request<Streaming<HandshakeRequest>>,
+    ) -> Result<Response<BoxStream<'static, Result<HandshakeResponse, Status>>>, Status> {
+        let handshake = self.state.handshakes.fetch_add(1, Ordering::Relaxed) + 1;
+        self.state.check_credentials(&request)?;
+        let token = format!("fake-token-{}", handshake);
+        self.state.tokens.lock().unwrap().insert(token.clone());
+        let reply = HandshakeResponse {
+            protocol_version: 0,
+            payload: token.clone().into(),
+        };
+        let mut response = Response::new(stream::iter([Ok(reply)]).boxed());
+        let value = MetadataValue::try_from(format!("Bearer {}", token))
+            .map_err(|e| Status::internal(e.to_string()))?;
+        response.metadata_mut().insert("authorization", value);
+        Ok(response)
+    }
+
+    async fn get_flight_info_statement(
+        &self,
+        query: CommandStatementQuery,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        self.state.authorize(&request)?;
+        self.flight_info(&query.query, request).await
+    }
+
+    async fn get_flight_info_prepared_statement(
+        &self,
+        query: CommandPreparedStatementQuery,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        self.state.authorize(&request)?;
+        let sql = prepared_sql(&query.prepared_statement_handle)?;
+        self.flight_info(&sql, request).await
+    }
+
+    async fn do_get_statement(
+        &self,
+        ticket: TicketStatementQuery,
+        request: Request<Ticket>,
+    ) -> Result<Response<BoxStream<'static, Result<arrow_flight::FlightData, Status>>>, Status>
+    {
+        self.state.authorize(&request)?;
+        self.state.record_headers(&request);
+        let (response, endpoint) = self.state.endpoint(&ticket.statement_handle)?;
+        let batches = stream::iter(endpoint.batches.clone().into_iter().map(Ok));
+        let app_metadata = response.app_metadata.clone();
+        let data = FlightDataEncoderBuilder::new()
+            .with_schema(response.schema.clone())
+            .build(batches)
+            .map_err(Status::from)
+            .enumerate()
+            .map(move |(i, data)| {
+                // The first message is the schema, the following the batches.
+                match i.checked_sub(1).and_then(|i| app_metadata.get(i)) {
+                    Some(metadata) => data.map(|data| data.with_app_metadata(metadata.clone())),
+                    None => data,
+                }
+            })
+            .chain(stream::iter(endpoint.error.clone().map(Err)));
+        Ok(Response::new(data.boxed()))
+    }
+
+    async fn do_put_statement_ingest(
+        &self,
+        command: CommandStatementIngest,
+        request: Request<PeekableFlightDataStream>,
+    ) -> Result<i64, Status> {
+        self.state.authorize(&request)?;
+        let mut path = Vec::new();
+        for prefix in [command.catalog, command.schema].into_iter().flatten() {
+            path.extend(
+                sql::parse_path(&prefix).map_err(|e| Status::invalid_argument(e.to_string()))?,
+            );
+        }
+        path.push(command.table);
+        let name = table_name(&path);
+        let options = command.table_definition_options.unwrap_or_default();
+        let exists = self.state.tables.lock().unwrap().contains_key(&name);
+        let replace = match (exists, options.if_exists(), options.if_not_exist()) {
+            (true, TableExistsOption::Append, _) => false,
+            (true, TableExistsOption::Replace, _) => true,
+            (true, _, _) => return Err(Status::already_exists(format!("table {} exists", name))),
+            (false, _, TableNotExistOption::Create) => true,
+            (false, _, _) => return Err(Status::not_found(format!("table {} not found", name))),
+        };
+        let data = request
+            .into_inner()
+            .map_err(|e| FlightError::Tonic(Box::new(e)));
+        let batches: Vec<RecordBatch> = FlightRecordBatchStream::new_from_flight_data(data)
+            .try_collect()
+            .await
+            .map_err(Status::from)?;
+        let rows = batches.iter().map(|batch| batch.num_rows() as i64).sum();
+        let mut tables = self.state.tables.lock().unwrap();
+        let table = tables.entry(name).or_default();
+        if replace {
+            table.clear();
+        }
+        table.extend(batches);
+        Ok(rows)
+    }
+
+    async fn do_put_statement_update(
+        &self,
+        command: CommandStatementUpdate,
+        request: Request<PeekableFlightDataStream>,
+    ) -> Result<i64, Status> {
+        self.state.authorize(&request)?;
+        self.state.record_headers(&request);
+        self.state
+            .queries
+            .lock()
+            .unwrap()
+            .push(command.query.clone());
+        self.state.update(&command.query).ok_or_else(|| {
+            Status::invalid_argument(format!("no response for update {:?}", command.query))
+        })
+    }
+
+    async fn do_action_create_prepared_statement(
+        &self,
+        query: ActionCreatePreparedStatementRequest,
+        request: Request<Action>,
+    ) -> Result<ActionCreatePreparedStatementResult, Status> {
+        self.state.authorize(&request)?;
+        let schema = match self
+            .state
+            .rules
+            .iter()
+            .find(|(pattern, _)| pattern.matches(&query.query))
+        {
+            Some((_, response)) => response.schema.clone(),
+            None if self.state.update(&query.query).is_some() => Arc::new(Schema::empty()),
+            None => {
+                return Err(Status::invalid_argument(format!(
+                    "no response for query {:?}",
+                    query.query
+                )))
+            }
+        };
+        let options = IpcWriteOptions::default();
+        let dataset_schema = IpcMessage::try_from(SchemaAsIpc::new(&schema, &options))
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(ActionCreatePreparedStatementResult {
+            prepared_statement_handle: query.query.into(),
+            dataset_schema: dataset_schema.0,
+            parameter_schema: Default::default(),
+        })
+    }
+
+    async fn do_put_prepared_statement_query(
+        &self,
+        query: CommandPreparedStatementQuery,
+        request: Request<PeekableFlightDataStream>,
+    ) -> Result<DoPutPreparedStatementResult, Status> {
+        self.state.authorize(&request)?;
+        let data = request
+            .into_inner()
+            .map_err(|e| FlightError::Tonic(Box::new(e)));
+        let batches: Vec<RecordBatch> = FlightRecordBatchStream::new_from_flight_data(data)
+            .try_collect()
+            .await
+            .map_err(Status::from)?;
+        self.state.parameters.lock().unwrap().extend(batches);
+        Ok(DoPutPreparedStatementResult {
+            prepared_statement_handle: Some(query.prepared_statement_handle),
+        })
+    }
+
+    async fn do_put_prepared_statement_update(
+        &self,
+        query: CommandPreparedStatementUpdate,
+        request: Request<PeekableFlightDataStream>,
+    ) -> Result<i64, Status> {
+        self.state.authorize(&request)?;
+        let sql = prepared_sql(&query.prepared_statement_handle)?;
+        self.state.queries.lock().unwrap().push(sql.clone());
+        self.state
+            .update(&sql)
+            .ok_or_else(|| Status::invalid_argument(format!("no response for update {:?}", sql)))
+    }
+
+    async fn do_action_close_prepared_statement(
+        &self,
+        _query: ActionClosePreparedStatementRequest,
+        request: Request<Action>,
+    ) -> Result<(), Status> {
+        self.state.authorize(&request)
+    }
+
+    async fn do_put_fallback(
+        &self,
+        request: Request<PeekableFlightDataStream>,
+        _message: Any,
+    ) -> Result<Response<BoxStream<'static, Result<PutResult, Status>>>, Status> {
+        // Plain Flight uploads stand in for servers outside of Dremio, which
+        // do not share its tokens.
+        let data: Vec<FlightData> = request.into_inner().try_collect().await?;
+        let path = data
+            .first()
+            .and_then(|data| data.flight_descriptor.as_ref())
+            .filter(|descriptor| descriptor.r#type() == DescriptorType::Path)
+            .map(|descriptor| descriptor.path.join("."))
+            .ok_or_else(|| Status::unimplemented("only path descriptors are supported"))?;
+        let batches: Vec<RecordBatch> =
+            FlightRecordBatchStream::new_from_flight_data(stream::iter(data.into_iter().map(Ok)))
+                .try_collect()
+                .await
+                .map_err(Status::from)?;
+        self.state.tables.lock().unwrap().insert(path, batches);
+        Ok(Response::new(
+            stream::iter([Ok(PutResult::default())]).boxed(),
+        ))
+    }
+
+    async fn get_flight_info_catalogs(
+        &self,
+        query: CommandGetCatalogs,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        let schema = query.into_builder().schema();
+        self.metadata_info(query, schema, request)
+    }
+
+    async fn get_flight_info_schemas(
+        &self,
+        query: CommandGetDbSchemas,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        let schema = query.clone().into_builder().schema();
+        self.metadata_info(query, schema, request)
+    }
+
+    async fn get_flight_info_tables(
+        &self,
+        query: CommandGetTables,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        let schema = query.clone().into_builder().schema();
+        self.metadata_info(query, schema, request)
+    }
+
+    async fn get_flight_info_table_types(
+        &self,
+        query: CommandGetTableTypes,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        let schema = query.into_builder().schema();
+        self.metadata_info(query, schema, request)
+    }
+
+    async fn get_flight_info_sql_info(
+        &self,
+        query: CommandGetSqlInfo,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        let schema = SqlInfoDataBuilder::schema().clone();
+        self.metadata_info(query, Arc::new(schema), request)
+    }
+
+    async fn do_get_catalogs(
+        &self,
+        query: CommandGetCatalogs,
+        request: Request<Ticket>,
+    ) -> Result<Response<BoxStream<'static, Result<FlightData, Status>>>, Status> {
+        self.state.authorize(&request)?;
+        let mut builder = query.into_builder();
+        builder.append(CATALOG);
+        self.metadata_batch(builder.build())
+    }
+
+    async fn do_get_schemas(
+        &self,
+        query: CommandGetDbSchemas,
+        request: Request<Ticket>,
+    ) -> Result<Response<BoxStream<'static, Result<FlightData, Status>>>, Status> {
+        self.state.authorize(&request)?;
+        self.metadata_batch(schemas_batch(query, &self.state.table_paths()))
+    }
+
+    async fn do_get_tables(
+        &self,
+        query: CommandGetTables,
+        request: Request<Ticket>,
+    ) -> Result<Response<BoxStream<'static, Result<FlightData, Status>>>, Status> {
+        self.state.authorize(&request)?;
+        self.metadata_batch(tables_batch(query, &self.state.table_paths()))
+    }
+
+    async fn do_get_table_types(
+        &self,
+        query: CommandGetTableTypes,
+        request: Request<Ticket>,
+    ) -> Result<Response<BoxStream<'static, Result<FlightData, Status>>>, Status> {
+        self.state.authorize(&request)?;
+        let mut builder = query.into_builder();
+        for table_type in TABLE_TYPES {
+            builder.append(table_type);
+        }
+        self.metadata_batch(builder.build())
+    }
+
+    async fn do_get_sql_info(
+        &self,
+        query: CommandGetSqlInfo,
+        request: Request<Ticket>,
+    ) -> Result<Response<BoxStream<'static, Result<FlightData, Status>>>, Status> {
+        self.state.authorize(&request)?;
+        self.metadata_batch(sql_info_batch(query))
+    }
+
+    async fn do_action_fallback(
+        &self,
+        request: Request<Action>,
+    ) -> Result<Response<BoxStream<'static, Result<arrow_flight::Result, Status>>>, Status> {
+        if request.get_ref().r#type != CANCEL_FLIGHT_INFO {
+            return Err(Status::unimplemented(format!(
+                "unsupported action {:?}",
+                request.get_ref().r#type
+            )));
+        }
+        self.state.authorize(&request)?;
+        let cancel = CancelFlightInfoRequest::decode(request.into_inner().body)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let command = cancel
+            .info
+            .and_then(|info| info.flight_descriptor)
+            .and_then(|descriptor| Any::decode(descriptor.cmd).ok())
+            .and_then(|any| Command::try_from(any).ok());
+        let query = match command {
+            Some(Command::CommandStatementQuery(query)) => query.query,
+            Some(Command::CommandPreparedStatementQuery(query)) => {
+                prepared_sql(&query.prepared_statement_handle)?
+            }
+            _ => return Err(Status::invalid_argument("not a query")),
+        };
+        self.state.cancelled.lock().unwrap().push(query);
+        let result = CancelFlightInfoResult::new(CancelStatus::Cancelled);
+        Ok(Response::new(
+            stream::iter([Ok(arrow_flight::Result::new(result.encode_to_vec()))]).boxed(),
+        ))
+    }
+
+    async fn register_sql_info(&self, _id: i32, _result: &SqlInfo) {}
+}
@@ -0,0 +1,166 @@
+use crate::snapshot::{read_bundles, Bundle};
+use crate::{DremioClientError, DremioExecutor, QueryOptions, QueryStream};
+use arrow::array::RecordBatch;
+use arrow::error::ArrowError;
+use std::path::Path;
+
+use super::{matches, Pattern};
+
+type ErrorFactory = Box<dyn Fn() -> DremioClientError + Send + Sync>;
+
+enum Response {
+    Batches(Vec<RecordBatch>),
+    Error(ErrorFactory),
+}
+
+struct Rule {
+    pattern: Pattern,
+    response: Response,
+}
+
+/// A [`DremioExecutor`] that answers queries with canned results.
+///
+/// Responses are registered against SQL patterns. A pattern matches a query
+/// when the two are equal after whitespace normalization, ignoring case, with
+/// `*` in the pattern matching any sequence of characters. The first matching
+/// pattern wins; queries that match none fail.
+///
+/// Every executed statement is recorded, so tests can assert on what the code
+/// under test sent.
+///
+/// # Example
+///
+/// ```
+/// use arrow::array::{Int32Array, RecordBatch};
+/// use arrow::datatypes::{DataType, Field, Schema};
+/// use dremio_rs::testing::MockClient;
+/// use dremio_rs::DremioExecutor;
+/// use std::sync::Arc;
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let schema = Arc::new(Schema::new(vec![Field::new("n", DataType::Int32, false)]));
+/// let batch = RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(vec![42]))]).unwrap();
+/// let mut mock = MockClient::new().on("SELECT COUNT(*) AS n FROM *", vec![batch]);
+///
+/// let batches = mock.get_record_batches("select count(*) as n from sales").await.unwrap();
+/// assert_eq!(batches[0].num_rows(), 1);
+/// mock.assert_executed("* FROM sales");
+/// # }
+/// ```
+#[derive(Default)]
+pub struct MockClient {
+    rules: Vec<Rule>,
+    executed: Vec<String>,
+}
+
+impl MockClient {
+    /// Creates a mock without any registered responses.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answers queries matching `pattern` with `batches`.
+    pub fn on(mut self, pattern: &str, batches: Vec<RecordBatch>) -> Self {
+        self.rules.push(Rule {
+            pattern: Pattern::Glob(pattern.to_string()),
+            response: Response::Batches(batches),
+        });
+        self
+    }
+
+    /// Fails queries matching `pattern` with the error returned by `error`.
+    pub fn on_error<F>(mut self, pattern: &str, error: F) -> Self
+    where
+        F: Fn() -> DremioClientError + Send + Sync + 'static,
+    {
+        self.rules.push(Rule {
+            pattern: Pattern::Glob(pattern.to_string()),
+            response: Response::Error(Box::new(error)),
+        });
+        self
+    }
+
+    /// Creates a mock answering the queries recorded by a
+    /// [`Recorder`](super::Recorder) in `dir`.
+    ///
+    /// Recorded queries only match the exact same SQL, up to whitespace.
+    /// Further responses can be added with [`on`](Self::on).
+    pub fn replay(dir: impl AsRef<Path>) -> Result<Self, DremioClientError> {
+        let mut mock = Self::new();
+        for recording in read_bundles(dir.as_ref())? {
+            let response = match recording.error {
+                Some(message) => Response::Error(Box::new(move || Bundle::error(&message))),
+                None => Response::Batches(recording.batches),
+            };
+            mock.rules.push(Rule {
+                pattern: Pattern::Literal(recording.query),
+                response,
+            });
+        }
+        Ok(mock)
+    }
+
+    /// Returns the statements executed so far, in order.
+    pub fn executed(&self) -> &[String] {
+        &self.executed
+    }
+
+    /// Panics unless a statement matching `pattern` has been executed.
+    pub fn assert_executed(&self, pattern: &str) {
+        assert!(
+            self.executed.iter().any(|sql| matches(pattern, sql)),
+            "no executed statement matches {:?}; executed: {:#?}",
+            pattern,
+            self.executed
+        );
+    }
+
+    /// Forgets the statements executed so far.
+    pub fn clear_executed(&mut self) {
+        self.executed.clear();
+    }
+
+    fn respond(&mut self, query: &str) -> Result<Vec<RecordBatch>, DremioClientError> {
+        self.executed.push(query.to_string());
+        let rule = self
+            .rules
+            .iter()
+            .find(|rule| rule.pattern.matches(query))
+            .ok_or_else(|| {
+                ArrowError::InvalidArgumentError(format!(
+                    "MockClient has no response for query {:?}",
+                    query
+                ))
+            })?;
+        match &rule.response {
+            Response::Batches(batches) => Ok(batches.clone()),
+            Response::Error(error) => Err(error()),
+        }
+    }
+}
+
+impl DremioExecutor for MockClient {
+    async fn execute_stream_with_options(
+        &mut self,
+        query: &str,
+        options: &QueryOptions,
+    ) -> Result<QueryStream, DremioClientError> {
+        let batches = self.respond(query)?;
+        Ok(QueryStream::from_batches(batches)
+            .map_batches(options.batch_transformer(false))
+            .rechunk(options.batch_size())
+            .with_checks(options.checks().clone()))
+    }
+
+    async fn get_record_batches_with_options(
+        &mut self,
+        query: &str,
+        options: &QueryOptions,
+    ) -> Result<Vec<RecordBatch>, DremioClientError> {
+        self.execute_stream_with_options(query, options)
+            .await?
+            .collect_batches()
+            .await
+    }
+}
@@ -0,0 +1,63 @@
+//! Helpers for testing code that uses this crate without a live cluster.
+//!
+//! This module is only available with the `testing` feature, which is meant to
+//! be enabled in `[dev-dependencies]`. The `testcontainers` feature adds
+//! [`DremioContainer`], which runs a real Dremio instance in Docker.
+
+#[cfg(feature = "testcontainers")]
+mod container;
+mod fake;
+mod mock;
+mod record;
+
+#[cfg(feature = "testcontainers")]
+pub use container::{DremioContainer, RunningDremio};
+pub use fake::{FakeFlightSqlServer, FakeResponse, FakeServerHandle};
+pub use mock::MockClient;
+pub use record::Recorder;
+
+use crate::cache::normalize_sql;
+
+/// The SQL a canned response is registered against.
+#[derive(Clone)]
+enum Pattern {
+    /// A glob, see [`matches`].
+    Glob(String),
+    /// A recorded query, matched exactly after whitespace normalization.
+    Literal(String),
+}
+
+impl Pattern {
+    fn matches(&self, sql: &str) -> bool {
+        match self {
+            Pattern::Glob(pattern) => matches(pattern, sql),
+            Pattern::Literal(query) => normalize_sql(query) == normalize_sql(sql),
+        }
+    }
+}
+
+/// Returns `true` if `sql` matches the glob `pattern`, compared after
+/// whitespace normalization and ignoring case.
+fn matches(pattern: &str, sql: &str) -> bool {
+    let pattern: Vec<char> = normalize_sql(pattern).to_lowercase().chars().collect();
+    let sql: Vec<char> = normalize_sql(sql).to_lowercase().chars().collect();
+    // Iterative wildcard matching, backtracking to the most recent `*`.
+    let (mut p, mut s) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while s < sql.len() {
+        if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, s));
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == sql[s] {
+            p += 1;
+            s += 1;
+        } else if let Some((star_p, star_s)) = star {
+            p = star_p + 1;
+            s = star_s + 1;
+            star = Some((star_p, star_s + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
@@ -0,0 +1,108 @@
+use crate::snapshot::{bundle_file_name, write_bundle};
+use crate::{DremioClientError, DremioExecutor, QueryOptions, QueryStream};
+use arrow::array::RecordBatch;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A [`DremioExecutor`] that records the results of the queries it forwards.
+///
+/// Each query is executed by the wrapped executor, typically a
+/// [`Client`](crate::Client) connected to a real cluster, and its schema and
+/// batches, or its error, are saved as an Arrow IPC file in a directory. The
+/// directory can later be replayed with [`MockClient::replay`] or
+/// [`FakeFlightSqlServer::replay`], so tests run against realistic payloads
+/// without a cluster.
+///
+/// Results are recorded before any result transformations configured in
+/// [`QueryOptions`] are applied; those are applied again when replaying.
+/// Recording a query again replaces its previous recording.
+///
+/// [`MockClient::replay`]: super::MockClient::replay
+/// [`FakeFlightSqlServer::replay`]: super::FakeFlightSqlServer::replay
+///
+/// # Example
+///
+/// ```no_run
+/// use dremio_rs::testing::{MockClient, Recorder};
+/// use dremio_rs::{Client, DremioExecutor};
+///
+/// #[tokio::main]
+/// async fn main() {
+///    let client = Client::new("http://localhost:32010", "dremio", "dremio123").await.unwrap();
+///    let mut recorder = Recorder::new(client, "tests/recordings").unwrap();
+///    recorder.get_record_batches("SELECT * FROM sys.options").await.unwrap();
+///
+///    // Later, without a cluster:
+///    let mut mock = MockClient::replay("tests/recordings").unwrap();
+///    mock.get_record_batches("SELECT * FROM sys.options").await.unwrap();
+/// }
+/// ```
+pub struct Recorder<E> {
+    inner: E,
+    dir: PathBuf,
+}
+
+impl<E: DremioExecutor> Recorder<E> {
+    /// Wraps `inner`, recording into `dir`, which is created if needed.
+    pub fn new(inner: E, dir: impl AsRef<Path>) -> Result<Self, DremioClientError> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { inner, dir })
+    }
+
+    /// Returns the directory holding the recordings.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the wrapped executor.
+    pub fn into_inner(self) -> E {
+        self.inner
+    }
+
+    async fn record(
+        &mut self,
+        query: &str,
+        options: &QueryOptions,
+    ) -> Result<Vec<RecordBatch>, DremioClientError> {
+        let result = match self
+            .inner
+            .execute_stream_with_options(query, &options.transport_only())
+            .await
+        {
+            Ok(stream) => stream.collect_batches().await,
+            Err(e) => Err(e),
+        };
+        let path = self.dir.join(bundle_file_name(query));
+        match &result {
+            Ok(batches) => write_bundle(&path, query, batches, None)?,
+            Err(e) => write_bundle(&path, query, &[], Some(&e.to_string()))?,
+        }
+        result
+    }
+}
+
+impl<E: DremioExecutor> DremioExecutor for Recorder<E> {
+    async fn execute_stream_with_options(
+        &mut self,
+        query: &str,
+        options: &QueryOptions,
+    ) -> Result<QueryStream, DremioClientError> {
+        let batches = self.record(query, options).await?;
+        Ok(QueryStream::from_batches(batches)
+            .map_batches(options.batch_transformer(false))
+            .rechunk(options.batch_size())
+            .with_checks(options.checks().clone()))
+    }
+
+    async fn get_record_batches_with_options(
+        &mut self,
+        query: &str,
+        options: &QueryOptions,
+    ) -> Result<Vec<RecordBatch>, DremioClientError> {
+        self.execute_stream_with_options(query, options)
+            .await?
+            .collect_batches()
+            .await
+    }
+}
@@ -0,0 +1,264 @@
+//! Warming up reflections after a change.
+//!
+//! After a reflection is created or changed, dashboards stay slow until it
+//! has been refreshed and the queries they run have been planned against it.
+//! [`Client::warm_reflections`](crate::Client::warm_reflections) does that
+//! work up front, so a deployment pipeline can finish with fast dashboards:
+//!
+//! 1. With a [`RestClient`](crate::rest::RestClient) (`rest` feature), it
+//!    triggers a refresh of the reflections of each dataset that has one
+//!    that cannot accelerate queries, because it is expired, failed or has
+//!    never been built.
+//! 2. It polls the `sys.reflections` system table until every enabled
+//!    reflection of the datasets can accelerate queries.
+//! 3. It runs each representative query once, fetching all of its results.
+
+use crate::sql::parse_path;
+use crate::{Client, DremioClientError, QueryOptions};
+use arrow::array::{Array, AsArray};
+use arrow::compute::cast;
+use arrow::datatypes::DataType;
+use futures::TryStreamExt;
+use std::time::{Duration, Instant};
+
+/// Statuses of reflections that can accelerate queries.
+const READY: &[&str] = &["CAN_ACCELERATE", "CAN_ACCELERATE_WITH_FAILURES"];
+
+/// Statuses of reflections that will not become ready until refreshed.
+const STALE: &[&str] = &[
+    "CANNOT_ACCELERATE_MANUAL",
+    "CANNOT_ACCELERATE_SCHEDULED",
+    "EXPIRED",
+    "FAILED",
+    "INCOMPLETE",
+    "INVALID",
+];
+
+/// The datasets and queries to warm up with
+/// [`Client::warm_reflections`](crate::Client::warm_reflections).
+///
+/// # Example
+///
+/// ```
+/// use dremio_rs::warmup::Warmup;
+/// use std::time::Duration;
+///
+/// let warmup = Warmup::new()
+///     .with_dataset("lake.sales.orders")
+///     .unwrap()
+///     .with_query("SELECT region, SUM(amount) FROM lake.sales.orders GROUP BY region")
+///     .with_timeout(Duration::from_secs(600));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Warmup {
+    datasets: Vec<Vec<String>>,
+    queries: Vec<String>,
+    timeout: Duration,
+    poll_interval: Duration,
+    #[cfg(feature = "rest")]
+    rest: Option<crate::rest::RestClient>,
+}
+
+impl Default for Warmup {
+    fn default() -> Self {
+        Self {
+            datasets: Vec::new(),
+            queries: Vec::new(),
+            timeout: Duration::from_secs(30 * 60),
+            poll_interval: Duration::from_secs(10),
+            #[cfg(feature = "rest")]
+            rest: None,
+        }
+    }
+}
+
+impl Warmup {
+    /// Creates a warm-up of nothing, waiting at most 30 minutes and polling
+    /// every 10 seconds.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Waits for the reflections of the dataset at the dotted `path`, such
+    /// as `lake.sales.orders`.
+    ///
+    /// Fails if `path` is not a valid dataset path.
+    pub fn with_dataset(mut self, path: &str) -> Result<Self, DremioClientError> {
+        self.datasets.push(parse_path(path)?);
+        Ok(self)
+    }
+
+    /// Runs `query` once the reflections are ready.
+    pub fn with_query(mut self, query: impl Into<String>) -> Self {
+        self.queries.push(query.into());
+        self
+    }
+
+    /// Sets how long to wait for the reflections to become ready.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets how often `sys.reflections` is queried while waiting.
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Triggers refreshes of stale reflections through the REST API. Without
+    /// it, stale reflections are only waited for, which requires their
+    /// refresh policy to rebuild them.
+    #[cfg(feature = "rest")]
+    pub fn with_rest_client(mut self, rest: crate::rest::RestClient) -> Self {
+        self.rest = Some(rest);
+        self
+    }
+}
+
+/// A reflection, as listed in the `sys.reflections` system table.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reflection {
+    /// The ID of the reflection.
+    pub id: String,
+    /// The name of the reflection.
+    pub name: String,
+    /// The path of the dataset the reflection is defined on.
+    pub dataset: Vec<String>,
+    /// The status, such as `CAN_ACCELERATE` or `REFRESHING`.
+    pub status: String,
+}
+
+impl Reflection {
+    /// Returns whether the reflection can accelerate queries.
+    pub fn is_ready(&self) -> bool {
+        READY.contains(&self.status.as_str())
+    }
+
+    /// Returns whether the reflection is disabled. Disabled reflections are
+    /// not waited for.
+    pub fn is_disabled(&self) -> bool {
+        self.status == "DISABLED"
+    }
+
+    /// Returns whether the reflection cannot accelerate queries until it is
+    /// refreshed, for example because it expired or its last refresh failed.
+    pub fn is_stale(&self) -> bool {
+        STALE.contains(&self.status.as_str())
+    }
+}
+
+/// A representative query run by a warm-up.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WarmedQuery {
+    /// The SQL text of the query.
+    pub query: String,
+    /// The time taken to run the query and fetch its results.
+    pub latency: Duration,
+    /// The number of rows returned.
+    pub rows: u64,
+}
+
+/// What a warm-up did, as returned by
+/// [`Client::warm_reflections`](crate::Client::warm_reflections).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WarmupReport {
+    /// The reflections of the datasets, with their final status.
+    pub reflections: Vec<Reflection>,
+    /// The datasets whose reflections were refreshed.
+    pub refreshed: Vec<Vec<String>>,
+    /// The representative queries, in the order they ran.
+    pub queries: Vec<WarmedQuery>,
+    /// The time spent waiting for reflections to become ready.
+    pub waited: Duration,
+}
+
+/// Runs `warmup` against `client`.
+pub(crate) async fn warm_reflections(
+    client: &mut Client,
+    warmup: &Warmup,
+) -> Result<WarmupReport, DremioClientError> {
+    let options = QueryOptions::default();
+    let mut report = WarmupReport::default();
+    if !warmup.datasets.is_empty() {
+        let mut reflections = reflections_of(client, &warmup.datasets, &options).await?;
+        #[cfg(feature = "rest")]
+        if let Some(rest) = &warmup.rest {
+            for dataset in &warmup.datasets {
+                if reflections
+                    .iter()
+                    .any(|r| &r.dataset == dataset && r.is_stale())
+                {
+                    let path: Vec<&str> = dataset.iter().map(String::as_str).collect();
+                    rest.refresh_reflections(&path).await?;
+                    report.refreshed.push(dataset.clone());
+                }
+            }
+        }
+        let start = Instant::now();
+        while !reflections.iter().all(|r| r.is_ready() || r.is_disabled()) {
+            if start.elapsed() + warmup.poll_interval > warmup.timeout {
+                return Err(DremioClientError::TimeoutError(warmup.timeout));
+            }
+            tokio::time::sleep(warmup.poll_interval).await;
+            reflections = reflections_of(client, &warmup.datasets, &options).await?;
+        }
+        report.waited = start.elapsed();
+        report.reflections = reflections;
+    }
+    for query in &warmup.queries {
+        let start = Instant::now();
+        let mut stream = client.open_query(query, &options).await?;
+        let mut rows = 0;
+        while let Some(batch) = stream.try_next().await? {
+            rows += batch.num_rows() as u64;
+        }
+        report.queries.push(WarmedQuery {
+            query: query.clone(),
+            latency: start.elapsed(),
+            rows,
+        });
+    }
+    Ok(report)
+}
+
+/// Lists the reflections defined on `datasets`.
+async fn reflections_of(
+    client: &mut Client,
+    datasets: &[Vec<String>],
+    options: &QueryOptions,
+) -> Result<Vec<Reflection>, DremioClientError> {
+    let query = "SELECT reflection_id, reflection_name, dataset_name, status FROM sys.reflections";
+    let mut reflections = Vec::new();
+    for batch in client.collect_query(query, options).await? {
+        let columns = batch
+            .columns()
+            .iter()
+            .map(|column| cast(column, &DataType::Utf8))
+            .collect::<Result<Vec<_>, _>>()?;
+        let [id, name, dataset, status] = [0, 1, 2, 3].map(|i| columns[i].as_string::<i32>());
+        for row in 0..batch.num_rows() {
+            // Paths are printed as SQL, quoted where needed.
+            let Ok(path) = parse_path(dataset.value(row)) else {
+                continue;
+            };
+            if !datasets.contains(&path) {
+                continue;
+            }
+            let text = |array: &arrow::array::StringArray| {
+                if array.is_null(row) {
+                    String::new()
+                } else {
+                    array.value(row).to_string()
+                }
+            };
+            reflections.push(Reflection {
+                id: text(id),
+                name: text(name),
+                dataset: path,
+                status: text(status),
+            });
+        }
+    }
+    Ok(reflections)
+}
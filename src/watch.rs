@@ -0,0 +1,190 @@
+//! Tailing append-only datasets by polling.
+//!
+//! A [`Watch`], created by [`Client::watch`](crate::Client::watch), runs a
+//! query over and over, each time only asking for the rows whose key is
+//! greater than the greatest key seen so far, and yields the new rows as
+//! they turn up. The key is typically an increasing ID or an ingestion
+//! timestamp, which gives a simple form of change data capture for datasets
+//! that are only ever appended to.
+//!
+//! The query is a template containing an `{after}` placeholder, which is
+//! replaced with a condition on the key column, such as `"id" > 42`. The first
+//! query, unless given a key to start after, uses `TRUE` instead, and later
+//! ones use `"id" IS NOT NULL` until a key has been seen:
+//!
+//! ```sql
+//! SELECT * FROM lake.events WHERE {after} ORDER BY id
+//! ```
+//!
+//! Rows are only picked up once, so a row appended with a key lower than or
+//! equal to one already seen is missed. Keys must be integers, decimals,
+//! floats, strings, dates or timestamps. Rows with a null key are only
+//! yielded by the first query, as no later condition matches them.
+
+use crate::sql::{max_value, quote_ident, value_literal};
+use crate::{Client, DremioClientError, QueryOptions, QueryStream};
+use arrow::array::{ArrayRef, RecordBatch};
+use arrow::error::ArrowError;
+use futures::future::BoxFuture;
+use futures::{Stream, StreamExt};
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// The placeholder replaced with the condition on the key column.
+const PLACEHOLDER: &str = "{after}";
+
+/// A stream of the rows appended to a dataset, see the [module
+/// documentation](self).
+///
+/// The first query runs as soon as the stream is polled and the following
+/// ones `interval` after the previous one completed. Batches are yielded as
+/// they arrive, so the first query, which fetches the whole dataset, is never
+/// held in memory. A failed query ends the stream with its error; a new
+/// watch can pick up where it stopped with [`Watch::with_start_after`] and
+/// [`Watch::last_key`].
+pub struct Watch<'a> {
+    client: &'a Client,
+    template: String,
+    key_column: String,
+    interval: Duration,
+    options: QueryOptions,
+    last_key: Option<String>,
+    polled: bool,
+    done: bool,
+    greatest: Option<ArrayRef>,
+    query: Option<BoxFuture<'a, Result<QueryStream, DremioClientError>>>,
+    results: Option<QueryStream>,
+}
+
+impl<'a> Watch<'a> {
+    pub(crate) fn new(
+        client: &'a Client,
+        template: &str,
+        key_column: &str,
+        interval: Duration,
+        options: QueryOptions,
+    ) -> Result<Self, DremioClientError> {
+        if !template.contains(PLACEHOLDER) {
+            return Err(ArrowError::InvalidArgumentError(format!(
+                "watch query must contain the {} placeholder",
+                PLACEHOLDER
+            ))
+            .into());
+        }
+        Ok(Watch {
+            client,
+            template: template.to_string(),
+            key_column: key_column.to_string(),
+            interval,
+            options,
+            last_key: None,
+            polled: false,
+            done: false,
+            greatest: None,
+            query: None,
+            results: None,
+        })
+    }
+
+    /// Only yields rows whose key is greater than `key`, a SQL literal such
+    /// as a [`Literal`](crate::sql::Literal) or a value returned by
+    /// [`Watch::last_key`].
+    pub fn with_start_after(mut self, key: impl fmt::Display) -> Self {
+        self.last_key = Some(key.to_string());
+        self
+    }
+
+    /// Returns the greatest key of the rows yielded so far as a SQL literal,
+    /// such as `42` or `TIMESTAMP '2024-01-31 10:11:12.500'`, or the starting
+    /// key if no row has been seen yet.
+    pub fn last_key(&self) -> Option<&str> {
+        self.last_key.as_deref()
+    }
+
+    /// Returns the SQL of the next query.
+    pub fn sql(&self) -> String {
+        let key = quote_ident(&self.key_column);
+        let condition = match &self.last_key {
+            Some(last_key) => format!("{} > {}", key, last_key),
+            // The first query yielded the rows with a null key.
+            None if self.polled => format!("{} IS NOT NULL", key),
+            None => "TRUE".to_string(),
+        };
+        self.template.replace(PLACEHOLDER, &condition)
+    }
+
+    /// Moves past the greatest key of `batch`.
+    fn advance(&mut self, batch: &RecordBatch) -> Result<(), DremioClientError> {
+        let column = batch.column(batch.schema().index_of(&self.key_column)?);
+        self.greatest = max_value(self.greatest.take(), column)?;
+        if let Some(greatest) = &self.greatest {
+            self.last_key = Some(value_literal(greatest, 0)?);
+        }
+        Ok(())
+    }
+}
+
+impl Stream for Watch<'_> {
+    type Item = Result<RecordBatch, DremioClientError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if this.done {
+                return Poll::Ready(None);
+            }
+            if let Some(results) = &mut this.results {
+                let Poll::Ready(next) = results.poll_next_unpin(cx) else {
+                    return Poll::Pending;
+                };
+                match next {
+                    Some(Ok(batch)) if batch.num_rows() == 0 => continue,
+                    Some(Ok(batch)) => match this.advance(&batch) {
+                        Ok(()) => return Poll::Ready(Some(Ok(batch))),
+                        Err(e) => {
+                            this.done = true;
+                            return Poll::Ready(Some(Err(e)));
+                        }
+                    },
+                    Some(Err(e)) => {
+                        this.done = true;
+                        return Poll::Ready(Some(Err(e)));
+                    }
+                    None => {
+                        this.results = None;
+                        continue;
+                    }
+                }
+            }
+            let query = match &mut this.query {
+                Some(query) => query,
+                None => {
+                    let delay = if this.polled {
+                        this.interval
+                    } else {
+                        Duration::ZERO
+                    };
+                    let (client, sql, options) = (this.client, this.sql(), this.options.clone());
+                    this.polled = true;
+                    this.query.insert(Box::pin(async move {
+                        tokio::time::sleep(delay).await;
+                        client.open_query(&sql, &options).await
+                    }))
+                }
+            };
+            let Poll::Ready(result) = query.as_mut().poll(cx) else {
+                return Poll::Pending;
+            };
+            this.query = None;
+            match result {
+                Ok(results) => this.results = Some(results),
+                Err(e) => {
+                    this.done = true;
+                    return Poll::Ready(Some(Err(e)));
+                }
+            }
+        }
+    }
+}
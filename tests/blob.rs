@@ -0,0 +1,47 @@
+use arrow::array::{Array, AsArray, RecordBatch, StringArray};
+use dremio_rs::blob::{spill_large_cells, SpillOptions};
+use std::sync::Arc;
+
+fn docs(values: Vec<&str>) -> RecordBatch {
+    RecordBatch::try_from_iter([("doc", Arc::new(StringArray::from(values)) as _)]).unwrap()
+}
+
+fn spilled_paths(batch: &RecordBatch) -> Vec<String> {
+    batch
+        .column_by_name("doc__spilled")
+        .unwrap()
+        .as_string::<i32>()
+        .iter()
+        .flatten()
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn test_spill_options_sharing_a_directory_do_not_collide() {
+    let dir = std::env::temp_dir().join(format!("dremio-rs-blob-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let first = spill_large_cells(
+        docs(vec!["first document", "tiny"]),
+        &SpillOptions::new(&dir, 8),
+    )
+    .unwrap();
+    let second =
+        spill_large_cells(docs(vec!["second document"]), &SpillOptions::new(&dir, 8)).unwrap();
+
+    assert!(first.column(0).is_null(0));
+    assert!(first.column(0).is_valid(1));
+    let first = spilled_paths(&first);
+    let second = spilled_paths(&second);
+    assert_eq!(first.len(), 1);
+    assert_ne!(first, second);
+    assert_eq!(
+        std::fs::read_to_string(&first[0]).unwrap(),
+        "first document"
+    );
+    assert_eq!(
+        std::fs::read_to_string(&second[0]).unwrap(),
+        "second document"
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
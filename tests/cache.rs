@@ -0,0 +1,98 @@
+use arrow::array::{Int32Array, RecordBatch};
+use arrow::datatypes::{DataType, Field, Schema};
+use dremio_rs::cache::{CacheKey, DiskCache, MemoryCache, ResultCache};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn batch(values: Vec<i32>) -> RecordBatch {
+    let schema = Arc::new(Schema::new(vec![Field::new("v", DataType::Int32, false)]));
+    RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(values))]).unwrap()
+}
+
+#[test]
+fn test_cache_key_normalization() {
+    assert_eq!(
+        CacheKey::new("SELECT  *\n FROM t ;"),
+        CacheKey::new("SELECT * FROM t")
+    );
+    assert_ne!(
+        CacheKey::new("SELECT 'a  b'"),
+        CacheKey::new("SELECT 'a b'")
+    );
+    assert_ne!(
+        CacheKey::new("SELECT * FROM t -- x\nWHERE id = 1"),
+        CacheKey::new("SELECT * FROM t -- x WHERE id = 1")
+    );
+    assert_eq!(
+        CacheKey::new("SELECT * -- all\n\n  FROM t\n"),
+        CacheKey::new("SELECT * -- all\nFROM t")
+    );
+    assert_ne!(
+        CacheKey::new("SELECT ?").with_param(1),
+        CacheKey::new("SELECT ?").with_param(2)
+    );
+}
+
+#[test]
+fn test_memory_cache_lru_eviction() {
+    let cache = MemoryCache::new().with_max_entries(2);
+    cache.put(CacheKey::new("a"), &[batch(vec![1])]);
+    cache.put(CacheKey::new("b"), &[batch(vec![2])]);
+    assert!(cache.get(&CacheKey::new("a")).is_some());
+    cache.put(CacheKey::new("c"), &[batch(vec![3])]);
+    assert!(cache.get(&CacheKey::new("a")).is_some());
+    assert!(cache.get(&CacheKey::new("b")).is_none());
+    assert!(cache.get(&CacheKey::new("c")).is_some());
+    assert_eq!(cache.len(), 2);
+}
+
+#[test]
+fn test_memory_cache_ttl_and_size() {
+    let cache = MemoryCache::new().with_ttl(Duration::from_millis(0));
+    cache.put(CacheKey::new("a"), &[batch(vec![1])]);
+    std::thread::sleep(Duration::from_millis(5));
+    assert!(cache.get(&CacheKey::new("a")).is_none());
+
+    let cache = MemoryCache::new().with_max_bytes(1);
+    cache.put(CacheKey::new("a"), &[batch(vec![1, 2, 3])]);
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn test_disk_cache_roundtrip() {
+    let dir = std::env::temp_dir().join(format!("dremio-rs-cache-{}", std::process::id()));
+    let cache = DiskCache::new(&dir).unwrap();
+    let key = CacheKey::new("SELECT 1");
+    cache.put(key.clone(), &[batch(vec![1, 2]), batch(vec![3])]);
+
+    let reopened = DiskCache::new(&dir).unwrap();
+    let batches = reopened.get(&key).unwrap();
+    assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 3);
+
+    reopened.invalidate(&key);
+    assert!(cache.get(&key).is_none());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_disk_cache_concurrent_puts() {
+    let dir = std::env::temp_dir().join(format!("dremio-rs-cache-race-{}", std::process::id()));
+    let cache = DiskCache::new(&dir).unwrap();
+    let key = CacheKey::new("SELECT * FROM t");
+    std::thread::scope(|scope| {
+        for i in 0..8 {
+            let (cache, key) = (&cache, &key);
+            scope.spawn(move || {
+                for _ in 0..20 {
+                    cache.put(key.clone(), &[batch((0..1000).map(|v| v * i).collect())]);
+                }
+            });
+        }
+    });
+
+    let batches = cache.get(&key).unwrap();
+    assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 1000);
+    // Only the entry remains, no temporary files.
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,88 @@
+use arrow::array::{Float64Array, Int32Array, RecordBatch};
+use arrow::datatypes::{DataType, Field, Schema};
+use dremio_rs::checks::{Check, Checks, Severity, Violation};
+use dremio_rs::{DremioClientError, QueryStream};
+use std::sync::{Arc, Mutex};
+
+fn batch(ids: Vec<Option<i32>>, amounts: Vec<f64>) -> RecordBatch {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int32, true),
+        Field::new("amount", DataType::Float64, false),
+    ]));
+    RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int32Array::from(ids)),
+            Arc::new(Float64Array::from(amounts)),
+        ],
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_required_checks_fail_the_stream() {
+    let batches = vec![
+        batch(vec![Some(1), Some(2)], vec![1.0, 2.0]),
+        batch(vec![Some(3), Some(2)], vec![3.0, 4.0]),
+    ];
+    let checks = Checks::new()
+        .require(Check::not_null("id"))
+        .require(Check::unique(["id"]));
+    let err = QueryStream::from_batches(batches)
+        .with_checks(checks)
+        .collect_batches()
+        .await
+        .unwrap_err();
+    let DremioClientError::CheckFailed(violation) = err else {
+        panic!("unexpected error: {}", err);
+    };
+    assert_eq!(violation.check, Check::unique(["id"]));
+    assert_eq!(violation.rows, 1);
+
+    let err = QueryStream::from_batches(vec![batch(vec![Some(1)], vec![1.0])])
+        .with_checks(Checks::new().require(Check::min_rows(2)))
+        .collect_batches()
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Check Failed: expected at least 2 rows, got 1"
+    );
+}
+
+#[tokio::test]
+async fn test_warning_checks_are_reported() {
+    let batches = vec![
+        batch(vec![Some(1), None], vec![-1.0, 50.0]),
+        batch(vec![None], vec![150.0]),
+    ];
+    let warnings: Arc<Mutex<Vec<Violation>>> = Arc::default();
+    let reported = warnings.clone();
+    let checks = Checks::new()
+        .warn(Check::not_null("id"))
+        .warn(Check::range("amount", Some(0.0), Some(100.0)))
+        .warn(Check::unique(["amount"]))
+        .on_warning(move |violation| reported.lock().unwrap().push(violation.clone()));
+    let batches = QueryStream::from_batches(batches)
+        .with_checks(checks)
+        .collect_batches()
+        .await
+        .unwrap();
+    assert_eq!(batches.len(), 2);
+    let warnings = warnings.lock().unwrap();
+    assert_eq!(
+        *warnings,
+        vec![
+            Violation {
+                check: Check::not_null("id"),
+                severity: Severity::Warn,
+                rows: 2,
+            },
+            Violation {
+                check: Check::range("amount", Some(0.0), Some(100.0)),
+                severity: Severity::Warn,
+                rows: 2,
+            },
+        ]
+    );
+}
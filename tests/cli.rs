@@ -0,0 +1,545 @@
+#![cfg(all(feature = "cli", feature = "testing"))]
+
+use arrow::array::{Int32Array, RecordBatch};
+use arrow::datatypes::{DataType, Field, Schema};
+use dremio_rs::testing::{FakeFlightSqlServer, FakeResponse, FakeServerHandle};
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use std::path::{Path, PathBuf};
+use std::process::{Output, Stdio};
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+mod common;
+
+use common::rest_server;
+
+fn batch(name: &str, values: Vec<i32>) -> RecordBatch {
+    let schema = Arc::new(Schema::new(vec![Field::new(name, DataType::Int32, false)]));
+    RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(values))]).unwrap()
+}
+
+/// A directory of its own for the configuration and output files of a test.
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("dremio-rs-cli-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+async fn server() -> FakeServerHandle {
+    FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM t",
+            FakeResponse::new(batch("v", vec![]).schema())
+                .with_endpoint(vec![batch("v", vec![1, 2]), batch("v", vec![3])]),
+        )
+        .start()
+        .await
+        .unwrap()
+}
+
+/// Runs the `dremio` binary with `args` in an environment of its own, which
+/// keeps the profiles and history in `home`.
+async fn dremio(home: &Path, env: &[(&str, &str)], args: &[&str], stdin: &str) -> Output {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_dremio"));
+    for key in [
+        "DREMIO_PROFILE",
+        "DREMIO_URL",
+        "DREMIO_USER",
+        "DREMIO_PASSWORD",
+        "DREMIO_TOKEN",
+        "DREMIO_REST_URL",
+        "PAGER",
+    ] {
+        command.env_remove(key);
+    }
+    let mut child = command
+        .env("HOME", home)
+        .env("DREMIO_CONFIG", home.join("config.toml"))
+        .envs(env.iter().copied())
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut input = child.stdin.take().unwrap();
+    input.write_all(stdin.as_bytes()).await.unwrap();
+    drop(input);
+    child.wait_with_output().await.unwrap()
+}
+
+fn credentials(url: &str) -> [(&str, &str); 3] {
+    [
+        ("DREMIO_URL", url),
+        ("DREMIO_USER", "dremio"),
+        ("DREMIO_PASSWORD", "dremio123"),
+    ]
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[tokio::test]
+async fn test_query() {
+    let server = server().await;
+    let home = temp_dir("query");
+    let url = server.url();
+    let env = credentials(&url);
+
+    let table = stdout(&dremio(&home, &env, &["query", "SELECT * FROM t"], "").await);
+    assert!(table.contains("| v |"), "{}", table);
+    assert!(table.ends_with("(3 rows)\n"), "{}", table);
+
+    let csv = dremio(&home, &env, &["query", "SELECT * FROM t", "-o", "csv"], "").await;
+    assert_eq!(stdout(&csv), "v\n1\n2\n3\n");
+
+    let json = dremio(&home, &env, &["query", "SELECT * FROM t", "-o", "json"], "").await;
+    assert_eq!(stdout(&json), "{\"v\":1}\n{\"v\":2}\n{\"v\":3}\n");
+
+    let path = home.join("t.parquet");
+    let parquet = dremio(
+        &home,
+        &env,
+        &[
+            "query",
+            "SELECT * FROM t",
+            "-o",
+            "parquet",
+            "--file",
+            path.to_str().unwrap(),
+        ],
+        "",
+    )
+    .await;
+    stdout(&parquet);
+    assert!(path.exists());
+
+    let failed = dremio(
+        &home,
+        &env,
+        &["query", "SELECT * FROM t", "-o", "parquet"],
+        "",
+    )
+    .await;
+    assert!(!failed.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&failed.stderr),
+        "error: parquet output requires --file\n"
+    );
+
+    let anonymous = dremio(&home, &[("DREMIO_URL", &url)], &["query", "SELECT 1"], "").await;
+    assert!(!anonymous.status.success());
+    assert!(String::from_utf8_lossy(&anonymous.stderr).contains("no user given"));
+
+    std::fs::remove_dir_all(&home).unwrap();
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_repl() {
+    let server = server().await;
+    let home = temp_dir("repl");
+    let url = server.url();
+    let input = "\\timing\nSELECT *\n  FROM t;\nSELECT * FROM missing;\n\\d lake.orders\n\\x\n\\q\nSELECT 1;\n";
+    let output = dremio(&home, &credentials(&url), &["repl"], input).await;
+
+    let out = stdout(&output);
+    assert!(
+        out.starts_with(&format!("Connected to {}.", url)),
+        "{}",
+        out
+    );
+    assert!(out.contains("Timing is on."), "{}", out);
+    assert!(out.contains("(3 rows)"), "{}", out);
+    assert_eq!(out.matches("Time: ").count(), 3, "{}", out);
+    // Failed statements are reported without ending the session.
+    let err = String::from_utf8_lossy(&output.stderr);
+    assert!(err.contains("Unknown command \\x."), "{}", err);
+    let queries = server.queries();
+    assert_eq!(queries[0], "SELECT *\n  FROM t");
+    assert_eq!(queries[1], "SELECT * FROM missing");
+    assert!(
+        queries[2].ends_with(
+            "WHERE TABLE_NAME = 'orders' AND TABLE_SCHEMA = 'lake' ORDER BY ORDINAL_POSITION"
+        ),
+        "{}",
+        queries[2]
+    );
+    // Nothing runs after `\q`.
+    assert_eq!(queries.len(), 3);
+    assert!(home.join(".dremio_history").exists());
+
+    std::fs::remove_dir_all(&home).unwrap();
+    server.shutdown().await;
+}
+
+fn parquet_rows(path: &Path) -> i64 {
+    let file = std::fs::File::open(path).unwrap();
+    let reader = SerializedFileReader::new(file).unwrap();
+    reader.metadata().file_metadata().num_rows()
+}
+
+#[tokio::test]
+async fn test_export() {
+    let orders = RecordBatch::try_from_iter([
+        (
+            "region",
+            Arc::new(arrow::array::StringArray::from(vec!["eu", "us", "eu"])) as _,
+        ),
+        ("v", Arc::new(Int32Array::from(vec![1, 2, 3])) as _),
+    ])
+    .unwrap();
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM orders",
+            FakeResponse::new(orders.schema()).with_endpoint(vec![orders.clone()]),
+        )
+        .on(
+            "SELECT * FROM empty",
+            FakeResponse::new(orders.schema()).with_endpoint(vec![]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let home = temp_dir("export");
+    let url = server.url();
+    let env = credentials(&url);
+    let export = |sql: &'static str, to: PathBuf, extra: &'static [&'static str]| {
+        let home = home.clone();
+        async move {
+            let to = to.to_str().unwrap().to_string();
+            let mut args = vec!["export", "--no-progress", "--sql", sql, "--to", &to];
+            args.extend_from_slice(extra);
+            dremio(&home, &env, &args, "").await
+        }
+    };
+
+    let path = home.join("orders.parquet");
+    let output = export("SELECT * FROM orders", path.clone(), &["--verify"]).await;
+    stdout(&output);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "Exported 3 rows to 1 file.\nVerified 3 rows.\n"
+    );
+    assert_eq!(parquet_rows(&path), 3);
+
+    let root = home.join("partitioned");
+    let output = export(
+        "SELECT * FROM orders",
+        root.clone(),
+        &["--partition-by", "region", "--verify", "checksums"],
+    )
+    .await;
+    stdout(&output);
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("Exported 3 rows to 2 files."));
+    let mut partitions: Vec<_> = std::fs::read_dir(&root)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    partitions.sort();
+    assert_eq!(partitions, ["region=eu", "region=us"]);
+
+    let output = export("SELECT * FROM orders", "-".into(), &["--format", "csv"]).await;
+    assert_eq!(stdout(&output), "region,v\neu,1\nus,2\neu,3\n");
+
+    // An empty result writes no file, which verification reports.
+    let path = home.join("empty.parquet");
+    let output = export("SELECT * FROM empty", path.clone(), &["--verify"]).await;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .ends_with("error: verification failed: 0 rows exported, no file written\n"));
+    assert!(!path.exists());
+
+    for (to, extra, message) in [
+        (
+            "out.csv",
+            &["--format", "csv", "--partition-by", "region"][..],
+            "--partition-by is only supported for parquet exports",
+        ),
+        ("-", &[][..], "parquet exports require a file"),
+        (
+            "s3://bucket/out.parquet",
+            &[][..],
+            "s3:// destinations are not supported yet",
+        ),
+    ] {
+        let output = dremio(
+            &home,
+            &env,
+            &[
+                &["export", "--sql", "SELECT * FROM orders", "--to", to][..],
+                extra,
+            ]
+            .concat(),
+            "",
+        )
+        .await;
+        assert!(!output.status.success());
+        assert!(
+            String::from_utf8_lossy(&output.stderr).contains(message),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    std::fs::remove_dir_all(&home).unwrap();
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_catalog_commands() {
+    let names = RecordBatch::try_from_iter([
+        (
+            "NAME",
+            Arc::new(arrow::array::StringArray::from(vec!["lake", "space"])) as _,
+        ),
+        (
+            "TYPE",
+            Arc::new(arrow::array::StringArray::from(vec!["SCHEMA", "SCHEMA"])) as _,
+        ),
+    ])
+    .unwrap();
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "*FROM INFORMATION_SCHEMA.*",
+            FakeResponse::new(names.schema()).with_endpoint(vec![names]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let home = temp_dir("catalog");
+    let url = server.url();
+    let env = credentials(&url);
+
+    let output = dremio(&home, &env, &["ls", "-o", "csv"], "").await;
+    assert_eq!(stdout(&output), "NAME,TYPE\nlake,SCHEMA\nspace,SCHEMA\n");
+    stdout(&dremio(&home, &env, &["ls", "lake.\"my_folder\""], "").await);
+    stdout(&dremio(&home, &env, &["describe", "lake.orders"], "").await);
+    stdout(&dremio(&home, &env, &["search", "Sales_%"], "").await);
+
+    let queries = server.queries();
+    assert!(queries[0].contains("FROM INFORMATION_SCHEMA.SCHEMATA"));
+    // Wildcards in names are escaped, and the listed names are relative to
+    // the folder.
+    assert!(
+        queries[1].contains("SUBSTR(SCHEMA_NAME, 16)"),
+        "{}",
+        queries[1]
+    );
+    assert!(
+        queries[1].contains("LIKE 'lake.my\\_folder.%' ESCAPE '\\'"),
+        "{}",
+        queries[1]
+    );
+    assert!(queries[1].contains("TABLE_SCHEMA = 'lake.my_folder'"));
+    assert!(
+        queries[2].contains("TABLE_NAME = 'orders' AND TABLE_SCHEMA = 'lake'"),
+        "{}",
+        queries[2]
+    );
+    assert!(
+        queries[3].contains("LIKE '%sales\\_\\%%' ESCAPE '\\'"),
+        "{}",
+        queries[3]
+    );
+
+    let output = dremio(&home, &env, &["describe", "lake.\"unterminated"], "").await;
+    assert!(!output.status.success());
+    assert_eq!(server.queries().len(), 4);
+
+    std::fs::remove_dir_all(&home).unwrap();
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_jobs_commands() {
+    let jobs = RecordBatch::try_from_iter([(
+        "job_id",
+        Arc::new(arrow::array::StringArray::from(vec!["1f"])) as _,
+    )])
+    .unwrap();
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "*FROM sys.jobs_recent*",
+            FakeResponse::new(jobs.schema()).with_endpoint(vec![jobs]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let rest = rest_server(&[
+        (
+            "GET /api/v3/job/1f",
+            r#"{"jobState":"COMPLETED","rowCount":3,"queryType":"FLIGHT","errorMessage":""}"#,
+        ),
+        ("POST /api/v3/job/1f/cancel", "{}"),
+        ("GET /apiv2/profiles/1f.json?attempt=1", r#"{"id":"1f"}"#),
+    ])
+    .await;
+    let home = temp_dir("jobs");
+    let url = server.url();
+    let env = [
+        ("DREMIO_URL", url.as_str()),
+        ("DREMIO_USER", "dremio"),
+        ("DREMIO_PASSWORD", "dremio123"),
+        ("DREMIO_REST_URL", &rest.url),
+    ];
+
+    let output = dremio(
+        &home,
+        &env,
+        &[
+            "jobs",
+            "list",
+            "--running",
+            "--user",
+            "o'brien",
+            "--limit",
+            "5",
+            "-o",
+            "csv",
+        ],
+        "",
+    )
+    .await;
+    assert_eq!(stdout(&output), "job_id\n1f\n");
+    assert_eq!(
+        server.queries()[0],
+        "SELECT job_id, status, query_type, user_name, submitted_ts, final_state_ts, \
+         rows_returned, query FROM sys.jobs_recent WHERE 1 = 1 \
+         AND status NOT IN ('COMPLETED', 'FAILED', 'CANCELED') \
+         AND user_name = 'o''brien' ORDER BY submitted_ts DESC LIMIT 5"
+    );
+
+    // The REST commands authenticate with the token alone.
+    let env = [
+        ("DREMIO_USER", "dremio"),
+        ("DREMIO_TOKEN", "pat"),
+        ("DREMIO_REST_URL", &rest.url),
+    ];
+    let output = dremio(&home, &env, &["jobs", "show", "1f"], "").await;
+    assert_eq!(
+        stdout(&output),
+        "State:      Completed\nQuery type: FLIGHT\nRows:       3\n"
+    );
+    let output = dremio(&home, &env, &["jobs", "cancel", "1f"], "").await;
+    stdout(&output);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "Cancellation of job 1f requested.\n"
+    );
+    let output = dremio(
+        &home,
+        &env,
+        &["jobs", "profile", "1f", "--attempt", "1"],
+        "",
+    )
+    .await;
+    assert_eq!(stdout(&output), "{\n  \"id\": \"1f\"\n}\n");
+    let output = dremio(&home, &env, &["jobs", "show", "2e"], "").await;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not found"));
+    assert_eq!(
+        rest.requests(),
+        [
+            "GET /api/v3/job/1f",
+            "POST /api/v3/job/1f/cancel",
+            "GET /apiv2/profiles/1f.json?attempt=1",
+            "GET /api/v3/job/2e",
+        ]
+    );
+
+    std::fs::remove_dir_all(&home).unwrap();
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_login_saves_profiles_without_passwords() {
+    let server = FakeFlightSqlServer::new()
+        .with_credentials("alice", "pat")
+        .on(
+            "SELECT * FROM t",
+            FakeResponse::new(batch("v", vec![]).schema()).with_endpoint(vec![batch("v", vec![1])]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let home = temp_dir("login");
+    let config = home.join("config.toml");
+    let url = server.url();
+    let env = [("DREMIO_URL", url.as_str()), ("DREMIO_USER", "alice")];
+
+    let output = dremio(&home, &env, &["login", "--password", "pat"], "").await;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("does not save passwords"));
+    assert!(!config.exists());
+
+    // A token given alongside a password is saved in its place.
+    let output = dremio(
+        &home,
+        &env,
+        &[
+            "login",
+            "--profile",
+            "dev",
+            "--password",
+            "x",
+            "--token",
+            "pat",
+        ],
+        "",
+    )
+    .await;
+    stdout(&output);
+    let saved = std::fs::read_to_string(&config).unwrap();
+    assert!(saved.contains("default_profile = \"dev\""), "{}", saved);
+    assert!(saved.contains("token = \"pat\""), "{}", saved);
+    assert!(!saved.contains("password"), "{}", saved);
+
+    let output = dremio(
+        &home,
+        &env,
+        &["login", "--profile", "ci", "--token", "pat", "--no-default"],
+        "",
+    )
+    .await;
+    stdout(&output);
+    let saved = std::fs::read_to_string(&config).unwrap();
+    assert!(saved.contains("default_profile = \"dev\""), "{}", saved);
+    assert!(saved.contains("[profiles.ci]"), "{}", saved);
+
+    // Without any settings, commands use the default profile.
+    let output = dremio(&home, &[], &["query", "SELECT * FROM t", "-o", "csv"], "").await;
+    assert_eq!(stdout(&output), "v\n1\n");
+    let output = dremio(
+        &home,
+        &[],
+        &["--profile", "ci", "query", "SELECT * FROM t"],
+        "",
+    )
+    .await;
+    stdout(&output);
+    let output = dremio(&home, &[], &["--profile", "prod", "query", "SELECT 1"], "").await;
+    assert!(!output.status.success());
+
+    // A wrong token is not saved.
+    let output = dremio(
+        &home,
+        &env,
+        &["login", "--profile", "bad", "--token", "wrong"],
+        "",
+    )
+    .await;
+    assert!(!output.status.success());
+    assert!(!std::fs::read_to_string(&config).unwrap().contains("bad"));
+
+    std::fs::remove_dir_all(&home).unwrap();
+    server.shutdown().await;
+}
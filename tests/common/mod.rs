@@ -0,0 +1,92 @@
+//! Helpers shared by the integration tests.
+
+// Each test crate uses only some of the helpers.
+#![allow(dead_code)]
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+
+/// Serves canned JSON responses to REST requests, keyed by method and path,
+/// and records the requests received. A route listed several times is
+/// answered with its responses in turn, the last one repeating. Unknown
+/// paths are answered with 404.
+pub async fn rest_server(routes: &[(&str, &str)]) -> FakeRest {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let mut responses: HashMap<String, VecDeque<String>> = HashMap::new();
+    for (route, body) in routes {
+        responses
+            .entry(route.to_string())
+            .or_default()
+            .push_back(body.to_string());
+    }
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let received = requests.clone();
+    tokio::spawn(async move {
+        while let Ok((socket, _)) = listener.accept().await {
+            let mut socket = BufReader::new(socket);
+            let mut request = String::new();
+            socket.read_line(&mut request).await.unwrap();
+            let mut length = 0;
+            loop {
+                let mut header = String::new();
+                socket.read_line(&mut header).await.unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut content = vec![0; length];
+            socket.read_exact(&mut content).await.unwrap();
+            let route = request.rsplit_once(' ').unwrap().0.to_string();
+            let (status, body) = match responses.get_mut(&route) {
+                Some(bodies) if bodies.len() > 1 => ("200 OK", bodies.pop_front().unwrap()),
+                Some(bodies) => ("200 OK", bodies[0].clone()),
+                None => (
+                    "404 Not Found",
+                    r#"{"errorMessage":"not found"}"#.to_string(),
+                ),
+            };
+            received
+                .lock()
+                .unwrap()
+                .push((route, String::from_utf8(content).unwrap()));
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+                 Connection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+    FakeRest { url, requests }
+}
+
+/// A fake REST API started by [`rest_server`].
+pub struct FakeRest {
+    /// The base URL of the API.
+    pub url: String,
+    requests: Arc<Mutex<Vec<(String, String)>>>,
+}
+
+impl FakeRest {
+    /// Returns the method and path of the requests received so far, in order.
+    pub fn requests(&self) -> Vec<String> {
+        let requests = self.requests.lock().unwrap();
+        requests.iter().map(|(route, _)| route.clone()).collect()
+    }
+
+    /// Returns the bodies of the requests received so far, in order.
+    pub fn bodies(&self) -> Vec<String> {
+        let requests = self.requests.lock().unwrap();
+        requests.iter().map(|(_, body)| body.clone()).collect()
+    }
+}
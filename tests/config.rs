@@ -0,0 +1,24 @@
+#![cfg(feature = "config")]
+
+use dremio_rs::config::Profile;
+
+#[test]
+fn test_profile_debug_redacts_secrets() {
+    let profile = Profile {
+        url: "http://localhost:32010".to_string(),
+        user: Some("alice".to_string()),
+        password: Some("hunter2".to_string()),
+        token: Some("s3cr3t".to_string()),
+        rest_url: None,
+    };
+    let debug = format!("{:?}", profile);
+    assert_eq!(
+        debug,
+        "Profile { url: \"http://localhost:32010\", user: Some(\"alice\"), \
+         password: Some(..), token: Some(..), rest_url: None }"
+    );
+    assert_eq!(
+        format!("{:?}", Profile::default()),
+        "Profile { url: \"\", user: None, password: None, token: None, rest_url: None }"
+    );
+}
@@ -0,0 +1,271 @@
+use arrow::array::{
+    Array, AsArray, Decimal128Array, IntervalDayTimeArray, IntervalMonthDayNanoArray,
+    IntervalYearMonthArray, RecordBatch, TimestampMillisecondArray,
+};
+use arrow::datatypes::{
+    DataType, Float64Type, IntervalDayTime, IntervalMonthDayNano, TimeUnit,
+    TimestampMillisecondType,
+};
+use dremio_rs::convert::{self, IntervalValue};
+use std::sync::Arc;
+
+// 2023-11-14T22:13:20.123Z and 1969-12-31T23:59:58.500Z.
+const MILLIS: [i64; 2] = [1_700_000_000_123, -1_500];
+
+fn timestamps() -> RecordBatch {
+    RecordBatch::try_from_iter([
+        (
+            "at",
+            Arc::new(TimestampMillisecondArray::from(vec![
+                Some(MILLIS[0]),
+                Some(MILLIS[1]),
+                None,
+            ])) as _,
+        ),
+        (
+            "zoned",
+            Arc::new(TimestampMillisecondArray::from(vec![0, 0, 0]).with_timezone("+02:00")) as _,
+        ),
+    ])
+    .unwrap()
+}
+
+#[test]
+fn test_with_timezone_keeps_instants() {
+    let batch = convert::with_timezone(timestamps(), "Europe/Berlin").unwrap();
+    for (field, column) in batch.schema().fields().iter().zip(batch.columns()) {
+        assert_eq!(
+            field.data_type(),
+            &DataType::Timestamp(TimeUnit::Millisecond, Some("Europe/Berlin".into()))
+        );
+        assert_eq!(field.data_type(), column.data_type());
+    }
+    let at = batch.column(0).as_primitive::<TimestampMillisecondType>();
+    assert_eq!(at.values(), &[MILLIS[0], MILLIS[1], 0]);
+    assert!(at.is_null(2));
+}
+
+#[test]
+fn test_timestamp_parts() {
+    let batch = timestamps();
+    let at = batch.column(0).as_ref();
+    assert_eq!(
+        convert::timestamp_parts(at, 0).unwrap(),
+        Some((1_700_000_000, 123_000_000))
+    );
+    // Instants before the epoch round down to the previous second.
+    assert_eq!(
+        convert::timestamp_parts(at, 1).unwrap(),
+        Some((-2, 500_000_000))
+    );
+    assert_eq!(convert::timestamp_parts(at, 2).unwrap(), None);
+    assert_eq!(
+        convert::timestamp_nanos(at, 1).unwrap(),
+        Some(-1_500_000_000)
+    );
+
+    let not_a_timestamp = arrow::array::Int64Array::from(vec![1]);
+    assert!(convert::timestamp_parts(&not_a_timestamp, 0).is_err());
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn test_timestamp_to_chrono() {
+    let batch = timestamps();
+    let at = convert::timestamp_to_chrono(batch.column(0).as_ref(), 0)
+        .unwrap()
+        .unwrap();
+    assert_eq!(at.to_rfc3339(), "2023-11-14T22:13:20.123+00:00");
+    assert_eq!(
+        convert::timestamp_to_chrono(batch.column(0).as_ref(), 2).unwrap(),
+        None
+    );
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn test_timestamp_to_time() {
+    let batch = timestamps();
+    let at = convert::timestamp_to_time(batch.column(0).as_ref(), 1)
+        .unwrap()
+        .unwrap();
+    assert_eq!(at.unix_timestamp_nanos(), -1_500_000_000);
+    assert_eq!(at.offset(), time::UtcOffset::UTC);
+}
+
+fn decimals() -> RecordBatch {
+    RecordBatch::try_from_iter([
+        (
+            "price",
+            Arc::new(
+                Decimal128Array::from(vec![Some(1250), Some(-5), None])
+                    .with_precision_and_scale(10, 2)
+                    .unwrap(),
+            ) as _,
+        ),
+        (
+            "thousands",
+            Arc::new(
+                Decimal128Array::from(vec![12, 0, 1])
+                    .with_precision_and_scale(5, -3)
+                    .unwrap(),
+            ) as _,
+        ),
+    ])
+    .unwrap()
+}
+
+#[test]
+fn test_decimal_values() {
+    let batch = decimals();
+    let price = batch.column(0).as_ref();
+    assert_eq!(convert::decimal_parts(price, 0).unwrap(), Some((1250, 2)));
+    assert_eq!(convert::decimal_to_f64(price, 1).unwrap(), Some(-0.05));
+    assert_eq!(
+        convert::decimal_to_string(price, 0).unwrap().as_deref(),
+        Some("12.50")
+    );
+    assert_eq!(convert::decimal_to_string(price, 2).unwrap(), None);
+    assert_eq!(
+        convert::decimal_to_f64(batch.column(1).as_ref(), 0).unwrap(),
+        Some(12_000.0)
+    );
+
+    let not_a_decimal = arrow::array::Int64Array::from(vec![1]);
+    assert!(convert::decimal_parts(&not_a_decimal, 0).is_err());
+}
+
+#[test]
+fn test_cast_decimals() {
+    let batch = convert::cast_decimals(decimals(), &DataType::Float64).unwrap();
+    assert_eq!(batch.schema().field(0).data_type(), &DataType::Float64);
+    let price = batch.column(0).as_primitive::<Float64Type>();
+    assert_eq!((price.value(0), price.value(1)), (12.5, -0.05));
+    assert!(price.is_null(2));
+
+    let batch = convert::cast_decimals(decimals(), &DataType::Utf8).unwrap();
+    let price = batch.column(0).as_string::<i32>();
+    assert_eq!((price.value(0), price.value(1)), ("12.50", "-0.05"));
+
+    // Batches without decimals are returned unchanged.
+    let batch = convert::cast_decimals(timestamps(), &DataType::Utf8).unwrap();
+    assert_eq!(batch, timestamps());
+}
+
+#[cfg(feature = "rust_decimal")]
+#[test]
+fn test_decimal_to_rust_decimal() {
+    let batch = decimals();
+    assert_eq!(
+        convert::decimal_to_rust_decimal(batch.column(0).as_ref(), 0).unwrap(),
+        Some(rust_decimal::Decimal::new(1250, 2))
+    );
+    // A negative scale is folded into the value.
+    assert_eq!(
+        convert::decimal_to_rust_decimal(batch.column(1).as_ref(), 0).unwrap(),
+        Some(rust_decimal::Decimal::new(12_000, 0))
+    );
+}
+
+fn intervals() -> RecordBatch {
+    RecordBatch::try_from_iter([
+        (
+            "months",
+            Arc::new(IntervalYearMonthArray::from(vec![Some(14), Some(-3), None])) as _,
+        ),
+        (
+            "day_time",
+            Arc::new(IntervalDayTimeArray::from(vec![
+                IntervalDayTime::new(3, 4 * 3_600_000 + 5 * 60_000 + 6_500),
+                IntervalDayTime::new(0, 0),
+                IntervalDayTime::new(-1, -1),
+            ])) as _,
+        ),
+        (
+            "month_day_nano",
+            Arc::new(IntervalMonthDayNanoArray::from(vec![
+                IntervalMonthDayNano::new(1, 2, 1),
+                IntervalMonthDayNano::new(0, 1, 3_600_000_000_000),
+                IntervalMonthDayNano::new(0, 0, -90_000_000_000),
+            ])) as _,
+        ),
+    ])
+    .unwrap()
+}
+
+#[test]
+fn test_interval_value() {
+    let batch = intervals();
+    assert_eq!(
+        convert::interval_value(batch.column(0).as_ref(), 0).unwrap(),
+        Some(IntervalValue {
+            months: 14,
+            days: 0,
+            nanos: 0
+        })
+    );
+    assert_eq!(
+        convert::interval_value(batch.column(0).as_ref(), 2).unwrap(),
+        None
+    );
+    assert_eq!(
+        convert::interval_value(batch.column(1).as_ref(), 2).unwrap(),
+        Some(IntervalValue {
+            months: 0,
+            days: -1,
+            nanos: -1_000_000
+        })
+    );
+
+    let not_an_interval = arrow::array::Int64Array::from(vec![1]);
+    assert!(convert::interval_value(&not_an_interval, 0).is_err());
+}
+
+#[test]
+fn test_interval_to_iso8601() {
+    let batch = intervals();
+    let iso = |column: usize, row: usize| {
+        convert::interval_to_iso8601(batch.column(column).as_ref(), row).unwrap()
+    };
+    assert_eq!(iso(0, 0).as_deref(), Some("P1Y2M"));
+    assert_eq!(iso(0, 1).as_deref(), Some("P-3M"));
+    assert_eq!(iso(0, 2), None);
+    assert_eq!(iso(1, 0).as_deref(), Some("P3DT4H5M6.5S"));
+    assert_eq!(iso(1, 1).as_deref(), Some("PT0S"));
+    assert_eq!(iso(1, 2).as_deref(), Some("P-1DT-0.001S"));
+    assert_eq!(iso(2, 0).as_deref(), Some("P1M2DT0.000000001S"));
+    assert_eq!(iso(2, 1).as_deref(), Some("P1DT1H"));
+    assert_eq!(iso(2, 2).as_deref(), Some("PT-1M-30S"));
+}
+
+#[test]
+fn test_cast_intervals() {
+    let batch = convert::cast_intervals(intervals()).unwrap();
+    for field in batch.schema().fields() {
+        assert_eq!(field.data_type(), &DataType::Utf8);
+    }
+    let months = batch.column(0).as_string::<i32>();
+    assert_eq!(months.value(1), "P-3M");
+    assert!(months.is_null(2));
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn test_interval_to_chrono() {
+    let batch = intervals();
+    assert_eq!(
+        convert::interval_to_chrono(batch.column(1).as_ref(), 0).unwrap(),
+        Some(
+            chrono::Duration::days(3)
+                + chrono::Duration::hours(4)
+                + chrono::Duration::minutes(5)
+                + chrono::Duration::milliseconds(6_500)
+        )
+    );
+    // Months have no fixed length.
+    assert!(convert::interval_to_chrono(batch.column(0).as_ref(), 0).is_err());
+    assert_eq!(
+        convert::interval_to_chrono(batch.column(0).as_ref(), 2).unwrap(),
+        None
+    );
+}
@@ -0,0 +1,121 @@
+#![cfg(feature = "testing")]
+
+use arrow::array::{Int32Array, RecordBatch};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow_flight::FlightDescriptor;
+use dremio_rs::copy::{copy, CopyOptions, IfExists};
+use dremio_rs::testing::{FakeFlightSqlServer, FakeResponse};
+use dremio_rs::Client;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+fn batch(values: Vec<i32>) -> RecordBatch {
+    let schema = Arc::new(Schema::new(vec![Field::new("v", DataType::Int32, false)]));
+    RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(values))]).unwrap()
+}
+
+#[tokio::test]
+async fn test_copy_between_clusters() {
+    let response = FakeResponse::new(batch(vec![]).schema())
+        .with_endpoint(vec![batch(vec![1, 2]), batch(vec![3])])
+        .with_endpoint(vec![batch(vec![4, 5, 6]), batch(vec![7])]);
+    let source = FakeFlightSqlServer::new()
+        .on("SELECT * FROM orders", response)
+        .start()
+        .await
+        .unwrap();
+    let destination = FakeFlightSqlServer::new()
+        .with_table("lake.orders", vec![batch(vec![0])])
+        .start()
+        .await
+        .unwrap();
+
+    let mut source_client = Client::new(&source.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    let destination_client = Client::new(&destination.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    let query = "SELECT * FROM orders";
+    let table = ["lake", "orders"];
+
+    let result = copy(
+        &mut source_client,
+        query,
+        &destination_client,
+        table,
+        &CopyOptions::new(),
+    )
+    .await;
+    assert!(result.is_err());
+
+    let progress = Arc::new(AtomicU64::new(0));
+    let seen = progress.clone();
+    let options = CopyOptions::new()
+        .with_if_exists(IfExists::Replace)
+        .with_parallelism(2)
+        .with_progress(move |metrics| seen.store(metrics.rows, Ordering::Relaxed));
+    let summary = copy(
+        &mut source_client,
+        query,
+        &destination_client,
+        table,
+        &options,
+    )
+    .await
+    .unwrap();
+    assert_eq!(summary.rows, 7);
+    assert_eq!(summary.batches, 4);
+    assert_eq!(progress.load(Ordering::Relaxed), 7);
+
+    let mut values: Vec<i32> = destination
+        .table("lake.orders")
+        .unwrap()
+        .iter()
+        .flat_map(|b| {
+            b.column(0)
+                .as_any()
+                .downcast_ref::<Int32Array>()
+                .unwrap()
+                .values()
+                .to_vec()
+        })
+        .collect();
+    values.sort();
+    assert_eq!(values, (1..=7).collect::<Vec<_>>());
+}
+
+#[tokio::test]
+async fn test_forward_to_flight_server() {
+    let response = FakeResponse::new(batch(vec![]).schema())
+        .with_endpoint(vec![batch(vec![1, 2]), batch(vec![3])]);
+    let source = FakeFlightSqlServer::new()
+        .on("SELECT * FROM orders", response)
+        .start()
+        .await
+        .unwrap();
+    let target = FakeFlightSqlServer::new().start().await.unwrap();
+
+    let mut client = Client::new(&source.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    let descriptor = FlightDescriptor::new_path(vec!["cache".to_string(), "orders".to_string()]);
+    let error = client
+        .forward("SELECT * FROM missing", &target.url(), descriptor.clone())
+        .await
+        .unwrap_err();
+    assert_eq!(error.query(), Some("SELECT * FROM missing"));
+
+    let results = client
+        .forward("SELECT * FROM orders", &target.url(), descriptor)
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    let rows: usize = target
+        .table("cache.orders")
+        .unwrap()
+        .iter()
+        .map(|b| b.num_rows())
+        .sum();
+    assert_eq!(rows, 3);
+}
@@ -0,0 +1,479 @@
+use arrow::array::{Int32Array, RecordBatch};
+use arrow::datatypes::{DataType, Field, Schema};
+use dremio_rs::export::{write_text, TextFormat};
+use dremio_rs::QueryStream;
+use std::sync::Arc;
+
+fn batch(values: Vec<i32>) -> RecordBatch {
+    let schema = Arc::new(Schema::new(vec![Field::new("v", DataType::Int32, false)]));
+    RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(values))]).unwrap()
+}
+
+#[tokio::test]
+async fn test_write_text() {
+    let batches = vec![batch(vec![1, 2]), batch(vec![3])];
+
+    let mut csv = Vec::new();
+    let rows = write_text(
+        QueryStream::from_batches(batches.clone()),
+        TextFormat::Csv,
+        &mut csv,
+    )
+    .await
+    .unwrap();
+    assert_eq!(rows, 3);
+    assert_eq!(String::from_utf8(csv).unwrap(), "v\n1\n2\n3\n");
+
+    // Dremio often sends an empty batch first, which must not repeat the header.
+    let mut csv = Vec::new();
+    write_text(
+        QueryStream::from_batches(vec![batch(vec![]), batch(vec![1]), batch(vec![])]),
+        TextFormat::Csv,
+        &mut csv,
+    )
+    .await
+    .unwrap();
+    assert_eq!(String::from_utf8(csv).unwrap(), "v\n1\n");
+
+    let mut json = Vec::new();
+    write_text(
+        QueryStream::from_batches(batches),
+        TextFormat::Json,
+        &mut json,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(json).unwrap(),
+        "{\"v\":1}\n{\"v\":2}\n{\"v\":3}\n"
+    );
+}
+
+#[cfg(feature = "parquet")]
+#[test]
+fn test_verified_partitioned_export() {
+    use arrow::array::StringArray;
+    use dremio_rs::export::{PartitionedParquetWriter, Verification, Verifier};
+
+    let root = std::env::temp_dir().join(format!("dremio-rs-verify-{}", std::process::id()));
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("dt", DataType::Utf8, false),
+        Field::new("v", DataType::Int32, true),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(StringArray::from(vec!["a", "b", "a"])),
+            Arc::new(Int32Array::from(vec![Some(1), None, Some(3)])),
+        ],
+    )
+    .unwrap();
+
+    let mut writer = PartitionedParquetWriter::new(&root, vec!["dt".to_string()])
+        .with_verification(Verification::Checksums);
+    writer.write(&batch).unwrap();
+    let summary = writer.close().unwrap();
+    let report = summary.verification.unwrap();
+    assert!(report.is_ok(), "{}", report);
+    assert_eq!(report.written_rows, 3);
+    assert_eq!(report.columns.len(), 1);
+    assert_eq!(report.columns[0].written_nulls, 1);
+
+    // Rows that never reached the files are reported.
+    let mut verifier = Verifier::new(Verification::RowCounts);
+    verifier.update(&batch).unwrap();
+    let report = verifier.verify(&summary.files[..1]).unwrap();
+    std::fs::remove_dir_all(&root).unwrap();
+    assert!(!report.is_ok());
+    assert_eq!((report.expected_rows, report.written_rows), (3, 2));
+}
+
+#[cfg(all(feature = "parquet", feature = "testing"))]
+#[tokio::test]
+async fn test_partitioned_export_rolls_files() {
+    use arrow::array::StringArray;
+    use dremio_rs::export::PartitionedParquetWriter;
+    use dremio_rs::testing::{FakeFlightSqlServer, FakeResponse};
+    use dremio_rs::Client;
+
+    let sales = RecordBatch::try_from_iter([
+        (
+            "region",
+            Arc::new(StringArray::from(vec!["EU", "EU", "US", "EU"])) as _,
+        ),
+        ("v", Arc::new(Int32Array::from(vec![1, 2, 3, 4])) as _),
+    ])
+    .unwrap();
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM sales",
+            FakeResponse::new(sales.schema()).with_endpoint(vec![sales]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+
+    let root = std::env::temp_dir().join(format!("dremio-rs-partitioned-{}", std::process::id()));
+    let writer =
+        PartitionedParquetWriter::new(&root, vec!["region".to_string()]).with_max_rows_per_file(2);
+    let summary = client
+        .write_parquet_partitioned("SELECT * FROM sales", writer)
+        .await
+        .unwrap();
+    std::fs::remove_dir_all(&root).unwrap();
+    assert_eq!(summary.rows, 4);
+    assert_eq!(
+        summary.files,
+        [
+            root.join("region=EU/part-0000.parquet"),
+            root.join("region=EU/part-0001.parquet"),
+            root.join("region=US/part-0000.parquet"),
+        ]
+    );
+    server.shutdown().await;
+}
+
+#[cfg(all(feature = "object_store", feature = "testing"))]
+#[tokio::test]
+async fn test_write_to_store() {
+    use dremio_rs::export::ObjectFormat;
+    use dremio_rs::testing::{FakeFlightSqlServer, FakeResponse};
+    use dremio_rs::Client;
+    use object_store::memory::InMemory;
+    use object_store::path::Path;
+    use object_store::ObjectStore;
+
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM t",
+            FakeResponse::new(batch(vec![]).schema()).with_endpoint(vec![batch(vec![1, 2])]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+
+    let store = Arc::new(InMemory::new());
+    let path = Path::from("exports/t.jsonl");
+    let rows = client
+        .write_to_store("SELECT * FROM t", store.clone(), &path, ObjectFormat::Json)
+        .await
+        .unwrap();
+    assert_eq!(rows, 2);
+    let bytes = store.get(&path).await.unwrap().bytes().await.unwrap();
+    assert_eq!(&bytes[..], b"{\"v\":1}\n{\"v\":2}\n");
+
+    // A failed query leaves no object behind.
+    let path = Path::from("exports/missing.jsonl");
+    assert!(client
+        .write_to_store(
+            "SELECT * FROM missing",
+            store.clone(),
+            &path,
+            ObjectFormat::Json
+        )
+        .await
+        .is_err());
+    assert!(store.head(&path).await.is_err());
+    server.shutdown().await;
+}
+
+#[cfg(all(feature = "parquet", feature = "testing"))]
+#[tokio::test]
+async fn test_parallel_export() {
+    use arrow::array::Date32Array;
+    use dremio_rs::export::ParallelExporter;
+    use dremio_rs::testing::{FakeFlightSqlServer, FakeResponse};
+    use dremio_rs::Client;
+
+    let dates = |values: Vec<i32>| {
+        let schema = Arc::new(Schema::new(vec![Field::new("dt", DataType::Date32, true)]));
+        RecordBatch::try_new(schema, vec![Arc::new(Date32Array::from(values))]).unwrap()
+    };
+    let bounds = {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("lo", DataType::Date32, true),
+            Field::new("hi", DataType::Date32, true),
+        ]));
+        let (lo, hi) = (
+            Date32Array::from(vec![19723]),
+            Date32Array::from(vec![19726]),
+        );
+        RecordBatch::try_new(schema, vec![Arc::new(lo), Arc::new(hi)]).unwrap()
+    };
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT MIN(\"dt\")*",
+            FakeResponse::new(bounds.schema()).with_endpoint(vec![bounds]),
+        )
+        .on(
+            "*\"dt\" >= DATE '2024-01-01'*",
+            FakeResponse::new(dates(vec![]).schema())
+                .with_endpoint(vec![dates(vec![19723, 19724])]),
+        )
+        .on(
+            "*\"dt\" >= DATE '2024-01-03'*",
+            FakeResponse::new(dates(vec![]).schema()).with_endpoint(vec![dates(vec![19726])]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut clients = Vec::new();
+    for _ in 0..2 {
+        clients.push(
+            Client::new(&server.url(), "dremio", "dremio123")
+                .await
+                .unwrap(),
+        );
+    }
+
+    let exporter = ParallelExporter::new("SELECT * FROM events", "dt").with_shards(2);
+    assert_eq!(
+        exporter.shard_queries(&mut clients[0]).await.unwrap(),
+        [
+            "SELECT * FROM (\nSELECT * FROM events\n) WHERE (\"dt\" >= DATE '2024-01-01' AND \"dt\" < DATE '2024-01-03') OR \"dt\" IS NULL",
+            "SELECT * FROM (\nSELECT * FROM events\n) WHERE \"dt\" >= DATE '2024-01-03'",
+        ]
+    );
+
+    let dir = std::env::temp_dir().join(format!("dremio-rs-parallel-{}", std::process::id()));
+    let summary = exporter
+        .with_verification(dremio_rs::export::Verification::Checksums)
+        .export(&mut clients, &dir)
+        .await
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(summary.rows, 3);
+    let report = summary.verification.unwrap();
+    assert!(report.is_ok(), "{}", report);
+    assert_eq!((report.files, report.written_rows), (2, 3));
+    assert_eq!(
+        summary.files,
+        [dir.join("part-0000.parquet"), dir.join("part-0001.parquet")]
+    );
+}
+
+#[cfg(all(feature = "parquet", feature = "testing"))]
+#[tokio::test]
+async fn test_failed_parallel_export_leaves_no_files() {
+    use dremio_rs::export::ParallelExporter;
+    use dremio_rs::testing::{FakeFlightSqlServer, FakeResponse};
+    use dremio_rs::Client;
+
+    let bounds = {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("lo", DataType::Int32, true),
+            Field::new("hi", DataType::Int32, true),
+        ]));
+        let (lo, hi) = (Int32Array::from(vec![1]), Int32Array::from(vec![4]));
+        RecordBatch::try_new(schema, vec![Arc::new(lo), Arc::new(hi)]).unwrap()
+    };
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT MIN(\"v\")*",
+            FakeResponse::new(bounds.schema()).with_endpoint(vec![bounds]),
+        )
+        .on(
+            "*\"v\" >= 1*",
+            FakeResponse::new(batch(vec![]).schema()).with_endpoint(vec![batch(vec![1, 2])]),
+        )
+        // Fails once the first shard is complete, after writing a batch.
+        .on(
+            "*\"v\" >= 3*",
+            FakeResponse::new(batch(vec![]).schema())
+                .with_delay(std::time::Duration::from_millis(300))
+                .with_failing_endpoint(vec![batch(vec![3])], tonic::Status::internal("lost")),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut clients = Vec::new();
+    for _ in 0..2 {
+        clients.push(
+            Client::new(&server.url(), "dremio", "dremio123")
+                .await
+                .unwrap(),
+        );
+    }
+
+    let dir =
+        std::env::temp_dir().join(format!("dremio-rs-parallel-failed-{}", std::process::id()));
+    let result = ParallelExporter::new("SELECT * FROM events", "v")
+        .with_shards(2)
+        .export(&mut clients, &dir)
+        .await;
+    let left: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(result.is_err());
+    assert!(left.is_empty(), "{:?}", left);
+}
+
+#[cfg(all(feature = "parquet", feature = "testing"))]
+#[tokio::test]
+async fn test_incremental_sync() {
+    use dremio_rs::export::IncrementalSync;
+    use dremio_rs::testing::{FakeFlightSqlServer, FakeResponse};
+    use dremio_rs::Client;
+
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM events",
+            FakeResponse::new(batch(vec![]).schema()).with_endpoint(vec![batch(vec![3, 1, 2])]),
+        )
+        .on(
+            "*WHERE \"v\" > 3",
+            FakeResponse::new(batch(vec![]).schema()).with_endpoint(vec![]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    let dir = std::env::temp_dir().join(format!("dremio-rs-sync-{}", std::process::id()));
+    let sync = IncrementalSync::new("SELECT * FROM events", "v", dir.join("watermark"))
+        .with_verification(dremio_rs::export::Verification::RowCounts);
+
+    let first = sync.run(&mut client, &dir).await.unwrap();
+    assert_eq!(first.rows, 3);
+    assert!(first.verification.unwrap().is_ok());
+    assert_eq!(first.watermark.as_deref(), Some("3"));
+    assert!(first.file.unwrap().exists());
+    assert_eq!(
+        sync.next_query().unwrap(),
+        "SELECT * FROM (\nSELECT * FROM events\n) WHERE \"v\" > 3"
+    );
+
+    let second = sync.run(&mut client, &dir).await.unwrap();
+    assert_eq!(second.rows, 0);
+    assert_eq!(second.file, None);
+    assert_eq!(second.watermark.as_deref(), Some("3"));
+    assert_eq!(second.verification, None);
+
+    // Syncs sharing a directory never overwrite each other's files, even
+    // within the same millisecond.
+    let files: Vec<_> = futures::future::join_all((0..4).map(|i| {
+        let sync = IncrementalSync::new("SELECT * FROM events", "v", dir.join(format!("w{}", i)));
+        let mut client = client.clone();
+        let dir = dir.clone();
+        async move { sync.run(&mut client, &dir).await.unwrap().file.unwrap() }
+    }))
+    .await;
+    let parts = std::fs::read_dir(&dir)
+        .unwrap()
+        .filter(|entry| entry.as_ref().unwrap().path().extension() == Some("parquet".as_ref()))
+        .count();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(parts, 5);
+    assert_eq!(
+        files.iter().collect::<std::collections::HashSet<_>>().len(),
+        4
+    );
+}
+
+#[cfg(all(feature = "parquet", feature = "testing"))]
+#[tokio::test]
+async fn test_write_parquet_verified() {
+    use dremio_rs::export::Verification;
+    use dremio_rs::testing::{FakeFlightSqlServer, FakeResponse};
+    use dremio_rs::Client;
+    use parquet::basic::Compression;
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    let schema = batch(vec![]).schema();
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM events",
+            FakeResponse::new(schema.clone()).with_endpoint(vec![batch(vec![1, 2, 3])]),
+        )
+        .on("SELECT * FROM empty", FakeResponse::new(schema.clone()))
+        .on(
+            "SELECT * FROM broken",
+            FakeResponse::new(schema).with_failing_endpoint(
+                vec![batch(vec![1])],
+                tonic::Status::internal("lost executor"),
+            ),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    let dir = std::env::temp_dir().join(format!("dremio-rs-verified-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let report = client
+        .write_parquet_verified(
+            "SELECT * FROM events",
+            &path("events.parquet"),
+            Some(properties),
+            Verification::Checksums,
+        )
+        .await
+        .unwrap();
+    assert!(report.is_ok(), "{}", report);
+    assert_eq!((report.files, report.written_rows), (1, 3));
+    let reader =
+        SerializedFileReader::new(std::fs::File::open(path("events.parquet")).unwrap()).unwrap();
+    assert_eq!(
+        reader.metadata().row_group(0).column(0).compression(),
+        Compression::SNAPPY
+    );
+
+    // Empty results are written, and reported as such.
+    let report = client
+        .write_parquet_verified(
+            "SELECT * FROM empty",
+            &path("empty.parquet"),
+            None,
+            Verification::RowCounts,
+        )
+        .await
+        .unwrap();
+    assert!(report.is_ok() && report.is_empty(), "{}", report);
+    assert_eq!(report.files, 1);
+
+    // A failed export leaves no file behind.
+    assert!(client
+        .write_parquet_verified(
+            "SELECT * FROM broken",
+            &path("broken.parquet"),
+            None,
+            Verification::RowCounts,
+        )
+        .await
+        .is_err());
+    let mut names: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    names.sort();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(names, ["empty.parquet", "events.parquet"]);
+}
+
+#[cfg(feature = "parquet")]
+#[test]
+fn test_verification_without_files_fails() {
+    use dremio_rs::export::{Verification, Verifier};
+
+    let mut verifier = Verifier::new(Verification::RowCounts);
+    verifier.update(&batch(vec![])).unwrap();
+    let report = verifier.verify(std::iter::empty::<&str>()).unwrap();
+    assert!(!report.is_ok());
+    assert_eq!(report.to_string(), "0 rows exported, no file written");
+}
@@ -0,0 +1,61 @@
+use arrow::array::{AsArray, Date32Array, Int32Array, RecordBatch, StringArray};
+use arrow::datatypes::Int32Type;
+use dremio_rs::filter::Predicate;
+use std::sync::Arc;
+
+fn orders() -> RecordBatch {
+    RecordBatch::try_from_iter([
+        ("id", Arc::new(Int32Array::from(vec![1, 2, 3, 4, 5])) as _),
+        (
+            "region",
+            Arc::new(StringArray::from(vec![
+                Some("eu"),
+                Some("us"),
+                None,
+                Some("eu"),
+                Some("apac"),
+            ])) as _,
+        ),
+        (
+            // 2024-01-01 to 2024-01-05
+            "day",
+            Arc::new(Date32Array::from(vec![19723, 19724, 19725, 19726, 19727])) as _,
+        ),
+    ])
+    .unwrap()
+}
+
+fn ids(batch: &RecordBatch) -> Vec<i32> {
+    batch
+        .column(0)
+        .as_primitive::<Int32Type>()
+        .values()
+        .to_vec()
+}
+
+#[test]
+fn test_predicates() {
+    let batch = orders();
+    assert_eq!(
+        ids(&Predicate::eq("region", "eu").filter(&batch).unwrap()),
+        [1, 4]
+    );
+    // Comparisons with null never match, negated or not.
+    assert_eq!(
+        ids(&(!Predicate::eq("region", "eu")).filter(&batch).unwrap()),
+        [2, 5]
+    );
+    assert_eq!(
+        ids(&Predicate::is_null("region").filter(&batch).unwrap()),
+        [3]
+    );
+    assert_eq!(
+        ids(&Predicate::between("day", "2024-01-02", "2024-01-04")
+            .filter(&batch)
+            .unwrap()),
+        [2, 3, 4]
+    );
+    let predicate = Predicate::gt("id", 3).or(Predicate::eq("region", "us"));
+    assert_eq!(ids(&predicate.filter(&batch).unwrap()), [2, 4, 5]);
+    assert!(Predicate::eq("country", "fr").filter(&batch).is_err());
+}
@@ -0,0 +1,91 @@
+use arrow::array::{
+    Array, ArrayRef, AsArray, FixedSizeListArray, Int32Array, ListArray, RecordBatch, StringArray,
+    StructArray,
+};
+use arrow::buffer::NullBuffer;
+use arrow::datatypes::{DataType, Field, Fields, Int32Type, Schema};
+use dremio_rs::flatten::{flatten_batch, FlattenOptions, ListMode};
+use std::sync::Arc;
+
+/// A nullable struct column whose non-nullable child is only valid where the
+/// parent is, as Dremio sends it.
+fn addresses() -> RecordBatch {
+    let city = Arc::new(Field::new("city", DataType::Utf8, false));
+    let zip = Arc::new(Field::new("zip", DataType::Int32, true));
+    let address = StructArray::new(
+        Fields::from(vec![city, zip]),
+        vec![
+            Arc::new(StringArray::from(vec!["Berlin", ""])) as ArrayRef,
+            Arc::new(Int32Array::from(vec![Some(10115), None])),
+        ],
+        Some(NullBuffer::from(vec![true, false])),
+    );
+    let schema = Schema::new(vec![Field::new(
+        "address",
+        address.data_type().clone(),
+        true,
+    )]);
+    RecordBatch::try_new(Arc::new(schema), vec![Arc::new(address)]).unwrap()
+}
+
+#[test]
+fn test_flatten_struct_with_null_parent() {
+    let batch = flatten_batch(&addresses(), &FlattenOptions::new().with_separator("_")).unwrap();
+    let schema = batch.schema();
+    let names: Vec<_> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+    assert_eq!(names, ["address_city", "address_zip"]);
+    assert!(schema.field(0).is_nullable());
+    let city = batch.column(0).as_string::<i32>();
+    assert_eq!(city.value(0), "Berlin");
+    assert!(city.is_null(1));
+}
+
+#[test]
+fn test_flatten_lists() {
+    let tags =
+        ListArray::from_iter_primitive::<Int32Type, _, _>(vec![Some(vec![Some(1), Some(2)]), None]);
+    let batch = RecordBatch::try_from_iter([
+        ("id", Arc::new(Int32Array::from(vec![1, 2])) as ArrayRef),
+        ("tags", Arc::new(tags) as ArrayRef),
+    ])
+    .unwrap();
+
+    let json = flatten_batch(&batch, &FlattenOptions::new()).unwrap();
+    let tags = json.column(1).as_string::<i32>();
+    assert_eq!(tags.value(0), "[1,2]");
+    assert!(tags.is_null(1));
+
+    let kept = flatten_batch(
+        &batch,
+        &FlattenOptions::new().with_list_mode(ListMode::Keep),
+    );
+    assert_eq!(kept.unwrap().schema(), batch.schema());
+
+    let exploded = flatten_batch(
+        &batch,
+        &FlattenOptions::new().with_list_mode(ListMode::Explode),
+    )
+    .unwrap();
+    let ids = exploded.column(0).as_primitive::<Int32Type>();
+    let tags = exploded.column(1).as_primitive::<Int32Type>();
+    assert_eq!(ids.values(), &[1, 1, 2]);
+    assert_eq!(tags.iter().collect::<Vec<_>>(), [Some(1), Some(2), None]);
+}
+
+#[test]
+fn test_explode_fixed_size_lists() {
+    let values = Arc::new(Int32Array::from(vec![1, 2, 3, 4]));
+    let item = Arc::new(Field::new("item", DataType::Int32, false));
+    let points =
+        FixedSizeListArray::new(item, 2, values, Some(NullBuffer::from(vec![true, false])));
+    let batch = RecordBatch::try_from_iter([("point", Arc::new(points) as ArrayRef)]).unwrap();
+
+    let exploded = flatten_batch(
+        &batch,
+        &FlattenOptions::new().with_list_mode(ListMode::Explode),
+    )
+    .unwrap();
+    assert_eq!(exploded.schema().field(0).data_type(), &DataType::Int32);
+    let points = exploded.column(0).as_primitive::<Int32Type>();
+    assert_eq!(points.iter().collect::<Vec<_>>(), [Some(1), Some(2), None]);
+}
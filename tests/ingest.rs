@@ -0,0 +1,103 @@
+#![cfg(feature = "testing")]
+
+use arrow::array::{Int32Array, RecordBatch};
+use arrow::datatypes::{DataType, Field, Schema};
+use dremio_rs::ingest::{IngestMode, IngestOptions};
+use dremio_rs::testing::FakeFlightSqlServer;
+use dremio_rs::{Client, DremioClientError, QueryPhase};
+use futures::stream;
+use std::sync::Arc;
+
+fn batch(values: Vec<i32>) -> RecordBatch {
+    let schema = Arc::new(Schema::new(vec![Field::new("v", DataType::Int32, false)]));
+    RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(values))]).unwrap()
+}
+
+fn rows(batches: Option<Vec<RecordBatch>>) -> usize {
+    batches.unwrap().iter().map(RecordBatch::num_rows).sum()
+}
+
+#[tokio::test]
+async fn test_ingest_modes() {
+    let server = FakeFlightSqlServer::new()
+        .with_table("lake.orders", vec![batch(vec![0])])
+        .start()
+        .await
+        .unwrap();
+    let client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    let batches = || stream::iter([batch(vec![1, 2]), batch(vec![3])]);
+
+    // Creating a table fails if it exists.
+    let create = IngestOptions::new();
+    let e = client
+        .ingest(["lake", "orders"], batches(), &create)
+        .await
+        .unwrap_err();
+    assert_eq!(e.phase(), Some(QueryPhase::Execute));
+    assert_eq!(e.query(), Some("\"lake\".\"orders\""));
+    assert!(
+        matches!(
+            e.root(),
+            DremioClientError::QueryFailed {
+                code: tonic::Code::AlreadyExists,
+                ..
+            }
+        ),
+        "{}",
+        e
+    );
+    let written = client
+        .ingest(["lake", "returns"], batches(), &create)
+        .await
+        .unwrap();
+    assert_eq!(written, 3);
+    assert_eq!(rows(server.table("lake.returns")), 3);
+
+    let append = IngestOptions::new().with_mode(IngestMode::Append);
+    client
+        .ingest(["lake", "orders"], batches(), &append)
+        .await
+        .unwrap();
+    assert_eq!(rows(server.table("lake.orders")), 4);
+    let replace = IngestOptions::new().with_mode(IngestMode::Replace);
+    client
+        .ingest(["lake", "orders"], batches(), &replace)
+        .await
+        .unwrap();
+    assert_eq!(rows(server.table("lake.orders")), 3);
+
+    // Nothing is sent without batches, and the path must name a table.
+    let written = client
+        .ingest(["lake", "empty"], stream::empty(), &create)
+        .await
+        .unwrap();
+    assert_eq!(written, 0);
+    assert!(server.table("lake.empty").is_none());
+    let empty: [&str; 0] = [];
+    assert!(client.ingest(empty, batches(), &create).await.is_err());
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_ingest_quotes_schema_path() {
+    let server = FakeFlightSqlServer::new().start().await.unwrap();
+    let client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+
+    // A dot inside a folder name does not split it into two folders.
+    let written = client
+        .ingest(
+            ["lake", "2024.q1", "orders"],
+            stream::iter([batch(vec![1, 2])]),
+            &IngestOptions::new(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(written, 2);
+    assert_eq!(rows(server.table("lake.\"2024.q1\".orders")), 2);
+    assert!(server.table("lake.2024.q1.orders").is_none());
+    server.shutdown().await;
+}
@@ -0,0 +1,85 @@
+use arrow::array::{Array, AsArray, Int64Array, RecordBatch, StringArray};
+use arrow::datatypes::DataType;
+use dremio_rs::mask::{MaskRule, Masking};
+use std::sync::Arc;
+
+fn customers() -> RecordBatch {
+    RecordBatch::try_from_iter([
+        ("id", Arc::new(Int64Array::from(vec![1, 2, 3])) as _),
+        (
+            "email",
+            Arc::new(StringArray::from(vec![
+                Some("ada@example.com"),
+                Some("ada@example.com"),
+                None,
+            ])) as _,
+        ),
+        (
+            "name",
+            Arc::new(StringArray::from(vec!["Ada", "Grace", "Linus"])) as _,
+        ),
+        (
+            "phone",
+            Arc::new(StringArray::from(vec!["555-0100", "555-0101", "555-0102"])) as _,
+        ),
+    ])
+    .unwrap()
+}
+
+#[test]
+fn test_masking_rules() {
+    let masking = Masking::new()
+        .with_salt("pepper")
+        .with_rule("id", MaskRule::Hash)
+        .with_rule("email", MaskRule::Hash)
+        .with_rule("name", "truncate:1".parse().unwrap())
+        .with_rule("phone", MaskRule::Redact);
+    let masked = masking.apply(customers()).unwrap();
+
+    let ids = masked.column(0).as_string::<i32>();
+    assert_eq!(ids.value(0).len(), 16);
+    assert_ne!(ids.value(0), ids.value(1));
+    let emails = masked.column(1).as_string::<i32>();
+    assert_eq!(emails.value(0), emails.value(1));
+    assert!(emails.is_null(2));
+    let salted = Masking::new()
+        .with_rule("email", MaskRule::Hash)
+        .apply(customers())
+        .unwrap();
+    assert_ne!(
+        salted.column(1).as_string::<i32>().value(0),
+        emails.value(0)
+    );
+
+    let names = masked.column(2).as_string::<i32>();
+    assert_eq!(
+        names.iter().collect::<Vec<_>>(),
+        [Some("A"), Some("G"), Some("L")]
+    );
+    assert_eq!(masked.schema().field(3).data_type(), &DataType::Utf8);
+    assert_eq!(masked.column(3).null_count(), 3);
+
+    let constant = Masking::new()
+        .with_rule("email", "constant:hidden".parse().unwrap())
+        .apply(customers())
+        .unwrap();
+    let emails = constant.column(1).as_string::<i32>();
+    assert_eq!(
+        emails.iter().collect::<Vec<_>>(),
+        [Some("hidden"), Some("hidden"), None]
+    );
+}
+
+#[test]
+fn test_masking_rejects_unknown_columns() {
+    let err = Masking::new()
+        .with_rule("ssn", MaskRule::Redact)
+        .apply(customers())
+        .unwrap_err();
+    assert!(err.to_string().contains("\"ssn\""), "{}", err);
+    assert!(Masking::new()
+        .with_rule("id", MaskRule::Truncate(2))
+        .apply(customers())
+        .is_err());
+    assert!("scramble".parse::<MaskRule>().is_err());
+}
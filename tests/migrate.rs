@@ -0,0 +1,96 @@
+#![cfg(feature = "testing")]
+
+use arrow::array::{Int64Array, RecordBatch, StringArray};
+use dremio_rs::migrate::{Migration, Migrator};
+use dremio_rs::testing::MockClient;
+use std::sync::Arc;
+
+fn applied(versions: Vec<i64>, checksums: Vec<String>) -> RecordBatch {
+    RecordBatch::try_from_iter([
+        ("version", Arc::new(Int64Array::from(versions)) as _),
+        ("checksum", Arc::new(StringArray::from(checksums)) as _),
+    ])
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_migrations() {
+    let first = Migration::new(1, "create_space", "CREATE SPACE sales");
+    let second = Migration::new(
+        2,
+        "views",
+        "-- views for the sales team\nCREATE VIEW sales.a AS SELECT ';' AS s;\n\
+         /* second; view */ CREATE VIEW sales.b AS SELECT 1;\n-- trailing comment\n",
+    );
+    assert_eq!(
+        second.statements(),
+        [
+            "-- views for the sales team\nCREATE VIEW sales.a AS SELECT ';' AS s",
+            "/* second; view */ CREATE VIEW sales.b AS SELECT 1",
+        ]
+    );
+    let migrator = Migrator::new("lake.meta.migrations")
+        .unwrap()
+        .with_migration(second.clone())
+        .unwrap()
+        .with_migration(first.clone())
+        .unwrap();
+    assert!(migrator.clone().with_migration(first.clone()).is_err());
+
+    let tracked = applied(vec![1], vec![first.checksum()]);
+    let mut mock = MockClient::new()
+        .on("SELECT version, checksum FROM *", vec![tracked.clone()])
+        .on("*", vec![]);
+    let report = migrator
+        .clone()
+        .with_dry_run(true)
+        .run(&mut mock)
+        .await
+        .unwrap();
+    assert_eq!(report.already_applied, [1]);
+    assert_eq!(report.migrations[0].version, 2);
+    assert_eq!(mock.executed().len(), 1);
+
+    mock.clear_executed();
+    let report = migrator.run(&mut mock).await.unwrap();
+    assert_eq!(report.migrations.len(), 1);
+    assert_eq!(mock.executed().len(), 5);
+    mock.assert_executed("CREATE TABLE IF NOT EXISTS \"lake\".\"meta\".\"migrations\" *");
+    mock.assert_executed("/* second; view */ CREATE VIEW sales.b AS SELECT 1");
+    mock.assert_executed(&format!(
+        "INSERT INTO * VALUES (2, 'views', '{}', CURRENT_TIMESTAMP)",
+        second.checksum()
+    ));
+
+    let edited = Migration::new(1, "create_space", "CREATE SPACE marketing");
+    let migrator = Migrator::new("lake.meta.migrations")
+        .unwrap()
+        .with_migration(edited)
+        .unwrap();
+    let error = migrator.run(&mut mock).await.unwrap_err();
+    assert!(error.to_string().contains("modified after it was applied"));
+}
+
+#[test]
+fn test_migration_files() {
+    let dir = std::env::temp_dir().join(format!("dremio-rs-migrations-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("V3__create_space.sql"), "CREATE SPACE a").unwrap();
+    std::fs::write(
+        dir.join("0004_grants.sql"),
+        "GRANT SELECT ON SPACE a TO ROLE r",
+    )
+    .unwrap();
+    std::fs::write(dir.join("README.md"), "notes").unwrap();
+    let migrator = Migrator::new("lake.migrations")
+        .unwrap()
+        .with_dir(&dir)
+        .unwrap();
+    let names: Vec<String> = migrator.migrations().map(ToString::to_string).collect();
+    assert_eq!(names, ["V3 create_space", "V4 grants"]);
+
+    std::fs::write(dir.join("grants.sql"), "").unwrap();
+    let result = Migrator::new("lake.migrations").unwrap().with_dir(&dir);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(result.is_err());
+}
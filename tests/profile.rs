@@ -0,0 +1,110 @@
+use arrow::array::{Float64Array, Int64Array, RecordBatch, StringArray};
+use arrow::datatypes::DataType;
+use dremio_rs::profile::{ProfileOptions, Profiler, ValueCount};
+use std::sync::Arc;
+
+fn batch(start: i64, end: i64) -> RecordBatch {
+    let ids: Vec<i64> = (start..end).collect();
+    let amounts: Vec<Option<f64>> = ids
+        .iter()
+        .map(|id| (id % 10 != 0).then_some((id % 100) as f64))
+        .collect();
+    let regions: Vec<&str> = ids
+        .iter()
+        .map(|id| ["eu", "us", "apac"][(id % 3) as usize])
+        .collect();
+    RecordBatch::try_from_iter([
+        ("id", Arc::new(Int64Array::from(ids)) as _),
+        ("amount", Arc::new(Float64Array::from(amounts)) as _),
+        ("region", Arc::new(StringArray::from(regions)) as _),
+    ])
+    .unwrap()
+}
+
+#[test]
+fn test_profiler() {
+    let mut profiler = Profiler::new();
+    for start in (0..20_000).step_by(5_000) {
+        profiler.update(&batch(start, start + 5_000)).unwrap();
+    }
+    let summary = profiler.finish();
+    assert_eq!(summary.rows, 20_000);
+
+    let id = summary.column("id").unwrap();
+    assert_eq!((id.count, id.nulls), (20_000, 0));
+    let distinct = id.distinct.unwrap() as f64;
+    assert!(
+        (distinct - 20_000.0).abs() / 20_000.0 < 0.05,
+        "{}",
+        distinct
+    );
+    assert_eq!(id.min.as_deref(), Some("0"));
+    assert_eq!(id.max.as_deref(), Some("19999"));
+    assert_eq!(id.mean, Some(9_999.5));
+
+    let amount = summary.column("amount").unwrap();
+    assert_eq!((amount.count, amount.nulls), (18_000, 2_000));
+    assert!((88..=92).contains(&amount.distinct.unwrap()));
+    assert_eq!(amount.max.as_deref(), Some("99.0"));
+
+    let region = summary.column("region").unwrap();
+    assert_eq!(region.data_type, DataType::Utf8);
+    assert_eq!(region.distinct, Some(3));
+    assert_eq!(region.min.as_deref(), Some("apac"));
+    assert_eq!(region.mean, None);
+
+    let text = summary.to_string();
+    assert!(text.starts_with("20000 rows\ncolumn"), "{}", text);
+}
+
+#[test]
+fn test_histograms_and_top_values() {
+    let options = ProfileOptions::new()
+        .with_histogram_bins(10)
+        .with_top_values(2);
+    let mut profiler = Profiler::with_options(options);
+    // The range of ids grows with every batch.
+    for start in (0..20_000).step_by(5_000) {
+        profiler.update(&batch(start, start + 5_000)).unwrap();
+    }
+    let summary = profiler.finish();
+
+    let id = summary.column("id").unwrap();
+    let histogram = id.histogram.as_ref().unwrap();
+    assert_eq!(histogram.bins.len(), 10);
+    assert_eq!(
+        histogram.bins.iter().map(|bin| bin.count).sum::<u64>(),
+        20_000
+    );
+    assert!(histogram.bins[0].lower <= 0.0);
+    assert!(histogram.bins[9].upper >= 19_999.0);
+
+    let amount = summary.column("amount").unwrap();
+    let counts: u64 = amount
+        .histogram
+        .as_ref()
+        .unwrap()
+        .bins
+        .iter()
+        .map(|bin| bin.count)
+        .sum();
+    assert_eq!(counts, 18_000);
+
+    let region = summary.column("region").unwrap();
+    assert_eq!(region.histogram, None);
+    assert_eq!(
+        region.top_values,
+        [
+            ValueCount {
+                value: "eu".to_string(),
+                count: 6_667,
+                error: 0
+            },
+            ValueCount {
+                value: "us".to_string(),
+                count: 6_667,
+                error: 0
+            },
+        ]
+    );
+}
@@ -0,0 +1,33 @@
+use arrow::array::{Float64Array, RecordBatch, StringArray};
+use dremio_rs::render::{to_html, to_markdown};
+use std::sync::Arc;
+
+fn batch(names: Vec<Option<&str>>, amounts: Vec<f64>) -> RecordBatch {
+    RecordBatch::try_from_iter([
+        ("name", Arc::new(StringArray::from(names)) as _),
+        ("amount", Arc::new(Float64Array::from(amounts)) as _),
+    ])
+    .unwrap()
+}
+
+#[test]
+fn test_render_tables() {
+    let batches = [
+        batch(vec![Some("line\nbreak"), None], vec![1.5, 2.0]),
+        batch(vec![Some("R&D")], vec![3.0]),
+    ];
+    assert_eq!(
+        to_markdown(&batches, 10).unwrap(),
+        "| name | amount |\n| --- | ---: |\n| line<br>break | 1.5 |\n|  | 2.0 |\n| R&D | 3.0 |\n"
+    );
+
+    let html = to_html(&batches, 2).unwrap();
+    assert!(html.starts_with(
+        "<table>\n<thead>\n<tr><th>name</th><th style=\"text-align: right\">amount</th></tr>"
+    ));
+    assert!(html.contains("<tr><td></td><td style=\"text-align: right\">2.0</td></tr>"));
+    assert!(!html.contains("R&amp;D"));
+    assert!(html.ends_with("</table>\n<p><em>Showing the first 2 rows.</em></p>\n"));
+
+    assert_eq!(to_markdown(&[], 10).unwrap(), "");
+}
@@ -0,0 +1,540 @@
+#![cfg(all(feature = "rest", feature = "testing"))]
+
+use arrow::array::AsArray;
+use arrow::datatypes::{DataType, Int32Type};
+use dremio_rs::rest::{JobState, Lineage, LineageNode, PrivilegeEntry, RestClient, SearchHit};
+use dremio_rs::testing::FakeFlightSqlServer;
+use dremio_rs::{Client, DremioClientError};
+use std::time::Duration;
+
+mod common;
+
+use common::rest_server;
+
+#[tokio::test]
+async fn test_client_execute_via_rest() {
+    let server = FakeFlightSqlServer::new().start().await.unwrap();
+    let rest = rest_server(&[
+        ("POST /apiv2/login", r#"{"token":"t"}"#),
+        ("POST /api/v3/sql", r#"{"id":"j1"}"#),
+        (
+            "GET /api/v3/job/j1",
+            r#"{"jobState":"COMPLETED","rowCount":3}"#,
+        ),
+        (
+            "GET /api/v3/job/j1/results?offset=0&limit=500",
+            r#"{"rowCount":3,"schema":[{"name":"v","type":{"name":"INTEGER"}}],
+                "rows":[{"v":1},{"v":2}]}"#,
+        ),
+        (
+            "GET /api/v3/job/j1/results?offset=2&limit=500",
+            r#"{"rowCount":3,"schema":[{"name":"v","type":{"name":"INTEGER"}}],
+                "rows":[{"v":null}]}"#,
+        ),
+    ])
+    .await;
+    let client = Client::builder(server.url())
+        .with_credentials("dremio", "dremio123")
+        .with_rest_url(&rest.url)
+        .connect()
+        .await
+        .unwrap();
+
+    let batches = client.execute_via_rest("SELECT v FROM t").await.unwrap();
+    assert_eq!(batches.len(), 2);
+    assert_eq!(batches[0].schema().field(0).data_type(), &DataType::Int32);
+    let values: Vec<_> = batches
+        .iter()
+        .flat_map(|b| {
+            b.column(0)
+                .as_primitive::<Int32Type>()
+                .iter()
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    assert_eq!(values, [Some(1), Some(2), None]);
+    assert_eq!(
+        rest.requests(),
+        [
+            "POST /apiv2/login",
+            "POST /api/v3/sql",
+            "GET /api/v3/job/j1",
+            "GET /api/v3/job/j1/results?offset=0&limit=500",
+            "GET /api/v3/job/j1/results?offset=2&limit=500",
+        ]
+    );
+    // The query never went through Flight SQL.
+    assert!(server.queries().is_empty());
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_wait_for_job() {
+    let server = rest_server(&[
+        ("GET /api/v3/job/j1", r#"{"jobState":"ENQUEUED"}"#),
+        ("GET /api/v3/job/j1", r#"{"jobState":"RUNNING"}"#),
+        (
+            "GET /api/v3/job/j1",
+            r#"{"jobState":"FAILED","errorMessage":"out of memory"}"#,
+        ),
+        ("GET /api/v3/job/j2", r#"{"jobState":"RUNNING"}"#),
+    ])
+    .await;
+    let rest = RestClient::with_token(&server.url, "pat");
+    let jobs = rest.jobs().with_poll_interval(Duration::from_millis(10));
+
+    // Failed jobs are returned, not turned into errors.
+    let job = jobs.wait("j1", Duration::from_secs(10)).await.unwrap();
+    assert_eq!(job.job_state, JobState::Failed);
+    assert_eq!(job.error_message.as_deref(), Some("out of memory"));
+    assert_eq!(server.requests().len(), 3);
+
+    let e = jobs
+        .wait("j2", Duration::from_millis(100))
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(e, DremioClientError::TimeoutError(timeout) if timeout == Duration::from_millis(100)),
+        "{}",
+        e
+    );
+    let polls = server.requests().len() - 3;
+    assert!((2..=11).contains(&polls), "{} polls", polls);
+}
+
+#[tokio::test]
+async fn test_search() {
+    let long_wiki = "a ".repeat(150);
+    let first_page = serde_json::json!({
+        "results": [
+            {
+                "category": "TABLE",
+                "catalogObject": {
+                    "path": ["lake", "orders"],
+                    "type": "TABLE",
+                    "labels": ["sales"],
+                    "wiki": "Orders placed  online.\nOne row per order.\n\nLoaded nightly.",
+                },
+            },
+            { "category": "JOB" },
+            {
+                "category": "VIEW",
+                "catalogObject": { "path": ["marts", "revenue"], "wiki": long_wiki },
+            },
+        ],
+        "nextPageToken": "p2",
+    })
+    .to_string();
+    let api = rest_server(&[
+        ("POST /api/v3/search", &first_page),
+        (
+            "POST /api/v3/search",
+            r#"{"results":[{"category":"FOLDER","catalogObject":{"path":["lake"],"wiki":" \n "}}],
+                "nextPageToken":""}"#,
+        ),
+    ])
+    .await;
+    let rest = RestClient::with_token(&api.url, "pat");
+
+    let hits = rest.search("orders").await.unwrap();
+    let hit = |path: &[&str], object_type: &str, tags: &[&str], wiki: Option<String>| SearchHit {
+        path: path.iter().map(|p| p.to_string()).collect(),
+        object_type: object_type.to_string(),
+        tags: tags.iter().map(|t| t.to_string()).collect(),
+        wiki_snippet: wiki,
+    };
+    let mut truncated = vec!["a"; 100];
+    truncated.push("…");
+    assert_eq!(
+        hits,
+        [
+            hit(
+                &["lake", "orders"],
+                "TABLE",
+                &["sales"],
+                Some("Orders placed online. One row per order.".to_string())
+            ),
+            // Without a type, the category of the result is used.
+            hit(
+                &["marts", "revenue"],
+                "VIEW",
+                &[],
+                Some(truncated.join(" "))
+            ),
+            hit(&["lake"], "FOLDER", &[], None),
+        ]
+    );
+    assert_eq!(
+        api.requests(),
+        ["POST /api/v3/search", "POST /api/v3/search"]
+    );
+    let bodies: Vec<serde_json::Value> = api
+        .bodies()
+        .iter()
+        .map(|body| serde_json::from_str(body).unwrap())
+        .collect();
+    assert_eq!(bodies[0]["query"], "orders");
+    assert!(bodies[0]["filter"]
+        .as_str()
+        .unwrap()
+        .starts_with("category in"));
+    assert!(bodies[0].get("pageToken").is_none());
+    assert_eq!(bodies[1]["pageToken"], "p2");
+}
+
+#[tokio::test]
+async fn test_lineage() {
+    let api = rest_server(&[
+        (
+            "GET /api/v3/catalog/by-path/lake/my%20folder/orders.v2",
+            r#"{"id":"d1","entityType":"dataset"}"#,
+        ),
+        (
+            "GET /api/v3/catalog/d1/graph",
+            r#"{"parents":[{"id":"s1","path":["lake","raw"],"datasetType":"PROMOTED"}],
+                "children":[{"id":"v1","path":["marts","revenue"],"datasetType":"VIRTUAL"},
+                            {"id":"v2"}]}"#,
+        ),
+    ])
+    .await;
+    let rest = RestClient::with_token(&api.url, "pat");
+
+    let lineage = rest
+        .lineage(&["lake", "my folder", "orders.v2"])
+        .await
+        .unwrap();
+    let node = |id: &str, path: &[&str], dataset_type: Option<&str>| LineageNode {
+        id: id.to_string(),
+        path: path.iter().map(|p| p.to_string()).collect(),
+        dataset_type: dataset_type.map(str::to_string),
+    };
+    assert_eq!(
+        lineage,
+        Lineage {
+            parents: vec![node("s1", &["lake", "raw"], Some("PROMOTED"))],
+            children: vec![
+                node("v1", &["marts", "revenue"], Some("VIRTUAL")),
+                node("v2", &[], None),
+            ],
+        }
+    );
+    assert_eq!(
+        api.requests(),
+        [
+            "GET /api/v3/catalog/by-path/lake/my%20folder/orders.v2",
+            "GET /api/v3/catalog/d1/graph",
+        ]
+    );
+
+    // A path component with a slash stays one component.
+    let e = rest.lineage(&["lake", "a/b"]).await.unwrap_err();
+    assert!(
+        matches!(e, DremioClientError::RestError { status: 404, .. }),
+        "{}",
+        e
+    );
+    assert_eq!(api.requests()[2], "GET /api/v3/catalog/by-path/lake/a%2Fb");
+}
+
+#[tokio::test]
+async fn test_recommend_reflections() {
+    let recommendations = r#"{"data":[
+        {"type":"AGGREGATION",
+         "dimensionFields":[{"name":"region"},{"name":"day","granularity":"DATE"}],
+         "measureFields":[{"name":"amount","measureTypeList":["SUM"]},{}],
+         "partitionFields":[{"name":"day"}]},
+        {"type":"RAW","displayFields":[{"name":"id"},{"name":"amount"}],"sortFields":[{"name":"id"}]}
+    ]}"#;
+    let api = rest_server(&[
+        ("GET /api/v3/catalog/by-path/lake/orders", r#"{"id":"d1"}"#),
+        (
+            "POST /api/v3/dataset/d1/reflection/recommendation",
+            recommendations,
+        ),
+        ("GET /api/v3/catalog/by-path/lake/returns", r#"{"id":"d2"}"#),
+    ])
+    .await;
+    let rest = RestClient::with_token(&api.url, "pat");
+
+    let found = rest
+        .recommend_reflections(&["lake", "orders"])
+        .await
+        .unwrap();
+    let names = |names: &[&str]| -> Vec<String> { names.iter().map(|n| n.to_string()).collect() };
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0].reflection_type, "AGGREGATION");
+    assert_eq!(found[0].dimension_fields, names(&["region", "day"]));
+    // Fields without a name are skipped.
+    assert_eq!(found[0].measure_fields, names(&["amount"]));
+    assert_eq!(found[0].partition_fields, names(&["day"]));
+    assert!(found[0].display_fields.is_empty());
+    assert_eq!(
+        found[0].definition["measureFields"][0]["measureTypeList"][0],
+        "SUM"
+    );
+    assert_eq!(found[1].reflection_type, "RAW");
+    assert_eq!(found[1].display_fields, names(&["id", "amount"]));
+    assert_eq!(found[1].sort_fields, names(&["id"]));
+
+    // Versions without recommendations answer with an error.
+    let e = rest
+        .recommend_reflections(&["lake", "returns"])
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(&e, DremioClientError::RestError { status: 404, message } if message == "not found"),
+        "{}",
+        e
+    );
+    assert_eq!(
+        api.requests(),
+        [
+            "GET /api/v3/catalog/by-path/lake/orders",
+            "POST /api/v3/dataset/d1/reflection/recommendation",
+            "GET /api/v3/catalog/by-path/lake/returns",
+            "POST /api/v3/dataset/d2/reflection/recommendation",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_privileges() {
+    let api = rest_server(&[
+        ("GET /api/v3/catalog/by-path/marketing", r#"{"id":"s1"}"#),
+        (
+            "GET /api/v3/catalog/s1?maxChildren=1000",
+            r#"{"path":["marketing"],"entityType":"space","children":[{"id":"f1"}],
+                "nextPageToken":"n2"}"#,
+        ),
+        (
+            "GET /api/v3/catalog/s1?maxChildren=1000&pageToken=n2",
+            r#"{"path":["marketing"],"entityType":"space","children":[{"id":"v1"}]}"#,
+        ),
+        (
+            "GET /api/v3/catalog/s1/grants",
+            r#"{"grants":[{"name":"analysts","granteeType":"ROLE","privileges":["SELECT"]},
+                          {"name":"alice","granteeType":"USER","privileges":["ALTER","SELECT"]}]}"#,
+        ),
+        (
+            "GET /api/v3/catalog/f1?maxChildren=1000",
+            r#"{"path":["marketing","campaigns"],"entityType":"folder","children":[{"id":"v2"}]}"#,
+        ),
+        (
+            "GET /api/v3/catalog/f1/grants",
+            r#"{"grants":[{"name":"analysts","granteeType":"ROLE","privileges":["SELECT"]}]}"#,
+        ),
+        (
+            "GET /api/v3/catalog/v1?maxChildren=1000",
+            r#"{"path":["marketing","summary.v1"],"entityType":"dataset"}"#,
+        ),
+        ("GET /api/v3/catalog/v1/grants", r#"{"grants":[]}"#),
+        (
+            "GET /api/v3/catalog/v2?maxChildren=1000",
+            r#"{"path":["marketing","campaigns","clicks"],"entityType":"dataset"}"#,
+        ),
+        (
+            "GET /api/v3/catalog/v2/grants",
+            r#"{"grants":[{"name":"bob","granteeType":"USER","privileges":["SELECT"]}]}"#,
+        ),
+    ])
+    .await;
+    let rest = RestClient::with_token(&api.url, "pat");
+
+    let report = rest.privileges(&["marketing"]).await.unwrap();
+    let entry = |path: &[&str],
+                 object_type: &str,
+                 grantee: &str,
+                 grantee_type: &str,
+                 privileges: &[&str]| {
+        PrivilegeEntry {
+            path: path.iter().map(|p| p.to_string()).collect(),
+            object_type: object_type.to_string(),
+            grantee: grantee.to_string(),
+            grantee_type: grantee_type.to_string(),
+            privileges: privileges.iter().map(|p| p.to_string()).collect(),
+        }
+    };
+    assert_eq!(
+        report.entries,
+        [
+            entry(&["marketing"], "space", "analysts", "ROLE", &["SELECT"]),
+            entry(
+                &["marketing"],
+                "space",
+                "alice",
+                "USER",
+                &["ALTER", "SELECT"]
+            ),
+            entry(
+                &["marketing", "campaigns"],
+                "folder",
+                "analysts",
+                "ROLE",
+                &["SELECT"]
+            ),
+            entry(
+                &["marketing", "campaigns", "clicks"],
+                "dataset",
+                "bob",
+                "USER",
+                &["SELECT"]
+            ),
+        ]
+    );
+    assert_eq!(report.for_grantee("analysts").count(), 2);
+    // Objects are visited breadth-first, children from every page.
+    assert_eq!(
+        api.requests(),
+        [
+            "GET /api/v3/catalog/by-path/marketing",
+            "GET /api/v3/catalog/s1?maxChildren=1000",
+            "GET /api/v3/catalog/s1?maxChildren=1000&pageToken=n2",
+            "GET /api/v3/catalog/s1/grants",
+            "GET /api/v3/catalog/f1?maxChildren=1000",
+            "GET /api/v3/catalog/f1/grants",
+            "GET /api/v3/catalog/v1?maxChildren=1000",
+            "GET /api/v3/catalog/v1/grants",
+            "GET /api/v3/catalog/v2?maxChildren=1000",
+            "GET /api/v3/catalog/v2/grants",
+        ]
+    );
+
+    let batch = report.to_record_batch().unwrap();
+    let names: Vec<_> = batch
+        .schema()
+        .fields()
+        .iter()
+        .map(|f| f.name().clone())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "path",
+            "object_type",
+            "grantee",
+            "grantee_type",
+            "privileges"
+        ]
+    );
+    assert_eq!(batch.num_rows(), 4);
+    assert_eq!(
+        batch.column(0).as_string::<i32>().value(3),
+        r#""marketing"."campaigns"."clicks""#
+    );
+    assert_eq!(batch.column(2).as_string::<i32>().value(1), "alice");
+    let privileges = batch.column(4).as_list::<i32>().value(1);
+    let privileges: Vec<_> = privileges.as_string::<i32>().iter().flatten().collect();
+    assert_eq!(privileges, ["ALTER", "SELECT"]);
+}
+
+#[tokio::test]
+async fn test_catalog_operations() {
+    let view = r#"{"id":"v1","tag":"t1","entityType":"dataset","path":["marts","revenue"]}"#;
+    let api = rest_server(&[
+        (
+            "GET /api/v3/catalog/by-path/marts/my%20views/revenue.v2",
+            view,
+        ),
+        ("GET /api/v3/catalog/v1", view),
+        ("POST /api/v3/catalog", view),
+        ("PUT /api/v3/catalog/v1", r#"{"id":"v1","tag":"t2"}"#),
+        ("DELETE /api/v3/catalog/v1?tag=t%2B2", ""),
+        ("DELETE /api/v3/catalog/v2", ""),
+        ("GET /api/v3/catalog/by-path/lake/orders", r#"{"id":"d1"}"#),
+        ("POST /api/v3/catalog/d1/refresh", ""),
+        ("POST /api/v3/reflection/r1/refresh", ""),
+    ])
+    .await;
+    let rest = RestClient::with_token(&api.url, "pat");
+    let catalog = rest.catalog();
+
+    let entity = catalog
+        .get(&["marts", "my views", "revenue.v2"])
+        .await
+        .unwrap();
+    assert_eq!(entity["tag"], "t1");
+    assert_eq!(catalog.get_by_id("v1").await.unwrap()["id"], "v1");
+    let created = catalog
+        .create_view(&["marts", "revenue"], "SELECT 1")
+        .await
+        .unwrap();
+    assert_eq!(created["id"], "v1");
+    let updated = catalog
+        .update("v1", &serde_json::json!({ "tag": "t1", "sql": "SELECT 2" }))
+        .await
+        .unwrap();
+    assert_eq!(updated["tag"], "t2");
+    catalog.delete("v1", Some("t+2")).await.unwrap();
+    catalog.delete("v2", None).await.unwrap();
+    rest.refresh_reflections(&["lake", "orders"]).await.unwrap();
+    rest.refresh_reflection("r1").await.unwrap();
+
+    assert_eq!(
+        api.requests(),
+        [
+            "GET /api/v3/catalog/by-path/marts/my%20views/revenue.v2",
+            "GET /api/v3/catalog/v1",
+            "POST /api/v3/catalog",
+            "PUT /api/v3/catalog/v1",
+            "DELETE /api/v3/catalog/v1?tag=t%2B2",
+            "DELETE /api/v3/catalog/v2",
+            "GET /api/v3/catalog/by-path/lake/orders",
+            "POST /api/v3/catalog/d1/refresh",
+            "POST /api/v3/reflection/r1/refresh",
+        ]
+    );
+    let bodies = api.bodies();
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&bodies[2]).unwrap(),
+        serde_json::json!({
+            "entityType": "dataset",
+            "type": "VIRTUAL_DATASET",
+            "path": ["marts", "revenue"],
+            "sql": "SELECT 1",
+        })
+    );
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&bodies[3]).unwrap(),
+        serde_json::json!({ "tag": "t1", "sql": "SELECT 2" })
+    );
+
+    // Unknown entities are reported with Dremio's message.
+    let e = catalog.get_by_id("missing").await.unwrap_err();
+    assert!(
+        matches!(&e, DremioClientError::RestError { status: 404, message } if message == "not found"),
+        "{}",
+        e
+    );
+}
+
+#[cfg(feature = "config")]
+#[test]
+fn test_profile_rest_url() {
+    use dremio_rs::config::Profile;
+
+    let rest_url = |url: &str| {
+        Profile {
+            url: url.to_string(),
+            ..Default::default()
+        }
+        .rest_url()
+        .unwrap()
+    };
+    assert_eq!(rest_url("http://localhost:32010"), "http://localhost:9047");
+    assert_eq!(
+        rest_url("grpc+tls://dremio.example.com:32010"),
+        "https://dremio.example.com:9047"
+    );
+    assert_eq!(rest_url("grpc://localhost:32010"), "http://localhost:9047");
+    assert_eq!(
+        rest_url("dremio.example.com"),
+        "http://dremio.example.com:9047"
+    );
+
+    let explicit = Profile {
+        url: "grpc+tls://dremio.example.com:32010".to_string(),
+        rest_url: Some("https://rest.example.com".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(explicit.rest_url().unwrap(), "https://rest.example.com");
+}
@@ -0,0 +1,142 @@
+#![cfg(all(feature = "testing", feature = "serde"))]
+
+use arrow::array::{Int32Array, RecordBatch, StringArray};
+use arrow::datatypes::{DataType, Field, Schema};
+use dremio_rs::testing::{FakeFlightSqlServer, FakeResponse};
+use dremio_rs::{Client, DremioClientError};
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Order {
+    id: i32,
+    customer: Option<String>,
+}
+
+fn orders(ids: Vec<i32>, customers: Vec<Option<&str>>) -> RecordBatch {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int32, false),
+        Field::new("customer", DataType::Utf8, true),
+    ]));
+    RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int32Array::from(ids)),
+            Arc::new(StringArray::from(customers)),
+        ],
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_typed_cursor() {
+    let first = orders(vec![1, 2], vec![Some("ada"), None]);
+    let second = orders(vec![3], vec![Some("grace")]);
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM orders",
+            FakeResponse::new(first.schema()).with_endpoint(vec![first, second]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+
+    let mut cursor = client
+        .cursor::<Order>("SELECT * FROM orders")
+        .await
+        .unwrap();
+    let mut seen = Vec::new();
+    while let Some(order) = cursor.try_next().await.unwrap() {
+        seen.push(order);
+    }
+    assert_eq!(
+        seen,
+        vec![
+            Order {
+                id: 1,
+                customer: Some("ada".to_string())
+            },
+            Order {
+                id: 2,
+                customer: None
+            },
+            Order {
+                id: 3,
+                customer: Some("grace".to_string())
+            },
+        ]
+    );
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Mistyped {
+        customer: i64,
+    }
+    let mut cursor = client
+        .cursor::<Mistyped>("SELECT * FROM orders")
+        .await
+        .unwrap();
+    match cursor.try_next().await {
+        Err(DremioClientError::RowError(error)) => {
+            assert_eq!(error.row, Some(0));
+            assert_eq!(error.column.as_deref(), Some("customer"));
+        }
+        other => panic!("expected a row error, got {:?}", other),
+    }
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_query_as() {
+    let first = orders(vec![1, 2], vec![Some("ada"), None]);
+    let second = orders(vec![3], vec![Some("grace")]);
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM orders",
+            FakeResponse::new(first.schema()).with_endpoint(vec![first, second]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+
+    let orders = client
+        .query_as::<Order>("SELECT * FROM orders")
+        .await
+        .unwrap();
+    assert_eq!(orders.len(), 3);
+    assert_eq!(orders[1].customer, None);
+    assert_eq!(orders[2].customer.as_deref(), Some("grace"));
+
+    // Nulls need an `Option`, and fields need a column of the same name.
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Required {
+        id: i32,
+        customer: String,
+    }
+    match client.query_as::<Required>("SELECT * FROM orders").await {
+        Err(DremioClientError::RowError(error)) => {
+            assert_eq!(error.row, Some(1));
+            assert_eq!(error.column.as_deref(), Some("customer"));
+        }
+        other => panic!("expected a row error, got {:?}", other),
+    }
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Renamed {
+        order_id: i32,
+    }
+    match client.query_as::<Renamed>("SELECT * FROM orders").await {
+        Err(DremioClientError::RowError(error)) => {
+            assert!(error.message.contains("order_id"), "{}", error)
+        }
+        other => panic!("expected a row error, got {:?}", other),
+    }
+    server.shutdown().await;
+}
@@ -0,0 +1,74 @@
+#![cfg(feature = "scheduler")]
+
+use chrono::{TimeZone, Utc};
+use dremio_rs::scheduler::{Job, JobOutcome, Schedule, Scheduler};
+use dremio_rs::DremioClientError;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[test]
+fn test_cron_schedule() {
+    let at = |d, h, m| Utc.with_ymd_and_hms(2024, 3, d, h, m, 0).unwrap();
+    // Friday 2024-03-01 14:07.
+    let now = at(1, 14, 7);
+    let next = |expression: &str| Schedule::cron(expression).unwrap().next_after(now);
+
+    assert_eq!(next("*/15 * * * *"), Some(at(1, 14, 15)));
+    assert_eq!(next("0 2 * * *"), Some(at(2, 2, 0)));
+    assert_eq!(next("30 9 * * 1-5"), Some(at(4, 9, 30)));
+    assert_eq!(next("0 0 1,15 * *"), Some(at(15, 0, 0)));
+    // Restricted day fields match either; a step on one of them still
+    // restricts it, so both must match: the first odd Monday is the 11th.
+    assert_eq!(next("0 0 2,3 * 1"), Some(at(2, 0, 0)));
+    assert_eq!(next("0 0 */2 * 1"), Some(at(11, 0, 0)));
+    assert_eq!(next("0 0 * * 1"), Some(at(4, 0, 0)));
+    assert_eq!(
+        next("0 0 29 2 *"),
+        Some(Utc.with_ymd_and_hms(2028, 2, 29, 0, 0, 0).unwrap())
+    );
+    assert!(Schedule::cron("61 * * * *").is_err());
+    assert!(Schedule::cron("* * * *").is_err());
+}
+
+#[tokio::test]
+async fn test_scheduler_retries_and_skips_overlapping_runs() {
+    let calls = Arc::new(AtomicU32::new(0));
+    let counter = calls.clone();
+    let job = Job::new(
+        "slow",
+        Schedule::every(Duration::from_millis(20)),
+        move || {
+            let attempt = counter.fetch_add(1, Ordering::SeqCst);
+            async move {
+                if attempt == 0 {
+                    return Err(DremioClientError::ConfigError("first attempt".to_string()));
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                Ok(())
+            }
+        },
+    )
+    .with_retries(1, Duration::ZERO);
+
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let sink = reports.clone();
+    let handle = Scheduler::new()
+        .with_job(job)
+        .on_complete(move |report| {
+            let outcome = match &report.outcome {
+                JobOutcome::Succeeded => "succeeded",
+                JobOutcome::Failed(_) => "failed",
+                JobOutcome::Skipped => "skipped",
+            };
+            sink.lock().unwrap().push((outcome, report.attempts));
+        })
+        .start();
+    tokio::time::sleep(Duration::from_millis(110)).await;
+    handle.stop().await;
+
+    let reports = reports.lock().unwrap();
+    assert!(reports.contains(&("skipped", 0)));
+    assert!(reports.contains(&("succeeded", 2)));
+    assert!(!reports.iter().any(|(outcome, _)| *outcome == "failed"));
+}
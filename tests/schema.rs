@@ -0,0 +1,129 @@
+use arrow::array::{Int32Array, RecordBatch};
+use arrow::datatypes::{DataType, Field, Schema};
+use dremio_rs::schema::{
+    rename_columns, to_snake_case, ColumnNaming, SchemaDifference, SchemaExpectation,
+};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+#[test]
+fn test_to_snake_case() {
+    for (name, expected) in [
+        ("CustomerID", "customer_id"),
+        ("orderDate", "order_date"),
+        ("First Name", "first_name"),
+        ("HTTPServer", "http_server"),
+        ("col2Value", "col2_value"),
+        ("__total__", "total"),
+        ("already_snake", "already_snake"),
+    ] {
+        assert_eq!(to_snake_case(name), expected, "{}", name);
+    }
+}
+
+#[test]
+fn test_column_naming() {
+    let mapping = BTreeMap::from([("OrderDate".to_string(), "day".to_string())]);
+    for (naming, expected) in [
+        (ColumnNaming::AsReturned, ["OrderDate", "ID"]),
+        (ColumnNaming::Lowercase, ["orderdate", "id"]),
+        (ColumnNaming::SnakeCase, ["order_date", "id"]),
+        (ColumnNaming::Mapping(mapping), ["day", "ID"]),
+    ] {
+        let batch = RecordBatch::try_from_iter([
+            ("OrderDate", Arc::new(Int32Array::from(vec![1, 2])) as _),
+            ("ID", Arc::new(Int32Array::from(vec![3, 4])) as _),
+        ])
+        .unwrap();
+        let renamed = rename_columns(batch.clone(), &naming).unwrap();
+        let names: Vec<_> = renamed
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect();
+        assert_eq!(names, expected, "{:?}", naming);
+        assert_eq!(renamed.columns(), batch.columns());
+    }
+}
+
+fn expected() -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, true),
+    ]))
+}
+
+#[test]
+fn test_schema_expectation_reports_every_difference() {
+    let actual = Schema::new(vec![
+        Field::new("extra", DataType::Boolean, false),
+        Field::new("id", DataType::Int32, true),
+    ]);
+    let mismatch = SchemaExpectation::strict(expected())
+        .validate(&actual)
+        .unwrap_err();
+    assert_eq!(
+        mismatch.differences(),
+        &[
+            SchemaDifference::TypeMismatch {
+                name: "id".into(),
+                expected: DataType::Int64,
+                actual: DataType::Int32,
+            },
+            SchemaDifference::NullabilityMismatch { name: "id".into() },
+            SchemaDifference::OrderMismatch {
+                name: "id".into(),
+                expected: 0,
+                actual: 1,
+            },
+            SchemaDifference::Missing {
+                name: "name".into(),
+                data_type: DataType::Utf8,
+            },
+            SchemaDifference::Unexpected {
+                name: "extra".into(),
+                data_type: DataType::Boolean,
+            },
+        ]
+    );
+    assert_eq!(
+        mismatch.to_string(),
+        "column \"id\" has type Int32, expected Int64; \
+         column \"id\" is nullable, expected non-nullable; \
+         column \"id\" is at position 1, expected 0; \
+         missing column \"name\" (Utf8); \
+         unexpected column \"extra\" (Boolean)"
+    );
+}
+
+#[test]
+fn test_schema_expectation_defaults() {
+    // Order and nullability are not checked by default.
+    let reordered = Schema::new(vec![
+        Field::new("name", DataType::Utf8, true),
+        Field::new("id", DataType::Int64, true),
+    ]);
+    SchemaExpectation::new(expected())
+        .validate(&reordered)
+        .unwrap();
+
+    let wider = Schema::new(vec![
+        Field::new("id", DataType::Int32, false),
+        Field::new("name", DataType::Utf8, true),
+        Field::new("extra", DataType::Boolean, false),
+    ]);
+    assert_eq!(
+        SchemaExpectation::new(expected())
+            .validate(&wider)
+            .unwrap_err()
+            .differences()
+            .len(),
+        2
+    );
+    SchemaExpectation::new(expected())
+        .with_check_types(false)
+        .with_allow_extra_columns(true)
+        .validate(&wider)
+        .unwrap();
+}
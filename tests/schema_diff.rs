@@ -0,0 +1,61 @@
+#![cfg(feature = "delta")]
+
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use dremio_rs::schema_diff::{delta_schema, diff, ColumnChange};
+
+#[test]
+fn test_delta_schema() {
+    let table = std::env::temp_dir().join(format!("dremio-rs-delta-{}", std::process::id()));
+    let log = table.join("_delta_log");
+    std::fs::create_dir_all(&log).unwrap();
+    let metadata = |fields: &str| {
+        let schema = format!(r#"{{"type":"struct","fields":[{}]}}"#, fields);
+        format!(
+            r#"{{"metaData":{{"id":"t","schemaString":"{}"}}}}"#,
+            schema.replace('"', "\\\"")
+        )
+    };
+    std::fs::write(
+        log.join("00000000000000000000.json"),
+        metadata(r#"{"name":"id","type":"integer","nullable":false,"metadata":{}}"#),
+    )
+    .unwrap();
+    std::fs::write(
+        log.join("00000000000000000001.json"),
+        format!(
+            "{{\"commitInfo\":{{}}}}\n{}\n",
+            metadata(concat!(
+                r#"{"name":"id","type":"long","nullable":false,"metadata":{}},"#,
+                r#"{"name":"at","type":"timestamp","nullable":true,"metadata":{}}"#
+            ))
+        ),
+    )
+    .unwrap();
+
+    let schema = delta_schema(&table).unwrap();
+    std::fs::remove_dir_all(&table).unwrap();
+    let at = DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()));
+    assert_eq!(
+        schema.as_ref(),
+        &Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("at", at.clone(), true),
+        ])
+    );
+
+    let old = Schema::new(vec![Field::new("id", DataType::Int32, false)]);
+    assert_eq!(
+        diff(&old, &schema).changes(),
+        [
+            ColumnChange::Retyped {
+                name: "id".to_string(),
+                from: DataType::Int32,
+                to: DataType::Int64,
+            },
+            ColumnChange::Added {
+                name: "at".to_string(),
+                data_type: at,
+            },
+        ]
+    );
+}
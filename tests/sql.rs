@@ -0,0 +1,144 @@
+use dremio_rs::sql::{
+    parse_path, quote_path, sample_sql, Comparison, Literal, Order, QueryBuilder, SampleSpec,
+};
+
+#[test]
+fn test_literals() {
+    for (literal, expected) in [
+        (Literal::Null, "NULL"),
+        (Literal::from(true), "TRUE"),
+        (Literal::from(-7i8), "-7"),
+        (Literal::from(u32::MAX), "4294967295"),
+        (Literal::from(1.5f32), "1.5"),
+        // Whole floats keep their decimal point, so they stay DOUBLE.
+        (Literal::from(2.0), "2.0"),
+        (Literal::from(f64::NAN), "CAST('NaN' AS DOUBLE)"),
+        (Literal::from(f64::INFINITY), "CAST('Infinity' AS DOUBLE)"),
+        (
+            Literal::from(f64::NEG_INFINITY),
+            "CAST('-Infinity' AS DOUBLE)",
+        ),
+        (Literal::from("it's"), "'it''s'"),
+        (Literal::from("''"), "''''''"),
+        (Literal::from(None::<i32>), "NULL"),
+        (Literal::from(Some("x".to_string())), "'x'"),
+    ] {
+        assert_eq!(literal.to_string(), expected, "{:?}", literal);
+    }
+}
+
+#[test]
+fn test_query_builder_filters() {
+    assert_eq!(QueryBuilder::new().build(), "SELECT *");
+
+    // An empty IN list matches no rows rather than being invalid SQL.
+    let sql = QueryBuilder::new()
+        .from(["t"])
+        .filter_in("id", Vec::<i32>::new())
+        .build();
+    assert_eq!(sql, r#"SELECT * FROM "t" WHERE FALSE"#);
+
+    let sql = QueryBuilder::new()
+        .select(["a\"b"])
+        .select_raw("COUNT(*) AS n")
+        .from(["lake", "orders"])
+        .filter_in("id", [1, 2])
+        .filter_null("deleted_at", true)
+        .filter_null("region", false)
+        .filter("name", Comparison::Like, "O'%")
+        .filter("score", Comparison::NotEq, f64::NAN)
+        .filter_raw("a = 1 OR b = 2")
+        .order_by("id", Order::Asc)
+        .order_by("region", Order::default())
+        .build();
+    assert_eq!(
+        sql,
+        r#"SELECT "a""b", COUNT(*) AS n FROM "lake"."orders" WHERE "id" IN (1, 2) AND "deleted_at" IS NULL AND "region" IS NOT NULL AND "name" LIKE 'O''%' AND "score" <> CAST('NaN' AS DOUBLE) AND (a = 1 OR b = 2) ORDER BY "id" ASC, "region" ASC"#
+    );
+}
+
+#[test]
+fn test_query_builder_paging() {
+    // An offset is rendered without a limit.
+    let sql = QueryBuilder::new().from_raw("t").offset(20).build();
+    assert_eq!(sql, "SELECT * FROM t OFFSET 20");
+    let sql = QueryBuilder::new()
+        .from_raw("(SELECT 1) x")
+        .limit(10)
+        .offset(20)
+        .build();
+    assert_eq!(sql, "SELECT * FROM (SELECT 1) x LIMIT 10 OFFSET 20");
+    assert_eq!(QueryBuilder::new().limit(0).to_string(), "SELECT * LIMIT 0");
+}
+
+#[test]
+fn test_parse_path() {
+    for (path, expected) in [
+        ("lake.orders", vec!["lake", "orders"]),
+        (" lake . orders ", vec!["lake", "orders"]),
+        (r#""a.b".c"#, vec!["a.b", "c"]),
+        (r#""say ""hi""".t"#, vec![r#"say "hi""#, "t"]),
+        (
+            r#"lake."my folder"."x""y""#,
+            vec!["lake", "my folder", r#"x"y"#],
+        ),
+        ("@dremio", vec!["@dremio"]),
+    ] {
+        assert_eq!(parse_path(path).unwrap(), expected, "{}", path);
+        // Quoting gives the components back.
+        assert_eq!(
+            parse_path(&quote_path(&expected)).unwrap(),
+            expected,
+            "{}",
+            path
+        );
+    }
+
+    for (path, reason) in [
+        ("", "empty component"),
+        ("lake..orders", "empty component"),
+        (".orders", "empty component"),
+        ("lake.", "empty component"),
+        ("lake. .orders", "empty component"),
+        (r#""".orders"#, "empty component"),
+        (r#""lake.orders"#, "unterminated quote"),
+        (r#"lake."a""b"#, "unterminated quote"),
+    ] {
+        let e = parse_path(path).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            format!(
+                "Arrow Error: Invalid argument error: invalid dataset path {:?}: {}",
+                path, reason
+            )
+        );
+    }
+}
+
+#[test]
+fn test_sample_sql() {
+    assert_eq!(
+        sample_sql("lake.orders", SampleSpec::Rows(100)).unwrap(),
+        r#"SELECT * FROM "lake"."orders" ORDER BY RANDOM() LIMIT 100"#
+    );
+    assert_eq!(
+        sample_sql(r#"lake."my folder".t"#, SampleSpec::Fraction(1.0)).unwrap(),
+        r#"SELECT * FROM "lake"."my folder"."t" WHERE RANDOM() < 1.0"#
+    );
+    assert_eq!(
+        sample_sql("t", SampleSpec::Fraction(0.0)).unwrap(),
+        r#"SELECT * FROM "t" WHERE RANDOM() < 0.0"#
+    );
+
+    for fraction in [-0.1, 1.5, f64::NAN] {
+        let e = sample_sql("t", SampleSpec::Fraction(fraction)).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            format!(
+                "Arrow Error: Invalid argument error: sample fraction {} is not between 0 and 1",
+                fraction
+            )
+        );
+    }
+    assert!(sample_sql("lake..orders", SampleSpec::Rows(1)).is_err());
+}
@@ -0,0 +1,92 @@
+use dremio_rs::templates::{Params, TemplateLibrary};
+use std::fs;
+
+#[test]
+fn test_load_and_render() {
+    let dir = std::env::temp_dir().join(format!("dremio-rs-templates-{}", std::process::id()));
+    fs::create_dir_all(dir.join("filters")).unwrap();
+    fs::write(
+        dir.join("daily.sql"),
+        "SELECT {{ column | ident }}, SUM(amount)\nFROM {{table|path}}\nWHERE {{> filters/active }} AND region IN ({{ regions }})",
+    )
+    .unwrap();
+    fs::write(dir.join("filters/active.sql"), "status = {{ status }}").unwrap();
+    fs::write(dir.join("notes.txt"), "{{ ignored").unwrap();
+
+    let library = TemplateLibrary::load(&dir).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(
+        library.names().collect::<Vec<_>>(),
+        ["daily", "filters/active"]
+    );
+
+    let params = Params::new()
+        .with("column", "order date")
+        .with("table", "@dremio.orders")
+        .with("status", "open")
+        .with_list("regions", ["EU", "US"]);
+    assert_eq!(
+        library.render("daily", &params).unwrap(),
+        "SELECT \"order date\", SUM(amount)\nFROM \"@dremio\".\"orders\"\nWHERE status = 'open' AND region IN ('EU', 'US')"
+    );
+    let params = params.with_list("regions", Vec::<&str>::new());
+    assert!(library
+        .render("daily", &params)
+        .unwrap()
+        .ends_with("region IN (NULL)"));
+
+    let error = library.render("daily", &Params::new()).unwrap_err();
+    assert!(error.to_string().contains("needs the parameter column"));
+    let error = library
+        .render("daily", &params.with("column", 1))
+        .unwrap_err();
+    assert!(error.to_string().contains("must be a string"));
+}
+
+#[test]
+fn test_template_errors() {
+    let error = TemplateLibrary::new()
+        .with_template("broken", "SELECT 1\nWHERE x = {{ x")
+        .unwrap_err();
+    assert!(error.to_string().contains("broken:2: unclosed placeholder"));
+    let error = TemplateLibrary::new()
+        .with_template("broken", "SELECT {{ x | upper }}")
+        .unwrap_err();
+    assert!(error.to_string().contains("unknown filter"));
+
+    let library = TemplateLibrary::new()
+        .with_template("a", "{{> b }}")
+        .unwrap()
+        .with_template("b", "{{> a }}")
+        .unwrap();
+    let error = library.render("a", &Params::new()).unwrap_err();
+    assert!(error.to_string().contains("a > b > a"));
+    assert!(library.render("c", &Params::new()).is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_render_with_struct() {
+    #[derive(serde::Serialize)]
+    struct Filter {
+        state: Option<String>,
+        limit: u32,
+        ids: Vec<i64>,
+    }
+
+    let library = TemplateLibrary::new()
+        .with_template(
+            "q",
+            "SELECT * FROM t WHERE state = {{ state }} AND id IN ({{ ids }}) LIMIT {{ limit }}",
+        )
+        .unwrap();
+    let filter = Filter {
+        state: None,
+        limit: 10,
+        ids: vec![1, 2],
+    };
+    assert_eq!(
+        library.render_with("q", &filter).unwrap(),
+        "SELECT * FROM t WHERE state = NULL AND id IN (1, 2) LIMIT 10"
+    );
+}
@@ -0,0 +1,2699 @@
+#![cfg(feature = "testing")]
+
+use arrow::array::{Array, AsArray, Int32Array, RecordBatch, StringArray};
+use arrow::datatypes::{DataType, Field, Int32Type, Schema, TimeUnit};
+use dremio_rs::schema::{ColumnNaming, SchemaDifference, SchemaExpectation};
+use dremio_rs::sql::SampleSpec;
+use dremio_rs::testing::{FakeFlightSqlServer, FakeResponse, MockClient, Recorder};
+use dremio_rs::{
+    Client, DecimalMode, DremioClientError, DremioExecutor, IntervalMode, QueryOptions, QueryPhase,
+    QueryStream, TimestampMode,
+};
+use futures::TryStreamExt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tonic::Status;
+
+fn batch(name: &str, values: Vec<i32>) -> RecordBatch {
+    let schema = Arc::new(Schema::new(vec![Field::new(name, DataType::Int32, false)]));
+    RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(values))]).unwrap()
+}
+
+#[tokio::test]
+async fn test_mock_client_patterns() {
+    let mut mock = MockClient::new()
+        .on("SELECT * FROM sales WHERE *", vec![batch("v", vec![1, 2])])
+        .on("SELECT * FROM sales", vec![batch("v", vec![1, 2, 3])])
+        .on_error("SELECT * FROM slow", || {
+            DremioClientError::TimeoutError(Duration::from_secs(1))
+        });
+
+    let batches = mock
+        .get_record_batches("select *\n  from SALES where v < 3;")
+        .await
+        .unwrap();
+    assert_eq!(batches[0].num_rows(), 2);
+    let batches = mock
+        .get_record_batches("SELECT * FROM sales")
+        .await
+        .unwrap();
+    assert_eq!(batches[0].num_rows(), 3);
+    assert!(matches!(
+        mock.get_record_batches("SELECT * FROM slow").await,
+        Err(DremioClientError::TimeoutError(_))
+    ));
+    assert!(mock.get_record_batches("SELECT 1").await.is_err());
+
+    assert_eq!(mock.executed().len(), 4);
+    mock.assert_executed("* where v < 3");
+}
+
+#[tokio::test]
+async fn test_mock_client_applies_options() {
+    let mut mock = MockClient::new().on("SELECT *", vec![batch("orderId", vec![7])]);
+    let options = QueryOptions::new().with_column_naming(ColumnNaming::SnakeCase);
+    let batches = mock
+        .get_record_batches_with_options("SELECT * FROM orders", &options)
+        .await
+        .unwrap();
+    assert_eq!(batches[0].schema().field(0).name(), "order_id");
+}
+
+#[tokio::test]
+async fn test_timestamp_mode() {
+    let naive = RecordBatch::try_from_iter([(
+        "at",
+        Arc::new(arrow::array::TimestampMicrosecondArray::from(vec![1])) as _,
+    )])
+    .unwrap();
+    let mut mock = MockClient::new().on("SELECT *", vec![naive]);
+    for (mode, tz) in [
+        (TimestampMode::AsReturned, None),
+        (TimestampMode::Utc, Some("UTC")),
+        (TimestampMode::Timezone("+05:30".into()), Some("+05:30")),
+    ] {
+        let options = QueryOptions::new().with_timestamp_mode(mode);
+        let batches = mock
+            .get_record_batches_with_options("SELECT * FROM t", &options)
+            .await
+            .unwrap();
+        assert_eq!(
+            batches[0].schema().field(0).data_type(),
+            &DataType::Timestamp(TimeUnit::Microsecond, tz.map(Into::into))
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_decimal_mode() {
+    let prices = RecordBatch::try_from_iter([(
+        "price",
+        Arc::new(
+            arrow::array::Decimal128Array::from(vec![1250])
+                .with_precision_and_scale(10, 2)
+                .unwrap(),
+        ) as _,
+    )])
+    .unwrap();
+    let mut mock = MockClient::new().on("SELECT *", vec![prices]);
+    for (mode, data_type) in [
+        (DecimalMode::AsReturned, DataType::Decimal128(10, 2)),
+        (DecimalMode::Float64, DataType::Float64),
+        (DecimalMode::Utf8, DataType::Utf8),
+    ] {
+        let options = QueryOptions::new().with_decimal_mode(mode);
+        let batches = mock
+            .get_record_batches_with_options("SELECT * FROM t", &options)
+            .await
+            .unwrap();
+        assert_eq!(batches[0].schema().field(0).data_type(), &data_type);
+    }
+}
+
+#[tokio::test]
+async fn test_expect_schema_fails_before_fetching() {
+    let orders = batch("OrderId", vec![1]);
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM orders",
+            FakeResponse::new(orders.schema())
+                .with_failing_endpoint(vec![orders], Status::internal("results were fetched")),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    // The check runs on the renamed columns.
+    let expected = Arc::new(Schema::new(vec![Field::new(
+        "order_id",
+        DataType::Int64,
+        false,
+    )]));
+    let options = QueryOptions::new()
+        .with_column_naming(ColumnNaming::SnakeCase)
+        .expect_schema(SchemaExpectation::new(expected));
+    let err = client
+        .get_record_batches_with_options("SELECT * FROM orders", &options)
+        .await
+        .unwrap_err();
+    let DremioClientError::SchemaMismatch(mismatch) = err.root() else {
+        panic!("unexpected error: {}", err);
+    };
+    assert_eq!(
+        mismatch.differences(),
+        &[SchemaDifference::TypeMismatch {
+            name: "order_id".into(),
+            expected: DataType::Int64,
+            actual: DataType::Int32,
+        }]
+    );
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_interval_mode() {
+    let intervals = RecordBatch::try_from_iter([(
+        "wait",
+        Arc::new(arrow::array::IntervalYearMonthArray::from(vec![13])) as _,
+    )])
+    .unwrap();
+    let mut mock = MockClient::new().on("SELECT *", vec![intervals]);
+    let options = QueryOptions::new().with_interval_mode(IntervalMode::Iso8601);
+    let batches = mock
+        .get_record_batches_with_options("SELECT * FROM t", &options)
+        .await
+        .unwrap();
+    let wait = batches[0].column(0).as_string::<i32>();
+    assert_eq!(wait.value(0), "P1Y1M");
+}
+
+#[tokio::test]
+async fn test_select_columns() {
+    let orders = RecordBatch::try_from_iter([
+        ("orderId", Arc::new(Int32Array::from(vec![1, 2])) as _),
+        ("customer", Arc::new(StringArray::from(vec!["a", "b"])) as _),
+        ("notes", Arc::new(StringArray::from(vec!["x", "y"])) as _),
+    ])
+    .unwrap();
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM orders",
+            FakeResponse::new(orders.schema()).with_endpoint(vec![orders]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    let options = QueryOptions::new()
+        .with_column_naming(ColumnNaming::SnakeCase)
+        .select_columns(&["customer", "order_id"]);
+    let batches = client
+        .get_record_batches_with_options("SELECT * FROM orders", &options)
+        .await
+        .unwrap();
+    let schema = batches[0].schema();
+    let names: Vec<_> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+    assert_eq!(names, ["customer", "order_id"]);
+
+    let options = QueryOptions::new().select_columns(&["total"]);
+    assert!(client
+        .get_record_batches_with_options("SELECT * FROM orders", &options)
+        .await
+        .is_err());
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_fake_server_multiple_endpoints() {
+    let schema = batch("v", vec![]).schema();
+    let response = FakeResponse::new(schema)
+        .with_endpoint(vec![batch("v", vec![1, 2])])
+        .with_endpoint(vec![batch("v", vec![3]), batch("v", vec![4])]);
+    let server = FakeFlightSqlServer::new()
+        .with_credentials("dremio", "dremio123")
+        .on("SELECT * FROM t", response)
+        .start()
+        .await
+        .unwrap();
+
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    let stream = client
+        .execute_stream_with_options("SELECT * FROM t", &QueryOptions::new())
+        .await
+        .unwrap();
+    assert_eq!(stream.expected_rows(), Some(4));
+    let rows: usize = stream
+        .collect_batches()
+        .await
+        .unwrap()
+        .iter()
+        .map(|b| b.num_rows())
+        .sum();
+    assert_eq!(rows, 4);
+    assert_eq!(server.queries(), vec!["SELECT * FROM t"]);
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_endpoint_order() {
+    use dremio_rs::EndpointOrder;
+
+    let schema = batch("v", vec![]).schema();
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM t",
+            FakeResponse::new(schema.clone())
+                .with_endpoint(vec![batch("v", vec![1, 2]), batch("v", vec![3])])
+                .with_endpoint(vec![batch("v", vec![4])])
+                .with_endpoint(vec![batch("v", vec![5]), batch("v", vec![6])]),
+        )
+        .on(
+            "SELECT * FROM failing",
+            FakeResponse::new(schema)
+                .with_failing_endpoint(vec![batch("v", vec![1])], Status::internal("node lost"))
+                .with_endpoint(vec![batch("v", vec![2])]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    let values = |batches: Vec<RecordBatch>| -> Vec<i32> {
+        batches
+            .iter()
+            .flat_map(|b| {
+                b.column(0)
+                    .as_any()
+                    .downcast_ref::<Int32Array>()
+                    .unwrap()
+                    .values()
+                    .to_vec()
+            })
+            .collect()
+    };
+
+    let options = QueryOptions::new().with_endpoint_concurrency(2);
+    let batches = client
+        .get_record_batches_with_options("SELECT * FROM t", &options)
+        .await
+        .unwrap();
+    assert_eq!(values(batches), vec![1, 2, 3, 4, 5, 6]);
+
+    let options = options.with_endpoint_order(EndpointOrder::AsReady);
+    let batches = client
+        .get_record_batches_with_options("SELECT * FROM t", &options)
+        .await
+        .unwrap();
+    let mut sorted = values(batches);
+    sorted.sort();
+    assert_eq!(sorted, vec![1, 2, 3, 4, 5, 6]);
+
+    // The first endpoint streams through rather than being collected first,
+    // so its batches arrive before it fails.
+    let options = QueryOptions::new().with_endpoint_concurrency(2);
+    let mut stream = client
+        .execute_stream_with_options("SELECT * FROM failing", &options)
+        .await
+        .unwrap();
+    let first = stream.try_next().await.unwrap().unwrap();
+    assert_eq!(values(vec![first]), vec![1]);
+    assert!(stream.try_next().await.is_err());
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_batch_size_rechunks_results() {
+    let schema = batch("v", vec![]).schema();
+    let response = FakeResponse::new(schema)
+        .with_endpoint(vec![batch("v", vec![1]), batch("v", vec![2, 3])])
+        .with_endpoint(vec![batch("v", (4..=10).collect())]);
+    let server = FakeFlightSqlServer::new()
+        .on("SELECT * FROM t", response)
+        .start()
+        .await
+        .unwrap();
+
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    let options = QueryOptions::new().with_batch_size(4);
+    let batches = client
+        .get_record_batches_with_options("SELECT * FROM t", &options)
+        .await
+        .unwrap();
+    let sizes: Vec<usize> = batches.iter().map(|b| b.num_rows()).collect();
+    assert_eq!(sizes, vec![4, 4, 2]);
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_peek() {
+    let response = FakeResponse::new(batch("v", vec![]).schema())
+        .with_endpoint(vec![batch("v", vec![1, 2]), batch("v", vec![3, 4])]);
+    let server = FakeFlightSqlServer::new()
+        .on("SELECT * FROM (*) LIMIT 3", response)
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    let preview = client.peek("SELECT * FROM t;", 3).await.unwrap();
+    assert_eq!(preview.num_rows(), 3);
+    assert_eq!(
+        server.queries(),
+        vec!["SELECT * FROM (\nSELECT * FROM t\n) LIMIT 3"]
+    );
+
+    let mut mock = MockClient::new().on("SHOW TABLES", vec![batch("v", vec![1, 2, 3])]);
+    assert_eq!(mock.peek("SHOW TABLES", 2).await.unwrap().num_rows(), 2);
+}
+
+#[tokio::test]
+async fn test_peek_keeps_order() {
+    let schema = batch("v", vec![]).schema();
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM t ORDER BY v DESC*LIMIT 2",
+            FakeResponse::new(schema.clone()).with_endpoint(vec![batch("v", vec![4, 3])]),
+        )
+        .on(
+            "SELECT * FROM (*) LIMIT 2",
+            FakeResponse::new(schema.clone()).with_endpoint(vec![batch("v", vec![1, 2])]),
+        )
+        .on(
+            "SELECT * FROM t ORDER BY v OFFSET 1",
+            FakeResponse::new(schema.clone())
+                .with_endpoint(vec![batch("v", vec![2, 3]), batch("v", vec![4])]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+
+    // A trailing ORDER BY gets the LIMIT appended, as it would be dropped
+    // from a subquery without a limit.
+    let preview = client
+        .peek("SELECT * FROM t ORDER BY v DESC -- newest first\n;", 2)
+        .await
+        .unwrap();
+    assert_eq!(
+        preview.column(0).as_primitive::<Int32Type>().values(),
+        &[4, 3]
+    );
+    // ORDER BY inside a subquery or window is not the statement's own.
+    client
+        .peek("SELECT * FROM (SELECT * FROM t ORDER BY v) x", 2)
+        .await
+        .unwrap();
+    client
+        .peek("SELECT ROW_NUMBER() OVER (ORDER BY v) FROM t", 2)
+        .await
+        .unwrap();
+    // With a LIMIT of its own, the subquery keeps its order.
+    client
+        .peek("SELECT * FROM t ORDER BY v LIMIT 10", 2)
+        .await
+        .unwrap();
+    // An OFFSET without a LIMIT cannot take one, so the rest is cancelled.
+    let preview = client
+        .peek("SELECT * FROM t ORDER BY v OFFSET 1", 2)
+        .await
+        .unwrap();
+    assert_eq!(preview.num_rows(), 2);
+    assert_eq!(
+        server.queries(),
+        [
+            "SELECT * FROM t ORDER BY v DESC -- newest first\nLIMIT 2",
+            "SELECT * FROM (\nSELECT * FROM (SELECT * FROM t ORDER BY v) x\n) LIMIT 2",
+            "SELECT * FROM (\nSELECT ROW_NUMBER() OVER (ORDER BY v) FROM t\n) LIMIT 2",
+            "SELECT * FROM (\nSELECT * FROM t ORDER BY v LIMIT 10\n) LIMIT 2",
+            "SELECT * FROM t ORDER BY v OFFSET 1",
+        ]
+    );
+    assert_eq!(server.cancelled(), ["SELECT * FROM t ORDER BY v OFFSET 1"]);
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_peek_cancels_unlimited_statements() {
+    let schema = batch("v", vec![]).schema();
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SHOW TABLES",
+            FakeResponse::new(schema.clone())
+                .with_endpoint(vec![batch("v", vec![1, 2]), batch("v", vec![3, 4])]),
+        )
+        .on(
+            "SELECT * FROM (*) LIMIT 5",
+            FakeResponse::new(schema.clone()),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    let preview = client.peek("SHOW TABLES", 2).await.unwrap();
+    assert_eq!(preview.num_rows(), 2);
+    assert_eq!(server.cancelled(), ["SHOW TABLES"]);
+
+    // Limited queries finish on their own; empty results keep their schema.
+    let preview = client.peek("SELECT * FROM empty", 5).await.unwrap();
+    assert_eq!(preview.num_rows(), 0);
+    assert_eq!(preview.schema(), schema);
+    assert_eq!(server.cancelled(), ["SHOW TABLES"]);
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_record_batches_with_schema() {
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM empty",
+            FakeResponse::new(batch("OrderId", vec![]).schema()),
+        )
+        .on(
+            "SELECT * FROM full",
+            FakeResponse::new(batch("OrderId", vec![]).schema())
+                .with_endpoint(vec![batch("OrderId", vec![1])]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap()
+        .with_query_options(QueryOptions::new().with_column_naming(ColumnNaming::SnakeCase));
+
+    let (schema, batches) = client
+        .get_record_batches_with_schema("SELECT * FROM empty")
+        .await
+        .unwrap();
+    assert!(batches.is_empty());
+    assert_eq!(schema.field(0).name(), "order_id");
+
+    let (schema, batches) = client
+        .get_record_batches_with_schema("SELECT * FROM full")
+        .await
+        .unwrap();
+    assert_eq!(schema, batches[0].schema());
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_watch() {
+    let schema = batch("id", vec![]).schema();
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM events WHERE TRUE",
+            FakeResponse::new(schema.clone()).with_endpoint(vec![batch("id", vec![2, 1])]),
+        )
+        .on(
+            r#"SELECT * FROM events WHERE "id" > 2"#,
+            FakeResponse::new(schema.clone()).with_endpoint(vec![batch("id", vec![3])]),
+        )
+        .on(
+            r#"SELECT * FROM events WHERE "id" > 3"#,
+            FakeResponse::new(schema),
+        )
+        .start()
+        .await
+        .unwrap();
+    let client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+
+    assert!(client
+        .watch("SELECT * FROM events", "id", Duration::ZERO)
+        .is_err());
+    let mut watch = client
+        .watch(
+            "SELECT * FROM events WHERE {after}",
+            "id",
+            Duration::from_millis(10),
+        )
+        .unwrap();
+    assert_eq!(watch.try_next().await.unwrap().unwrap().num_rows(), 2);
+    assert_eq!(watch.last_key(), Some("2"));
+    assert_eq!(
+        watch.try_next().await.unwrap().unwrap(),
+        batch("id", vec![3])
+    );
+    assert_eq!(watch.sql(), r#"SELECT * FROM events WHERE "id" > 3"#);
+
+    let mut watch = client
+        .watch("SELECT * FROM events WHERE {after}", "id", Duration::ZERO)
+        .unwrap()
+        .with_start_after(2);
+    assert_eq!(
+        watch.try_next().await.unwrap().unwrap(),
+        batch("id", vec![3])
+    );
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_watch_yields_null_keys_once() {
+    let ids = |ids: Vec<Option<f64>>| {
+        let ids = Arc::new(arrow::array::Float64Array::from(ids)) as _;
+        RecordBatch::try_from_iter_with_nullable([("id", ids, true)]).unwrap()
+    };
+    let schema = ids(vec![]).schema();
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM events WHERE TRUE",
+            FakeResponse::new(schema.clone()).with_endpoint(vec![ids(vec![None])]),
+        )
+        .on(
+            r#"SELECT * FROM events WHERE "id" IS NOT NULL"#,
+            FakeResponse::new(schema.clone()).with_endpoint(vec![ids(vec![Some(1.5)])]),
+        )
+        .on(
+            r#"SELECT * FROM events WHERE "id" > 1.5"#,
+            FakeResponse::new(schema),
+        )
+        .start()
+        .await
+        .unwrap();
+    let client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    let mut watch = client
+        .watch("SELECT * FROM events WHERE {after}", "id", Duration::ZERO)
+        .unwrap();
+
+    assert_eq!(watch.try_next().await.unwrap().unwrap(), ids(vec![None]));
+    assert_eq!(watch.last_key(), None);
+    assert_eq!(
+        watch.sql(),
+        r#"SELECT * FROM events WHERE "id" IS NOT NULL"#
+    );
+    assert_eq!(
+        watch.try_next().await.unwrap().unwrap(),
+        ids(vec![Some(1.5)])
+    );
+    assert_eq!(watch.last_key(), Some("1.5"));
+    assert_eq!(watch.sql(), r#"SELECT * FROM events WHERE "id" > 1.5"#);
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_watch_yields_batches_as_they_arrive() {
+    let schema = batch("id", vec![]).schema();
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM events WHERE TRUE",
+            FakeResponse::new(schema).with_failing_endpoint(
+                vec![
+                    batch("id", vec![2, 1]),
+                    batch("id", vec![]),
+                    batch("id", vec![4]),
+                ],
+                Status::internal("lost executor"),
+            ),
+        )
+        .start()
+        .await
+        .unwrap();
+    let client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    let mut watch = client
+        .watch("SELECT * FROM events WHERE {after}", "id", Duration::ZERO)
+        .unwrap();
+
+    // Rows fetched before the failure are yielded, and move the watch.
+    assert_eq!(
+        watch.try_next().await.unwrap().unwrap(),
+        batch("id", vec![2, 1])
+    );
+    assert_eq!(watch.last_key(), Some("2"));
+    assert_eq!(
+        watch.try_next().await.unwrap().unwrap(),
+        batch("id", vec![4])
+    );
+    assert_eq!(watch.last_key(), Some("4"));
+    assert!(watch.try_next().await.is_err());
+    assert!(watch.try_next().await.unwrap().is_none());
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_app_metadata() {
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM t",
+            FakeResponse::new(batch("v", vec![]).schema())
+                .with_endpoint(vec![batch("v", vec![1]), batch("v", vec![2])])
+                .with_app_metadata(b"50%")
+                .with_app_metadata(b"100%"),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+
+    for lossy in [false, true] {
+        let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = received.clone();
+        let options = QueryOptions::new()
+            .with_lossy_utf8(lossy)
+            .with_app_metadata(move |metadata| sink.lock().unwrap().push(metadata.to_vec()));
+        let mut stream = client
+            .execute_stream_with_options("SELECT * FROM t", &options)
+            .await
+            .unwrap();
+        while stream.try_next().await.unwrap().is_some() {}
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![b"50%".to_vec(), b"100%".to_vec()]
+        );
+        assert_eq!(stream.stats().app_metadata_messages, 2);
+    }
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_query_progress() {
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM t",
+            FakeResponse::new(batch("v", vec![]).schema())
+                .with_endpoint(vec![batch("v", vec![1, 2]), batch("v", vec![3])])
+                .with_endpoint(vec![batch("v", vec![4])]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+
+    for lossy in [false, true] {
+        let options = QueryOptions::new().with_lossy_utf8(lossy);
+        let stream = client
+            .execute_stream_with_options("SELECT * FROM t", &options)
+            .await
+            .unwrap();
+        let (stream, progress) = stream.watch_progress();
+        stream.collect_batches().await.unwrap();
+        let metrics = progress.borrow().clone();
+        assert_eq!((metrics.rows, metrics.batches), (4, 3));
+        assert_eq!(metrics.endpoint_batches, vec![2, 1]);
+        assert!(metrics.bytes_transferred > 0);
+    }
+
+    // Methods writing the results report progress through the options.
+    let (sender, progress) = tokio::sync::watch::channel(Default::default());
+    let mut client = client.with_query_options(QueryOptions::new().with_progress_channel(sender));
+    let path = std::env::temp_dir().join(format!("dremio-rs-progress-{}.json", std::process::id()));
+    client
+        .write_json("SELECT * FROM t", path.to_str().unwrap())
+        .await
+        .unwrap();
+    assert_eq!(progress.borrow().rows, 4);
+    assert_eq!(progress.borrow().fraction(), Some(1.0));
+    std::fs::remove_file(&path).unwrap();
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_impersonation() {
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM t",
+            FakeResponse::new(batch("v", vec![]).schema()).with_endpoint(vec![batch("v", vec![1])]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+
+    let alice = QueryOptions::new().with_impersonation("alice");
+    client
+        .get_record_batches_with_options("SELECT * FROM t", &alice)
+        .await
+        .unwrap();
+    let lossy = alice.with_lossy_utf8(true);
+    client
+        .get_record_batches_with_options("SELECT * FROM t", &lossy)
+        .await
+        .unwrap();
+    client.get_record_batches("SELECT * FROM t").await.unwrap();
+    let alice = Some("alice".to_string());
+    assert_eq!(
+        server.impersonations(),
+        vec![
+            alice.clone(),
+            alice.clone(),
+            alice.clone(),
+            alice,
+            None,
+            None
+        ]
+    );
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_session_options() {
+    use dremio_rs::SessionOptions;
+
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM t",
+            FakeResponse::new(batch("v", vec![]).schema()).with_endpoint(vec![batch("v", vec![1])]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::builder(server.url())
+        .with_credentials("dremio", "dremio123")
+        .with_session_options(
+            SessionOptions::new()
+                .with_schema("lake")
+                .with_routing_tag("etl"),
+        )
+        .connect()
+        .await
+        .unwrap();
+
+    client.get_record_batches("SELECT * FROM t").await.unwrap();
+    let reporting = QueryOptions::new().with_session_options(
+        SessionOptions::new()
+            .with_schema("lake.sales")
+            .with_routing_queue("Reporting"),
+    );
+    client
+        .get_record_batches_with_options("SELECT * FROM t", &reporting)
+        .await
+        .unwrap();
+    client
+        .get_record_batches_with_options("SELECT * FROM t", &reporting.with_lossy_utf8(true))
+        .await
+        .unwrap();
+
+    // Each query is submitted and fetched with the client's properties,
+    // overridden by those of the query.
+    let (lake, sales) = (Some("lake".to_string()), Some("lake.sales".to_string()));
+    assert_eq!(
+        server.headers("schema"),
+        [
+            lake.clone(),
+            lake,
+            sales.clone(),
+            sales.clone(),
+            sales.clone(),
+            sales
+        ]
+    );
+    assert_eq!(
+        server.headers("routing_tag"),
+        vec![Some("etl".to_string()); 6]
+    );
+    let queues = server.headers("routing_queue");
+    assert_eq!(queues[..2], [None, None]);
+    assert_eq!(queues[2..], vec![Some("Reporting".to_string()); 4]);
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_fake_server_errors() {
+    let schema = batch("v", vec![]).schema();
+    let server = FakeFlightSqlServer::new()
+        .with_credentials("dremio", "dremio123")
+        .on(
+            "SELECT * FROM missing",
+            FakeResponse::error(Status::not_found("no such table")),
+        )
+        .on(
+            "SELECT * FROM flaky",
+            FakeResponse::new(schema)
+                .with_failing_endpoint(vec![batch("v", vec![1])], Status::internal("node lost")),
+        )
+        .start()
+        .await
+        .unwrap();
+
+    assert!(Client::new(&server.url(), "dremio", "wrong").await.is_err());
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    let err = client
+        .get_record_batches("SELECT * FROM missing")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("no such table"), "{}", err);
+    assert_eq!(err.phase(), Some(QueryPhase::Execute));
+    assert_eq!(err.query(), Some("SELECT * FROM missing"));
+    let err = client
+        .get_record_batches("SELECT * FROM flaky")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("node lost"), "{}", err);
+    assert_eq!(err.phase(), Some(QueryPhase::Fetch));
+    assert!(matches!(
+        err.root(),
+        DremioClientError::FlightError(_) | DremioClientError::ArrowError(_)
+    ));
+    assert!(client.get_record_batches("SELECT 1").await.is_err());
+
+    let rejecting = FakeFlightSqlServer::new()
+        .rejecting_handshake("maintenance")
+        .start()
+        .await
+        .unwrap();
+    assert!(Client::new(&rejecting.url(), "dremio", "dremio123")
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_error_classification() {
+    let server = FakeFlightSqlServer::new()
+        .with_credentials("dremio", "dremio123")
+        .on(
+            "SELECT * FRM t",
+            FakeResponse::error(Status::invalid_argument(
+                "PARSE ERROR: Failure parsing the query. (Query ID: 1a6b8f2c-34d1-a9e0-7b2c-5d1f0e9a8c00)",
+            )),
+        )
+        .start()
+        .await
+        .unwrap();
+
+    let e = Client::new(&server.url(), "dremio", "wrong")
+        .await
+        .err()
+        .unwrap();
+    assert!(
+        matches!(&e, DremioClientError::AuthenticationFailed(message) if message == "invalid user name or password"),
+        "{}",
+        e
+    );
+
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    let e = client
+        .get_record_batches("SELECT * FRM t")
+        .await
+        .unwrap_err();
+    assert_eq!(e.phase(), Some(QueryPhase::Execute));
+    match e.root() {
+        DremioClientError::QueryFailed {
+            code,
+            message,
+            query_id,
+        } => {
+            assert_eq!(*code, tonic::Code::InvalidArgument);
+            assert!(message.starts_with("PARSE ERROR"), "{}", message);
+            assert_eq!(
+                query_id.as_deref(),
+                Some("1a6b8f2c-34d1-a9e0-7b2c-5d1f0e9a8c00")
+            );
+        }
+        other => panic!("unexpected error: {:?}", other),
+    }
+
+    // Transient failures keep their transport error, for retries to spot.
+    server.fail_next(1, Status::unavailable("coordinator restarting"));
+    let e = client.get_catalogs().await.unwrap_err();
+    assert!(
+        matches!(e.root(), DremioClientError::ArrowError(_)),
+        "{}",
+        e
+    );
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_stale_metadata_retry() {
+    let schema = batch("v", vec![]).schema();
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM lake.orders",
+            FakeResponse::error(Status::not_found(
+                "VALIDATION ERROR: Object 'orders' not found within 'lake'",
+            )),
+        )
+        .on("ALTER TABLE *", FakeResponse::new(schema))
+        .start()
+        .await
+        .unwrap();
+
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    let error = client
+        .get_record_batches("SELECT * FROM lake.orders")
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.stale_dataset(),
+        Some(vec!["lake".to_string(), "orders".to_string()])
+    );
+    assert_eq!(server.queries().len(), 1);
+
+    let mut client = client.with_stale_metadata_retry(true);
+    assert!(client
+        .get_record_batches("SELECT * FROM lake.orders")
+        .await
+        .is_err());
+    assert_eq!(
+        server.queries()[1..],
+        [
+            "SELECT * FROM lake.orders",
+            r#"ALTER TABLE "lake"."orders" REFRESH METADATA"#,
+            "SELECT * FROM lake.orders",
+        ]
+    );
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_sql_error_position() {
+    let sql = "SELECT *\nFROM sys.options\nWHERE nme = 'x'";
+    let server = FakeFlightSqlServer::new()
+        .on(
+            sql,
+            FakeResponse::error(Status::invalid_argument(
+                "VALIDATION ERROR: From line 3, column 7 to line 3, column 9: Column 'nme' not found",
+            )),
+        )
+        .start()
+        .await
+        .unwrap();
+
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    let error = client.get_record_batches(sql).await.unwrap_err();
+    let position = error.sql_position().unwrap();
+    assert_eq!(
+        (
+            position.line,
+            position.column,
+            position.end_line,
+            position.end_column
+        ),
+        (3, 7, 3, 9)
+    );
+    assert_eq!(position.to_string(), "line 3, column 7");
+    assert_eq!(
+        position.render_snippet(sql),
+        "3 | WHERE nme = 'x'\n  |       ^^^\n"
+    );
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_record_and_replay() {
+    let dir = std::env::temp_dir().join(format!("dremio-rs-recordings-{}", std::process::id()));
+    let source = MockClient::new()
+        .on(
+            "SELECT * FROM orders",
+            vec![batch("orderId", vec![1, 2, 3])],
+        )
+        .on_error("SELECT * FROM missing", || {
+            DremioClientError::TimeoutError(Duration::from_secs(1))
+        });
+    let mut recorder = Recorder::new(source, &dir).unwrap();
+    let options = QueryOptions::new().with_column_naming(ColumnNaming::SnakeCase);
+    let batches = recorder
+        .get_record_batches_with_options("SELECT * FROM orders", &options)
+        .await
+        .unwrap();
+    assert_eq!(batches[0].schema().field(0).name(), "order_id");
+    assert!(recorder
+        .get_record_batches("SELECT * FROM missing")
+        .await
+        .is_err());
+
+    let mut mock = MockClient::replay(&dir).unwrap();
+    let batches = mock
+        .get_record_batches("SELECT *\n  FROM orders;")
+        .await
+        .unwrap();
+    assert_eq!(batches[0].schema().field(0).name(), "orderId");
+    assert_eq!(batches[0].num_rows(), 3);
+    assert!(mock
+        .get_record_batches("SELECT * FROM missing")
+        .await
+        .is_err());
+    assert!(mock
+        .get_record_batches("SELECT * FROM other")
+        .await
+        .is_err());
+
+    let server = FakeFlightSqlServer::replay(&dir)
+        .unwrap()
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    let batches = client
+        .get_record_batches_with_options("SELECT * FROM orders", &options)
+        .await
+        .unwrap();
+    assert_eq!(batches[0].schema().field(0).name(), "order_id");
+    assert!(client
+        .get_record_batches("SELECT * FROM missing")
+        .await
+        .is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_record_with_spill() {
+    use dremio_rs::blob::SpillOptions;
+
+    let dir =
+        std::env::temp_dir().join(format!("dremio-rs-spill-recordings-{}", std::process::id()));
+    let schema = Arc::new(Schema::new(vec![Field::new("doc", DataType::Utf8, false)]));
+    let docs = RecordBatch::try_new(
+        schema,
+        vec![Arc::new(StringArray::from(vec![
+            "short",
+            "a much longer document",
+        ]))],
+    )
+    .unwrap();
+    let source = MockClient::new().on("SELECT * FROM docs", vec![docs]);
+    let mut recorder = Recorder::new(source, dir.join("recordings")).unwrap();
+    let options = QueryOptions::new().with_spill(SpillOptions::new(dir.join("cells"), 10));
+    let batches = recorder
+        .get_record_batches_with_options("SELECT * FROM docs", &options)
+        .await
+        .unwrap();
+    // Cells are spilled once, when replaying the recording, not when recording.
+    let names: Vec<_> = batches[0]
+        .schema()
+        .fields()
+        .iter()
+        .map(|f| f.name().clone())
+        .collect();
+    assert_eq!(names, vec!["doc", "doc__spilled"]);
+    assert_eq!(std::fs::read_dir(dir.join("cells")).unwrap().count(), 1);
+
+    let mut mock = MockClient::replay(dir.join("recordings")).unwrap();
+    let batches = mock.get_record_batches("SELECT * FROM docs").await.unwrap();
+    assert_eq!(batches[0].num_columns(), 1);
+    assert_eq!(batches[0].column(0).null_count(), 0);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_snapshot_and_replay() {
+    use dremio_rs::snapshot::SnapshotReader;
+
+    let dir = std::env::temp_dir().join(format!("dremio-rs-snapshots-{}", std::process::id()));
+    let orders = batch("orderId", vec![1, 2, 3]);
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM orders",
+            FakeResponse::new(orders.schema()).with_endpoint(vec![orders]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    let info = client
+        .snapshot("SELECT * FROM orders", dir.join("orders.arrow"))
+        .await
+        .unwrap();
+    assert_eq!(info.rows, 3);
+    server.shutdown().await;
+
+    let mut reader = SnapshotReader::open(&dir).unwrap();
+    assert_eq!(reader.snapshots(), [info]);
+    let options = QueryOptions::new().with_column_naming(ColumnNaming::SnakeCase);
+    let batches = reader
+        .get_record_batches_with_options("SELECT *  FROM orders", &options)
+        .await
+        .unwrap();
+    assert_eq!(batches[0].schema().field(0).name(), "order_id");
+    assert_eq!(batches[0].num_rows(), 3);
+    assert!(reader
+        .get_record_batches("SELECT * FROM other")
+        .await
+        .is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_diff() {
+    use dremio_rs::diff::RowChange;
+
+    let rows = |ids: Vec<i32>, names: Vec<&str>| {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("name", DataType::Utf8, false),
+        ]));
+        RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(ids)),
+                Arc::new(StringArray::from(names)),
+            ],
+        )
+        .unwrap()
+    };
+    let response =
+        |batches: Vec<RecordBatch>| FakeResponse::new(batches[0].schema()).with_endpoint(batches);
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "*FROM v1*ORDER BY \"id\" ASC NULLS LAST",
+            response(vec![
+                rows(vec![1, 2], vec!["a", "b"]),
+                rows(vec![3], vec!["c"]),
+            ]),
+        )
+        .on(
+            "*FROM v2*ORDER BY \"id\" ASC NULLS LAST",
+            response(vec![rows(vec![1, 3, 4], vec!["a", "x", "d"])]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+
+    let diff = client
+        .diff("SELECT * FROM v1", "SELECT * FROM v2", &["id"])
+        .await
+        .unwrap();
+    assert_eq!(
+        (diff.added, diff.removed, diff.changed, diff.unchanged),
+        (1, 1, 1, 1)
+    );
+    assert_eq!(
+        diff.examples,
+        [
+            RowChange::Removed(rows(vec![2], vec!["b"])),
+            RowChange::Changed {
+                before: rows(vec![3], vec!["c"]),
+                after: rows(vec![3], vec!["x"]),
+            },
+            RowChange::Added(rows(vec![4], vec!["d"])),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_benchmark() {
+    use dremio_rs::bench::BenchOptions;
+
+    let response = FakeResponse::new(batch("v", vec![]).schema())
+        .with_endpoint(vec![batch("v", vec![1, 2]), batch("v", vec![3])]);
+    let server = FakeFlightSqlServer::new()
+        .on("SELECT v FROM t", response)
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    let options = BenchOptions {
+        iterations: 5,
+        warmup: 2,
+        concurrency: 3,
+    };
+    let report = client.benchmark("SELECT v FROM t", options).await.unwrap();
+    assert_eq!(server.queries().len(), 7);
+    assert_eq!(report.runs.len(), 5);
+    for run in &report.runs {
+        assert_eq!((run.rows, run.bytes), (3, 12));
+        assert!(run.first_batch.unwrap() <= run.latency);
+    }
+    assert!(report.median() <= report.percentile(99.0));
+    assert!(report.to_string().starts_with("5 runs in "));
+}
+
+#[tokio::test]
+async fn test_warm_reflections() {
+    use dremio_rs::warmup::Warmup;
+
+    let reflections = |statuses: Vec<&str>| {
+        let column = |values: Vec<&str>| Arc::new(StringArray::from(values)) as _;
+        let batch = RecordBatch::try_from_iter([
+            ("reflection_id", column(vec!["r1", "r2", "r3"])),
+            ("reflection_name", column(vec!["raw", "agg", "other"])),
+            (
+                "dataset_name",
+                column(vec!["lake.orders", "lake.orders", "\"lake\".\"events\""]),
+            ),
+            ("status", column(statuses)),
+        ])
+        .unwrap();
+        FakeResponse::new(batch.schema()).with_endpoint(vec![batch])
+    };
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "*FROM sys.reflections",
+            reflections(vec!["CAN_ACCELERATE", "DISABLED", "REFRESHING"]),
+        )
+        .on(
+            "SELECT * FROM lake.orders",
+            FakeResponse::new(batch("v", vec![]).schema())
+                .with_endpoint(vec![batch("v", vec![1, 2, 3])]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+
+    let warmup = Warmup::new()
+        .with_dataset("lake.orders")
+        .unwrap()
+        .with_query("SELECT * FROM lake.orders");
+    let report = client.warm_reflections(&warmup).await.unwrap();
+    assert_eq!(report.reflections.len(), 2);
+    assert_eq!(report.queries.len(), 1);
+    assert_eq!(report.queries[0].rows, 3);
+
+    // The reflection on lake.events never becomes ready.
+    let warmup = Warmup::new()
+        .with_dataset("lake.events")
+        .unwrap()
+        .with_poll_interval(Duration::from_millis(10))
+        .with_timeout(Duration::from_millis(50));
+    assert!(matches!(
+        client.warm_reflections(&warmup).await,
+        Err(DremioClientError::TimeoutError(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_schema_cache() {
+    use dremio_rs::cache::SchemaCache;
+
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM (*) LIMIT 0",
+            FakeResponse::new(batch("v", vec![]).schema()),
+        )
+        .start()
+        .await
+        .unwrap();
+    let cache = SchemaCache::new();
+    let client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap()
+        .with_schema_cache(cache.clone());
+    let schema = client.get_schema("SELECT v FROM t").await.unwrap();
+    assert_eq!(schema.field(0).name(), "v");
+    let cached = client.get_schema("SELECT v\n  FROM t;").await.unwrap();
+    assert_eq!(cached, schema);
+    assert_eq!(server.queries().len(), 1);
+    assert_eq!(cache.len(), 1);
+
+    cache.clear();
+    client.get_schema("SELECT v FROM t").await.unwrap();
+    assert_eq!(server.queries().len(), 2);
+}
+
+#[tokio::test]
+async fn test_schema_of_statements() {
+    let tables = RecordBatch::try_from_iter([(
+        "TABLE_NAME",
+        Arc::new(StringArray::from(vec!["orders"])) as _,
+    )])
+    .unwrap();
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SHOW TABLES",
+            FakeResponse::new(tables.schema()).with_endpoint(vec![tables]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+
+    // Statements other than queries are described without being run.
+    let schema = client.get_schema("SHOW TABLES").await.unwrap();
+    assert_eq!(schema.field(0).name(), "TABLE_NAME");
+    assert!(server.queries().is_empty());
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_resolver_and_address() {
+    use dremio_rs::dns::StaticHosts;
+    use dremio_rs::ClientBuilder;
+
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT 1",
+            FakeResponse::new(batch("v", vec![]).schema()).with_endpoint(vec![batch("v", vec![1])]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let port = server.url().rsplit(':').next().unwrap().to_string();
+    let url = format!("http://coordinator.registry.invalid:{}", port);
+
+    let hosts =
+        StaticHosts::new().with_host("coordinator.registry.invalid", "127.0.0.1".parse().unwrap());
+    let mut client = ClientBuilder::new(&url)
+        .with_credentials("dremio", "dremio123")
+        .with_resolver(hosts)
+        .connect()
+        .await
+        .unwrap();
+    let batches = client.get_record_batches("SELECT 1").await.unwrap();
+    assert_eq!(batches[0].num_rows(), 1);
+
+    // An explicit address is dialed without resolving the host name.
+    let address = format!("127.0.0.1:{}", port).parse().unwrap();
+    let mut client = Client::builder("http://dremio.example.invalid:32010")
+        .with_credentials("dremio", "dremio123")
+        .with_address(address)
+        .connect()
+        .await
+        .unwrap();
+    assert!(client.get_record_batches("SELECT 1").await.is_ok());
+
+    let failing = |_: &str| async {
+        Err::<Vec<std::net::SocketAddr>, _>(std::io::Error::other("not registered"))
+    };
+    assert!(Client::builder(&url)
+        .with_resolver(failing)
+        .connect()
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_custom_connector() {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::task::{Context, Poll};
+    use tokio::net::TcpStream;
+    use tonic::codegen::Service;
+    use tonic::transport::Uri;
+
+    /// Stands in for a tunnel: ignores the URL and connects to a fixed port.
+    #[derive(Clone)]
+    struct Tunnel(String, Arc<AtomicUsize>);
+
+    impl Service<Uri> for Tunnel {
+        type Response = TcpStream;
+        type Error = std::io::Error;
+        type Future = Pin<Box<dyn Future<Output = std::io::Result<TcpStream>> + Send>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _: Uri) -> Self::Future {
+            self.1.fetch_add(1, Ordering::SeqCst);
+            Box::pin(TcpStream::connect(self.0.clone()))
+        }
+    }
+
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT 1",
+            FakeResponse::new(batch("v", vec![]).schema()).with_endpoint(vec![batch("v", vec![1])]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let connections = Arc::new(AtomicUsize::new(0));
+    let tunnel = Tunnel(
+        server.url().trim_start_matches("http://").to_string(),
+        connections.clone(),
+    );
+    let mut client = Client::builder("http://dremio.tunnel.invalid")
+        .with_credentials("dremio", "dremio123")
+        .with_connector(tunnel)
+        .connect()
+        .await
+        .unwrap();
+    let batches = client.get_record_batches("SELECT 1").await.unwrap();
+    assert_eq!(batches[0].num_rows(), 1);
+    assert_eq!(connections.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_client_name() {
+    let server = FakeFlightSqlServer::new()
+        .on("SELECT 1", FakeResponse::new(batch("v", vec![]).schema()))
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    client.get_record_batches("SELECT 1").await.unwrap();
+    let mut client = Client::builder(server.url())
+        .with_credentials("dremio", "dremio123")
+        .with_client_name("etl-loader", "1.4.2")
+        .connect()
+        .await
+        .unwrap();
+    client.get_record_batches("SELECT 1").await.unwrap();
+
+    let user_agents = server.user_agents();
+    assert!(user_agents[0].starts_with("dremio-rs/"));
+    assert!(user_agents[1].starts_with("etl-loader/1.4.2 dremio-rs/"));
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_trace_phases() {
+    use dremio_rs::phases::JobPhase;
+
+    let jobs = {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("job_id", DataType::Utf8, false),
+            Field::new("status", DataType::Utf8, false),
+        ]));
+        RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(StringArray::from(vec!["1a2b"])),
+                Arc::new(StringArray::from(vec!["ENQUEUED"])),
+            ],
+        )
+        .unwrap()
+    };
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT job_id, status FROM sys.jobs_recent *",
+            FakeResponse::new(jobs.schema()).with_endpoint(vec![jobs]),
+        )
+        .on(
+            "SELECT * FROM lake.orders*",
+            FakeResponse::new(batch("v", vec![]).schema())
+                .with_endpoint(vec![batch("v", vec![1, 2])])
+                .with_delay(Duration::from_millis(300)),
+        )
+        .start()
+        .await
+        .unwrap();
+
+    let client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    let mut seen = Vec::new();
+    let report = client
+        .trace_phases(
+            "SELECT * FROM lake.orders;",
+            Duration::from_millis(20),
+            |event| seen.push(event.phase),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        seen,
+        [JobPhase::Submitted, JobPhase::Queued, JobPhase::Fetching]
+    );
+    assert_eq!(report.job_id.as_deref(), Some("1a2b"));
+    assert_eq!(report.batches[0].num_rows(), 2);
+    assert!(report.time_in(JobPhase::Queued) >= Duration::from_millis(200));
+    assert_eq!(report.time_in(JobPhase::Planning), Duration::ZERO);
+
+    // The query carries a marker that the samples look for.
+    let queries = server.queries();
+    let marker = queries[0]
+        .strip_prefix("SELECT * FROM lake.orders\n")
+        .unwrap();
+    assert!(marker.starts_with("/* dremio-rs trace "));
+    assert!(queries[1].contains(&format!("LIKE '%{}'", marker)));
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_reconfigure() {
+    let schema = batch("v", vec![]).schema();
+    let old = FakeFlightSqlServer::new()
+        .with_credentials("dremio", "old-token")
+        .on(
+            "SELECT * FROM t",
+            FakeResponse::new(schema.clone())
+                .with_endpoint(vec![batch("v", vec![1, 2])])
+                .with_delay(Duration::from_millis(100)),
+        )
+        .start()
+        .await
+        .unwrap();
+    let new = FakeFlightSqlServer::new()
+        .with_credentials("dremio", "new-token")
+        .on(
+            "SELECT * FROM t",
+            FakeResponse::new(schema).with_endpoint(vec![batch("v", vec![3])]),
+        )
+        .start()
+        .await
+        .unwrap();
+
+    let mut client = Client::new(&old.url(), "dremio", "old-token")
+        .await
+        .unwrap();
+    let running = client
+        .execute_stream_with_options("SELECT * FROM t", &QueryOptions::new())
+        .await
+        .unwrap();
+
+    // Wrong credentials leave the old connection in place.
+    assert!(client
+        .reconfigure(Client::builder(new.url()).with_credentials("dremio", "old-token"))
+        .await
+        .is_err());
+    client
+        .reconfigure(Client::builder(new.url()).with_credentials("dremio", "new-token"))
+        .await
+        .unwrap();
+
+    // The query started before finishes on the old connection.
+    let batches = running.collect_batches().await.unwrap();
+    assert_eq!(batches[0].num_rows(), 2);
+    let batches = client.get_record_batches("SELECT * FROM t").await.unwrap();
+    assert_eq!(batches[0].num_rows(), 1);
+    assert_eq!(old.queries(), vec!["SELECT * FROM t"]);
+    assert_eq!(new.queries(), vec!["SELECT * FROM t"]);
+    old.shutdown().await;
+    new.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_reconfigure_does_not_serve_cache_of_previous_user() {
+    let schema = batch("v", vec![]).schema();
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM t",
+            FakeResponse::new(schema).with_endpoint(vec![batch("v", vec![1])]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "alice", "dremio123")
+        .await
+        .unwrap()
+        .with_result_cache(dremio_rs::cache::MemoryCache::new());
+    client.get_record_batches("SELECT * FROM t").await.unwrap();
+    client.get_record_batches("SELECT * FROM t").await.unwrap();
+    assert_eq!(server.queries().len(), 1);
+
+    client
+        .reconfigure(Client::builder(server.url()).with_credentials("bob", "dremio123"))
+        .await
+        .unwrap();
+    client.get_record_batches("SELECT * FROM t").await.unwrap();
+    assert_eq!(server.queries().len(), 2);
+
+    // Switching back finds the entries cached for the first user.
+    client
+        .reconfigure(Client::builder(server.url()).with_credentials("alice", "dremio123"))
+        .await
+        .unwrap();
+    client.get_record_batches("SELECT * FROM t").await.unwrap();
+    assert_eq!(server.queries().len(), 2);
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_connect_probes_bare_host() {
+    let server = FakeFlightSqlServer::new()
+        .with_credentials("dremio", "dremio123")
+        .start()
+        .await
+        .unwrap();
+    let host = server.url().trim_start_matches("http://").to_string();
+
+    Client::new(&host, "dremio", "dremio123").await.unwrap();
+    Client::new(&format!("grpc://{}", host), "dremio", "dremio123")
+        .await
+        .unwrap();
+    // The coordinator was found, so the rejected password is reported as is.
+    let e = Client::new(&host, "dremio", "wrong").await.err().unwrap();
+    assert!(!matches!(e, DremioClientError::ProbeFailed(_)), "{}", e);
+    server.shutdown().await;
+
+    let e = Client::builder(host.as_str())
+        .with_credentials("dremio", "dremio123")
+        .with_probe_timeout(Duration::from_millis(500))
+        .connect()
+        .await
+        .err()
+        .unwrap();
+    let DremioClientError::ProbeFailed(failure) = &e else {
+        panic!("unexpected error: {}", e);
+    };
+    assert_eq!(failure.host(), host);
+    let tried: Vec<_> = failure.attempts().iter().map(|a| a.url.as_str()).collect();
+    assert!(tried.contains(&format!("http://{}", host).as_str()));
+    assert!(
+        e.to_string().contains(&format!("http://{}: ", host)),
+        "{}",
+        e
+    );
+}
+
+#[tokio::test]
+async fn test_execute_many() {
+    let schema = batch("v", vec![]).schema();
+    let server = FakeFlightSqlServer::new()
+        .with_credentials("dremio", "dremio123")
+        .on(
+            "SELECT 1",
+            FakeResponse::new(schema.clone())
+                .with_endpoint(vec![batch("v", vec![1])])
+                .with_delay(Duration::from_millis(200)),
+        )
+        .on(
+            "SELECT 2",
+            FakeResponse::new(schema)
+                .with_endpoint(vec![batch("v", vec![2, 2])])
+                .with_delay(Duration::from_millis(200)),
+        )
+        .start()
+        .await
+        .unwrap();
+
+    let client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    let start = std::time::Instant::now();
+    let results = client
+        .execute_many(["SELECT 2", "SELECT 3", "SELECT 1", "SELECT 2"], 2)
+        .await;
+    // Two rounds of two delayed queries, the unknown one failing at once.
+    assert!(start.elapsed() >= Duration::from_millis(400));
+    let queries: Vec<_> = results.iter().map(|(query, _)| query.as_str()).collect();
+    assert_eq!(queries, ["SELECT 2", "SELECT 3", "SELECT 1", "SELECT 2"]);
+    let rows: Vec<_> = results
+        .iter()
+        .map(|(_, result)| result.as_ref().ok().map(|batches| batches[0].num_rows()))
+        .collect();
+    assert_eq!(rows, [Some(2), None, Some(1), Some(2)]);
+    assert_eq!(results[1].1.as_ref().unwrap_err().query(), Some("SELECT 3"));
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_execute_stream() {
+    let schema = batch("v", vec![]).schema();
+    let server = FakeFlightSqlServer::new()
+        .with_credentials("dremio", "dremio123")
+        .on(
+            "SELECT * FROM t",
+            FakeResponse::new(schema)
+                .with_endpoint(vec![batch("v", vec![1, 2]), batch("v", vec![3])]),
+        )
+        .start()
+        .await
+        .unwrap();
+
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    let mut stream = client.execute_stream("SELECT * FROM t").await.unwrap();
+    let mut rows = Vec::new();
+    while let Some(batch) = stream.try_next().await.unwrap() {
+        rows.push(batch.num_rows());
+    }
+    assert_eq!(rows, [2, 1]);
+
+    let mut mock = MockClient::new().on("SELECT * FROM t", vec![batch("v", vec![1])]);
+    let batches: Vec<_> = DremioExecutor::execute_stream(&mut mock, "SELECT * FROM t")
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(batches.len(), 1);
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_token_auth() {
+    let server = FakeFlightSqlServer::new()
+        .rejecting_handshake("use a personal access token")
+        .with_token("pat-123")
+        .on(
+            "SELECT 1",
+            FakeResponse::new(batch("v", vec![]).schema()).with_endpoint(vec![batch("v", vec![1])]),
+        )
+        .start()
+        .await
+        .unwrap();
+
+    let mut client = Client::new_with_token(&server.url(), "pat-123")
+        .await
+        .unwrap();
+    let batches = client.get_record_batches("SELECT 1").await.unwrap();
+    assert_eq!(batches[0].num_rows(), 1);
+
+    let mut client = Client::builder(server.url())
+        .with_auth(dremio_rs::Auth::Token("expired".to_string()))
+        .connect()
+        .await
+        .unwrap();
+    let e = client.get_record_batches("SELECT 1").await.unwrap_err();
+    assert!(e.to_string().contains("bearer token"), "{}", e);
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_builder_tuning() {
+    let large = batch("v", (0..10_000).collect());
+    let server = FakeFlightSqlServer::new()
+        .with_credentials("dremio", "dremio123")
+        .on(
+            "SELECT * FROM large",
+            FakeResponse::new(large.schema()).with_endpoint(vec![large]),
+        )
+        .start()
+        .await
+        .unwrap();
+
+    let mut client = Client::builder(server.url())
+        .with_credentials("dremio", "dremio123")
+        .with_max_inbound_message_size(1024)
+        .connect()
+        .await
+        .unwrap();
+    let e = client
+        .get_record_batches("SELECT * FROM large")
+        .await
+        .unwrap_err();
+    assert!(e.to_string().contains("message length too large"), "{}", e);
+
+    let mut client = Client::builder(server.url())
+        .with_credentials("dremio", "dremio123")
+        .with_connect_timeout(Duration::from_secs(5))
+        .with_request_timeout(Duration::from_secs(5))
+        .with_tcp_keepalive(Duration::from_secs(60))
+        .with_http2_keepalive_interval(Duration::from_secs(30))
+        .with_max_inbound_message_size(1024 * 1024)
+        .with_header("x-request-source", "tests")
+        .connect()
+        .await
+        .unwrap();
+    let batches = client
+        .get_record_batches("SELECT * FROM large")
+        .await
+        .unwrap();
+    assert_eq!(batches[0].num_rows(), 10_000);
+
+    let e = Client::builder(server.url())
+        .with_header("x-bad", "line\nbreak")
+        .connect()
+        .await
+        .err()
+        .unwrap();
+    assert!(e.to_string().contains("invalid header \"x-bad\""), "{}", e);
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_endpoint_locations() {
+    let schema = batch("v", vec![]).schema();
+    let executor = FakeFlightSqlServer::new()
+        .with_token("pat-123")
+        .on(
+            "SELECT * FROM t",
+            FakeResponse::new(schema.clone())
+                .with_endpoint(vec![])
+                .with_endpoint(vec![batch("v", vec![3, 4])]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let location = executor.url().replace("http://", "grpc+tcp://");
+    let coordinator = FakeFlightSqlServer::new()
+        .with_token("pat-123")
+        .on(
+            "SELECT * FROM t",
+            FakeResponse::new(schema)
+                .with_endpoint(vec![batch("v", vec![1, 2])])
+                .with_endpoint_at(&location, vec![batch("v", vec![0])]),
+        )
+        .start()
+        .await
+        .unwrap();
+
+    let mut client = Client::new_with_token(&coordinator.url(), "pat-123")
+        .await
+        .unwrap();
+    let options = QueryOptions::new().with_endpoint_concurrency(2);
+    let batches = client
+        .get_record_batches_with_options("SELECT * FROM t", &options)
+        .await
+        .unwrap();
+    let values: Vec<i32> = batches
+        .iter()
+        .flat_map(|b| {
+            b.column(0)
+                .as_any()
+                .downcast_ref::<Int32Array>()
+                .unwrap()
+                .values()
+                .to_vec()
+        })
+        .collect();
+    assert_eq!(values, [1, 2, 3, 4]);
+    coordinator.shutdown().await;
+    executor.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_prepared_statement() {
+    let server = FakeFlightSqlServer::new()
+        .with_credentials("dremio", "dremio123")
+        .on(
+            "SELECT * FROM t WHERE name = ?",
+            FakeResponse::new(batch("v", vec![]).schema()).with_endpoint(vec![batch("v", vec![7])]),
+        )
+        .on_update("DELETE FROM t WHERE name = ?", 3)
+        .start()
+        .await
+        .unwrap();
+
+    let client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    let mut statement = client
+        .prepare("SELECT * FROM t WHERE name = ?")
+        .await
+        .unwrap();
+    assert_eq!(statement.dataset_schema().field(0).name(), "v");
+    let name: arrow::array::ArrayRef = Arc::new(StringArray::from(vec!["x'; DROP TABLE t; --"]));
+    statement.bind_values([name.clone()]).unwrap();
+    let batches = statement
+        .execute()
+        .await
+        .unwrap()
+        .collect_batches()
+        .await
+        .unwrap();
+    assert_eq!(batches[0].num_rows(), 1);
+    statement.close().await.unwrap();
+
+    let mut statement = client
+        .prepare("DELETE FROM t WHERE name = ?")
+        .await
+        .unwrap();
+    statement.bind_values([name]).unwrap();
+    assert_eq!(statement.execute_update().await.unwrap(), 3);
+    statement.close().await.unwrap();
+
+    // The value never becomes part of the SQL text.
+    assert_eq!(
+        server.queries(),
+        vec![
+            "SELECT * FROM t WHERE name = ?",
+            "DELETE FROM t WHERE name = ?"
+        ]
+    );
+    let parameters = server.parameters();
+    assert_eq!(parameters.len(), 2);
+    assert_eq!(parameters[0].schema().field(0).name(), "$1");
+    assert_eq!(
+        parameters[1]
+            .column(0)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap()
+            .value(0),
+        "x'; DROP TABLE t; --"
+    );
+    assert!(client.prepare("SELECT * FROM unknown").await.is_err());
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_execute_update() {
+    let server = FakeFlightSqlServer::new()
+        .with_credentials("dremio", "dremio123")
+        .on_update("INSERT INTO t *", 2)
+        .on_update("CREATE TABLE *", 0)
+        .start()
+        .await
+        .unwrap();
+
+    let client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    assert_eq!(
+        client
+            .execute_update("INSERT INTO t VALUES (1), (2)")
+            .await
+            .unwrap(),
+        2
+    );
+    assert_eq!(
+        client
+            .execute_update("CREATE TABLE s AS SELECT 1")
+            .await
+            .unwrap(),
+        0
+    );
+    let e = client.execute_update("DROP TABLE t").await.unwrap_err();
+    assert_eq!(e.query(), Some("DROP TABLE t"));
+    assert_eq!(e.phase(), Some(QueryPhase::Execute));
+    server.shutdown().await;
+}
+
+#[cfg(feature = "parquet")]
+#[tokio::test]
+async fn test_write_parquet() {
+    use parquet::file::properties::{EnabledStatistics, WriterProperties};
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    let dir = std::env::temp_dir().join(format!("dremio-rs-parquet-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let schema = batch("v", vec![]).schema();
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM t",
+            FakeResponse::new(schema.clone())
+                .with_endpoint(vec![batch("v", vec![1, 2]), batch("v", vec![3])])
+                .with_endpoint(vec![batch("v", vec![4])]),
+        )
+        .on("SELECT * FROM empty", FakeResponse::new(schema))
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+
+    let path = dir.join("t.parquet");
+    client
+        .write_parquet("SELECT * FROM t", path.to_str().unwrap())
+        .await
+        .unwrap();
+    let reader = SerializedFileReader::new(std::fs::File::open(&path).unwrap()).unwrap();
+    assert_eq!(reader.metadata().file_metadata().num_rows(), 4);
+
+    let properties = WriterProperties::builder()
+        .set_max_row_group_size(3)
+        .set_statistics_enabled(EnabledStatistics::None)
+        .build();
+    client
+        .write_parquet_with_properties("SELECT * FROM t", path.to_str().unwrap(), properties)
+        .await
+        .unwrap();
+    let reader = SerializedFileReader::new(std::fs::File::open(&path).unwrap()).unwrap();
+    assert_eq!(reader.num_row_groups(), 2);
+    assert!(reader
+        .metadata()
+        .row_group(0)
+        .column(0)
+        .statistics()
+        .is_none());
+
+    // The announced schema is written even without rows.
+    let path = dir.join("empty.parquet");
+    client
+        .write_parquet("SELECT * FROM empty", path.to_str().unwrap())
+        .await
+        .unwrap();
+    let reader = SerializedFileReader::new(std::fs::File::open(&path).unwrap()).unwrap();
+    let metadata = reader.metadata().file_metadata();
+    assert_eq!(metadata.num_rows(), 0);
+    assert_eq!(metadata.schema_descr().column(0).name(), "v");
+    server.shutdown().await;
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_write_csv_json_ipc() {
+    use arrow::csv::WriterBuilder;
+    use arrow::ipc::reader::FileReader;
+    use arrow::ipc::writer::IpcWriteOptions;
+
+    let dir = std::env::temp_dir().join(format!("dremio-rs-files-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let schema = batch("v", vec![]).schema();
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM t",
+            FakeResponse::new(schema.clone())
+                .with_endpoint(vec![batch("v", vec![1, 2]), batch("v", vec![3])]),
+        )
+        .on("SELECT * FROM empty", FakeResponse::new(schema))
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+
+    let path = dir.join("t.csv");
+    let rows = client
+        .write_csv(
+            "SELECT * FROM t",
+            path.to_str().unwrap(),
+            WriterBuilder::new(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(rows, 3);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "v\n1\n2\n3\n");
+    let builder = WriterBuilder::new().with_header(false);
+    client
+        .write_csv("SELECT * FROM t", path.to_str().unwrap(), builder)
+        .await
+        .unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "1\n2\n3\n");
+    client
+        .write_csv(
+            "SELECT * FROM empty",
+            path.to_str().unwrap(),
+            WriterBuilder::new(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "v\n");
+
+    let path = dir.join("t.jsonl");
+    client
+        .write_json("SELECT * FROM t", path.to_str().unwrap())
+        .await
+        .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "{\"v\":1}\n{\"v\":2}\n{\"v\":3}\n"
+    );
+
+    let path = dir.join("t.arrow");
+    client
+        .write_ipc(
+            "SELECT * FROM t",
+            path.to_str().unwrap(),
+            IpcWriteOptions::default(),
+        )
+        .await
+        .unwrap();
+    let reader = FileReader::try_new(std::fs::File::open(&path).unwrap(), None).unwrap();
+    let batches: Vec<_> = reader.map(Result::unwrap).collect();
+    assert_eq!(batches, [batch("v", vec![1, 2]), batch("v", vec![3])]);
+    server.shutdown().await;
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_write_text_empty_result() {
+    use dremio_rs::export::{write_text, TextFormat};
+
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM empty",
+            FakeResponse::new(batch("v", vec![]).schema()),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    let stream = client.execute_stream("SELECT * FROM empty").await.unwrap();
+    let mut csv = Vec::new();
+    assert_eq!(
+        write_text(stream, TextFormat::Csv, &mut csv).await.unwrap(),
+        0
+    );
+    assert_eq!(String::from_utf8(csv).unwrap(), "v\n");
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_catalog_metadata() {
+    use arrow::array::{AsArray, UInt32Array};
+    use arrow_flight::sql::SqlInfo;
+    use dremio_rs::metadata::{SchemaInfo, TableInfo};
+
+    let orders = batch("orderId", vec![1]);
+    let server = FakeFlightSqlServer::new()
+        .with_table("lake.sales.orders", vec![orders.clone()])
+        .with_table("lake.sales.returns", vec![orders.clone()])
+        .with_table("scratch.tmp", vec![orders])
+        .start()
+        .await
+        .unwrap();
+    let client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+
+    assert_eq!(client.get_catalogs().await.unwrap(), ["DREMIO"]);
+    let schemas = client.get_schemas(None, Some("lake%")).await.unwrap();
+    let names: Vec<_> = schemas.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["lake", "lake.sales"]);
+    assert_eq!(
+        schemas[0],
+        SchemaInfo {
+            catalog: Some("DREMIO".to_string()),
+            name: "lake".to_string(),
+        }
+    );
+
+    let tables = client
+        .get_tables(None, Some("lake.%"), Some("ord%"), &["TABLE"])
+        .await
+        .unwrap();
+    assert_eq!(
+        tables,
+        [TableInfo {
+            catalog: Some("DREMIO".to_string()),
+            schema: Some("lake.sales".to_string()),
+            name: "orders".to_string(),
+            table_type: "TABLE".to_string(),
+        }]
+    );
+    assert!(client
+        .get_tables(None, None, None, &["VIEW"])
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(client
+        .get_table_types()
+        .await
+        .unwrap()
+        .contains(&"VIEW".to_string()));
+
+    let info = client
+        .get_sql_info([SqlInfo::FlightSqlServerName])
+        .await
+        .unwrap();
+    let names = info[0].column(0).as_any().downcast_ref::<UInt32Array>();
+    assert_eq!(
+        names.unwrap().values(),
+        &[SqlInfo::FlightSqlServerName as u32]
+    );
+    let value = info[0].column(1).as_union();
+    assert_eq!(
+        value.value(0).as_string::<i32>().value(0),
+        "Fake Flight SQL server"
+    );
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_cancel_query() {
+    use arrow_flight::CancelStatus;
+
+    let schema = batch("v", vec![]).schema();
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM *",
+            FakeResponse::new(schema).with_endpoint(vec![batch("v", vec![1])]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+
+    let stream = client.execute_stream("SELECT * FROM a").await.unwrap();
+    let handle = stream.handle().unwrap();
+    assert_eq!(handle.cancel().await.unwrap(), CancelStatus::Cancelled);
+    assert_eq!(stream.cancel().await.unwrap(), CancelStatus::Cancelled);
+    assert_eq!(server.cancelled(), ["SELECT * FROM a", "SELECT * FROM a"]);
+    assert_eq!(
+        QueryStream::from_batches(vec![]).cancel().await.unwrap(),
+        CancelStatus::NotCancellable
+    );
+
+    // Queries still running at their deadline are cancelled; finished ones
+    // are left alone.
+    let options = QueryOptions::new().with_timeout(Duration::from_millis(200));
+    let _unread = client
+        .execute_stream_with_options("SELECT * FROM b", &options)
+        .await
+        .unwrap();
+    client
+        .get_record_batches_with_options("SELECT * FROM c", &options)
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(server.cancelled()[2..], ["SELECT * FROM b"]);
+
+    // Dropped streams are cancelled without waiting for their deadline.
+    let options = QueryOptions::new().with_timeout(Duration::from_secs(60));
+    let unread = client
+        .execute_stream_with_options("SELECT * FROM d", &options)
+        .await
+        .unwrap();
+    drop(unread);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(server.cancelled()[3..], ["SELECT * FROM d"]);
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_retry_and_reauthenticate() {
+    use dremio_rs::retry::RetryPolicy;
+
+    let schema = batch("v", vec![]).schema();
+    let server = FakeFlightSqlServer::new()
+        .with_credentials("dremio", "dremio123")
+        .on(
+            "SELECT * FROM t",
+            FakeResponse::new(schema).with_endpoint(vec![batch("v", vec![1, 2])]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let policy = RetryPolicy::new().with_initial_backoff(Duration::from_millis(10));
+    let mut client = Client::builder(server.url())
+        .with_credentials("dremio", "dremio123")
+        .with_retry_policy(policy)
+        .connect()
+        .await
+        .unwrap();
+
+    // Submitting the query fails twice before the third attempt succeeds.
+    server.fail_next(2, Status::unavailable("coordinator restarting"));
+    let batches = client.get_record_batches("SELECT * FROM t").await.unwrap();
+    assert_eq!(batches[0].num_rows(), 2);
+
+    // So does fetching the results.
+    let stream = client.execute_stream("SELECT * FROM t").await.unwrap();
+    server.fail_next(1, Status::unavailable("executor restarting"));
+    assert_eq!(stream.collect_batches().await.unwrap()[0].num_rows(), 2);
+
+    // The attempts are limited, and other errors are not retried.
+    server.fail_next(3, Status::unavailable("coordinator down"));
+    let e = client
+        .get_record_batches("SELECT * FROM t")
+        .await
+        .unwrap_err();
+    assert!(e.to_string().contains("coordinator down"), "{}", e);
+    server.fail_next(1, Status::internal("boom"));
+    assert!(client.get_catalogs().await.is_err());
+    assert!(client.get_catalogs().await.is_ok());
+
+    // A lost session is re-established with a new handshake.
+    assert_eq!(server.handshakes(), 1);
+    server.revoke_tokens();
+    assert_eq!(client.get_catalogs().await.unwrap(), ["DREMIO"]);
+    assert_eq!(
+        client.get_record_batches("SELECT * FROM t").await.unwrap()[0].num_rows(),
+        2
+    );
+    assert_eq!(server.handshakes(), 2);
+    // The underlying client is handed out with the new session token.
+    assert!(client.inner_mut().get_catalogs().await.is_ok());
+    assert_eq!(server.handshakes(), 2);
+
+    // Without a policy, nothing is retried.
+    let client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    server.fail_next(1, Status::unavailable("coordinator restarting"));
+    assert!(client.get_catalogs().await.is_err());
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_concurrent_clones() {
+    let schema = batch("v", vec![]).schema();
+    let server = FakeFlightSqlServer::new()
+        .with_credentials("dremio", "dremio123")
+        .on(
+            "SELECT * FROM t",
+            FakeResponse::new(schema)
+                .with_endpoint(vec![batch("v", vec![1, 2])])
+                .with_delay(Duration::from_millis(100)),
+        )
+        .start()
+        .await
+        .unwrap();
+    let client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+
+    let tasks: Vec<_> = (0..8)
+        .map(|_| {
+            let mut client = client.clone();
+            tokio::spawn(async move { client.get_record_batches("SELECT * FROM t").await })
+        })
+        .collect();
+    for task in tasks {
+        assert_eq!(task.await.unwrap().unwrap()[0].num_rows(), 2);
+    }
+    assert_eq!(server.queries().len(), 8);
+    assert_eq!(server.handshakes(), 1);
+    server.shutdown().await;
+}
+
+async fn count_rows(client: &mut Client, sql: &str) -> usize {
+    let batches = client.get_record_batches(sql).await.unwrap();
+    batches.iter().map(|b| b.num_rows()).sum()
+}
+
+#[tokio::test]
+async fn test_result_cache_is_scoped_to_cluster_and_user() {
+    let schema = batch("v", vec![]).schema();
+    let start = |values: Vec<i32>| {
+        FakeFlightSqlServer::new()
+            .on(
+                "SELECT * FROM t",
+                FakeResponse::new(schema.clone()).with_endpoint(vec![batch("v", values)]),
+            )
+            .start()
+    };
+    let a = start(vec![1]).await.unwrap();
+    let b = start(vec![2, 3]).await.unwrap();
+    let cache = dremio_rs::cache::MemoryCache::new();
+    let connect = |url: String, user: &'static str| {
+        let cache = cache.clone();
+        async move {
+            Client::new(&url, user, "dremio123")
+                .await
+                .unwrap()
+                .with_result_cache(cache)
+        }
+    };
+
+    let mut alice_a = connect(a.url(), "alice").await;
+    let mut alice_b = connect(b.url(), "alice").await;
+    let mut bob_a = connect(a.url(), "bob").await;
+    let mut alice_a_again = connect(a.url(), "alice").await;
+    assert_eq!(count_rows(&mut alice_a, "SELECT * FROM t").await, 1);
+    assert_eq!(count_rows(&mut alice_b, "SELECT * FROM t").await, 2);
+    assert_eq!(count_rows(&mut bob_a, "SELECT * FROM t").await, 1);
+    assert_eq!(a.queries().len(), 2);
+    assert_eq!(b.queries().len(), 1);
+
+    // The same user on the same cluster shares the entry.
+    assert_eq!(count_rows(&mut alice_a_again, "SELECT * FROM t").await, 1);
+    assert_eq!(a.queries().len(), 2);
+
+    // Different session options are a different scope.
+    let mut scoped = Client::builder(a.url())
+        .with_credentials("alice", "dremio123")
+        .with_session_options(dremio_rs::SessionOptions::new().with_schema("lake"))
+        .connect()
+        .await
+        .unwrap()
+        .with_result_cache(cache.clone());
+    scoped.get_record_batches("SELECT * FROM t").await.unwrap();
+    assert_eq!(a.queries().len(), 3);
+    a.shutdown().await;
+    b.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_cached_results_are_checked() {
+    use dremio_rs::checks::{Check, Checks};
+
+    let schema = batch("v", vec![]).schema();
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM t",
+            FakeResponse::new(schema).with_endpoint(vec![batch("v", vec![1])]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap()
+        .with_result_cache(dremio_rs::cache::MemoryCache::new());
+    client.get_record_batches("SELECT * FROM t").await.unwrap();
+
+    // The checks fail on the cached rows without running the query again.
+    let options = QueryOptions::new().with_checks(Checks::new().require(Check::min_rows(2)));
+    let e = client
+        .get_record_batches_with_options("SELECT * FROM t", &options)
+        .await
+        .unwrap_err();
+    assert_eq!(e.phase(), Some(QueryPhase::Fetch));
+    assert!(
+        matches!(e.root(), DremioClientError::CheckFailed(_)),
+        "{}",
+        e
+    );
+    assert_eq!(server.queries().len(), 1);
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_timeout_is_sent_as_grpc_deadline() {
+    let schema = batch("v", vec![]).schema();
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM t",
+            FakeResponse::new(schema.clone()).with_endpoint(vec![batch("v", vec![1])]),
+        )
+        .on(
+            "SELECT * FROM slow",
+            FakeResponse::new(schema).with_delay(Duration::from_millis(500)),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+
+    client.get_record_batches("SELECT * FROM t").await.unwrap();
+    assert!(server.headers("grpc-timeout").iter().all(Option::is_none));
+
+    let options = QueryOptions::new().with_timeout(Duration::from_secs(30));
+    client
+        .get_record_batches_with_options("SELECT * FROM t", &options)
+        .await
+        .unwrap();
+    // Both the execute and the do_get call carry the remaining time.
+    let deadlines = server.headers("grpc-timeout").split_off(2);
+    assert_eq!(deadlines.len(), 2);
+    for deadline in deadlines {
+        let deadline = deadline.unwrap();
+        let (value, unit) = deadline.split_at(deadline.len() - 1);
+        let value: u64 = value.parse().unwrap();
+        let remaining = match unit {
+            "u" => Duration::from_micros(value),
+            "m" => Duration::from_millis(value),
+            "S" => Duration::from_secs(value),
+            _ => panic!("unexpected grpc-timeout {}", deadline),
+        };
+        assert!(remaining > Duration::from_secs(25) && remaining <= Duration::from_secs(30));
+    }
+
+    let options = QueryOptions::new().with_timeout(Duration::from_millis(100));
+    let error = client
+        .get_record_batches_with_options("SELECT * FROM slow", &options)
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(error.root(), DremioClientError::TimeoutError(_)),
+        "{}",
+        error
+    );
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_prefetch_reads_ahead_up_to_depth() {
+    let batches: Vec<_> = (0..6).map(|i| batch("v", vec![i])).collect();
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM t",
+            FakeResponse::new(batches[0].schema()).with_endpoint(batches),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+
+    for (depth, ahead) in [(0, 0), (2, 3)] {
+        let fetched = Arc::new(AtomicU64::new(0));
+        let seen = fetched.clone();
+        let options = QueryOptions::new()
+            .with_prefetch(depth)
+            .with_progress(move |metrics| seen.store(metrics.batches, Ordering::Relaxed));
+        let stream = client
+            .execute_stream_with_options("SELECT * FROM t", &options)
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        // Two batches wait in the buffer and a third one waits for room.
+        assert_eq!(fetched.load(Ordering::Relaxed), ahead);
+        let batches = stream.collect_batches().await.unwrap();
+        assert_eq!(batches.len(), 6);
+        assert_eq!(fetched.load(Ordering::Relaxed), 6);
+    }
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_lossy_utf8_repairs_invalid_strings() {
+    // Arrow validates strings when decoding, not when encoding.
+    let names = unsafe {
+        StringArray::new_unchecked(
+            arrow::buffer::OffsetBuffer::new(vec![0, 2, 6, 6].into()),
+            arrow::buffer::Buffer::from(b"okbad\xff".as_slice()),
+            Some(vec![true, true, false].into()),
+        )
+    };
+    let names = RecordBatch::try_from_iter([("name", Arc::new(names) as _)]).unwrap();
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM t",
+            FakeResponse::new(names.schema()).with_endpoint(vec![names]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+
+    assert!(client.get_record_batches("SELECT * FROM t").await.is_err());
+
+    let options = QueryOptions::new().with_lossy_utf8(true);
+    let mut stream = client
+        .execute_stream_with_options("SELECT * FROM t", &options)
+        .await
+        .unwrap();
+    let batch = stream.try_next().await.unwrap().unwrap();
+    assert!(stream.try_next().await.unwrap().is_none());
+    assert_eq!(batch.schema().field(0).data_type(), &DataType::Utf8);
+    let names = batch.column(0).as_string::<i32>();
+    assert_eq!(names.value(0), "ok");
+    assert_eq!(names.value(1), "bad\u{FFFD}");
+    assert!(names.is_null(2));
+    assert_eq!(stream.stats().invalid_utf8_values, 1);
+    server.shutdown().await;
+}
+
+/// Returns the number of rows of `query` and of a preview of two rows,
+/// through any executor.
+async fn rows_and_preview<E: DremioExecutor>(executor: &mut E, query: &str) -> (usize, usize) {
+    let batches = executor.get_record_batches(query).await.unwrap();
+    let preview = executor.peek(query, 2).await.unwrap();
+    (
+        batches.iter().map(|b| b.num_rows()).sum(),
+        preview.num_rows(),
+    )
+}
+
+/// An executor implementing only the required methods, counting the queries
+/// it runs.
+struct Counting {
+    inner: MockClient,
+    queries: usize,
+}
+
+impl DremioExecutor for Counting {
+    async fn execute_stream_with_options(
+        &mut self,
+        query: &str,
+        options: &QueryOptions,
+    ) -> Result<QueryStream, DremioClientError> {
+        self.queries += 1;
+        self.inner.execute_stream_with_options(query, options).await
+    }
+
+    async fn get_record_batches_with_options(
+        &mut self,
+        query: &str,
+        options: &QueryOptions,
+    ) -> Result<Vec<RecordBatch>, DremioClientError> {
+        self.queries += 1;
+        self.inner
+            .get_record_batches_with_options(query, options)
+            .await
+    }
+}
+
+#[tokio::test]
+async fn test_executor_trait() {
+    let batches = vec![batch("v", vec![1, 2]), batch("v", vec![3])];
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "*FROM t*",
+            FakeResponse::new(batches[0].schema()).with_endpoint(batches.clone()),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    assert_eq!(
+        rows_and_preview(&mut client, "SELECT * FROM t").await,
+        (3, 2)
+    );
+
+    let mut mock = MockClient::new().on("SELECT * FROM t", batches.clone());
+    assert_eq!(rows_and_preview(&mut mock, "SELECT * FROM t").await, (3, 2));
+
+    // The provided methods are built on the required ones.
+    let mut counting = Counting {
+        inner: MockClient::new().on("SELECT * FROM t", batches),
+        queries: 0,
+    };
+    assert_eq!(
+        rows_and_preview(&mut counting, "SELECT * FROM t").await,
+        (3, 2)
+    );
+    let path =
+        std::env::temp_dir().join(format!("dremio-rs-executor-{}.parquet", std::process::id()));
+    counting
+        .write_parquet("SELECT * FROM t", path.to_str().unwrap())
+        .await
+        .unwrap();
+    assert!(path.exists());
+    std::fs::remove_file(&path).unwrap();
+    let stream = counting.execute_stream("SELECT * FROM t").await.unwrap();
+    assert_eq!(stream.collect_batches().await.unwrap().len(), 2);
+    assert_eq!(counting.queries, 4);
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_sample() {
+    let schema = batch("v", vec![]).schema();
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM *",
+            FakeResponse::new(schema.clone()).with_endpoint(vec![batch("v", vec![1, 2])]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+
+    let rows = client
+        .sample("lake.orders", SampleSpec::Rows(2))
+        .await
+        .unwrap();
+    assert_eq!(rows.iter().map(|b| b.num_rows()).sum::<usize>(), 2);
+    client
+        .sample("lake.orders", SampleSpec::Fraction(0.5))
+        .await
+        .unwrap();
+    assert_eq!(
+        server.queries(),
+        [
+            r#"SELECT * FROM "lake"."orders" ORDER BY RANDOM() LIMIT 2"#,
+            r#"SELECT * FROM "lake"."orders" WHERE RANDOM() < 0.5"#,
+        ]
+    );
+
+    // An invalid fraction is rejected before anything is sent.
+    assert!(client
+        .sample("lake.orders", SampleSpec::Fraction(2.0))
+        .await
+        .is_err());
+    assert_eq!(server.queries().len(), 2);
+    server.shutdown().await;
+}
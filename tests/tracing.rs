@@ -0,0 +1,119 @@
+#![cfg(all(feature = "testing", feature = "tracing"))]
+
+use arrow::array::{Int32Array, RecordBatch};
+use arrow::datatypes::{DataType, Field, Schema};
+use dremio_rs::testing::{FakeFlightSqlServer, FakeResponse};
+use dremio_rs::Client;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field as TraceField, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+fn batch(name: &str, values: Vec<i32>) -> RecordBatch {
+    let schema = Arc::new(Schema::new(vec![Field::new(name, DataType::Int32, false)]));
+    RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(values))]).unwrap()
+}
+
+/// The metadata and recorded fields of a span.
+type SpanRecord = (&'static Metadata<'static>, HashMap<String, String>);
+
+/// Collects the spans created while it is the default subscriber.
+///
+/// It does not track the current span, which takes a registry such as that
+/// of `tracing-subscriber`, so fields recorded on it are not seen.
+#[derive(Clone, Default)]
+struct Spans {
+    spans: Arc<Mutex<Vec<SpanRecord>>>,
+}
+
+impl Spans {
+    fn named(&self, name: &str) -> Vec<HashMap<String, String>> {
+        let spans = self.spans.lock().unwrap();
+        spans
+            .iter()
+            .filter(|(metadata, _)| metadata.name() == name)
+            .map(|(_, fields)| fields.clone())
+            .collect()
+    }
+}
+
+struct Fields<'a>(&'a mut HashMap<String, String>);
+
+impl Visit for Fields<'_> {
+    fn record_debug(&mut self, field: &TraceField, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{:?}", value));
+    }
+
+    fn record_str(&mut self, field: &TraceField, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+}
+
+impl Subscriber for Spans {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut spans = self.spans.lock().unwrap();
+        let mut fields = HashMap::new();
+        span.record(&mut Fields(&mut fields));
+        spans.push((span.metadata(), fields));
+        Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        let mut spans = self.spans.lock().unwrap();
+        let (_, fields) = &mut spans[span.into_u64() as usize - 1];
+        values.record(&mut Fields(fields));
+    }
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, _: &Event<'_>) {}
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+#[tokio::test]
+async fn test_tracing_spans() {
+    let spans = Spans::default();
+    let _guard = tracing::subscriber::set_default(spans.clone());
+    let server = FakeFlightSqlServer::new()
+        .on(
+            "SELECT * FROM t",
+            FakeResponse::new(batch("v", vec![]).schema())
+                .with_endpoint(vec![batch("v", vec![1, 2]), batch("v", vec![3])])
+                .with_endpoint(vec![batch("v", vec![4])]),
+        )
+        .start()
+        .await
+        .unwrap();
+    let mut client = Client::new(&server.url(), "dremio", "dremio123")
+        .await
+        .unwrap();
+    assert_eq!(spans.named("connect").len(), 1);
+    assert_eq!(spans.named("handshake").len(), 1);
+
+    client.get_record_batches("SELECT * FROM t").await.unwrap();
+    let execute = &spans.named("execute")[0];
+    assert_eq!(execute["query"], "SELECT * FROM t");
+    let endpoints = spans.named("fetch_endpoint");
+    let counts: Vec<_> = endpoints
+        .iter()
+        .map(|fields| {
+            (
+                fields["endpoint"].as_str(),
+                fields["batches"].as_str(),
+                fields["rows"].as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(counts, vec![("0", "2", "3"), ("1", "1", "1")]);
+    server.shutdown().await;
+}